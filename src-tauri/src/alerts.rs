@@ -0,0 +1,576 @@
+use crate::credential_store::{
+    alert_password_delete, alert_password_get, alert_password_has, alert_password_set,
+};
+use crate::host_probe::{HostFilesystem, HostLiveInfo};
+use crate::models::Host;
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+const ALERT_SEND_TIMEOUT: Duration = Duration::from_secs(15);
+const SMTPS_PORT: u16 = 465;
+const DEFAULT_DISK_THRESHOLD: f64 = 90.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertChannelKind {
+    /// POSTs the alert as JSON to `url`.
+    Webhook,
+    /// POSTs a Slack-compatible `{"text": ...}` payload to `url`.
+    Slack,
+    /// Mails the alert through `smtp_host`.
+    Smtp,
+}
+
+/// A notification channel configured in settings. The SMTP password is kept
+/// in the keychain; like the WebDAV password it is write-only here.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AlertChannel {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    pub kind: AlertChannelKind,
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub smtp_host: Option<String>,
+    #[serde(default)]
+    pub smtp_port: Option<u16>,
+    #[serde(default)]
+    pub smtp_username: Option<String>,
+    #[serde(default)]
+    pub smtp_from: Option<String>,
+    #[serde(default)]
+    pub smtp_to: Vec<String>,
+    #[serde(default)]
+    pub has_smtp_password: bool,
+    #[serde(default, skip_serializing)]
+    pub smtp_password: Option<String>,
+    #[serde(default, skip_serializing)]
+    pub smtp_password_clear: bool,
+}
+
+/// What an alert rule watches for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertCondition {
+    /// The host's SSH port stopped answering pings.
+    HostDown,
+    /// A live probe of the host failed.
+    ProbeFailed,
+    /// A filesystem of the host is at least `threshold` percent full.
+    DiskUsage,
+}
+
+/// An alert rule from settings. It fires when its condition starts to hold
+/// for a host, and again only once the condition cleared in between.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AlertRule {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    pub condition: AlertCondition,
+    /// Percent full for `disk_usage`; 90 when unset.
+    #[serde(default)]
+    pub threshold: Option<f64>,
+    /// Hosts the rule watches; every host when empty.
+    #[serde(default)]
+    pub host_ids: Vec<String>,
+    /// Channels the alert is sent to.
+    #[serde(default)]
+    pub channel_ids: Vec<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Something just learned about a host, which rules are checked against.
+pub(crate) enum Observation<'a> {
+    Ping(Result<(), &'a str>),
+    LiveProbe(Result<&'a HostLiveInfo, &'a str>),
+}
+
+fn used_percent(fs: &HostFilesystem) -> Option<f64> {
+    // Like `df`: space reserved for root counts as neither used nor free.
+    let usable = fs.used_kb + fs.available_kb;
+    (usable > 0).then(|| fs.used_kb as f64 * 100.0 / usable as f64)
+}
+
+impl AlertRule {
+    fn watches(&self, host_id: &str) -> bool {
+        self.enabled && (self.host_ids.is_empty() || self.host_ids.iter().any(|id| id == host_id))
+    }
+
+    // None when the observation tells nothing about the condition; otherwise
+    // what is wrong, if the condition holds.
+    fn check(&self, observation: &Observation) -> Option<Option<String>> {
+        match (self.condition, observation) {
+            (AlertCondition::HostDown, Observation::Ping(result)) => Some(
+                result
+                    .err()
+                    .map(|e| format!("SSH port is not reachable: {e}")),
+            ),
+            (AlertCondition::ProbeFailed, Observation::LiveProbe(result)) => {
+                Some(result.err().map(|e| format!("Live probe failed: {e}")))
+            }
+            (AlertCondition::DiskUsage, Observation::LiveProbe(Ok(info))) => {
+                let threshold = self.threshold.unwrap_or(DEFAULT_DISK_THRESHOLD);
+                let full: Vec<String> = info
+                    .filesystems
+                    .iter()
+                    .filter_map(|fs| {
+                        let percent = used_percent(fs)?;
+                        (percent >= threshold)
+                            .then(|| format!("{} is {percent:.0}% full", fs.mount_point))
+                    })
+                    .collect();
+                Some((!full.is_empty()).then(|| full.join(", ")))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Checked when settings are saved, so a rule that could never be delivered
+/// is reported there.
+pub(crate) fn validate_rules(rules: &[AlertRule], channels: &[AlertChannel]) -> Result<(), String> {
+    for rule in rules {
+        if let Some(missing) = rule
+            .channel_ids
+            .iter()
+            .find(|id| !channels.iter().any(|channel| &channel.id == *id))
+        {
+            return Err(format!("Alert rule uses unknown channel {missing}"));
+        }
+        if rule
+            .threshold
+            .is_some_and(|threshold| !(threshold > 0.0 && threshold <= 100.0))
+        {
+            return Err("Disk usage threshold must be between 0 and 100 percent".to_string());
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Alert {
+    pub title: String,
+    pub message: String,
+    /// `info`, `warning` or `critical`.
+    pub severity: String,
+    pub host_id: Option<String>,
+    pub fired_at: String,
+}
+
+/// Outcome of sending an alert to one channel.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertDelivery {
+    pub channel_id: String,
+    pub ok: bool,
+    pub error: Option<String>,
+}
+
+/// Move newly entered SMTP passwords into the keychain and drop the ones of
+/// channels that were removed.
+pub(crate) fn store_channel_secrets(
+    previous: &[AlertChannel],
+    channels: &mut [AlertChannel],
+) -> Result<(), String> {
+    for channel in channels.iter_mut() {
+        if channel.smtp_password_clear {
+            alert_password_delete(&channel.id)?;
+        } else if let Some(password) = channel
+            .smtp_password
+            .as_deref()
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+        {
+            alert_password_set(&channel.id, password)?;
+        }
+    }
+    for removed in previous
+        .iter()
+        .filter(|old| !channels.iter().any(|channel| channel.id == old.id))
+    {
+        alert_password_delete(&removed.id)?;
+    }
+    mark_channel_secrets(channels);
+    Ok(())
+}
+
+/// Fill in `has_smtp_password` and clear the write-only fields.
+pub(crate) fn mark_channel_secrets(channels: &mut [AlertChannel]) {
+    for channel in channels.iter_mut() {
+        channel.has_smtp_password =
+            channel.kind == AlertChannelKind::Smtp && alert_password_has(&channel.id);
+        channel.smtp_password = None;
+        channel.smtp_password_clear = false;
+    }
+}
+
+fn slack_text(alert: &Alert) -> String {
+    let icon = match alert.severity.as_str() {
+        "critical" => ":red_circle:",
+        "warning" => ":warning:",
+        _ => ":information_source:",
+    };
+    format!("{icon} *{}*\n{}", alert.title, alert.message)
+}
+
+fn required<'a>(value: &'a Option<String>, what: &str) -> Result<&'a str, String> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .ok_or_else(|| format!("{what} is required"))
+}
+
+fn post_json(url: &str, body: &serde_json::Value) -> Result<(), String> {
+    let response = reqwest::blocking::Client::builder()
+        .timeout(ALERT_SEND_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?
+        .post(url)
+        .json(body)
+        .send()
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("{url} answered {}", response.status()));
+    }
+    Ok(())
+}
+
+fn send_mail(channel: &AlertChannel, alert: &Alert) -> Result<(), String> {
+    let host = required(&channel.smtp_host, "SMTP host")?;
+    let from: Mailbox = required(&channel.smtp_from, "Sender address")?
+        .parse()
+        .map_err(|e| format!("Invalid sender address: {e}"))?;
+    if channel.smtp_to.is_empty() {
+        return Err("At least one recipient is required".to_string());
+    }
+    let mut message = Message::builder()
+        .from(from)
+        .subject(format!("[{}] {}", alert.severity, alert.title))
+        .header(ContentType::TEXT_PLAIN);
+    for to in &channel.smtp_to {
+        message = message.to(to
+            .trim()
+            .parse()
+            .map_err(|e| format!("Invalid recipient {to}: {e}"))?);
+    }
+    let message = message
+        .body(format!("{}\n\n{}", alert.message, alert.fired_at))
+        .map_err(|e| e.to_string())?;
+
+    // Port 465 speaks TLS from the start; anything else must upgrade with
+    // STARTTLS, so credentials never go out in the clear.
+    let port = channel.smtp_port.unwrap_or(SMTPS_PORT);
+    let builder = if port == SMTPS_PORT {
+        SmtpTransport::relay(host)
+    } else {
+        SmtpTransport::starttls_relay(host)
+    }
+    .map_err(|e| e.to_string())?
+    .port(port)
+    .timeout(Some(ALERT_SEND_TIMEOUT));
+    let builder = match channel
+        .smtp_username
+        .as_deref()
+        .map(str::trim)
+        .filter(|u| !u.is_empty())
+    {
+        Some(username) => {
+            let password = alert_password_get(&channel.id)?.unwrap_or_default();
+            builder.credentials(Credentials::new(username.to_string(), password))
+        }
+        None => builder,
+    };
+    builder
+        .build()
+        .send(&message)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+fn send(channel: &AlertChannel, alert: &Alert) -> Result<(), String> {
+    match channel.kind {
+        AlertChannelKind::Webhook => post_json(
+            required(&channel.url, "Webhook URL")?,
+            &serde_json::to_value(alert).map_err(|e| e.to_string())?,
+        ),
+        AlertChannelKind::Slack => post_json(
+            required(&channel.url, "Webhook URL")?,
+            &serde_json::json!({ "text": slack_text(alert) }),
+        ),
+        AlertChannelKind::Smtp => send_mail(channel, alert),
+    }
+}
+
+fn last_alerts() -> &'static Mutex<HashMap<String, Alert>> {
+    static LAST: OnceLock<Mutex<HashMap<String, Alert>>> = OnceLock::new();
+    LAST.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// The last alert fired for each host since the app started, by host id.
+pub(crate) fn last_alerts_by_host() -> HashMap<String, Alert> {
+    last_alerts()
+        .lock()
+        .map(|alerts| alerts.clone())
+        .unwrap_or_default()
+}
+
+// (rule id, host id) of the rules whose condition currently holds.
+fn firing() -> &'static Mutex<HashSet<(String, String)>> {
+    static FIRING: OnceLock<Mutex<HashSet<(String, String)>>> = OnceLock::new();
+    FIRING.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+/// The rules loaded from settings; none when they cannot be read.
+pub(crate) fn alert_rules() -> Vec<AlertRule> {
+    crate::host_store::settings_load()
+        .map(|settings| settings.alert_rules)
+        .unwrap_or_default()
+}
+
+// The alerts of the rules that start firing for `host`, each with the
+// channels to send it to.
+fn triggered(
+    rules: &[AlertRule],
+    host: &Host,
+    observation: &Observation,
+) -> Vec<(Vec<String>, Alert)> {
+    let Ok(mut firing) = firing().lock() else {
+        return Vec::new();
+    };
+    let name = if host.alias.trim().is_empty() {
+        host.hostname.trim()
+    } else {
+        host.alias.trim()
+    };
+    let mut alerts = Vec::new();
+    for rule in rules.iter().filter(|rule| rule.watches(&host.id)) {
+        let Some(problem) = rule.check(observation) else {
+            continue;
+        };
+        let key = (rule.id.clone(), host.id.clone());
+        let Some(message) = problem else {
+            firing.remove(&key);
+            continue;
+        };
+        if !firing.insert(key) {
+            continue;
+        }
+        let (title, severity) = match rule.condition {
+            AlertCondition::HostDown => (format!("{name} is down"), "critical"),
+            AlertCondition::ProbeFailed => (format!("{name}: probe failed"), "warning"),
+            AlertCondition::DiskUsage => (format!("{name}: disk almost full"), "warning"),
+        };
+        alerts.push((
+            rule.channel_ids.clone(),
+            Alert {
+                title,
+                message,
+                severity: severity.to_string(),
+                host_id: Some(host.id.clone()),
+                fired_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            },
+        ));
+    }
+    alerts
+}
+
+/// Check `rules` against what was just learned about `host`. An alert that
+/// fires becomes the host's last alert and is sent to the rule's channels
+/// from a background thread.
+pub(crate) fn observe(rules: &[AlertRule], host: &Host, observation: Observation) {
+    for (channel_ids, alert) in triggered(rules, host, &observation) {
+        if let Ok(mut alerts) = last_alerts().lock() {
+            alerts.insert(host.id.clone(), alert.clone());
+        }
+        if channel_ids.is_empty() {
+            continue;
+        }
+        let spawned = thread::Builder::new()
+            .name("alert-send".to_string())
+            .spawn(move || match notify(&channel_ids, &alert) {
+                Ok(deliveries) => {
+                    for delivery in deliveries.into_iter().filter(|delivery| !delivery.ok) {
+                        eprintln!(
+                            "[alerts] sending to {} failed: {}",
+                            delivery.channel_id,
+                            delivery.error.unwrap_or_default()
+                        );
+                    }
+                }
+                Err(e) => eprintln!("[alerts] failed to send alert: {e}"),
+            });
+        if let Err(e) = spawned {
+            eprintln!("[alerts] failed to start sending an alert: {e}");
+        }
+    }
+}
+
+fn configured_channels() -> Result<Vec<AlertChannel>, String> {
+    Ok(crate::host_store::settings_load()?.alert_channels)
+}
+
+/// Send `alert` to each of `channel_ids` (the channels an alert rule
+/// selected). A failing channel does not stop the others.
+pub(crate) fn notify(channel_ids: &[String], alert: &Alert) -> Result<Vec<AlertDelivery>, String> {
+    let channels = configured_channels()?;
+    Ok(channel_ids
+        .iter()
+        .map(|channel_id| {
+            let result = channels
+                .iter()
+                .find(|channel| &channel.id == channel_id)
+                .ok_or_else(|| "Alert channel not found".to_string())
+                .and_then(|channel| send(channel, alert));
+            AlertDelivery {
+                channel_id: channel_id.clone(),
+                ok: result.is_ok(),
+                error: result.err(),
+            }
+        })
+        .collect())
+}
+
+/// Send a sample alert through one channel to check its configuration.
+#[tauri::command]
+pub async fn alert_channel_test(channel_id: String) -> Result<AlertDelivery, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let alert = Alert {
+            title: "Test alert from xTermius".to_string(),
+            message: "This channel is set up correctly.".to_string(),
+            severity: "info".to_string(),
+            host_id: None,
+            fired_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        };
+        notify(&[channel_id], &alert)?
+            .pop()
+            .ok_or_else(|| "Alert channel not found".to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        slack_text, triggered, validate_rules, Alert, AlertChannel, AlertChannelKind,
+        AlertCondition, AlertRule, Observation,
+    };
+    use crate::host_probe::{HostFilesystem, HostLiveInfo};
+    use crate::mock_backends::test_host;
+
+    #[test]
+    fn formats_slack_text_and_keeps_password_write_only() {
+        let alert = Alert {
+            title: "Disk almost full".to_string(),
+            message: "web-1: / is 95% full".to_string(),
+            severity: "critical".to_string(),
+            host_id: Some("h1".to_string()),
+            fired_at: "2026-01-01T00:00:00.000Z".to_string(),
+        };
+        assert_eq!(
+            slack_text(&alert),
+            ":red_circle: *Disk almost full*\nweb-1: / is 95% full"
+        );
+
+        let channel: AlertChannel = serde_json::from_str(
+            r#"{"id":"c1","kind":"smtp","smtp_host":"mail.example.com","smtp_password":"secret"}"#,
+        )
+        .unwrap();
+        assert_eq!(channel.kind, AlertChannelKind::Smtp);
+        assert_eq!(channel.smtp_password.as_deref(), Some("secret"));
+        assert!(!serde_json::to_string(&channel).unwrap().contains("secret"));
+    }
+
+    fn rule(id: &str, condition: AlertCondition) -> AlertRule {
+        AlertRule {
+            id: id.to_string(),
+            name: String::new(),
+            enabled: true,
+            condition,
+            threshold: None,
+            host_ids: Vec::new(),
+            channel_ids: vec!["c1".to_string()],
+        }
+    }
+
+    #[test]
+    fn rules_fire_once_per_outage() {
+        let host = test_host("alerts-web");
+        let rules = vec![
+            rule("alerts-down", AlertCondition::HostDown),
+            AlertRule {
+                host_ids: vec!["someone-else".to_string()],
+                ..rule("alerts-other", AlertCondition::HostDown)
+            },
+        ];
+        let down = Observation::Ping(Err("Connection refused"));
+
+        let fired = triggered(&rules, &host, &down);
+        assert_eq!(fired.len(), 1);
+        let (channels, alert) = &fired[0];
+        assert_eq!(channels, &vec!["c1".to_string()]);
+        assert_eq!(alert.title, "alerts-web.example.com is down");
+        assert_eq!(alert.host_id.as_deref(), Some("alerts-web"));
+        // Still down: already reported.
+        assert!(triggered(&rules, &host, &down).is_empty());
+        // A probe says nothing about pings, so the outage is not over.
+        assert!(triggered(&rules, &host, &Observation::LiveProbe(Err("timeout"))).is_empty());
+        assert!(triggered(&rules, &host, &down).is_empty());
+
+        assert!(triggered(&rules, &host, &Observation::Ping(Ok(()))).is_empty());
+        assert_eq!(triggered(&rules, &host, &down).len(), 1);
+    }
+
+    #[test]
+    fn disk_rule_names_the_full_filesystems() {
+        let host = test_host("alerts-db");
+        let rules = vec![AlertRule {
+            threshold: Some(80.0),
+            ..rule("alerts-disk", AlertCondition::DiskUsage)
+        }];
+        let fs = |mount_point: &str, used_kb: u64| HostFilesystem {
+            mount_point: mount_point.to_string(),
+            device: "/dev/sda1".to_string(),
+            fs_type: None,
+            total_kb: 100,
+            used_kb,
+            available_kb: 100 - used_kb,
+            inodes_total: None,
+            inodes_used: None,
+        };
+        let info = HostLiveInfo {
+            filesystems: vec![fs("/", 40), fs("/var", 85)],
+            ..Default::default()
+        };
+        let fired = triggered(&rules, &host, &Observation::LiveProbe(Ok(&info)));
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].1.message, "/var is 85% full");
+
+        let channels: Vec<AlertChannel> =
+            serde_json::from_str(r#"[{"id":"c1","kind":"slack","url":"https://example.com"}]"#)
+                .unwrap();
+        assert!(validate_rules(&rules, &channels).is_ok());
+        assert!(validate_rules(&rules, &[]).is_err());
+        let rules = vec![AlertRule {
+            threshold: Some(150.0),
+            ..rule("alerts-disk", AlertCondition::DiskUsage)
+        }];
+        assert!(validate_rules(&rules, &channels).is_err());
+    }
+}
@@ -3,9 +3,15 @@ use tauri::Manager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    crate::safe_mode::init();
     let builder = tauri::Builder::default()
         .manage(PtyState::default())
         .setup(|app| {
+            crate::credential_store::init(app.handle().clone());
+            crate::jobs::init(app.handle().clone());
+            crate::ssh_prompt::init(app.handle().clone());
+            crate::tmux::init(app.handle().clone());
+            crate::startup_health::spawn_startup_checks(app.handle().clone());
             #[cfg(target_os = "macos")]
             {
                 use window_vibrancy::{
@@ -23,6 +29,11 @@ pub fn run() {
             }
             Ok(())
         })
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::Destroyed = event {
+                window.state::<PtyState>().detach_window(window.label());
+            }
+        })
         .plugin(tauri_plugin_dialog::init());
 
     #[cfg(desktop)]
@@ -35,23 +46,143 @@ pub fn run() {
 
     builder
         .invoke_handler(tauri::generate_handler![
+            crate::startup_health::startup_status,
+            crate::safe_mode::safe_mode_status,
+            crate::safe_mode::safe_mode_export,
+            crate::app_lock::app_lock_status,
+            crate::app_lock::app_lock_enable,
+            crate::app_lock::app_lock_disable,
+            crate::app_lock::app_unlock,
+            crate::app_lock::app_lock,
+            crate::biometric::biometric_available,
             crate::host_store::hosts_load,
             crate::host_store::hosts_save,
+            crate::host_store::hosts_bulk_update,
+            crate::host_store::hosts_find_duplicates,
+            crate::host_store::hosts_merge,
+            crate::host_store::hosts_db_encrypt,
+            crate::host_store::hosts_db_encryption_key,
+            crate::data_schema::data_schemas,
+            crate::host_store::hosts_trash_list,
+            crate::host_store::host_restore,
+            crate::host_store::hosts_trash_purge,
+            crate::connection_history::host_connection_history,
+            crate::banner_ack::host_banner_status,
+            crate::banner_ack::host_banner_acknowledge,
+            crate::banner_ack::host_banner_ack_log,
+            crate::connection_history::host_connection_stats,
             crate::ssh_config::generate_ssh_config,
+            crate::ssh_config::hosts_export_ssh_config,
+            crate::ssh_config::ssh_connection_close,
             crate::ssh_import::ssh_config_scan_importable_hosts,
+            crate::ssh_import::ssh_known_hosts_scan_importable_hosts,
+            crate::discovery::discovery_scan,
+            crate::discovery::discovery_scan_subnet,
+            crate::discovery::discovery_add,
+            crate::tailscale::tailscale_peers,
+            crate::tailscale::tailscale_import,
+            crate::cloud_import::cloud_aws_instances,
+            crate::cloud_import::cloud_aws_import,
+            crate::cloud_import::cloud_aws_refresh,
             crate::host_store::settings_load,
             crate::host_store::settings_save,
+            crate::settings_sync::settings_sync_push,
+            crate::settings_sync::settings_sync_pull,
+            crate::host_probe::host_runtimes,
+            crate::wake_on_lan::host_wake,
+            crate::ssh_prompt::ssh_prompt_answer,
+            crate::host_compare::hosts_compare,
+            crate::reboot_schedule::host_schedule_reboot,
+            crate::reboot_schedule::host_cancel_reboot,
+            crate::reboot_schedule::host_pending_reboots,
+            crate::totp::host_totp_set,
+            crate::totp::host_totp_code,
+            crate::ssh_options::ssh_options_check,
+            crate::audit_log::audit_log_list,
+            crate::identity_discovery::identity_files_discover,
+            crate::identity_discovery::host_identity_suggest,
+            crate::backup::backup_export,
+            crate::backup::backup_import,
+            crate::disk_usage::remote_du,
+            crate::log_bundle::host_collect_logs,
+            crate::host_status::hosts_ping,
+            crate::host_status::hosts_ping_watch,
+            crate::host_status::hosts_ping_watch_stop,
+            crate::host_status::hosts_health_overview,
+            crate::host_probe::host_capabilities,
+            crate::fact_tags::tag_rules_load,
+            crate::fact_tags::tag_rules_save,
+            crate::fact_tags::host_facts,
+            crate::host_probe::host_privileged_probes,
             crate::host_probe::host_probe_static,
+            crate::host_rename::host_adopt_remote_name,
             crate::host_probe::host_probe_live,
+            crate::host_probe::host_reboot_status,
+            crate::host_probe::host_oom_events,
+            crate::host_probe::host_probe_docker,
+            crate::host_probe::hosts_probe_all_live,
+            crate::metrics_history::host_metrics_history,
+            crate::host_probe::host_probe_services,
+            crate::host_probe::host_service_restart,
+            crate::speedtest::host_speedtest,
+            crate::ssh_diagnose::host_ssh_diagnose,
+            crate::exec_diff::bulk_exec_diff,
+            crate::exec_diff::exec_results_diff,
+            crate::remote_exec::ssh_exec,
+            crate::remote_exec::ssh_exec_batch,
+            crate::remote_files::remote_archive,
+            crate::remote_files::remote_extract,
+            crate::remote_files::remote_delete,
+            crate::remote_files::remote_trash_list,
+            crate::remote_files::remote_trash_restore,
+            crate::operations::operation_cancel,
+            crate::operations::operations_list,
+            crate::jobs::jobs_list,
+            crate::jobs::job_cancel,
+            crate::alerts::alert_channel_test,
             crate::webdav_sync::webdav_pull,
             crate::webdav_sync::webdav_push,
             crate::credential_store::host_password_set,
             crate::credential_store::host_password_delete,
+            crate::credential_store::keychain_migrate_namespace,
+            crate::credential_store::credential_backend_status,
+            crate::credential_import::credentials_import_csv,
             crate::pty::pty_spawn_ssh,
+            crate::pty::pty_spawn_local,
             crate::pty::pty_write,
+            crate::pty::pty_type_secret,
             crate::pty::pty_resize,
+            crate::pty::pty_session_info,
+            crate::pty::pty_session_stats,
+            crate::pty::pty_scrollback,
+            crate::pty::pty_attach,
+            crate::pty::pty_detach,
+            crate::pty::pty_list,
+            crate::pty::pty_pause,
+            crate::pty::pty_resume,
+            crate::pty::pty_read_chunk,
             crate::pty::pty_kill,
+            crate::pty::serial_spawn,
+            crate::serial::serial_ports,
+            crate::session_restore::sessions_at_exit,
+            crate::session_restore::sessions_at_exit_clear,
+            crate::shell_profiles::shell_profiles_list,
+            crate::shell_profiles::shell_profile_save,
+            crate::shell_profiles::shell_profile_delete,
+            crate::tmux::tmux_attach,
+            crate::tmux::tmux_clients,
+            crate::tmux::tmux_windows,
+            crate::tmux::tmux_send_keys,
+            crate::tmux::tmux_resize,
+            crate::tmux::tmux_new_window,
+            crate::tmux::tmux_capture_pane,
+            crate::tmux::tmux_detach,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                app.state::<PtyState>().shutdown();
+            }
+        });
 }
@@ -0,0 +1,233 @@
+use crate::host_store::{atomic_write, ensure_config_dir, get_config_dir};
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+const DEFAULT_AUTO_LOCK_SECONDS: u64 = 15 * 60;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct AppLockConfig {
+    password_hash: String,
+    // 0 disables auto-lock; the app then stays unlocked until locked manually.
+    #[serde(default = "default_auto_lock_seconds")]
+    auto_lock_seconds: u64,
+}
+
+fn default_auto_lock_seconds() -> u64 {
+    DEFAULT_AUTO_LOCK_SECONDS
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppLockStatus {
+    pub enabled: bool,
+    pub locked: bool,
+    pub auto_lock_seconds: Option<u64>,
+}
+
+#[derive(Default)]
+struct LockState {
+    loaded: bool,
+    config: Option<AppLockConfig>,
+    // None while locked; otherwise the last time a gated operation ran.
+    last_activity: Option<Instant>,
+}
+
+fn get_app_lock_path() -> PathBuf {
+    get_config_dir().join("app_lock.json")
+}
+
+// The lock config is read once and then owned by this module: every change
+// goes through the commands below, so the on-disk file and memory stay in sync.
+fn with_lock_state<T>(f: impl FnOnce(&mut LockState) -> T) -> Result<T, String> {
+    static STATE: OnceLock<Mutex<LockState>> = OnceLock::new();
+    let mut state = STATE
+        .get_or_init(|| Mutex::new(LockState::default()))
+        .lock()
+        .map_err(|_| "app lock state poisoned".to_string())?;
+    if !state.loaded {
+        let path = get_app_lock_path();
+        if path.exists() {
+            let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+            state.config = Some(serde_json::from_str(&content).map_err(|e| e.to_string())?);
+        }
+        state.loaded = true;
+    }
+    Ok(f(&mut state))
+}
+
+fn is_expired(last_activity: Instant, auto_lock_seconds: u64, now: Instant) -> bool {
+    auto_lock_seconds > 0
+        && now.saturating_duration_since(last_activity) > Duration::from_secs(auto_lock_seconds)
+}
+
+fn is_locked(state: &mut LockState, now: Instant) -> bool {
+    let Some(config) = state.config.as_ref() else {
+        return false;
+    };
+    match state.last_activity {
+        Some(last) if !is_expired(last, config.auto_lock_seconds, now) => false,
+        _ => {
+            state.last_activity = None;
+            crate::biometric::reset_session();
+            true
+        }
+    }
+}
+
+fn hash_master_password(password: &str) -> Result<String, String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| format!("Failed to hash master password: {e}"))
+}
+
+fn verify_master_password(password: &str, password_hash: &str) -> bool {
+    PasswordHash::new(password_hash)
+        .map(|hash| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &hash)
+                .is_ok()
+        })
+        .unwrap_or(false)
+}
+
+fn save_config(config: &AppLockConfig) -> Result<(), String> {
+    ensure_config_dir()?;
+    let content = serde_json::to_string_pretty(config).map_err(|e| e.to_string())?;
+    atomic_write(&get_app_lock_path(), content.as_bytes())
+}
+
+/// Gate for operations that expose secrets or open sessions. Succeeds when
+/// no master password is set, and counts as activity for the auto-lock timer.
+pub(crate) fn ensure_unlocked() -> Result<(), String> {
+    with_lock_state(|state| {
+        let now = Instant::now();
+        if is_locked(state, now) {
+            return Err("xTermius is locked. Unlock with your master password first.".to_string());
+        }
+        if state.config.is_some() {
+            state.last_activity = Some(now);
+        }
+        Ok(())
+    })?
+}
+
+/// Refresh the auto-lock timer for user activity that is not itself gated
+/// (e.g. typing into an already open terminal).
+pub(crate) fn note_activity() {
+    let _ = with_lock_state(|state| {
+        let now = Instant::now();
+        if state.config.is_some() && !is_locked(state, now) {
+            state.last_activity = Some(now);
+        }
+    });
+}
+
+#[tauri::command]
+pub fn app_lock_status() -> Result<AppLockStatus, String> {
+    with_lock_state(|state| {
+        let locked = is_locked(state, Instant::now());
+        AppLockStatus {
+            enabled: state.config.is_some(),
+            locked,
+            auto_lock_seconds: state.config.as_ref().map(|c| c.auto_lock_seconds),
+        }
+    })
+}
+
+/// Set (or change) the master password. Changing an existing one requires
+/// `current_password`, as disabling the lock does.
+#[tauri::command]
+pub async fn app_lock_enable(
+    password: String,
+    current_password: Option<String>,
+    auto_lock_seconds: Option<u64>,
+) -> Result<(), String> {
+    if password.trim().is_empty() {
+        return Err("Master password is required".to_string());
+    }
+    if with_lock_state(|state| state.config.is_some())? {
+        let current_password = current_password
+            .filter(|current| !current.is_empty())
+            .ok_or_else(|| "Current master password is required".to_string())?;
+        app_unlock(current_password).await?;
+    }
+    let password_hash =
+        tauri::async_runtime::spawn_blocking(move || hash_master_password(&password))
+            .await
+            .map_err(|e| e.to_string())??;
+    let config = AppLockConfig {
+        password_hash,
+        auto_lock_seconds: auto_lock_seconds.unwrap_or(DEFAULT_AUTO_LOCK_SECONDS),
+    };
+    save_config(&config)?;
+    with_lock_state(|state| {
+        state.config = Some(config);
+        state.last_activity = Some(Instant::now());
+    })
+}
+
+#[tauri::command]
+pub async fn app_lock_disable(password: String) -> Result<(), String> {
+    app_unlock(password).await?;
+    let path = get_app_lock_path();
+    if path.exists() {
+        fs::remove_file(&path).map_err(|e| e.to_string())?;
+    }
+    with_lock_state(|state| {
+        state.config = None;
+        state.last_activity = None;
+    })
+}
+
+#[tauri::command]
+pub async fn app_unlock(password: String) -> Result<(), String> {
+    let Some(config) = with_lock_state(|state| state.config.clone())? else {
+        return Ok(());
+    };
+    let verified = tauri::async_runtime::spawn_blocking(move || {
+        verify_master_password(&password, &config.password_hash)
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    if !verified {
+        return Err("Incorrect master password".to_string());
+    }
+    with_lock_state(|state| state.last_activity = Some(Instant::now()))
+}
+
+#[tauri::command]
+pub fn app_lock() -> Result<(), String> {
+    crate::biometric::reset_session();
+    with_lock_state(|state| state.last_activity = None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hash_master_password, is_expired, verify_master_password};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn master_password_hash_roundtrip() {
+        let hash = hash_master_password("correct horse").unwrap();
+        assert!(hash.starts_with("$argon2"));
+        assert!(verify_master_password("correct horse", &hash));
+        assert!(!verify_master_password("wrong horse", &hash));
+        assert!(!verify_master_password("correct horse", "not-a-phc-string"));
+    }
+
+    #[test]
+    fn auto_lock_expires_only_after_timeout() {
+        let now = Instant::now();
+        assert!(!is_expired(now, 60, now + Duration::from_secs(60)));
+        assert!(is_expired(now, 60, now + Duration::from_secs(61)));
+        assert!(!is_expired(now, 0, now + Duration::from_secs(86_400)));
+    }
+}
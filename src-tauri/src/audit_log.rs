@@ -0,0 +1,295 @@
+// Append-only record of credential reads and writes and of sync runs, for
+// shared workstations and anyone who has to show who touched what. Kept in
+// its own audit.db so it never waits on a hosts.db transaction and never
+// travels with a synced hosts.db.
+
+use crate::host_store::{ensure_config_dir, get_config_dir};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+pub(crate) const ACTION_PASSWORD_READ: &str = "password_read";
+pub(crate) const ACTION_PASSWORD_SET: &str = "password_set";
+pub(crate) const ACTION_PASSWORD_DELETE: &str = "password_delete";
+pub(crate) const ACTION_TOTP_READ: &str = "totp_read";
+pub(crate) const ACTION_TOTP_SET: &str = "totp_set";
+pub(crate) const ACTION_TOTP_DELETE: &str = "totp_delete";
+
+const DEFAULT_LIST_LIMIT: u32 = 200;
+// Probes read a host's password on every run; repeated successful reads by
+// the same caller within this window are recorded once.
+const READ_COALESCE_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub id: i64,
+    pub at: String,
+    pub action: String,
+    pub host_id: Option<String>,
+    /// What asked: `ui`, `terminal`, `probe`, `backup`, `merge`...
+    pub caller: String,
+    pub ok: bool,
+    /// The error, for a failed operation.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AuditFilter {
+    pub host_id: Option<String>,
+    pub action: Option<String>,
+    pub caller: Option<String>,
+    /// RFC 3339; entries at or after it.
+    pub since: Option<String>,
+    pub limit: Option<u32>,
+}
+
+fn get_audit_db_path() -> PathBuf {
+    get_config_dir().join("audit.db")
+}
+
+fn open_audit_db() -> Result<Connection, String> {
+    ensure_config_dir()?;
+    let conn = Connection::open(get_audit_db_path()).map_err(|e| e.to_string())?;
+    conn.busy_timeout(Duration::from_secs(5))
+        .map_err(|e| e.to_string())?;
+    ensure_audit_schema(&conn)?;
+    Ok(conn)
+}
+
+fn ensure_audit_schema(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS audit_log (
+          id      INTEGER PRIMARY KEY AUTOINCREMENT,
+          at      TEXT NOT NULL,
+          action  TEXT NOT NULL,
+          host_id TEXT,
+          caller  TEXT NOT NULL,
+          ok      INTEGER NOT NULL,
+          error   TEXT
+        );
+        CREATE INDEX IF NOT EXISTS audit_log_at ON audit_log (at);
+        CREATE TRIGGER IF NOT EXISTS audit_log_no_update BEFORE UPDATE ON audit_log
+        BEGIN SELECT RAISE(ABORT, 'audit log is append-only'); END;
+        CREATE TRIGGER IF NOT EXISTS audit_log_no_delete BEFORE DELETE ON audit_log
+        BEGIN SELECT RAISE(ABORT, 'audit log is append-only'); END;
+        "#,
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn insert_entry(
+    conn: &Connection,
+    at: &str,
+    action: &str,
+    host_id: Option<&str>,
+    caller: &str,
+    error: Option<&str>,
+) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO audit_log (at, action, host_id, caller, ok, error) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            at,
+            action,
+            host_id,
+            caller,
+            if error.is_none() { 1 } else { 0 },
+            error
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// (action, host id, caller)
+type ReadKey = (String, String, String);
+
+fn recent_reads() -> &'static Mutex<HashMap<ReadKey, Instant>> {
+    static READS: OnceLock<Mutex<HashMap<ReadKey, Instant>>> = OnceLock::new();
+    READS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Whether a successful read was already recorded within the window.
+fn read_recently_recorded(action: &str, host_id: Option<&str>, caller: &str) -> bool {
+    let Ok(mut reads) = recent_reads().lock() else {
+        return false;
+    };
+    let now = Instant::now();
+    reads.retain(|_, at| now.duration_since(*at) < READ_COALESCE_WINDOW);
+    let key = (
+        action.to_string(),
+        host_id.unwrap_or_default().to_string(),
+        caller.to_string(),
+    );
+    if reads.contains_key(&key) {
+        return true;
+    }
+    reads.insert(key, now);
+    false
+}
+
+/// Record an operation and how it ended. Best effort: failing to write the
+/// log must not fail the operation itself.
+pub(crate) fn record<T>(
+    action: &str,
+    host_id: Option<&str>,
+    caller: &str,
+    result: &Result<T, String>,
+) {
+    let host_id = host_id.map(str::trim).filter(|id| !id.is_empty());
+    let error = result.as_ref().err().map(String::as_str);
+    if error.is_none()
+        && matches!(action, ACTION_PASSWORD_READ | ACTION_TOTP_READ)
+        && read_recently_recorded(action, host_id, caller)
+    {
+        return;
+    }
+    let at = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+    let written =
+        open_audit_db().and_then(|conn| insert_entry(&conn, &at, action, host_id, caller, error));
+    if let Err(e) = written {
+        eprintln!("[audit] failed to record {action}: {e}");
+    }
+}
+
+fn query_entries(conn: &Connection, filter: &AuditFilter) -> Result<Vec<AuditEntry>, String> {
+    let non_empty = |value: &Option<String>| {
+        value
+            .as_deref()
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    };
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT id, at, action, host_id, caller, ok, error
+            FROM audit_log
+            WHERE (?1 IS NULL OR host_id = ?1)
+              AND (?2 IS NULL OR action = ?2)
+              AND (?3 IS NULL OR caller = ?3)
+              AND (?4 IS NULL OR at >= ?4)
+            ORDER BY id DESC
+            LIMIT ?5
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+    let since = non_empty(&filter.since)
+        .map(|since| {
+            chrono::DateTime::parse_from_rfc3339(&since)
+                .map(|since| {
+                    since
+                        .with_timezone(&chrono::Utc)
+                        .to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+                })
+                .map_err(|e| format!("Invalid since: {e}"))
+        })
+        .transpose()?;
+    let rows = stmt
+        .query_map(
+            params![
+                non_empty(&filter.host_id),
+                non_empty(&filter.action),
+                non_empty(&filter.caller),
+                since,
+                filter.limit.unwrap_or(DEFAULT_LIST_LIMIT)
+            ],
+            |row| {
+                let ok: i64 = row.get(5)?;
+                Ok(AuditEntry {
+                    id: row.get(0)?,
+                    at: row.get(1)?,
+                    action: row.get(2)?,
+                    host_id: row.get(3)?,
+                    caller: row.get(4)?,
+                    ok: ok != 0,
+                    error: row.get(6)?,
+                })
+            },
+        )
+        .map_err(|e| e.to_string())?;
+    rows.map(|r| r.map_err(|e| e.to_string())).collect()
+}
+
+/// Audit entries matching `filter`, newest first.
+#[tauri::command]
+pub fn audit_log_list(filter: Option<AuditFilter>) -> Result<Vec<AuditEntry>, String> {
+    let conn = open_audit_db()?;
+    query_entries(&conn, &filter.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        audit_log_list, ensure_audit_schema, insert_entry, query_entries, AuditFilter,
+        ACTION_PASSWORD_READ,
+    };
+    use crate::credential_store::{keychain_get_password, keychain_set_password};
+    use crate::mock_backends::TestEnv;
+    use rusqlite::Connection;
+
+    #[test]
+    fn filters_entries_and_refuses_changes() {
+        let conn = Connection::open_in_memory().unwrap();
+        ensure_audit_schema(&conn).unwrap();
+        let at = "2026-03-01T10:00:00.000Z";
+        insert_entry(&conn, at, "password_set", Some("web"), "ui", None).unwrap();
+        insert_entry(&conn, at, "webdav_push", None, "ui", Some("offline")).unwrap();
+        insert_entry(
+            &conn,
+            "2026-03-02T10:00:00.000Z",
+            "password_read",
+            Some("web"),
+            "terminal",
+            None,
+        )
+        .unwrap();
+
+        let web = AuditFilter {
+            host_id: Some("web".to_string()),
+            ..Default::default()
+        };
+        let entries = query_entries(&conn, &web).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].caller, "terminal");
+        let since = AuditFilter {
+            since: Some("2026-03-02T11:00:00+02:00".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(query_entries(&conn, &since).unwrap().len(), 1);
+        let failed = &query_entries(&conn, &AuditFilter::default()).unwrap()[1];
+        assert!(!failed.ok);
+        assert_eq!(failed.error.as_deref(), Some("offline"));
+
+        assert!(conn.execute("DELETE FROM audit_log", []).is_err());
+        assert!(conn.execute("UPDATE audit_log SET ok = 1", []).is_err());
+    }
+
+    #[test]
+    fn records_credential_operations() {
+        let _env = TestEnv::new();
+        let id = uuid::Uuid::new_v4().to_string();
+        keychain_set_password(&id, "hunter22", "ui").unwrap();
+        keychain_get_password(&id, "terminal").unwrap();
+        keychain_get_password(&id, "terminal").unwrap();
+
+        let entries = audit_log_list(Some(AuditFilter {
+            host_id: Some(id.clone()),
+            ..Default::default()
+        }))
+        .unwrap();
+        let actions: Vec<(&str, &str)> = entries
+            .iter()
+            .map(|entry| (entry.action.as_str(), entry.caller.as_str()))
+            .collect();
+        assert_eq!(
+            actions,
+            vec![(ACTION_PASSWORD_READ, "terminal"), ("password_set", "ui")]
+        );
+    }
+}
@@ -0,0 +1,349 @@
+// Encrypted full backup for moving to another machine: hosts, settings, the
+// frontend's preferences (groups, snippets...) and the keychain secrets the
+// OS keychain would not carry over.
+
+use crate::credential_store::{alert_password_get, keychain_get_password, webdav_password_get};
+use crate::host_changes::forget_host_tombstones;
+use crate::host_store::{
+    atomic_write, hosts_load, hosts_save, merge_hosts_by_updated_at, open_hosts_db, settings_load,
+    settings_save,
+};
+use crate::models::{Host, Settings};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+const BACKUP_MAGIC: &[u8; 16] = b"XTERMIUS-BACKUP\0";
+const BACKUP_VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const HEADER_LEN: usize = BACKUP_MAGIC.len() + 1 + SALT_LEN + NONCE_LEN;
+const MIN_PASSPHRASE_CHARS: usize = 8;
+// Argon2id with today's defaults (as for the master password), pinned per
+// format version so a later change of the defaults cannot make old backups
+// unreadable.
+const KDF_MEMORY_KIB: u32 = 19 * 1024;
+const KDF_ITERATIONS: u32 = 2;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BackupSecrets {
+    host_passwords: BTreeMap<String, String>,
+    webdav_password: Option<String>,
+    alert_passwords: BTreeMap<String, String>,
+}
+
+impl BackupSecrets {
+    fn count(&self) -> usize {
+        self.host_passwords.len()
+            + self.alert_passwords.len()
+            + usize::from(self.webdav_password.is_some())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BackupPayload {
+    created_at: String,
+    hosts: Vec<Host>,
+    settings: Settings,
+    #[serde(default)]
+    preferences: Map<String, Value>,
+    #[serde(default)]
+    secrets: BackupSecrets,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupExportResult {
+    pub hosts: usize,
+    pub secrets: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupImportResult {
+    pub created_at: String,
+    pub hosts: usize,
+    pub secrets: usize,
+    /// For the UI to apply; preferences live in the frontend.
+    pub preferences: Map<String, Value>,
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32], String> {
+    let params = Params::new(KDF_MEMORY_KIB, KDF_ITERATIONS, 1, Some(32))
+        .map_err(|e| format!("Failed to derive backup key: {e}"))?;
+    let mut key = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Failed to derive backup key: {e}"))?;
+    Ok(key)
+}
+
+/// Magic, version, salt and nonce, followed by the sealed JSON. The header is
+/// authenticated too, so it cannot be swapped between files.
+fn seal(plaintext: &[u8], passphrase: &str) -> Result<Vec<u8>, String> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let mut out = Vec::with_capacity(HEADER_LEN + plaintext.len() + 16);
+    out.extend_from_slice(BACKUP_MAGIC);
+    out.push(BACKUP_VERSION);
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&nonce);
+    let key = derive_key(passphrase, &salt)?;
+    let sealed = XChaCha20Poly1305::new(&key.into())
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad: &out,
+            },
+        )
+        .map_err(|_| "Failed to encrypt backup".to_string())?;
+    out.extend_from_slice(&sealed);
+    Ok(out)
+}
+
+fn open(bytes: &[u8], passphrase: &str) -> Result<Vec<u8>, String> {
+    if bytes.len() < HEADER_LEN || !bytes.starts_with(BACKUP_MAGIC) {
+        return Err("Not an xTermius backup".to_string());
+    }
+    let version = bytes[BACKUP_MAGIC.len()];
+    if version != BACKUP_VERSION {
+        return Err(format!("Unsupported backup version {version}"));
+    }
+    let (header, sealed) = bytes.split_at(HEADER_LEN);
+    let salt = &header[BACKUP_MAGIC.len() + 1..BACKUP_MAGIC.len() + 1 + SALT_LEN];
+    let nonce = XNonce::from_slice(&header[HEADER_LEN - NONCE_LEN..]);
+    let key = derive_key(passphrase, salt)?;
+    XChaCha20Poly1305::new(&key.into())
+        .decrypt(
+            nonce,
+            Payload {
+                msg: sealed,
+                aad: header,
+            },
+        )
+        .map_err(|_| "Wrong passphrase or damaged backup".to_string())
+}
+
+fn check_passphrase(passphrase: &str) -> Result<(), String> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_CHARS {
+        return Err(format!(
+            "Backup passphrase must be at least {MIN_PASSPHRASE_CHARS} characters"
+        ));
+    }
+    Ok(())
+}
+
+fn collect_payload(preferences: Map<String, Value>) -> Result<BackupPayload, String> {
+    let hosts: Vec<Host> = hosts_load()?
+        .into_iter()
+        .filter(|host| !host.deleted)
+        .collect();
+    let settings = settings_load()?;
+    let mut secrets = BackupSecrets {
+        webdav_password: webdav_password_get()?,
+        ..Default::default()
+    };
+    for host in hosts.iter().filter(|host| host.has_password) {
+        if let Some(password) = keychain_get_password(&host.id, "backup")? {
+            secrets.host_passwords.insert(host.id.clone(), password);
+        }
+    }
+    for channel in settings
+        .alert_channels
+        .iter()
+        .filter(|channel| channel.has_smtp_password)
+    {
+        if let Some(password) = alert_password_get(&channel.id)? {
+            secrets.alert_passwords.insert(channel.id.clone(), password);
+        }
+    }
+    Ok(BackupPayload {
+        created_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        hosts,
+        settings,
+        preferences,
+        secrets,
+    })
+}
+
+fn export_backup(
+    path: &Path,
+    passphrase: &str,
+    preferences: Map<String, Value>,
+) -> Result<BackupExportResult, String> {
+    check_passphrase(passphrase)?;
+    // The archive holds every saved secret, so it is gated like revealing one.
+    crate::biometric::confirm_password_access()?;
+    let payload = collect_payload(preferences)?;
+    let plaintext = serde_json::to_vec(&payload).map_err(|e| e.to_string())?;
+    atomic_write(path, &seal(&plaintext, passphrase)?)?;
+    Ok(BackupExportResult {
+        hosts: payload.hosts.len(),
+        secrets: payload.secrets.count(),
+    })
+}
+
+/// Restore hosts (merged with the local ones; the newer copy of a host wins),
+/// settings and secrets. A host's saved password is restored when the backup
+/// copy wins or the local host has none.
+fn import_backup(path: &Path, passphrase: &str) -> Result<BackupImportResult, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let payload: BackupPayload = serde_json::from_slice(&open(&bytes, passphrase)?)
+        .map_err(|e| format!("Backup is invalid: {e}"))?;
+    let BackupPayload {
+        created_at,
+        hosts,
+        mut settings,
+        preferences,
+        secrets,
+    } = payload;
+    let secret_count = secrets.count();
+    let host_count = hosts.len();
+
+    let backup_updated_at: HashMap<String, String> = hosts
+        .iter()
+        .map(|host| (host.id.clone(), host.updated_at.clone()))
+        .collect();
+    // Restoring is an explicit request to have these hosts back, including
+    // ones purged since the backup was taken.
+    forget_host_tombstones(&open_hosts_db()?, backup_updated_at.keys())?;
+    let mut merged = merge_hosts_by_updated_at(hosts_load()?, hosts, &HashMap::new());
+    for host in merged.iter_mut() {
+        let from_backup = backup_updated_at.get(&host.id) == Some(&host.updated_at);
+        if let Some(password) = secrets.host_passwords.get(&host.id) {
+            if from_backup || !host.has_password {
+                host.password = Some(password.clone());
+            }
+        }
+    }
+    hosts_save(merged)?;
+
+    settings.webdav_password = secrets.webdav_password;
+    for channel in settings.alert_channels.iter_mut() {
+        channel.smtp_password = secrets.alert_passwords.get(&channel.id).cloned();
+    }
+    settings_save(settings)?;
+
+    Ok(BackupImportResult {
+        created_at,
+        hosts: host_count,
+        secrets: secret_count,
+        preferences,
+    })
+}
+
+/// Write an encrypted archive of hosts, settings, `preferences` (the
+/// frontend's groups, snippets...) and keychain passwords to `path`.
+#[tauri::command]
+pub async fn backup_export(
+    path: String,
+    passphrase: String,
+    preferences: Option<Map<String, Value>>,
+) -> Result<BackupExportResult, String> {
+    crate::app_lock::ensure_unlocked()?;
+    tauri::async_runtime::spawn_blocking(move || {
+        export_backup(
+            Path::new(&path),
+            &passphrase,
+            preferences.unwrap_or_default(),
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Restore a `backup_export` archive. Returns the backed-up preferences for
+/// the UI to apply.
+#[tauri::command]
+pub async fn backup_import(path: String, passphrase: String) -> Result<BackupImportResult, String> {
+    crate::app_lock::ensure_unlocked()?;
+    tauri::async_runtime::spawn_blocking(move || import_backup(Path::new(&path), &passphrase))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{export_backup, import_backup, open, seal};
+    use crate::biometric::PasswordPresence;
+    use crate::credential_store::{keychain_delete_password, keychain_get_password};
+    use crate::host_store::{hosts_load, hosts_save};
+    use crate::mock_backends::{mock_presence, test_host, TestEnv};
+    use crate::models::Host;
+
+    #[test]
+    fn seals_with_passphrase() {
+        let sealed = seal(b"{\"hosts\":[]}", "correct horse").unwrap();
+        assert_eq!(open(&sealed, "correct horse").unwrap(), b"{\"hosts\":[]}");
+        assert!(open(&sealed, "wrong horse").is_err());
+
+        let mut tampered = sealed.clone();
+        tampered[20] ^= 1;
+        assert!(open(&tampered, "correct horse").is_err());
+        assert_eq!(
+            open(b"hello", "correct horse").unwrap_err(),
+            "Not an xTermius backup"
+        );
+    }
+
+    #[test]
+    fn restores_hosts_and_passwords() {
+        let env = TestEnv::new();
+        let id = uuid::Uuid::new_v4().to_string();
+        let mut host = Host {
+            alias: "db".to_string(),
+            hostname: "db.example.com".to_string(),
+            user: "postgres".to_string(),
+            updated_at: "2026-01-01T00:00:00.000Z".to_string(),
+            ..test_host(&id)
+        };
+        host.password = Some("hunter22".to_string());
+        hosts_save(vec![host]).unwrap();
+        let path = env.config_dir().join("xtermius.backup");
+        let mut preferences = serde_json::Map::new();
+        preferences.insert(
+            "snippets".to_string(),
+            serde_json::json!([{ "body": "make" }]),
+        );
+
+        let exported = export_backup(&path, "passphrase", preferences).unwrap();
+        assert_eq!((exported.hosts, exported.secrets), (1, 1));
+        assert!(export_backup(&path, "short", serde_json::Map::new()).is_err());
+
+        // A new machine: no hosts and an empty keychain.
+        hosts_save(Vec::new()).unwrap();
+        keychain_delete_password(&id, "test").unwrap();
+        assert!(import_backup(&path, "not the passphrase").is_err());
+        let restored = import_backup(&path, "passphrase").unwrap();
+        assert_eq!(restored.hosts, 1);
+        assert!(restored.preferences.contains_key("snippets"));
+        let hosts = hosts_load().unwrap();
+        assert_eq!(hosts.len(), 1);
+        assert!(hosts[0].has_password);
+        assert_eq!(
+            keychain_get_password(&id, "test").unwrap().as_deref(),
+            Some("hunter22")
+        );
+    }
+
+    #[test]
+    fn export_needs_the_presence_check() {
+        let env = TestEnv::new();
+        let path = env.config_dir().join("xtermius.backup");
+        mock_presence(PasswordPresence::PerCall, false);
+        assert!(export_backup(&path, "passphrase", serde_json::Map::new()).is_err());
+        assert!(!path.exists());
+
+        mock_presence(PasswordPresence::PerCall, true);
+        assert!(export_backup(&path, "passphrase", serde_json::Map::new()).is_ok());
+    }
+}
@@ -0,0 +1,167 @@
+use crate::host_store::{atomic_write, ensure_config_dir, get_config_dir};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+/// One acknowledgment of a host's login banner. The log only grows, so it
+/// doubles as the audit trail.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BannerAck {
+    pub host_id: String,
+    pub banner_hash: String,
+    pub banner: String,
+    pub acknowledged_at: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BannerStatus {
+    pub banner_hash: String,
+    /// The current banner text was acknowledged before.
+    pub acknowledged: bool,
+    /// An earlier, different banner of the host was acknowledged.
+    pub changed: bool,
+    pub acknowledged_at: Option<String>,
+}
+
+fn get_banner_acks_path() -> PathBuf {
+    get_config_dir().join("banner_acks.json")
+}
+
+fn banner_acks_lock() -> &'static Mutex<()> {
+    static LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+    LOCK.get_or_init(|| Mutex::new(()))
+}
+
+fn load_acks() -> Result<Vec<BannerAck>, String> {
+    let path = get_banner_acks_path();
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&content).map_err(|e| e.to_string())
+}
+
+// Line endings and trailing whitespace differ between how sshd sends the
+// banner and how the terminal captured it; neither changes what it says.
+fn normalize_banner(banner: &str) -> String {
+    let normalized = banner.replace("\r\n", "\n").replace('\r', "\n");
+    let lines: Vec<&str> = normalized.lines().map(str::trim_end).collect();
+    lines.join("\n").trim_matches('\n').to_string()
+}
+
+fn banner_hash(banner: &str) -> String {
+    Sha256::digest(banner.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+fn banner_status(acks: &[BannerAck], host_id: &str, hash: &str) -> BannerStatus {
+    let host_acks: Vec<&BannerAck> = acks.iter().filter(|ack| ack.host_id == host_id).collect();
+    let current = host_acks.iter().rev().find(|ack| ack.banner_hash == hash);
+    BannerStatus {
+        banner_hash: hash.to_string(),
+        acknowledged: current.is_some(),
+        changed: current.is_none() && !host_acks.is_empty(),
+        acknowledged_at: current.map(|ack| ack.acknowledged_at.clone()),
+    }
+}
+
+fn normalized_input(host_id: &str, banner: &str) -> Result<(String, String), String> {
+    let host_id = host_id.trim();
+    if host_id.is_empty() {
+        return Err("host_id is required".to_string());
+    }
+    let banner = normalize_banner(banner);
+    if banner.is_empty() {
+        return Err("Banner text is empty".to_string());
+    }
+    Ok((host_id.to_string(), banner))
+}
+
+/// Whether `banner` (as shown when connecting to the host) still needs to be
+/// acknowledged: it was never acknowledged, or its text changed since.
+#[tauri::command]
+pub fn host_banner_status(host_id: String, banner: String) -> Result<BannerStatus, String> {
+    let (host_id, banner) = normalized_input(&host_id, &banner)?;
+    Ok(banner_status(
+        &load_acks()?,
+        &host_id,
+        &banner_hash(&banner),
+    ))
+}
+
+#[tauri::command]
+pub fn host_banner_acknowledge(host_id: String, banner: String) -> Result<BannerAck, String> {
+    let (host_id, banner) = normalized_input(&host_id, &banner)?;
+    let _guard = banner_acks_lock()
+        .lock()
+        .map_err(|_| "banner log lock poisoned".to_string())?;
+    let mut acks = load_acks()?;
+    let ack = BannerAck {
+        host_id,
+        banner_hash: banner_hash(&banner),
+        banner,
+        acknowledged_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+    };
+    acks.push(ack.clone());
+    ensure_config_dir()?;
+    let content = serde_json::to_string_pretty(&acks).map_err(|e| e.to_string())?;
+    atomic_write(&get_banner_acks_path(), content.as_bytes())?;
+    Ok(ack)
+}
+
+/// The acknowledgment log, newest first, optionally for one host.
+#[tauri::command]
+pub fn host_banner_ack_log(host_id: Option<String>) -> Result<Vec<BannerAck>, String> {
+    let host_id = host_id.map(|id| id.trim().to_string());
+    let mut acks: Vec<BannerAck> = load_acks()?
+        .into_iter()
+        .filter(|ack| host_id.as_deref().is_none_or(|id| ack.host_id == id))
+        .collect();
+    acks.reverse();
+    Ok(acks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{banner_hash, banner_status, normalize_banner, BannerAck};
+
+    fn ack(host_id: &str, banner: &str, at: &str) -> BannerAck {
+        BannerAck {
+            host_id: host_id.to_string(),
+            banner_hash: banner_hash(banner),
+            banner: banner.to_string(),
+            acknowledged_at: at.to_string(),
+        }
+    }
+
+    #[test]
+    fn requires_reacknowledgment_when_banner_changes() {
+        assert_eq!(
+            normalize_banner("\r\nAuthorized use only.  \r\n\r\n"),
+            "Authorized use only."
+        );
+        let acks = vec![
+            ack("h1", "Authorized use only.", "2026-01-01T00:00:00.000Z"),
+            ack("h2", "Other host", "2026-01-02T00:00:00.000Z"),
+        ];
+
+        let same = banner_status(&acks, "h1", &banner_hash("Authorized use only."));
+        assert!(same.acknowledged && !same.changed);
+        assert_eq!(
+            same.acknowledged_at.as_deref(),
+            Some("2026-01-01T00:00:00.000Z")
+        );
+
+        let edited = banner_status(&acks, "h1", &banner_hash("Monitored. Authorized use only."));
+        assert!(!edited.acknowledged && edited.changed);
+
+        let new_host = banner_status(&acks, "h3", &banner_hash("Authorized use only."));
+        assert!(!new_host.acknowledged && !new_host.changed);
+    }
+}
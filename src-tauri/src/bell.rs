@@ -0,0 +1,117 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// What the UI does when a session rings the terminal bell.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BellAction {
+    /// Play the system alert sound.
+    #[default]
+    Sound,
+    /// Show a desktop notification (useful when the app is in the background).
+    Notification,
+    /// Stay silent and mark the session's tab.
+    Badge,
+    /// Drop the bell; no event is sent.
+    Ignore,
+}
+
+impl BellAction {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            BellAction::Sound => "sound",
+            BellAction::Notification => "notification",
+            BellAction::Badge => "badge",
+            BellAction::Ignore => "ignore",
+        }
+    }
+
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "sound" => Some(BellAction::Sound),
+            "notification" => Some(BellAction::Notification),
+            "badge" => Some(BellAction::Badge),
+            "ignore" => Some(BellAction::Ignore),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BellPayload {
+    pub session_id: String,
+    pub host_id: Option<String>,
+    pub action: BellAction,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+enum BellScan {
+    #[default]
+    Ground,
+    Escape,
+    // Inside an OSC/DCS/APC/PM/SOS string, where BEL is a terminator.
+    String,
+    StringEscape,
+}
+
+/// Counts BEL characters that ring the bell, skipping the ones that end an
+/// OSC sequence (window titles, hyperlinks...). Keeps its state across
+/// output chunks.
+#[derive(Debug, Default)]
+pub(crate) struct BellDetector {
+    scan: BellScan,
+}
+
+impl BellDetector {
+    pub(crate) fn feed(&mut self, data: &str) -> usize {
+        let mut bells = 0;
+        for ch in data.chars() {
+            self.scan = match (self.scan, ch) {
+                (BellScan::Ground, '\x07') => {
+                    bells += 1;
+                    BellScan::Ground
+                }
+                (BellScan::Ground, '\x1b') => BellScan::Escape,
+                (BellScan::Ground, _) => BellScan::Ground,
+                (BellScan::Escape, ']' | 'P' | '_' | '^' | 'X') => BellScan::String,
+                (BellScan::Escape, '\x1b') => BellScan::Escape,
+                (BellScan::Escape, '\x07') => {
+                    bells += 1;
+                    BellScan::Ground
+                }
+                (BellScan::Escape, _) => BellScan::Ground,
+                (BellScan::String | BellScan::StringEscape, '\x07') => BellScan::Ground,
+                (BellScan::String | BellScan::StringEscape, '\x1b') => BellScan::StringEscape,
+                (BellScan::StringEscape, '\\') => BellScan::Ground,
+                (BellScan::String | BellScan::StringEscape, _) => BellScan::String,
+            };
+        }
+        bells
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BellAction, BellDetector};
+
+    #[test]
+    fn counts_bells_outside_osc_strings() {
+        let mut detector = BellDetector::default();
+        assert_eq!(detector.feed("make: done\x07"), 1);
+        assert_eq!(detector.feed("\x1b]0;deploy@web\x07$ "), 0);
+        assert_eq!(
+            detector.feed("\x1b]8;;https://example.com\x1b\\link\x07"),
+            1
+        );
+        // A title split across chunks.
+        assert_eq!(detector.feed("\x1b]2;vim"), 0);
+        assert_eq!(detector.feed(" notes.txt\x07\x07"), 1);
+
+        assert_eq!(BellAction::parse("badge"), Some(BellAction::Badge));
+        assert_eq!(
+            BellAction::parse(BellAction::Ignore.as_str()),
+            Some(BellAction::Ignore)
+        );
+        assert_eq!(BellAction::parse("loud"), None);
+    }
+}
@@ -0,0 +1,200 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+/// When revealing a saved host password or TOTP code to the user requires
+/// confirming the device owner (Touch ID / Windows Hello, falling back to the
+/// account password). The app's own reads, for probes or logins, never ask.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PasswordPresence {
+    #[default]
+    Off,
+    PerCall,
+    PerSession,
+}
+
+// Set once the user confirmed presence in PerSession mode; cleared when the
+// app is locked so the next read asks again.
+static SESSION_CONFIRMED: AtomicBool = AtomicBool::new(false);
+
+fn presence_mode() -> &'static Mutex<Option<PasswordPresence>> {
+    static MODE: OnceLock<Mutex<Option<PasswordPresence>>> = OnceLock::new();
+    MODE.get_or_init(|| Mutex::new(None))
+}
+
+fn current_mode() -> PasswordPresence {
+    #[cfg(test)]
+    if let Some((mode, _)) = crate::mock_backends::presence_override() {
+        return mode;
+    }
+    if let Ok(mode) = presence_mode().lock() {
+        if let Some(mode) = *mode {
+            return mode;
+        }
+    }
+    let mode = crate::host_store::settings_load()
+        .map(|settings| settings.password_presence)
+        .unwrap_or_default();
+    set_presence_mode(mode);
+    mode
+}
+
+/// Changing the mode drops an earlier confirmation, so the next password use
+/// asks again under the new mode.
+pub(crate) fn set_presence_mode(mode: PasswordPresence) {
+    if let Ok(mut current) = presence_mode().lock() {
+        if *current != Some(mode) {
+            SESSION_CONFIRMED.store(false, Ordering::Release);
+        }
+        *current = Some(mode);
+    }
+}
+
+pub(crate) fn reset_session() {
+    SESSION_CONFIRMED.store(false, Ordering::Release);
+}
+
+/// Ask the OS to confirm the device owner before a saved secret is revealed.
+pub(crate) fn confirm_password_access() -> Result<(), String> {
+    match current_mode() {
+        PasswordPresence::Off => Ok(()),
+        PasswordPresence::PerSession if SESSION_CONFIRMED.load(Ordering::Acquire) => Ok(()),
+        mode => {
+            if !confirm_presence("access a saved SSH password")? {
+                return Err("Authentication was cancelled or failed".to_string());
+            }
+            if mode == PasswordPresence::PerSession {
+                SESSION_CONFIRMED.store(true, Ordering::Release);
+            }
+            Ok(())
+        }
+    }
+}
+
+// Higher asks more often.
+fn strictness(mode: PasswordPresence) -> u8 {
+    match mode {
+        PasswordPresence::Off => 0,
+        PasswordPresence::PerSession => 1,
+        PasswordPresence::PerCall => 2,
+    }
+}
+
+/// Asking less often than now needs the device owner, every time, so the
+/// check cannot be turned off from an unlocked app alone.
+pub(crate) fn confirm_mode_change(mode: PasswordPresence) -> Result<(), String> {
+    if strictness(mode) >= strictness(current_mode()) {
+        return Ok(());
+    }
+    if !confirm_presence("change when saved passwords need confirmation")? {
+        return Err("Authentication was cancelled or failed".to_string());
+    }
+    Ok(())
+}
+
+fn confirm_presence(reason: &str) -> Result<bool, String> {
+    #[cfg(test)]
+    if let Some((_, confirmed)) = crate::mock_backends::presence_override() {
+        return Ok(confirmed);
+    }
+    platform_confirm_presence(reason)
+}
+
+#[cfg(target_os = "macos")]
+fn platform_available() -> bool {
+    use objc2_local_authentication::{LAContext, LAPolicy};
+
+    let context = unsafe { LAContext::new() };
+    unsafe { context.canEvaluatePolicy_error(LAPolicy::DeviceOwnerAuthentication) }.is_ok()
+}
+
+#[cfg(target_os = "macos")]
+fn platform_confirm_presence(reason: &str) -> Result<bool, String> {
+    use block2::RcBlock;
+    use objc2::runtime::Bool;
+    use objc2_foundation::{NSError, NSString};
+    use objc2_local_authentication::{LAContext, LAPolicy};
+    use std::sync::mpsc;
+
+    let context = unsafe { LAContext::new() };
+    let policy = LAPolicy::DeviceOwnerAuthentication;
+    if let Err(error) = unsafe { context.canEvaluatePolicy_error(policy) } {
+        return Err(format!(
+            "Device owner authentication is unavailable: {}",
+            error.localizedDescription()
+        ));
+    }
+    // The reply block runs on a LocalAuthentication queue; block this (worker)
+    // thread until the user answers the system prompt.
+    let (tx, rx) = mpsc::channel();
+    let reply = RcBlock::new(move |success: Bool, _error: *mut NSError| {
+        let _ = tx.send(success.as_bool());
+    });
+    unsafe {
+        context.evaluatePolicy_localizedReason_reply(policy, &NSString::from_str(reason), &reply);
+    }
+    rx.recv().map_err(|e| e.to_string())
+}
+
+#[cfg(windows)]
+fn platform_available() -> bool {
+    use windows::Security::Credentials::UI::{
+        UserConsentVerifier, UserConsentVerifierAvailability,
+    };
+
+    UserConsentVerifier::CheckAvailabilityAsync()
+        .and_then(|op| op.get())
+        .map(|availability| availability == UserConsentVerifierAvailability::Available)
+        .unwrap_or(false)
+}
+
+#[cfg(windows)]
+fn platform_confirm_presence(reason: &str) -> Result<bool, String> {
+    use windows::core::HSTRING;
+    use windows::Security::Credentials::UI::{UserConsentVerificationResult, UserConsentVerifier};
+
+    let result = UserConsentVerifier::RequestVerificationAsync(&HSTRING::from(reason))
+        .and_then(|op| op.get())
+        .map_err(|e| e.to_string())?;
+    Ok(result == UserConsentVerificationResult::Verified)
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn platform_available() -> bool {
+    false
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn platform_confirm_presence(_reason: &str) -> Result<bool, String> {
+    Err("OS authentication is not supported on this platform".to_string())
+}
+
+#[tauri::command]
+pub async fn biometric_available() -> Result<bool, String> {
+    tauri::async_runtime::spawn_blocking(platform_available)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{confirm_mode_change, PasswordPresence};
+    use crate::mock_backends::{mock_presence, TestEnv};
+
+    #[test]
+    fn weakening_the_check_needs_presence() {
+        let _env = TestEnv::new();
+        mock_presence(PasswordPresence::PerCall, false);
+        assert!(confirm_mode_change(PasswordPresence::PerCall).is_ok());
+        assert!(confirm_mode_change(PasswordPresence::PerSession).is_err());
+        assert!(confirm_mode_change(PasswordPresence::Off).is_err());
+
+        mock_presence(PasswordPresence::PerCall, true);
+        assert!(confirm_mode_change(PasswordPresence::Off).is_ok());
+
+        mock_presence(PasswordPresence::Off, false);
+        assert!(confirm_mode_change(PasswordPresence::PerSession).is_ok());
+    }
+}
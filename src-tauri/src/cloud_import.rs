@@ -0,0 +1,287 @@
+use crate::host_import::{
+    alias_from_name, import_candidates, linked_host_id, run_inventory_cli, HostImportReport,
+    ImportCandidate,
+};
+use crate::host_store::hosts_load;
+use serde::{Deserialize, Serialize};
+use std::net::Ipv4Addr;
+
+const AWS_BINARIES: &[&str] = &[
+    "/opt/homebrew/bin/aws",
+    "/usr/local/bin/aws",
+    "/usr/bin/aws",
+    "aws",
+];
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribeInstances {
+    #[serde(default)]
+    reservations: Vec<Reservation>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Reservation {
+    #[serde(default)]
+    instances: Vec<InstanceJson>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct InstanceJson {
+    instance_id: String,
+    #[serde(default)]
+    key_name: Option<String>,
+    #[serde(default)]
+    private_ip_address: Option<String>,
+    #[serde(default)]
+    public_ip_address: Option<String>,
+    #[serde(default)]
+    tags: Vec<TagJson>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TagJson {
+    key: String,
+    value: String,
+}
+
+/// A running EC2 instance offered for import.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CloudInstance {
+    pub provider: String,
+    pub region: String,
+    pub instance_id: String,
+    pub name: Option<String>,
+    pub public_ip: Option<String>,
+    pub private_ip: Option<String>,
+    pub key_name: Option<String>,
+    /// The host already imported from this instance, if any.
+    pub linked_host_id: Option<String>,
+}
+
+fn validate_region(region: &str) -> Result<&str, String> {
+    let region = region.trim();
+    if region.is_empty()
+        || !region
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+    {
+        return Err(format!("Invalid AWS region: {region}"));
+    }
+    Ok(region)
+}
+
+fn describe_running_instances(profile: Option<&str>, region: &str) -> Result<Vec<u8>, String> {
+    let mut args = vec![
+        "ec2",
+        "describe-instances",
+        "--filters",
+        "Name=instance-state-name,Values=running",
+        "--output",
+        "json",
+        "--region",
+        region,
+    ];
+    if let Some(profile) = profile.map(str::trim).filter(|p| !p.is_empty()) {
+        args.push("--profile");
+        args.push(profile);
+    }
+    run_inventory_cli(AWS_BINARIES, &args)
+}
+
+fn parse_instances(json: &[u8], region: &str) -> Result<Vec<CloudInstance>, String> {
+    let parsed: DescribeInstances = serde_json::from_slice(json)
+        .map_err(|e| format!("Unexpected describe-instances output: {e}"))?;
+    let mut instances: Vec<CloudInstance> = parsed
+        .reservations
+        .into_iter()
+        .flat_map(|reservation| reservation.instances)
+        .map(|instance| CloudInstance {
+            provider: "aws".to_string(),
+            region: region.to_string(),
+            name: instance
+                .tags
+                .iter()
+                .find(|tag| tag.key == "Name")
+                .map(|tag| tag.value.trim().to_string())
+                .filter(|name| !name.is_empty()),
+            instance_id: instance.instance_id,
+            public_ip: instance.public_ip_address,
+            private_ip: instance.private_ip_address,
+            key_name: instance.key_name,
+            linked_host_id: None,
+        })
+        .collect();
+    instances.sort_by(|a, b| {
+        (a.name.as_deref(), &a.instance_id).cmp(&(b.name.as_deref(), &b.instance_id))
+    });
+    Ok(instances)
+}
+
+fn source_of(instance: &CloudInstance) -> String {
+    format!(
+        "{}:{}:{}",
+        instance.provider, instance.region, instance.instance_id
+    )
+}
+
+fn list_instances(profile: Option<&str>, region: &str) -> Result<Vec<CloudInstance>, String> {
+    let region = validate_region(region)?;
+    let mut instances = parse_instances(&describe_running_instances(profile, region)?, region)?;
+    let hosts = hosts_load()?;
+    for instance in instances.iter_mut() {
+        instance.linked_host_id = linked_host_id(&hosts, &source_of(instance));
+    }
+    Ok(instances)
+}
+
+// EC2 key pairs are usually saved as ~/.ssh/<key name>.pem.
+fn identity_file_for(key_name: Option<&str>) -> Option<String> {
+    let key_name = key_name?.trim();
+    if key_name.is_empty() || key_name.contains(['/', '\\']) {
+        return None;
+    }
+    let ssh_dir = dirs::home_dir()?.join(".ssh");
+    [format!("{key_name}.pem"), key_name.to_string()]
+        .into_iter()
+        .map(|file| ssh_dir.join(file))
+        .find(|path| path.is_file())
+        .map(|path| path.to_string_lossy().to_string())
+}
+
+fn is_private_address(address: &str) -> bool {
+    address.parse::<Ipv4Addr>().is_ok_and(|ip| ip.is_private())
+}
+
+fn candidate_for(instance: &CloudInstance, use_private_ip: bool) -> Option<ImportCandidate> {
+    let hostname = if use_private_ip {
+        instance.private_ip.clone()
+    } else {
+        instance
+            .public_ip
+            .clone()
+            .or_else(|| instance.private_ip.clone())
+    }?;
+    let name = instance
+        .name
+        .clone()
+        .unwrap_or_else(|| instance.instance_id.clone());
+    Some(ImportCandidate {
+        source: source_of(instance),
+        alias: alias_from_name(&name, &instance.instance_id),
+        name,
+        hostname,
+        port: 22,
+        identity_file: identity_file_for(instance.key_name.as_deref()),
+        tags: Vec::new(),
+    })
+}
+
+/// Running EC2 instances of `region` (with the CLI's default credentials or
+/// `profile`), each with the host it is already imported as.
+#[tauri::command]
+pub async fn cloud_aws_instances(
+    profile: Option<String>,
+    region: String,
+) -> Result<Vec<CloudInstance>, String> {
+    tauri::async_runtime::spawn_blocking(move || list_instances(profile.as_deref(), &region))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Import the selected instances. `use_private_ip` picks the private address
+/// (for VPN or bastion setups); otherwise the public one is used when the
+/// instance has one.
+#[tauri::command]
+pub async fn cloud_aws_import(
+    profile: Option<String>,
+    region: String,
+    instance_ids: Vec<String>,
+    user: Option<String>,
+    use_private_ip: Option<bool>,
+) -> Result<HostImportReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let use_private_ip = use_private_ip.unwrap_or(false);
+        let candidates = list_instances(profile.as_deref(), &region)?
+            .iter()
+            .filter(|instance| instance_ids.contains(&instance.instance_id))
+            .filter_map(|instance| candidate_for(instance, use_private_ip))
+            .collect();
+        import_candidates(candidates, user)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Re-sync hosts imported from `region`: instances that got a new address
+/// (e.g. after a stop/start) are updated in place. Each host keeps the kind
+/// of address it was imported with.
+#[tauri::command]
+pub async fn cloud_aws_refresh(
+    profile: Option<String>,
+    region: String,
+) -> Result<HostImportReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let hosts = hosts_load()?;
+        let candidates = list_instances(profile.as_deref(), &region)?
+            .iter()
+            .filter_map(|instance| {
+                let host_id = instance.linked_host_id.as_deref()?;
+                let host = hosts.iter().find(|host| host.id == host_id)?;
+                candidate_for(instance, is_private_address(host.hostname.trim()))
+            })
+            .collect();
+        import_candidates(candidates, None)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{candidate_for, parse_instances, validate_region};
+
+    #[test]
+    fn maps_instances_to_candidates() {
+        let json = br#"{
+            "Reservations": [
+                { "Instances": [
+                    { "InstanceId": "i-0b", "PrivateIpAddress": "10.0.1.5",
+                      "Tags": [{ "Key": "env", "Value": "prod" }] }
+                ] },
+                { "Instances": [
+                    { "InstanceId": "i-0a", "KeyName": "deploy", "PrivateIpAddress": "10.0.1.4",
+                      "PublicIpAddress": "3.5.7.9",
+                      "Tags": [{ "Key": "Name", "Value": "Web Server" }] }
+                ] }
+            ]
+        }"#;
+        let instances = parse_instances(json, "eu-west-1").unwrap();
+        assert_eq!(instances.len(), 2);
+        assert_eq!(instances[0].instance_id, "i-0b");
+        assert_eq!(instances[0].name, None);
+        assert_eq!(instances[1].name.as_deref(), Some("Web Server"));
+        assert_eq!(instances[1].key_name.as_deref(), Some("deploy"));
+
+        let web = candidate_for(&instances[1], false).unwrap();
+        assert_eq!(web.source, "aws:eu-west-1:i-0a");
+        assert_eq!(web.alias, "web-server");
+        assert_eq!(web.hostname, "3.5.7.9");
+        assert_eq!(
+            candidate_for(&instances[1], true).unwrap().hostname,
+            "10.0.1.4"
+        );
+        let unnamed = candidate_for(&instances[0], false).unwrap();
+        assert_eq!(
+            (unnamed.name.as_str(), unnamed.hostname.as_str()),
+            ("i-0b", "10.0.1.5")
+        );
+
+        assert!(validate_region(" us-east-1 ").is_ok());
+        assert!(validate_region("us-east-1 --debug").is_err());
+    }
+}
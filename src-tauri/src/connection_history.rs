@@ -0,0 +1,270 @@
+use crate::host_store::open_hosts_db;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::time::Duration;
+
+const DEFAULT_HISTORY_LIMIT: u32 = 50;
+// ssh reserves 255 for its own failures (unreachable, auth, dropped link);
+// any other exit status means the session was established.
+const SSH_FAILURE_EXIT_CODE: u32 = 255;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionRecord {
+    pub started_at: String,
+    pub ended_at: String,
+    pub duration_ms: u64,
+    pub exit_code: u32,
+    pub succeeded: bool,
+    /// The host's session time limit ended the session.
+    pub limit_enforced: bool,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HostConnectionStats {
+    pub host_id: String,
+    pub total_sessions: u64,
+    pub failed_sessions: u64,
+    pub total_duration_ms: u64,
+    pub last_connected_at: Option<String>,
+}
+
+fn ensure_connections_schema(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS connections (
+          id          INTEGER PRIMARY KEY AUTOINCREMENT,
+          host_id     TEXT NOT NULL,
+          started_at  TEXT NOT NULL,
+          ended_at    TEXT NOT NULL,
+          duration_ms INTEGER NOT NULL,
+          exit_code   INTEGER NOT NULL,
+          succeeded   INTEGER NOT NULL,
+          limit_enforced INTEGER NOT NULL DEFAULT 0
+        );
+        CREATE INDEX IF NOT EXISTS connections_host_started
+          ON connections (host_id, started_at);
+        "#,
+    )
+    .map_err(|e| e.to_string())?;
+    let _ = conn.execute(
+        "ALTER TABLE connections ADD COLUMN limit_enforced INTEGER NOT NULL DEFAULT 0",
+        [],
+    );
+    Ok(())
+}
+
+fn insert_connection(
+    conn: &Connection,
+    host_id: &str,
+    ended_at: chrono::DateTime<chrono::Utc>,
+    duration: Duration,
+    exit_code: u32,
+    limit_enforced: bool,
+) -> Result<(), String> {
+    ensure_connections_schema(conn)?;
+    let started_at = ended_at
+        - chrono::Duration::from_std(duration).unwrap_or_else(|_| chrono::Duration::zero());
+    let timestamp =
+        |dt: chrono::DateTime<chrono::Utc>| dt.to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+    conn.execute(
+        r#"
+        INSERT INTO connections (
+          host_id, started_at, ended_at, duration_ms, exit_code, succeeded, limit_enforced
+        )
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+        "#,
+        params![
+            host_id,
+            timestamp(started_at),
+            timestamp(ended_at),
+            duration.as_millis().min(i64::MAX as u128) as i64,
+            exit_code,
+            if exit_code != SSH_FAILURE_EXIT_CODE {
+                1
+            } else {
+                0
+            },
+            if limit_enforced { 1 } else { 0 }
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Called when a host-backed PTY session exits. Best effort: a history write
+/// failure must never affect the session teardown.
+pub(crate) fn record_connection(
+    host_id: &str,
+    duration: Duration,
+    exit_code: u32,
+    limit_enforced: bool,
+) {
+    let result = open_hosts_db().and_then(|conn| {
+        insert_connection(
+            &conn,
+            host_id,
+            chrono::Utc::now(),
+            duration,
+            exit_code,
+            limit_enforced,
+        )
+    });
+    if let Err(e) = result {
+        eprintln!("[history] failed to record connection for host {host_id}: {e}");
+    }
+}
+
+pub(crate) fn delete_connection_history(conn: &Connection, host_id: &str) -> Result<(), String> {
+    ensure_connections_schema(conn)?;
+    conn.execute(
+        "DELETE FROM connections WHERE host_id = ?1",
+        params![host_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub(crate) fn reassign_connection_history(
+    conn: &Connection,
+    from_host_id: &str,
+    to_host_id: &str,
+) -> Result<(), String> {
+    ensure_connections_schema(conn)?;
+    conn.execute(
+        "UPDATE connections SET host_id = ?2 WHERE host_id = ?1",
+        params![from_host_id, to_host_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn query_history(
+    conn: &Connection,
+    host_id: &str,
+    limit: u32,
+) -> Result<Vec<ConnectionRecord>, String> {
+    ensure_connections_schema(conn)?;
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT started_at, ended_at, duration_ms, exit_code, succeeded, limit_enforced
+            FROM connections
+            WHERE host_id = ?1
+            ORDER BY started_at DESC, id DESC
+            LIMIT ?2
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![host_id, limit], |row| {
+            let duration_ms: i64 = row.get(2)?;
+            let succeeded: i64 = row.get(4)?;
+            let limit_enforced: i64 = row.get(5)?;
+            Ok(ConnectionRecord {
+                started_at: row.get(0)?,
+                ended_at: row.get(1)?,
+                duration_ms: duration_ms.max(0) as u64,
+                exit_code: row.get(3)?,
+                succeeded: succeeded != 0,
+                limit_enforced: limit_enforced != 0,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.map(|r| r.map_err(|e| e.to_string())).collect()
+}
+
+pub(crate) fn query_stats(conn: &Connection) -> Result<Vec<HostConnectionStats>, String> {
+    ensure_connections_schema(conn)?;
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT
+              host_id,
+              COUNT(*),
+              SUM(CASE WHEN succeeded = 0 THEN 1 ELSE 0 END),
+              SUM(duration_ms),
+              MAX(CASE WHEN succeeded != 0 THEN started_at END)
+            FROM connections
+            GROUP BY host_id
+            ORDER BY host_id
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            let total: i64 = row.get(1)?;
+            let failed: i64 = row.get(2)?;
+            let duration_ms: i64 = row.get(3)?;
+            Ok(HostConnectionStats {
+                host_id: row.get(0)?,
+                total_sessions: total.max(0) as u64,
+                failed_sessions: failed.max(0) as u64,
+                total_duration_ms: duration_ms.max(0) as u64,
+                last_connected_at: row.get(4)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.map(|r| r.map_err(|e| e.to_string())).collect()
+}
+
+/// Most recent sessions of a host, newest first.
+#[tauri::command]
+pub fn host_connection_history(
+    host_id: String,
+    limit: Option<u32>,
+) -> Result<Vec<ConnectionRecord>, String> {
+    let conn = open_hosts_db()?;
+    query_history(
+        &conn,
+        host_id.trim(),
+        limit.unwrap_or(DEFAULT_HISTORY_LIMIT),
+    )
+}
+
+/// Per-host totals for the host list; hosts never connected are omitted.
+#[tauri::command]
+pub fn host_connection_stats() -> Result<Vec<HostConnectionStats>, String> {
+    let conn = open_hosts_db()?;
+    query_stats(&conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{insert_connection, query_history, query_stats, HostConnectionStats};
+    use chrono::TimeZone;
+    use rusqlite::Connection;
+    use std::time::Duration;
+
+    #[test]
+    fn records_history_and_aggregates_stats() {
+        let conn = Connection::open_in_memory().unwrap();
+        let at = |h: u32| chrono::Utc.with_ymd_and_hms(2026, 3, 1, h, 0, 0).unwrap();
+        insert_connection(&conn, "web", at(10), Duration::from_secs(60), 0, false).unwrap();
+        insert_connection(&conn, "web", at(12), Duration::from_secs(5), 255, false).unwrap();
+        insert_connection(&conn, "web", at(11), Duration::from_millis(1500), 130, true).unwrap();
+        insert_connection(&conn, "db", at(9), Duration::from_secs(1), 0, false).unwrap();
+
+        let history = query_history(&conn, "web", 2).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].started_at, "2026-03-01T11:59:55.000Z");
+        assert!(!history[0].succeeded);
+        assert_eq!(history[1].exit_code, 130);
+        assert!(history[1].succeeded);
+        assert!(history[1].limit_enforced);
+        assert!(!history[0].limit_enforced);
+
+        let stats = query_stats(&conn).unwrap();
+        assert_eq!(
+            stats[1],
+            HostConnectionStats {
+                host_id: "web".to_string(),
+                total_sessions: 3,
+                failed_sessions: 1,
+                total_duration_ms: 66_500,
+                last_connected_at: Some("2026-03-01T10:59:58.500Z".to_string()),
+            }
+        );
+    }
+}
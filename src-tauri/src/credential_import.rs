@@ -0,0 +1,240 @@
+use crate::credential_store::keychain_set_password;
+use crate::models::Host;
+use serde::Serialize;
+use std::fs;
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialCsvRowIssue {
+    pub line: usize,
+    pub alias: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialCsvImportReport {
+    pub imported: usize,
+    pub rejected: Vec<CredentialCsvRowIssue>,
+}
+
+struct CsvRecord {
+    line: usize,
+    fields: Vec<String>,
+}
+
+// Minimal RFC 4180 reader: quoted fields may contain commas, newlines and
+// doubled quotes. `line` is where each record starts, for error reporting.
+fn parse_csv(content: &str) -> Result<Vec<CsvRecord>, String> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut chars = content.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if in_quotes {
+            match ch {
+                '"' if chars.next_if_eq(&'"').is_some() => field.push('"'),
+                '"' => in_quotes = false,
+                _ => {
+                    if ch == '\n' {
+                        line += 1;
+                    }
+                    field.push(ch);
+                }
+            }
+            continue;
+        }
+        match ch {
+            '"' if field.is_empty() => in_quotes = true,
+            ',' => fields.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => {
+                fields.push(std::mem::take(&mut field));
+                records.push(CsvRecord {
+                    line: record_line,
+                    fields: std::mem::take(&mut fields),
+                });
+                line += 1;
+                record_line = line;
+            }
+            _ => field.push(ch),
+        }
+    }
+    if in_quotes {
+        return Err(format!(
+            "Unterminated quoted field starting on line {record_line}"
+        ));
+    }
+    if !field.is_empty() || !fields.is_empty() {
+        fields.push(field);
+        records.push(CsvRecord {
+            line: record_line,
+            fields,
+        });
+    }
+    records.retain(|record| record.fields.iter().any(|f| !f.trim().is_empty()));
+    Ok(records)
+}
+
+fn is_header(record: &CsvRecord) -> bool {
+    matches!(
+        record.fields.as_slice(),
+        [alias, password, ..]
+            if alias.trim().eq_ignore_ascii_case("alias")
+                && password.trim().eq_ignore_ascii_case("password")
+    )
+}
+
+fn host_matches_alias(host: &Host, alias: &str) -> bool {
+    let target = if host.alias.trim().is_empty() {
+        host.hostname.trim()
+    } else {
+        host.alias.trim()
+    };
+    target.eq_ignore_ascii_case(alias)
+}
+
+/// Resolve every row to a host id before anything is written, so a typo in
+/// the sheet never leaves the keychain half-imported.
+fn plan_csv_import(
+    records: &[CsvRecord],
+    hosts: &[Host],
+) -> (Vec<(String, String)>, Vec<CredentialCsvRowIssue>) {
+    let mut planned: Vec<(String, String)> = Vec::new();
+    let mut rejected = Vec::new();
+    let skip = usize::from(records.first().is_some_and(is_header));
+    for record in &records[skip..] {
+        let alias = record.fields.first().map(|f| f.trim()).unwrap_or("");
+        let mut reject = |reason: &str| {
+            rejected.push(CredentialCsvRowIssue {
+                line: record.line,
+                alias: alias.to_string(),
+                reason: reason.to_string(),
+            })
+        };
+        if record.fields.len() != 2 {
+            reject("expected two columns: alias,password");
+            continue;
+        }
+        let password = record.fields[1].trim();
+        if alias.is_empty() {
+            reject("alias is empty");
+            continue;
+        }
+        if password.is_empty() {
+            reject("password is empty");
+            continue;
+        }
+        let matches: Vec<&Host> = hosts
+            .iter()
+            .filter(|host| !host.deleted && host_matches_alias(host, alias))
+            .collect();
+        let host = match matches.as_slice() {
+            [host] => host,
+            [] => {
+                reject("no host with this alias");
+                continue;
+            }
+            _ => {
+                reject("alias matches more than one host");
+                continue;
+            }
+        };
+        if planned.iter().any(|(id, _)| id == &host.id) {
+            reject("duplicate row for this host");
+            continue;
+        }
+        planned.push((host.id.clone(), password.to_string()));
+    }
+    (planned, rejected)
+}
+
+/// One-off import of `alias,password` rows (e.g. exported from a team password
+/// sheet). Passwords go straight to the keychain; nothing is written to
+/// hosts.db. Unless `allow_unmatched` is set, any rejected row aborts the
+/// whole import.
+#[tauri::command]
+pub async fn credentials_import_csv(
+    path: String,
+    allow_unmatched: Option<bool>,
+) -> Result<CredentialCsvImportReport, String> {
+    crate::app_lock::ensure_unlocked()?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let content = fs::read_to_string(path.trim()).map_err(|e| e.to_string())?;
+        let records = parse_csv(&content)?;
+        let hosts = crate::host_store::hosts_load()?;
+        let (planned, rejected) = plan_csv_import(&records, &hosts);
+        if !rejected.is_empty() && !allow_unmatched.unwrap_or(false) {
+            return Ok(CredentialCsvImportReport {
+                imported: 0,
+                rejected,
+            });
+        }
+        let mut imported = 0;
+        for (host_id, password) in planned {
+            keychain_set_password(&host_id, &password, "import")
+                .map_err(|e| format!("Failed to save password to Keychain: {e}"))?;
+            imported += 1;
+        }
+        Ok(CredentialCsvImportReport { imported, rejected })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_csv, plan_csv_import};
+    use crate::mock_backends::test_host;
+    use crate::models::Host;
+
+    fn host(id: &str, alias: &str, hostname: &str) -> Host {
+        Host {
+            alias: alias.to_string(),
+            hostname: hostname.to_string(),
+            ..test_host(id)
+        }
+    }
+
+    #[test]
+    fn parses_quoted_csv_fields() {
+        let records =
+            parse_csv("\u{feff}alias,password\r\nweb,\"pa,ss\"\"word\"\n\ndb,\"multi\nline\"\n")
+                .unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[1].fields, vec!["web", "pa,ss\"word"]);
+        assert_eq!(records[2].line, 4);
+        assert_eq!(records[2].fields, vec!["db", "multi\nline"]);
+        assert!(parse_csv("web,\"open").is_err());
+    }
+
+    #[test]
+    fn plans_import_and_reports_unmatched_rows() {
+        let hosts = vec![
+            host("1", "web", "10.0.0.1"),
+            host("2", "", "db.internal"),
+            host("3", "dup", "a"),
+            host("4", "dup", "b"),
+        ];
+        let records =
+            parse_csv("alias,password\nWEB,one\ndb.internal,two\nmissing,x\ndup,y\nweb,again\n")
+                .unwrap();
+        let (planned, rejected) = plan_csv_import(&records, &hosts);
+        assert_eq!(
+            planned,
+            vec![
+                ("1".to_string(), "one".to_string()),
+                ("2".to_string(), "two".to_string())
+            ]
+        );
+        let lines: Vec<(usize, &str)> = rejected
+            .iter()
+            .map(|issue| (issue.line, issue.alias.as_str()))
+            .collect();
+        assert_eq!(lines, vec![(4, "missing"), (5, "dup"), (6, "web")]);
+    }
+}
@@ -1,89 +1,627 @@
+use crate::audit_log::{
+    self, ACTION_PASSWORD_DELETE, ACTION_PASSWORD_READ, ACTION_PASSWORD_SET, ACTION_TOTP_DELETE,
+    ACTION_TOTP_READ, ACTION_TOTP_SET,
+};
 use keyring::{Entry, Error as KeyringError};
+use serde::Serialize;
 use std::collections::HashMap;
-use std::sync::{Mutex, OnceLock};
+use std::sync::{Mutex, Once, OnceLock};
+use tauri::{AppHandle, Emitter, Runtime};
 
+const MAIN_WINDOW_LABEL: &str = "main";
+
+// Service names used by the default profile of release builds (and by every
+// build before namespacing existed).
 const HOST_PASSWORD_SERVICE: &str = "xTermius";
+// WebDAV credentials live under their own service so they can never collide
+// with a host whose id happens to equal the account name.
+const WEBDAV_PASSWORD_SERVICE: &str = "xTermius-webdav";
 const WEBDAV_PASSWORD_ACCOUNT: &str = "webdav-sync";
+const HOSTS_DB_KEY_SERVICE: &str = "xTermius-db";
+const HOSTS_DB_KEY_ACCOUNT: &str = "hosts-db";
+// SMTP passwords of alert channels, keyed by channel id.
+const ALERT_PASSWORD_SERVICE: &str = "xTermius-alerts";
+// TOTP secrets of hosts, keyed by host id.
+const TOTP_SECRET_SERVICE: &str = "xTermius-totp";
+
+// Windows Credential Manager caps a secret at 2560 bytes of UTF-16 and some
+// Secret Service setups cut long values silently. A secret the backend
+// rejects or returns altered is split across `<account>#shard-<n>` entries,
+// with a manifest naming the shard count stored under the account itself.
+const SHARD_MANIFEST_PREFIX: &str = "xtermius-shards:v1:";
+const SHARD_CHARS: usize = 512;
+const MAX_SHARDS: usize = 64;
+const HEALTH_CHECK_ACCOUNT: &str = "xtermius-health-check";
+const KEYCHAIN_LOCKED_MESSAGE: &str = "Keychain is locked; unlock it to use saved passwords";
+
+/// Whether the keychain answers. `Locked` (a locked login keychain on macOS,
+/// the only keychain backend built in) is told apart from other failures so
+/// the UI can ask the user to unlock instead of showing every password as
+/// missing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CredentialBackendState {
+    Available,
+    Locked,
+    Unavailable,
+}
+
+/// Sent with every `credential:backend-status` event.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialBackendStatus {
+    pub state: CredentialBackendState,
+    pub detail: Option<String>,
+    /// Saves and deletes made while locked, applied once it unlocks.
+    pub pending_writes: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeychainMigrationReport {
+    pub namespace: String,
+    pub migrated: usize,
+    pub skipped: usize,
+}
+
+struct KeychainServices {
+    host: String,
+    webdav: String,
+    hosts_db: String,
+    alerts: String,
+    totp: String,
+}
+
+fn keychain_services_for(profile: Option<&str>, debug_build: bool) -> KeychainServices {
+    // Debug builds get their own namespace so a dev build can never overwrite
+    // the secrets of an installed release.
+    let namespace = match (profile, debug_build) {
+        (Some(profile), _) => Some(profile),
+        (None, true) => Some("dev"),
+        (None, false) => None,
+    };
+    match namespace {
+        Some(namespace) => KeychainServices {
+            host: format!("{HOST_PASSWORD_SERVICE}.{namespace}"),
+            webdav: format!("{WEBDAV_PASSWORD_SERVICE}.{namespace}"),
+            hosts_db: format!("{HOSTS_DB_KEY_SERVICE}.{namespace}"),
+            alerts: format!("{ALERT_PASSWORD_SERVICE}.{namespace}"),
+            totp: format!("{TOTP_SECRET_SERVICE}.{namespace}"),
+        },
+        None => KeychainServices {
+            host: HOST_PASSWORD_SERVICE.to_string(),
+            webdav: WEBDAV_PASSWORD_SERVICE.to_string(),
+            hosts_db: HOSTS_DB_KEY_SERVICE.to_string(),
+            alerts: ALERT_PASSWORD_SERVICE.to_string(),
+            totp: TOTP_SECRET_SERVICE.to_string(),
+        },
+    }
+}
+
+fn keychain_services() -> &'static KeychainServices {
+    static SERVICES: OnceLock<KeychainServices> = OnceLock::new();
+    SERVICES.get_or_init(|| {
+        keychain_services_for(crate::host_store::app_profile(), cfg!(debug_assertions))
+    })
+}
+
+fn host_password_service() -> &'static str {
+    keychain_services().host.as_str()
+}
+
+fn webdav_password_service() -> &'static str {
+    keychain_services().webdav.as_str()
+}
+
+fn hosts_db_key_service() -> &'static str {
+    keychain_services().hosts_db.as_str()
+}
+
+fn alert_password_service() -> &'static str {
+    keychain_services().alerts.as_str()
+}
+
+fn totp_secret_service() -> &'static str {
+    keychain_services().totp.as_str()
+}
 
 // Keychain lookups go through securityd (one IPC round-trip each) and
 // hosts_load queries every host, so cache the has-password flag in memory.
 // All keychain writes in this process go through this module, which keeps the
 // cache coherent; external keychain edits are picked up on restart.
-fn has_password_cache() -> &'static Mutex<HashMap<String, bool>> {
-    static CACHE: OnceLock<Mutex<HashMap<String, bool>>> = OnceLock::new();
+fn has_password_cache() -> &'static Mutex<HashMap<(&'static str, String), bool>> {
+    static CACHE: OnceLock<Mutex<HashMap<(&'static str, String), bool>>> = OnceLock::new();
     CACHE.get_or_init(|| Mutex::new(HashMap::new()))
 }
 
-fn cache_store(host_id: &str, has_password: bool) {
+fn cache_store(service: &'static str, account: &str, has_password: bool) {
     if let Ok(mut cache) = has_password_cache().lock() {
-        cache.insert(host_id.to_string(), has_password);
+        cache.insert((service, account.to_string()), has_password);
     }
 }
 
-fn keychain_entry(host_id: &str) -> Result<Entry, String> {
-    Entry::new(HOST_PASSWORD_SERVICE, host_id).map_err(|e| e.to_string())
+// A save (Some) or delete (None) made while the keychain was locked. Held in
+// memory only, so secrets never hit the disk in the clear; quitting before
+// the keychain unlocks loses them.
+struct PendingWrite {
+    service: &'static str,
+    account: String,
+    secret: Option<String>,
 }
 
-pub(crate) fn keychain_set_password(host_id: &str, password: &str) -> Result<(), String> {
-    let entry = keychain_entry(host_id)?;
-    // Some keychain backends do not reliably replace existing entries in-place.
-    // Best-effort delete first makes password updates deterministic.
+#[derive(Default)]
+struct Backend {
+    // Why the keychain is taken to be locked; None while it answers.
+    locked: Option<String>,
+    pending: Vec<PendingWrite>,
+}
+
+impl Backend {
+    fn status(&self) -> CredentialBackendStatus {
+        CredentialBackendStatus {
+            state: if self.locked.is_some() {
+                CredentialBackendState::Locked
+            } else {
+                CredentialBackendState::Available
+            },
+            detail: self.locked.clone(),
+            pending_writes: self.pending.len(),
+        }
+    }
+}
+
+type BackendEmitFn = Box<dyn Fn(&CredentialBackendStatus) + Send + Sync>;
+
+fn backend() -> &'static Mutex<Backend> {
+    static BACKEND: OnceLock<Mutex<Backend>> = OnceLock::new();
+    BACKEND.get_or_init(|| Mutex::new(Backend::default()))
+}
+
+fn backend_emitter() -> &'static OnceLock<BackendEmitFn> {
+    static EMITTER: OnceLock<BackendEmitFn> = OnceLock::new();
+    &EMITTER
+}
+
+/// Announce keychain lock changes to the main window as
+/// `credential:backend-status`: when it locks, so the user can be asked to
+/// unlock it, and when it answers again.
+pub(crate) fn init<R: Runtime>(app: AppHandle<R>) {
+    let _ = backend_emitter().set(Box::new(move |status| {
+        let _ = app.emit_to(MAIN_WINDOW_LABEL, "credential:backend-status", status);
+    }));
+}
+
+fn emit_backend_status(status: CredentialBackendStatus) {
+    if let Some(emit) = backend_emitter().get() {
+        emit(&status);
+    }
+}
+
+/// Errors of a login keychain that is there but locked (macOS): it could
+/// not ask to be unlocked (`errSecInteractionNotAllowed`) or the user
+/// dismissed the prompt (`errSecUserCanceled`). A missing or damaged
+/// keychain (`NoStorageAccess`) is a failure of its own.
+fn is_locked_error(error: &KeyringError) -> bool {
+    if !matches!(error, KeyringError::PlatformFailure(_)) {
+        return false;
+    }
+    let message = error.to_string().to_ascii_lowercase();
+    message.contains("interaction is not allowed") || message.contains("user canceled")
+}
+
+/// Lookup of an account that never exists: succeeds as long as the keychain
+/// backend itself answers.
+fn check_backend() -> Result<(), KeyringError> {
+    match Entry::new(host_password_service(), HEALTH_CHECK_ACCOUNT)?.get_password() {
+        Ok(_) | Err(KeyringError::NoEntry) => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+fn mark_locked(detail: String) {
+    let status = {
+        let Ok(mut backend) = backend().lock() else {
+            return;
+        };
+        if backend.locked.is_some() {
+            return;
+        }
+        eprintln!("[keychain] locked: {detail}");
+        backend.locked = Some(detail);
+        backend.status()
+    };
+    emit_backend_status(status);
+}
+
+/// After a keychain call failed: whether that was because it is locked.
+/// Once known to be locked, further failures are put down to that without
+/// asking the backend again.
+fn failed_because_locked() -> bool {
+    if backend()
+        .lock()
+        .is_ok_and(|backend| backend.locked.is_some())
+    {
+        return true;
+    }
+    match check_backend() {
+        Err(e) if is_locked_error(&e) => {
+            mark_locked(e.to_string());
+            true
+        }
+        _ => false,
+    }
+}
+
+/// After a keychain call succeeded: if it was locked, it is not any more, so
+/// apply the writes queued meanwhile.
+fn backend_answered() {
+    let pending = {
+        let Ok(mut backend) = backend().lock() else {
+            return;
+        };
+        if backend.locked.take().is_none() {
+            return;
+        }
+        std::mem::take(&mut backend.pending)
+    };
+    let mut failed = Vec::new();
+    for write in pending {
+        let result = match write.secret.as_deref() {
+            Some(secret) => store_now(write.service, &write.account, secret),
+            None => delete_now(write.service, &write.account),
+        };
+        if let Err(e) = result {
+            eprintln!("[keychain] queued write of {} failed: {e}", write.account);
+            failed.push(write);
+        }
+    }
+    let retry = !failed.is_empty();
+    let status = {
+        let Ok(mut backend) = backend().lock() else {
+            return;
+        };
+        // Writes queued while these were applied are newer.
+        failed.retain(|write| {
+            !backend
+                .pending
+                .iter()
+                .any(|queued| queued.service == write.service && queued.account == write.account)
+        });
+        backend.pending.splice(0..0, failed);
+        backend.status()
+    };
+    emit_backend_status(status);
+    if retry {
+        // A queued write failing again most likely means it locked again.
+        failed_because_locked();
+    }
+}
+
+fn queue_write(service: &'static str, account: &str, secret: Option<&str>) {
+    if let Ok(mut backend) = backend().lock() {
+        backend
+            .pending
+            .retain(|write| write.service != service || write.account != account);
+        backend.pending.push(PendingWrite {
+            service,
+            account: account.to_string(),
+            secret: secret.map(str::to_string),
+        });
+    }
+    cache_store(
+        service,
+        account,
+        secret.is_some_and(|secret| !secret.trim().is_empty()),
+    );
+}
+
+// The value a queued write will give an entry: Some(None) for a delete.
+fn pending_secret(service: &str, account: &str) -> Option<Option<String>> {
+    let backend = backend().lock().ok()?;
+    backend
+        .pending
+        .iter()
+        .rev()
+        .find(|write| write.service == service && write.account == account)
+        .map(|write| write.secret.clone())
+}
+
+fn keychain_entry(service: &str, account: &str) -> Result<Entry, String> {
+    Entry::new(service, account).map_err(|e| e.to_string())
+}
+
+fn delete_entry(entry: &Entry) -> Result<(), String> {
     match entry.delete_credential() {
-        Ok(()) | Err(KeyringError::NoEntry) => {}
+        Ok(()) | Err(KeyringError::NoEntry) => Ok(()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn shard_account(account: &str, index: usize) -> String {
+    format!("{account}#shard-{index}")
+}
+
+fn shard_count(stored: &str) -> Option<usize> {
+    stored
+        .strip_prefix(SHARD_MANIFEST_PREFIX)?
+        .parse()
+        .ok()
+        .filter(|count| (1..=MAX_SHARDS).contains(count))
+}
+
+/// Store `secret` in a single entry; `Ok(false)` when the backend rejected
+/// it as too long or did not keep it intact.
+fn store_whole(entry: &Entry, secret: &str) -> Result<bool, String> {
+    match entry.set_password(secret) {
+        Ok(()) => {}
+        Err(KeyringError::TooLong(..)) => return Ok(false),
         Err(e) => return Err(e.to_string()),
     }
-    entry.set_password(password).map_err(|e| e.to_string())?;
-    cache_store(host_id, !password.trim().is_empty());
-    Ok(())
+    // Short secrets fit every backend; only long ones are read back.
+    if secret.chars().count() <= SHARD_CHARS || entry.get_password().ok().as_deref() == Some(secret)
+    {
+        return Ok(true);
+    }
+    delete_entry(entry)?;
+    Ok(false)
 }
 
-pub(crate) fn keychain_has_password(host_id: &str) -> bool {
-    if let Ok(cache) = has_password_cache().lock() {
-        if let Some(&cached) = cache.get(host_id) {
-            return cached;
-        }
+fn store_sharded(entry: &Entry, service: &str, account: &str, secret: &str) -> Result<(), String> {
+    let chars: Vec<char> = secret.chars().collect();
+    let shards: Vec<String> = chars
+        .chunks(SHARD_CHARS)
+        .map(|chunk| chunk.iter().collect())
+        .collect();
+    if shards.len() > MAX_SHARDS {
+        return Err("Secret is too long for the keychain".to_string());
+    }
+    for (index, shard) in shards.iter().enumerate() {
+        let shard_entry = keychain_entry(service, &shard_account(account, index))?;
+        delete_entry(&shard_entry)?;
+        shard_entry.set_password(shard).map_err(|e| e.to_string())?;
     }
-    let entry = match keychain_entry(host_id) {
-        Ok(e) => e,
-        Err(_) => return false,
+    // Written last, so a failed write never points at missing shards.
+    entry
+        .set_password(&format!("{SHARD_MANIFEST_PREFIX}{}", shards.len()))
+        .map_err(|e| e.to_string())
+}
+
+fn read_shards(service: &str, account: &str, stored: String) -> Result<String, String> {
+    let Some(count) = shard_count(&stored) else {
+        return Ok(stored);
     };
-    let has_password = match entry.get_password() {
-        Ok(pw) => !pw.trim().is_empty(),
-        Err(KeyringError::NoEntry) => false,
-        Err(_) => return false,
+    let mut secret = String::new();
+    for index in 0..count {
+        match keychain_entry(service, &shard_account(account, index))?.get_password() {
+            Ok(shard) => secret.push_str(&shard),
+            Err(KeyringError::NoEntry) => {
+                return Err(format!(
+                    "Keychain entry {account} is missing part {} of {count}",
+                    index + 1
+                ))
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+    Ok(secret)
+}
+
+fn delete_shards(service: &str, account: &str) -> Result<(), String> {
+    let count = match keychain_entry(service, account)?.get_password() {
+        Ok(stored) => shard_count(&stored).unwrap_or(0),
+        Err(_) => 0,
     };
-    cache_store(host_id, has_password);
-    has_password
+    for index in 0..count {
+        delete_entry(&keychain_entry(service, &shard_account(account, index))?)?;
+    }
+    Ok(())
 }
 
-pub(crate) fn keychain_delete_password(host_id: &str) -> Result<(), String> {
-    match keychain_entry(host_id)?.delete_credential() {
+fn store_now(service: &'static str, account: &str, password: &str) -> Result<(), String> {
+    let entry = keychain_entry(service, account)?;
+    delete_shards(service, account)?;
+    // Some keychain backends do not reliably replace existing entries in-place.
+    // Best-effort delete first makes password updates deterministic.
+    delete_entry(&entry)?;
+    if !store_whole(&entry, password)? {
+        store_sharded(&entry, service, account, password)?;
+    }
+    cache_store(service, account, !password.trim().is_empty());
+    Ok(())
+}
+
+fn delete_now(service: &'static str, account: &str) -> Result<(), String> {
+    delete_shards(service, account)?;
+    match keychain_entry(service, account)?.delete_credential() {
         Ok(()) | Err(KeyringError::NoEntry) => {
-            cache_store(host_id, false);
+            cache_store(service, account, false);
             Ok(())
         }
         Err(e) => Err(e.to_string()),
     }
 }
 
-pub(crate) fn keychain_get_password(host_id: &str) -> Result<Option<String>, String> {
-    if host_id.trim().is_empty() {
-        return Ok(None);
+/// Save a secret; while the keychain is locked the save is queued and
+/// reported as done.
+fn entry_set_password(service: &'static str, account: &str, password: &str) -> Result<(), String> {
+    match store_now(service, account, password) {
+        Ok(()) => {
+            backend_answered();
+            Ok(())
+        }
+        Err(_) if failed_because_locked() => {
+            queue_write(service, account, Some(password));
+            Ok(())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+fn entry_delete_password(service: &'static str, account: &str) -> Result<(), String> {
+    match delete_now(service, account) {
+        Ok(()) => {
+            backend_answered();
+            Ok(())
+        }
+        Err(_) if failed_because_locked() => {
+            queue_write(service, account, None);
+            Ok(())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Whether an entry holds a secret; None when the keychain cannot tell
+/// (locked or failing), which callers must not take for "no".
+fn entry_has_password(service: &'static str, account: &str) -> Option<bool> {
+    if let Ok(cache) = has_password_cache().lock() {
+        if let Some(&cached) = cache.get(&(service, account.to_string())) {
+            return Some(cached);
+        }
     }
-    let entry = keychain_entry(host_id.trim())?;
+    let has_password = match keychain_entry(service, account).ok()?.get_password() {
+        Ok(pw) => !pw.trim().is_empty(),
+        Err(KeyringError::NoEntry) => false,
+        Err(_) => {
+            failed_because_locked();
+            return None;
+        }
+    };
+    backend_answered();
+    cache_store(service, account, has_password);
+    Some(has_password)
+}
+
+fn entry_get_password(service: &'static str, account: &str) -> Result<Option<String>, String> {
+    if let Some(secret) = pending_secret(service, account) {
+        return Ok(secret);
+    }
+    let result = read_now(service, account);
+    match &result {
+        Ok(_) => backend_answered(),
+        Err(_) if failed_because_locked() => return Err(KEYCHAIN_LOCKED_MESSAGE.to_string()),
+        Err(_) => {}
+    }
+    result
+}
+
+fn read_now(service: &'static str, account: &str) -> Result<Option<String>, String> {
+    let entry = keychain_entry(service, account)?;
     match entry.get_password() {
         Ok(pw) => {
-            cache_store(host_id.trim(), !pw.trim().is_empty());
+            let pw = read_shards(service, account, pw)?;
+            cache_store(service, account, !pw.trim().is_empty());
             Ok(Some(pw))
         }
         Err(KeyringError::NoEntry) => {
-            cache_store(host_id.trim(), false);
+            cache_store(service, account, false);
             Ok(None)
         }
         Err(e) => Err(e.to_string()),
     }
 }
 
+/// Succeeds as long as the keychain backend answers (unlocked, service
+/// reachable).
+pub(crate) fn keychain_health_check() -> Result<(), String> {
+    match check_backend() {
+        Ok(()) => {
+            backend_answered();
+            Ok(())
+        }
+        Err(e) if is_locked_error(&e) => {
+            mark_locked(e.to_string());
+            Err(KEYCHAIN_LOCKED_MESSAGE.to_string())
+        }
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Whether the keychain was last found locked.
+pub(crate) fn keychain_locked() -> bool {
+    backend()
+        .lock()
+        .is_ok_and(|backend| backend.locked.is_some())
+}
+
+/// Ask the keychain whether it answers now, applying queued writes if it
+/// was unlocked meanwhile. Call after the user was asked to unlock it.
+#[tauri::command]
+pub async fn credential_backend_status() -> Result<CredentialBackendStatus, String> {
+    tauri::async_runtime::spawn_blocking(|| {
+        if let Err(e) = keychain_health_check() {
+            let mut status = backend()
+                .lock()
+                .map_err(|_| "keychain state poisoned".to_string())?
+                .status();
+            if status.state == CredentialBackendState::Available {
+                status.state = CredentialBackendState::Unavailable;
+                status.detail = Some(e);
+            }
+            return Ok(status);
+        }
+        Ok(backend()
+            .lock()
+            .map_err(|_| "keychain state poisoned".to_string())?
+            .status())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// `caller` names what asked, for the audit log (see `audit_log`).
+pub(crate) fn keychain_set_password(
+    host_id: &str,
+    password: &str,
+    caller: &str,
+) -> Result<(), String> {
+    let result = entry_set_password(host_password_service(), host_id, password);
+    audit_log::record(ACTION_PASSWORD_SET, Some(host_id), caller, &result);
+    result
+}
+
+/// None while the keychain cannot tell, e.g. when it is locked.
+pub(crate) fn keychain_has_password(host_id: &str) -> Option<bool> {
+    entry_has_password(host_password_service(), host_id)
+}
+
+pub(crate) fn keychain_delete_password(host_id: &str, caller: &str) -> Result<(), String> {
+    let result = entry_delete_password(host_password_service(), host_id);
+    audit_log::record(ACTION_PASSWORD_DELETE, Some(host_id), caller, &result);
+    result
+}
+
+/// For the app's own use of a password (probes, terminal login, backup,
+/// merge). Reads the user asks to see go through `keychain_reveal_password`.
+pub(crate) fn keychain_get_password(host_id: &str, caller: &str) -> Result<Option<String>, String> {
+    if host_id.trim().is_empty() {
+        return Ok(None);
+    }
+    let result = crate::app_lock::ensure_unlocked()
+        .and_then(|()| entry_get_password(host_password_service(), host_id.trim()));
+    audit_log::record(ACTION_PASSWORD_READ, Some(host_id), caller, &result);
+    result
+}
+
+/// A password handed to the user, e.g. typed into a session on request:
+/// confirms the device owner first when settings ask for it. A cancelled
+/// prompt is an error, not a missing password.
+pub(crate) fn keychain_reveal_password(
+    host_id: &str,
+    caller: &str,
+) -> Result<Option<String>, String> {
+    if let Err(error) = crate::biometric::confirm_password_access() {
+        audit_log::record::<()>(
+            ACTION_PASSWORD_READ,
+            Some(host_id),
+            caller,
+            &Err(error.clone()),
+        );
+        return Err(error);
+    }
+    keychain_get_password(host_id, caller)
+}
+
 #[tauri::command]
 pub fn host_password_set(host_id: String, password: String) -> Result<(), String> {
     let id = host_id.trim();
@@ -92,9 +630,10 @@ pub fn host_password_set(host_id: String, password: String) -> Result<(), String
     }
     let pw = password.trim();
     if pw.is_empty() {
-        return keychain_delete_password(id);
+        return keychain_delete_password(id, "ui");
     }
-    keychain_set_password(id, pw).map_err(|e| format!("Failed to save password to Keychain: {e}"))
+    keychain_set_password(id, pw, "ui")
+        .map_err(|e| format!("Failed to save password to Keychain: {e}"))
 }
 
 #[tauri::command]
@@ -103,15 +642,44 @@ pub fn host_password_delete(host_id: String) -> Result<(), String> {
     if id.is_empty() {
         return Err("host_id is required".to_string());
     }
-    keychain_delete_password(id)
+    keychain_delete_password(id, "ui")
+}
+
+/// Move a WebDAV password saved by older builds (stored under the host
+/// service) into the dedicated WebDAV entry. Runs at most once per process.
+pub(crate) fn webdav_password_migrate_legacy() {
+    static LEGACY_MIGRATION: Once = Once::new();
+    LEGACY_MIGRATION.call_once(|| {
+        // The legacy entry belongs to the default namespace; other profiles
+        // pick it up through keychain_migrate_namespace instead of moving it.
+        if host_password_service() != HOST_PASSWORD_SERVICE {
+            return;
+        }
+        let legacy = match entry_get_password(HOST_PASSWORD_SERVICE, WEBDAV_PASSWORD_ACCOUNT) {
+            Ok(Some(pw)) => pw,
+            Ok(None) => return,
+            Err(e) => {
+                eprintln!("[keychain] legacy WebDAV password lookup failed: {e}");
+                return;
+            }
+        };
+        let legacy = legacy.trim();
+        if !legacy.is_empty() && !webdav_password_has() {
+            if let Err(e) = webdav_password_set(legacy) {
+                eprintln!("[keychain] legacy WebDAV password migration failed: {e}");
+                return;
+            }
+        }
+        let _ = entry_delete_password(HOST_PASSWORD_SERVICE, WEBDAV_PASSWORD_ACCOUNT);
+    });
 }
 
 pub(crate) fn webdav_password_get() -> Result<Option<String>, String> {
-    keychain_get_password(WEBDAV_PASSWORD_ACCOUNT)
+    entry_get_password(webdav_password_service(), WEBDAV_PASSWORD_ACCOUNT)
 }
 
 pub(crate) fn webdav_password_has() -> bool {
-    keychain_has_password(WEBDAV_PASSWORD_ACCOUNT)
+    entry_has_password(webdav_password_service(), WEBDAV_PASSWORD_ACCOUNT).unwrap_or(false)
 }
 
 pub(crate) fn webdav_password_set(password: &str) -> Result<(), String> {
@@ -119,10 +687,208 @@ pub(crate) fn webdav_password_set(password: &str) -> Result<(), String> {
     if pw.is_empty() {
         return webdav_password_delete();
     }
-    keychain_set_password(WEBDAV_PASSWORD_ACCOUNT, pw)
+    entry_set_password(webdav_password_service(), WEBDAV_PASSWORD_ACCOUNT, pw)
         .map_err(|e| format!("Failed to save WebDAV password to Keychain: {e}"))
 }
 
 pub(crate) fn webdav_password_delete() -> Result<(), String> {
-    keychain_delete_password(WEBDAV_PASSWORD_ACCOUNT)
+    entry_delete_password(webdav_password_service(), WEBDAV_PASSWORD_ACCOUNT)
+}
+
+pub(crate) fn alert_password_get(channel_id: &str) -> Result<Option<String>, String> {
+    entry_get_password(alert_password_service(), channel_id)
+}
+
+pub(crate) fn alert_password_has(channel_id: &str) -> bool {
+    entry_has_password(alert_password_service(), channel_id).unwrap_or(false)
+}
+
+pub(crate) fn alert_password_set(channel_id: &str, password: &str) -> Result<(), String> {
+    let pw = password.trim();
+    if pw.is_empty() {
+        return alert_password_delete(channel_id);
+    }
+    entry_set_password(alert_password_service(), channel_id, pw)
+        .map_err(|e| format!("Failed to save SMTP password to Keychain: {e}"))
+}
+
+pub(crate) fn alert_password_delete(channel_id: &str) -> Result<(), String> {
+    entry_delete_password(alert_password_service(), channel_id)
+}
+
+/// A host's TOTP secret, behind the same lock as its password. Like
+/// passwords, only reads that produce a code for the user confirm the device
+/// owner (see `totp_secret_reveal`).
+pub(crate) fn totp_secret_get(host_id: &str, caller: &str) -> Result<Option<String>, String> {
+    let result = crate::app_lock::ensure_unlocked()
+        .and_then(|()| entry_get_password(totp_secret_service(), host_id.trim()));
+    audit_log::record(ACTION_TOTP_READ, Some(host_id), caller, &result);
+    result
+}
+
+pub(crate) fn totp_secret_reveal(host_id: &str, caller: &str) -> Result<Option<String>, String> {
+    if let Err(error) = crate::biometric::confirm_password_access() {
+        audit_log::record::<()>(ACTION_TOTP_READ, Some(host_id), caller, &Err(error.clone()));
+        return Err(error);
+    }
+    totp_secret_get(host_id, caller)
+}
+
+pub(crate) fn totp_secret_has(host_id: &str) -> bool {
+    entry_has_password(totp_secret_service(), host_id.trim()).unwrap_or(false)
+}
+
+pub(crate) fn totp_secret_set(host_id: &str, secret: &str, caller: &str) -> Result<(), String> {
+    let result = entry_set_password(totp_secret_service(), host_id.trim(), secret)
+        .map_err(|e| format!("Failed to save TOTP secret to Keychain: {e}"));
+    audit_log::record(ACTION_TOTP_SET, Some(host_id), caller, &result);
+    result
+}
+
+pub(crate) fn totp_secret_delete(host_id: &str, caller: &str) -> Result<(), String> {
+    let result = entry_delete_password(totp_secret_service(), host_id.trim());
+    audit_log::record(ACTION_TOTP_DELETE, Some(host_id), caller, &result);
+    result
+}
+
+pub(crate) fn hosts_db_key_get() -> Result<Option<String>, String> {
+    entry_get_password(hosts_db_key_service(), HOSTS_DB_KEY_ACCOUNT)
+}
+
+pub(crate) fn hosts_db_key_set(key: &str) -> Result<(), String> {
+    // Never queued while locked: a key held only in memory would be lost on
+    // quit, and the data encrypted under it with it.
+    store_now(hosts_db_key_service(), HOSTS_DB_KEY_ACCOUNT, key)
+        .map_err(|e| format!("Failed to save hosts.db key to Keychain: {e}"))
+}
+
+// Copies only fill gaps: an entry that already exists in the current namespace
+// is never overwritten, and legacy entries stay in place for builds that
+// still read them.
+fn copy_legacy_entry(
+    legacy_service: &'static str,
+    service: &'static str,
+    account: &str,
+    report: &mut KeychainMigrationReport,
+) -> Result<(), String> {
+    if entry_has_password(service, account) == Some(true) {
+        report.skipped += 1;
+        return Ok(());
+    }
+    match entry_get_password(legacy_service, account)? {
+        Some(pw) if !pw.trim().is_empty() => {
+            entry_set_password(service, account, pw.trim())?;
+            report.migrated += 1;
+        }
+        _ => report.skipped += 1,
+    }
+    Ok(())
+}
+
+/// Copy secrets from the un-namespaced legacy services into the keychain
+/// namespace of the running profile/build.
+#[tauri::command]
+pub async fn keychain_migrate_namespace() -> Result<KeychainMigrationReport, String> {
+    crate::app_lock::ensure_unlocked()?;
+    tauri::async_runtime::spawn_blocking(|| {
+        let mut report = KeychainMigrationReport {
+            namespace: host_password_service().to_string(),
+            migrated: 0,
+            skipped: 0,
+        };
+        if host_password_service() == HOST_PASSWORD_SERVICE {
+            return Ok(report);
+        }
+        for host in crate::host_store::hosts_load()? {
+            copy_legacy_entry(
+                HOST_PASSWORD_SERVICE,
+                host_password_service(),
+                &host.id,
+                &mut report,
+            )?;
+        }
+        copy_legacy_entry(
+            WEBDAV_PASSWORD_SERVICE,
+            webdav_password_service(),
+            WEBDAV_PASSWORD_ACCOUNT,
+            &mut report,
+        )?;
+        Ok(report)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        entry_delete_password, entry_get_password, entry_set_password, is_locked_error,
+        keychain_services_for, SHARD_MANIFEST_PREFIX,
+    };
+    use crate::mock_backends::{install_mock_keychain, mock_keychain_value};
+    use keyring::Error as KeyringError;
+
+    #[test]
+    fn keychain_services_are_namespaced_per_profile_and_build() {
+        let release = keychain_services_for(None, false);
+        assert_eq!(release.host, "xTermius");
+        assert_eq!(release.webdav, "xTermius-webdav");
+        assert_eq!(release.hosts_db, "xTermius-db");
+
+        let dev = keychain_services_for(None, true);
+        assert_eq!(dev.host, "xTermius.dev");
+        assert_eq!(dev.webdav, "xTermius-webdav.dev");
+
+        let work = keychain_services_for(Some("work"), true);
+        assert_eq!(work.host, "xTermius.work");
+        assert_eq!(work.webdav, "xTermius-webdav.work");
+        assert_eq!(work.hosts_db, "xTermius-db.work");
+        assert_eq!(work.alerts, "xTermius-alerts.work");
+        assert_eq!(work.totp, "xTermius-totp.work");
+    }
+
+    #[test]
+    fn shards_secrets_the_backend_rejects_or_truncates() {
+        install_mock_keychain();
+        let long: String = (0..3000)
+            .map(|i| char::from(b'a' + (i % 26) as u8))
+            .collect();
+        for service in ["xTermius-test-limited", "xTermius-test-truncating"] {
+            entry_set_password(service, "h1", &long).unwrap();
+            assert!(mock_keychain_value(service, "h1")
+                .unwrap()
+                .starts_with(SHARD_MANIFEST_PREFIX));
+            assert_eq!(
+                entry_get_password(service, "h1").unwrap().as_deref(),
+                Some(long.as_str())
+            );
+
+            entry_set_password(service, "h1", "short").unwrap();
+            assert_eq!(mock_keychain_value(service, "h1").as_deref(), Some("short"));
+            assert_eq!(mock_keychain_value(service, "h1#shard-0"), None);
+
+            entry_set_password(service, "h1", &long).unwrap();
+            entry_delete_password(service, "h1").unwrap();
+            assert_eq!(entry_get_password(service, "h1").unwrap(), None);
+            assert_eq!(mock_keychain_value(service, "h1#shard-5"), None);
+        }
+    }
+
+    #[test]
+    fn tells_a_locked_keychain_from_other_failures() {
+        // The messages Security.framework gives for these codes.
+        assert!(is_locked_error(&KeyringError::PlatformFailure(
+            "User interaction is not allowed.".into()
+        )));
+        assert!(is_locked_error(&KeyringError::PlatformFailure(
+            "User canceled the operation.".into()
+        )));
+        assert!(!is_locked_error(&KeyringError::NoStorageAccess(
+            "The specified keychain could not be found.".into()
+        )));
+        assert!(!is_locked_error(&KeyringError::PlatformFailure(
+            "The specified item already exists in the keychain.".into()
+        )));
+        assert!(!is_locked_error(&KeyringError::NoEntry));
+    }
 }
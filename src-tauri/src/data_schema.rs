@@ -0,0 +1,54 @@
+use crate::models::{Host, Settings};
+use schemars::schema::RootSchema;
+use schemars::schema_for;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataSchemas {
+    pub host: RootSchema,
+    /// `hosts.json`: the legacy migration file and WebDAV fallback format.
+    pub hosts: RootSchema,
+    pub settings: RootSchema,
+}
+
+fn data_schemas_impl() -> DataSchemas {
+    DataSchemas {
+        host: schema_for!(Host),
+        hosts: schema_for!(Vec<Host>),
+        settings: schema_for!(Settings),
+    }
+}
+
+/// JSON Schemas generated from the Rust types the commands deserialize, so
+/// scripts that write hosts.json can be validated against exactly what the
+/// app accepts.
+#[tauri::command]
+pub fn data_schemas() -> DataSchemas {
+    data_schemas_impl()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::data_schemas_impl;
+
+    #[test]
+    fn host_schema_uses_wire_names_and_defaults() {
+        let schemas = serde_json::to_value(data_schemas_impl()).unwrap();
+        let host = &schemas["host"];
+        let required: Vec<&str> = host["required"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v.as_str().unwrap())
+            .collect();
+        assert!(required.contains(&"id"));
+        assert!(required.contains(&"hostname"));
+        assert!(!required.contains(&"port"));
+        assert!(host["properties"]["identityFile"].is_object());
+        assert!(host["properties"]["identity_file"].is_null());
+        assert_eq!(host["properties"]["port"]["default"], 22);
+        assert_eq!(schemas["hosts"]["type"], "array");
+        assert!(schemas["settings"]["properties"]["password_presence"].is_object());
+    }
+}
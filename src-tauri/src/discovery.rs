@@ -0,0 +1,661 @@
+use crate::host_import::{alias_from_name, new_host};
+use crate::host_store::{hosts_load, hosts_save};
+use crate::models::Host;
+use crate::operations;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream, UdpSocket};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const MDNS_ADDR: (Ipv4Addr, u16) = (Ipv4Addr::new(224, 0, 0, 251), 5353);
+const SSH_SERVICE: &str = "_ssh._tcp.local";
+const SFTP_SERVICE: &str = "_sftp-ssh._tcp.local";
+const DEFAULT_SCAN_MS: u64 = 2000;
+const MAX_SCAN_MS: u64 = 10_000;
+// Responders often leave the SRV or address records out of the PTR answer;
+// one follow-up round asks for exactly those.
+const FOLLOW_UP_MS: u64 = 800;
+const RECV_POLL: Duration = Duration::from_millis(100);
+
+// Subnet scans: at most a /20, a bounded pool of connect attempts, and a
+// short per-address timeout since lab hosts answer within a few ms.
+const SUBNET_MAX_ADDRESSES: u64 = 4096;
+const SUBNET_SCAN_CONCURRENCY: usize = 64;
+const DEFAULT_CONNECT_MS: u64 = 500;
+const MAX_CONNECT_MS: u64 = 5000;
+const SUBNET_SCAN_TIMEOUT: Duration = Duration::from_secs(600);
+const SUBNET_SCAN_SERVICE: &str = "port-scan";
+
+const TYPE_A: u16 = 1;
+const TYPE_PTR: u16 = 12;
+const TYPE_AAAA: u16 = 28;
+const TYPE_SRV: u16 = 33;
+const CLASS_IN: u16 = 1;
+// Asks responders to answer by unicast (RFC 6762 section 5.4).
+const UNICAST_RESPONSE: u16 = 0x8000;
+
+/// An SSH server advertised on the local network.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoveredHost {
+    pub name: String,
+    pub hostname: String,
+    pub ip: Option<String>,
+    pub port: u16,
+    pub service: String,
+    pub alias: String,
+    #[serde(default)]
+    pub already_added: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum RecordData {
+    Ptr(String),
+    Srv { port: u16, target: String },
+    Addr(IpAddr),
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Record {
+    name: String,
+    data: RecordData,
+}
+
+fn encode_name(out: &mut Vec<u8>, name: &str) {
+    for label in name.trim_end_matches('.').split('.') {
+        out.push(label.len() as u8);
+        out.extend_from_slice(label.as_bytes());
+    }
+    out.push(0);
+}
+
+fn build_query(questions: &[(&str, u16)]) -> Vec<u8> {
+    let mut out = vec![0, 0, 0, 0];
+    out.extend_from_slice(&(questions.len() as u16).to_be_bytes());
+    out.extend_from_slice(&[0; 6]);
+    for (name, qtype) in questions {
+        encode_name(&mut out, name);
+        out.extend_from_slice(&qtype.to_be_bytes());
+        out.extend_from_slice(&(CLASS_IN | UNICAST_RESPONSE).to_be_bytes());
+    }
+    out
+}
+
+fn read_u16(buf: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([*buf.get(at)?, *buf.get(at + 1)?]))
+}
+
+/// Decode a possibly compressed name at `at`; returns it with the offset
+/// just past it in the original position.
+fn read_name(buf: &[u8], mut at: usize) -> Option<(String, usize)> {
+    let mut labels = Vec::new();
+    let mut end = None;
+    // Bounds pointer chains, so a malicious loop cannot hang the scan.
+    for _ in 0..64 {
+        let len = *buf.get(at)? as usize;
+        if len == 0 {
+            return Some((labels.join("."), end.unwrap_or(at + 1)));
+        }
+        if len & 0xC0 == 0xC0 {
+            let pointer = (read_u16(buf, at)? & 0x3FFF) as usize;
+            end.get_or_insert(at + 2);
+            at = pointer;
+            continue;
+        }
+        let label = buf.get(at + 1..at + 1 + len)?;
+        labels.push(String::from_utf8_lossy(label).into_owned());
+        at += 1 + len;
+    }
+    None
+}
+
+/// Answer, authority and additional records of an mDNS response.
+fn parse_response(buf: &[u8]) -> Option<Vec<Record>> {
+    let flags = read_u16(buf, 2)?;
+    if flags & 0x8000 == 0 {
+        return None;
+    }
+    let questions = read_u16(buf, 4)?;
+    let records =
+        read_u16(buf, 6)? as usize + read_u16(buf, 8)? as usize + read_u16(buf, 10)? as usize;
+    let mut at = 12;
+    for _ in 0..questions {
+        at = read_name(buf, at)?.1 + 4;
+    }
+    let mut out = Vec::with_capacity(records);
+    for _ in 0..records {
+        let (name, next) = read_name(buf, at)?;
+        let rtype = read_u16(buf, next)?;
+        let rdlen = read_u16(buf, next + 8)? as usize;
+        let rdata_at = next + 10;
+        let rdata = buf.get(rdata_at..rdata_at + rdlen)?;
+        let data = match rtype {
+            TYPE_PTR => RecordData::Ptr(read_name(buf, rdata_at)?.0),
+            TYPE_SRV => RecordData::Srv {
+                port: read_u16(buf, rdata_at + 4)?,
+                target: read_name(buf, rdata_at + 6)?.0,
+            },
+            TYPE_A if rdlen == 4 => RecordData::Addr(IpAddr::V4(Ipv4Addr::new(
+                rdata[0], rdata[1], rdata[2], rdata[3],
+            ))),
+            TYPE_AAAA if rdlen == 16 => {
+                let octets: [u8; 16] = rdata.try_into().ok()?;
+                RecordData::Addr(IpAddr::V6(Ipv6Addr::from(octets)))
+            }
+            _ => RecordData::Other,
+        };
+        out.push(Record { name, data });
+        at = rdata_at + rdlen;
+    }
+    Some(out)
+}
+
+#[derive(Debug, Default)]
+struct RecordSet {
+    instances: Vec<(String, String)>,
+    services: HashMap<String, (u16, String)>,
+    addrs: HashMap<String, Vec<IpAddr>>,
+}
+
+fn name_key(name: &str) -> String {
+    name.trim_end_matches('.').to_ascii_lowercase()
+}
+
+impl RecordSet {
+    fn add(&mut self, record: Record) {
+        let key = name_key(&record.name);
+        match record.data {
+            RecordData::Ptr(instance) if key == SSH_SERVICE || key == SFTP_SERVICE => {
+                let entry = (key, instance);
+                if !self.instances.contains(&entry) {
+                    self.instances.push(entry);
+                }
+            }
+            RecordData::Srv { port, target } => {
+                self.services.insert(key, (port, target));
+            }
+            RecordData::Addr(ip) => {
+                let addrs = self.addrs.entry(key).or_default();
+                if !addrs.contains(&ip) {
+                    addrs.push(ip);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Names whose SRV or address records have not arrived yet.
+    fn missing(&self) -> Vec<(String, u16)> {
+        let mut missing = Vec::new();
+        for (_, instance) in &self.instances {
+            match self.services.get(&name_key(instance)) {
+                None => missing.push((instance.clone(), TYPE_SRV)),
+                Some((_, target)) if !self.addrs.contains_key(&name_key(target)) => {
+                    missing.push((target.clone(), TYPE_A));
+                }
+                Some(_) => {}
+            }
+        }
+        missing.dedup();
+        missing
+    }
+
+    fn candidates(&self) -> Vec<DiscoveredHost> {
+        let mut found: Vec<DiscoveredHost> = Vec::new();
+        for (service, instance) in &self.instances {
+            let Some((port, target)) = self.services.get(&name_key(instance)) else {
+                continue;
+            };
+            let ip = self.addrs.get(&name_key(target)).and_then(|addrs| {
+                addrs
+                    .iter()
+                    .find(|ip| ip.is_ipv4())
+                    .or_else(|| addrs.first())
+                    .map(|ip| ip.to_string())
+            });
+            let name = instance
+                .strip_suffix(&format!(".{service}"))
+                .unwrap_or(instance)
+                .to_string();
+            let hostname = target.trim_end_matches('.').to_string();
+            let candidate = DiscoveredHost {
+                alias: alias_for(&name),
+                name,
+                hostname,
+                ip,
+                port: *port,
+                service: if service == SSH_SERVICE {
+                    "ssh"
+                } else {
+                    "sftp-ssh"
+                }
+                .to_string(),
+                already_added: false,
+            };
+            // A server announcing both services is one host; keep the ssh one.
+            match found.iter_mut().find(|existing| {
+                existing.port == candidate.port
+                    && existing.hostname.eq_ignore_ascii_case(&candidate.hostname)
+            }) {
+                Some(existing) if candidate.service == "ssh" => *existing = candidate,
+                Some(_) => {}
+                None => found.push(candidate),
+            }
+        }
+        found.sort_by_key(|host| host.name.to_lowercase());
+        found
+    }
+}
+
+fn alias_for(name: &str) -> String {
+    alias_from_name(name, "mdns-host")
+}
+
+fn receive_until(socket: &UdpSocket, deadline: Instant, records: &mut RecordSet) {
+    let mut buf = [0u8; 9000];
+    while Instant::now() < deadline {
+        match socket.recv_from(&mut buf) {
+            Ok((len, _)) => {
+                for record in parse_response(&buf[..len]).unwrap_or_default() {
+                    records.add(record);
+                }
+            }
+            // Timeouts are the poll tick; other errors (e.g. ICMP
+            // unreachable surfacing on some platforms) are not fatal either.
+            Err(_) => continue,
+        }
+    }
+}
+
+fn scan(window: Duration) -> Result<Vec<DiscoveredHost>, String> {
+    // Queries from an ephemeral port are "legacy unicast" queries: responders
+    // answer directly to it, so port 5353 (owned by the system responder on
+    // macOS) is never needed.
+    let socket = UdpSocket::bind(SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)))
+        .map_err(|e| format!("Failed to open mDNS socket: {e}"))?;
+    socket
+        .set_read_timeout(Some(RECV_POLL))
+        .map_err(|e| e.to_string())?;
+    let _ = socket.set_multicast_ttl_v4(255);
+
+    let query = build_query(&[(SSH_SERVICE, TYPE_PTR), (SFTP_SERVICE, TYPE_PTR)]);
+    socket
+        .send_to(&query, MDNS_ADDR)
+        .map_err(|e| format!("Failed to send mDNS query: {e}"))?;
+    let mut records = RecordSet::default();
+    receive_until(&socket, Instant::now() + window, &mut records);
+
+    let missing = records.missing();
+    if !missing.is_empty() {
+        let questions: Vec<(&str, u16)> = missing
+            .iter()
+            .map(|(name, qtype)| (name.as_str(), *qtype))
+            .collect();
+        if socket.send_to(&build_query(&questions), MDNS_ADDR).is_ok() {
+            receive_until(
+                &socket,
+                Instant::now() + Duration::from_millis(FOLLOW_UP_MS),
+                &mut records,
+            );
+        }
+    }
+    Ok(records.candidates())
+}
+
+fn mark_already_added(candidates: &mut [DiscoveredHost], hosts: &[Host]) {
+    for candidate in candidates.iter_mut() {
+        candidate.already_added = hosts.iter().filter(|host| !host.deleted).any(|host| {
+            let hostname = host.hostname.trim();
+            host.port == candidate.port
+                && (hostname.eq_ignore_ascii_case(&candidate.hostname)
+                    || candidate.ip.as_deref() == Some(hostname))
+        });
+    }
+}
+
+/// Browse `_ssh._tcp.local` and `_sftp-ssh._tcp.local` for `timeout_ms`
+/// (default 2s) and return the servers found, flagging ones already in the
+/// host list.
+#[tauri::command]
+pub async fn discovery_scan(timeout_ms: Option<u64>) -> Result<Vec<DiscoveredHost>, String> {
+    let window = Duration::from_millis(
+        timeout_ms
+            .unwrap_or(DEFAULT_SCAN_MS)
+            .clamp(200, MAX_SCAN_MS),
+    );
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut candidates = scan(window)?;
+        mark_already_added(&mut candidates, &hosts_load()?);
+        Ok(candidates)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Addresses to probe in `cidr` (`10.0.0.0/24`, or a bare address). Network
+/// and broadcast addresses are skipped except in /31 and /32.
+fn subnet_addresses(cidr: &str) -> Result<Vec<Ipv4Addr>, String> {
+    let cidr = cidr.trim();
+    let (addr, prefix) = match cidr.split_once('/') {
+        Some((addr, prefix)) => (
+            addr.trim(),
+            prefix
+                .trim()
+                .parse::<u32>()
+                .ok()
+                .filter(|prefix| *prefix <= 32)
+                .ok_or_else(|| format!("Invalid prefix length in {cidr}"))?,
+        ),
+        None => (cidr, 32),
+    };
+    let addr: Ipv4Addr = addr
+        .parse()
+        .map_err(|_| format!("Invalid IPv4 network: {cidr}"))?;
+    let size = 1u64 << (32 - prefix);
+    if size > SUBNET_MAX_ADDRESSES {
+        return Err(format!(
+            "{cidr} has {size} addresses; scan at most {SUBNET_MAX_ADDRESSES} (a /20) at a time"
+        ));
+    }
+    let mask = if prefix == 0 {
+        0
+    } else {
+        u32::MAX << (32 - prefix)
+    };
+    let network = u64::from(u32::from(addr) & mask);
+    let (first, last) = if prefix >= 31 {
+        (network, network + size - 1)
+    } else {
+        (network + 1, network + size - 2)
+    };
+    Ok((first..=last).map(|ip| Ipv4Addr::from(ip as u32)).collect())
+}
+
+#[cfg(unix)]
+fn reverse_dns(ip: Ipv4Addr) -> Option<String> {
+    // NI_MAXHOST
+    const HOST_BUF_LEN: usize = 1025;
+    let mut addr: libc::sockaddr_in = unsafe { std::mem::zeroed() };
+    #[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+    {
+        addr.sin_len = std::mem::size_of::<libc::sockaddr_in>() as u8;
+    }
+    addr.sin_family = libc::AF_INET as libc::sa_family_t;
+    addr.sin_addr = libc::in_addr {
+        s_addr: u32::from(ip).to_be(),
+    };
+    let mut host = [0 as libc::c_char; HOST_BUF_LEN];
+    let rc = unsafe {
+        libc::getnameinfo(
+            &addr as *const libc::sockaddr_in as *const libc::sockaddr,
+            std::mem::size_of::<libc::sockaddr_in>() as libc::socklen_t,
+            host.as_mut_ptr(),
+            HOST_BUF_LEN as libc::socklen_t,
+            std::ptr::null_mut(),
+            0,
+            libc::NI_NAMEREQD,
+        )
+    };
+    if rc != 0 {
+        return None;
+    }
+    let name = unsafe { std::ffi::CStr::from_ptr(host.as_ptr()) }
+        .to_string_lossy()
+        .trim_end_matches('.')
+        .to_string();
+    (!name.is_empty()).then_some(name)
+}
+
+#[cfg(not(unix))]
+fn reverse_dns(_ip: Ipv4Addr) -> Option<String> {
+    None
+}
+
+fn subnet_candidate(ip: Ipv4Addr, port: u16) -> DiscoveredHost {
+    let ip_text = ip.to_string();
+    let hostname = reverse_dns(ip).unwrap_or_else(|| ip_text.clone());
+    DiscoveredHost {
+        name: hostname.clone(),
+        alias: alias_for(&hostname),
+        hostname,
+        ip: Some(ip_text),
+        port,
+        service: SUBNET_SCAN_SERVICE.to_string(),
+        already_added: false,
+    }
+}
+
+fn scan_subnet(
+    addresses: &[Ipv4Addr],
+    port: u16,
+    connect_timeout: Duration,
+) -> Vec<DiscoveredHost> {
+    let next = AtomicUsize::new(0);
+    let scanned = AtomicUsize::new(0);
+    let found = Mutex::new(Vec::new());
+    // Workers share the scan's operation, so a cancel stops the whole pool.
+    let op = operations::current();
+    thread::scope(|scope| {
+        for _ in 0..SUBNET_SCAN_CONCURRENCY.min(addresses.len()) {
+            let op = op.as_ref();
+            let (next, scanned, found) = (&next, &scanned, &found);
+            scope.spawn(move || {
+                operations::enter(op, || loop {
+                    if operations::check_current().is_err() {
+                        break;
+                    }
+                    let Some(ip) = addresses.get(next.fetch_add(1, Ordering::Relaxed)) else {
+                        break;
+                    };
+                    let target = SocketAddr::from((*ip, port));
+                    if TcpStream::connect_timeout(&target, connect_timeout).is_ok() {
+                        let candidate = subnet_candidate(*ip, port);
+                        if let Ok(mut found) = found.lock() {
+                            found.push(candidate);
+                        }
+                    }
+                    let done = scanned.fetch_add(1, Ordering::Relaxed) + 1;
+                    operations::report_progress(done as u64, Some(addresses.len() as u64), None);
+                })
+            });
+        }
+    });
+    let mut found = found.into_inner().unwrap_or_default();
+    found.sort_by_key(|candidate| {
+        candidate
+            .ip
+            .as_deref()
+            .and_then(|ip| ip.parse::<Ipv4Addr>().ok())
+    });
+    found
+}
+
+/// Probe every address of `cidr` for an open TCP `port` (default 22) and
+/// return the responsive ones with their reverse-DNS names, ready for
+/// `discovery_add`.
+#[tauri::command]
+pub async fn discovery_scan_subnet(
+    cidr: String,
+    port: Option<u16>,
+    timeout_ms: Option<u64>,
+    op_id: String,
+) -> Result<Vec<DiscoveredHost>, String> {
+    let addresses = subnet_addresses(&cidr)?;
+    let port = port.unwrap_or(22);
+    if port == 0 {
+        return Err("Port must be between 1 and 65535".to_string());
+    }
+    let connect_timeout = Duration::from_millis(
+        timeout_ms
+            .unwrap_or(DEFAULT_CONNECT_MS)
+            .clamp(50, MAX_CONNECT_MS),
+    );
+    tauri::async_runtime::spawn_blocking(move || {
+        operations::run(op_id, "discovery_scan_subnet", SUBNET_SCAN_TIMEOUT, || {
+            let mut candidates = scan_subnet(&addresses, port, connect_timeout);
+            operations::check_current()?;
+            mark_already_added(&mut candidates, &hosts_load()?);
+            Ok(candidates)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Add a discovered server to the host list. The `.local` name is kept as
+/// the hostname so the entry survives DHCP address changes.
+#[tauri::command]
+pub fn discovery_add(candidate: DiscoveredHost, user: Option<String>) -> Result<Host, String> {
+    let mut hosts = hosts_load()?;
+    let host = new_host(
+        &hosts,
+        candidate.name,
+        &candidate.alias,
+        candidate.hostname,
+        user.unwrap_or_default().trim().to_string(),
+        candidate.port,
+    );
+    hosts.push(host.clone());
+    hosts_save(hosts)?;
+    Ok(host)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        alias_for, build_query, encode_name, parse_response, read_name, scan_subnet,
+        subnet_addresses, RecordSet, SFTP_SERVICE, SSH_SERVICE, TYPE_A, TYPE_PTR, TYPE_SRV,
+    };
+    use std::net::{Ipv4Addr, TcpListener};
+    use std::time::Duration;
+
+    fn record(out: &mut Vec<u8>, name: &str, rtype: u16, rdata: &[u8]) {
+        encode_name(out, name);
+        out.extend_from_slice(&rtype.to_be_bytes());
+        out.extend_from_slice(&0x8001u16.to_be_bytes());
+        out.extend_from_slice(&120u32.to_be_bytes());
+        out.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+        out.extend_from_slice(rdata);
+    }
+
+    fn response(records: &[(&str, u16, Vec<u8>)]) -> Vec<u8> {
+        let mut out = vec![0, 0, 0x84, 0, 0, 0];
+        out.extend_from_slice(&(records.len() as u16).to_be_bytes());
+        out.extend_from_slice(&[0; 4]);
+        for (name, rtype, rdata) in records {
+            record(&mut out, name, *rtype, rdata);
+        }
+        out
+    }
+
+    fn name_bytes(name: &str) -> Vec<u8> {
+        let mut out = Vec::new();
+        encode_name(&mut out, name);
+        out
+    }
+
+    fn srv(port: u16, target: &str) -> Vec<u8> {
+        let mut out = vec![0, 0, 0, 0];
+        out.extend_from_slice(&port.to_be_bytes());
+        out.extend_from_slice(&name_bytes(target));
+        out
+    }
+
+    #[test]
+    fn decodes_compressed_names() {
+        let mut buf = build_query(&[(SSH_SERVICE, TYPE_PTR)]);
+        let pointer_at = buf.len();
+        buf.extend_from_slice(&[3, b'b', b'o', b'x', 0xC0, 12]);
+        assert_eq!(
+            read_name(&buf, pointer_at),
+            Some(("box._ssh._tcp.local".to_string(), pointer_at + 6))
+        );
+        // A pointer to itself must not hang.
+        assert_eq!(read_name(&[0xC0, 0], 0), None);
+    }
+
+    #[test]
+    fn assembles_candidates_and_prefers_the_ssh_service() {
+        let packet = response(&[
+            (
+                SSH_SERVICE,
+                TYPE_PTR,
+                name_bytes("Jane's Mac._ssh._tcp.local"),
+            ),
+            (
+                SFTP_SERVICE,
+                TYPE_PTR,
+                name_bytes("Jane's Mac._sftp-ssh._tcp.local"),
+            ),
+            (
+                SFTP_SERVICE,
+                TYPE_PTR,
+                name_bytes("nas._sftp-ssh._tcp.local"),
+            ),
+            (
+                "Jane's Mac._ssh._tcp.local",
+                TYPE_SRV,
+                srv(22, "janes-mac.local"),
+            ),
+            (
+                "Jane's Mac._sftp-ssh._tcp.local",
+                TYPE_SRV,
+                srv(22, "janes-mac.local"),
+            ),
+            ("janes-mac.local", TYPE_A, vec![192, 168, 1, 20]),
+        ]);
+        let mut records = RecordSet::default();
+        for record in parse_response(&packet).unwrap() {
+            records.add(record);
+        }
+        assert_eq!(
+            records.missing(),
+            vec![("nas._sftp-ssh._tcp.local".to_string(), TYPE_SRV)]
+        );
+
+        let candidates = records.candidates();
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].name, "Jane's Mac");
+        assert_eq!(candidates[0].alias, "jane-s-mac");
+        assert_eq!(candidates[0].hostname, "janes-mac.local");
+        assert_eq!(candidates[0].ip.as_deref(), Some("192.168.1.20"));
+        assert_eq!(candidates[0].service, "ssh");
+        assert_eq!(alias_for("  "), "mdns-host");
+    }
+
+    #[test]
+    fn expands_cidr_ranges() {
+        let hosts = subnet_addresses("192.168.1.77/30").unwrap();
+        assert_eq!(
+            hosts,
+            vec![
+                Ipv4Addr::new(192, 168, 1, 77),
+                Ipv4Addr::new(192, 168, 1, 78)
+            ]
+        );
+        assert_eq!(subnet_addresses("10.0.0.0/24").unwrap().len(), 254);
+        assert_eq!(
+            subnet_addresses(" 10.0.0.9 ").unwrap(),
+            vec![Ipv4Addr::new(10, 0, 0, 9)]
+        );
+        assert!(subnet_addresses("10.0.0.0/8").is_err());
+        assert!(subnet_addresses("10.0.0.0/33").is_err());
+        assert!(subnet_addresses("host.local/24").is_err());
+    }
+
+    #[test]
+    fn scan_reports_open_ports_only() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let addresses = vec![Ipv4Addr::LOCALHOST, Ipv4Addr::new(127, 0, 0, 2)];
+        let found = scan_subnet(&addresses, port, Duration::from_millis(300));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].ip.as_deref(), Some("127.0.0.1"));
+        assert_eq!(found[0].port, port);
+        assert_eq!(found[0].service, "port-scan");
+    }
+}
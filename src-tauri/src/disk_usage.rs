@@ -0,0 +1,189 @@
+use crate::host_probe::run_probe;
+use crate::models::Host;
+use crate::operations;
+use crate::remote_files::remote_path_arg;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+const DU_TIMEOUT: Duration = Duration::from_secs(600);
+const DEFAULT_DU_DEPTH: u32 = 2;
+const MAX_DU_DEPTH: u32 = 6;
+// A parent is never smaller than its children, so keeping the largest lines
+// keeps every ancestor of each line that is kept.
+const MAX_DU_LINES: usize = 5000;
+const MAX_CHILDREN: usize = 25;
+const DU_CACHE_TTL: Duration = Duration::from_secs(600);
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskUsageNode {
+    pub path: String,
+    pub name: String,
+    pub size_kb: u64,
+    /// Largest first; at most `MAX_CHILDREN`.
+    pub children: Vec<DiskUsageNode>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteDiskUsage {
+    pub root: DiskUsageNode,
+    pub depth: u32,
+    pub scanned_at: String,
+    /// Served from a scan of the last few minutes.
+    pub cached: bool,
+}
+
+// Keyed by host id, path as given and depth.
+type DuCache = Mutex<HashMap<(String, String, u32), (Instant, RemoteDiskUsage)>>;
+
+fn du_cache() -> &'static DuCache {
+    static CACHE: OnceLock<DuCache> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// `du` stays on the filesystem of `path` (-x) and runs at low priority; paths
+// are relative to `path` so the tree does not depend on how it was spelled.
+fn du_script(path: &str, depth: u32) -> Result<String, String> {
+    Ok(format!(
+        "cd {} || exit 1\nprintf 'root=%s\\n' \"$(pwd)\"\n\
+         nice -n 19 du -x -k -d {depth} . 2>/dev/null | sort -rn | head -n {MAX_DU_LINES}\n",
+        remote_path_arg(path)?
+    ))
+}
+
+fn build_node(
+    path: &str,
+    root: &str,
+    sizes: &HashMap<&str, u64>,
+    children: &HashMap<&str, Vec<&str>>,
+) -> DiskUsageNode {
+    let absolute = match path.strip_prefix("./") {
+        Some(relative) => format!("{}/{relative}", root.trim_end_matches('/')),
+        None => root.to_string(),
+    };
+    let mut nodes: Vec<DiskUsageNode> = children
+        .get(path)
+        .map(|paths| {
+            paths
+                .iter()
+                .map(|child| build_node(child, root, sizes, children))
+                .collect()
+        })
+        .unwrap_or_default();
+    nodes.sort_by(|a, b| b.size_kb.cmp(&a.size_kb).then_with(|| a.name.cmp(&b.name)));
+    nodes.truncate(MAX_CHILDREN);
+    DiskUsageNode {
+        name: absolute
+            .rsplit('/')
+            .find(|part| !part.is_empty())
+            .unwrap_or("/")
+            .to_string(),
+        path: absolute,
+        size_kb: sizes.get(path).copied().unwrap_or_default(),
+        children: nodes,
+    }
+}
+
+fn parse_du(stdout: &str) -> Result<DiskUsageNode, String> {
+    let mut root = None;
+    let mut sizes = HashMap::new();
+    for line in stdout.lines() {
+        if let Some(path) = line.strip_prefix("root=") {
+            root = Some(path.trim());
+        } else if let Some((size, path)) = line.split_once('\t') {
+            if let Ok(size) = size.trim().parse::<u64>() {
+                sizes.insert(path, size);
+            }
+        }
+    }
+    let root = root.ok_or("du did not report its directory")?;
+    if !sizes.contains_key(".") {
+        return Err(format!("du could not read {root}"));
+    }
+    let mut children: HashMap<&str, Vec<&str>> = HashMap::new();
+    for path in sizes.keys().copied().filter(|path| *path != ".") {
+        if let Some((parent, _)) = path.rsplit_once('/') {
+            children.entry(parent).or_default().push(path);
+        }
+    }
+    Ok(build_node(".", root, &sizes, &children))
+}
+
+/// The largest directories under `path` on the host, `depth` levels deep
+/// (2 by default). `du` over a large tree is slow, so results are cached for
+/// a few minutes unless `refresh` is set, and the scan can be cancelled.
+#[tauri::command]
+pub async fn remote_du(
+    host: Host,
+    path: String,
+    depth: Option<u32>,
+    refresh: Option<bool>,
+    op_id: String,
+) -> Result<RemoteDiskUsage, String> {
+    let depth = depth.unwrap_or(DEFAULT_DU_DEPTH).clamp(1, MAX_DU_DEPTH);
+    let key = (host.id.clone(), path.trim().to_string(), depth);
+    if !refresh.unwrap_or(false) {
+        let cache = du_cache()
+            .lock()
+            .map_err(|_| "disk usage cache poisoned".to_string())?;
+        if let Some((at, usage)) = cache.get(&key) {
+            if at.elapsed() < DU_CACHE_TTL {
+                return Ok(RemoteDiskUsage {
+                    cached: true,
+                    ..usage.clone()
+                });
+            }
+        }
+    }
+    let script = du_script(&path, depth)?;
+    let usage = tauri::async_runtime::spawn_blocking(move || {
+        operations::run(op_id, "remote_du", DU_TIMEOUT, || {
+            Ok(RemoteDiskUsage {
+                root: parse_du(&run_probe(&host, &script)?)?,
+                depth,
+                scanned_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                cached: false,
+            })
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    if let Ok(mut cache) = du_cache().lock() {
+        cache.retain(|_, (at, _)| at.elapsed() < DU_CACHE_TTL);
+        cache.insert(key, (Instant::now(), usage.clone()));
+    }
+    Ok(usage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{du_script, parse_du};
+
+    #[test]
+    fn builds_tree_of_largest_directories() {
+        let stdout = "root=/var\n\
+                      900\t.\n\
+                      600\t./lib\n\
+                      500\t./lib/mysql\n\
+                      250\t./log\n\
+                      90\t./lib/apt\n\
+                      50\t./cache\n\
+                      40\t./orphan/child\n";
+        let root = parse_du(stdout).unwrap();
+        assert_eq!((root.path.as_str(), root.size_kb), ("/var", 900));
+        let names: Vec<&str> = root.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["lib", "log", "cache"]);
+        let lib = &root.children[0];
+        assert_eq!(lib.path, "/var/lib");
+        assert_eq!(lib.children[0].path, "/var/lib/mysql");
+        assert_eq!(lib.children[0].size_kb, 500);
+
+        assert!(parse_du("root=/root\n").is_err());
+        assert!(du_script("~/data", 2)
+            .unwrap()
+            .contains("cd \"$HOME\"/'data'"));
+    }
+}
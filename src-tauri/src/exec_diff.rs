@@ -0,0 +1,270 @@
+use crate::host_probe::{run_probe_capped, shell_quote};
+use crate::models::Host;
+use crate::operations::{self, OutputTruncation};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::thread;
+use std::time::Duration;
+
+// Locale and timezone change how many tools format output (dates, decimal
+// separators, sort order); pin them so hosts only differ where they really do.
+const PINNED_EXEC_ENV: &[(&str, &str)] = &[("LC_ALL", "C"), ("LANG", "C"), ("TZ", "UTC")];
+const BULK_EXEC_DIFF_CONCURRENCY: usize = 8;
+const BULK_EXEC_DIFF_TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HostExecOutput {
+    pub host_id: String,
+    #[serde(default)]
+    pub stdout: String,
+    #[serde(default)]
+    pub exit_code: Option<i32>,
+    /// Set when `stdout` was cut at the output cap.
+    #[serde(default)]
+    pub truncated: Option<OutputTruncation>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecOutputGroup {
+    pub output: String,
+    pub exit_code: Option<i32>,
+    pub host_ids: Vec<String>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecDiffReport {
+    /// Identical (normalized) results, largest group first.
+    pub groups: Vec<ExecOutputGroup>,
+    /// Hosts outside the largest group; empty when every host agrees or when
+    /// there is no single majority to compare against.
+    pub outlier_host_ids: Vec<String>,
+    /// Hosts whose output hit the cap, so their group compares a prefix only.
+    pub truncated: BTreeMap<String, OutputTruncation>,
+}
+
+/// Wrap a command so it runs with the pinned environment used for diffing.
+pub(crate) fn pinned_exec_command(command: &str) -> String {
+    let env = PINNED_EXEC_ENV
+        .iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!("env {env} sh -c {}", shell_quote(command))
+}
+
+fn normalize_output(output: &str) -> String {
+    let normalized = output.replace("\r\n", "\n").replace('\r', "\n");
+    let lines: Vec<&str> = normalized.lines().map(str::trim_end).collect();
+    let end = lines
+        .iter()
+        .rposition(|line| !line.is_empty())
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    lines[..end].join("\n")
+}
+
+pub(crate) fn diff_exec_results(results: Vec<HostExecOutput>) -> ExecDiffReport {
+    let mut index: HashMap<(String, Option<i32>), usize> = HashMap::new();
+    let mut groups: Vec<ExecOutputGroup> = Vec::new();
+    let mut truncated = BTreeMap::new();
+    for result in results {
+        if let Some(truncation) = result.truncated {
+            truncated.insert(result.host_id.clone(), truncation);
+        }
+        let key = (normalize_output(&result.stdout), result.exit_code);
+        match index.get(&key) {
+            Some(&i) => groups[i].host_ids.push(result.host_id),
+            None => {
+                index.insert(key.clone(), groups.len());
+                groups.push(ExecOutputGroup {
+                    output: key.0,
+                    exit_code: key.1,
+                    host_ids: vec![result.host_id],
+                });
+            }
+        }
+    }
+    // Stable sort keeps first-seen order among equally sized groups.
+    groups.sort_by_key(|group| Reverse(group.host_ids.len()));
+
+    let has_majority = match groups.as_slice() {
+        [first, second, ..] => first.host_ids.len() > second.host_ids.len(),
+        _ => false,
+    };
+    let outlier_host_ids = if has_majority {
+        groups[1..]
+            .iter()
+            .flat_map(|group| group.host_ids.iter().cloned())
+            .collect()
+    } else {
+        Vec::new()
+    };
+    ExecDiffReport {
+        groups,
+        outlier_host_ids,
+        truncated,
+    }
+}
+
+// The marker keeps a cut output from looking complete when it is shown or
+// copied on its own.
+fn with_truncation_marker(mut stdout: String, truncation: OutputTruncation) -> String {
+    if !stdout.is_empty() && !stdout.ends_with('\n') {
+        stdout.push('\n');
+    }
+    stdout.push_str(&format!(
+        "[output truncated: kept {} of {} bytes]",
+        truncation.cap_bytes.min(truncation.stdout_bytes),
+        truncation.stdout_bytes
+    ));
+    stdout
+}
+
+fn run_pinned_on_hosts(hosts: Vec<Host>, command: &str) -> Vec<HostExecOutput> {
+    let script = pinned_exec_command(command);
+    // Workers share the command's operation, so one cancel stops every host.
+    let op = operations::current();
+    let total = hosts.len() as u64;
+    let mut results = Vec::with_capacity(hosts.len());
+    for chunk in hosts.chunks(BULK_EXEC_DIFF_CONCURRENCY) {
+        thread::scope(|scope| {
+            let handles: Vec<_> = chunk
+                .iter()
+                .map(|host| {
+                    let script = script.as_str();
+                    let op = op.as_ref();
+                    scope.spawn(move || {
+                        // run_probe_capped folds non-zero exits into Err(stderr), so
+                        // failures group by their error text.
+                        let (stdout, exit_code, truncated) =
+                            match operations::enter(op, || run_probe_capped(host, script)) {
+                                Ok((stdout, truncated)) => (stdout, Some(0), truncated),
+                                Err(error) => (error, None, None),
+                            };
+                        HostExecOutput {
+                            host_id: host.id.clone(),
+                            stdout: match truncated {
+                                Some(truncation) => with_truncation_marker(stdout, truncation),
+                                None => stdout,
+                            },
+                            exit_code,
+                            truncated,
+                        }
+                    })
+                })
+                .collect();
+            for handle in handles {
+                if let Ok(result) = handle.join() {
+                    results.push(result);
+                }
+            }
+            operations::report_progress(results.len() as u64, Some(total), None);
+        });
+    }
+    results
+}
+
+/// Run `command` on every host with a pinned locale/timezone and group the
+/// outputs, so drift across a fleet stands out. Each host's output is capped
+/// at `max_output_kb` (1 MiB by default).
+#[tauri::command]
+pub async fn bulk_exec_diff(
+    hosts: Vec<Host>,
+    command: String,
+    op_id: String,
+    max_output_kb: Option<u64>,
+) -> Result<ExecDiffReport, String> {
+    if command.trim().is_empty() {
+        return Err("command is required".to_string());
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        operations::run(op_id, "bulk_exec_diff", BULK_EXEC_DIFF_TIMEOUT, || {
+            if let Some(kb) = max_output_kb {
+                operations::set_output_cap(kb.saturating_mul(1024) as usize);
+            }
+            let results = run_pinned_on_hosts(hosts, &command);
+            // A cancelled run would otherwise report every host as failing
+            // with the same error.
+            operations::check_current()?;
+            Ok(diff_exec_results(results))
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub fn exec_results_diff(results: Vec<HostExecOutput>) -> Result<ExecDiffReport, String> {
+    Ok(diff_exec_results(results))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff_exec_results, pinned_exec_command, with_truncation_marker, HostExecOutput};
+    use crate::operations::OutputTruncation;
+
+    fn output(host_id: &str, stdout: &str) -> HostExecOutput {
+        HostExecOutput {
+            host_id: host_id.to_string(),
+            stdout: stdout.to_string(),
+            exit_code: Some(0),
+            truncated: None,
+        }
+    }
+
+    #[test]
+    fn groups_normalized_outputs_and_flags_outliers() {
+        let report = diff_exec_results(vec![
+            output("a", "6.1.0-18-amd64\n"),
+            output("b", "6.1.0-18-amd64   \r\n\r\n"),
+            output("c", "5.10.0-28-amd64\n"),
+            output("d", "6.1.0-18-amd64"),
+        ]);
+        assert_eq!(report.groups.len(), 2);
+        assert_eq!(report.groups[0].output, "6.1.0-18-amd64");
+        assert_eq!(report.groups[0].host_ids, vec!["a", "b", "d"]);
+        assert_eq!(report.outlier_host_ids, vec!["c"]);
+    }
+
+    #[test]
+    fn no_outliers_without_a_majority() {
+        let report = diff_exec_results(vec![output("a", "x"), output("b", "y")]);
+        assert_eq!(report.groups.len(), 2);
+        assert!(report.outlier_host_ids.is_empty());
+
+        let mut failed = output("c", "x");
+        failed.exit_code = Some(1);
+        let report = diff_exec_results(vec![output("a", "x"), output("b", "x"), failed]);
+        assert_eq!(report.outlier_host_ids, vec!["c"]);
+    }
+
+    #[test]
+    fn reports_truncated_hosts() {
+        let truncation = OutputTruncation {
+            stdout_bytes: 10_000,
+            stderr_bytes: 0,
+            cap_bytes: 4096,
+        };
+        assert_eq!(
+            with_truncation_marker("abc".to_string(), truncation),
+            "abc\n[output truncated: kept 4096 of 10000 bytes]"
+        );
+        let mut cut = output("b", "x");
+        cut.truncated = Some(truncation);
+        let report = diff_exec_results(vec![output("a", "x"), cut]);
+        assert_eq!(report.groups.len(), 1);
+        assert_eq!(report.truncated.keys().collect::<Vec<_>>(), vec!["b"]);
+    }
+
+    #[test]
+    fn pins_locale_and_timezone() {
+        assert_eq!(
+            pinned_exec_command("date; echo 'hi'"),
+            "env LC_ALL=C LANG=C TZ=UTC sh -c 'date; echo '\\''hi'\\'''"
+        );
+    }
+}
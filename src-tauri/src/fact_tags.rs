@@ -0,0 +1,317 @@
+use crate::host_store::{atomic_write, ensure_config_dir, get_config_dir, host_retag};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use tauri::{AppHandle, Emitter, Runtime};
+
+const MAIN_WINDOW_LABEL: &str = "main";
+
+/// Auto-assigns `tag` to every host whose probe fact `fact` is known (and
+/// equals `equals`, when set). `{value}` in the tag is replaced with the
+/// fact's value, so `arch={value}` yields `arch=arm64`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagRule {
+    pub fact: String,
+    #[serde(default)]
+    pub equals: Option<String>,
+    pub tag: String,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HostFacts {
+    #[serde(default)]
+    facts: BTreeMap<String, String>,
+    // Tags this module added, so a refresh only removes its own tags and
+    // never ones the user assigned by hand.
+    #[serde(default)]
+    auto_tags: Vec<String>,
+}
+
+type FactsStore = BTreeMap<String, HostFacts>;
+
+/// Sent as `hosts:changed` when tag rules retagged hosts, so the UI reloads
+/// them.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostsChangedPayload {
+    pub host_ids: Vec<String>,
+}
+
+fn emit_hosts_changed<R: Runtime>(app: &AppHandle<R>, host_ids: Vec<String>) {
+    if !host_ids.is_empty() {
+        let _ = app.emit_to(
+            MAIN_WINDOW_LABEL,
+            "hosts:changed",
+            HostsChangedPayload { host_ids },
+        );
+    }
+}
+
+fn default_rules() -> Vec<TagRule> {
+    vec![
+        TagRule {
+            fact: "os".to_string(),
+            equals: None,
+            tag: "os={value}".to_string(),
+        },
+        TagRule {
+            fact: "arch".to_string(),
+            equals: None,
+            tag: "arch={value}".to_string(),
+        },
+        TagRule {
+            fact: "docker".to_string(),
+            equals: Some("true".to_string()),
+            tag: "has-docker".to_string(),
+        },
+    ]
+}
+
+fn get_tag_rules_path() -> PathBuf {
+    get_config_dir().join("tag_rules.json")
+}
+
+fn get_host_facts_path() -> PathBuf {
+    get_config_dir().join("host_facts.json")
+}
+
+// Serializes read-modify-write of the facts file; probes of several hosts
+// finish concurrently.
+fn facts_lock() -> &'static Mutex<()> {
+    static LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+    LOCK.get_or_init(|| Mutex::new(()))
+}
+
+fn load_rules() -> Result<Vec<TagRule>, String> {
+    let path = get_tag_rules_path();
+    if !path.exists() {
+        return Ok(default_rules());
+    }
+    let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&content).map_err(|e| e.to_string())
+}
+
+fn load_facts() -> Result<FactsStore, String> {
+    let path = get_host_facts_path();
+    if !path.exists() {
+        return Ok(FactsStore::new());
+    }
+    let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&content).map_err(|e| e.to_string())
+}
+
+fn save_facts(store: &FactsStore) -> Result<(), String> {
+    ensure_config_dir()?;
+    let content = serde_json::to_string_pretty(store).map_err(|e| e.to_string())?;
+    atomic_write(&get_host_facts_path(), content.as_bytes())
+}
+
+fn tag_value(value: &str) -> String {
+    value
+        .trim()
+        .to_ascii_lowercase()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// `ubuntu` + `22.04` -> `ubuntu22`; hosts without os-release get the
+/// kernel name (`darwin`).
+pub(crate) fn os_fact(os_id: Option<&str>, os_version: Option<&str>) -> Option<String> {
+    let id = tag_value(os_id?);
+    if id.is_empty() {
+        return None;
+    }
+    let major = os_version
+        .and_then(|version| version.trim().split('.').next())
+        .filter(|major| !major.is_empty() && major.chars().all(|c| c.is_ascii_alphanumeric()))
+        .unwrap_or("");
+    Some(format!("{id}{major}"))
+}
+
+/// `uname -m` reports the same CPU differently across systems.
+pub(crate) fn arch_fact(arch: &str) -> Option<String> {
+    let arch = tag_value(arch);
+    match arch.as_str() {
+        "" => None,
+        "aarch64" | "arm64" => Some("arm64".to_string()),
+        "x86_64" | "amd64" => Some("amd64".to_string()),
+        _ => Some(arch),
+    }
+}
+
+fn evaluate_rules(rules: &[TagRule], facts: &BTreeMap<String, String>) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for rule in rules {
+        let Some(value) = facts.get(rule.fact.trim()) else {
+            continue;
+        };
+        if let Some(expected) = rule.equals.as_deref() {
+            if !expected.trim().eq_ignore_ascii_case(value) {
+                continue;
+            }
+        }
+        let tag = rule.tag.trim().replace("{value}", value);
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+// True when the host's tags changed; the user may already have set a tag
+// the rules now assign.
+fn apply_tags(host_id: &str, entry: &mut HostFacts, tags: Vec<String>) -> Result<bool, String> {
+    let remove_tags: Vec<String> = entry
+        .auto_tags
+        .iter()
+        .filter(|tag| !tags.contains(tag))
+        .cloned()
+        .collect();
+    let add_tags: Vec<String> = tags
+        .iter()
+        .filter(|tag| !entry.auto_tags.contains(tag))
+        .cloned()
+        .collect();
+    if add_tags.is_empty() && remove_tags.is_empty() {
+        return Ok(false);
+    }
+    let changed = host_retag(host_id, &add_tags, &remove_tags)?;
+    entry.auto_tags = tags;
+    Ok(changed)
+}
+
+/// Merge freshly probed facts of a host and re-apply the tag rules to it.
+/// Returns the tags the rules assign to the host.
+pub(crate) fn record_facts<R: Runtime>(
+    app: &AppHandle<R>,
+    host_id: &str,
+    facts: BTreeMap<String, String>,
+) -> Result<Vec<String>, String> {
+    let _guard = facts_lock()
+        .lock()
+        .map_err(|_| "host facts lock poisoned".to_string())?;
+    let rules = load_rules()?;
+    let mut store = load_facts()?;
+    let entry = store.entry(host_id.to_string()).or_default();
+    entry
+        .facts
+        .extend(facts.into_iter().filter(|(_, value)| !value.is_empty()));
+    let tags = evaluate_rules(&rules, &entry.facts);
+    let retagged = apply_tags(host_id, entry, tags.clone())?;
+    save_facts(&store)?;
+    if retagged {
+        emit_hosts_changed(app, vec![host_id.to_string()]);
+    }
+    Ok(tags)
+}
+
+#[tauri::command]
+pub fn tag_rules_load() -> Result<Vec<TagRule>, String> {
+    load_rules()
+}
+
+/// Save the rules and re-apply them to every host with recorded facts, so
+/// edits take effect without probing again. Returns how many hosts changed.
+#[tauri::command]
+pub fn tag_rules_save<R: Runtime>(rules: Vec<TagRule>, app: AppHandle<R>) -> Result<usize, String> {
+    if rules
+        .iter()
+        .any(|rule| rule.fact.trim().is_empty() || rule.tag.trim().is_empty())
+    {
+        return Err("Every tag rule needs a fact and a tag".to_string());
+    }
+    let _guard = facts_lock()
+        .lock()
+        .map_err(|_| "host facts lock poisoned".to_string())?;
+    ensure_config_dir()?;
+    let content = serde_json::to_string_pretty(&rules).map_err(|e| e.to_string())?;
+    atomic_write(&get_tag_rules_path(), content.as_bytes())?;
+
+    let live_ids: Vec<String> = crate::host_store::hosts_load()?
+        .into_iter()
+        .filter(|host| !host.deleted)
+        .map(|host| host.id)
+        .collect();
+    let mut store = load_facts()?;
+    store.retain(|host_id, _| live_ids.contains(host_id));
+    let mut changed = 0;
+    let mut retagged = Vec::new();
+    for (host_id, entry) in store.iter_mut() {
+        let before = entry.auto_tags.clone();
+        let tags = evaluate_rules(&rules, &entry.facts);
+        if apply_tags(host_id, entry, tags)? {
+            retagged.push(host_id.clone());
+        }
+        if entry.auto_tags != before {
+            changed += 1;
+        }
+    }
+    save_facts(&store)?;
+    emit_hosts_changed(&app, retagged);
+    Ok(changed)
+}
+
+/// The last probed facts of a host (`os`, `arch`, `docker`...), for
+/// building rules in the UI.
+#[tauri::command]
+pub fn host_facts(host_id: String) -> Result<BTreeMap<String, String>, String> {
+    Ok(load_facts()?
+        .remove(host_id.trim())
+        .map(|entry| entry.facts)
+        .unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{arch_fact, default_rules, evaluate_rules, os_fact, TagRule};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn derives_os_and_arch_facts() {
+        assert_eq!(
+            os_fact(Some("ubuntu"), Some("22.04")).as_deref(),
+            Some("ubuntu22")
+        );
+        assert_eq!(
+            os_fact(Some("debian"), Some("12")).as_deref(),
+            Some("debian12")
+        );
+        assert_eq!(os_fact(Some("Darwin"), None).as_deref(), Some("darwin"));
+        assert_eq!(os_fact(Some(" "), Some("1")), None);
+        assert_eq!(arch_fact("aarch64").as_deref(), Some("arm64"));
+        assert_eq!(arch_fact("x86_64").as_deref(), Some("amd64"));
+        assert_eq!(arch_fact("armv7l").as_deref(), Some("armv7l"));
+    }
+
+    #[test]
+    fn evaluates_rules_against_facts() {
+        let facts: BTreeMap<String, String> =
+            [("os", "ubuntu22"), ("arch", "arm64"), ("docker", "true")]
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+        assert_eq!(
+            evaluate_rules(&default_rules(), &facts),
+            vec!["os=ubuntu22", "arch=arm64", "has-docker"]
+        );
+
+        let mut rules = default_rules();
+        rules.push(TagRule {
+            fact: "arch".to_string(),
+            equals: Some("AMD64".to_string()),
+            tag: "x86".to_string(),
+        });
+        let mut no_docker = facts.clone();
+        no_docker.insert("docker".to_string(), "false".to_string());
+        no_docker.insert("arch".to_string(), "amd64".to_string());
+        assert_eq!(
+            evaluate_rules(&rules, &no_docker),
+            vec!["os=ubuntu22", "arch=amd64", "x86"]
+        );
+    }
+}
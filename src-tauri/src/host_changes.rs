@@ -0,0 +1,275 @@
+use crate::models::Host;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
+
+pub(crate) const OP_UPSERT: &str = "upsert";
+pub(crate) const OP_DELETE: &str = "delete";
+
+/// A host that changed locally since some sequence number, reduced to its
+/// latest operation.
+#[derive(Debug, PartialEq)]
+pub(crate) struct PendingChange {
+    pub(crate) host_id: String,
+    pub(crate) op: String,
+    pub(crate) changed_at: String,
+}
+
+pub(crate) fn ensure_host_changes_schema(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS host_changes (
+          seq        INTEGER PRIMARY KEY AUTOINCREMENT,
+          host_id    TEXT NOT NULL,
+          op         TEXT NOT NULL,
+          changed_at TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS sync_state (
+          key   TEXT PRIMARY KEY,
+          value TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS host_tombstones (
+          host_id    TEXT PRIMARY KEY,
+          deleted_at TEXT NOT NULL
+        );
+        "#,
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Record a local change. Deleting a row also leaves a tombstone, so a full
+/// snapshot merge can tell a purged host from one the other side never had.
+pub(crate) fn log_host_change(conn: &Connection, host_id: &str, op: &str) -> Result<(), String> {
+    ensure_host_changes_schema(conn)?;
+    let changed_at = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+    conn.execute(
+        "INSERT INTO host_changes (host_id, op, changed_at) VALUES (?1, ?2, ?3)",
+        params![host_id, op, changed_at],
+    )
+    .map_err(|e| e.to_string())?;
+    if op == OP_DELETE {
+        record_host_tombstones(conn, &HashMap::from([(host_id.to_string(), changed_at)]))?;
+    }
+    Ok(())
+}
+
+/// Hosts deleted for good, by id, with when they were deleted.
+pub(crate) fn host_tombstones(conn: &Connection) -> Result<HashMap<String, String>, String> {
+    ensure_host_changes_schema(conn)?;
+    let mut stmt = conn
+        .prepare("SELECT host_id, deleted_at FROM host_tombstones")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?;
+    rows.map(|r| r.map_err(|e| e.to_string())).collect()
+}
+
+pub(crate) fn forget_host_tombstones<'a>(
+    conn: &Connection,
+    host_ids: impl IntoIterator<Item = &'a String>,
+) -> Result<(), String> {
+    ensure_host_changes_schema(conn)?;
+    for host_id in host_ids {
+        conn.execute(
+            "DELETE FROM host_tombstones WHERE host_id = ?1",
+            params![host_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Add tombstones (e.g. pulled from another device), keeping the later
+/// deletion time where both sides have one.
+pub(crate) fn record_host_tombstones(
+    conn: &Connection,
+    tombstones: &HashMap<String, String>,
+) -> Result<(), String> {
+    ensure_host_changes_schema(conn)?;
+    for (host_id, deleted_at) in tombstones {
+        conn.execute(
+            "INSERT INTO host_tombstones (host_id, deleted_at) VALUES (?1, ?2)
+             ON CONFLICT(host_id) DO UPDATE SET deleted_at = MAX(deleted_at, excluded.deleted_at)",
+            params![host_id, deleted_at],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+// What other devices see of a host. `hasPassword` and `hasTotp` only reflect
+// this device's keychain, so they never count as a change.
+fn sync_fingerprint(host: &Host) -> serde_json::Value {
+    let mut host = host.clone();
+    host.password = None;
+    host.has_password = false;
+    host.has_totp = false;
+    serde_json::to_value(host).unwrap_or_default()
+}
+
+/// Log the difference between the host table before and after a full
+/// rewrite, so saving the whole list only records the hosts that changed.
+pub(crate) fn log_host_list_changes(
+    conn: &Connection,
+    before: &[Host],
+    after: &[Host],
+) -> Result<(), String> {
+    let before: HashMap<&str, serde_json::Value> = before
+        .iter()
+        .map(|host| (host.id.as_str(), sync_fingerprint(host)))
+        .collect();
+    for host in after {
+        if before.get(host.id.as_str()) != Some(&sync_fingerprint(host)) {
+            log_host_change(conn, &host.id, OP_UPSERT)?;
+        }
+    }
+    for id in before.keys() {
+        if !after.iter().any(|host| host.id == *id) {
+            log_host_change(conn, id, OP_DELETE)?;
+        }
+    }
+    Ok(())
+}
+
+/// The last sequence number handed out, including pruned changes.
+pub(crate) fn latest_change_seq(conn: &Connection) -> Result<i64, String> {
+    ensure_host_changes_schema(conn)?;
+    conn.query_row(
+        "SELECT MAX(
+           COALESCE((SELECT MAX(seq) FROM host_changes), 0),
+           COALESCE((SELECT seq FROM sqlite_sequence WHERE name = 'host_changes'), 0)
+         )",
+        [],
+        |row| row.get(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Drop the changes up to `seq` once a pushed snapshot holds them.
+pub(crate) fn prune_changes_through(conn: &Connection, seq: i64) -> Result<(), String> {
+    ensure_host_changes_schema(conn)?;
+    conn.execute("DELETE FROM host_changes WHERE seq <= ?1", params![seq])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Hosts changed in `(after, up_to]`, one entry per host with its latest
+/// operation, in the order they were last touched.
+pub(crate) fn changes_between(
+    conn: &Connection,
+    after: i64,
+    up_to: i64,
+) -> Result<Vec<PendingChange>, String> {
+    ensure_host_changes_schema(conn)?;
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT c.host_id, c.op, c.changed_at
+            FROM host_changes c
+            WHERE c.seq = (
+              SELECT MAX(seq) FROM host_changes
+              WHERE host_id = c.host_id AND seq > ?1 AND seq <= ?2
+            )
+            ORDER BY c.seq
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![after, up_to], |row| {
+            Ok(PendingChange {
+                host_id: row.get(0)?,
+                op: row.get(1)?,
+                changed_at: row.get(2)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.map(|r| r.map_err(|e| e.to_string())).collect()
+}
+
+pub(crate) fn sync_state_get(conn: &Connection, key: &str) -> Result<Option<String>, String> {
+    ensure_host_changes_schema(conn)?;
+    conn.query_row(
+        "SELECT value FROM sync_state WHERE key = ?1",
+        params![key],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+pub(crate) fn sync_state_set(conn: &Connection, key: &str, value: &str) -> Result<(), String> {
+    ensure_host_changes_schema(conn)?;
+    conn.execute(
+        "INSERT INTO sync_state (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        params![key, value],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        changes_between, host_tombstones, latest_change_seq, log_host_list_changes,
+        prune_changes_through, OP_DELETE, OP_UPSERT,
+    };
+    use crate::mock_backends::test_host;
+    use crate::models::Host;
+    use rusqlite::Connection;
+
+    fn host(id: &str, user: &str) -> Host {
+        Host {
+            user: user.to_string(),
+            ..test_host(id)
+        }
+    }
+
+    #[test]
+    fn logs_only_changed_hosts_and_collapses_per_host() {
+        let conn = Connection::open_in_memory().unwrap();
+        let a = host("a", "root");
+        let b = host("b", "root");
+        log_host_list_changes(&conn, &[], &[a.clone(), b.clone()]).unwrap();
+        let after_import = latest_change_seq(&conn).unwrap();
+        assert_eq!(after_import, 2);
+
+        let mut b_with_password = b.clone();
+        b_with_password.has_password = true;
+        log_host_list_changes(
+            &conn,
+            &[a.clone(), b.clone()],
+            &[a.clone(), b_with_password],
+        )
+        .unwrap();
+        assert_eq!(latest_change_seq(&conn).unwrap(), after_import);
+
+        let a2 = host("a", "deploy");
+        log_host_list_changes(&conn, &[a.clone(), b.clone()], std::slice::from_ref(&a2)).unwrap();
+        log_host_list_changes(&conn, std::slice::from_ref(&a2), &[host("a", "admin")]).unwrap();
+
+        let pending =
+            changes_between(&conn, after_import, latest_change_seq(&conn).unwrap()).unwrap();
+        assert_eq!(
+            changes_between(&conn, after_import, after_import + 1)
+                .unwrap()
+                .len(),
+            1
+        );
+        let ops: Vec<(&str, &str)> = pending
+            .iter()
+            .map(|change| (change.host_id.as_str(), change.op.as_str()))
+            .collect();
+        assert_eq!(ops, vec![("b", OP_DELETE), ("a", OP_UPSERT)]);
+        let tombstones = host_tombstones(&conn).unwrap();
+        assert_eq!(tombstones.keys().collect::<Vec<_>>(), vec!["b"]);
+
+        // Pruned changes are gone, but their numbers are not handed out again.
+        let latest = latest_change_seq(&conn).unwrap();
+        prune_changes_through(&conn, latest).unwrap();
+        assert!(changes_between(&conn, 0, latest).unwrap().is_empty());
+        assert_eq!(latest_change_seq(&conn).unwrap(), latest);
+        log_host_list_changes(&conn, &[], &[host("c", "root")]).unwrap();
+        assert_eq!(latest_change_seq(&conn).unwrap(), latest + 1);
+    }
+}
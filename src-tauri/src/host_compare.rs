@@ -0,0 +1,401 @@
+use crate::exec_diff::pinned_exec_command;
+use crate::host_probe::run_probe;
+use crate::models::Host;
+use crate::operations;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::thread;
+use std::time::Duration;
+
+const HOSTS_COMPARE_TIMEOUT: Duration = Duration::from_secs(300);
+const SECTION_MARKER: &str = "@@";
+const UNAVAILABLE_MARKER: &str = "!unavailable";
+// Kernel counters and random values that differ between any two machines
+// (and between two reads on one machine).
+const VOLATILE_SYSCTL_PREFIXES: &[&str] = &[
+    "fs.dentry-state",
+    "fs.file-nr",
+    "fs.inode-",
+    "fs.quota.",
+    "kernel.ns_last_pid",
+    "kernel.pty.nr",
+    "kernel.random.",
+    "kernel.hostname",
+    "kernel.domainname",
+    "net.netfilter.nf_conntrack_count",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompareAspect {
+    /// Installed packages and their versions (dpkg, rpm or pacman).
+    Packages,
+    /// Enabled systemd services.
+    Services,
+    /// Kernel parameters from `sysctl -a`, without counters.
+    Sysctl,
+    /// Listening TCP/UDP ports and the addresses they are bound to.
+    Ports,
+}
+
+impl CompareAspect {
+    const ALL: [CompareAspect; 4] = [
+        CompareAspect::Packages,
+        CompareAspect::Services,
+        CompareAspect::Sysctl,
+        CompareAspect::Ports,
+    ];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            CompareAspect::Packages => "packages",
+            CompareAspect::Services => "services",
+            CompareAspect::Sysctl => "sysctl",
+            CompareAspect::Ports => "ports",
+        }
+    }
+
+    // Each prints `key<TAB>value` lines, or the unavailable marker when the
+    // host has no tool for it.
+    fn script(self) -> &'static str {
+        match self {
+            CompareAspect::Packages => {
+                r#"if command -v dpkg-query >/dev/null 2>&1; then
+  dpkg-query -W -f='${Package}\t${Version}\n'
+elif command -v rpm >/dev/null 2>&1; then
+  rpm -qa --qf '%{NAME}\t%{VERSION}-%{RELEASE}\n'
+elif command -v pacman >/dev/null 2>&1; then
+  pacman -Q | awk '{print $1 "\t" $2}'
+else
+  echo '!unavailable'
+fi"#
+            }
+            CompareAspect::Services => {
+                r#"if command -v systemctl >/dev/null 2>&1; then
+  systemctl list-unit-files --type=service --state=enabled --no-legend --no-pager 2>/dev/null | awk '{print $1 "\tenabled"}'
+else
+  echo '!unavailable'
+fi"#
+            }
+            CompareAspect::Sysctl => {
+                r#"if command -v sysctl >/dev/null 2>&1; then
+  sysctl -a 2>/dev/null | awk -F ' = ' 'NF == 2 {print $1 "\t" $2}'
+else
+  echo '!unavailable'
+fi"#
+            }
+            CompareAspect::Ports => {
+                r#"if command -v ss >/dev/null 2>&1; then
+  ss -H -tuln 2>/dev/null | awk '{print $1 "\t" $5}'
+elif command -v netstat >/dev/null 2>&1; then
+  netstat -tuln 2>/dev/null | awk '$1 ~ /^(tcp|udp)/ {print $1 "\t" $4}'
+else
+  echo '!unavailable'
+fi"#
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangedEntry {
+    pub key: String,
+    pub a: String,
+    pub b: String,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AspectDiff {
+    pub aspect: CompareAspect,
+    pub only_a: BTreeMap<String, String>,
+    pub only_b: BTreeMap<String, String>,
+    pub changed: Vec<ChangedEntry>,
+    /// Entries with the same value on both hosts.
+    pub same: usize,
+    /// Hosts (by id) without a tool for this aspect; nothing is compared then.
+    pub unavailable_on: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostCompareReport {
+    pub host_a_id: String,
+    pub host_b_id: String,
+    pub aspects: Vec<AspectDiff>,
+    pub compared_at: String,
+}
+
+/// `None` for an aspect the host cannot report.
+type Inventory = BTreeMap<CompareAspect, Option<BTreeMap<String, String>>>;
+
+fn inventory_script(aspects: &[CompareAspect]) -> String {
+    aspects
+        .iter()
+        .map(|aspect| {
+            format!(
+                "echo '{SECTION_MARKER}{}'\n{}\n",
+                aspect.as_str(),
+                aspect.script()
+            )
+        })
+        .collect()
+}
+
+fn is_volatile_sysctl(key: &str) -> bool {
+    VOLATILE_SYSCTL_PREFIXES
+        .iter()
+        .any(|prefix| key.starts_with(prefix))
+}
+
+// `0.0.0.0:22`, `[::]:22`, `127.0.0.53%lo:53` and `*:68` are keyed by
+// protocol and port; the bind addresses are the value, so a service moving
+// from localhost to all interfaces shows up as a change.
+fn port_entry(proto: &str, local: &str) -> Option<(String, String)> {
+    let (address, port) = local.rsplit_once(':')?;
+    port.parse::<u16>().ok()?;
+    let proto = proto.trim_end_matches('6');
+    Some((format!("{proto}/{port}"), address.to_string()))
+}
+
+fn parse_section(aspect: CompareAspect, lines: &[&str]) -> Option<BTreeMap<String, String>> {
+    if lines.first().map(|line| line.trim()) == Some(UNAVAILABLE_MARKER) {
+        return None;
+    }
+    let mut entries: BTreeMap<String, String> = BTreeMap::new();
+    let mut addresses: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for line in lines {
+        let Some((key, value)) = line.split_once('\t') else {
+            continue;
+        };
+        let (key, value) = (key.trim(), value.trim());
+        if key.is_empty() {
+            continue;
+        }
+        match aspect {
+            CompareAspect::Ports => {
+                if let Some((key, address)) = port_entry(key, value) {
+                    addresses.entry(key).or_default().insert(address);
+                }
+            }
+            CompareAspect::Sysctl if is_volatile_sysctl(key) => {}
+            _ => {
+                entries.insert(key.to_string(), value.to_string());
+            }
+        }
+    }
+    for (key, bound) in addresses {
+        entries.insert(key, bound.into_iter().collect::<Vec<_>>().join(" "));
+    }
+    Some(entries)
+}
+
+fn parse_inventory(stdout: &str) -> Inventory {
+    let mut inventory = Inventory::new();
+    let mut current: Option<(CompareAspect, Vec<&str>)> = None;
+    for line in stdout.lines() {
+        if let Some(name) = line.strip_prefix(SECTION_MARKER) {
+            if let Some((aspect, lines)) = current.take() {
+                inventory.insert(aspect, parse_section(aspect, &lines));
+            }
+            current = CompareAspect::ALL
+                .into_iter()
+                .find(|aspect| aspect.as_str() == name.trim())
+                .map(|aspect| (aspect, Vec::new()));
+        } else if let Some((_, lines)) = current.as_mut() {
+            lines.push(line);
+        }
+    }
+    if let Some((aspect, lines)) = current {
+        inventory.insert(aspect, parse_section(aspect, &lines));
+    }
+    inventory
+}
+
+fn diff_aspect(
+    aspect: CompareAspect,
+    a: (&str, Option<&BTreeMap<String, String>>),
+    b: (&str, Option<&BTreeMap<String, String>>),
+) -> AspectDiff {
+    let mut diff = AspectDiff {
+        aspect,
+        only_a: BTreeMap::new(),
+        only_b: BTreeMap::new(),
+        changed: Vec::new(),
+        same: 0,
+        unavailable_on: Vec::new(),
+    };
+    let (Some(entries_a), Some(entries_b)) = (a.1, b.1) else {
+        for (host_id, entries) in [a, b] {
+            if entries.is_none() {
+                diff.unavailable_on.push(host_id.to_string());
+            }
+        }
+        return diff;
+    };
+    for (key, value_a) in entries_a {
+        match entries_b.get(key) {
+            Some(value_b) if value_b == value_a => diff.same += 1,
+            Some(value_b) => diff.changed.push(ChangedEntry {
+                key: key.clone(),
+                a: value_a.clone(),
+                b: value_b.clone(),
+            }),
+            None => {
+                diff.only_a.insert(key.clone(), value_a.clone());
+            }
+        }
+    }
+    for (key, value_b) in entries_b {
+        if !entries_a.contains_key(key) {
+            diff.only_b.insert(key.clone(), value_b.clone());
+        }
+    }
+    diff
+}
+
+fn collect_both(
+    host_a: &Host,
+    host_b: &Host,
+    script: &str,
+) -> (Result<String, String>, Result<String, String>) {
+    let op = operations::current();
+    thread::scope(|scope| {
+        let op = op.as_ref();
+        let a = scope.spawn(move || operations::enter(op, || run_probe(host_a, script)));
+        let b = scope.spawn(move || operations::enter(op, || run_probe(host_b, script)));
+        let joined = |handle: thread::ScopedJoinHandle<'_, Result<String, String>>| {
+            handle
+                .join()
+                .unwrap_or_else(|_| Err("inventory collection panicked".to_string()))
+        };
+        (joined(a), joined(b))
+    })
+}
+
+/// Collect `aspects` (all of them by default) from both hosts and report what
+/// differs: entries only one host has and entries whose values disagree.
+#[tauri::command]
+pub async fn hosts_compare(
+    host_a: Host,
+    host_b: Host,
+    aspects: Option<Vec<CompareAspect>>,
+    op_id: String,
+) -> Result<HostCompareReport, String> {
+    let mut aspects = aspects
+        .filter(|aspects| !aspects.is_empty())
+        .unwrap_or_else(|| CompareAspect::ALL.to_vec());
+    aspects.sort();
+    aspects.dedup();
+    tauri::async_runtime::spawn_blocking(move || {
+        operations::run(op_id, "hosts_compare", HOSTS_COMPARE_TIMEOUT, || {
+            let script = pinned_exec_command(&inventory_script(&aspects));
+            let (stdout_a, stdout_b) = collect_both(&host_a, &host_b, &script);
+            let name = |host: &Host| {
+                if host.name.trim().is_empty() {
+                    host.hostname.trim().to_string()
+                } else {
+                    host.name.trim().to_string()
+                }
+            };
+            let inventory_a =
+                parse_inventory(&stdout_a.map_err(|e| format!("{}: {e}", name(&host_a)))?);
+            let inventory_b =
+                parse_inventory(&stdout_b.map_err(|e| format!("{}: {e}", name(&host_b)))?);
+            let diffs = aspects
+                .iter()
+                .map(|aspect| {
+                    diff_aspect(
+                        *aspect,
+                        (&host_a.id, inventory_a.get(aspect).and_then(Option::as_ref)),
+                        (&host_b.id, inventory_b.get(aspect).and_then(Option::as_ref)),
+                    )
+                })
+                .collect();
+            Ok(HostCompareReport {
+                host_a_id: host_a.id.clone(),
+                host_b_id: host_b.id.clone(),
+                aspects: diffs,
+                compared_at: chrono::Utc::now()
+                    .to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            })
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff_aspect, inventory_script, parse_inventory, CompareAspect};
+
+    #[test]
+    fn diffs_inventories_per_aspect() {
+        let staging = "@@packages\n\
+                       nginx\t1.24.0-1\n\
+                       openssl\t3.0.11-1\n\
+                       redis\t7.0.15-1\n\
+                       @@sysctl\n\
+                       vm.swappiness\t10\n\
+                       fs.file-nr\t1024\t0\t9223372036854775807\n\
+                       kernel.random.boot_id\tabc\n\
+                       @@ports\n\
+                       tcp\t0.0.0.0:443\n\
+                       tcp\t[::]:443\n\
+                       tcp\t127.0.0.1:6379\n\
+                       @@services\n\
+                       !unavailable\n";
+        let prod = "@@packages\n\
+                    nginx\t1.24.0-1\n\
+                    openssl\t3.0.13-1\n\
+                    @@sysctl\n\
+                    vm.swappiness\t60\n\
+                    kernel.random.boot_id\tdef\n\
+                    @@ports\n\
+                    tcp6\t[::]:443\n\
+                    tcp\t0.0.0.0:443\n\
+                    tcp\t0.0.0.0:6379\n\
+                    @@services\n\
+                    nginx.service\tenabled\n";
+        let staging = parse_inventory(staging);
+        let prod = parse_inventory(prod);
+        let diff = |aspect| {
+            diff_aspect(
+                aspect,
+                ("s", staging[&aspect].as_ref()),
+                ("p", prod[&aspect].as_ref()),
+            )
+        };
+
+        let packages = diff(CompareAspect::Packages);
+        assert_eq!(packages.same, 1);
+        assert_eq!(packages.only_a.keys().collect::<Vec<_>>(), vec!["redis"]);
+        assert_eq!(packages.changed[0].key, "openssl");
+        assert_eq!(
+            (
+                packages.changed[0].a.as_str(),
+                packages.changed[0].b.as_str()
+            ),
+            ("3.0.11-1", "3.0.13-1")
+        );
+
+        let sysctl = diff(CompareAspect::Sysctl);
+        assert_eq!(sysctl.changed.len(), 1);
+        assert_eq!(sysctl.changed[0].key, "vm.swappiness");
+        assert!(sysctl.only_a.is_empty());
+
+        let ports = diff(CompareAspect::Ports);
+        assert_eq!(ports.same, 1);
+        assert_eq!(ports.changed[0].key, "tcp/6379");
+        assert_eq!(ports.changed[0].a, "127.0.0.1");
+
+        let services = diff(CompareAspect::Services);
+        assert_eq!(services.unavailable_on, vec!["s"]);
+        assert!(services.only_b.is_empty());
+
+        let script = inventory_script(&[CompareAspect::Ports]);
+        assert!(script.starts_with("echo '@@ports'\n"));
+        assert!(!script.contains("@@packages"));
+    }
+}
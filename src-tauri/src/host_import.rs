@@ -0,0 +1,270 @@
+use crate::host_store::{hosts_load, hosts_save};
+use crate::models::Host;
+use serde::Serialize;
+use std::process::{Command, Stdio};
+
+/// A host offered by an external inventory (Tailscale, a cloud API...).
+/// `source` identifies it there and is stored as the host's `importSource`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ImportCandidate {
+    pub source: String,
+    pub name: String,
+    pub alias: String,
+    pub hostname: String,
+    pub port: u16,
+    pub identity_file: Option<String>,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostImportReport {
+    pub added: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+}
+
+/// Run the first of `binaries` that exists with `args` and return its stdout.
+/// GUI apps on macOS do not inherit the shell's PATH, so callers list
+/// absolute install locations before the bare command name.
+pub(crate) fn run_inventory_cli(binaries: &[&str], args: &[&str]) -> Result<Vec<u8>, String> {
+    let mut last_error = format!("{} not found", binaries.last().copied().unwrap_or("CLI"));
+    for binary in binaries {
+        let output = match Command::new(binary)
+            .args(args)
+            .stdin(Stdio::null())
+            .output()
+        {
+            Ok(output) => output,
+            Err(_) => continue,
+        };
+        if output.status.success() {
+            return Ok(output.stdout);
+        }
+        last_error = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        if last_error.is_empty() {
+            last_error = format!("{binary} exited with {}", output.status);
+        }
+    }
+    Err(last_error)
+}
+
+fn now_updated_at() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+// Instance and node names are free text ("Jane's MacBook Pro"); aliases end
+// up in ssh_config and may not contain whitespace.
+pub(crate) fn alias_from_name(name: &str, fallback: &str) -> String {
+    let mut alias = String::new();
+    for ch in name.chars() {
+        if ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' || ch == '.' {
+            alias.push(ch.to_ascii_lowercase());
+        } else if !alias.ends_with('-') {
+            alias.push('-');
+        }
+    }
+    let alias = alias.trim_matches('-').to_string();
+    if alias.is_empty() {
+        fallback.to_string()
+    } else {
+        alias
+    }
+}
+
+/// `base`, or `base-2`, `base-3`... if another host already uses it.
+fn unique_alias(hosts: &[Host], base: &str) -> String {
+    let mut alias = base.to_string();
+    let mut suffix = 2;
+    while hosts.iter().any(|host| host.alias == alias) {
+        alias = format!("{base}-{suffix}");
+        suffix += 1;
+    }
+    alias
+}
+
+/// A new host with the app's defaults for everything the caller does not set.
+pub(crate) fn new_host(
+    hosts: &[Host],
+    name: String,
+    alias: &str,
+    hostname: String,
+    user: String,
+    port: u16,
+) -> Host {
+    Host {
+        id: uuid::Uuid::new_v4().to_string(),
+        sort_order: Some(hosts.len() as i64),
+        name,
+        alias: unique_alias(hosts, alias),
+        hostname,
+        user,
+        port,
+        password: None,
+        has_password: false,
+        has_totp: false,
+        host_insights_enabled: true,
+        host_live_metrics_enabled: true,
+        identity_file: None,
+        proxy_jump: None,
+        jump_host_ids: Vec::new(),
+        proxy_command: None,
+        env_vars: None,
+        encoding: None,
+        startup_commands: Vec::new(),
+        startup_commands_delay_ms: None,
+        probe_shell: None,
+        sync_excluded: false,
+        import_source: None,
+        mac_address: None,
+        max_session_minutes: None,
+        live_probe_lite: false,
+        extended_probe: false,
+        prompt_rules: Vec::new(),
+        forward_agent: None,
+        compression: None,
+        server_alive_count_max: None,
+        connect_timeout: None,
+        local_forwards: Vec::new(),
+        remote_forwards: Vec::new(),
+        request_tty: None,
+        host_key_algorithms: None,
+        extra_config: None,
+        bell_action: None,
+        keepalive_idle_minutes: None,
+        keepalive_string: None,
+        tags: Vec::new(),
+        notes: String::new(),
+        updated_at: now_updated_at(),
+        deleted: false,
+    }
+}
+
+/// Add candidates not seen before and refresh the address of hosts linked to
+/// them by `importSource`. Names, users and other edits made in the app are
+/// kept; the inventory's tags are only ever added.
+fn merge_candidates(
+    hosts: &mut Vec<Host>,
+    candidates: Vec<ImportCandidate>,
+    user: &str,
+    updated_at: &str,
+) -> HostImportReport {
+    let mut report = HostImportReport::default();
+    for candidate in candidates {
+        let linked = hosts.iter_mut().find(|host| {
+            !host.deleted && host.import_source.as_deref() == Some(candidate.source.as_str())
+        });
+        match linked {
+            Some(host) => {
+                let mut changed = false;
+                if host.hostname != candidate.hostname {
+                    host.hostname = candidate.hostname;
+                    changed = true;
+                }
+                for tag in candidate.tags {
+                    if !host.tags.contains(&tag) {
+                        host.tags.push(tag);
+                        changed = true;
+                    }
+                }
+                if changed {
+                    host.updated_at = updated_at.to_string();
+                    report.updated += 1;
+                } else {
+                    report.unchanged += 1;
+                }
+            }
+            None => {
+                let mut host = new_host(
+                    hosts,
+                    candidate.name,
+                    &candidate.alias,
+                    candidate.hostname,
+                    user.to_string(),
+                    candidate.port,
+                );
+                host.identity_file = candidate.identity_file;
+                host.tags = candidate.tags;
+                host.import_source = Some(candidate.source);
+                host.updated_at = updated_at.to_string();
+                hosts.push(host);
+                report.added += 1;
+            }
+        }
+    }
+    report
+}
+
+pub(crate) fn import_candidates(
+    candidates: Vec<ImportCandidate>,
+    user: Option<String>,
+) -> Result<HostImportReport, String> {
+    let mut hosts = hosts_load()?;
+    let user = user.unwrap_or_default().trim().to_string();
+    let report = merge_candidates(&mut hosts, candidates, &user, &now_updated_at());
+    if report.added + report.updated > 0 {
+        hosts_save(hosts)?;
+    }
+    Ok(report)
+}
+
+/// Id of the live host linked to `source`, if any.
+pub(crate) fn linked_host_id(hosts: &[Host], source: &str) -> Option<String> {
+    hosts
+        .iter()
+        .find(|host| !host.deleted && host.import_source.as_deref() == Some(source))
+        .map(|host| host.id.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{merge_candidates, ImportCandidate};
+
+    fn candidate(source: &str, hostname: &str, tags: &[&str]) -> ImportCandidate {
+        ImportCandidate {
+            source: source.to_string(),
+            name: "web".to_string(),
+            alias: "web".to_string(),
+            hostname: hostname.to_string(),
+            port: 22,
+            identity_file: None,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn reimport_updates_linked_hosts_instead_of_duplicating() {
+        let mut hosts = Vec::new();
+        let report = merge_candidates(
+            &mut hosts,
+            vec![
+                candidate("ts:1", "100.64.0.1", &["prod"]),
+                candidate("ts:2", "100.64.0.2", &[]),
+            ],
+            "deploy",
+            "2026-01-01T00:00:00.000Z",
+        );
+        assert_eq!((report.added, report.updated), (2, 0));
+        assert_eq!(hosts[0].alias, "web");
+        assert_eq!(hosts[1].alias, "web-2");
+        assert_eq!(hosts[1].user, "deploy");
+
+        hosts[0].name = "Renamed".to_string();
+        let report = merge_candidates(
+            &mut hosts,
+            vec![
+                candidate("ts:1", "100.64.0.9", &["prod", "arm"]),
+                candidate("ts:2", "100.64.0.2", &[]),
+            ],
+            "",
+            "2026-01-02T00:00:00.000Z",
+        );
+        assert_eq!((report.added, report.updated, report.unchanged), (0, 1, 1));
+        assert_eq!(hosts.len(), 2);
+        assert_eq!(hosts[0].hostname, "100.64.0.9");
+        assert_eq!(hosts[0].name, "Renamed");
+        assert_eq!(hosts[0].tags, vec!["prod", "arm"]);
+        assert_eq!(hosts[0].updated_at, "2026-01-02T00:00:00.000Z");
+        assert_eq!(hosts[1].updated_at, "2026-01-01T00:00:00.000Z");
+    }
+}
@@ -1,18 +1,30 @@
+use crate::alerts::{self, Observation};
 use crate::credential_store::keychain_get_password;
+use crate::fact_tags;
+use crate::host_rename::{emit_rename_suggestion, REMOTE_NAME_FACT};
 use crate::models::Host;
-use crate::ssh_config::{ensure_ssh_config, get_ssh_config_path};
+use crate::operations::{self, OutputTruncation};
+use crate::probe_cache::ProbeCache;
+use crate::ssh_config::{
+    control_persist_secs, ensure_ssh_config, get_ssh_config_path, get_ssh_control_path,
+    ssh_multiplex_args,
+};
+use crate::ssh_prompt::{PromptRelay, CHALLENGE_NEEDLES};
+use base64::Engine;
 use serde::Serialize;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs::{self, OpenOptions};
 use std::io::{ErrorKind, Write};
 use std::path::PathBuf;
-use std::process::{Command, Stdio};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::process::{Command, Output, Stdio};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Runtime};
 
 #[cfg(unix)]
 use std::os::unix::fs::OpenOptionsExt;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct HostStaticInfo {
     pub system_name: Option<String>,
@@ -21,6 +33,12 @@ pub struct HostStaticInfo {
     pub cpu_model: Option<String>,
     pub cpu_cores: Option<u32>,
     pub mem_total_kb: Option<u64>,
+    pub os_id: Option<String>,
+    pub os_version: Option<String>,
+    /// What the machine calls itself (`hostname`).
+    pub hostname: Option<String>,
+    /// Tags the fact tag rules assign to the host after this probe.
+    pub fact_tags: Vec<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -32,7 +50,7 @@ pub struct HostLiveProcess {
     pub mem_percent: f64,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Default, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct HostLiveInfo {
     pub cpu_percent: Option<f64>,
@@ -46,15 +64,185 @@ pub struct HostLiveInfo {
     pub mem_used_kb: Option<u64>,
     pub mem_free_kb: Option<u64>,
     pub mem_page_cache_kb: Option<u64>,
+    pub swap_total_kb: Option<u64>,
+    pub swap_used_kb: Option<u64>,
     pub load_1: Option<f64>,
     pub load_5: Option<f64>,
     pub load_15: Option<f64>,
     pub disk_root_total_kb: Option<u64>,
     pub disk_root_used_kb: Option<u64>,
+    /// Every real (disk-backed or network) mount, by mount point.
+    pub filesystems: Vec<HostFilesystem>,
+    /// Network interfaces other than loopback, by name.
+    pub network: Vec<HostNetInterface>,
+    /// Only collected for hosts with `extended_probe` set.
+    pub temperatures: Vec<HostThermalZone>,
+    pub gpus: Vec<HostGpu>,
     pub processes: Vec<HostLiveProcess>,
+    /// Set on single-board computers (hosts with a device-tree model).
+    pub sbc: Option<HostSbcInfo>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HostFilesystem {
+    pub mount_point: String,
+    pub device: String,
+    /// Not reported by every `df` (busybox, BSD).
+    pub fs_type: Option<String>,
+    pub total_kb: u64,
+    pub used_kb: u64,
+    pub available_kb: u64,
+    /// `None` where the filesystem has no fixed inode table (btrfs, ZFS).
+    pub inodes_total: Option<u64>,
+    pub inodes_used: Option<u64>,
+}
+
+/// Byte counters of a network interface since boot, and its throughput over
+/// the probe's sampling window.
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HostNetInterface {
+    pub name: String,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    /// `None` when the interface appeared during the window or its counters
+    /// wrapped.
+    pub rx_bytes_per_sec: Option<f64>,
+    pub tx_bytes_per_sec: Option<f64>,
+}
+
+/// A kernel thermal zone (`/sys/class/thermal`), e.g. `x86_pkg_temp`.
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HostThermalZone {
+    pub name: String,
+    pub temp_c: f64,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HostGpu {
+    /// `nvidia` or `amd`.
+    pub vendor: String,
+    pub index: u32,
+    pub name: Option<String>,
+    pub utilization_percent: Option<f64>,
+    pub mem_used_kb: Option<u64>,
+    pub mem_total_kb: Option<u64>,
+    pub temp_c: Option<f64>,
+}
+
+/// Board health of a Raspberry Pi or similar ARM board. The throttling
+/// flags come from `vcgencmd get_throttled` and are Raspberry Pi only.
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HostSbcInfo {
+    pub model: String,
+    pub soc_temp_c: Option<f64>,
+    /// Raw `get_throttled` bits, e.g. `0x50005`.
+    pub throttled_raw: Option<String>,
+    pub under_voltage_now: bool,
+    pub freq_capped_now: bool,
+    pub throttled_now: bool,
+    pub soft_temp_limit_now: bool,
+    /// The same conditions at any time since boot.
+    pub under_voltage_occurred: bool,
+    pub freq_capped_occurred: bool,
+    pub throttled_occurred: bool,
+    pub soft_temp_limit_occurred: bool,
+}
+
+// A probe that takes longer than this is stuck on the network or a hung
+// mount; live probes are polled, so they give up sooner.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(60);
+const LIVE_PROBE_TIMEOUT: Duration = Duration::from_secs(20);
+const DEFAULT_FLEET_PROBE_CONCURRENCY: usize = 8;
+const MAX_FLEET_PROBE_CONCURRENCY: usize = 32;
+
+// Tried in order when a host has no probe shell override. Minimal images and
+// appliances often ship only busybox/ash, so `sh` alone is not enough;
+// PowerShell comes last, for Windows servers.
+const PROBE_SHELL_CANDIDATES: &[&str] = &[
+    "sh",
+    "/bin/sh",
+    "busybox sh",
+    "ash",
+    "bash",
+    POWERSHELL_PROBE_SHELL,
+];
+const POWERSHELL_PROBE_SHELL: &str = "powershell";
+const PROBE_SHELL_MARKER: &str = "__xtermius_probe_shell_ok__";
+
+/// What the probe layer has learned about a host while talking to it.
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostCapabilities {
+    pub probe_shell: Option<String>,
+    pub probe_shell_overridden: bool,
+    pub privileged_probes: Option<PrivilegedProbeAccess>,
+    pub runtimes: Option<HostRuntimes>,
+}
+
+/// How the probe user may run a tool that usually needs elevated rights.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProbeAccess {
+    /// Not installed on the host.
+    #[default]
+    Missing,
+    /// Usable as the probe user (root, docker socket access, journal group).
+    Direct,
+    /// Allowed through `sudo -n` per the host's sudoers.
+    Sudo,
+    /// Installed but neither direct nor passwordless sudo access works.
+    Denied,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrivilegedProbeAccess {
+    pub smartctl: ProbeAccess,
+    pub docker: ProbeAccess,
+    pub journalctl: ProbeAccess,
+}
+
+/// Versions of the runtimes and tools that features like directory sync or
+/// shell integration build on; `None` when the tool is not installed.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostRuntimes {
+    pub python3: Option<String>,
+    pub node: Option<String>,
+    pub git: Option<String>,
+    pub rsync: Option<String>,
+    pub tmux: Option<String>,
+    /// Names of the tools above that are missing.
+    pub missing: Vec<String>,
+}
+
+fn host_capabilities_cache() -> &'static Mutex<HashMap<String, HostCapabilities>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, HostCapabilities>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
 }
 
-fn shell_quote(input: &str) -> String {
+fn record_probe_shell(host_id: &str, shell: &str, overridden: bool) {
+    if let Ok(mut cache) = host_capabilities_cache().lock() {
+        let entry = cache.entry(host_id.to_string()).or_default();
+        entry.probe_shell = Some(shell.to_string());
+        entry.probe_shell_overridden = overridden;
+    }
+}
+
+fn cached_probe_shell(host_id: &str) -> Option<String> {
+    let cache = host_capabilities_cache().lock().ok()?;
+    cache
+        .get(host_id)
+        .filter(|caps| !caps.probe_shell_overridden)
+        .and_then(|caps| caps.probe_shell.clone())
+}
+
+pub(crate) fn shell_quote(input: &str) -> String {
     format!("'{}'", input.replace('\'', "'\\''"))
 }
 
@@ -119,15 +307,34 @@ impl Drop for AskpassScript {
     }
 }
 
-fn create_askpass_script(password: &str) -> Result<AskpassScript, String> {
-    let script = format!("#!/bin/sh\nprintf '%s\\n' {}\n", shell_quote(password));
+/// Answers password prompts with `password` and hands any other prompt (an
+/// OTP challenge, a key passphrase) to the UI through `relay`, whose private
+/// directory also holds the script.
+fn create_askpass_script(password: &str, relay: &PromptRelay) -> Result<AskpassScript, String> {
+    let challenges: Vec<String> = CHALLENGE_NEEDLES
+        .iter()
+        .map(|needle| format!("*{}*", shell_quote(needle)))
+        .collect();
+    let script = format!(
+        "#!/bin/sh\n\
+         case \"$(printf '%s' \"$1\" | tr '[:upper:]' '[:lower:]')\" in\n\
+         {}) ;;\n\
+         *password*) printf '%s\\n' {}; exit 0 ;;\n\
+         esac\n\
+         {}",
+        challenges.join("|"),
+        shell_quote(password),
+        relay.script_lines()
+    );
 
     for attempt in 0..16 {
         let nonce = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .map(|d| d.as_nanos())
             .unwrap_or(0);
-        let path = std::env::temp_dir().join(format!("xtermius-askpass-{nonce}-{attempt}.sh"));
+        let path = relay
+            .dir()
+            .join(format!("xtermius-askpass-{nonce}-{attempt}.sh"));
         let mut options = OpenOptions::new();
         options.write(true).create_new(true);
         #[cfg(unix)]
@@ -151,14 +358,36 @@ fn create_askpass_script(password: &str) -> Result<AskpassScript, String> {
 
 #[cfg(test)]
 mod tests {
-    use super::{create_askpass_script, probe_ssh_args};
+    use super::{
+        create_askpass_script, host_probe_live_lite_impl, is_powershell, parse_docker,
+        parse_filesystems, parse_gpus, parse_kv, parse_live_lite, parse_network, parse_oom_events,
+        parse_privileged_probe_access, parse_reboot_status, parse_runtimes, parse_sbc_info,
+        parse_services, parse_thermal_zones, probe_remote_command, probe_shell_override,
+        probe_ssh_args, validate_unit_pattern, windows_probe_script, ProbeAccess,
+        WINDOWS_LIVE_PROBE_SCRIPT, WINDOWS_STATIC_PROBE_SCRIPT,
+    };
+    use crate::mock_backends::{mock_ssh, test_host};
     use crate::models::Host;
+    use crate::ssh_prompt::PromptRelay;
 
     #[test]
     fn askpass_script_is_removed_when_guard_drops() {
-        let script = create_askpass_script("secret").unwrap();
+        let relay = PromptRelay::start("1").unwrap();
+        let script = create_askpass_script("secret", &relay).unwrap();
         let path = script.path().to_path_buf();
         assert!(path.exists());
+        let ask = |prompt: &str| {
+            let output = std::process::Command::new("sh")
+                .arg(&path)
+                .arg(prompt)
+                .output()
+                .unwrap();
+            String::from_utf8_lossy(&output.stdout).to_string()
+        };
+        assert_eq!(ask("(root@prod) Password: "), "secret\n");
+        // A challenge waits for the answer the UI leaves in the relay.
+        std::fs::write(relay.dir().join("answer"), "424242\n").unwrap();
+        assert_eq!(ask("Verification code: "), "424242\n");
         drop(script);
         assert!(!path.exists());
     }
@@ -175,12 +404,37 @@ mod tests {
             port: 2222,
             password: None,
             has_password: false,
+            has_totp: false,
             host_insights_enabled: true,
             host_live_metrics_enabled: true,
             identity_file: Some("/tmp/key".to_string()),
             proxy_jump: Some("jump".to_string()),
+            jump_host_ids: Vec::new(),
+            proxy_command: None,
             env_vars: None,
             encoding: Some("utf-8".to_string()),
+            startup_commands: Vec::new(),
+            startup_commands_delay_ms: None,
+            probe_shell: None,
+            sync_excluded: false,
+            import_source: None,
+            mac_address: None,
+            max_session_minutes: None,
+            live_probe_lite: false,
+            extended_probe: false,
+            prompt_rules: Vec::new(),
+            forward_agent: None,
+            compression: None,
+            server_alive_count_max: None,
+            connect_timeout: None,
+            local_forwards: Vec::new(),
+            remote_forwards: Vec::new(),
+            request_tty: None,
+            host_key_algorithms: None,
+            extra_config: None,
+            bell_action: None,
+            keepalive_idle_minutes: None,
+            keepalive_string: None,
             tags: vec![],
             notes: "".to_string(),
             updated_at: "2026-01-01T00:00:00Z".to_string(),
@@ -189,7 +443,8 @@ mod tests {
         let args = probe_ssh_args(
             &host,
             "/tmp/xtermius/ssh_config",
-            "/tmp/xtermius/probe_mux_%C",
+            "/tmp/xtermius/mux_%C",
+            30,
             false,
         );
         assert!(args
@@ -203,16 +458,384 @@ mod tests {
         assert!(args.iter().any(|arg| arg == "ControlPersist=30s"));
         assert!(args
             .iter()
-            .any(|arg| arg == "ControlPath=/tmp/xtermius/probe_mux_%C"));
+            .any(|arg| arg == "ControlPath=/tmp/xtermius/mux_%C"));
         assert!(args.iter().any(|arg| arg == "BatchMode=yes"));
         assert_eq!(args.last().map(String::as_str), Some("prod-box"));
     }
+
+    #[test]
+    fn probe_script_is_passed_as_one_quoted_argument() {
+        assert_eq!(
+            probe_remote_command("busybox sh", "set -eu\necho 'ok'"),
+            "busybox sh -lc 'set -eu\necho '\\''ok'\\'''"
+        );
+    }
+
+    #[test]
+    fn powershell_probes_are_sent_encoded() {
+        use base64::Engine;
+        assert!(is_powershell("powershell"));
+        assert!(is_powershell(
+            r"C:\Windows\System32\WindowsPowerShell\v1.0\PowerShell.exe"
+        ));
+        assert!(is_powershell("/usr/bin/pwsh"));
+        assert!(!is_powershell("bash"));
+
+        let command = probe_remote_command("powershell", "'ok'");
+        let encoded = command
+            .strip_prefix("powershell -NoProfile -NonInteractive -EncodedCommand ")
+            .unwrap();
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .unwrap();
+        let utf16: Vec<u16> = bytes
+            .chunks(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        assert_eq!(String::from_utf16(&utf16).unwrap(), "'ok'");
+
+        // OpenSSH for Windows runs commands through cmd.exe, whose command
+        // line stops at 8191 characters.
+        for script in [WINDOWS_STATIC_PROBE_SCRIPT, WINDOWS_LIVE_PROBE_SCRIPT] {
+            let command = probe_remote_command("powershell", &windows_probe_script(script));
+            assert!(command.len() < 8191, "{} characters", command.len());
+        }
+    }
+
+    #[test]
+    fn parses_windows_live_probe_output() {
+        let stdout = "\
+net_window_s=0.2154
+net_start=Intel[R] Ethernet Connection I219-LM 1000 2000
+net_end=Intel[R] Ethernet Connection I219-LM 1431 2000
+cpu_percent=7
+fs=C: NTFS 248717308 120000000 128717308 - C:\\
+fs=D: ReFS 1000 10 990 - D:\\
+";
+        let network = parse_network(stdout, Some(0.2154));
+        assert_eq!(network[0].name, "Intel[R] Ethernet Connection I219-LM");
+        assert_eq!(network[0].rx_bytes_per_sec.map(f64::round), Some(2001.0));
+        let filesystems = parse_filesystems(stdout);
+        assert_eq!(filesystems[0].mount_point, "C:\\");
+        assert_eq!(filesystems[0].fs_type.as_deref(), Some("NTFS"));
+        assert_eq!(filesystems[1].used_kb, 10);
+        // No tick counters: the lite parser takes the sampled load.
+        assert_eq!(parse_live_lite("win", stdout).cpu_percent, Some(7.0));
+    }
+
+    #[test]
+    fn parses_privileged_probe_access() {
+        let access =
+            parse_privileged_probe_access("smartctl=sudo\ndocker=denied\njournalctl=direct\n");
+        assert_eq!(access.smartctl, ProbeAccess::Sudo);
+        assert_eq!(access.docker, ProbeAccess::Denied);
+        assert_eq!(access.journalctl, ProbeAccess::Direct);
+        assert_eq!(
+            parse_privileged_probe_access("docker=weird").docker,
+            ProbeAccess::Missing
+        );
+    }
+
+    #[test]
+    fn parses_runtime_versions() {
+        let runtimes = parse_runtimes(
+            "python3=Python 3.11.2\nnode=v20.11.1\ngit=git version 2.39.2\n\
+             rsync=rsync  version 3.2.7  protocol version 31\ntmux=\n",
+        );
+        assert_eq!(runtimes.python3.as_deref(), Some("3.11.2"));
+        assert_eq!(runtimes.node.as_deref(), Some("20.11.1"));
+        assert_eq!(runtimes.git.as_deref(), Some("2.39.2"));
+        assert_eq!(runtimes.rsync.as_deref(), Some("3.2.7"));
+        assert_eq!(runtimes.tmux, None);
+        assert_eq!(runtimes.missing, vec!["tmux"]);
+    }
+
+    #[test]
+    fn lite_probe_derives_cpu_from_previous_sample() {
+        let first = parse_live_lite(
+            "lite-test",
+            "cpu_ticks=100 0 50 800 50 0 0 0 0 0\nuptime_seconds=3600\nmem_total_kb=1000\n\
+             mem_available_kb=400\nload_1=0.50\nload_5=0.25\nload_15=0.10\ncpu_cores=4\n",
+        );
+        assert_eq!(first.cpu_percent, None);
+        assert_eq!(first.mem_used_kb, Some(600));
+        assert_eq!(first.swap_used_kb, None);
+        assert_eq!(first.cpu_cores, Some(4));
+        assert!(first.processes.is_empty());
+
+        let second = parse_live_lite(
+            "lite-test",
+            "cpu_ticks=130 10 60 850 50 0 0 0 0 0\nswap_total_kb=2048\nswap_free_kb=512\n",
+        );
+        assert_eq!(second.swap_total_kb, Some(2048));
+        assert_eq!(second.swap_used_kb, Some(1536));
+        assert_eq!(second.cpu_percent, Some(50.0));
+        assert_eq!(second.cpu_user_percent, Some(40.0));
+        assert_eq!(second.cpu_system_percent, Some(10.0));
+        assert_eq!(second.cpu_iowait_percent, Some(0.0));
+        assert_eq!(second.cpu_idle_percent, Some(50.0));
+    }
+
+    #[test]
+    fn lite_probe_runs_against_mock_ssh() {
+        let host: Host = serde_json::from_str(
+            r#"{"id":"mock-ssh-lite","alias":"a","hostname":"pi.local","user":"pi","tags":[],"notes":""}"#,
+        )
+        .unwrap();
+        mock_ssh(&host.id, |script| {
+            assert!(script.contains("/proc/stat"));
+            Ok(
+                "cpu_ticks=1 0 1 8 0 0 0 0 0 0\nmem_total_kb=2000\nmem_available_kb=500\n"
+                    .to_string(),
+            )
+        });
+        let info = host_probe_live_lite_impl(host).unwrap();
+        assert_eq!(info.mem_used_kb, Some(1500));
+        assert_eq!(info.cpu_percent, None);
+    }
+
+    #[test]
+    fn decodes_raspberry_pi_throttling() {
+        let (kv, _) = parse_kv(
+            "sbc_model=Raspberry Pi 4 Model B Rev 1.4\nsbc_throttled=0x50005\nsbc_soc_temp_c=81.3\n",
+        );
+        let sbc = parse_sbc_info(&kv).unwrap();
+        assert_eq!(sbc.model, "Raspberry Pi 4 Model B Rev 1.4");
+        assert_eq!(sbc.soc_temp_c, Some(81.3));
+        assert!(sbc.under_voltage_now && sbc.throttled_now);
+        assert!(!sbc.freq_capped_now && !sbc.soft_temp_limit_now);
+        assert!(sbc.under_voltage_occurred && sbc.throttled_occurred);
+        assert!(!sbc.freq_capped_occurred);
+
+        // Other ARM boards have a model but no vcgencmd.
+        let (kv, _) = parse_kv("sbc_model=Radxa ROCK 5B\nsbc_throttled=\nsbc_soc_temp_c=45.0\n");
+        let sbc = parse_sbc_info(&kv).unwrap();
+        assert_eq!(sbc.throttled_raw, None);
+        assert!(!sbc.under_voltage_occurred);
+        assert!(parse_sbc_info(&parse_kv("cpu_cores=4\n").0).is_none());
+    }
+
+    #[test]
+    fn lists_real_filesystems_with_inodes() {
+        let stdout = "\
+fs=/dev/sda2 ext4 102626232 61440000 35926668 64% /
+fs=tmpfs tmpfs 8123456 0 8123456 0% /dev/shm
+fs=/dev/sda3 ext4 51474912 49000000 0 100% /var
+fs=/dev/sda2 ext4 102626232 61440000 35926668 64% /var/lib/docker
+fs=overlay overlay 102626232 61440000 35926668 64% /var/lib/docker/overlay2/abc/merged
+fs=//nas/share cifs 976762584 500000000 476762584 52% /mnt/nas share
+fs=/dev/sdb1 btrfs 20971520 1024 20000000 1% /data
+fs_inodes=/dev/sda2 6553600 412000 6141600 7% /
+fs_inodes=/dev/sda3 3276800 3276790 10 100% /var
+fs_inodes=/dev/sdb1 0 0 0 - /data
+";
+        let filesystems = parse_filesystems(stdout);
+        let mounts: Vec<&str> = filesystems
+            .iter()
+            .map(|fs| fs.mount_point.as_str())
+            .collect();
+        assert_eq!(mounts, vec!["/", "/data", "/mnt/nas share", "/var"]);
+        let var = &filesystems[3];
+        assert_eq!(var.fs_type.as_deref(), Some("ext4"));
+        assert_eq!(var.available_kb, 0);
+        assert_eq!(var.inodes_used, Some(3276790));
+        assert_eq!(filesystems[1].inodes_total, None);
+
+        let untyped = parse_filesystems(
+            "fs_untyped=/dev/root 30000000 1000 29999000 1% /\nfs_untyped=tmpfs 100 0 100 0% /tmp\n",
+        );
+        assert_eq!(untyped.len(), 1);
+        assert_eq!(untyped[0].fs_type, None);
+    }
+
+    #[test]
+    fn derives_interface_throughput_from_two_samples() {
+        let stdout = "\
+net_window_s=0.25
+net_start=lo 1000 1000
+net_start=eth0 1000000 500000
+net_start=wg0 4294967000 10
+net_end=lo 2000 2000
+net_end=eth0 1250000 525000
+net_end=wg0 200 10
+net_end=veth1 10 10
+";
+        let network = parse_network(stdout, Some(0.25));
+        let names: Vec<&str> = network.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(names, vec!["eth0", "veth1", "wg0"]);
+        assert_eq!(network[0].rx_bytes, 1250000);
+        assert_eq!(network[0].rx_bytes_per_sec, Some(1_000_000.0));
+        assert_eq!(network[0].tx_bytes_per_sec, Some(100_000.0));
+        assert_eq!(network[1].rx_bytes_per_sec, None);
+        // The 32-bit counter wrapped.
+        assert_eq!(network[2].rx_bytes_per_sec, None);
+        assert_eq!(network[2].tx_bytes_per_sec, Some(0.0));
+
+        let nominal = parse_network("net_start=eth0 0 0\nnet_end=eth0 100 0\n", None);
+        assert_eq!(nominal[0].rx_bytes_per_sec, Some(500.0));
+    }
+
+    #[test]
+    fn parses_extended_probe_temperatures_and_gpus() {
+        let stdout = r#"thermal=x86_pkg_temp|54000
+thermal=acpitz|27850
+thermal=broken|
+gpu_nvidia=0, NVIDIA GeForce RTX 3090, 87, 20480, 24576, 71
+gpu_nvidia=1, Tesla T4, [N/A], 0, 15360, 40
+gpu_rocm={"card0": {"Card series": "Instinct MI210", "GPU use (%)": "12", "Temperature (Sensor edge) (C)": "45.0", "Temperature (Sensor junction) (C)": "51.0", "VRAM Total Memory (B)": "68702699520", "VRAM Total Used Memory (B)": "1073741824"}, "system": {}}
+"#;
+        let zones = parse_thermal_zones(stdout);
+        assert_eq!(zones.len(), 2);
+        assert_eq!(zones[0].name, "x86_pkg_temp");
+        assert_eq!(zones[0].temp_c, 54.0);
+        assert_eq!(zones[1].temp_c, 27.9);
+
+        let gpus = parse_gpus(stdout);
+        assert_eq!(gpus.len(), 3);
+        assert_eq!(gpus[0].name.as_deref(), Some("NVIDIA GeForce RTX 3090"));
+        assert_eq!(gpus[0].utilization_percent, Some(87.0));
+        assert_eq!(gpus[0].mem_used_kb, Some(20480 * 1024));
+        assert_eq!(gpus[0].temp_c, Some(71.0));
+        assert_eq!(gpus[1].index, 1);
+        assert_eq!(gpus[1].utilization_percent, None);
+        let amd = &gpus[2];
+        assert_eq!(amd.vendor, "amd");
+        assert_eq!(amd.name.as_deref(), Some("Instinct MI210"));
+        assert_eq!(amd.temp_c, Some(45.0));
+        assert_eq!(amd.mem_used_kb, Some(1048576));
+        assert_eq!(amd.mem_total_kb, Some(67092480));
+    }
+
+    #[test]
+    fn parses_oom_kills_from_journal_and_dmesg() {
+        let stdout = "\
+2026-03-01T08:00:00+0000 db-1 kernel: Out of memory: Killed process 812 (mysqld) total-vm:4194304kB, anon-rss:3145728kB, file-rss:0kB, shmem-rss:0kB, UID:27 pgtables:6500kB oom_score_adj:0
+2026-03-01T11:30:00,412345+00:00 Memory cgroup out of memory: Killed process 2290 (java) total-vm:2097152kB, anon-rss:524288kB, file-rss:1024kB
+2026-02-20T09:00:00+0000 db-1 kernel: Out of memory: Killed process 77 (old) anon-rss:1kB
+";
+        let since = chrono::DateTime::parse_from_rfc3339("2026-02-28T12:00:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        let events = parse_oom_events(stdout, since);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].process, "java");
+        assert_eq!(events[0].at.as_deref(), Some("2026-03-01T11:30:00Z"));
+        assert!(events[0].cgroup);
+        assert_eq!(events[1].process, "mysqld");
+        assert_eq!(events[1].pid, Some(812));
+        assert_eq!(events[1].anon_rss_kb, Some(3145728));
+        assert!(!events[1].cgroup);
+    }
+
+    #[test]
+    fn parses_docker_containers_with_usage() {
+        let stdout = r#"access=sudo
+ps={"Command":"\"nginx -g\"","ID":"a1b2","Image":"nginx:1.25","Names":"web","State":"running","Status":"Up 3 hours (healthy)"}
+ps={"ID":"c3d4","Image":"postgres:16","Names":"db","Status":"Exited (0) 2 days ago"}
+ps={"ID":"e5f6","Image":"redis","Names":"cache","Status":"Up 5 minutes (Paused)"}
+stats={"CPUPerc":"12.50%","ID":"a1b2","MemPerc":"0.63%","MemUsage":"12.5MiB / 1.944GiB","Name":"web"}
+"#;
+        let docker = parse_docker(stdout);
+        assert_eq!(docker.access, ProbeAccess::Sudo);
+        assert_eq!(docker.containers.len(), 3);
+        let web = &docker.containers[0];
+        assert_eq!(web.name, "web");
+        assert_eq!(web.state, "running");
+        assert_eq!(web.status, "Up 3 hours (healthy)");
+        assert_eq!(web.cpu_percent, Some(12.5));
+        assert_eq!(web.mem_used_bytes, Some(13_107_200));
+        assert_eq!(web.mem_limit_bytes, Some(2_087_354_106));
+        assert_eq!(web.mem_percent, Some(0.63));
+        assert_eq!(docker.containers[1].state, "exited");
+        assert_eq!(docker.containers[1].cpu_percent, None);
+        assert_eq!(docker.containers[2].state, "paused");
+
+        let denied = parse_docker("access=denied\n");
+        assert_eq!(denied.access, ProbeAccess::Denied);
+        assert!(denied.containers.is_empty());
+    }
+
+    #[test]
+    fn merges_loaded_and_installed_services() {
+        let stdout = "\
+systemd=1
+unit=nginx.service loaded active running A high performance web server
+unit=● postgresql.service loaded failed failed PostgreSQL database server
+unit=ghost.service not-found inactive dead ghost.service
+file=nginx.service enabled enabled
+file=postgresql.service enabled disabled
+file=nginx-debug.service disabled enabled
+";
+        let services = parse_services(stdout).unwrap();
+        let units: Vec<&str> = services.iter().map(|s| s.unit.as_str()).collect();
+        assert_eq!(
+            units,
+            vec![
+                "ghost.service",
+                "nginx-debug.service",
+                "nginx.service",
+                "postgresql.service"
+            ]
+        );
+        assert_eq!(services[0].load_state, "not-found");
+        assert_eq!(services[0].enabled, None);
+        assert_eq!(services[1].load_state, "not-loaded");
+        assert_eq!(services[1].active_state, "inactive");
+        assert_eq!(services[1].enabled.as_deref(), Some("disabled"));
+        assert_eq!(
+            services[2].description.as_deref(),
+            Some("A high performance web server")
+        );
+        assert_eq!(services[2].sub_state, "running");
+        assert_eq!(services[3].active_state, "failed");
+        assert_eq!(services[3].enabled.as_deref(), Some("enabled"));
+
+        assert!(parse_services("systemd=0\n").is_err());
+        assert!(validate_unit_pattern("sshd@*.service").is_ok());
+        assert!(validate_unit_pattern("--now").is_err());
+        assert!(validate_unit_pattern("a;reboot").is_err());
+    }
+
+    #[test]
+    fn reboot_required_when_flagged_or_kernel_outdated() {
+        let outdated = parse_reboot_status(
+            "running_kernel=5.14.0-362.el9.x86_64\nflagged=0\ninstalled_kernel=5.14.0-427.el9.x86_64\n",
+        );
+        assert!(outdated.reboot_required);
+        assert!(!outdated.flagged_by_system);
+
+        let current = parse_reboot_status(
+            "running_kernel=6.1.0-18-amd64\nflagged=0\ninstalled_kernel=6.1.0-18-amd64\n",
+        );
+        assert!(!current.reboot_required);
+
+        let flagged = parse_reboot_status(
+            "running_kernel=6.1.0-18-amd64\nflagged=1\ninstalled_kernel=\npackages=libc6 openssl \n",
+        );
+        assert!(flagged.reboot_required);
+        assert_eq!(flagged.installed_kernel, None);
+        assert_eq!(flagged.packages, vec!["libc6", "openssl"]);
+    }
+
+    #[test]
+    fn probe_shell_override_is_trimmed_and_single_line() {
+        let mut host = Host {
+            probe_shell: Some("  ash ".to_string()),
+            ..test_host("1")
+        };
+        assert_eq!(probe_shell_override(&host).unwrap().as_deref(), Some("ash"));
+        host.probe_shell = Some("   ".to_string());
+        assert_eq!(probe_shell_override(&host).unwrap(), None);
+        host.probe_shell = Some("sh\nreboot".to_string());
+        assert!(probe_shell_override(&host).is_err());
+    }
 }
 
 fn probe_ssh_args(
     host: &Host,
     ssh_config_path: &str,
     control_path: &str,
+    control_persist_secs: u64,
     has_password: bool,
 ) -> Vec<String> {
     let mut args = vec![
@@ -231,13 +854,10 @@ fn probe_ssh_args(
         "ServerAliveInterval=10".to_string(),
         "-o".to_string(),
         "ServerAliveCountMax=1".to_string(),
-        "-o".to_string(),
-        "ControlMaster=auto".to_string(),
-        "-o".to_string(),
-        "ControlPersist=30s".to_string(),
-        "-o".to_string(),
-        format!("ControlPath={control_path}"),
     ];
+    // The terminal's connection: a probe on a host with an open tab costs a
+    // channel rather than a login (and a second MFA prompt).
+    args.extend(ssh_multiplex_args(control_path, control_persist_secs));
     if has_password {
         args.push("-o".to_string());
         args.push("BatchMode=no".to_string());
@@ -251,7 +871,215 @@ fn probe_ssh_args(
     args
 }
 
-fn run_probe(host: &Host, script: &str) -> Result<String, String> {
+fn probe_shell_override(host: &Host) -> Result<Option<String>, String> {
+    let Some(shell) = host
+        .probe_shell
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    else {
+        return Ok(None);
+    };
+    // The override is spliced into the remote command line as-is (so values
+    // like `busybox sh` work); it must stay on one line.
+    if shell.chars().any(|ch| ch.is_control()) {
+        return Err("Probe shell must not contain control characters".to_string());
+    }
+    Ok(Some(shell.to_string()))
+}
+
+fn is_powershell(shell: &str) -> bool {
+    let program = shell.rsplit(['/', '\\']).next().unwrap_or(shell);
+    let program = program.to_ascii_lowercase();
+    let program = program.strip_suffix(".exe").unwrap_or(&program);
+    program == POWERSHELL_PROBE_SHELL || program == "pwsh"
+}
+
+// ssh joins its trailing arguments with spaces and hands them to the remote
+// login shell, so the script must be quoted as a single `-c` argument.
+// Windows servers start cmd.exe, which has no such quoting; PowerShell takes
+// the script as base64 of its UTF-16LE text instead.
+fn probe_remote_command(shell: &str, script: &str) -> String {
+    if is_powershell(shell) {
+        let utf16: Vec<u8> = script.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        return format!(
+            "{shell} -NoProfile -NonInteractive -EncodedCommand {}",
+            base64::engine::general_purpose::STANDARD.encode(utf16)
+        );
+    }
+    format!("{shell} -lc {}", shell_quote(script))
+}
+
+fn resolve_probe_shell(host: &Host) -> Result<String, String> {
+    if let Some(shell) = probe_shell_override(host)? {
+        record_probe_shell(&host.id, &shell, true);
+        return Ok(shell);
+    }
+    if let Some(shell) = cached_probe_shell(&host.id) {
+        return Ok(shell);
+    }
+    let check = format!("echo {PROBE_SHELL_MARKER}");
+    for candidate in PROBE_SHELL_CANDIDATES {
+        let output = ssh_exec(host, &probe_remote_command(candidate, &check))?;
+        // 255 is ssh's own failure (auth, network); no other shell will fix it.
+        if output.status.code() == Some(255) {
+            return Err(probe_failure_message(&output));
+        }
+        if String::from_utf8_lossy(&output.stdout).contains(PROBE_SHELL_MARKER) {
+            record_probe_shell(&host.id, candidate, false);
+            return Ok(candidate.to_string());
+        }
+    }
+    Err(format!(
+        "No usable shell found for probes (tried {}); set a probe shell for this host",
+        PROBE_SHELL_CANDIDATES.join(", ")
+    ))
+}
+
+pub(crate) fn probe_failure_message(output: &Output) -> String {
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let msg = if !stderr.is_empty() { stderr } else { stdout };
+    if msg.is_empty() {
+        format!("ssh exited with status {}", output.status)
+    } else {
+        msg
+    }
+}
+
+pub(crate) fn run_probe(host: &Host, script: &str) -> Result<String, String> {
+    run_probe_capped(host, script).map(|(stdout, _)| stdout)
+}
+
+/// `run_probe` that also reports whether the output hit the current
+/// operation's output cap.
+pub(crate) fn run_probe_capped(
+    host: &Host,
+    script: &str,
+) -> Result<(String, Option<OutputTruncation>), String> {
+    #[cfg(test)]
+    if let Some(result) = crate::mock_backends::mock_ssh_response(host, script) {
+        return result.map(|stdout| (stdout, None));
+    }
+    let shell = resolve_probe_shell(host)?;
+    let (output, truncation) = ssh_exec_capped(host, &probe_remote_command(&shell, script))?;
+    if !output.status.success() {
+        return Err(probe_failure_message(&output));
+    }
+    Ok((
+        String::from_utf8_lossy(&output.stdout).to_string(),
+        truncation,
+    ))
+}
+
+/// `run_probe` for probes with a Windows version: `powershell_script` runs
+/// instead of `script` when the host's probe shell is PowerShell. It prints
+/// the same `key=value` lines, so one parser reads both.
+fn run_portable_probe(
+    host: &Host,
+    script: &str,
+    powershell_script: &str,
+) -> Result<String, String> {
+    #[cfg(test)]
+    if let Some(result) = crate::mock_backends::mock_ssh_response(host, script) {
+        return result;
+    }
+    if is_powershell(&resolve_probe_shell(host)?) {
+        run_probe(host, &windows_probe_script(powershell_script))
+    } else {
+        run_probe(host, script)
+    }
+}
+
+// Shared by the Windows probes: CIM where it exists, WMI on PowerShell 2, and
+// UTF-8 output whatever the console code page. Errors are dropped so a
+// missing class only leaves its keys empty, as in the POSIX scripts.
+const WINDOWS_PROBE_PRELUDE: &str = r#"
+$ErrorActionPreference = 'SilentlyContinue'
+[Console]::OutputEncoding = [Text.Encoding]::UTF8
+function Get-Class($Name) {
+  if (Get-Command Get-CimInstance) { Get-CimInstance $Name } else { Get-WmiObject $Name }
+}
+"#;
+
+fn windows_probe_script(body: &str) -> String {
+    format!("{WINDOWS_PROBE_PRELUDE}{body}\nexit 0\n")
+}
+
+const WINDOWS_STATIC_PROBE_SCRIPT: &str = r#"
+$os = Get-Class Win32_OperatingSystem
+"system_name=$($os.Caption)"
+"kernel=Windows NT $($os.Version)"
+"arch=$env:PROCESSOR_ARCHITECTURE"
+"cpu_model=$((Get-Class Win32_Processor | Select-Object -First 1).Name)"
+"cpu_cores=$((Get-Class Win32_ComputerSystem).NumberOfLogicalProcessors)"
+"mem_total_kb=$($os.TotalVisibleMemorySize)"
+"os_id=windows"
+"os_version=$($os.Version)"
+"hostname=$env:COMPUTERNAME"
+"#;
+
+// Windows has no load average, iowait or inode counts, so those stay empty;
+// CPU use is the processors' own sampled load. `mem_available_kb` is for the
+// lite parser. Numbers interpolated into strings are culture-invariant.
+const WINDOWS_LIVE_PROBE_SCRIPT: &str = r#"
+$os = Get-Class Win32_OperatingSystem
+$cores = (Get-Class Win32_ComputerSystem).NumberOfLogicalProcessors
+if (-not $cores) { $cores = 1 }
+$net = { Get-Class Win32_PerfRawData_Tcpip_NetworkInterface | ForEach-Object { "$($_.Name) $($_.BytesReceivedPersec) $($_.BytesSentPersec)" } }
+$net1 = & $net
+$clock = [Diagnostics.Stopwatch]::StartNew()
+Start-Sleep -Milliseconds 200
+$net2 = & $net
+"net_window_s=$($clock.Elapsed.TotalSeconds)"
+$net1 | ForEach-Object { "net_start=$_" }
+$net2 | ForEach-Object { "net_end=$_" }
+"cpu_percent=$((Get-Class Win32_Processor | Measure-Object -Property LoadPercentage -Average).Average)"
+"cpu_cores=$cores"
+$boot = $os.LastBootUpTime
+if ($boot -is [string]) { $boot = [Management.ManagementDateTimeConverter]::ToDateTime($boot) }
+if ($boot) { "uptime_seconds=$([math]::Floor(((Get-Date) - $boot).TotalSeconds))" }
+$memTotal = [long]$os.TotalVisibleMemorySize
+$memFree = [long]$os.FreePhysicalMemory
+"mem_total_kb=$memTotal"
+"mem_used_kb=$($memTotal - $memFree)"
+"mem_free_kb=$memFree"
+"mem_available_kb=$memFree"
+$page = Get-Class Win32_PageFileUsage
+$swapTotal = ($page | Measure-Object -Property AllocatedBaseSize -Sum).Sum
+$swapUsed = ($page | Measure-Object -Property CurrentUsage -Sum).Sum
+if ($swapTotal) { "swap_total_kb=$($swapTotal * 1024)"; "swap_free_kb=$(($swapTotal - $swapUsed) * 1024)" }
+Get-Class Win32_LogicalDisk | Where-Object { $_.DriveType -eq 3 -and $_.Size } | ForEach-Object {
+  $total = [math]::Floor($_.Size / 1024)
+  $free = [math]::Floor($_.FreeSpace / 1024)
+  if ($_.DeviceID -eq $env:SystemDrive) { "disk_root_total_kb=$total"; "disk_root_used_kb=$($total - $free)" }
+  $type = if ($_.FileSystem) { $_.FileSystem } else { 'unknown' }
+  "fs=$($_.DeviceID) $type $total $($total - $free) $free - $($_.DeviceID)\"
+}
+Get-Class Win32_PerfFormattedData_PerfProc_Process |
+  Where-Object { $_.IDProcess -ne 0 -and $_.Name -ne '_Total' } |
+  Sort-Object PercentProcessorTime -Descending | Select-Object -First 5 | ForEach-Object {
+  $name = $_.Name -replace '#\d+$', ''
+  "proc=$($_.IDProcess)|$name|$([math]::Round($_.PercentProcessorTime / $cores, 1))|$([math]::Round($_.WorkingSetPrivate / 10.24 / $memTotal, 1))"
+}
+"#;
+
+/// A probe-configured `ssh` invocation. The askpass script (if any) lives as
+/// long as this value, so keep it alive until the child has authenticated.
+pub(crate) struct ProbeSshCommand {
+    pub(crate) command: Command,
+    _askpass_script: Option<AskpassScript>,
+    _prompt_relay: Option<PromptRelay>,
+}
+
+/// Build an `ssh` command with the probe options (generated config, shared
+/// ControlMaster, askpass) running `remote_command` on the host. Extra `-o`
+/// options go before the target.
+pub(crate) fn probe_ssh_command(
+    host: &Host,
+    extra_options: &[&str],
+    remote_command: &str,
+) -> Result<ProbeSshCommand, String> {
     let target = target_alias_of(host);
     if target.trim().is_empty() {
         return Err("hostname is required".to_string());
@@ -260,18 +1088,19 @@ fn run_probe(host: &Host, script: &str) -> Result<String, String> {
     ensure_ssh_config()?;
 
     let mut askpass_script: Option<AskpassScript> = None;
+    let mut prompt_relay: Option<PromptRelay> = None;
     let mut cmd = Command::new("/usr/bin/ssh");
-    let maybe_password = keychain_get_password(&host.id).ok().flatten();
+    let maybe_password = keychain_get_password(&host.id, "probe").ok().flatten();
     let has_password = maybe_password
         .as_ref()
         .map(|s| !s.trim().is_empty())
         .unwrap_or(false);
     let ssh_config_path = get_ssh_config_path();
-    let control_path = ssh_config_path.with_file_name("probe_mux_%C");
     let mut args = probe_ssh_args(
         host,
         ssh_config_path.to_string_lossy().as_ref(),
-        control_path.to_string_lossy().as_ref(),
+        get_ssh_control_path().to_string_lossy().as_ref(),
+        control_persist_secs(),
         has_password,
     );
     if let Some(password) = maybe_password
@@ -279,41 +1108,52 @@ fn run_probe(host: &Host, script: &str) -> Result<String, String> {
         .map(|s| s.trim())
         .filter(|s| !s.is_empty())
     {
-        let script = create_askpass_script(password)?;
+        let relay = PromptRelay::start(&host.id)?;
+        let script = create_askpass_script(password, &relay)?;
         cmd.env("DISPLAY", "xtermius:0");
         cmd.env("SSH_ASKPASS_REQUIRE", "force");
         cmd.env("SSH_ASKPASS", script.path().as_os_str());
         askpass_script = Some(script);
+        prompt_relay = Some(relay);
     }
 
-    args.push("sh".to_string());
-    args.push("-lc".to_string());
-    args.push(script.to_string());
+    let target = args.pop().unwrap_or(target);
+    for option in extra_options {
+        args.push("-o".to_string());
+        args.push(option.to_string());
+    }
+    args.push(target);
+    args.push(remote_command.to_string());
+    cmd.args(args);
 
-    let output = cmd
-        .args(args)
-        .stdin(Stdio::null())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .output()
-        .map_err(|e| e.to_string())?;
+    Ok(ProbeSshCommand {
+        command: cmd,
+        _askpass_script: askpass_script,
+        _prompt_relay: prompt_relay,
+    })
+}
 
-    drop(askpass_script);
+pub(crate) fn ssh_exec(host: &Host, remote_command: &str) -> Result<Output, String> {
+    ssh_exec_capped(host, remote_command).map(|(output, _)| output)
+}
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
-        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
-        let msg = if !stderr.is_empty() { stderr } else { stdout };
-        return Err(if msg.is_empty() {
-            format!("ssh exited with status {}", output.status)
-        } else {
-            msg
-        });
-    }
-    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+pub(crate) fn ssh_exec_capped(
+    host: &Host,
+    remote_command: &str,
+) -> Result<(Output, Option<OutputTruncation>), String> {
+    let mut prepared = probe_ssh_command(host, &[], remote_command)?;
+    prepared
+        .command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    operations::command_output_capped(&mut prepared.command)
 }
 
-fn host_probe_static_impl(host: Host) -> Result<HostStaticInfo, String> {
+fn host_probe_static_impl<R: Runtime>(
+    app: &AppHandle<R>,
+    host: Host,
+) -> Result<HostStaticInfo, String> {
     let script = r#"
 set -eu
 SYSTEM_NAME="$(hostnamectl --pretty 2>/dev/null || true)"
@@ -324,6 +1164,15 @@ if [ -z "$SYSTEM_NAME" ]; then
   SYSTEM_NAME="$(uname -s 2>/dev/null || true)"
 fi
 KERNEL="$(uname -sr 2>/dev/null || true)"
+OS_ID=""
+OS_VERSION=""
+if [ -r /etc/os-release ]; then
+  OS_ID="$(awk -F= '/^ID=/{gsub(/^"|"$/,"",$2);print $2;exit}' /etc/os-release 2>/dev/null || true)"
+  OS_VERSION="$(awk -F= '/^VERSION_ID=/{gsub(/^"|"$/,"",$2);print $2;exit}' /etc/os-release 2>/dev/null || true)"
+fi
+if [ -z "$OS_ID" ]; then
+  OS_ID="$(uname -s 2>/dev/null | tr '[:upper:]' '[:lower:]' || true)"
+fi
 ARCH="$(uname -m 2>/dev/null || true)"
 CPU_MODEL="$(awk -F: '/model name/{print $2;exit}' /proc/cpuinfo 2>/dev/null | sed 's/^ *//' || true)"
 if [ -z "$CPU_MODEL" ]; then
@@ -343,17 +1192,59 @@ printf 'arch=%s\n' "$ARCH"
 printf 'cpu_model=%s\n' "$CPU_MODEL"
 printf 'cpu_cores=%s\n' "$CPU_CORES"
 printf 'mem_total_kb=%s\n' "$MEM_TOTAL_KB"
+printf 'os_id=%s\n' "$OS_ID"
+printf 'os_version=%s\n' "$OS_VERSION"
+printf 'hostname=%s\n' "$(hostname 2>/dev/null || uname -n 2>/dev/null || true)"
 "#;
 
-    let stdout = run_probe(&host, script)?;
+    let stdout = run_portable_probe(&host, script, WINDOWS_STATIC_PROBE_SCRIPT)?;
     let (kv, _) = parse_kv(&stdout);
-    Ok(HostStaticInfo {
+    let mut info = HostStaticInfo {
         system_name: maybe_text(kv.get("system_name")),
         kernel: maybe_text(kv.get("kernel")),
         arch: maybe_text(kv.get("arch")),
         cpu_model: maybe_text(kv.get("cpu_model")),
         cpu_cores: parse_u32(kv.get("cpu_cores")),
         mem_total_kb: parse_u64(kv.get("mem_total_kb")),
+        os_id: maybe_text(kv.get("os_id")),
+        os_version: maybe_text(kv.get("os_version")),
+        hostname: maybe_text(kv.get("hostname")),
+        fact_tags: Vec::new(),
+    };
+    info.fact_tags = record_probe_facts(app, &host, static_facts(&info));
+    Ok(info)
+}
+
+fn static_facts(info: &HostStaticInfo) -> BTreeMap<String, String> {
+    let mut facts = BTreeMap::new();
+    if let Some(os) = fact_tags::os_fact(info.os_id.as_deref(), info.os_version.as_deref()) {
+        facts.insert("os".to_string(), os);
+    }
+    if let Some(arch) = info.arch.as_deref().and_then(fact_tags::arch_fact) {
+        facts.insert("arch".to_string(), arch);
+    }
+    if let Some(cores) = info.cpu_cores {
+        facts.insert("cpu_cores".to_string(), cores.to_string());
+    }
+    if let Some(hostname) = info.hostname.as_deref() {
+        facts.insert(REMOTE_NAME_FACT.to_string(), hostname.to_string());
+    }
+    facts
+}
+
+// Tagging is a side effect of probing; a failure there must not fail the
+// probe itself.
+fn record_probe_facts<R: Runtime>(
+    app: &AppHandle<R>,
+    host: &Host,
+    facts: BTreeMap<String, String>,
+) -> Vec<String> {
+    fact_tags::record_facts(app, &host.id, facts).unwrap_or_else(|error| {
+        eprintln!(
+            "[host_probe] failed to apply fact tags to {}: {error}",
+            host.id
+        );
+        Vec::new()
     })
 }
 
@@ -367,9 +1258,18 @@ CPU_IOWAIT_PERCENT=""
 CPU_IDLE_PERCENT=""
 if [ -r /proc/stat ]; then
   LINE1="$(grep '^cpu ' /proc/stat || true)"
+  NET1="$(sed 's/:/ /' /proc/net/dev 2>/dev/null | awk 'NR > 2 { print $1, $2, $10 }' || true)"
+  UPTIME1="$(awk '{print $1}' /proc/uptime 2>/dev/null || true)"
   CPU_CORES="$(grep -c '^cpu[0-9]' /proc/stat 2>/dev/null || true)"
   sleep 0.2
   LINE2="$(grep '^cpu ' /proc/stat || true)"
+  NET2="$(sed 's/:/ /' /proc/net/dev 2>/dev/null | awk 'NR > 2 { print $1, $2, $10 }' || true)"
+  UPTIME2="$(awk '{print $1}' /proc/uptime 2>/dev/null || true)"
+  if [ -n "$NET1" ] && [ -n "$NET2" ]; then
+    printf 'net_window_s=%s\n' "$(awk -v A="$UPTIME1" -v B="$UPTIME2" 'BEGIN{ if (A != "" && B > A) print B - A }')"
+    printf '%s\n' "$NET1" | sed 's/^/net_start=/'
+    printf '%s\n' "$NET2" | sed 's/^/net_end=/'
+  fi
   if [ -n "$LINE1" ] && [ -n "$LINE2" ]; then
     CPU_ALL="$(awk -v A="$LINE1" -v B="$LINE2" 'BEGIN{
       split(A,a," "); split(B,b," ");
@@ -409,6 +1309,8 @@ MEM_TOTAL_KB="$(awk '/MemTotal:/ {print $2;exit}' /proc/meminfo 2>/dev/null || t
 MEM_AVAIL_KB="$(awk '/MemAvailable:/ {print $2;exit}' /proc/meminfo 2>/dev/null || true)"
 MEM_FREE_KB="$(awk '/MemFree:/ {print $2;exit}' /proc/meminfo 2>/dev/null || true)"
 MEM_CACHE_KB="$(awk '/^Cached:/ {print $2;exit}' /proc/meminfo 2>/dev/null || true)"
+SWAP_TOTAL_KB="$(awk '/^SwapTotal:/ {print $2;exit}' /proc/meminfo 2>/dev/null || true)"
+SWAP_FREE_KB="$(awk '/^SwapFree:/ {print $2;exit}' /proc/meminfo 2>/dev/null || true)"
 MEM_USED_KB=""
 if [ -n "$MEM_TOTAL_KB" ] && [ -n "$MEM_AVAIL_KB" ]; then
   MEM_USED_KB="$((MEM_TOTAL_KB - MEM_AVAIL_KB))"
@@ -428,6 +1330,7 @@ LOAD_15="$(printf '%s\n' "$LOAD_RAW" | awk '{c=0;for(i=1;i<=NF;i++) if ($i ~ /^[
 DISK_LINE="$(df -kP / 2>/dev/null | tail -n 1 || true)"
 DISK_TOTAL_KB="$(printf '%s\n' "$DISK_LINE" | awk '{print $2}')"
 DISK_USED_KB="$(printf '%s\n' "$DISK_LINE" | awk '{print $3}')"
+DF_TYPED="$(df -kPT 2>/dev/null | sed '1d' || true)"
 
 printf 'cpu_percent=%s\n' "$CPU_PERCENT"
 printf 'cpu_user_percent=%s\n' "$CPU_USER_PERCENT"
@@ -440,11 +1343,19 @@ printf 'mem_total_kb=%s\n' "$MEM_TOTAL_KB"
 printf 'mem_used_kb=%s\n' "$MEM_USED_KB"
 printf 'mem_free_kb=%s\n' "$MEM_FREE_KB"
 printf 'mem_page_cache_kb=%s\n' "$MEM_CACHE_KB"
+printf 'swap_total_kb=%s\n' "$SWAP_TOTAL_KB"
+printf 'swap_free_kb=%s\n' "$SWAP_FREE_KB"
 printf 'load_1=%s\n' "$LOAD_1"
 printf 'load_5=%s\n' "$LOAD_5"
 printf 'load_15=%s\n' "$LOAD_15"
 printf 'disk_root_total_kb=%s\n' "$DISK_TOTAL_KB"
 printf 'disk_root_used_kb=%s\n' "$DISK_USED_KB"
+if [ -n "$DF_TYPED" ]; then
+  printf '%s\n' "$DF_TYPED" | sed 's/^/fs=/'
+else
+  df -kP 2>/dev/null | sed '1d;s/^/fs_untyped=/' || true
+fi
+df -iP 2>/dev/null | sed '1d;s/^/fs_inodes=/' || true
 PROC_LINES="$(ps -eo pid=,comm=,pcpu=,pmem= --sort=-pcpu 2>/dev/null | head -n 5 || true)"
 if [ -z "$PROC_LINES" ]; then
   PROC_LINES="$(ps -Ao pid=,comm=,pcpu=,pmem= -r 2>/dev/null | head -n 5 || true)"
@@ -454,9 +1365,24 @@ printf '%s\n' "$PROC_LINES" | while read -r pid cmd cpu mem; do
   [ -n "$cmd" ] || continue
   printf 'proc=%s|%s|%s|%s\n' "$pid" "$cmd" "$cpu" "$mem"
 done
+
+SBC_MODEL="$(tr -d '\000' < /proc/device-tree/model 2>/dev/null || true)"
+if [ -n "$SBC_MODEL" ]; then
+  printf 'sbc_model=%s\n' "$SBC_MODEL"
+  printf 'sbc_throttled=%s\n' "$(vcgencmd get_throttled 2>/dev/null | cut -d= -f2 || true)"
+  SOC_TEMP="$(vcgencmd measure_temp 2>/dev/null | sed -n 's/^temp=\([0-9.]*\).*/\1/p' || true)"
+  if [ -z "$SOC_TEMP" ] && [ -r /sys/class/thermal/thermal_zone0/temp ]; then
+    SOC_TEMP="$(awk '{printf "%.1f", $1/1000}' /sys/class/thermal/thermal_zone0/temp 2>/dev/null || true)"
+  fi
+  printf 'sbc_soc_temp_c=%s\n' "$SOC_TEMP"
+fi
 "#;
 
-    let stdout = run_probe(&host, script)?;
+    let stdout = run_portable_probe(
+        &host,
+        &live_probe_script(&host, script),
+        WINDOWS_LIVE_PROBE_SCRIPT,
+    )?;
     let (kv, proc_lines) = parse_kv(&stdout);
     let mut processes = Vec::new();
     for line in proc_lines {
@@ -498,25 +1424,1225 @@ done
         mem_used_kb: parse_u64(kv.get("mem_used_kb")),
         mem_free_kb: parse_u64(kv.get("mem_free_kb")),
         mem_page_cache_kb: parse_u64(kv.get("mem_page_cache_kb")),
+        swap_total_kb: parse_u64(kv.get("swap_total_kb")),
+        swap_used_kb: swap_used_kb(&kv),
         load_1: parse_f64(kv.get("load_1")),
         load_5: parse_f64(kv.get("load_5")),
         load_15: parse_f64(kv.get("load_15")),
         disk_root_total_kb: parse_u64(kv.get("disk_root_total_kb")),
         disk_root_used_kb: parse_u64(kv.get("disk_root_used_kb")),
+        filesystems: parse_filesystems(&stdout),
+        network: parse_network(&stdout, parse_f64(kv.get("net_window_s"))),
+        temperatures: parse_thermal_zones(&stdout),
+        gpus: parse_gpus(&stdout),
         processes,
+        sbc: parse_sbc_info(&kv),
     })
 }
 
+// Memory-backed, container-layer and kernel filesystems, which never fill a
+// disk.
+const PSEUDO_FILESYSTEMS: &[&str] = &[
+    "tmpfs", "devtmpfs", "overlay", "squashfs", "ramfs", "proc", "sysfs", "devfs", "efivarfs",
+    "cgroup", "cgroup2", "shm", "udev", "none", "map",
+];
+
+/// Mounts from `df -kPT` (`fs=`) or, without `-T`, `df -kP`
+/// (`fs_untyped=`), with inode counts from `df -iP` (`fs_inodes=`). A
+/// device mounted more than once (bind mounts, btrfs subvolumes) is listed
+/// once, under its shortest mount point.
+fn parse_filesystems(stdout: &str) -> Vec<HostFilesystem> {
+    let mut inodes: HashMap<String, (u64, u64)> = HashMap::new();
+    let mut filesystems: Vec<HostFilesystem> = Vec::new();
+    for line in stdout.lines().map(str::trim) {
+        if let Some(rest) = line.strip_prefix("fs_inodes=") {
+            let fields: Vec<&str> = rest.split_whitespace().collect();
+            if let [_, total, used, _, _, mount @ ..] = fields.as_slice() {
+                if let (Ok(total), Ok(used)) = (total.parse(), used.parse()) {
+                    inodes.insert(mount.join(" "), (total, used));
+                }
+            }
+            continue;
+        }
+        let (typed, rest) = if let Some(rest) = line.strip_prefix("fs=") {
+            (true, rest)
+        } else if let Some(rest) = line.strip_prefix("fs_untyped=") {
+            (false, rest)
+        } else {
+            continue;
+        };
+        let mut fields = rest.split_whitespace();
+        let device = fields.next().unwrap_or_default().to_string();
+        let fs_type = if typed {
+            fields.next().map(str::to_string)
+        } else {
+            None
+        };
+        let mut number = || fields.next().and_then(|v| v.parse::<u64>().ok());
+        let (Some(total_kb), Some(used_kb), Some(available_kb)) = (number(), number(), number())
+        else {
+            continue;
+        };
+        // Capacity, then the mount point, which may contain spaces.
+        fields.next();
+        let mount_point = fields.collect::<Vec<_>>().join(" ");
+        let pseudo = PSEUDO_FILESYSTEMS.contains(&fs_type.as_deref().unwrap_or(&device))
+            || ["/dev", "/proc", "/sys", "/run"]
+                .iter()
+                .any(|dir| mount_point == *dir || mount_point.starts_with(&format!("{dir}/")));
+        if pseudo || mount_point.is_empty() || total_kb == 0 {
+            continue;
+        }
+        if let Some(existing) = filesystems.iter_mut().find(|fs| fs.device == device) {
+            if mount_point.len() < existing.mount_point.len() {
+                existing.mount_point = mount_point;
+            }
+            continue;
+        }
+        filesystems.push(HostFilesystem {
+            mount_point,
+            device,
+            fs_type,
+            total_kb,
+            used_kb,
+            available_kb,
+            inodes_total: None,
+            inodes_used: None,
+        });
+    }
+    for fs in &mut filesystems {
+        if let Some(&(total, used)) = inodes.get(&fs.mount_point).filter(|(total, _)| *total > 0) {
+            fs.inodes_total = Some(total);
+            fs.inodes_used = Some(used);
+        }
+    }
+    filesystems.sort_by(|a, b| a.mount_point.cmp(&b.mount_point));
+    filesystems
+}
+
+// The nominal sampling window, for when /proc/uptime did not give one.
+const LIVE_SAMPLE_WINDOW_SECS: f64 = 0.2;
+
+/// Interfaces from the `net_start=` and `net_end=` samples of /proc/net/dev
+/// (`name rx_bytes tx_bytes`), taken `window_secs` apart. Windows interface
+/// names contain spaces, so the counters are the last two fields.
+fn parse_network(stdout: &str, window_secs: Option<f64>) -> Vec<HostNetInterface> {
+    let window_secs = window_secs
+        .filter(|secs| *secs > 0.0)
+        .unwrap_or(LIVE_SAMPLE_WINDOW_SECS);
+    let parse = |rest: &str| {
+        let mut fields = rest.split_whitespace();
+        let tx = fields.next_back()?.parse::<u64>().ok()?;
+        let rx = fields.next_back()?.parse::<u64>().ok()?;
+        let name = fields.collect::<Vec<_>>().join(" ");
+        (!name.is_empty()).then_some((name, rx, tx))
+    };
+    let mut start: HashMap<String, (u64, u64)> = HashMap::new();
+    let mut network = Vec::new();
+    for line in stdout.lines().map(str::trim) {
+        if let Some((name, rx, tx)) = line.strip_prefix("net_start=").and_then(parse) {
+            start.insert(name, (rx, tx));
+        } else if let Some((name, rx_bytes, tx_bytes)) =
+            line.strip_prefix("net_end=").and_then(parse)
+        {
+            if name == "lo" {
+                continue;
+            }
+            let rate = |before: Option<u64>, after: u64| {
+                before
+                    .and_then(|before| after.checked_sub(before))
+                    .map(|delta| delta as f64 / window_secs)
+            };
+            let before = start.get(&name);
+            network.push(HostNetInterface {
+                rx_bytes_per_sec: rate(before.map(|b| b.0), rx_bytes),
+                tx_bytes_per_sec: rate(before.map(|b| b.1), tx_bytes),
+                name,
+                rx_bytes,
+                tx_bytes,
+            });
+        }
+    }
+    network.sort_by(|a, b| a.name.cmp(&b.name));
+    network
+}
+
+fn swap_used_kb(kv: &HashMap<String, String>) -> Option<u64> {
+    let total = parse_u64(kv.get("swap_total_kb"))?;
+    let free = parse_u64(kv.get("swap_free_kb"))?;
+    Some(total.saturating_sub(free))
+}
+
+// Appended to the live probe for hosts with `extended_probe`. The vendor
+// tools get five seconds each where `timeout` exists; a wedged driver can
+// otherwise hang them for good.
+const EXTENDED_LIVE_PROBE_SCRIPT: &str = r#"
+for ZONE in /sys/class/thermal/thermal_zone*; do
+  [ -r "$ZONE/temp" ] || continue
+  printf 'thermal=%s|%s\n' "$(cat "$ZONE/type" 2>/dev/null || basename "$ZONE")" "$(cat "$ZONE/temp" 2>/dev/null || true)"
+done
+LIMIT=""
+command -v timeout >/dev/null 2>&1 && LIMIT="timeout 5"
+if command -v nvidia-smi >/dev/null 2>&1; then
+  $LIMIT nvidia-smi --query-gpu=index,name,utilization.gpu,memory.used,memory.total,temperature.gpu --format=csv,noheader,nounits 2>/dev/null | sed 's/^/gpu_nvidia=/' || true
+fi
+if command -v rocm-smi >/dev/null 2>&1; then
+  printf 'gpu_rocm=%s\n' "$($LIMIT rocm-smi --showproductname --showuse --showtemp --showmeminfo vram --json 2>/dev/null | tr -d '\n' || true)"
+fi
+"#;
+
+fn live_probe_script(host: &Host, script: &str) -> String {
+    if host.extended_probe {
+        format!("{script}\n{EXTENDED_LIVE_PROBE_SCRIPT}")
+    } else {
+        script.to_string()
+    }
+}
+
+/// `thermal=<type>|<millidegrees>` lines.
+fn parse_thermal_zones(stdout: &str) -> Vec<HostThermalZone> {
+    stdout
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("thermal="))
+        .filter_map(|rest| {
+            let (name, millidegrees) = rest.split_once('|')?;
+            let millidegrees: f64 = millidegrees.trim().parse().ok()?;
+            Some(HostThermalZone {
+                name: name.trim().to_string(),
+                temp_c: (millidegrees / 100.0).round() / 10.0,
+            })
+        })
+        .collect()
+}
+
+/// GPUs from `nvidia-smi` CSV (`gpu_nvidia=`, memory in MiB) and the
+/// `rocm-smi` JSON (`gpu_rocm=`, memory in bytes). Readings a tool reports
+/// as `[N/A]` are `None`.
+fn parse_gpus(stdout: &str) -> Vec<HostGpu> {
+    let mut gpus = Vec::new();
+    for line in stdout.lines().map(str::trim) {
+        if let Some(rest) = line.strip_prefix("gpu_nvidia=") {
+            let fields: Vec<&str> = rest.split(',').map(str::trim).collect();
+            let [index, name, utilization, mem_used, mem_total, temp] = fields.as_slice() else {
+                continue;
+            };
+            let Ok(index) = index.parse() else {
+                continue;
+            };
+            let mib_to_kb = |v: &str| v.parse::<u64>().ok().map(|mib| mib * 1024);
+            gpus.push(HostGpu {
+                vendor: "nvidia".to_string(),
+                index,
+                name: Some(name.to_string()).filter(|name| !name.is_empty()),
+                utilization_percent: utilization.parse().ok(),
+                mem_used_kb: mib_to_kb(mem_used),
+                mem_total_kb: mib_to_kb(mem_total),
+                temp_c: temp.parse().ok(),
+            });
+        } else if let Some(rest) = line.strip_prefix("gpu_rocm=") {
+            let Ok(serde_json::Value::Object(cards)) = serde_json::from_str(rest) else {
+                continue;
+            };
+            for (card, fields) in &cards {
+                let Some(index) = card.strip_prefix("card").and_then(|i| i.parse().ok()) else {
+                    continue;
+                };
+                // Field names vary across rocm-smi versions, so match on
+                // their stable parts.
+                let field = |matches: &dyn Fn(&str) -> bool| {
+                    fields.as_object().and_then(|fields| {
+                        fields
+                            .iter()
+                            .find(|(key, _)| matches(key))
+                            .and_then(|(_, value)| value.as_str())
+                            .map(|value| value.trim().to_string())
+                    })
+                };
+                let bytes_to_kb = |v: Option<String>| v?.parse::<u64>().ok().map(|b| b / 1024);
+                gpus.push(HostGpu {
+                    vendor: "amd".to_string(),
+                    index,
+                    name: field(&|key| key == "Card series" || key == "Card SKU"),
+                    utilization_percent: field(&|key| key.starts_with("GPU use"))
+                        .and_then(|v| v.parse().ok()),
+                    mem_used_kb: bytes_to_kb(field(&|key| key.starts_with("VRAM Total Used"))),
+                    mem_total_kb: bytes_to_kb(field(&|key| key == "VRAM Total Memory (B)")),
+                    temp_c: field(&|key| key.starts_with("Temperature") && key.contains("edge"))
+                        .or_else(|| field(&|key| key.starts_with("Temperature")))
+                        .and_then(|v| v.parse().ok()),
+                });
+            }
+        }
+    }
+    gpus
+}
+
+fn parse_sbc_info(kv: &HashMap<String, String>) -> Option<HostSbcInfo> {
+    let model = maybe_text(kv.get("sbc_model"))?;
+    let throttled_raw = maybe_text(kv.get("sbc_throttled"));
+    let bits = throttled_raw
+        .as_deref()
+        .and_then(|raw| u32::from_str_radix(raw.trim_start_matches("0x"), 16).ok())
+        .unwrap_or(0);
+    let bit = |n: u32| bits & (1 << n) != 0;
+    Some(HostSbcInfo {
+        model,
+        soc_temp_c: parse_f64(kv.get("sbc_soc_temp_c")),
+        throttled_raw,
+        under_voltage_now: bit(0),
+        freq_capped_now: bit(1),
+        throttled_now: bit(2),
+        soft_temp_limit_now: bit(3),
+        under_voltage_occurred: bit(16),
+        freq_capped_occurred: bit(17),
+        throttled_occurred: bit(18),
+        soft_temp_limit_occurred: bit(19),
+    })
+}
+
+// One pass over /proc with no sleep and no process list, for hosts where the
+// full probe is too heavy (routers, small boards). Linux only.
+const LIVE_PROBE_LITE_SCRIPT: &str = r#"
+awk '
+FILENAME == "/proc/stat" && $1 == "cpu" { $1 = ""; print "cpu_ticks=" substr($0, 2) }
+FILENAME == "/proc/stat" && $1 ~ /^cpu[0-9]/ { cores++ }
+FILENAME == "/proc/uptime" { print "uptime_seconds=" int($1) }
+FILENAME == "/proc/meminfo" && $1 == "MemTotal:" { print "mem_total_kb=" $2 }
+FILENAME == "/proc/meminfo" && $1 == "MemAvailable:" { print "mem_available_kb=" $2 }
+FILENAME == "/proc/meminfo" && $1 == "MemFree:" { print "mem_free_kb=" $2 }
+FILENAME == "/proc/meminfo" && $1 == "Cached:" { print "mem_page_cache_kb=" $2 }
+FILENAME == "/proc/meminfo" && $1 == "SwapTotal:" { print "swap_total_kb=" $2 }
+FILENAME == "/proc/meminfo" && $1 == "SwapFree:" { print "swap_free_kb=" $2 }
+FILENAME == "/proc/loadavg" { print "load_1=" $1; print "load_5=" $2; print "load_15=" $3 }
+END { print "cpu_cores=" cores }
+' /proc/stat /proc/uptime /proc/meminfo /proc/loadavg
+"#;
+
+// Last `/proc/stat` cpu ticks per host; the lite probe reads them once, so
+// CPU usage is the difference to the previous probe.
+fn live_cpu_samples() -> &'static Mutex<HashMap<String, Vec<u64>>> {
+    static SAMPLES: OnceLock<Mutex<HashMap<String, Vec<u64>>>> = OnceLock::new();
+    SAMPLES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn round_tenth(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+/// Busy, user, system, iowait and idle percent between two samples, with the
+/// same arithmetic as the full probe.
+fn cpu_usage_between(previous: &[u64], current: &[u64]) -> Option<[f64; 5]> {
+    if previous.len() < 5 || current.len() < 5 {
+        return None;
+    }
+    let total = |ticks: &[u64]| ticks.iter().take(10).sum::<u64>();
+    let dt = total(current)
+        .checked_sub(total(previous))
+        .filter(|dt| *dt > 0)? as f64;
+    let delta = |i: usize| current[i].saturating_sub(previous[i]) as f64 * 100.0 / dt;
+    let idle = delta(3);
+    Some([
+        round_tenth(100.0 - idle),
+        round_tenth(delta(0) + delta(1)),
+        round_tenth(delta(2)),
+        round_tenth(delta(4)),
+        round_tenth(idle),
+    ])
+}
+
+fn parse_live_lite(host_id: &str, stdout: &str) -> HostLiveInfo {
+    let (kv, _) = parse_kv(stdout);
+    let ticks: Vec<u64> = kv
+        .get("cpu_ticks")
+        .map(|line| {
+            line.split_whitespace()
+                .filter_map(|v| v.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    let previous = live_cpu_samples()
+        .lock()
+        .ok()
+        .and_then(|mut samples| samples.insert(host_id.to_string(), ticks.clone()));
+    let cpu = previous.and_then(|previous| cpu_usage_between(&previous, &ticks));
+    let mem_total_kb = parse_u64(kv.get("mem_total_kb"));
+    let mem_available_kb = parse_u64(kv.get("mem_available_kb"));
+    HostLiveInfo {
+        // Windows hosts report a sampled load rather than tick counters.
+        cpu_percent: cpu
+            .map(|cpu| cpu[0])
+            .or_else(|| parse_f64(kv.get("cpu_percent"))),
+        cpu_user_percent: cpu.map(|cpu| cpu[1]),
+        cpu_system_percent: cpu.map(|cpu| cpu[2]),
+        cpu_iowait_percent: cpu.map(|cpu| cpu[3]),
+        cpu_idle_percent: cpu.map(|cpu| cpu[4]),
+        cpu_cores: parse_u32(kv.get("cpu_cores")),
+        uptime_seconds: parse_u64(kv.get("uptime_seconds")),
+        mem_total_kb,
+        mem_used_kb: mem_total_kb
+            .zip(mem_available_kb)
+            .map(|(total, available)| total.saturating_sub(available)),
+        mem_free_kb: parse_u64(kv.get("mem_free_kb")),
+        mem_page_cache_kb: parse_u64(kv.get("mem_page_cache_kb")),
+        swap_total_kb: parse_u64(kv.get("swap_total_kb")),
+        swap_used_kb: swap_used_kb(&kv),
+        load_1: parse_f64(kv.get("load_1")),
+        load_5: parse_f64(kv.get("load_5")),
+        load_15: parse_f64(kv.get("load_15")),
+        disk_root_total_kb: None,
+        disk_root_used_kb: None,
+        filesystems: Vec::new(),
+        network: Vec::new(),
+        temperatures: parse_thermal_zones(stdout),
+        gpus: parse_gpus(stdout),
+        processes: Vec::new(),
+        sbc: None,
+    }
+}
+
+fn host_probe_live_lite_impl(host: Host) -> Result<HostLiveInfo, String> {
+    let stdout = run_portable_probe(
+        &host,
+        &live_probe_script(&host, LIVE_PROBE_LITE_SCRIPT),
+        WINDOWS_LIVE_PROBE_SCRIPT,
+    )?;
+    Ok(parse_live_lite(&host.id, &stdout))
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HostRebootStatus {
+    pub reboot_required: bool,
+    /// Set by the distro (`/var/run/reboot-required`, `needs-restarting -r`).
+    pub flagged_by_system: bool,
+    pub running_kernel: Option<String>,
+    pub installed_kernel: Option<String>,
+    /// From `/var/run/reboot-required.pkgs` (Debian/Ubuntu only).
+    pub packages: Vec<String>,
+}
+
+// The newest installed kernel comes from the package database on rpm systems
+// and from /boot otherwise; both print in `uname -r` form.
+const REBOOT_STATUS_SCRIPT: &str = r#"
+printf 'running_kernel=%s\n' "$(uname -r 2>/dev/null || true)"
+FLAGGED=0
+[ -e /var/run/reboot-required ] && FLAGGED=1
+if command -v needs-restarting >/dev/null 2>&1; then
+  needs-restarting -r >/dev/null 2>&1
+  [ "$?" = "1" ] && FLAGGED=1
+fi
+printf 'flagged=%s\n' "$FLAGGED"
+INSTALLED=""
+if command -v rpm >/dev/null 2>&1; then
+  INSTALLED="$(rpm -q --qf '%{VERSION}-%{RELEASE}.%{ARCH}\n' kernel-core kernel 2>/dev/null | grep -v 'not installed' | sort -V 2>/dev/null | tail -n 1 || true)"
+fi
+if [ -z "$INSTALLED" ]; then
+  INSTALLED="$(ls -1 /boot/vmlinuz-* 2>/dev/null | sed 's|^/boot/vmlinuz-||' | grep -v rescue | sort -V 2>/dev/null | tail -n 1 || true)"
+fi
+printf 'installed_kernel=%s\n' "$INSTALLED"
+if [ -r /var/run/reboot-required.pkgs ]; then
+  printf 'packages=%s\n' "$(sort -u /var/run/reboot-required.pkgs | tr '\n' ' ')"
+fi
+"#;
+
+fn parse_reboot_status(stdout: &str) -> HostRebootStatus {
+    let (kv, _) = parse_kv(stdout);
+    let running_kernel = maybe_text(kv.get("running_kernel"));
+    let installed_kernel = maybe_text(kv.get("installed_kernel"));
+    let flagged_by_system = kv.get("flagged").is_some_and(|v| v == "1");
+    let kernel_outdated = matches!(
+        (&running_kernel, &installed_kernel),
+        (Some(running), Some(installed)) if running != installed
+    );
+    HostRebootStatus {
+        reboot_required: flagged_by_system || kernel_outdated,
+        flagged_by_system,
+        running_kernel,
+        installed_kernel,
+        packages: kv
+            .get("packages")
+            .map(|v| v.split_whitespace().map(str::to_string).collect())
+            .unwrap_or_default(),
+    }
+}
+
+const DEFAULT_OOM_WINDOW_HOURS: u32 = 24;
+const MAX_OOM_WINDOW_HOURS: u32 = 24 * 30;
+
+/// A process the kernel's OOM killer ended.
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HostOomEvent {
+    /// RFC 3339, in UTC; `None` when the log line had no usable timestamp.
+    pub at: Option<String>,
+    pub pid: Option<u32>,
+    pub process: String,
+    pub anon_rss_kb: Option<u64>,
+    /// Killed for exceeding a memory cgroup limit rather than system memory.
+    pub cgroup: bool,
+}
+
+// The journal is filtered by time remotely; `dmesg` (ring buffer only) is
+// filtered by the caller.
+const OOM_EVENTS_SCRIPT: &str = r#"
+if command -v journalctl >/dev/null 2>&1; then
+  journalctl -k -q --no-pager -o short-iso --since "-__HOURS__h" 2>/dev/null | grep 'Killed process' && exit 0
+fi
+dmesg --time-format iso 2>/dev/null | grep 'Killed process' || true
+"#;
+
+// journald prints `2026-01-01T10:00:00+0000 host kernel: ...`, dmesg
+// `2026-01-01T10:00:00,123456+00:00 ...`.
+fn parse_log_timestamp(token: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    let token = token.replace(',', ".");
+    chrono::DateTime::parse_from_rfc3339(&token)
+        .or_else(|_| chrono::DateTime::parse_from_str(&token, "%Y-%m-%dT%H:%M:%S%.f%z"))
+        .ok()
+        .map(|at| at.with_timezone(&chrono::Utc))
+}
+
+fn parse_oom_line(line: &str) -> Option<(Option<chrono::DateTime<chrono::Utc>>, HostOomEvent)> {
+    let rest = &line[line.find("Killed process ")? + "Killed process ".len()..];
+    let (pid, rest) = rest.split_once(' ').unwrap_or((rest, ""));
+    let process = rest
+        .strip_prefix('(')
+        .and_then(|name| name.split_once(')'))
+        .map(|(name, _)| name.to_string())?;
+    let anon_rss_kb = line
+        .split_once("anon-rss:")
+        .and_then(|(_, v)| v.split_once("kB"))
+        .and_then(|(v, _)| v.trim().parse().ok());
+    let at = line.split_whitespace().next().and_then(parse_log_timestamp);
+    Some((
+        at,
+        HostOomEvent {
+            at: at.map(|at| at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)),
+            pid: pid.parse().ok(),
+            process,
+            anon_rss_kb,
+            cgroup: line.contains("Memory cgroup out of memory"),
+        },
+    ))
+}
+
+/// OOM kills since `since`, newest first. Lines without a timestamp are kept,
+/// after the dated ones.
+fn parse_oom_events(stdout: &str, since: chrono::DateTime<chrono::Utc>) -> Vec<HostOomEvent> {
+    let mut events: Vec<_> = stdout
+        .lines()
+        .filter_map(parse_oom_line)
+        .filter(|(at, _)| at.is_none_or(|at| at >= since))
+        .collect();
+    events.sort_by_key(|(at, _)| std::cmp::Reverse(*at));
+    events.into_iter().map(|(_, event)| event).collect()
+}
+
+/// A container as listed by `docker ps -a`, with its usage from
+/// `docker stats` while it runs.
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HostDockerContainer {
+    pub id: String,
+    pub name: String,
+    pub image: String,
+    /// `running`, `exited`, `paused`, `created`, ...
+    pub state: String,
+    /// Docker's summary, e.g. `Up 3 hours (healthy)`.
+    pub status: String,
+    pub cpu_percent: Option<f64>,
+    pub mem_used_bytes: Option<u64>,
+    pub mem_limit_bytes: Option<u64>,
+    pub mem_percent: Option<f64>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HostDocker {
+    /// Whether the containers could be listed; `denied` also covers a daemon
+    /// that is not running.
+    pub access: ProbeAccess,
+    pub containers: Vec<HostDockerContainer>,
+}
+
+// Docker runs through `sudo -n` when the socket is not writable, as for the
+// privileged probe check. `{{json .}}` rather than `--format json`, which
+// needs Docker 23.
+const DOCKER_PROBE_SCRIPT: &str = r#"
+if ! command -v docker >/dev/null 2>&1; then
+  printf 'access=missing\n'
+  exit 0
+fi
+if docker version --format '{{.Server.Version}}' >/dev/null 2>&1; then
+  DOCKER="docker"
+  printf 'access=direct\n'
+elif command -v sudo >/dev/null 2>&1 && sudo -n docker version --format '{{.Server.Version}}' >/dev/null 2>&1; then
+  DOCKER="sudo -n docker"
+  printf 'access=sudo\n'
+else
+  printf 'access=denied\n'
+  exit 0
+fi
+$DOCKER ps -a --no-trunc --format 'ps={{json .}}' 2>/dev/null || true
+$DOCKER stats --no-stream --no-trunc --format 'stats={{json .}}' 2>/dev/null || true
+"#;
+
+/// Docker's sizes: `12.5MiB`, `1.944GiB`, `3.2kB`, `0B`.
+fn parse_docker_size(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let multiplier: f64 = match unit.trim() {
+        "" | "B" => 1.0,
+        "kB" | "KB" => 1e3,
+        "MB" => 1e6,
+        "GB" => 1e9,
+        "TB" => 1e12,
+        "KiB" => 1024.0,
+        "MiB" => 1024.0 * 1024.0,
+        "GiB" => 1024.0 * 1024.0 * 1024.0,
+        "TiB" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+    Some((number * multiplier).round() as u64)
+}
+
+fn parse_docker_percent(value: Option<&serde_json::Value>) -> Option<f64> {
+    value?.as_str()?.trim().trim_end_matches('%').parse().ok()
+}
+
+fn parse_docker(stdout: &str) -> HostDocker {
+    let (kv, _) = parse_kv(stdout);
+    let field = |json: &serde_json::Value, key: &str| {
+        json.get(key)
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .trim()
+            .to_string()
+    };
+    let lines = |prefix: &'static str| {
+        stdout
+            .lines()
+            .filter_map(move |line| line.trim().strip_prefix(prefix))
+            .filter_map(|json| serde_json::from_str::<serde_json::Value>(json).ok())
+    };
+    let stats: Vec<serde_json::Value> = lines("stats=").collect();
+    let containers = lines("ps=")
+        .map(|ps| {
+            let id = field(&ps, "ID");
+            let name = field(&ps, "Names");
+            let status = field(&ps, "Status");
+            // `State` is missing before Docker 20.10; derive it from the
+            // status then.
+            let mut state = field(&ps, "State");
+            if state.is_empty() {
+                state = match status.split_whitespace().next() {
+                    Some("Up") if status.contains("(Paused)") => "paused".to_string(),
+                    Some("Up") => "running".to_string(),
+                    Some(word) => word.to_ascii_lowercase(),
+                    None => String::new(),
+                };
+            }
+            let usage = stats.iter().find(|stats| {
+                let stats_id = field(stats, "ID");
+                (!stats_id.is_empty() && stats_id == id) || field(stats, "Name") == name
+            });
+            let mem = usage.map(|usage| field(usage, "MemUsage"));
+            let (mem_used, mem_limit) = mem
+                .as_deref()
+                .and_then(|mem| mem.split_once('/'))
+                .unwrap_or_default();
+            HostDockerContainer {
+                image: field(&ps, "Image"),
+                cpu_percent: usage.and_then(|usage| parse_docker_percent(usage.get("CPUPerc"))),
+                mem_used_bytes: parse_docker_size(mem_used),
+                mem_limit_bytes: parse_docker_size(mem_limit),
+                mem_percent: usage.and_then(|usage| parse_docker_percent(usage.get("MemPerc"))),
+                id,
+                name,
+                state,
+                status,
+            }
+        })
+        .collect();
+    HostDocker {
+        access: parse_probe_access(kv.get("access")),
+        containers,
+    }
+}
+
+/// A systemd service and its state, as `systemctl` reports it.
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HostService {
+    pub unit: String,
+    pub description: Option<String>,
+    /// `loaded`, `not-found`, `masked`; `not-loaded` for an installed unit
+    /// systemd has not loaded (usually a disabled, stopped one).
+    pub load_state: String,
+    /// `active`, `inactive`, `failed`, `activating`, ...
+    pub active_state: String,
+    /// `running`, `exited`, `dead`, ...
+    pub sub_state: String,
+    /// The unit file state: `enabled`, `disabled`, `static`, `masked`, ...
+    pub enabled: Option<String>,
+}
+
+// Loaded units come from `list-units`, installed ones (with their enablement)
+// from `list-unit-files`; a unit may be in either or both.
+const SERVICES_PROBE_SCRIPT: &str = r#"
+if ! command -v systemctl >/dev/null 2>&1; then
+  printf 'systemd=0\n'
+  exit 0
+fi
+printf 'systemd=1\n'
+systemctl list-units --all --type=service --plain --no-legend --no-pager __PATTERNS__ 2>/dev/null | sed 's/^/unit=/' || true
+systemctl list-unit-files --type=service --no-legend --no-pager __PATTERNS__ 2>/dev/null | sed 's/^/file=/' || true
+"#;
+
+const SERVICE_RESTART_SCRIPT: &str = r#"
+if [ "$(id -u 2>/dev/null)" = "0" ]; then
+  systemctl restart __UNIT__
+else
+  sudo -n systemctl restart __UNIT__
+fi
+"#;
+
+/// A unit name or glob for `systemctl`; never an option.
+fn validate_unit_pattern(pattern: &str) -> Result<String, String> {
+    let pattern = pattern.trim();
+    let valid = !pattern.is_empty()
+        && !pattern.starts_with('-')
+        && pattern
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || ":_.@-\\*?[]".contains(c));
+    if !valid {
+        return Err(format!("Invalid unit name: {pattern}"));
+    }
+    Ok(pattern.to_string())
+}
+
+fn services_probe_script(patterns: &[String]) -> String {
+    let patterns: Vec<String> = patterns.iter().map(|p| shell_quote(p)).collect();
+    SERVICES_PROBE_SCRIPT.replace("__PATTERNS__", &patterns.join(" "))
+}
+
+fn parse_services(stdout: &str) -> Result<Vec<HostService>, String> {
+    let (kv, _) = parse_kv(stdout);
+    if kv.get("systemd").is_some_and(|v| v == "0") {
+        return Err("systemd is not available on this host".to_string());
+    }
+    let mut services: BTreeMap<String, HostService> = BTreeMap::new();
+    for line in stdout.lines().map(str::trim) {
+        if let Some(rest) = line.strip_prefix("unit=") {
+            // Older systemd marks failed units with a bullet even with --plain.
+            let rest = rest.trim_start_matches(['●', '*', ' ']);
+            let mut words = rest.splitn(5, char::is_whitespace);
+            let (Some(unit), Some(load), Some(active), Some(sub)) =
+                (words.next(), words.next(), words.next(), words.next())
+            else {
+                continue;
+            };
+            let description = words.next().map(str::trim).filter(|d| !d.is_empty());
+            let service = services
+                .entry(unit.to_string())
+                .or_insert_with(|| HostService {
+                    unit: unit.to_string(),
+                    description: None,
+                    load_state: String::new(),
+                    active_state: String::new(),
+                    sub_state: String::new(),
+                    enabled: None,
+                });
+            service.description = description.map(str::to_string);
+            service.load_state = load.to_string();
+            service.active_state = active.to_string();
+            service.sub_state = sub.to_string();
+        } else if let Some(rest) = line.strip_prefix("file=") {
+            let mut words = rest.split_whitespace();
+            let (Some(unit), Some(state)) = (words.next(), words.next()) else {
+                continue;
+            };
+            services
+                .entry(unit.to_string())
+                .or_insert_with(|| HostService {
+                    unit: unit.to_string(),
+                    description: None,
+                    load_state: "not-loaded".to_string(),
+                    active_state: "inactive".to_string(),
+                    sub_state: "dead".to_string(),
+                    enabled: None,
+                })
+                .enabled = Some(state.to_string());
+        }
+    }
+    Ok(services.into_values().collect())
+}
+
+fn host_probe_services_impl(host: &Host, patterns: &[String]) -> Result<Vec<HostService>, String> {
+    parse_services(&run_probe(host, &services_probe_script(patterns))?)
+}
+
+// `sudo -n` never prompts: anything that would need a password counts as
+// denied, so privileged probes never hang on (or lock out) the account.
+const PRIVILEGED_PROBE_CHECK_SCRIPT: &str = r#"
+IS_ROOT=0
+[ "$(id -u 2>/dev/null)" = "0" ] && IS_ROOT=1
+USER_GROUPS="$(id -nG 2>/dev/null || true)"
+for TOOL in smartctl docker journalctl; do
+  TOOL_PATH="$(command -v "$TOOL" 2>/dev/null || true)"
+  if [ -z "$TOOL_PATH" ]; then
+    printf '%s=missing\n' "$TOOL"
+    continue
+  fi
+  DIRECT="$IS_ROOT"
+  case "$TOOL" in
+    docker) [ -w /var/run/docker.sock ] && DIRECT=1 ;;
+    journalctl)
+      for G in $USER_GROUPS; do
+        case "$G" in systemd-journal|adm|wheel) DIRECT=1 ;; esac
+      done
+      ;;
+  esac
+  if [ "$DIRECT" = "1" ]; then
+    printf '%s=direct\n' "$TOOL"
+  elif command -v sudo >/dev/null 2>&1 && sudo -n -l "$TOOL_PATH" >/dev/null 2>&1; then
+    printf '%s=sudo\n' "$TOOL"
+  else
+    printf '%s=denied\n' "$TOOL"
+  fi
+done
+"#;
+
+// Prints `tool=<first line of its version output>`, empty when missing.
+const RUNTIME_PROBE_SCRIPT: &str = r#"
+probe_version() {
+  NAME="$1"
+  shift
+  if command -v "$NAME" >/dev/null 2>&1; then
+    printf '%s=%s\n' "$NAME" "$("$@" 2>&1 | head -n 1)"
+  else
+    printf '%s=\n' "$NAME"
+  fi
+}
+probe_version python3 python3 --version
+probe_version node node --version
+probe_version git git --version
+probe_version rsync rsync --version
+probe_version tmux tmux -V
+"#;
+
+/// The first version-looking word: `Python 3.11.2` -> `3.11.2`,
+/// `v20.11.1` -> `20.11.1`, `tmux 3.3a` -> `3.3a`.
+fn parse_tool_version(line: Option<&String>) -> Option<String> {
+    let line = line?.trim();
+    if line.is_empty() {
+        return None;
+    }
+    let version = line
+        .split_whitespace()
+        .map(|word| word.trim_start_matches('v'))
+        .find(|word| word.starts_with(|c: char| c.is_ascii_digit()))
+        .unwrap_or("unknown");
+    Some(version.to_string())
+}
+
+fn parse_runtimes(stdout: &str) -> HostRuntimes {
+    let (kv, _) = parse_kv(stdout);
+    let mut runtimes = HostRuntimes {
+        python3: parse_tool_version(kv.get("python3")),
+        node: parse_tool_version(kv.get("node")),
+        git: parse_tool_version(kv.get("git")),
+        rsync: parse_tool_version(kv.get("rsync")),
+        tmux: parse_tool_version(kv.get("tmux")),
+        missing: Vec::new(),
+    };
+    runtimes.missing = [
+        ("python3", &runtimes.python3),
+        ("node", &runtimes.node),
+        ("git", &runtimes.git),
+        ("rsync", &runtimes.rsync),
+        ("tmux", &runtimes.tmux),
+    ]
+    .into_iter()
+    .filter(|(_, version)| version.is_none())
+    .map(|(name, _)| name.to_string())
+    .collect();
+    runtimes
+}
+
+/// The host's runtimes, probed once and then served from the capability
+/// cache until `refresh` is set.
+pub(crate) fn host_runtimes_cached(host: &Host, refresh: bool) -> Result<HostRuntimes, String> {
+    if !refresh {
+        let cached = host_capabilities_cache()
+            .lock()
+            .ok()
+            .and_then(|cache| cache.get(&host.id).and_then(|caps| caps.runtimes.clone()));
+        if let Some(runtimes) = cached {
+            return Ok(runtimes);
+        }
+    }
+    let runtimes = parse_runtimes(&run_probe(host, RUNTIME_PROBE_SCRIPT)?);
+    if let Ok(mut cache) = host_capabilities_cache().lock() {
+        cache.entry(host.id.clone()).or_default().runtimes = Some(runtimes.clone());
+    }
+    Ok(runtimes)
+}
+
+fn parse_probe_access(value: Option<&String>) -> ProbeAccess {
+    match value.map(|v| v.trim()) {
+        Some("direct") => ProbeAccess::Direct,
+        Some("sudo") => ProbeAccess::Sudo,
+        Some("denied") => ProbeAccess::Denied,
+        _ => ProbeAccess::Missing,
+    }
+}
+
+fn parse_privileged_probe_access(stdout: &str) -> PrivilegedProbeAccess {
+    let (kv, _) = parse_kv(stdout);
+    PrivilegedProbeAccess {
+        smartctl: parse_probe_access(kv.get("smartctl")),
+        docker: parse_probe_access(kv.get("docker")),
+        journalctl: parse_probe_access(kv.get("journalctl")),
+    }
+}
+
+fn host_privileged_probes_impl<R: Runtime>(
+    app: &AppHandle<R>,
+    host: Host,
+    refresh: bool,
+) -> Result<PrivilegedProbeAccess, String> {
+    if !refresh {
+        let cached = host_capabilities_cache().lock().ok().and_then(|cache| {
+            cache
+                .get(&host.id)
+                .and_then(|caps| caps.privileged_probes.clone())
+        });
+        if let Some(access) = cached {
+            return Ok(access);
+        }
+    }
+    let access = parse_privileged_probe_access(&run_probe(&host, PRIVILEGED_PROBE_CHECK_SCRIPT)?);
+    let docker = access.docker != ProbeAccess::Missing;
+    record_probe_facts(
+        app,
+        &host,
+        BTreeMap::from([("docker".to_string(), docker.to_string())]),
+    );
+    if let Ok(mut cache) = host_capabilities_cache().lock() {
+        cache.entry(host.id.clone()).or_default().privileged_probes = Some(access.clone());
+    }
+    Ok(access)
+}
+
+/// Which privileged probes (SMART, Docker, journal) the host allows, so the
+/// UI can hide panels that would only show permission errors. Cached per
+/// host until `refresh` is set.
 #[tauri::command]
-pub async fn host_probe_static(host: Host) -> Result<HostStaticInfo, String> {
-    tauri::async_runtime::spawn_blocking(move || host_probe_static_impl(host))
-        .await
-        .map_err(|e| e.to_string())?
+pub async fn host_privileged_probes<R: Runtime>(
+    host: Host,
+    refresh: Option<bool>,
+    op_id: String,
+    app: AppHandle<R>,
+) -> Result<PrivilegedProbeAccess, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        operations::run(op_id, "host_privileged_probes", PROBE_TIMEOUT, || {
+            host_privileged_probes_impl(&app, host, refresh.unwrap_or(false))
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
 }
 
+/// Which of python3, node, git, rsync and tmux the host has, and in which
+/// version.
 #[tauri::command]
-pub async fn host_probe_live(host: Host) -> Result<HostLiveInfo, String> {
-    tauri::async_runtime::spawn_blocking(move || host_probe_live_impl(host))
-        .await
-        .map_err(|e| e.to_string())?
+pub async fn host_runtimes(
+    host: Host,
+    refresh: Option<bool>,
+    op_id: String,
+) -> Result<HostRuntimes, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        operations::run(op_id, "host_runtimes", PROBE_TIMEOUT, || {
+            host_runtimes_cached(&host, refresh.unwrap_or(false))
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub fn host_capabilities(host_id: String) -> Result<HostCapabilities, String> {
+    let cache = host_capabilities_cache()
+        .lock()
+        .map_err(|_| "host capabilities cache poisoned".to_string())?;
+    Ok(cache.get(host_id.trim()).cloned().unwrap_or_default())
+}
+
+/// Whether the host is waiting for a reboot, for the host list badge.
+#[tauri::command]
+pub async fn host_reboot_status(host: Host, op_id: String) -> Result<HostRebootStatus, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        operations::run(op_id, "host_reboot_status", PROBE_TIMEOUT, || {
+            run_probe(&host, REBOOT_STATUS_SCRIPT).map(|stdout| parse_reboot_status(&stdout))
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Processes the OOM killer ended in the last `hours` (24 by default), from
+/// the kernel journal or, without journald, the kernel ring buffer.
+#[tauri::command]
+pub async fn host_oom_events(
+    host: Host,
+    hours: Option<u32>,
+    op_id: String,
+) -> Result<Vec<HostOomEvent>, String> {
+    let hours = hours
+        .unwrap_or(DEFAULT_OOM_WINDOW_HOURS)
+        .clamp(1, MAX_OOM_WINDOW_HOURS);
+    tauri::async_runtime::spawn_blocking(move || {
+        operations::run(op_id, "host_oom_events", PROBE_TIMEOUT, || {
+            let since = chrono::Utc::now() - chrono::Duration::hours(hours.into());
+            let script = OOM_EVENTS_SCRIPT.replace("__HOURS__", &hours.to_string());
+            run_probe(&host, &script).map(|stdout| parse_oom_events(&stdout, since))
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// The host's Docker containers, running or not, with CPU and memory use of
+/// the running ones.
+#[tauri::command]
+pub async fn host_probe_docker(host: Host, op_id: String) -> Result<HostDocker, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        operations::run(op_id, "host_probe_docker", PROBE_TIMEOUT, || {
+            run_probe(&host, DOCKER_PROBE_SCRIPT).map(|stdout| parse_docker(&stdout))
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// State of the systemd services matching `patterns` (unit names or globs
+/// such as `nginx*`), or of every service when there are none.
+#[tauri::command]
+pub async fn host_probe_services(
+    host: Host,
+    patterns: Option<Vec<String>>,
+    op_id: String,
+) -> Result<Vec<HostService>, String> {
+    let patterns = patterns
+        .unwrap_or_default()
+        .iter()
+        .map(|p| validate_unit_pattern(p))
+        .collect::<Result<Vec<_>, _>>()?;
+    tauri::async_runtime::spawn_blocking(move || {
+        operations::run(op_id, "host_probe_services", PROBE_TIMEOUT, || {
+            host_probe_services_impl(&host, &patterns)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Restart a systemd unit, as root or through `sudo -n`, and return its
+/// state afterwards. Fails rather than prompt when sudo wants a password.
+#[tauri::command]
+pub async fn host_service_restart(
+    host: Host,
+    unit: String,
+    op_id: String,
+) -> Result<HostService, String> {
+    let mut unit = validate_unit_pattern(&unit)?;
+    if unit.contains(['*', '?', '[']) {
+        return Err("Restart takes a single unit, not a pattern".to_string());
+    }
+    // `list-units` patterns need the suffix that `restart` would assume.
+    if !unit.contains('.') {
+        unit.push_str(".service");
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        operations::run(op_id, "host_service_restart", PROBE_TIMEOUT, || {
+            let script = SERVICE_RESTART_SCRIPT.replace("__UNIT__", &shell_quote(&unit));
+            run_probe(&host, &script)?;
+            let units = [unit.clone()];
+            host_probe_services_impl(&host, &units)?
+                .into_iter()
+                .find(|service| service.unit == unit)
+                .ok_or_else(|| "Unit not found".to_string())
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+static STATIC_INFO_CACHE: ProbeCache<HostStaticInfo> = ProbeCache::new();
+
+/// Probe OS and hardware facts. When the machine reports a name that matches
+/// none of the host's names, a `host:rename-suggested` event is emitted.
+/// Results are kept for the app run, or `max_age_secs` if given, unless
+/// `force_refresh` is set.
+#[tauri::command]
+pub async fn host_probe_static<R: Runtime>(
+    host: Host,
+    force_refresh: Option<bool>,
+    max_age_secs: Option<u64>,
+    op_id: String,
+    app: AppHandle<R>,
+) -> Result<HostStaticInfo, String> {
+    crate::safe_mode::ensure_not_safe_mode("Host monitoring")?;
+    tauri::async_runtime::spawn_blocking(move || {
+        operations::run(op_id, "host_probe_static", PROBE_TIMEOUT, || {
+            let (info, cached) = STATIC_INFO_CACHE.get_or_probe(
+                &host,
+                max_age_secs.map(Duration::from_secs),
+                force_refresh.unwrap_or(false),
+                || host_probe_static_impl(&app, host.clone()),
+            )?;
+            // A cached result was already checked when it was probed.
+            if let (false, Some(hostname)) = (cached, info.hostname.as_deref()) {
+                emit_rename_suggestion(&app, &host, hostname);
+            }
+            Ok(info)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Sample CPU, memory, load and disk. Hosts set to the lite probe (or all
+/// hosts, with `live_probe_lite` in settings) skip the disk and process list
+/// and report CPU usage from the previous sample onward.
+#[tauri::command]
+pub async fn host_probe_live(host: Host, op_id: String) -> Result<HostLiveInfo, String> {
+    crate::safe_mode::ensure_not_safe_mode("Host monitoring")?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let lite_everywhere = live_probe_lite_everywhere();
+        operations::run(op_id, "host_probe_live", LIVE_PROBE_TIMEOUT, || {
+            probe_live_and_record(host, lite_everywhere)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+fn live_probe_lite_everywhere() -> bool {
+    crate::host_store::settings_load().is_ok_and(|settings| settings.live_probe_lite)
+}
+
+fn probe_live_and_record(host: Host, lite_everywhere: bool) -> Result<HostLiveInfo, String> {
+    let result = if lite_everywhere || host.live_probe_lite {
+        host_probe_live_lite_impl(host.clone())
+    } else {
+        host_probe_live_impl(host.clone())
+    };
+    let observation = match &result {
+        Ok(info) => Observation::LiveProbe(Ok(info)),
+        Err(error) => Observation::LiveProbe(Err(error.as_str())),
+    };
+    alerts::observe(&alerts::alert_rules(), &host, observation);
+    let info = result?;
+    crate::metrics_history::record(&host.id, &info);
+    Ok(info)
+}
+
+/// One host of `hosts_probe_all_live`: its sample, or why there is none.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostLiveEntry {
+    pub host_id: String,
+    pub live: Option<HostLiveInfo>,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostsLiveSummary {
+    /// In host list order.
+    pub hosts: Vec<HostLiveEntry>,
+    pub reachable: u32,
+    pub failed: u32,
+    /// Hosts with live metrics turned off, which were not probed.
+    pub skipped: Vec<String>,
+}
+
+fn summarize_live(hosts: Vec<HostLiveEntry>, skipped: Vec<String>) -> HostsLiveSummary {
+    let reachable = hosts.iter().filter(|entry| entry.live.is_some()).count() as u32;
+    HostsLiveSummary {
+        failed: hosts.len() as u32 - reachable,
+        reachable,
+        hosts,
+        skipped,
+    }
+}
+
+/// Live-probe every host at once for a fleet overview, `concurrency` (8 by
+/// default) at a time, each within `timeout_secs` (20 by default). Deleted
+/// hosts are left out and hosts with live metrics turned off are skipped.
+/// Finished hosts count towards the job's progress.
+#[tauri::command]
+pub async fn hosts_probe_all_live(
+    concurrency: Option<usize>,
+    timeout_secs: Option<u64>,
+    op_id: String,
+) -> Result<HostsLiveSummary, String> {
+    crate::safe_mode::ensure_not_safe_mode("Host monitoring")?;
+    let concurrency = concurrency
+        .unwrap_or(DEFAULT_FLEET_PROBE_CONCURRENCY)
+        .clamp(1, MAX_FLEET_PROBE_CONCURRENCY);
+    let per_host = timeout_secs
+        .map(|secs| Duration::from_secs(secs.clamp(1, PROBE_TIMEOUT.as_secs())))
+        .unwrap_or(LIVE_PROBE_TIMEOUT);
+    tauri::async_runtime::spawn_blocking(move || {
+        let (hosts, skipped): (Vec<Host>, Vec<Host>) = crate::host_store::hosts_load()?
+            .into_iter()
+            .filter(|host| !host.deleted)
+            .partition(|host| host.host_live_metrics_enabled);
+        let host_ids: Vec<String> = hosts.iter().map(|host| host.id.clone()).collect();
+        // Enough for every round of the pool to use its full per-host time.
+        let rounds = host_ids.len().div_ceil(concurrency).max(1) as u32;
+        let timeout = per_host * rounds + per_host;
+        let lite_everywhere = live_probe_lite_everywhere();
+        operations::run(op_id, "hosts_probe_all_live", timeout, || {
+            let total = host_ids.len() as u64;
+            let entries = crate::remote_exec::run_batch(
+                &host_ids,
+                concurrency,
+                |host_id| {
+                    let started = std::time::Instant::now();
+                    let host = hosts.iter().find(|host| host.id == host_id).cloned();
+                    let outcome = operations::with_timeout(per_host, || {
+                        probe_live_and_record(host.ok_or("Host not found")?, lite_everywhere)
+                    });
+                    let (live, error) = match outcome {
+                        Ok(live) => (Some(live), None),
+                        Err(error) => (None, Some(error)),
+                    };
+                    HostLiveEntry {
+                        host_id: host_id.to_string(),
+                        live,
+                        error,
+                        duration_ms: started.elapsed().as_millis().min(u64::MAX as u128) as u64,
+                    }
+                },
+                |entry, done| {
+                    operations::report_progress(done, Some(total), Some(&entry.host_id));
+                },
+            );
+            operations::check_current()?;
+            Ok(summarize_live(
+                entries,
+                skipped.into_iter().map(|host| host.id).collect(),
+            ))
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
 }
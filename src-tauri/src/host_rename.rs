@@ -0,0 +1,114 @@
+use crate::host_store::{hosts_load, hosts_save};
+use crate::models::Host;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Runtime};
+
+const MAIN_WINDOW_LABEL: &str = "main";
+/// Probe fact holding the name the machine reports for itself.
+pub(crate) const REMOTE_NAME_FACT: &str = "hostname";
+// Names that say nothing about which machine this is.
+const PLACEHOLDER_NAMES: &[&str] = &["localhost", "localhost.localdomain", "(none)"];
+
+/// Sent as `host:rename-suggested` when a probe finds that the machine calls
+/// itself something other than the host's name, alias and hostname.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostRenameSuggestion {
+    pub host_id: String,
+    pub current_name: String,
+    pub remote_name: String,
+}
+
+fn display_name(host: &Host) -> &str {
+    [&host.name, &host.alias, &host.hostname]
+        .into_iter()
+        .map(|name| name.trim())
+        .find(|name| !name.is_empty())
+        .unwrap_or_default()
+}
+
+fn first_label(name: &str) -> &str {
+    name.split('.').next().unwrap_or(name)
+}
+
+/// `None` when the reported name is a placeholder or already matches the
+/// name, alias or hostname (compared case-insensitively, with or without
+/// the domain part).
+pub(crate) fn rename_suggestion(host: &Host, remote_name: &str) -> Option<HostRenameSuggestion> {
+    let remote = remote_name
+        .trim()
+        .trim_end_matches('.')
+        .to_ascii_lowercase();
+    if remote.is_empty() || PLACEHOLDER_NAMES.contains(&remote.as_str()) {
+        return None;
+    }
+    let known = [&host.name, &host.alias, &host.hostname]
+        .into_iter()
+        .map(|name| name.trim().trim_end_matches('.').to_ascii_lowercase())
+        .filter(|name| !name.is_empty())
+        .any(|name| name == remote || first_label(&name) == first_label(&remote));
+    if known {
+        return None;
+    }
+    Some(HostRenameSuggestion {
+        host_id: host.id.clone(),
+        current_name: display_name(host).to_string(),
+        remote_name: remote_name.trim().trim_end_matches('.').to_string(),
+    })
+}
+
+pub(crate) fn emit_rename_suggestion<R: Runtime>(
+    app: &AppHandle<R>,
+    host: &Host,
+    remote_name: &str,
+) {
+    if let Some(suggestion) = rename_suggestion(host, remote_name) {
+        let _ = app.emit_to(MAIN_WINDOW_LABEL, "host:rename-suggested", suggestion);
+    }
+}
+
+/// Rename the host to the name its machine reported in the last probe. Only
+/// the display name changes; alias and hostname keep working as before.
+#[tauri::command]
+pub fn host_adopt_remote_name(id: String) -> Result<Host, String> {
+    let remote_name = crate::fact_tags::host_facts(id.clone())?
+        .remove(REMOTE_NAME_FACT)
+        .ok_or_else(|| "No probed hostname for this host yet".to_string())?;
+    let mut hosts = hosts_load()?;
+    let host = hosts
+        .iter_mut()
+        .find(|host| host.id == id.trim() && !host.deleted)
+        .ok_or_else(|| "Host not found".to_string())?;
+    let suggestion = rename_suggestion(host, &remote_name)
+        .ok_or_else(|| "Host already matches its remote name".to_string())?;
+    host.name = suggestion.remote_name;
+    host.updated_at = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+    let renamed = host.clone();
+    hosts_save(hosts)?;
+    Ok(renamed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::rename_suggestion;
+    use crate::mock_backends::test_host;
+    use crate::models::Host;
+
+    #[test]
+    fn suggests_only_real_renames() {
+        let host = Host {
+            name: "Web 1".to_string(),
+            alias: "web1".to_string(),
+            hostname: "web1.prod.example.com".to_string(),
+            ..test_host("h1")
+        };
+        assert_eq!(rename_suggestion(&host, "WEB1"), None);
+        assert_eq!(rename_suggestion(&host, "web1.prod.example.com."), None);
+        assert_eq!(rename_suggestion(&host, "localhost"), None);
+        assert_eq!(rename_suggestion(&host, " "), None);
+
+        let suggestion = rename_suggestion(&host, "api-3.prod.example.com").unwrap();
+        assert_eq!(suggestion.current_name, "Web 1");
+        assert_eq!(suggestion.remote_name, "api-3.prod.example.com");
+    }
+}
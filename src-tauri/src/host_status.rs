@@ -0,0 +1,381 @@
+use crate::alerts::{alert_rules, observe, Alert, Observation};
+use crate::connection_history::HostConnectionStats;
+use crate::host_store::{hosts_load, open_hosts_db};
+use crate::models::Host;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Runtime};
+
+const MAIN_WINDOW_LABEL: &str = "main";
+const HOST_PING_CONCURRENCY: usize = 32;
+const DEFAULT_PING_TIMEOUT_MS: u64 = 3000;
+const MIN_WATCH_INTERVAL_SECS: u64 = 5;
+const MAX_WATCH_INTERVAL_SECS: u64 = 3600;
+
+/// Reachability of a host's SSH port, also sent as `host:status` events.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostPingResult {
+    pub host_id: String,
+    /// `None` for hosts reached through a jump host or proxy command, which
+    /// are not pinged: their port is not reachable directly from here.
+    pub reachable: Option<bool>,
+    /// Time to complete the TCP handshake.
+    pub rtt_ms: Option<f64>,
+    pub error: Option<String>,
+    pub checked_at: String,
+}
+
+/// What is already known about a host's health, gathered without contacting
+/// it.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostHealth {
+    pub host_id: String,
+    /// The last ping since the app started; `None` before the first.
+    pub ping: Option<HostPingResult>,
+    /// Start of the last session that got past the SSH handshake.
+    pub last_connected_at: Option<String>,
+    /// The last alert a rule fired for the host since the app started, from
+    /// its pings or live probes.
+    pub last_alert: Option<Alert>,
+}
+
+// Bumped to stop the running watch loop; each loop exits once the
+// generation it started with is no longer current.
+static WATCH_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Time to open a TCP connection to `hostname:port`, trying each resolved
+/// address in turn.
+pub(crate) fn tcp_connect_rtt(
+    hostname: &str,
+    port: u16,
+    timeout: Duration,
+) -> Result<Duration, String> {
+    let addrs = (hostname, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("{hostname}: {e}"))?;
+    let mut last_error = format!("{hostname}: no address");
+    for addr in addrs {
+        let started = Instant::now();
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(_) => return Ok(started.elapsed()),
+            Err(e) => last_error = e.to_string(),
+        }
+    }
+    Err(last_error)
+}
+
+fn via_jump(host: &Host) -> bool {
+    let set = |value: &Option<String>| value.as_deref().is_some_and(|v| !v.trim().is_empty());
+    !host.jump_host_ids.is_empty() || set(&host.proxy_jump) || set(&host.proxy_command)
+}
+
+fn ping_host(host: &Host, timeout: Duration) -> HostPingResult {
+    let checked_at = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+    if via_jump(host) {
+        return HostPingResult {
+            host_id: host.id.clone(),
+            reachable: None,
+            rtt_ms: None,
+            error: None,
+            checked_at,
+        };
+    }
+    let hostname = host.hostname.trim();
+    let result = if hostname.is_empty() {
+        Err("Host has no hostname".to_string())
+    } else {
+        tcp_connect_rtt(hostname, host.port, timeout)
+    };
+    HostPingResult {
+        host_id: host.id.clone(),
+        reachable: Some(result.is_ok()),
+        rtt_ms: result.as_ref().ok().map(|rtt| rtt.as_secs_f64() * 1000.0),
+        error: result.err(),
+        checked_at,
+    }
+}
+
+fn last_pings() -> &'static Mutex<HashMap<String, HostPingResult>> {
+    static LAST: OnceLock<Mutex<HashMap<String, HostPingResult>>> = OnceLock::new();
+    LAST.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn ping_hosts(hosts: &[Host], timeout: Duration) -> Vec<HostPingResult> {
+    let mut results = Vec::with_capacity(hosts.len());
+    for chunk in hosts.chunks(HOST_PING_CONCURRENCY) {
+        thread::scope(|scope| {
+            let handles: Vec<_> = chunk
+                .iter()
+                .map(|host| scope.spawn(move || ping_host(host, timeout)))
+                .collect();
+            results.extend(handles.into_iter().filter_map(|handle| handle.join().ok()));
+        });
+    }
+    if let Ok(mut last) = last_pings().lock() {
+        for result in &results {
+            last.insert(result.host_id.clone(), result.clone());
+        }
+    }
+    let rules = alert_rules();
+    if !rules.is_empty() {
+        let by_id: HashMap<&str, &Host> =
+            hosts.iter().map(|host| (host.id.as_str(), host)).collect();
+        for result in &results {
+            let Some(host) = by_id.get(result.host_id.as_str()) else {
+                continue;
+            };
+            let ping = match (result.reachable, &result.error) {
+                (None, _) => continue,
+                (Some(true), _) => Ok(()),
+                (Some(false), error) => Err(error.as_deref().unwrap_or("unreachable")),
+            };
+            observe(&rules, host, Observation::Ping(ping));
+        }
+    }
+    results
+}
+
+/// The live hosts among `ids`; every live host when `ids` is empty.
+fn select_hosts(hosts: Vec<Host>, ids: &[String]) -> Vec<Host> {
+    hosts
+        .into_iter()
+        .filter(|host| !host.deleted && (ids.is_empty() || ids.contains(&host.id)))
+        .collect()
+}
+
+fn ping_timeout(timeout_ms: Option<u64>) -> Duration {
+    Duration::from_millis(
+        timeout_ms
+            .unwrap_or(DEFAULT_PING_TIMEOUT_MS)
+            .clamp(100, 30_000),
+    )
+}
+
+/// Check the SSH port of each host (all hosts when `ids` is empty) at once.
+/// Hosts behind a jump host or proxy command come back with `reachable`
+/// unknown.
+#[tauri::command]
+pub async fn hosts_ping(
+    ids: Vec<String>,
+    timeout_ms: Option<u64>,
+) -> Result<Vec<HostPingResult>, String> {
+    let timeout = ping_timeout(timeout_ms);
+    tauri::async_runtime::spawn_blocking(move || {
+        Ok(ping_hosts(&select_hosts(hosts_load()?, &ids), timeout))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Ping the hosts every `interval_secs` and emit a `host:status` event per
+/// host until `hosts_ping_watch_stop`. Starting a new watch replaces the
+/// previous one. The host list is reloaded every round.
+#[tauri::command]
+pub fn hosts_ping_watch<R: Runtime>(
+    ids: Vec<String>,
+    interval_secs: u64,
+    timeout_ms: Option<u64>,
+    app: AppHandle<R>,
+) -> Result<(), String> {
+    crate::safe_mode::ensure_not_safe_mode("Host monitoring")?;
+    let interval =
+        Duration::from_secs(interval_secs.clamp(MIN_WATCH_INTERVAL_SECS, MAX_WATCH_INTERVAL_SECS));
+    let timeout = ping_timeout(timeout_ms);
+    let generation = WATCH_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    thread::Builder::new()
+        .name("host-status".to_string())
+        .spawn(move || {
+            while WATCH_GENERATION.load(Ordering::SeqCst) == generation {
+                match hosts_load() {
+                    Ok(hosts) => {
+                        for result in ping_hosts(&select_hosts(hosts, &ids), timeout) {
+                            if WATCH_GENERATION.load(Ordering::SeqCst) != generation {
+                                return;
+                            }
+                            let _ = app.emit_to(MAIN_WINDOW_LABEL, "host:status", result);
+                        }
+                    }
+                    Err(e) => eprintln!("[host-status] failed to load hosts: {e}"),
+                }
+                thread::sleep(interval);
+            }
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn hosts_ping_watch_stop() {
+    WATCH_GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+fn health_overview(
+    hosts: Vec<Host>,
+    pings: &HashMap<String, HostPingResult>,
+    stats: Vec<HostConnectionStats>,
+    alerts: &HashMap<String, Alert>,
+) -> Vec<HostHealth> {
+    let mut last_connected: HashMap<String, Option<String>> = stats
+        .into_iter()
+        .map(|stats| (stats.host_id, stats.last_connected_at))
+        .collect();
+    select_hosts(hosts, &[])
+        .into_iter()
+        .map(|host| HostHealth {
+            ping: pings.get(&host.id).cloned(),
+            last_connected_at: last_connected.remove(&host.id).flatten(),
+            last_alert: alerts.get(&host.id).cloned(),
+            host_id: host.id,
+        })
+        .collect()
+}
+
+/// The health of every host in one call, in host list order: the last ping,
+/// the last session and the last alert. Nothing is probed, so a large sidebar
+/// can call it freely; pings come from `hosts_ping` and `hosts_ping_watch`.
+#[tauri::command]
+pub fn hosts_health_overview() -> Result<Vec<HostHealth>, String> {
+    let hosts = hosts_load()?;
+    let stats = crate::connection_history::query_stats(&open_hosts_db()?)?;
+    let pings = last_pings()
+        .lock()
+        .map(|pings| pings.clone())
+        .unwrap_or_default();
+    Ok(health_overview(
+        hosts,
+        &pings,
+        stats,
+        &crate::alerts::last_alerts_by_host(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{health_overview, ping_host, tcp_connect_rtt, HostPingResult};
+    use crate::alerts::{
+        last_alerts_by_host, observe, Alert, AlertCondition, AlertRule, Observation,
+    };
+    use crate::connection_history::HostConnectionStats;
+    use crate::mock_backends::test_host;
+    use crate::models::Host;
+    use std::collections::HashMap;
+    use std::net::TcpListener;
+    use std::time::Duration;
+
+    #[test]
+    fn measures_open_and_closed_ports() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        assert!(tcp_connect_rtt("127.0.0.1", port, Duration::from_secs(1)).is_ok());
+        drop(listener);
+        assert!(tcp_connect_rtt("127.0.0.1", port, Duration::from_secs(1)).is_err());
+
+        let host = Host {
+            hostname: String::new(),
+            ..test_host("h1")
+        };
+        let result = ping_host(&host, Duration::from_secs(1));
+        assert_eq!(result.reachable, Some(false));
+        assert_eq!(result.error.as_deref(), Some("Host has no hostname"));
+
+        // Only reachable through the bastion: not pinged, so not shown down.
+        let host = Host {
+            jump_host_ids: vec!["bastion".to_string()],
+            ..test_host("h2")
+        };
+        let result = ping_host(&host, Duration::from_secs(1));
+        assert_eq!((result.reachable, result.error), (None, None));
+        let host = Host {
+            proxy_command: Some("cloudflared access ssh --hostname %h".to_string()),
+            ..test_host("h3")
+        };
+        assert_eq!(ping_host(&host, Duration::from_secs(1)).reachable, None);
+    }
+
+    #[test]
+    fn overview_joins_cached_state_in_host_order() {
+        let host = |id: &str, deleted: bool| Host {
+            deleted,
+            ..test_host(id)
+        };
+        let hosts = vec![host("web", false), host("gone", true), host("db", false)];
+        let pings = HashMap::from([(
+            "db".to_string(),
+            HostPingResult {
+                host_id: "db".to_string(),
+                reachable: Some(true),
+                rtt_ms: Some(12.5),
+                error: None,
+                checked_at: "2026-03-01T12:00:00.000Z".to_string(),
+            },
+        )]);
+        let stats = vec![HostConnectionStats {
+            host_id: "web".to_string(),
+            total_sessions: 3,
+            failed_sessions: 1,
+            total_duration_ms: 1000,
+            last_connected_at: Some("2026-03-01T10:00:00.000Z".to_string()),
+        }];
+        let alerts = HashMap::from([(
+            "web".to_string(),
+            Alert {
+                title: "Disk almost full".to_string(),
+                message: "/ is 95% full".to_string(),
+                severity: "critical".to_string(),
+                host_id: Some("web".to_string()),
+                fired_at: "2026-03-01T11:00:00.000Z".to_string(),
+            },
+        )]);
+
+        let overview = health_overview(hosts, &pings, stats, &alerts);
+        let ids: Vec<&str> = overview.iter().map(|h| h.host_id.as_str()).collect();
+        assert_eq!(ids, vec!["web", "db"]);
+        assert!(overview[0].ping.is_none());
+        assert_eq!(
+            overview[0].last_connected_at.as_deref(),
+            Some("2026-03-01T10:00:00.000Z")
+        );
+        assert_eq!(
+            overview[0].last_alert.as_ref().map(|a| a.severity.as_str()),
+            Some("critical")
+        );
+        assert_eq!(
+            overview[1].ping.as_ref().map(|p| p.host_id.as_str()),
+            Some("db")
+        );
+        assert!(overview[1].last_connected_at.is_none() && overview[1].last_alert.is_none());
+    }
+
+    #[test]
+    fn overview_reports_the_alert_a_failed_ping_fired() {
+        let host = test_host("health-alerted");
+        let rules = vec![AlertRule {
+            id: "health-down".to_string(),
+            name: String::new(),
+            enabled: true,
+            condition: AlertCondition::HostDown,
+            threshold: None,
+            host_ids: vec![host.id.clone()],
+            channel_ids: Vec::new(),
+        }];
+        observe(&rules, &host, Observation::Ping(Err("Connection refused")));
+
+        let overview = health_overview(
+            vec![host.clone()],
+            &HashMap::new(),
+            Vec::new(),
+            &last_alerts_by_host(),
+        );
+        let alert = overview[0].last_alert.as_ref().unwrap();
+        assert_eq!(alert.host_id.as_deref(), Some("health-alerted"));
+        assert_eq!(alert.severity, "critical");
+        assert!(alert.message.contains("Connection refused"));
+    }
+}
@@ -1,18 +1,65 @@
+use crate::bell::BellAction;
 use crate::credential_store::{
-    keychain_delete_password, keychain_has_password, keychain_set_password, webdav_password_delete,
-    webdav_password_has, webdav_password_set,
+    hosts_db_key_get, hosts_db_key_set, keychain_delete_password, keychain_get_password,
+    keychain_has_password, keychain_set_password, totp_secret_delete, totp_secret_get,
+    totp_secret_has, totp_secret_set, webdav_password_delete, webdav_password_has,
+    webdav_password_migrate_legacy, webdav_password_set,
 };
+use crate::host_changes::{log_host_change, log_host_list_changes, OP_DELETE, OP_UPSERT};
 use crate::models::{Host, Settings};
-use crate::ssh_config::generate_ssh_config;
-use rusqlite::{params, Connection};
+use crate::ssh_config::{generate_ssh_config, validate_host_for_ssh_config};
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Deserialize;
 use std::fs;
+use std::io::{Read, Write};
 use std::path::PathBuf;
-use std::sync::Once;
+use std::sync::{Mutex, Once, OnceLock};
 
-fn get_config_dir() -> PathBuf {
+const PROFILE_ENV: &str = "XTERMIUS_PROFILE";
+const PROFILE_NAME_MAX_CHARS: usize = 32;
+
+fn normalize_profile_name(raw: &str) -> Option<String> {
+    let name = raw.trim();
+    if name.is_empty() || name.eq_ignore_ascii_case("default") {
+        return None;
+    }
+    let valid = name.chars().count() <= PROFILE_NAME_MAX_CHARS
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_');
+    if !valid {
+        eprintln!("[profile] ignoring invalid {PROFILE_ENV} value {name:?}");
+        return None;
+    }
+    Some(name.to_ascii_lowercase())
+}
+
+/// Profile selected via `XTERMIUS_PROFILE`. Each profile gets its own config
+/// directory and keychain namespace; `None` is the default profile.
+pub(crate) fn app_profile() -> Option<&'static str> {
+    static PROFILE: OnceLock<Option<String>> = OnceLock::new();
+    PROFILE
+        .get_or_init(|| {
+            std::env::var(PROFILE_ENV)
+                .ok()
+                .and_then(|raw| normalize_profile_name(&raw))
+        })
+        .as_deref()
+}
+
+pub(crate) fn get_config_dir() -> PathBuf {
+    #[cfg(test)]
+    if let Some(dir) = crate::mock_backends::config_dir_override() {
+        return dir;
+    }
+    let dir_name = match app_profile() {
+        Some(profile) => format!("xtermius-{profile}"),
+        None => "xtermius".to_string(),
+    };
     dirs::config_dir()
         .unwrap_or_else(|| PathBuf::from("."))
-        .join("xtermius")
+        .join(dir_name)
 }
 
 fn get_hosts_path() -> PathBuf {
@@ -36,7 +83,9 @@ pub(crate) fn ensure_config_dir() -> Result<(), String> {
 }
 
 /// Write `contents` to `path` atomically: a concurrent reader (e.g. `ssh -F`)
-/// always sees either the old or the new complete file, never a truncated one.
+/// always sees either the old or the new complete file, never a truncated one,
+/// and a crash mid-write leaves the old file in place. On Unix the file is
+/// only readable by the user (0600), as ssh expects of its config.
 pub(crate) fn atomic_write(path: &std::path::Path, contents: &[u8]) -> Result<(), String> {
     let dir = path.parent().unwrap_or_else(|| std::path::Path::new("."));
     let file_name = path
@@ -48,29 +97,195 @@ pub(crate) fn atomic_write(path: &std::path::Path, contents: &[u8]) -> Result<()
         .map(|d| d.as_nanos())
         .unwrap_or(0);
     let tmp = dir.join(format!(".{file_name}.tmp.{nonce}"));
-    if let Err(e) = fs::write(&tmp, contents) {
+    let written = (|| {
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&tmp)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    })();
+    if let Err(e) = written {
         let _ = fs::remove_file(&tmp);
         return Err(e.to_string());
     }
-    if let Err(e) = fs::rename(&tmp, path) {
-        let _ = fs::remove_file(&tmp);
-        return Err(e.to_string());
+    // Persist the rename itself; best effort, not every platform can open
+    // a directory.
+    #[cfg(unix)]
+    if let Ok(dir) = fs::File::open(dir) {
+        let _ = dir.sync_all();
     }
     Ok(())
 }
 
+fn hosts_db_key_cache() -> &'static Mutex<Option<Option<String>>> {
+    static KEY: OnceLock<Mutex<Option<Option<String>>>> = OnceLock::new();
+    KEY.get_or_init(|| Mutex::new(None))
+}
+
+// Read once per process: the DB is opened on every command and each keychain
+// read is an IPC round-trip. A keychain error is not cached, so a transient
+// failure is retried on the next open.
+fn hosts_db_key() -> Option<String> {
+    if let Ok(cache) = hosts_db_key_cache().lock() {
+        if let Some(key) = cache.as_ref() {
+            return key.clone();
+        }
+    }
+    match hosts_db_key_get() {
+        Ok(key) => {
+            let key = key.filter(|key| is_hosts_db_key(key));
+            if let Ok(mut cache) = hosts_db_key_cache().lock() {
+                *cache = Some(key.clone());
+            }
+            key
+        }
+        Err(e) => {
+            eprintln!("[hosts-db] failed to read encryption key from Keychain: {e}");
+            None
+        }
+    }
+}
+
+fn is_hosts_db_key(key: &str) -> bool {
+    key.len() == 64 && key.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+// A raw 256-bit key skips SQLCipher's PBKDF2 derivation, which would otherwise
+// cost noticeable time on every open.
+fn apply_hosts_db_key(conn: &Connection, key: &str) -> Result<(), String> {
+    if !is_hosts_db_key(key) {
+        return Err("Invalid hosts.db encryption key".to_string());
+    }
+    conn.execute_batch(&format!("PRAGMA key = \"x'{key}'\";"))
+        .map_err(|e| e.to_string())
+}
+
+/// Open a hosts DB file (the live one or a pulled copy), keyed with this
+/// device's hosts.db key when encryption is enabled.
+pub(crate) fn open_hosts_db_file(path: &std::path::Path) -> Result<Connection, String> {
+    let conn = Connection::open(path).map_err(|e| e.to_string())?;
+    if let Some(key) = hosts_db_key() {
+        apply_hosts_db_key(&conn, &key)?;
+    }
+    Ok(conn)
+}
+
+/// Open a hosts DB pulled from another device. It is keyed like the local
+/// one unless the two disagree on encryption: a plaintext copy pushed before
+/// the other device enabled encryption is read as is (merging writes it into
+/// the encrypted local DB), and an encrypted copy this device has no key for
+/// is reported rather than failing as a corrupt file.
+pub(crate) fn open_pulled_hosts_db(path: &std::path::Path) -> Result<Connection, String> {
+    let mut header = [0u8; 16];
+    let plaintext = fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .is_ok()
+        && header == *b"SQLite format 3\0";
+    match (plaintext, hosts_db_is_encrypted()) {
+        (true, true) => Connection::open(path).map_err(|e| e.to_string()),
+        (false, false) => Err(
+            "Remote hosts.db is encrypted (or damaged); to pull it, enable \
+             hosts.db encryption here with the key of the device that pushed it"
+                .to_string(),
+        ),
+        _ => open_hosts_db_file(path),
+    }
+}
+
+pub(crate) fn hosts_db_is_encrypted() -> bool {
+    hosts_db_key().is_some()
+}
+
 pub(crate) fn open_hosts_db() -> Result<Connection, String> {
+    #[cfg(test)]
+    if let Some(uri) = crate::mock_backends::hosts_db_uri_override() {
+        return Connection::open(uri).map_err(|e| e.to_string());
+    }
     ensure_config_dir()?;
     let path = get_hosts_db_path();
-    let conn = Connection::open(path).map_err(|e| e.to_string())?;
+    let conn = open_hosts_db_file(&path)?;
     // Main and settings windows share this DB from the same process; without a
     // busy timeout a concurrent write surfaces to the user as "database is locked".
     conn.busy_timeout(std::time::Duration::from_secs(5))
         .map_err(|e| e.to_string())?;
+    // WAL lets readers (probes, sync snapshots) run while another window
+    // writes. The mode is persistent; failing to switch (e.g. a read-only
+    // volume) only costs concurrency, so it is not an error.
+    let _ = conn.query_row("PRAGMA journal_mode = WAL", [], |_| Ok(()));
     Ok(conn)
 }
 
-fn ensure_hosts_schema(conn: &Connection) -> Result<(), String> {
+/// Copy a live hosts DB into `dest_path` with SQLite's online backup API,
+/// which yields a consistent snapshot even while other connections write.
+/// The copy uses a rollback journal so it is a single self-contained file.
+fn backup_hosts_db(source: &Connection, dest_path: &std::path::Path) -> Result<(), String> {
+    let mut dest = open_hosts_db_file(dest_path)?;
+    rusqlite::backup::Backup::new(source, &mut dest)
+        .and_then(|backup| {
+            backup.run_to_completion(256, std::time::Duration::from_millis(10), None)
+        })
+        .map_err(|e| format!("Failed to snapshot hosts.db: {e}"))?;
+    dest.query_row("PRAGMA journal_mode = DELETE", [], |_| Ok(()))
+        .map_err(|e| e.to_string())
+}
+
+// Rows of hosts kept out of sync, together with their change log and
+// connection history. The copy is vacuumed so the deleted rows do not linger
+// in free pages of the uploaded file.
+fn remove_hosts_from_snapshot(path: &std::path::Path, host_ids: &[String]) -> Result<(), String> {
+    if host_ids.is_empty() {
+        return Ok(());
+    }
+    let conn = open_hosts_db_file(path)?;
+    for (table, column) in [
+        ("hosts", "id"),
+        ("host_changes", "host_id"),
+        ("connections", "host_id"),
+    ] {
+        let exists: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+                params![table],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if exists == 0 {
+            continue;
+        }
+        for id in host_ids {
+            conn.execute(
+                &format!("DELETE FROM {table} WHERE {column} = ?1"),
+                params![id],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+    }
+    conn.execute_batch("VACUUM").map_err(|e| e.to_string())
+}
+
+/// Snapshot the hosts DB into a temporary file next to it and return the
+/// file's bytes, for uploading. `exclude_ids` are left out of the copy.
+pub(crate) fn hosts_db_snapshot_bytes(exclude_ids: &[String]) -> Result<Vec<u8>, String> {
+    if !get_hosts_db_path().exists() {
+        let _ = hosts_load()?;
+    }
+    let conn = open_hosts_db()?;
+    let timestamp = chrono::Utc::now().format("%Y%m%d%H%M%S%3f");
+    let temp_path = get_hosts_db_path().with_extension(format!("db.snapshot.{timestamp}"));
+    let result = backup_hosts_db(&conn, &temp_path)
+        .and_then(|()| remove_hosts_from_snapshot(&temp_path, exclude_ids))
+        .and_then(|()| fs::read(&temp_path).map_err(|e| e.to_string()));
+    let _ = fs::remove_file(&temp_path);
+    result
+}
+
+pub(crate) fn ensure_hosts_schema(conn: &Connection) -> Result<(), String> {
     conn.execute_batch(
         r#"
         CREATE TABLE IF NOT EXISTS hosts (
@@ -89,6 +304,30 @@ fn ensure_hosts_schema(conn: &Connection) -> Result<(), String> {
           proxy_jump    TEXT,
           env_vars      TEXT,
           encoding      TEXT,
+          startup_commands_json TEXT NOT NULL DEFAULT '[]',
+          startup_commands_delay_ms INTEGER,
+          probe_shell   TEXT,
+          sync_excluded INTEGER NOT NULL DEFAULT 0,
+          import_source TEXT,
+          mac_address   TEXT,
+          max_session_minutes INTEGER,
+          live_probe_lite INTEGER NOT NULL DEFAULT 0,
+          forward_agent INTEGER,
+          compression   INTEGER,
+          server_alive_count_max INTEGER,
+          connect_timeout INTEGER,
+          local_forwards_json TEXT NOT NULL DEFAULT '[]',
+          remote_forwards_json TEXT NOT NULL DEFAULT '[]',
+          request_tty   TEXT,
+          host_key_algorithms TEXT,
+          extra_config  TEXT,
+          jump_host_ids_json TEXT NOT NULL DEFAULT '[]',
+          bell_action   TEXT,
+          proxy_command TEXT,
+          keepalive_idle_minutes INTEGER,
+          keepalive_string TEXT,
+          extended_probe INTEGER NOT NULL DEFAULT 0,
+          prompt_rules_json TEXT NOT NULL DEFAULT '[]',
           tags_json     TEXT NOT NULL,
           notes         TEXT NOT NULL,
           updated_at    TEXT NOT NULL,
@@ -98,6 +337,8 @@ fn ensure_hosts_schema(conn: &Connection) -> Result<(), String> {
     )
     .map_err(|e| e.to_string())?;
 
+    // Columns added since the table was created. Failures are ignored, so
+    // these run in safe mode too: `read_hosts` selects every column.
     let _ = conn.execute(
         "ALTER TABLE hosts ADD COLUMN sort_order INTEGER NOT NULL DEFAULT 0",
         [],
@@ -114,6 +355,49 @@ fn ensure_hosts_schema(conn: &Connection) -> Result<(), String> {
         "ALTER TABLE hosts ADD COLUMN host_live_metrics_enabled INTEGER NOT NULL DEFAULT 1",
         [],
     );
+    let _ = conn.execute(
+        "ALTER TABLE hosts ADD COLUMN startup_commands_json TEXT NOT NULL DEFAULT '[]'",
+        [],
+    );
+    let _ = conn.execute(
+        "ALTER TABLE hosts ADD COLUMN startup_commands_delay_ms INTEGER",
+        [],
+    );
+    let _ = conn.execute("ALTER TABLE hosts ADD COLUMN probe_shell TEXT", []);
+    let _ = conn.execute(
+        "ALTER TABLE hosts ADD COLUMN sync_excluded INTEGER NOT NULL DEFAULT 0",
+        [],
+    );
+    let _ = conn.execute("ALTER TABLE hosts ADD COLUMN import_source TEXT", []);
+    let _ = conn.execute("ALTER TABLE hosts ADD COLUMN mac_address TEXT", []);
+    let _ = conn.execute(
+        "ALTER TABLE hosts ADD COLUMN max_session_minutes INTEGER",
+        [],
+    );
+    let _ = conn.execute(
+        "ALTER TABLE hosts ADD COLUMN live_probe_lite INTEGER NOT NULL DEFAULT 0",
+        [],
+    );
+    for column in [
+        "forward_agent INTEGER",
+        "compression INTEGER",
+        "server_alive_count_max INTEGER",
+        "connect_timeout INTEGER",
+        "local_forwards_json TEXT NOT NULL DEFAULT '[]'",
+        "remote_forwards_json TEXT NOT NULL DEFAULT '[]'",
+        "request_tty TEXT",
+        "host_key_algorithms TEXT",
+        "extra_config TEXT",
+        "jump_host_ids_json TEXT NOT NULL DEFAULT '[]'",
+        "bell_action TEXT",
+        "proxy_command TEXT",
+        "keepalive_idle_minutes INTEGER",
+        "keepalive_string TEXT",
+        "extended_probe INTEGER NOT NULL DEFAULT 0",
+        "prompt_rules_json TEXT NOT NULL DEFAULT '[]'",
+    ] {
+        let _ = conn.execute(&format!("ALTER TABLE hosts ADD COLUMN {column}"), []);
+    }
     Ok(())
 }
 
@@ -144,7 +428,7 @@ fn migrate_db_passwords_to_keychain(conn: &Connection) -> Result<(), String> {
         if pwt.is_empty() {
             continue;
         }
-        match keychain_set_password(&id, pwt) {
+        match keychain_set_password(&id, pwt, "migration") {
             Ok(()) => {
                 let _ = conn.execute(
                     "UPDATE hosts SET has_password = 1, password = NULL WHERE id = ?1",
@@ -165,25 +449,37 @@ pub(crate) fn import_hosts_json_to_db(
 ) -> Result<(), String> {
     ensure_hosts_schema(conn)?;
     let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let before = read_hosts(&tx)?;
     tx.execute("DELETE FROM hosts", [])
         .map_err(|e| e.to_string())?;
     for (i, h) in hosts.into_iter().enumerate() {
         let tags_json = serde_json::to_string(&h.tags).map_err(|e| e.to_string())?;
+        let startup_commands_json =
+            serde_json::to_string(&h.startup_commands).map_err(|e| e.to_string())?;
+        let local_forwards_json =
+            serde_json::to_string(&h.local_forwards).map_err(|e| e.to_string())?;
+        let remote_forwards_json =
+            serde_json::to_string(&h.remote_forwards).map_err(|e| e.to_string())?;
+        let jump_host_ids_json =
+            serde_json::to_string(&h.jump_host_ids).map_err(|e| e.to_string())?;
+        let prompt_rules_json =
+            serde_json::to_string(&h.prompt_rules).map_err(|e| e.to_string())?;
         let sort_order = h.sort_order.unwrap_or(i as i64);
 
         let has_password;
         if let Some(ref pw) = h.password {
             let pwt = pw.trim();
             if pwt.is_empty() {
-                keychain_delete_password(&h.id)?;
+                keychain_delete_password(&h.id, "hosts_save")?;
                 has_password = false;
             } else {
-                keychain_set_password(&h.id, pwt)
+                keychain_set_password(&h.id, pwt, "hosts_save")
                     .map_err(|e| format!("Failed to save password to Keychain: {e}"))?;
                 has_password = true;
             }
         } else {
-            has_password = keychain_has_password(&h.id);
+            // Keep what is known while the keychain cannot tell (locked).
+            has_password = keychain_has_password(&h.id).unwrap_or(h.has_password);
         }
 
         tx.execute(
@@ -191,8 +487,14 @@ pub(crate) fn import_hosts_json_to_db(
             INSERT INTO hosts (
               id, sort_order, name, alias, hostname, user, port,
               password, has_password, host_insights_enabled, host_live_metrics_enabled, identity_file, proxy_jump, env_vars, encoding,
-              tags_json, notes, updated_at, deleted
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)
+              tags_json, notes, updated_at, deleted, startup_commands_json, startup_commands_delay_ms,
+              probe_shell, sync_excluded, import_source, mac_address, max_session_minutes,
+              live_probe_lite, forward_agent, compression, server_alive_count_max, connect_timeout,
+              local_forwards_json, remote_forwards_json, request_tty, host_key_algorithms,
+              extra_config, jump_host_ids_json, bell_action, proxy_command, keepalive_idle_minutes,
+              keepalive_string, extended_probe, prompt_rules_json
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27,
+              ?28, ?29, ?30, ?31, ?32, ?33, ?34, ?35, ?36, ?37, ?38, ?39, ?40, ?41, ?42, ?43)
             "#,
             params![
                 h.id,
@@ -213,11 +515,36 @@ pub(crate) fn import_hosts_json_to_db(
                 tags_json,
                 h.notes,
                 h.updated_at,
-                if h.deleted { 1 } else { 0 }
+                if h.deleted { 1 } else { 0 },
+                startup_commands_json,
+                h.startup_commands_delay_ms.map(|ms| ms as i64),
+                h.probe_shell,
+                if h.sync_excluded { 1 } else { 0 },
+                h.import_source,
+                h.mac_address,
+                h.max_session_minutes,
+                if h.live_probe_lite { 1 } else { 0 },
+                h.forward_agent,
+                h.compression,
+                h.server_alive_count_max,
+                h.connect_timeout,
+                local_forwards_json,
+                remote_forwards_json,
+                h.request_tty,
+                h.host_key_algorithms,
+                h.extra_config,
+                jump_host_ids_json,
+                h.bell_action.map(BellAction::as_str),
+                h.proxy_command,
+                h.keepalive_idle_minutes,
+                h.keepalive_string,
+                if h.extended_probe { 1 } else { 0 },
+                prompt_rules_json
             ],
         )
         .map_err(|e| e.to_string())?;
     }
+    log_host_list_changes(&tx, &before, &read_hosts(&tx)?)?;
     tx.commit().map_err(|e| e.to_string())?;
     Ok(())
 }
@@ -244,7 +571,7 @@ fn sanitize_hosts_for_frontend(hosts: Vec<Host>) -> Vec<Host> {
 pub fn hosts_load() -> Result<Vec<Host>, String> {
     ensure_config_dir()?;
     let db_path = get_hosts_db_path();
-    if !db_path.exists() {
+    if !db_path.exists() && !crate::safe_mode::is_enabled() {
         let json_path = get_hosts_path();
         if json_path.exists() {
             let content = fs::read_to_string(&json_path).map_err(|e| e.to_string())?;
@@ -262,17 +589,29 @@ pub fn hosts_load() -> Result<Vec<Host>, String> {
     // process; hosts_load is on hot paths (spawn, probes) where the repeated
     // table scan plus keychain writes would add avoidable latency.
     static PASSWORD_MIGRATION: Once = Once::new();
-    PASSWORD_MIGRATION.call_once(|| {
-        let _ = migrate_db_passwords_to_keychain(&conn);
-    });
+    if !crate::safe_mode::is_enabled() {
+        PASSWORD_MIGRATION.call_once(|| {
+            let _ = migrate_db_passwords_to_keychain(&conn);
+        });
+    }
+
+    read_hosts(&conn)
+}
 
+/// Read every host row (including soft-deleted ones) from an open hosts DB.
+pub(crate) fn read_hosts(conn: &Connection) -> Result<Vec<Host>, String> {
     let mut stmt = conn
         .prepare(
             r#"
             SELECT
               id, sort_order, name, alias, hostname, user, port,
               password, has_password, host_insights_enabled, host_live_metrics_enabled, identity_file, proxy_jump, env_vars, encoding,
-              tags_json, notes, updated_at, deleted
+              tags_json, notes, updated_at, deleted, startup_commands_json, startup_commands_delay_ms,
+              probe_shell, sync_excluded, import_source, mac_address, max_session_minutes,
+              live_probe_lite, forward_agent, compression, server_alive_count_max, connect_timeout,
+              local_forwards_json, remote_forwards_json, request_tty, host_key_algorithms,
+              extra_config, jump_host_ids_json, bell_action, proxy_command, keepalive_idle_minutes,
+              keepalive_string, extended_probe, prompt_rules_json
             FROM hosts
             ORDER BY sort_order ASC, updated_at DESC
             "#,
@@ -283,6 +622,10 @@ pub fn hosts_load() -> Result<Vec<Host>, String> {
         .query_map([], |row| {
             let tags_json: String = row.get(15)?;
             let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
+            let startup_commands_json: String = row.get(19)?;
+            let startup_commands: Vec<String> =
+                serde_json::from_str(&startup_commands_json).unwrap_or_default();
+            let startup_commands_delay_ms: Option<i64> = row.get(20)?;
             let id: String = row.get(0)?;
             Ok(Host {
                 id: id.clone(),
@@ -296,7 +639,15 @@ pub fn hosts_load() -> Result<Vec<Host>, String> {
                     p as u16
                 },
                 password: None,
-                has_password: keychain_has_password(&id),
+                // The stored flag stands in while the keychain is locked.
+                has_password: match keychain_has_password(&id) {
+                    Some(has_password) => has_password,
+                    None => {
+                        let v: i64 = row.get(8)?;
+                        v != 0
+                    }
+                },
+                has_totp: totp_secret_has(&id),
                 host_insights_enabled: {
                     let v: i64 = row.get(9)?;
                     v != 0
@@ -307,8 +658,55 @@ pub fn hosts_load() -> Result<Vec<Host>, String> {
                 },
                 identity_file: row.get(11)?,
                 proxy_jump: row.get(12)?,
+                jump_host_ids: {
+                    let json: String = row.get(36)?;
+                    serde_json::from_str(&json).unwrap_or_default()
+                },
+                proxy_command: row.get(38)?,
                 env_vars: row.get(13)?,
                 encoding: row.get(14)?,
+                startup_commands,
+                startup_commands_delay_ms: startup_commands_delay_ms.map(|ms| ms.max(0) as u64),
+                probe_shell: row.get(21)?,
+                sync_excluded: {
+                    let v: i64 = row.get(22)?;
+                    v != 0
+                },
+                import_source: row.get(23)?,
+                mac_address: row.get(24)?,
+                max_session_minutes: row.get(25)?,
+                live_probe_lite: {
+                    let v: i64 = row.get(26)?;
+                    v != 0
+                },
+                forward_agent: row.get(27)?,
+                compression: row.get(28)?,
+                server_alive_count_max: row.get(29)?,
+                connect_timeout: row.get(30)?,
+                local_forwards: {
+                    let json: String = row.get(31)?;
+                    serde_json::from_str(&json).unwrap_or_default()
+                },
+                remote_forwards: {
+                    let json: String = row.get(32)?;
+                    serde_json::from_str(&json).unwrap_or_default()
+                },
+                request_tty: row.get(33)?,
+                host_key_algorithms: row.get(34)?,
+                extra_config: row.get(35)?,
+                bell_action: row
+                    .get::<_, Option<String>>(37)?
+                    .and_then(|action| BellAction::parse(&action)),
+                keepalive_idle_minutes: row.get(39)?,
+                keepalive_string: row.get(40)?,
+                extended_probe: {
+                    let v: i64 = row.get(41)?;
+                    v != 0
+                },
+                prompt_rules: {
+                    let json: String = row.get(42)?;
+                    serde_json::from_str(&json).unwrap_or_default()
+                },
                 tags,
                 notes: row.get(16)?,
                 updated_at: row.get(17)?,
@@ -327,8 +725,536 @@ pub fn hosts_load() -> Result<Vec<Host>, String> {
     Ok(hosts)
 }
 
+pub(crate) fn parse_updated_at(value: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    chrono::DateTime::parse_from_rfc3339(value.trim())
+        .ok()
+        .map(|dt| dt.with_timezone(&chrono::Utc))
+}
+
+fn now_updated_at() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+pub(crate) fn is_newer(candidate: &Host, current: &Host) -> bool {
+    match (
+        parse_updated_at(&candidate.updated_at),
+        parse_updated_at(&current.updated_at),
+    ) {
+        (Some(a), Some(b)) => a > b,
+        _ => candidate.updated_at > current.updated_at,
+    }
+}
+
+/// Drop hosts that were purged after their last edit. A copy edited after the
+/// purge (on a device that had not heard of it yet) is kept.
+pub(crate) fn without_purged_hosts(
+    hosts: Vec<Host>,
+    tombstones: &std::collections::HashMap<String, String>,
+) -> Vec<Host> {
+    hosts
+        .into_iter()
+        .filter(|host| match tombstones.get(&host.id) {
+            Some(deleted_at) => match (
+                parse_updated_at(&host.updated_at),
+                parse_updated_at(deleted_at),
+            ) {
+                (Some(updated), Some(deleted)) => updated > deleted,
+                _ => false,
+            },
+            None => true,
+        })
+        .collect()
+}
+
+/// Merge a pulled host list into the local one by id: whichever side touched a
+/// host last wins, so deleting and restoring both propagate as ordinary
+/// edits. Ties go to the remote copy; local-only hosts are kept unless
+/// `tombstones` says they were purged.
+pub(crate) fn merge_hosts_by_updated_at(
+    local: Vec<Host>,
+    remote: Vec<Host>,
+    tombstones: &std::collections::HashMap<String, String>,
+) -> Vec<Host> {
+    let mut local_by_id: std::collections::HashMap<String, Host> = local
+        .iter()
+        .cloned()
+        .map(|host| (host.id.clone(), host))
+        .collect();
+    let mut merged = Vec::with_capacity(local.len().max(remote.len()));
+    for remote_host in remote {
+        match local_by_id.remove(&remote_host.id) {
+            Some(local_host) if is_newer(&local_host, &remote_host) => merged.push(local_host),
+            _ => merged.push(remote_host),
+        }
+    }
+    merged.extend(
+        local
+            .into_iter()
+            .filter(|host| local_by_id.contains_key(&host.id)),
+    );
+    without_purged_hosts(merged, tombstones)
+}
+
+#[tauri::command]
+pub fn hosts_trash_list() -> Result<Vec<Host>, String> {
+    let mut trashed: Vec<Host> = hosts_load()?
+        .into_iter()
+        .filter(|host| host.deleted)
+        .collect();
+    trashed.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+    Ok(trashed)
+}
+
+#[tauri::command]
+pub fn host_restore(id: String) -> Result<Host, String> {
+    let conn = open_hosts_db()?;
+    ensure_hosts_schema(&conn)?;
+    let changed = conn
+        .execute(
+            "UPDATE hosts SET deleted = 0, updated_at = ?2 WHERE id = ?1 AND deleted != 0",
+            params![id.trim(), now_updated_at()],
+        )
+        .map_err(|e| e.to_string())?;
+    if changed == 0 {
+        return Err("Host is not in the trash".to_string());
+    }
+    log_host_change(&conn, id.trim(), OP_UPSERT)?;
+    drop(conn);
+    let hosts = hosts_load()?;
+    let _ = generate_ssh_config(hosts.clone());
+    hosts
+        .into_iter()
+        .find(|host| host.id == id.trim())
+        .ok_or_else(|| "Host not found".to_string())
+}
+
+/// Permanently remove trashed hosts (and their saved passwords) deleted more
+/// than `older_than_days` ago; 0 empties the trash. A host's `updated_at` is
+/// its deletion time while it sits in the trash.
+#[tauri::command]
+pub fn hosts_trash_purge(older_than_days: Option<u32>) -> Result<usize, String> {
+    let older_than_days = older_than_days.unwrap_or(30);
+    let cutoff = chrono::Utc::now() - chrono::Duration::days(i64::from(older_than_days));
+    let conn = open_hosts_db()?;
+    ensure_hosts_schema(&conn)?;
+    let purge_ids: Vec<String> = read_hosts(&conn)?
+        .into_iter()
+        .filter(|host| host.deleted)
+        .filter(|host| {
+            older_than_days == 0
+                || parse_updated_at(&host.updated_at).is_some_and(|deleted_at| deleted_at < cutoff)
+        })
+        .map(|host| host.id)
+        .collect();
+    for id in &purge_ids {
+        conn.execute(
+            "DELETE FROM hosts WHERE id = ?1 AND deleted != 0",
+            params![id],
+        )
+        .map_err(|e| e.to_string())?;
+        log_host_change(&conn, id, OP_DELETE)?;
+        crate::connection_history::delete_connection_history(&conn, id)?;
+        if let Err(e) = keychain_delete_password(id, "purge") {
+            eprintln!("[keychain] failed to delete password of purged host {id}: {e}");
+        }
+        if let Err(e) = totp_secret_delete(id, "purge") {
+            eprintln!("[keychain] failed to delete TOTP secret of purged host {id}: {e}");
+        }
+    }
+    Ok(purge_ids.len())
+}
+
+/// Fields `hosts_bulk_update` may change. Absent fields are left alone; an
+/// empty string clears `identityFile` / `proxyJump`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostBulkPatch {
+    #[serde(default)]
+    pub user: Option<String>,
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub identity_file: Option<String>,
+    #[serde(default)]
+    pub proxy_jump: Option<String>,
+    #[serde(default)]
+    pub add_tags: Vec<String>,
+    #[serde(default)]
+    pub remove_tags: Vec<String>,
+}
+
+fn optional_field(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+fn apply_bulk_patch(host: &mut Host, patch: &HostBulkPatch, updated_at: &str) {
+    if let Some(user) = &patch.user {
+        host.user = user.trim().to_string();
+    }
+    if let Some(port) = patch.port {
+        host.port = port;
+    }
+    if let Some(identity_file) = &patch.identity_file {
+        host.identity_file = optional_field(identity_file);
+    }
+    if let Some(proxy_jump) = &patch.proxy_jump {
+        host.proxy_jump = optional_field(proxy_jump);
+        host.jump_host_ids.clear();
+        if host.proxy_jump.is_some() {
+            host.proxy_command = None;
+        }
+    }
+    retag(&mut host.tags, &patch.add_tags, &patch.remove_tags);
+    host.updated_at = updated_at.to_string();
+}
+
+fn retag(tags: &mut Vec<String>, add_tags: &[String], remove_tags: &[String]) {
+    tags.retain(|tag| !remove_tags.iter().any(|removed| removed.trim() == tag));
+    for tag in add_tags.iter().map(|tag| tag.trim()) {
+        if !tag.is_empty() && !tags.iter().any(|existing| existing == tag) {
+            tags.push(tag.to_string());
+        }
+    }
+}
+
+/// Add and remove tags of one host without touching the rest of its row:
+/// for tags the app assigns itself (fact tag rules), which are not an edit
+/// and so leave `updated_at` and ssh_config alone. Returns whether the tags
+/// changed.
+pub(crate) fn host_retag(
+    id: &str,
+    add_tags: &[String],
+    remove_tags: &[String],
+) -> Result<bool, String> {
+    let conn = open_hosts_db()?;
+    ensure_hosts_schema(&conn)?;
+    let tags_json: String = conn
+        .query_row(
+            "SELECT tags_json FROM hosts WHERE id = ?1 AND deleted = 0",
+            params![id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Host not found: {id}"))?;
+    let before: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
+    let mut tags = before.clone();
+    retag(&mut tags, add_tags, remove_tags);
+    if tags == before {
+        return Ok(false);
+    }
+    let tags_json = serde_json::to_string(&tags).map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE hosts SET tags_json = ?2 WHERE id = ?1",
+        params![id, tags_json],
+    )
+    .map_err(|e| e.to_string())?;
+    log_host_change(&conn, id, OP_UPSERT)?;
+    Ok(true)
+}
+
+/// Apply one patch to many hosts (e.g. a rotated SSH user or bastion for a
+/// whole environment). All hosts are validated before anything is written,
+/// and the save is a single transaction.
+#[tauri::command]
+pub fn hosts_bulk_update(ids: Vec<String>, patch: HostBulkPatch) -> Result<Vec<Host>, String> {
+    if patch.port == Some(0) {
+        return Err("Port must be between 1 and 65535".to_string());
+    }
+    let mut hosts = hosts_load()?;
+    let updated_at = now_updated_at();
+    for id in &ids {
+        let host = hosts
+            .iter_mut()
+            .find(|host| host.id == id.trim() && !host.deleted)
+            .ok_or_else(|| format!("Host not found: {id}"))?;
+        apply_bulk_patch(host, &patch, &updated_at);
+        validate_host_for_ssh_config(host)?;
+    }
+    let mut conn = open_hosts_db()?;
+    import_hosts_json_to_db(&mut conn, hosts.clone())?;
+    generate_ssh_config(hosts.clone())?;
+    Ok(sanitize_hosts_for_frontend(hosts))
+}
+
+fn duplicate_key(host: &Host) -> (String, u16, String) {
+    (
+        host.hostname.trim().to_ascii_lowercase(),
+        host.port,
+        host.user.trim().to_string(),
+    )
+}
+
+/// Groups of live hosts sharing hostname+port+user, in host-list order.
+fn group_duplicates(hosts: Vec<Host>) -> Vec<Vec<Host>> {
+    let mut groups: Vec<((String, u16, String), Vec<Host>)> = Vec::new();
+    for host in hosts.into_iter().filter(|host| !host.deleted) {
+        let key = duplicate_key(&host);
+        match groups.iter_mut().find(|(existing, _)| *existing == key) {
+            Some((_, group)) => group.push(host),
+            None => groups.push((key, vec![host])),
+        }
+    }
+    groups
+        .into_iter()
+        .map(|(_, group)| group)
+        .filter(|group| group.len() > 1)
+        .collect()
+}
+
+fn merge_duplicate_fields(keep: &mut Host, removed: &[Host], updated_at: &str) {
+    for host in removed {
+        for tag in &host.tags {
+            if !keep.tags.contains(tag) {
+                keep.tags.push(tag.clone());
+            }
+        }
+        let notes = host.notes.trim();
+        if !notes.is_empty() && !keep.notes.contains(notes) {
+            if !keep.notes.trim().is_empty() {
+                keep.notes.push_str("\n\n");
+            }
+            keep.notes.push_str(notes);
+        }
+    }
+    keep.updated_at = updated_at.to_string();
+}
+
+#[tauri::command]
+pub fn hosts_find_duplicates() -> Result<Vec<Vec<Host>>, String> {
+    Ok(group_duplicates(hosts_load()?)
+        .into_iter()
+        .map(sanitize_hosts_for_frontend)
+        .collect())
+}
+
+/// Fold `remove_ids` into `keep_id`: tags and notes are merged, a saved
+/// password moves over if the kept host has none, connection history is
+/// reassigned, and the removed hosts go to the trash.
+#[tauri::command]
+pub fn hosts_merge(keep_id: String, remove_ids: Vec<String>) -> Result<Host, String> {
+    let keep_id = keep_id.trim().to_string();
+    let mut hosts = hosts_load()?;
+    let mut removed = Vec::new();
+    for id in &remove_ids {
+        let id = id.trim();
+        if id == keep_id {
+            return Err("Cannot merge a host into itself".to_string());
+        }
+        let host = hosts
+            .iter()
+            .find(|host| host.id == id && !host.deleted)
+            .ok_or_else(|| format!("Host not found: {id}"))?;
+        if !removed.iter().any(|existing: &Host| existing.id == id) {
+            removed.push(host.clone());
+        }
+    }
+    let updated_at = now_updated_at();
+    let keep = hosts
+        .iter_mut()
+        .find(|host| host.id == keep_id && !host.deleted)
+        .ok_or_else(|| format!("Host not found: {keep_id}"))?;
+    merge_duplicate_fields(keep, &removed, &updated_at);
+
+    if keychain_has_password(&keep_id) == Some(false) {
+        if let Some(source) = removed
+            .iter()
+            .find(|host| keychain_has_password(&host.id) == Some(true))
+        {
+            if let Some(password) = keychain_get_password(&source.id, "merge")? {
+                keychain_set_password(&keep_id, &password, "merge")
+                    .map_err(|e| format!("Failed to save password to Keychain: {e}"))?;
+            }
+        }
+    }
+    if !totp_secret_has(&keep_id) {
+        if let Some(source) = removed.iter().find(|host| totp_secret_has(&host.id)) {
+            if let Some(secret) = totp_secret_get(&source.id, "merge")? {
+                totp_secret_set(&keep_id, &secret, "merge")?;
+            }
+        }
+    }
+    // Hosts that jumped through a merged-away host now jump through the kept one.
+    for host in hosts.iter_mut() {
+        if !host
+            .jump_host_ids
+            .iter()
+            .any(|id| removed.iter().any(|r| r.id == *id))
+        {
+            continue;
+        }
+        let own_id = host.id.clone();
+        let mut jump_host_ids: Vec<String> = Vec::new();
+        for id in host.jump_host_ids.drain(..) {
+            let id = if removed.iter().any(|r| r.id == id) {
+                keep_id.clone()
+            } else {
+                id
+            };
+            if id != own_id && !jump_host_ids.contains(&id) {
+                jump_host_ids.push(id);
+            }
+        }
+        host.jump_host_ids = jump_host_ids;
+        host.updated_at = updated_at.clone();
+    }
+    for host in hosts
+        .iter_mut()
+        .filter(|host| removed.iter().any(|r| r.id == host.id))
+    {
+        host.deleted = true;
+        host.updated_at = updated_at.clone();
+    }
+
+    let mut conn = open_hosts_db()?;
+    import_hosts_json_to_db(&mut conn, hosts.clone())?;
+    for host in &removed {
+        crate::connection_history::reassign_connection_history(&conn, &host.id, &keep_id)?;
+        if let Err(e) = keychain_delete_password(&host.id, "merge") {
+            eprintln!(
+                "[keychain] failed to delete password of merged host {}: {e}",
+                host.id
+            );
+        }
+        if let Err(e) = totp_secret_delete(&host.id, "merge") {
+            eprintln!(
+                "[keychain] failed to delete TOTP secret of merged host {}: {e}",
+                host.id
+            );
+        }
+    }
+    drop(conn);
+    let hosts = hosts_load()?;
+    let _ = generate_ssh_config(hosts.clone());
+    sanitize_hosts_for_frontend(hosts)
+        .into_iter()
+        .find(|host| host.id == keep_id)
+        .ok_or_else(|| "Host not found".to_string())
+}
+
+fn generate_hosts_db_key() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn sqlcipher_available(conn: &Connection) -> bool {
+    conn.query_row("PRAGMA cipher_version", [], |row| row.get::<_, String>(0))
+        .is_ok()
+}
+
+fn export_encrypted_copy(
+    conn: &Connection,
+    target: &std::path::Path,
+    key: &str,
+) -> Result<(), String> {
+    conn.execute(
+        "ATTACH DATABASE ?1 AS encrypted KEY ?2",
+        params![target.to_string_lossy(), format!("x'{key}'")],
+    )
+    .map_err(|e| e.to_string())?;
+    let exported = conn
+        .query_row("SELECT sqlcipher_export('encrypted')", [], |_| Ok(()))
+        .map_err(|e| e.to_string());
+    let _ = conn.execute("DETACH DATABASE encrypted", []);
+    exported?;
+    let copy = Connection::open(target).map_err(|e| e.to_string())?;
+    apply_hosts_db_key(&copy, key)?;
+    read_hosts(&copy).map(|_| ())
+}
+
+/// One-time migration of hosts.db to SQLCipher. The key is stored in the
+/// keychain before the plaintext file is replaced, so it can never be lost
+/// halfway. Pass the key shown by `hosts_db_encryption_key` on another device
+/// to share one encrypted DB over WebDAV. Pull backups written before the
+/// migration (`hosts.db.bak.*`) are left as they are.
+#[tauri::command]
+pub fn hosts_db_encrypt(key: Option<String>) -> Result<(), String> {
+    crate::app_lock::ensure_unlocked()?;
+    if hosts_db_is_encrypted() {
+        return Err("hosts.db is already encrypted".to_string());
+    }
+    let key = match key {
+        Some(key) => {
+            let key = key.trim().to_ascii_lowercase();
+            if !is_hosts_db_key(&key) {
+                return Err("Encryption key must be 64 hexadecimal characters".to_string());
+            }
+            key
+        }
+        None => generate_hosts_db_key(),
+    };
+
+    let path = get_hosts_db_path();
+    if !path.exists() {
+        let _ = hosts_load()?;
+    }
+    let conn = open_hosts_db()?;
+    if !sqlcipher_available(&conn) {
+        return Err("This build does not support hosts.db encryption".to_string());
+    }
+    // Leaving WAL mode checkpoints and removes the -wal file, so nothing of
+    // the plaintext DB is left next to the encrypted one after the swap. The
+    // next open switches the encrypted DB back to WAL.
+    let journal_mode: String = conn
+        .query_row("PRAGMA journal_mode = DELETE", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    if !journal_mode.eq_ignore_ascii_case("delete") {
+        return Err("hosts.db is in use; close other windows and try again".to_string());
+    }
+    let timestamp = chrono::Utc::now().format("%Y%m%d%H%M%S%3f");
+    let temp_path = path.with_extension(format!("db.encrypt.{timestamp}"));
+    if let Err(e) = export_encrypted_copy(&conn, &temp_path, &key) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(format!("Failed to encrypt hosts.db: {e}"));
+    }
+    drop(conn);
+
+    if let Err(e) = hosts_db_key_set(&key) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e);
+    }
+    fs::rename(&temp_path, &path).map_err(|e| e.to_string())?;
+    if let Ok(mut cache) = hosts_db_key_cache().lock() {
+        *cache = Some(Some(key));
+    }
+    Ok(())
+}
+
+/// The hosts.db key, for enabling encryption with the same key on another
+/// synced device.
+#[tauri::command]
+pub fn hosts_db_encryption_key() -> Result<Option<String>, String> {
+    crate::app_lock::ensure_unlocked()?;
+    crate::biometric::confirm_password_access()?;
+    Ok(hosts_db_key())
+}
+
+// Run before anything is written, so a malformed env var line or prompt rule
+// is reported when saving rather than when connecting.
+fn validate_hosts_for_save(hosts: &[Host]) -> Result<(), String> {
+    crate::ssh_config::resolve_jump_chains(hosts)?;
+    for host in hosts.iter().filter(|host| !host.deleted) {
+        validate_host_for_ssh_config(host)?;
+        crate::pty::parse_env_vars(host.env_vars.as_deref())
+            .and_then(|_| crate::pty::validate_prompt_rules(&host.prompt_rules))
+            .map_err(|e| {
+                let name = if host.name.trim().is_empty() {
+                    host.hostname.trim()
+                } else {
+                    host.name.trim()
+                };
+                format!("{name}: {e}")
+            })?;
+    }
+    Ok(())
+}
+
 #[tauri::command]
 pub fn hosts_save(hosts: Vec<Host>) -> Result<(), String> {
+    validate_hosts_for_save(&hosts)?;
+    crate::ssh_options::validate_hosts_extra_config(&hosts)?;
     let mut conn = open_hosts_db()?;
     import_hosts_json_to_db(&mut conn, hosts.clone())?;
     generate_ssh_config(hosts)?;
@@ -338,6 +1264,10 @@ pub fn hosts_save(hosts: Vec<Host>) -> Result<(), String> {
 #[tauri::command]
 pub fn settings_load() -> Result<Settings, String> {
     ensure_config_dir()?;
+    let safe_mode = crate::safe_mode::is_enabled();
+    if !safe_mode {
+        webdav_password_migrate_legacy();
+    }
     let path = get_settings_path();
     if !path.exists() {
         return Ok(Settings {
@@ -347,6 +1277,17 @@ pub fn settings_load() -> Result<Settings, String> {
             has_webdav_password: webdav_password_has(),
             webdav_password: None,
             webdav_password_clear: false,
+            password_presence: Default::default(),
+            sync_excluded_tags: Vec::new(),
+            scrollback_memory_kb: None,
+            scrollback_spill_mb: None,
+            alert_channels: Vec::new(),
+            alert_rules: Vec::new(),
+            live_probe_lite: false,
+            otlp_endpoint: None,
+            reboot_critical_processes: None,
+            openssh_version: None,
+            ssh_control_persist_secs: None,
         });
     }
     let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
@@ -355,7 +1296,7 @@ pub fn settings_load() -> Result<Settings, String> {
         .webdav_password
         .as_ref()
         .map(|p| p.trim())
-        .filter(|p| !p.is_empty())
+        .filter(|p| !p.is_empty() && !safe_mode)
     {
         webdav_password_set(password)?;
         settings.webdav_password = None;
@@ -370,11 +1311,25 @@ pub fn settings_load() -> Result<Settings, String> {
     settings.has_webdav_password = webdav_password_has();
     settings.webdav_password = None;
     settings.webdav_password_clear = false;
+    crate::alerts::mark_channel_secrets(&mut settings.alert_channels);
     Ok(settings)
 }
 
 #[tauri::command]
 pub fn settings_save(mut settings: Settings) -> Result<(), String> {
+    // Everything is checked before anything is applied, so a rejected save
+    // leaves the keychain, the caches and settings.json as they were.
+    if let Some(version) = settings
+        .openssh_version
+        .as_deref()
+        .filter(|version| !version.trim().is_empty())
+    {
+        crate::ssh_options::parse_openssh_version(version)?;
+    }
+    let otlp_endpoint = crate::telemetry::parse_endpoint(settings.otlp_endpoint.as_deref())?;
+    crate::alerts::validate_rules(&settings.alert_rules, &settings.alert_channels)?;
+    crate::biometric::confirm_mode_change(settings.password_presence)?;
+
     ensure_config_dir()?;
     if settings.webdav_password_clear {
         webdav_password_delete()?;
@@ -389,6 +1344,16 @@ pub fn settings_save(mut settings: Settings) -> Result<(), String> {
     settings.has_webdav_password = webdav_password_has();
     settings.webdav_password = None;
     settings.webdav_password_clear = false;
+    let previous_channels = settings_load()
+        .map(|previous| previous.alert_channels)
+        .unwrap_or_default();
+    crate::alerts::store_channel_secrets(&previous_channels, &mut settings.alert_channels)?;
+    crate::biometric::set_presence_mode(settings.password_presence);
+    crate::scrollback::set_memory_cap_kb(settings.scrollback_memory_kb);
+    crate::scrollback::set_spill_cap_mb(settings.scrollback_spill_mb);
+    crate::ssh_config::set_control_persist_secs(settings.ssh_control_persist_secs);
+    crate::telemetry::set_endpoint(otlp_endpoint);
+
     let path = get_settings_path();
     let content = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
     atomic_write(&path, content.as_bytes())?;
@@ -397,9 +1362,196 @@ pub fn settings_save(mut settings: Settings) -> Result<(), String> {
 
 #[cfg(test)]
 mod tests {
-    use super::sanitize_hosts_for_frontend;
+    use super::{
+        apply_bulk_patch, atomic_write, backup_hosts_db, generate_hosts_db_key, group_duplicates,
+        is_hosts_db_key, merge_duplicate_fields, merge_hosts_by_updated_at, normalize_profile_name,
+        sanitize_hosts_for_frontend, settings_load, settings_save, validate_hosts_for_save,
+        HostBulkPatch,
+    };
+    use crate::credential_store::alert_password_get;
+    use crate::mock_backends::{test_host, TestEnv};
     use crate::models::Host;
 
+    fn synced_host(id: &str, updated_at: &str, deleted: bool) -> Host {
+        Host {
+            updated_at: updated_at.to_string(),
+            deleted,
+            ..test_host(id)
+        }
+    }
+
+    #[test]
+    fn atomic_write_replaces_file_privately() {
+        let dir = std::env::temp_dir().join(format!("xtermius-write-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config");
+        std::fs::write(&path, "old").unwrap();
+        atomic_write(&path, b"Host prod\n").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "Host prod\n");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        // No temp files are left next to the target.
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn save_rejects_invalid_env_vars() {
+        let mut host = synced_host("web", "2026-01-01T00:00:00Z", false);
+        host.env_vars = Some("LANG=en_US.UTF-8\nTZ=UTC".to_string());
+        assert!(validate_hosts_for_save(std::slice::from_ref(&host)).is_ok());
+
+        host.env_vars = Some("LANG=en_US.UTF-8\nGREETING=hello world".to_string());
+        let err = validate_hosts_for_save(std::slice::from_ref(&host)).unwrap_err();
+        assert!(err.starts_with("web.example.com: Invalid env var value on line 2"));
+
+        // Deleted hosts are kept as tombstones and never connected to.
+        host.deleted = true;
+        assert!(validate_hosts_for_save(&[host]).is_ok());
+    }
+
+    #[test]
+    fn backup_snapshot_includes_uncheckpointed_wal_writes() {
+        let dir = std::env::temp_dir().join(format!("xtermius-backup-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = rusqlite::Connection::open(dir.join("hosts.db")).unwrap();
+        source
+            .query_row("PRAGMA journal_mode = WAL", [], |_| Ok(()))
+            .unwrap();
+        source
+            .execute_batch("CREATE TABLE t (v TEXT); INSERT INTO t VALUES ('in-wal');")
+            .unwrap();
+
+        let snapshot_path = dir.join("snapshot.db");
+        backup_hosts_db(&source, &snapshot_path).unwrap();
+        let snapshot = rusqlite::Connection::open(&snapshot_path).unwrap();
+        let value: String = snapshot
+            .query_row("SELECT v FROM t", [], |row| row.get(0))
+            .unwrap();
+        let mode: String = snapshot
+            .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(value, "in-wal");
+        assert_eq!(mode, "delete");
+        drop((source, snapshot));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn generated_hosts_db_keys_are_raw_256_bit_hex() {
+        let key = generate_hosts_db_key();
+        assert!(is_hosts_db_key(&key));
+        assert_ne!(key, generate_hosts_db_key());
+        assert!(!is_hosts_db_key(&key[..63]));
+        assert!(!is_hosts_db_key(&format!("{}'", &key[..63])));
+    }
+
+    #[test]
+    fn bulk_patch_updates_only_given_fields() {
+        let mut host = synced_host("a", "2026-01-01T00:00:00.000Z", false);
+        host.user = "old".to_string();
+        host.proxy_jump = Some("bastion-old".to_string());
+        host.identity_file = Some("/keys/id".to_string());
+        host.tags = vec!["prod".to_string(), "legacy".to_string()];
+        let patch = HostBulkPatch {
+            user: Some(" deploy ".to_string()),
+            proxy_jump: Some("".to_string()),
+            add_tags: vec!["eu".to_string(), "prod".to_string()],
+            remove_tags: vec!["legacy".to_string()],
+            ..Default::default()
+        };
+        apply_bulk_patch(&mut host, &patch, "2026-03-01T00:00:00.000Z");
+        assert_eq!(host.user, "deploy");
+        assert_eq!(host.port, 22);
+        assert_eq!(host.proxy_jump, None);
+        assert_eq!(host.identity_file.as_deref(), Some("/keys/id"));
+        assert_eq!(host.tags, vec!["prod", "eu"]);
+        assert_eq!(host.updated_at, "2026-03-01T00:00:00.000Z");
+    }
+
+    #[test]
+    fn groups_duplicates_and_merges_tags_and_notes() {
+        let mut a = synced_host("a", "2026-01-01T00:00:00.000Z", false);
+        let mut b = synced_host("b", "2026-01-01T00:00:00.000Z", false);
+        let mut c = synced_host("c", "2026-01-01T00:00:00.000Z", false);
+        let mut d = synced_host("d", "2026-01-01T00:00:00.000Z", true);
+        for host in [&mut a, &mut b, &mut c] {
+            host.user = "root".to_string();
+        }
+        a.hostname = "Web.example.com".to_string();
+        b.hostname = "web.example.com ".to_string();
+        c.hostname = "web.example.com".to_string();
+        c.port = 2222;
+        d.hostname = "web.example.com".to_string();
+        d.user = "root".to_string();
+        a.tags = vec!["prod".to_string()];
+        a.notes = "primary".to_string();
+        b.tags = vec!["prod".to_string(), "eu".to_string()];
+        b.notes = "imported twice".to_string();
+
+        let groups = group_duplicates(vec![a.clone(), c, b.clone(), d]);
+        assert_eq!(groups.len(), 1);
+        let ids: Vec<&str> = groups[0].iter().map(|host| host.id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b"]);
+
+        merge_duplicate_fields(&mut a, &[b], "2026-03-01T00:00:00.000Z");
+        assert_eq!(a.tags, vec!["prod", "eu"]);
+        assert_eq!(a.notes, "primary\n\nimported twice");
+        assert_eq!(a.updated_at, "2026-03-01T00:00:00.000Z");
+    }
+
+    #[test]
+    fn merge_keeps_newest_copy_so_restores_survive_pull() {
+        let local = vec![
+            // Restored locally after the remote copy was deleted.
+            synced_host("a", "2026-03-02T10:00:00.000Z", false),
+            synced_host("b", "2026-03-01T10:00:00.000Z", false),
+            synced_host("local-only", "2026-03-01T10:00:00.000Z", false),
+            // Purged from the trash on another device.
+            synced_host("purged", "2026-03-01T10:00:00.000Z", true),
+        ];
+        let remote = vec![
+            synced_host("a", "2026-03-01T12:00:00+00:00", true),
+            synced_host("b", "2026-03-02T09:00:00.000Z", true),
+            synced_host("remote-only", "2026-03-01T10:00:00.000Z", false),
+        ];
+        let tombstones = std::collections::HashMap::from([
+            ("purged".to_string(), "2026-03-02T00:00:00.000Z".to_string()),
+            ("a".to_string(), "2026-03-02T00:00:00.000Z".to_string()),
+        ]);
+        let merged = merge_hosts_by_updated_at(local, remote, &tombstones);
+        let summary: Vec<(&str, bool)> = merged
+            .iter()
+            .map(|host| (host.id.as_str(), host.deleted))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("a", false),
+                ("b", true),
+                ("remote-only", false),
+                ("local-only", false)
+            ]
+        );
+    }
+
+    #[test]
+    fn profile_names_are_validated_and_normalized() {
+        assert_eq!(normalize_profile_name(" Work "), Some("work".to_string()));
+        assert_eq!(
+            normalize_profile_name("client_a-2"),
+            Some("client_a-2".to_string())
+        );
+        assert_eq!(normalize_profile_name(""), None);
+        assert_eq!(normalize_profile_name("default"), None);
+        assert_eq!(normalize_profile_name("../etc"), None);
+        assert_eq!(normalize_profile_name("a b"), None);
+    }
+
     #[test]
     fn sanitize_hosts_for_frontend_removes_plaintext_passwords() {
         let hosts = vec![Host {
@@ -412,12 +1564,37 @@ mod tests {
             port: 22,
             password: Some("secret".to_string()),
             has_password: false,
+            has_totp: false,
             host_insights_enabled: true,
             host_live_metrics_enabled: true,
             identity_file: None,
             proxy_jump: None,
+            jump_host_ids: Vec::new(),
+            proxy_command: None,
             env_vars: None,
             encoding: Some("utf-8".to_string()),
+            startup_commands: Vec::new(),
+            startup_commands_delay_ms: None,
+            probe_shell: None,
+            sync_excluded: false,
+            import_source: None,
+            mac_address: None,
+            max_session_minutes: None,
+            live_probe_lite: false,
+            extended_probe: false,
+            prompt_rules: Vec::new(),
+            forward_agent: None,
+            compression: None,
+            server_alive_count_max: None,
+            connect_timeout: None,
+            local_forwards: Vec::new(),
+            remote_forwards: Vec::new(),
+            request_tty: None,
+            host_key_algorithms: None,
+            extra_config: None,
+            bell_action: None,
+            keepalive_idle_minutes: None,
+            keepalive_string: None,
             tags: vec![],
             notes: "".to_string(),
             updated_at: "2026-01-01T00:00:00Z".to_string(),
@@ -428,4 +1605,21 @@ mod tests {
         assert_eq!(sanitized[0].password, None);
         assert!(sanitized[0].has_password);
     }
+
+    #[test]
+    fn rejected_settings_save_applies_nothing() {
+        let env = TestEnv::new();
+        let mut settings = settings_load().unwrap();
+        settings.alert_channels = vec![serde_json::from_value(serde_json::json!({
+            "id": "settings-save-smtp",
+            "kind": "smtp",
+            "smtp_password": "secret",
+        }))
+        .unwrap()];
+        settings.openssh_version = Some("banana".to_string());
+
+        assert!(settings_save(settings).is_err());
+        assert_eq!(alert_password_get("settings-save-smtp").unwrap(), None);
+        assert!(!env.config_dir().join("settings.json").exists());
+    }
 }
@@ -0,0 +1,299 @@
+use serde::Serialize;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Runtime};
+
+const MAIN_WINDOW_LABEL: &str = "main";
+const FINISHED_JOBS_KEPT: usize = 50;
+// Progress of busy jobs (a scan reporting every host) is coalesced to this
+// rate; start, finish and the last step are always emitted.
+const PROGRESS_EMIT_INTERVAL: Duration = Duration::from_millis(100);
+// Larger results (a full bulk exec report) are left to the command's own
+// return value instead of being kept in the job history.
+const JOB_RESULT_MAX_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobProgress {
+    pub done: u64,
+    /// Unknown for jobs that cannot tell up front how much work there is.
+    pub total: Option<u64>,
+}
+
+/// A transfer, sync, bulk command or other background job, as shown in the
+/// activity panel and sent with every `job:progress` event.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobInfo {
+    pub job_id: String,
+    pub kind: String,
+    pub status: JobStatus,
+    pub cancellable: bool,
+    pub progress: Option<JobProgress>,
+    pub message: Option<String>,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub result: Option<serde_json::Value>,
+    pub error: Option<String>,
+}
+
+struct JobEntry {
+    info: JobInfo,
+    last_emit: Option<Instant>,
+}
+
+type JobEmitFn = Box<dyn Fn(&JobInfo) + Send + Sync>;
+
+fn jobs() -> &'static Mutex<Vec<JobEntry>> {
+    static JOBS: OnceLock<Mutex<Vec<JobEntry>>> = OnceLock::new();
+    JOBS.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+fn job_emitter() -> &'static OnceLock<JobEmitFn> {
+    static EMITTER: OnceLock<JobEmitFn> = OnceLock::new();
+    &EMITTER
+}
+
+fn now_rfc3339() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+/// Send job updates to the main window. Jobs started before this only show
+/// up through `jobs_list`.
+pub(crate) fn init<R: Runtime>(app: AppHandle<R>) {
+    let _ = job_emitter().set(Box::new(move |info| {
+        let _ = app.emit_to(MAIN_WINDOW_LABEL, "job:progress", info);
+    }));
+}
+
+fn emit(info: Option<JobInfo>) {
+    if let (Some(info), Some(emit)) = (info, job_emitter().get()) {
+        emit(&info);
+    }
+}
+
+// Applies `f` to the job and returns a copy to emit, if one is due.
+fn update(job_id: &str, force_emit: bool, f: impl FnOnce(&mut JobInfo)) -> Option<JobInfo> {
+    let mut jobs = jobs().lock().ok()?;
+    let entry = jobs.iter_mut().find(|entry| entry.info.job_id == job_id)?;
+    f(&mut entry.info);
+    let now = Instant::now();
+    let due = force_emit
+        || entry
+            .last_emit
+            .is_none_or(|last| now.duration_since(last) >= PROGRESS_EMIT_INTERVAL);
+    if !due {
+        return None;
+    }
+    entry.last_emit = Some(now);
+    Some(entry.info.clone())
+}
+
+// Drops the oldest finished jobs beyond the history limit.
+fn prune(jobs: &mut Vec<JobEntry>) {
+    let mut finished = jobs
+        .iter()
+        .filter(|entry| entry.info.status != JobStatus::Running)
+        .count();
+    jobs.retain(|entry| {
+        if finished > FINISHED_JOBS_KEPT && entry.info.status != JobStatus::Running {
+            finished -= 1;
+            false
+        } else {
+            true
+        }
+    });
+}
+
+/// A registered job. Finish it with [`Job::finish`]; a job dropped without
+/// finishing (e.g. on a panic) is recorded as failed.
+pub(crate) struct Job {
+    id: String,
+    finished: bool,
+    // Exported over OTLP when configured.
+    span: Option<crate::telemetry::Span>,
+}
+
+/// Register a running job under `job_id` (generated when absent). A finished
+/// job with the same id is replaced, so clients may reuse ids.
+pub(crate) fn begin(job_id: Option<&str>, kind: &str, cancellable: bool) -> Job {
+    let id = job_id
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let info = JobInfo {
+        job_id: id.clone(),
+        kind: kind.to_string(),
+        status: JobStatus::Running,
+        cancellable,
+        progress: None,
+        message: None,
+        started_at: now_rfc3339(),
+        finished_at: None,
+        result: None,
+        error: None,
+    };
+    if let Ok(mut jobs) = jobs().lock() {
+        jobs.retain(|entry| entry.info.job_id != id);
+        jobs.push(JobEntry {
+            info: info.clone(),
+            last_emit: Some(Instant::now()),
+        });
+        prune(&mut jobs);
+    }
+    emit(Some(info));
+    let span = crate::telemetry::start_span(kind).map(|mut span| {
+        span.attribute("job.id", id.as_str());
+        span.attribute("job.kind", kind);
+        span
+    });
+    Job {
+        id,
+        finished: false,
+        span,
+    }
+}
+
+/// Report how far job `job_id` got; `message` replaces the previous one
+/// when set.
+pub(crate) fn report_progress(job_id: &str, done: u64, total: Option<u64>, message: Option<&str>) {
+    let last_step = total.is_some_and(|total| done >= total);
+    emit(update(job_id, last_step, |info| {
+        info.progress = Some(JobProgress { done, total });
+        if let Some(message) = message {
+            info.message = Some(message.to_string());
+        }
+    }));
+}
+
+impl Job {
+    /// Record the outcome. `cancelled` marks an `Err` that came from the user
+    /// cancelling rather than from a failure.
+    pub(crate) fn finish<T: Serialize>(mut self, result: &Result<T, String>, cancelled: bool) {
+        self.finished = true;
+        let (status, value, error) = match result {
+            Ok(value) => (
+                JobStatus::Succeeded,
+                serde_json::to_value(value)
+                    .ok()
+                    .filter(|value| value.to_string().len() <= JOB_RESULT_MAX_BYTES),
+                None,
+            ),
+            Err(error) if cancelled => (JobStatus::Cancelled, None, Some(error.clone())),
+            Err(error) => (JobStatus::Failed, None, Some(error.clone())),
+        };
+        if let Some(mut span) = self.span.take() {
+            span.attribute("job.status", format!("{status:?}").to_lowercase());
+            span.end(error.as_deref().filter(|_| status == JobStatus::Failed));
+        }
+        emit(update(&self.id, true, |info| {
+            info.status = status;
+            info.finished_at = Some(now_rfc3339());
+            info.result = value;
+            info.error = error;
+        }));
+    }
+}
+
+impl Drop for Job {
+    fn drop(&mut self) {
+        if !self.finished {
+            emit(update(&self.id, true, |info| {
+                info.status = JobStatus::Failed;
+                info.finished_at = Some(now_rfc3339());
+                info.error = Some("Job ended unexpectedly".to_string());
+            }));
+            if let Some(span) = self.span.take() {
+                span.end(Some("Job ended unexpectedly"));
+            }
+        }
+    }
+}
+
+/// Running jobs first, then the most recently finished ones.
+#[tauri::command]
+pub fn jobs_list() -> Result<Vec<JobInfo>, String> {
+    let jobs = jobs()
+        .lock()
+        .map_err(|_| "job registry poisoned".to_string())?;
+    let mut list: Vec<JobInfo> = jobs.iter().map(|entry| entry.info.clone()).collect();
+    list.sort_by(|a, b| {
+        (b.status == JobStatus::Running)
+            .cmp(&(a.status == JobStatus::Running))
+            .then_with(|| b.started_at.cmp(&a.started_at))
+    });
+    Ok(list)
+}
+
+/// Cancel a running job. Returns false if it already finished or cannot be
+/// cancelled.
+#[tauri::command]
+pub fn job_cancel(job_id: String) -> Result<bool, String> {
+    let cancellable = jobs()
+        .lock()
+        .map_err(|_| "job registry poisoned".to_string())?
+        .iter()
+        .any(|entry| {
+            entry.info.job_id == job_id.trim()
+                && entry.info.cancellable
+                && entry.info.status == JobStatus::Running
+        });
+    if !cancellable {
+        return Ok(false);
+    }
+    crate::operations::cancel(job_id.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{begin, jobs_list, report_progress, JobInfo, JobProgress, JobStatus};
+
+    fn find(job_id: &str) -> JobInfo {
+        jobs_list()
+            .unwrap()
+            .into_iter()
+            .find(|job| job.job_id == job_id)
+            .expect("job not listed")
+    }
+
+    #[test]
+    fn records_progress_and_outcome() {
+        let job = begin(Some("jobs-test-ok"), "test", false);
+        report_progress("jobs-test-ok", 3, Some(10), Some("host 3"));
+        let running = find("jobs-test-ok");
+        assert_eq!(running.status, JobStatus::Running);
+        assert_eq!(
+            running.progress,
+            Some(JobProgress {
+                done: 3,
+                total: Some(10)
+            })
+        );
+        assert_eq!(running.message.as_deref(), Some("host 3"));
+
+        job.finish(&Ok::<_, String>(vec!["a", "b"]), false);
+        let done = find("jobs-test-ok");
+        assert_eq!(done.status, JobStatus::Succeeded);
+        assert_eq!(done.result, Some(serde_json::json!(["a", "b"])));
+        assert!(done.finished_at.is_some());
+
+        begin(Some("jobs-test-cancel"), "test", true)
+            .finish(&Err::<(), _>("Operation cancelled".to_string()), true);
+        assert_eq!(find("jobs-test-cancel").status, JobStatus::Cancelled);
+
+        drop(begin(Some("jobs-test-dropped"), "test", false));
+        let dropped = find("jobs-test-dropped");
+        assert_eq!(dropped.status, JobStatus::Failed);
+        assert_eq!(dropped.error.as_deref(), Some("Job ended unexpectedly"));
+    }
+}
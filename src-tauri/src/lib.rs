@@ -1,11 +1,61 @@
+mod alerts;
 mod app;
+mod app_lock;
+mod audit_log;
+mod backup;
+mod banner_ack;
+mod bell;
+mod biometric;
+mod cloud_import;
+mod connection_history;
+mod credential_import;
 mod credential_store;
+mod data_schema;
+mod discovery;
+mod disk_usage;
+mod exec_diff;
+mod fact_tags;
+mod host_changes;
+mod host_compare;
+mod host_import;
 mod host_probe;
+mod host_rename;
+mod host_status;
 mod host_store;
+mod identity_discovery;
+mod jobs;
+mod log_bundle;
+mod metrics_history;
+#[cfg(test)]
+mod mock_backends;
 mod models;
+mod operations;
+mod probe_cache;
+mod process_info;
 mod pty;
+mod pty_io;
+mod pty_ring;
+mod reboot_schedule;
+mod remote_exec;
+mod remote_files;
+mod safe_mode;
+mod scrollback;
+mod serial;
+mod session_restore;
+mod settings_sync;
+mod shell_profiles;
+mod speedtest;
 mod ssh_config;
+mod ssh_diagnose;
 mod ssh_import;
+mod ssh_options;
+mod ssh_prompt;
+mod startup_health;
+mod tailscale;
+mod telemetry;
+mod tmux;
+mod totp;
+mod wake_on_lan;
 mod webdav_sync;
 mod webdav_url;
 
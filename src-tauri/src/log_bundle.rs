@@ -0,0 +1,345 @@
+use crate::host_probe::{probe_failure_message, probe_ssh_command, run_probe, shell_quote};
+use crate::host_store::{ensure_config_dir, get_config_dir};
+use crate::models::Host;
+use crate::operations;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+const DEFAULT_LOG_FILES: &[&str] = &[
+    "/var/log/syslog",
+    "/var/log/messages",
+    "/var/log/auth.log",
+    "/var/log/secure",
+    "/var/log/kern.log",
+];
+const DEFAULT_WINDOW_MINUTES: u32 = 30;
+const DEFAULT_MAX_FILE_KB: u64 = 10 * 1024;
+const DEFAULT_MAX_BUNDLE_MB: u64 = 100;
+const LOG_COLLECT_TIMEOUT: Duration = Duration::from_secs(1800);
+const DOWNLOAD_CHUNK_BYTES: usize = 256 * 1024;
+
+/// What to collect. Empty `files` means the usual system logs; files that do
+/// not exist or were last written before the window are skipped.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogCollectSpec {
+    /// RFC 3339 time of the incident, on the local clock.
+    pub at: String,
+    #[serde(default)]
+    pub window_minutes: Option<u32>,
+    #[serde(default)]
+    pub files: Vec<String>,
+    #[serde(default = "default_journal")]
+    pub journal: bool,
+    /// Limit the journal slice to these units; all units when empty.
+    #[serde(default)]
+    pub journal_units: Vec<String>,
+    /// Only the last this many KiB of each file or journal slice are kept.
+    #[serde(default)]
+    pub max_file_kb: Option<u64>,
+    /// The download is refused if the compressed bundle is larger.
+    #[serde(default)]
+    pub max_bundle_mb: Option<u64>,
+}
+
+fn default_journal() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogBundleResult {
+    /// Local path of the downloaded `.tar.gz`.
+    pub path: String,
+    pub size_bytes: u64,
+    /// Remote clock minus local clock; the window was shifted by it so it
+    /// covers the incident on the remote host's own timeline.
+    pub clock_offset_secs: i64,
+    pub since: String,
+    pub until: String,
+    pub included: Vec<String>,
+    pub skipped: Vec<String>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct RemoteBundle {
+    dir: Option<String>,
+    bundle: Option<String>,
+    size: Option<u64>,
+    included: Vec<String>,
+    skipped: Vec<String>,
+}
+
+fn get_diagnostics_dir() -> PathBuf {
+    get_config_dir().join("diagnostics")
+}
+
+// `/var/log/nginx/error.log` -> `var_log_nginx_error.log`
+fn bundle_entry_name(path: &str) -> String {
+    path.trim()
+        .trim_start_matches('/')
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || matches!(ch, '.' | '-' | '_') {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn collect_script(spec: &LogCollectSpec, since: i64, until: i64) -> Result<String, String> {
+    let max_bytes = spec.max_file_kb.unwrap_or(DEFAULT_MAX_FILE_KB).max(1) * 1024;
+    let files: Vec<String> = if spec.files.is_empty() {
+        DEFAULT_LOG_FILES.iter().map(|f| f.to_string()).collect()
+    } else {
+        spec.files.clone()
+    };
+    let mut script = String::from("set -eu\n");
+    script.push_str(
+        "command -v tar >/dev/null 2>&1 || { echo 'tar is not installed on the remote host' >&2; exit 127; }\n",
+    );
+    script.push_str("DIR=\"$(mktemp -d \"${TMPDIR:-/tmp}/xtermius-logs.XXXXXX\")\"\n");
+    script.push_str("mkdir \"$DIR/logs\"\n");
+    script.push_str("printf 'dir=%s\\n' \"$DIR\"\n");
+    // GNU stat first, BSD stat as the fallback.
+    script.push_str(
+        "mtime() { stat -c %Y \"$1\" 2>/dev/null || stat -f %m \"$1\" 2>/dev/null || echo 0; }\n",
+    );
+    for file in &files {
+        let path = file.trim();
+        if path.is_empty() || path.chars().any(char::is_control) {
+            return Err(format!("Invalid log file path: {path:?}"));
+        }
+        let quoted = shell_quote(path);
+        let entry = shell_quote(&bundle_entry_name(path));
+        script.push_str(&format!(
+            "if [ -r {quoted} ] && [ \"$(mtime {quoted})\" -ge {since} ]; then tail -c {max_bytes} {quoted} > \"$DIR/logs/\"{entry} && printf 'included=%s\\n' {quoted}; else printf 'skipped=%s\\n' {quoted}; fi\n"
+        ));
+    }
+    if spec.journal {
+        let mut units = String::new();
+        for unit in &spec.journal_units {
+            let unit = unit.trim();
+            if unit.is_empty()
+                || !unit
+                    .chars()
+                    .all(|ch| ch.is_ascii_alphanumeric() || "@._-:".contains(ch))
+            {
+                return Err(format!("Invalid journal unit: {unit:?}"));
+            }
+            units.push_str(&format!(" -u {}", shell_quote(unit)));
+        }
+        script.push_str(&format!(
+            "if command -v journalctl >/dev/null 2>&1 && journalctl --no-pager --utc -o short-iso --since @{since} --until @{until}{units} 2>/dev/null | tail -c {max_bytes} > \"$DIR/logs/journal.log\"; then printf 'included=journal\\n'; else rm -f \"$DIR/logs/journal.log\"; printf 'skipped=journal\\n'; fi\n"
+        ));
+    }
+    script.push_str("tar -czf \"$DIR/bundle.tar.gz\" -C \"$DIR\" logs\n");
+    script.push_str("printf 'bundle=%s\\n' \"$DIR/bundle.tar.gz\"\n");
+    script.push_str("printf 'size=%s\\n' \"$(wc -c < \"$DIR/bundle.tar.gz\" | tr -d ' ')\"\n");
+    Ok(script)
+}
+
+fn parse_remote_bundle(stdout: &str) -> RemoteBundle {
+    let mut bundle = RemoteBundle::default();
+    for line in stdout.lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim().to_string();
+        match key {
+            "dir" => bundle.dir = Some(value),
+            "bundle" => bundle.bundle = Some(value),
+            "size" => bundle.size = value.parse().ok(),
+            "included" => bundle.included.push(value),
+            "skipped" => bundle.skipped.push(value),
+            _ => {}
+        }
+    }
+    bundle
+}
+
+// Halfway between sending and receiving is the best guess for when the
+// remote clock was read; second resolution is plenty for log windows.
+fn remote_clock_offset(host: &Host) -> Result<i64, String> {
+    let sent = Utc::now();
+    let stdout = run_probe(host, "date -u +%s")?;
+    let received = Utc::now();
+    let remote: i64 = stdout
+        .trim()
+        .parse()
+        .map_err(|_| format!("Unexpected remote time: {}", stdout.trim()))?;
+    let local = sent + (received - sent) / 2;
+    Ok(remote - local.timestamp())
+}
+
+fn download_bundle(host: &Host, remote_path: &str, dest: &Path, size: u64) -> Result<(), String> {
+    let mut prepared = probe_ssh_command(host, &[], &format!("cat {}", shell_quote(remote_path)))?;
+    let mut child = prepared
+        .command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    let mut stdout = child.stdout.take().ok_or("ssh stdout unavailable")?;
+    let partial = dest.with_extension("part");
+    let result = (|| {
+        let mut file = File::create(&partial).map_err(|e| e.to_string())?;
+        let mut buf = vec![0u8; DOWNLOAD_CHUNK_BYTES];
+        let mut done = 0u64;
+        loop {
+            operations::check_current()?;
+            let n = stdout.read(&mut buf).map_err(|e| e.to_string())?;
+            if n == 0 {
+                break;
+            }
+            file.write_all(&buf[..n]).map_err(|e| e.to_string())?;
+            done += n as u64;
+            operations::report_progress(done, Some(size), None);
+        }
+        file.sync_all().map_err(|e| e.to_string())?;
+        if done != size {
+            return Err(format!("Downloaded {done} of {size} bytes"));
+        }
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = child.kill();
+    }
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    if let Err(error) = result {
+        let _ = fs::remove_file(&partial);
+        return Err(error);
+    }
+    if !output.status.success() {
+        let _ = fs::remove_file(&partial);
+        return Err(probe_failure_message(&output));
+    }
+    fs::rename(&partial, dest).map_err(|e| e.to_string())
+}
+
+fn host_collect_logs_impl(host: Host, spec: LogCollectSpec) -> Result<LogBundleResult, String> {
+    let at = DateTime::parse_from_rfc3339(spec.at.trim())
+        .map_err(|e| format!("Invalid timestamp {}: {e}", spec.at.trim()))?
+        .with_timezone(&Utc);
+    let window = i64::from(spec.window_minutes.unwrap_or(DEFAULT_WINDOW_MINUTES).max(1)) * 60;
+    let clock_offset_secs = remote_clock_offset(&host)?;
+    let since = at.timestamp() + clock_offset_secs - window;
+    let until = at.timestamp() + clock_offset_secs + window;
+
+    operations::report_progress(0, None, Some("Collecting logs"));
+    let remote = parse_remote_bundle(&run_probe(&host, &collect_script(&spec, since, until)?)?);
+    let dir = remote
+        .dir
+        .clone()
+        .ok_or("Remote log directory was not reported")?;
+    let result = (|| {
+        let bundle = remote
+            .bundle
+            .as_deref()
+            .ok_or("Remote bundle was not reported")?;
+        let size = remote.size.ok_or("Remote bundle size was not reported")?;
+        let max_bytes = spec.max_bundle_mb.unwrap_or(DEFAULT_MAX_BUNDLE_MB) * 1024 * 1024;
+        if size > max_bytes {
+            return Err(format!(
+                "Log bundle is {size} bytes, over the {max_bytes} byte limit"
+            ));
+        }
+        ensure_config_dir()?;
+        let diagnostics_dir = get_diagnostics_dir();
+        fs::create_dir_all(&diagnostics_dir).map_err(|e| e.to_string())?;
+        let name = if host.alias.trim().is_empty() {
+            host.hostname.trim()
+        } else {
+            host.alias.trim()
+        };
+        let dest = diagnostics_dir.join(format!(
+            "{}-{}.tar.gz",
+            bundle_entry_name(name),
+            at.format("%Y%m%dT%H%M%SZ")
+        ));
+        operations::report_progress(0, Some(size), Some("Downloading bundle"));
+        download_bundle(&host, bundle, &dest, size)?;
+        Ok((dest, size))
+    })();
+    // The remote copy is only a staging area; never leave logs behind.
+    let _ = run_probe(&host, &format!("rm -rf -- {}", shell_quote(&dir)));
+    let (dest, size_bytes) = result?;
+
+    let timestamp = |secs: i64| {
+        DateTime::from_timestamp(secs, 0)
+            .map(|dt| dt.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+            .unwrap_or_default()
+    };
+    Ok(LogBundleResult {
+        path: dest.to_string_lossy().to_string(),
+        size_bytes,
+        clock_offset_secs,
+        since: timestamp(since),
+        until: timestamp(until),
+        included: remote.included,
+        skipped: remote.skipped,
+    })
+}
+
+/// Gather log files and a journal slice from around `spec.at`, compress
+/// them on the host and download the bundle into the diagnostics folder,
+/// e.g. to attach to a vendor ticket.
+#[tauri::command]
+pub async fn host_collect_logs(
+    host: Host,
+    spec: LogCollectSpec,
+    op_id: String,
+) -> Result<LogBundleResult, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        operations::run(op_id, "host_collect_logs", LOG_COLLECT_TIMEOUT, || {
+            host_collect_logs_impl(host, spec)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bundle_entry_name, collect_script, parse_remote_bundle, LogCollectSpec};
+
+    #[test]
+    fn builds_collect_script_and_parses_report() {
+        let spec: LogCollectSpec = serde_json::from_str(
+            r#"{"at":"2026-03-01T10:00:00Z","files":["/var/log/app's.log"],"journalUnits":["nginx.service"],"maxFileKb":64}"#,
+        )
+        .unwrap();
+        assert!(spec.journal);
+        let script = collect_script(&spec, 1000, 2000).unwrap();
+        assert!(script.contains(
+            r#"tail -c 65536 '/var/log/app'\''s.log' > "$DIR/logs/"'var_log_app_s.log'"#
+        ));
+        assert!(script.contains("--since @1000 --until @2000 -u 'nginx.service'"));
+
+        let bad_unit = LogCollectSpec {
+            journal_units: vec!["x; rm -rf /".to_string()],
+            ..spec
+        };
+        assert!(collect_script(&bad_unit, 1000, 2000).is_err());
+        assert_eq!(
+            bundle_entry_name("/var/log/nginx/error.log"),
+            "var_log_nginx_error.log"
+        );
+
+        let report = parse_remote_bundle(
+            "dir=/tmp/xtermius-logs.abc\nincluded=/var/log/syslog\nskipped=/var/log/secure\nincluded=journal\nbundle=/tmp/xtermius-logs.abc/bundle.tar.gz\nsize=2048\n",
+        );
+        assert_eq!(report.dir.as_deref(), Some("/tmp/xtermius-logs.abc"));
+        assert_eq!(report.size, Some(2048));
+        assert_eq!(report.included, vec!["/var/log/syslog", "journal"]);
+        assert_eq!(report.skipped, vec!["/var/log/secure"]);
+    }
+}
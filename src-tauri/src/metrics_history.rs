@@ -0,0 +1,309 @@
+// Live probe samples kept for the dashboard's history graphs. One row per
+// host and minute for the last day, rolled up into hourly rows after that and
+// dropped after 30 days. Kept in its own metrics.db so the polling writes never
+// contend with hosts.db and never travel with a synced copy.
+
+use crate::host_probe::HostLiveInfo;
+use crate::host_store::{ensure_config_dir, get_config_dir};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+const MINUTE_SECS: i64 = 60;
+const HOUR_SECS: i64 = 60 * 60;
+const MINUTE_RETENTION_SECS: i64 = 24 * HOUR_SECS;
+const HOUR_RETENTION_SECS: i64 = 30 * 24 * HOUR_SECS;
+// Compaction only has whole hours to do, so running it more often is wasted.
+const COMPACT_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum MetricsRange {
+    #[serde(rename = "1h")]
+    Hour,
+    #[serde(rename = "24h")]
+    Day,
+    #[serde(rename = "7d")]
+    Week,
+    #[serde(rename = "30d")]
+    Month,
+}
+
+impl MetricsRange {
+    fn span_secs(self) -> i64 {
+        match self {
+            Self::Hour => HOUR_SECS,
+            Self::Day => 24 * HOUR_SECS,
+            Self::Week => 7 * 24 * HOUR_SECS,
+            Self::Month => 30 * 24 * HOUR_SECS,
+        }
+    }
+
+    /// Width of one point, for at most a few hundred per graph.
+    fn step_secs(self) -> i64 {
+        match self {
+            Self::Hour => MINUTE_SECS,
+            Self::Day => 5 * MINUTE_SECS,
+            Self::Week => HOUR_SECS,
+            Self::Month => 4 * HOUR_SECS,
+        }
+    }
+}
+
+/// One point of a host's history: averages over the step starting at `ts`,
+/// except `disk_percent`, which is the peak.
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricsSample {
+    /// Unix seconds.
+    pub ts: i64,
+    pub cpu_percent: Option<f64>,
+    pub mem_percent: Option<f64>,
+    pub load_1: Option<f64>,
+    /// Of the fullest filesystem, so a full /var shows even when / is not.
+    pub disk_percent: Option<f64>,
+}
+
+fn get_metrics_db_path() -> PathBuf {
+    get_config_dir().join("metrics.db")
+}
+
+fn open_metrics_db() -> Result<Connection, String> {
+    ensure_config_dir()?;
+    let conn = Connection::open(get_metrics_db_path()).map_err(|e| e.to_string())?;
+    conn.busy_timeout(Duration::from_secs(5))
+        .map_err(|e| e.to_string())?;
+    ensure_metrics_schema(&conn)?;
+    Ok(conn)
+}
+
+// `bucket_secs` is 60 for samples and 3600 for rolled-up hours; `ts` is the
+// start of the bucket.
+fn ensure_metrics_schema(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS metrics (
+          host_id      TEXT NOT NULL,
+          bucket_secs  INTEGER NOT NULL,
+          ts           INTEGER NOT NULL,
+          cpu_percent  REAL,
+          mem_percent  REAL,
+          load_1       REAL,
+          disk_percent REAL,
+          PRIMARY KEY (host_id, bucket_secs, ts)
+        );
+        "#,
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn percent(used: Option<u64>, total: Option<u64>) -> Option<f64> {
+    let total = total.filter(|total| *total > 0)?;
+    Some(used? as f64 * 100.0 / total as f64)
+}
+
+fn disk_percent(info: &HostLiveInfo) -> Option<f64> {
+    info.filesystems
+        .iter()
+        .filter_map(|fs| percent(Some(fs.used_kb), Some(fs.total_kb)))
+        .reduce(f64::max)
+        .or_else(|| percent(info.disk_root_used_kb, info.disk_root_total_kb))
+}
+
+// The last sample within a minute replaces the earlier ones.
+fn insert_sample(
+    conn: &Connection,
+    host_id: &str,
+    now: i64,
+    info: &HostLiveInfo,
+) -> Result<(), String> {
+    conn.execute(
+        r#"
+        INSERT OR REPLACE INTO metrics (
+          host_id, bucket_secs, ts, cpu_percent, mem_percent, load_1, disk_percent
+        )
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+        "#,
+        params![
+            host_id,
+            MINUTE_SECS,
+            now - now.rem_euclid(MINUTE_SECS),
+            info.cpu_percent,
+            percent(info.mem_used_kb, info.mem_total_kb),
+            info.load_1,
+            disk_percent(info)
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Roll minute rows older than a day up into hours and drop hours older
+/// than 30 days. Only whole hours are rolled up, so an hour is never
+/// written twice.
+fn compact(conn: &mut Connection, now: i64) -> Result<(), String> {
+    let cutoff = now - MINUTE_RETENTION_SECS;
+    let cutoff = cutoff - cutoff.rem_euclid(HOUR_SECS);
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        r#"
+        INSERT OR REPLACE INTO metrics (
+          host_id, bucket_secs, ts, cpu_percent, mem_percent, load_1, disk_percent
+        )
+        SELECT host_id, ?1, ts - ts % ?1, AVG(cpu_percent), AVG(mem_percent), AVG(load_1),
+               MAX(disk_percent)
+        FROM metrics
+        WHERE bucket_secs = ?2 AND ts < ?3
+        GROUP BY host_id, ts - ts % ?1
+        "#,
+        params![HOUR_SECS, MINUTE_SECS, cutoff],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "DELETE FROM metrics WHERE bucket_secs = ?1 AND ts < ?2",
+        params![MINUTE_SECS, cutoff],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "DELETE FROM metrics WHERE ts < ?1",
+        params![now - HOUR_RETENTION_SECS],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+fn last_compaction() -> &'static Mutex<Option<Instant>> {
+    static LAST: OnceLock<Mutex<Option<Instant>>> = OnceLock::new();
+    LAST.get_or_init(|| Mutex::new(None))
+}
+
+fn compaction_due() -> bool {
+    let Ok(mut last) = last_compaction().lock() else {
+        return false;
+    };
+    if last.is_some_and(|at| at.elapsed() < COMPACT_INTERVAL) {
+        return false;
+    }
+    *last = Some(Instant::now());
+    true
+}
+
+/// Keep a live probe result for the history graphs. Best effort: a failed
+/// write must not fail the probe.
+pub(crate) fn record(host_id: &str, info: &HostLiveInfo) {
+    let now = chrono::Utc::now().timestamp();
+    let written = open_metrics_db().and_then(|mut conn| {
+        insert_sample(&conn, host_id, now, info)?;
+        if compaction_due() {
+            compact(&mut conn, now)?;
+        }
+        Ok(())
+    });
+    if let Err(e) = written {
+        eprintln!("[metrics] failed to record a sample for {host_id}: {e}");
+    }
+}
+
+fn query_history(
+    conn: &Connection,
+    host_id: &str,
+    range: MetricsRange,
+    now: i64,
+) -> Result<Vec<MetricsSample>, String> {
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT ts - ts % ?2 AS bucket, AVG(cpu_percent), AVG(mem_percent), AVG(load_1),
+                   MAX(disk_percent)
+            FROM metrics
+            WHERE host_id = ?1 AND ts >= ?3
+            GROUP BY bucket
+            ORDER BY bucket
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(
+            params![host_id, range.step_secs(), now - range.span_secs()],
+            |row| {
+                Ok(MetricsSample {
+                    ts: row.get(0)?,
+                    cpu_percent: row.get(1)?,
+                    mem_percent: row.get(2)?,
+                    load_1: row.get(3)?,
+                    disk_percent: row.get(4)?,
+                })
+            },
+        )
+        .map_err(|e| e.to_string())?;
+    rows.map(|r| r.map_err(|e| e.to_string())).collect()
+}
+
+/// A host's CPU, memory, load and disk use over `range`, oldest first, from
+/// the live probes run while the app was open; gaps are simply missing.
+#[tauri::command]
+pub fn host_metrics_history(
+    host_id: String,
+    range: MetricsRange,
+) -> Result<Vec<MetricsSample>, String> {
+    let conn = open_metrics_db()?;
+    query_history(&conn, host_id.trim(), range, chrono::Utc::now().timestamp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compact, ensure_metrics_schema, insert_sample, query_history, MetricsRange};
+    use crate::host_probe::HostLiveInfo;
+    use rusqlite::Connection;
+
+    fn live(cpu: f64, disk_used_kb: u64) -> HostLiveInfo {
+        HostLiveInfo {
+            cpu_percent: Some(cpu),
+            mem_total_kb: Some(1000),
+            mem_used_kb: Some(250),
+            disk_root_total_kb: Some(100),
+            disk_root_used_kb: Some(disk_used_kb),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn downsamples_old_samples_and_queries_by_range() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        ensure_metrics_schema(&conn).unwrap();
+        let now = 1_780_000_000 - 1_780_000_000 % 3600;
+        // Two samples in the same minute: the later one wins.
+        insert_sample(&conn, "web", now - 30, &live(90.0, 10)).unwrap();
+        insert_sample(&conn, "web", now - 10, &live(10.0, 10)).unwrap();
+        // Two days ago, in one hour.
+        insert_sample(&conn, "web", now - 48 * 3600, &live(20.0, 50)).unwrap();
+        insert_sample(&conn, "web", now - 48 * 3600 + 600, &live(40.0, 70)).unwrap();
+        // Past retention.
+        insert_sample(&conn, "web", now - 40 * 24 * 3600, &live(1.0, 1)).unwrap();
+        insert_sample(&conn, "db", now - 60, &live(5.0, 5)).unwrap();
+
+        compact(&mut conn, now).unwrap();
+        let rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM metrics", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(rows, 3);
+
+        let hour = query_history(&conn, "web", MetricsRange::Hour, now).unwrap();
+        assert_eq!(hour.len(), 1);
+        assert_eq!(hour[0].cpu_percent, Some(10.0));
+        assert_eq!(hour[0].mem_percent, Some(25.0));
+
+        let week = query_history(&conn, "web", MetricsRange::Week, now).unwrap();
+        assert_eq!(week.len(), 2);
+        assert_eq!(week[0].ts, now - 48 * 3600);
+        assert_eq!(week[0].cpu_percent, Some(30.0));
+        assert_eq!(week[0].disk_percent, Some(70.0));
+        assert_eq!(
+            query_history(&conn, "web", MetricsRange::Month, now)
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+}
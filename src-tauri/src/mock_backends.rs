@@ -0,0 +1,270 @@
+// In-memory stand-ins for the keychain, ssh and the OS presence check, plus a per-test
+// environment (temporary config dir, in-memory hosts DB) and a host fixture,
+// so host storage, sync merging and probes can be tested without a real
+// keychain or remote servers. Compiled for unit tests only.
+
+use crate::biometric::PasswordPresence;
+use crate::models::Host;
+use keyring::credential::{Credential, CredentialApi, CredentialBuilderApi, CredentialPersistence};
+use keyring::Error as KeyringError;
+use rusqlite::Connection;
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, Once, OnceLock};
+
+/// Like real backends the mock keychain limits secret size: longer secrets
+/// are rejected with `TooLong`, or cut to the limit for services ending in
+/// `truncating` (as some Secret Service setups do).
+pub(crate) const MOCK_SECRET_LIMIT: usize = 2000;
+
+type SecretStore = Mutex<HashMap<(String, String), Vec<u8>>>;
+type SshResponder = Arc<dyn Fn(&str) -> Result<String, String> + Send + Sync>;
+
+fn secret_store() -> &'static SecretStore {
+    static STORE: OnceLock<SecretStore> = OnceLock::new();
+    STORE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn ssh_responders() -> &'static Mutex<HashMap<String, SshResponder>> {
+    static RESPONDERS: OnceLock<Mutex<HashMap<String, SshResponder>>> = OnceLock::new();
+    RESPONDERS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+struct MockCredential {
+    key: (String, String),
+}
+
+impl CredentialApi for MockCredential {
+    fn set_secret(&self, secret: &[u8]) -> keyring::Result<()> {
+        let mut secret = secret.to_vec();
+        if secret.len() > MOCK_SECRET_LIMIT {
+            if !self.key.0.ends_with("truncating") {
+                return Err(KeyringError::TooLong(
+                    "password".to_string(),
+                    MOCK_SECRET_LIMIT as u32,
+                ));
+            }
+            secret.truncate(MOCK_SECRET_LIMIT);
+        }
+        let mut store = secret_store().lock().unwrap_or_else(|e| e.into_inner());
+        store.insert(self.key.clone(), secret);
+        Ok(())
+    }
+
+    fn get_secret(&self) -> keyring::Result<Vec<u8>> {
+        let store = secret_store().lock().unwrap_or_else(|e| e.into_inner());
+        store.get(&self.key).cloned().ok_or(KeyringError::NoEntry)
+    }
+
+    fn delete_credential(&self) -> keyring::Result<()> {
+        let mut store = secret_store().lock().unwrap_or_else(|e| e.into_inner());
+        store
+            .remove(&self.key)
+            .map(|_| ())
+            .ok_or(KeyringError::NoEntry)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+struct MockCredentialBuilder;
+
+impl CredentialBuilderApi for MockCredentialBuilder {
+    fn build(
+        &self,
+        _target: Option<&str>,
+        service: &str,
+        user: &str,
+    ) -> keyring::Result<Box<Credential>> {
+        Ok(Box::new(MockCredential {
+            key: (service.to_string(), user.to_string()),
+        }))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn persistence(&self) -> CredentialPersistence {
+        CredentialPersistence::ProcessOnly
+    }
+}
+
+/// Route every keychain access of this process to the in-memory store.
+/// Unlike keyring's own mock, entries for the same service and account share
+/// their secret, so a value written by one call is read by the next.
+pub(crate) fn install_mock_keychain() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| keyring::set_default_credential_builder(Box::new(MockCredentialBuilder)));
+}
+
+/// The value stored for an entry as the backend holds it (a shard manifest
+/// rather than the reassembled secret).
+pub(crate) fn mock_keychain_value(service: &str, account: &str) -> Option<String> {
+    let store = secret_store().lock().unwrap_or_else(|e| e.into_inner());
+    store
+        .get(&(service.to_string(), account.to_string()))
+        .map(|bytes| String::from_utf8_lossy(bytes).into_owned())
+}
+
+/// A live host `id` at `<id>.example.com`, everything else as a freshly
+/// added host has it. Tests set the fields they care about with struct
+/// update syntax.
+pub(crate) fn test_host(id: &str) -> Host {
+    serde_json::from_value(serde_json::json!({
+        "id": id,
+        "hostname": format!("{id}.example.com"),
+    }))
+    .expect("test host fixture")
+}
+
+/// Answer probes of the host with `respond(script)` instead of running ssh.
+/// Only `run_probe`-style calls are covered, not streamed `ssh` commands.
+pub(crate) fn mock_ssh<F>(host_id: &str, respond: F)
+where
+    F: Fn(&str) -> Result<String, String> + Send + Sync + 'static,
+{
+    let mut responders = ssh_responders().lock().unwrap_or_else(|e| e.into_inner());
+    responders.insert(host_id.to_string(), Arc::new(respond));
+}
+
+pub(crate) fn mock_ssh_response(host: &Host, script: &str) -> Option<Result<String, String>> {
+    let responder = ssh_responders().lock().ok()?.get(&host.id).cloned()?;
+    Some(responder(script))
+}
+
+// Per thread, so tests running in parallel each see their own environment.
+thread_local! {
+    static CONFIG_DIR: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+    static HOSTS_DB_URI: RefCell<Option<String>> = const { RefCell::new(None) };
+    static PRESENCE: Cell<Option<(PasswordPresence, bool)>> = const { Cell::new(None) };
+}
+
+/// Password access from this thread runs under `mode`, and the OS presence
+/// check (Touch ID / Windows Hello) answers `confirmed` without a prompt.
+pub(crate) fn mock_presence(mode: PasswordPresence, confirmed: bool) {
+    PRESENCE.with(|slot| slot.set(Some((mode, confirmed))));
+}
+
+pub(crate) fn presence_override() -> Option<(PasswordPresence, bool)> {
+    PRESENCE.with(Cell::get)
+}
+
+pub(crate) fn config_dir_override() -> Option<PathBuf> {
+    CONFIG_DIR.with(|dir| dir.borrow().clone())
+}
+
+pub(crate) fn hosts_db_uri_override() -> Option<String> {
+    HOSTS_DB_URI.with(|uri| uri.borrow().clone())
+}
+
+/// A throwaway config dir and in-memory hosts DB for the current thread,
+/// with the mock keychain installed. Both go away when the value is dropped.
+pub(crate) struct TestEnv {
+    dir: PathBuf,
+    // A shared-cache memory DB lives as long as one connection to it is open.
+    _hosts_db: Connection,
+}
+
+impl TestEnv {
+    pub(crate) fn new() -> Self {
+        install_mock_keychain();
+        let id = uuid::Uuid::new_v4();
+        let dir = std::env::temp_dir().join(format!("xtermius-test-{id}"));
+        std::fs::create_dir_all(&dir).expect("create test config dir");
+        let uri = format!("file:xtermius-test-{id}?mode=memory&cache=shared");
+        let hosts_db = Connection::open(&uri).expect("open in-memory hosts DB");
+        CONFIG_DIR.with(|slot| *slot.borrow_mut() = Some(dir.clone()));
+        HOSTS_DB_URI.with(|slot| *slot.borrow_mut() = Some(uri));
+        Self {
+            dir,
+            _hosts_db: hosts_db,
+        }
+    }
+
+    pub(crate) fn config_dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+impl Default for TestEnv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for TestEnv {
+    fn drop(&mut self) {
+        CONFIG_DIR.with(|slot| *slot.borrow_mut() = None);
+        HOSTS_DB_URI.with(|slot| *slot.borrow_mut() = None);
+        PRESENCE.with(|slot| slot.set(None));
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{test_host, TestEnv};
+    use crate::credential_store::keychain_get_password;
+    use crate::host_store::{host_retag, hosts_load, hosts_save, merge_hosts_by_updated_at};
+    use crate::models::Host;
+
+    fn host(id: &str, updated_at: &str) -> Host {
+        Host {
+            alias: format!("{id}-alias"),
+            user: "deploy".to_string(),
+            updated_at: updated_at.to_string(),
+            ..test_host(id)
+        }
+    }
+
+    #[test]
+    fn hosts_round_trip_through_db_and_keychain() {
+        let env = TestEnv::new();
+        let id = uuid::Uuid::new_v4().to_string();
+        let mut local = host(&id, "2026-01-02T00:00:00.000Z");
+        local.password = Some("s3cret".to_string());
+        local.keepalive_idle_minutes = Some(5);
+        local.keepalive_string = Some(" \x08".to_string());
+        hosts_save(vec![local]).unwrap();
+
+        let loaded = hosts_load().unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert!(loaded[0].has_password);
+        assert_eq!(loaded[0].keepalive_idle_minutes, Some(5));
+        assert_eq!(loaded[0].keepalive_string.as_deref(), Some(" \x08"));
+        assert_eq!(loaded[0].password, None);
+        assert_eq!(
+            keychain_get_password(&id, "test").unwrap().as_deref(),
+            Some("s3cret")
+        );
+        assert!(env.config_dir().join("ssh_config").is_file());
+
+        // A stale remote copy from another device loses to the saved one.
+        let mut remote = host(&id, "2026-01-01T00:00:00.000Z");
+        remote.user = "root".to_string();
+        let merged = merge_hosts_by_updated_at(loaded, vec![remote], &Default::default());
+        hosts_save(merged).unwrap();
+        assert_eq!(hosts_load().unwrap()[0].user, "deploy");
+    }
+
+    #[test]
+    fn retagging_leaves_the_rest_of_the_host_alone() {
+        let _env = TestEnv::new();
+        let mut saved = host("a", "2026-01-02T00:00:00.000Z");
+        saved.tags = vec!["prod".to_string(), "os=debian11".to_string()];
+        hosts_save(vec![saved]).unwrap();
+
+        let add = vec!["os=debian12".to_string()];
+        let remove = vec!["os=debian11".to_string()];
+        assert!(host_retag("a", &add, &remove).unwrap());
+        assert!(!host_retag("a", &add, &remove).unwrap());
+        let loaded = hosts_load().unwrap();
+        assert_eq!(loaded[0].tags, vec!["prod", "os=debian12"]);
+        assert_eq!(loaded[0].updated_at, "2026-01-02T00:00:00.000Z");
+    }
+}
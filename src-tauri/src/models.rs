@@ -1,6 +1,10 @@
+use crate::alerts::{AlertChannel, AlertRule};
+use crate::bell::BellAction;
+use crate::biometric::PasswordPresence;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct Host {
     pub id: String,
     #[serde(rename = "sortOrder")]
@@ -20,6 +24,10 @@ pub struct Host {
     #[serde(rename = "hasPassword")]
     #[serde(default)]
     pub has_password: bool,
+    /// A TOTP secret for the host is in the keychain (see `host_totp_code`).
+    #[serde(rename = "hasTotp")]
+    #[serde(default)]
+    pub has_totp: bool,
     #[serde(rename = "hostInsightsEnabled")]
     #[serde(default = "default_true")]
     pub host_insights_enabled: bool,
@@ -30,11 +38,105 @@ pub struct Host {
     pub identity_file: Option<String>,
     #[serde(rename = "proxyJump")]
     pub proxy_jump: Option<String>,
+    /// Jump hosts by id, first hop first. When set, the `ProxyJump` written
+    /// for the host is built from their aliases and `proxy_jump` is ignored.
+    #[serde(rename = "jumpHostIds")]
+    #[serde(default)]
+    pub jump_host_ids: Vec<String>,
+    /// `ProxyCommand` for transports plain SSH cannot reach (cloudflared,
+    /// corkscrew, AWS SSM...). Runs locally, so it is never taken from a
+    /// synced copy of the host; excludes `proxy_jump` and `jump_host_ids`.
+    #[serde(rename = "proxyCommand")]
+    #[serde(default)]
+    pub proxy_command: Option<String>,
     #[serde(rename = "envVars")]
     #[serde(default)]
     pub env_vars: Option<String>,
     #[serde(default)]
     pub encoding: Option<String>,
+    #[serde(rename = "startupCommands")]
+    #[serde(default)]
+    pub startup_commands: Vec<String>,
+    #[serde(rename = "startupCommandsDelayMs")]
+    #[serde(default)]
+    pub startup_commands_delay_ms: Option<u64>,
+    #[serde(rename = "probeShell")]
+    #[serde(default)]
+    pub probe_shell: Option<String>,
+    #[serde(rename = "syncExcluded")]
+    #[serde(default)]
+    pub sync_excluded: bool,
+    /// Where an imported host came from (`tailscale:<node id>`), so a
+    /// re-import updates it instead of adding a duplicate.
+    #[serde(rename = "importSource")]
+    #[serde(default)]
+    pub import_source: Option<String>,
+    /// For Wake-on-LAN, as `aa:bb:cc:dd:ee:ff`.
+    #[serde(rename = "macAddress")]
+    #[serde(default)]
+    pub mac_address: Option<String>,
+    /// Sessions to the host are disconnected after this long, e.g. to
+    /// time-box access to production.
+    #[serde(rename = "maxSessionMinutes")]
+    #[serde(default)]
+    pub max_session_minutes: Option<u32>,
+    /// Use the lightweight live probe (routers, small boards).
+    #[serde(rename = "liveProbeLite")]
+    #[serde(default)]
+    pub live_probe_lite: bool,
+    /// Add temperatures and GPU metrics to the live probe; off by default
+    /// because `nvidia-smi` and `rocm-smi` can take seconds.
+    #[serde(rename = "extendedProbe")]
+    #[serde(default)]
+    pub extended_probe: bool,
+    /// ssh_config options; unset ones keep ssh's defaults.
+    #[serde(rename = "forwardAgent")]
+    #[serde(default)]
+    pub forward_agent: Option<bool>,
+    #[serde(default)]
+    pub compression: Option<bool>,
+    #[serde(rename = "serverAliveCountMax")]
+    #[serde(default)]
+    pub server_alive_count_max: Option<u32>,
+    #[serde(rename = "connectTimeout")]
+    #[serde(default)]
+    pub connect_timeout: Option<u32>,
+    /// `LocalForward` specs, e.g. `8080 localhost:80`.
+    #[serde(rename = "localForwards")]
+    #[serde(default)]
+    pub local_forwards: Vec<String>,
+    #[serde(rename = "remoteForwards")]
+    #[serde(default)]
+    pub remote_forwards: Vec<String>,
+    /// `yes`, `no`, `force` or `auto`.
+    #[serde(rename = "requestTty")]
+    #[serde(default)]
+    pub request_tty: Option<String>,
+    #[serde(rename = "hostKeyAlgorithms")]
+    #[serde(default)]
+    pub host_key_algorithms: Option<String>,
+    /// Further `ssh_config` lines for the host block, kept verbatim (e.g.
+    /// from an imported config).
+    #[serde(rename = "extraConfig")]
+    #[serde(default)]
+    pub extra_config: Option<String>,
+    /// What a terminal bell in the host's sessions does; a sound when unset.
+    #[serde(rename = "bellAction")]
+    #[serde(default)]
+    pub bell_action: Option<BellAction>,
+    /// Type `keepalive_string` into sessions that sat idle this long, for
+    /// firewalls that drop idle connections despite `ServerAliveInterval`.
+    #[serde(rename = "keepaliveIdleMinutes")]
+    #[serde(default)]
+    pub keepalive_idle_minutes: Option<u32>,
+    /// A NUL byte when unset.
+    #[serde(rename = "keepaliveString")]
+    #[serde(default)]
+    pub keepalive_string: Option<String>,
+    /// Answered automatically in the host's terminal sessions.
+    #[serde(rename = "promptRules")]
+    #[serde(default)]
+    pub prompt_rules: Vec<PromptRule>,
     #[serde(default)]
     pub tags: Vec<String>,
     #[serde(default)]
@@ -46,6 +148,24 @@ pub struct Host {
     pub deleted: bool,
 }
 
+/// An expect-style answer, typed when the line a session's output ends on
+/// matches `pattern`, e.g. `\[sudo\] password for`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptRule {
+    /// A regular expression.
+    pub pattern: String,
+    /// Typed followed by Enter; `{password}` stands for the host's saved
+    /// password.
+    pub response: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Answers per session before the rule stops, so a rejected password is
+    /// not replayed forever; 3 when unset, at most 20.
+    #[serde(default)]
+    pub max_replays: Option<u32>,
+}
+
 fn default_port() -> u16 {
     22
 }
@@ -54,7 +174,7 @@ fn default_true() -> bool {
     true
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Settings {
     pub webdav_url: Option<String>,
     #[serde(default)]
@@ -66,4 +186,43 @@ pub struct Settings {
     pub webdav_password: Option<String>,
     #[serde(default, skip_serializing)]
     pub webdav_password_clear: bool,
+    #[serde(default)]
+    pub password_presence: PasswordPresence,
+    /// Tags whose hosts stay on this device and are left out of WebDAV sync.
+    #[serde(default)]
+    pub sync_excluded_tags: Vec<String>,
+    /// Per-session scrollback kept in memory before older output spills to disk.
+    #[serde(default)]
+    pub scrollback_memory_kb: Option<u64>,
+    /// Per-session cap on scrollback spilled to disk, in MiB (64 by default);
+    /// 0 keeps only what fits in memory.
+    #[serde(default)]
+    pub scrollback_spill_mb: Option<u64>,
+    /// Where alerts are sent; rules pick channels by id.
+    #[serde(default)]
+    pub alert_channels: Vec<AlertChannel>,
+    /// Conditions watched on pings and live probes, each sent to the
+    /// channels it selects.
+    #[serde(default)]
+    pub alert_rules: Vec<AlertRule>,
+    /// Use the lightweight live probe for every host.
+    #[serde(default)]
+    pub live_probe_lite: bool,
+    /// OTLP/HTTP collector to send job traces to (e.g.
+    /// `http://localhost:4318`); no export when unset.
+    #[serde(default)]
+    pub otlp_endpoint: Option<String>,
+    /// Processes that hold off a scheduled reboot; a built-in list of package
+    /// managers and backup tools when unset.
+    #[serde(default)]
+    pub reboot_critical_processes: Option<Vec<String>>,
+    /// OpenSSH release (e.g. `9.6`) that hosts' extra config is checked
+    /// against; every known option is accepted when unset.
+    #[serde(default)]
+    pub openssh_version: Option<String>,
+    /// Seconds a host's shared SSH connection stays open after its last
+    /// session, tab or probe ends; 0 closes it with the last one. 60 when
+    /// unset.
+    #[serde(default)]
+    pub ssh_control_persist_secs: Option<u64>,
 }
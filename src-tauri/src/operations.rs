@@ -0,0 +1,440 @@
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{ErrorKind, Read};
+use std::process::{Child, Command, Output, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+const CHILD_POLL_INTERVAL: Duration = Duration::from_millis(50);
+// Per stream. Anything past the cap is still read (so the child never blocks
+// on a full pipe) but dropped instead of buffered.
+pub(crate) const DEFAULT_OUTPUT_CAP_BYTES: usize = 1024 * 1024;
+const MIN_OUTPUT_CAP_BYTES: usize = 4 * 1024;
+const MAX_OUTPUT_CAP_BYTES: usize = 64 * 1024 * 1024;
+
+/// A running long-running command, as listed for the UI.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationInfo {
+    pub op_id: String,
+    pub kind: String,
+    pub started_at: String,
+    pub timeout_secs: u64,
+}
+
+struct OperationState {
+    info: OperationInfo,
+    cancelled: AtomicBool,
+    deadline: Instant,
+    output_cap: AtomicUsize,
+    // Set for a part of an operation with its own timeout (`with_timeout`).
+    parent: Option<Operation>,
+}
+
+/// Set when a command printed more than the output cap; sizes are what the
+/// command wrote, not what was kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputTruncation {
+    pub stdout_bytes: u64,
+    pub stderr_bytes: u64,
+    pub cap_bytes: u64,
+}
+
+/// Handle to a registered operation. Cheap to clone so worker threads of a
+/// multi-host command can share their parent's cancellation.
+#[derive(Clone)]
+pub(crate) struct Operation(Arc<OperationState>);
+
+impl Operation {
+    pub(crate) fn id(&self) -> &str {
+        &self.0.info.op_id
+    }
+
+    fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// `Err` once the operation was cancelled or ran past its timeout.
+    pub(crate) fn check(&self) -> Result<(), String> {
+        if let Some(parent) = &self.0.parent {
+            parent.check()?;
+        }
+        if self.0.cancelled.load(Ordering::SeqCst) {
+            return Err("Operation cancelled".to_string());
+        }
+        if Instant::now() >= self.0.deadline {
+            return Err(format!(
+                "Operation timed out after {}s",
+                self.0.info.timeout_secs
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn operations() -> &'static Mutex<HashMap<String, Operation>> {
+    static OPERATIONS: OnceLock<Mutex<HashMap<String, Operation>>> = OnceLock::new();
+    OPERATIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Operation>> = const { RefCell::new(None) };
+}
+
+// Drops the registry entry even if the operation panics.
+struct Registration(String);
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        if let Ok(mut ops) = operations().lock() {
+            ops.remove(&self.0);
+        }
+    }
+}
+
+// Restores the previous current operation of this thread.
+struct CurrentGuard(Option<Operation>);
+
+impl Drop for CurrentGuard {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        CURRENT.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+/// Register an operation under `op_id` and run `f` with it as this thread's
+/// current operation. The client picks the id, since it is how it cancels
+/// the operation before the command returns. The operation is also listed as
+/// a cancellable job with the same id.
+pub(crate) fn run<T: Serialize>(
+    op_id: String,
+    kind: &str,
+    timeout: Duration,
+    f: impl FnOnce() -> Result<T, String>,
+) -> Result<T, String> {
+    let op_id = op_id.trim().to_string();
+    if op_id.is_empty() {
+        return Err("opId is required".to_string());
+    }
+    let op = Operation(Arc::new(OperationState {
+        info: OperationInfo {
+            op_id: op_id.clone(),
+            kind: kind.to_string(),
+            started_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            timeout_secs: timeout.as_secs(),
+        },
+        cancelled: AtomicBool::new(false),
+        deadline: Instant::now() + timeout,
+        output_cap: AtomicUsize::new(DEFAULT_OUTPUT_CAP_BYTES),
+        parent: None,
+    }));
+    {
+        let mut ops = operations()
+            .lock()
+            .map_err(|_| "operations registry poisoned".to_string())?;
+        if ops.contains_key(&op_id) {
+            return Err(format!("Operation {op_id} is already running"));
+        }
+        ops.insert(op_id.clone(), op.clone());
+    }
+    let job = crate::jobs::begin(Some(&op_id), kind, true);
+    let _registration = Registration(op_id);
+    let result = enter(Some(&op), f);
+    job.finish(&result, op.0.cancelled.load(Ordering::SeqCst));
+    result
+}
+
+/// Run `f` with `op` as this thread's current operation.
+pub(crate) fn enter<T>(op: Option<&Operation>, f: impl FnOnce() -> T) -> T {
+    let previous = CURRENT.with(|current| current.replace(op.cloned()));
+    let _guard = CurrentGuard(previous);
+    f()
+}
+
+/// Run `f` as a part of the current operation that times out on its own
+/// after `timeout`, e.g. one host of a multi-host command. Cancelling or
+/// timing out the whole operation still stops it. Just runs `f` outside of
+/// an operation.
+pub(crate) fn with_timeout<T>(timeout: Duration, f: impl FnOnce() -> T) -> T {
+    let Some(parent) = current() else {
+        return f();
+    };
+    let part = Operation(Arc::new(OperationState {
+        info: OperationInfo {
+            timeout_secs: timeout.as_secs(),
+            ..parent.0.info.clone()
+        },
+        cancelled: AtomicBool::new(false),
+        deadline: Instant::now() + timeout,
+        output_cap: AtomicUsize::new(parent.0.output_cap.load(Ordering::SeqCst)),
+        parent: Some(parent),
+    }));
+    enter(Some(&part), f)
+}
+
+pub(crate) fn current() -> Option<Operation> {
+    CURRENT.with(|current| current.borrow().clone())
+}
+
+/// `Err` if this thread runs inside an operation that was cancelled or timed
+/// out; always `Ok` outside of one.
+pub(crate) fn check_current() -> Result<(), String> {
+    current().map_or(Ok(()), |op| op.check())
+}
+
+/// Report progress of the current operation's job. No-op outside of an
+/// operation.
+pub(crate) fn report_progress(done: u64, total: Option<u64>, message: Option<&str>) {
+    if let Some(op) = current() {
+        crate::jobs::report_progress(&op.0.info.op_id, done, total, message);
+    }
+}
+
+/// Limit each output stream of the current operation's commands to `bytes`
+/// (clamped to 4 KiB..64 MiB). No-op outside of an operation.
+pub(crate) fn set_output_cap(bytes: usize) {
+    if let Some(op) = current() {
+        op.0.output_cap.store(
+            bytes.clamp(MIN_OUTPUT_CAP_BYTES, MAX_OUTPUT_CAP_BYTES),
+            Ordering::SeqCst,
+        );
+    }
+}
+
+fn output_cap() -> usize {
+    current().map_or(DEFAULT_OUTPUT_CAP_BYTES, |op| {
+        op.0.output_cap.load(Ordering::SeqCst)
+    })
+}
+
+// Keeps the first `cap` bytes and counts the rest.
+fn read_capped(
+    pipe: Option<impl Read + Send + 'static>,
+    cap: usize,
+) -> Option<JoinHandle<(Vec<u8>, u64)>> {
+    pipe.map(|mut pipe| {
+        thread::spawn(move || {
+            let mut buf = Vec::new();
+            let mut total = 0u64;
+            let mut chunk = [0u8; 8192];
+            loop {
+                match pipe.read(&mut chunk) {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        total += n as u64;
+                        let room = cap.saturating_sub(buf.len());
+                        buf.extend_from_slice(&chunk[..n.min(room)]);
+                    }
+                    Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                    Err(_) => break,
+                }
+            }
+            (buf, total)
+        })
+    })
+}
+
+fn join_output(reader: Option<JoinHandle<(Vec<u8>, u64)>>) -> (Vec<u8>, u64) {
+    reader
+        .and_then(|reader| reader.join().ok())
+        .unwrap_or_default()
+}
+
+/// Wait for `child`, killing it as soon as the current operation is
+/// cancelled or times out.
+pub(crate) fn wait_child(child: &mut Child) -> Result<std::process::ExitStatus, String> {
+    loop {
+        if let Some(status) = child.try_wait().map_err(|e| e.to_string())? {
+            return Ok(status);
+        }
+        if let Err(error) = check_current() {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(error);
+        }
+        thread::sleep(CHILD_POLL_INTERVAL);
+    }
+}
+
+/// `Command::output` that honours the current operation's cancellation,
+/// timeout and output cap. The truncation is `Some` when either stream was
+/// cut.
+pub(crate) fn command_output_capped(
+    command: &mut Command,
+) -> Result<(Output, Option<OutputTruncation>), String> {
+    check_current()?;
+    let cap = output_cap();
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    let stdout = read_capped(child.stdout.take(), cap);
+    let stderr = read_capped(child.stderr.take(), cap);
+    let status = if current().is_some() {
+        wait_child(&mut child)?
+    } else {
+        child.wait().map_err(|e| e.to_string())?
+    };
+    let (stdout, stdout_bytes) = join_output(stdout);
+    let (stderr, stderr_bytes) = join_output(stderr);
+    let truncation = (stdout_bytes.max(stderr_bytes) > cap as u64).then_some(OutputTruncation {
+        stdout_bytes,
+        stderr_bytes,
+        cap_bytes: cap as u64,
+    });
+    Ok((
+        Output {
+            status,
+            stdout,
+            stderr,
+        },
+        truncation,
+    ))
+}
+
+/// Cancel a running operation. Returns false if no operation has that id
+/// (it may already have finished).
+#[tauri::command]
+pub fn operation_cancel(op_id: String) -> Result<bool, String> {
+    cancel(op_id.trim())
+}
+
+pub(crate) fn cancel(op_id: &str) -> Result<bool, String> {
+    let ops = operations()
+        .lock()
+        .map_err(|_| "operations registry poisoned".to_string())?;
+    match ops.get(op_id) {
+        Some(op) => {
+            op.cancel();
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+#[tauri::command]
+pub fn operations_list() -> Result<Vec<OperationInfo>, String> {
+    let ops = operations()
+        .lock()
+        .map_err(|_| "operations registry poisoned".to_string())?;
+    let mut list: Vec<OperationInfo> = ops.values().map(|op| op.0.info.clone()).collect();
+    list.sort_by(|a, b| a.started_at.cmp(&b.started_at));
+    Ok(list)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        command_output_capped, operation_cancel, operations_list, run, set_output_cap, with_timeout,
+    };
+    use std::process::Command;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn times_out_and_kills_child() {
+        let started = Instant::now();
+        let result = run(
+            "op-timeout-test".to_string(),
+            "test",
+            Duration::from_secs(1),
+            || command_output_capped(Command::new("sleep").arg("5")).map(|_| ()),
+        );
+        assert_eq!(result.unwrap_err(), "Operation timed out after 1s");
+        assert!(started.elapsed() < Duration::from_secs(4));
+    }
+
+    #[test]
+    fn cancel_by_id_and_unregister_when_done() {
+        let worker = thread::spawn(|| {
+            run(
+                "op-cancel-test".to_string(),
+                "test",
+                Duration::from_secs(30),
+                || command_output_capped(Command::new("sleep").arg("5")).map(|_| ()),
+            )
+        });
+        let deadline = Instant::now() + Duration::from_secs(3);
+        while !operations_list()
+            .unwrap()
+            .iter()
+            .any(|op| op.op_id == "op-cancel-test")
+        {
+            assert!(Instant::now() < deadline, "operation never registered");
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(operation_cancel("op-cancel-test".to_string()).unwrap());
+        assert_eq!(worker.join().unwrap().unwrap_err(), "Operation cancelled");
+        assert!(!operation_cancel("op-cancel-test".to_string()).unwrap());
+    }
+
+    #[test]
+    fn parts_time_out_on_their_own() {
+        let started = Instant::now();
+        let (part, after) = run(
+            "op-cap-test".to_string(),
+            "test",
+            Duration::from_secs(10),
+            || {
+                let part = with_timeout(Duration::from_secs(1), || {
+                    command_output_capped(Command::new("sleep").arg("5")).map(|_| ())
+                });
+                let after = command_output_capped(&mut Command::new("true")).map(|_| ());
+                Ok((part, after))
+            },
+        )
+        .unwrap();
+        assert_eq!(part.unwrap_err(), "Operation timed out after 1s");
+        assert!(after.is_ok());
+        assert!(started.elapsed() < Duration::from_secs(4));
+    }
+
+    #[test]
+    fn rejects_duplicate_op_ids() {
+        let result = run(
+            "op-dup-test".to_string(),
+            "outer",
+            Duration::from_secs(5),
+            || {
+                run(
+                    "op-dup-test".to_string(),
+                    "inner",
+                    Duration::from_secs(5),
+                    || Ok(()),
+                )
+            },
+        );
+        assert!(result.unwrap_err().contains("already running"));
+    }
+
+    #[test]
+    fn caps_output_and_reports_truncation() {
+        let (success, kept, truncation) = run(
+            "op-truncate-test".to_string(),
+            "test",
+            Duration::from_secs(10),
+            || {
+                set_output_cap(8 * 1024);
+                command_output_capped(Command::new("sh").args(["-c", "head -c 100000 /dev/zero"]))
+                    .map(|(output, truncation)| {
+                        (output.status.success(), output.stdout.len(), truncation)
+                    })
+            },
+        )
+        .unwrap();
+        assert!(success);
+        assert_eq!(kept, 8 * 1024);
+        let truncation = truncation.unwrap();
+        assert_eq!(truncation.stdout_bytes, 100_000);
+        assert_eq!(truncation.cap_bytes, 8 * 1024);
+
+        let (output, truncation) = command_output_capped(Command::new("echo").arg("hi")).unwrap();
+        assert_eq!(output.stdout, b"hi\n");
+        assert_eq!(truncation, None);
+    }
+}
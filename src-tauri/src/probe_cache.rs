@@ -0,0 +1,124 @@
+// In-memory cache of probe results, so reopening a host's details does not
+// cost an SSH round trip every time. Entries are keyed by host id and
+// connection target: pointing a host somewhere else misses the cache.
+
+use crate::models::Host;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+pub(crate) struct ProbeCache<T> {
+    entries: OnceLock<Mutex<HashMap<String, (Instant, T)>>>,
+}
+
+fn cache_key(host: &Host) -> String {
+    format!(
+        "{}\n{}@{}:{}",
+        host.id.trim(),
+        host.user.trim(),
+        host.hostname.trim(),
+        host.port
+    )
+}
+
+impl<T: Clone> ProbeCache<T> {
+    pub(crate) const fn new() -> Self {
+        Self {
+            entries: OnceLock::new(),
+        }
+    }
+
+    fn entries(&self) -> &Mutex<HashMap<String, (Instant, T)>> {
+        self.entries.get_or_init(|| Mutex::new(HashMap::new()))
+    }
+
+    /// The cached result for `host`, if it is younger than `ttl`; `None`
+    /// keeps results for the whole app run.
+    pub(crate) fn get(&self, host: &Host, ttl: Option<Duration>) -> Option<T> {
+        let entries = self.entries().lock().ok()?;
+        let (at, value) = entries.get(&cache_key(host))?;
+        if ttl.is_some_and(|ttl| at.elapsed() >= ttl) {
+            return None;
+        }
+        Some(value.clone())
+    }
+
+    pub(crate) fn insert(&self, host: &Host, value: T) {
+        if let Ok(mut entries) = self.entries().lock() {
+            entries.insert(cache_key(host), (Instant::now(), value));
+        }
+    }
+
+    /// The cached result unless `force_refresh` is set or it is older than
+    /// `ttl`; otherwise run `probe` and cache what it returns. The flag is
+    /// true when the result came from the cache. Failures are not cached.
+    pub(crate) fn get_or_probe(
+        &self,
+        host: &Host,
+        ttl: Option<Duration>,
+        force_refresh: bool,
+        probe: impl FnOnce() -> Result<T, String>,
+    ) -> Result<(T, bool), String> {
+        if !force_refresh {
+            if let Some(value) = self.get(host, ttl) {
+                return Ok((value, true));
+            }
+        }
+        let value = probe()?;
+        self.insert(host, value.clone());
+        Ok((value, false))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProbeCache;
+    use crate::mock_backends::test_host;
+    use crate::models::Host;
+    use std::time::Duration;
+
+    fn host(hostname: &str) -> Host {
+        Host {
+            hostname: hostname.to_string(),
+            ..test_host("web")
+        }
+    }
+
+    #[test]
+    fn caches_until_expired_refreshed_or_retargeted() {
+        let cache = ProbeCache::new();
+        let web = host("10.0.0.1");
+        let probe = |value: u32| move || Ok(value);
+
+        assert_eq!(
+            cache.get_or_probe(&web, None, false, probe(1)),
+            Ok((1, false))
+        );
+        assert_eq!(
+            cache.get_or_probe(&web, None, false, probe(2)),
+            Ok((1, true))
+        );
+        assert_eq!(
+            cache.get_or_probe(&web, None, true, probe(3)),
+            Ok((3, false))
+        );
+        assert_eq!(
+            cache.get_or_probe(&web, Some(Duration::ZERO), false, probe(4)),
+            Ok((4, false))
+        );
+        assert_eq!(
+            cache.get_or_probe(&host("10.0.0.2"), None, false, probe(5)),
+            Ok((5, false))
+        );
+        assert!(cache
+            .get_or_probe(
+                &host("10.0.0.3"),
+                None,
+                false,
+                || Err("offline".to_string())
+            )
+            .is_err());
+        assert_eq!(cache.get(&host("10.0.0.3"), None), None);
+        assert_eq!(cache.get(&web, None), Some(4));
+    }
+}
@@ -0,0 +1,83 @@
+// Name and working directory of local processes, to tell what runs in the
+// foreground of a terminal session.
+
+/// Short name of a running process (e.g. `vim`).
+#[cfg(target_os = "linux")]
+pub(crate) fn process_name(pid: u32) -> Option<String> {
+    let name = std::fs::read_to_string(format!("/proc/{pid}/comm")).ok()?;
+    Some(name.trim_end().to_string()).filter(|name| !name.is_empty())
+}
+
+/// Current directory of a running process; None when it belongs to another
+/// user or has exited.
+#[cfg(target_os = "linux")]
+pub(crate) fn process_cwd(pid: u32) -> Option<String> {
+    let cwd = std::fs::read_link(format!("/proc/{pid}/cwd")).ok()?;
+    Some(cwd.to_string_lossy().into_owned())
+}
+
+#[cfg(target_os = "macos")]
+pub(crate) fn process_name(pid: u32) -> Option<String> {
+    // 2 * MAXCOMLEN + 1
+    const NAME_BUF_LEN: usize = 33;
+    let pid = libc::c_int::try_from(pid).ok()?;
+    let mut name = [0u8; NAME_BUF_LEN];
+    let len = unsafe {
+        libc::proc_name(
+            pid,
+            name.as_mut_ptr() as *mut libc::c_void,
+            NAME_BUF_LEN as u32,
+        )
+    };
+    let len = usize::try_from(len).ok().filter(|len| *len > 0)?;
+    Some(String::from_utf8_lossy(&name[..len]).into_owned())
+}
+
+#[cfg(target_os = "macos")]
+pub(crate) fn process_cwd(pid: u32) -> Option<String> {
+    let pid = libc::c_int::try_from(pid).ok()?;
+    let mut info: libc::proc_vnodepathinfo = unsafe { std::mem::zeroed() };
+    let size = std::mem::size_of::<libc::proc_vnodepathinfo>() as libc::c_int;
+    let written = unsafe {
+        libc::proc_pidinfo(
+            pid,
+            libc::PROC_PIDVNODEPATHINFO,
+            0,
+            &mut info as *mut libc::proc_vnodepathinfo as *mut libc::c_void,
+            size,
+        )
+    };
+    if written != size {
+        return None;
+    }
+    // `vip_path` is a NUL-terminated MAXPATHLEN buffer, split up by libc.
+    let path = unsafe { std::ffi::CStr::from_ptr(info.pvi_cdir.vip_path.as_ptr().cast()) };
+    Some(path.to_string_lossy().into_owned()).filter(|path| !path.is_empty())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub(crate) fn process_name(_pid: u32) -> Option<String> {
+    None
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub(crate) fn process_cwd(_pid: u32) -> Option<String> {
+    None
+}
+
+#[cfg(all(test, any(target_os = "linux", target_os = "macos")))]
+mod tests {
+    use super::{process_cwd, process_name};
+
+    #[test]
+    fn describes_the_current_process() {
+        let pid = std::process::id();
+        assert!(process_name(pid).is_some_and(|name| !name.is_empty()));
+        let cwd = std::env::current_dir().unwrap();
+        assert_eq!(
+            process_cwd(pid).map(std::path::PathBuf::from),
+            Some(cwd.canonicalize().unwrap())
+        );
+        assert_eq!(process_name(u32::MAX), None);
+    }
+}
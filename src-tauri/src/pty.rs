@@ -1,28 +1,55 @@
+use crate::bell::{BellAction, BellDetector, BellPayload};
+use crate::models::PromptRule;
+use crate::pty_io::{self, EmitFn, PtyOutputSink, PtyReader, ReadGate};
+use crate::pty_ring::PtyRing;
+use crate::scrollback::Scrollback;
+use crate::serial::SerialSettings;
+use crate::ssh_prompt::{challenge_prompt, SshPromptPayload};
 use portable_pty::{native_pty_system, CommandBuilder, PtySize};
-use serde::Serialize;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 use std::{
     collections::{BTreeMap, HashMap},
     ffi::OsString,
     io::{Read, Write},
     sync::{
-        atomic::{AtomicU32, Ordering},
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
         mpsc, Arc, Mutex,
     },
-    thread,
     time::{Duration, Instant},
 };
-use tauri::{AppHandle, Emitter, Runtime};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
 
 type SessionId = u32;
+// Bytes to type and what they are, for the error log.
+type InputQueue = mpsc::Sender<(Vec<u8>, &'static str)>;
 const AUTO_PASSWORD_TAIL_CHARS: usize = 512;
 const AUTO_PASSWORD_ARM_SECONDS: u64 = 15;
-// Terminal UI lives in the main window; targeted emits avoid serializing
-// PTY traffic for every open window (e.g. the settings window).
-const MAIN_WINDOW_LABEL: &str = "main";
-const PTY_READ_BUFFER_BYTES: usize = 64 * 1024;
-// Cap for how much decoded output a single pty:data event may carry when the
-// emitter coalesces backlogged chunks.
-const PTY_EMIT_MAX_BATCH_CHARS: usize = 1024 * 1024;
+// OTP challenges come after the password, possibly once the user found their
+// phone, so they are looked for a while longer.
+const CHALLENGE_PROMPT_ARM_SECONDS: u64 = 60;
+const STARTUP_COMMANDS_TAIL_CHARS: usize = 256;
+const PROMPT_RULE_DEFAULT_REPLAYS: u32 = 3;
+const PROMPT_RULE_MAX_REPLAYS: u32 = 20;
+const PROMPT_RULE_PASSWORD: &str = "{password}";
+// Startup commands are dropped if no shell prompt shows up in time, so a slow
+// banner or an unexpected interactive prompt never receives them late.
+const STARTUP_COMMANDS_PROMPT_WAIT_SECONDS: u64 = 30;
+const PTY_SCROLLBACK_DEFAULT_BYTES: usize = 2 * 1024 * 1024;
+// How long before a host's session time limit the UI is warned.
+const SESSION_EXPIRY_WARNING: Duration = Duration::from_secs(60);
+const THROUGHPUT_INTERVAL: Duration = Duration::from_secs(2);
+// Bells closer together than this ring once (e.g. `cat` of a binary file).
+const BELL_MIN_INTERVAL: Duration = Duration::from_millis(500);
+// Typed by the idle keep-alive when the host names no string: invisible to
+// the shell, but traffic on the connection.
+const KEEPALIVE_DEFAULT_STRING: &str = "\0";
+// How long quitting waits for hung-up sessions to exit.
+const SHUTDOWN_GRACE: Duration = Duration::from_millis(1500);
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(50);
+// Output buffered for a polled session before its reading pauses.
+const PTY_RING_BYTES: usize = 1024 * 1024;
+const PTY_READ_CHUNK_DEFAULT_BYTES: usize = 256 * 1024;
 
 struct PtyOutputDecoder {
     decoder: encoding_rs::Decoder,
@@ -30,13 +57,8 @@ struct PtyOutputDecoder {
 
 impl PtyOutputDecoder {
     fn new(label: Option<&str>) -> Self {
-        let encoding = label
-            .map(str::trim)
-            .filter(|value| !value.is_empty())
-            .and_then(|value| encoding_rs::Encoding::for_label(value.as_bytes()))
-            .unwrap_or(encoding_rs::UTF_8);
         Self {
-            decoder: encoding.new_decoder(),
+            decoder: resolve_pty_encoding(label).new_decoder(),
         }
     }
 
@@ -61,6 +83,42 @@ impl PtyOutputDecoder {
     }
 }
 
+// Unknown or empty labels fall back to UTF-8 rather than failing the spawn.
+fn resolve_pty_encoding(label: Option<&str>) -> &'static encoding_rs::Encoding {
+    label
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .and_then(|value| encoding_rs::Encoding::for_label(value.as_bytes()))
+        .map(|encoding| encoding.output_encoding())
+        .unwrap_or(encoding_rs::UTF_8)
+}
+
+// Keystrokes and injected text arrive as UTF-8 and must reach the remote side
+// in the session encoding. Characters the encoding cannot represent become
+// `?`; encoding_rs' default HTML numeric escapes would be typed literally.
+fn encode_pty_input<'a>(
+    encoding: &'static encoding_rs::Encoding,
+    data: &'a str,
+) -> std::borrow::Cow<'a, [u8]> {
+    if encoding == encoding_rs::UTF_8 || data.is_ascii() {
+        return std::borrow::Cow::Borrowed(data.as_bytes());
+    }
+    let mut encoder = encoding.new_encoder();
+    let mut output = Vec::with_capacity(data.len() * 2);
+    let mut input = data;
+    loop {
+        let (result, read) =
+            encoder.encode_from_utf8_to_vec_without_replacement(input, &mut output, true);
+        input = &input[read..];
+        match result {
+            encoding_rs::EncoderResult::InputEmpty => break,
+            encoding_rs::EncoderResult::OutputFull => output.reserve(input.len() * 2 + 8),
+            encoding_rs::EncoderResult::Unmappable(_) => output.push(b'?'),
+        }
+    }
+    std::borrow::Cow::Owned(output)
+}
+
 fn extract_ready_output_chunks(
     decoder: &mut PtyOutputDecoder,
     pending: &mut Vec<u8>,
@@ -81,7 +139,108 @@ fn drain_output_tail(decoder: &mut PtyOutputDecoder, pending: &mut Vec<u8>) -> O
     decoder.decode(&data, true)
 }
 
-fn parse_env_vars(input: Option<&str>) -> Result<BTreeMap<String, String>, String> {
+// Longest partial `ESC [ ? Pm` sequence carried between reads before it is
+// treated as garbage; real DECSET/DECRST sequences are far shorter.
+const TERMINAL_MODE_CARRY_MAX_CHARS: usize = 64;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MouseTrackingMode {
+    #[default]
+    Off,
+    X10,
+    Normal,
+    ButtonEvent,
+    AnyEvent,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct TerminalModes {
+    alternate_screen: bool,
+    mouse_tracking: MouseTrackingMode,
+    mouse_sgr: bool,
+}
+
+impl TerminalModes {
+    fn apply(&mut self, mode: u16, enabled: bool) {
+        let mouse = match mode {
+            47 | 1047 | 1049 => {
+                self.alternate_screen = enabled;
+                return;
+            }
+            1006 => {
+                self.mouse_sgr = enabled;
+                return;
+            }
+            9 => MouseTrackingMode::X10,
+            1000 => MouseTrackingMode::Normal,
+            1002 => MouseTrackingMode::ButtonEvent,
+            1003 => MouseTrackingMode::AnyEvent,
+            _ => return,
+        };
+        if enabled {
+            self.mouse_tracking = mouse;
+        } else if self.mouse_tracking == mouse {
+            self.mouse_tracking = MouseTrackingMode::Off;
+        }
+    }
+}
+
+/// Follows the DECSET/DECRST private modes full-screen programs (vim, htop,
+/// less) toggle, so the backend knows when output targets the alternate
+/// screen or the application has claimed the mouse.
+#[derive(Debug, Default)]
+struct TerminalModeTracker {
+    modes: TerminalModes,
+    carry: String,
+}
+
+impl TerminalModeTracker {
+    fn feed(&mut self, data: &str) {
+        let mut input = std::mem::take(&mut self.carry);
+        input.push_str(data);
+        let mut rest = input.as_str();
+        while let Some(start) = rest.find('\x1b') {
+            rest = &rest[start..];
+            let Some(body) = rest.strip_prefix("\x1b[?") else {
+                if "\x1b[?".starts_with(rest) {
+                    self.carry = rest.to_string();
+                    return;
+                }
+                if rest.starts_with("\x1bc") {
+                    // RIS (full reset) drops every private mode.
+                    self.modes = TerminalModes::default();
+                }
+                rest = &rest[1..];
+                continue;
+            };
+            let params_len = body
+                .find(|ch: char| !(ch.is_ascii_digit() || ch == ';'))
+                .unwrap_or(body.len());
+            let Some(final_char) = body[params_len..].chars().next() else {
+                if rest.len() <= TERMINAL_MODE_CARRY_MAX_CHARS {
+                    self.carry = rest.to_string();
+                }
+                return;
+            };
+            let enabled = match final_char {
+                'h' => Some(true),
+                'l' => Some(false),
+                _ => None,
+            };
+            if let Some(enabled) = enabled {
+                for param in body[..params_len].split(';') {
+                    if let Ok(mode) = param.parse::<u16>() {
+                        self.modes.apply(mode, enabled);
+                    }
+                }
+            }
+            rest = &body[params_len..];
+        }
+    }
+}
+
+pub(crate) fn parse_env_vars(input: Option<&str>) -> Result<BTreeMap<String, String>, String> {
     let mut env = BTreeMap::new();
     let Some(input) = input else {
         return Ok(env);
@@ -127,6 +286,66 @@ pub struct PtyState {
     sessions: Arc<Mutex<HashMap<SessionId, Arc<Session>>>>,
 }
 
+impl PtyState {
+    fn list(&self) -> Result<Vec<PtySessionSummary>, String> {
+        let sessions = self.sessions.lock().map_err(|_| "PtyState poisoned")?;
+        let mut ids: Vec<SessionId> = sessions.keys().copied().collect();
+        ids.sort_unstable();
+        Ok(ids
+            .into_iter()
+            .map(|id| PtySessionSummary {
+                session_id: id.to_string(),
+                host_id: sessions[&id].host_id.clone(),
+                windows: sessions[&id].window_labels(),
+            })
+            .collect())
+    }
+
+    /// Hang up every session as the app quits: note the hosts of the open
+    /// SSH sessions so the next launch can offer to reopen them, send SIGHUP
+    /// (ssh then closes its connection cleanly) and give them a moment to
+    /// exit, which also gets them into the connection history.
+    pub(crate) fn shutdown(&self) {
+        let mut sessions: Vec<(SessionId, Arc<Session>)> = match self.sessions.lock() {
+            Ok(sessions) => sessions
+                .iter()
+                .map(|(id, session)| (*id, session.clone()))
+                .collect(),
+            Err(_) => return,
+        };
+        sessions.sort_unstable_by_key(|(id, _)| *id);
+        let host_ids = sessions
+            .iter()
+            .filter_map(|(_, session)| session.host_id.clone())
+            .collect();
+        if let Err(e) = crate::session_restore::save_sessions_at_exit(host_ids) {
+            eprintln!("[pty] failed to record sessions open at exit: {e}");
+        }
+        for (_, session) in &sessions {
+            let _ = session.hang_up();
+        }
+        let deadline = Instant::now() + SHUTDOWN_GRACE;
+        while Instant::now() < deadline
+            && self
+                .sessions
+                .lock()
+                .is_ok_and(|sessions| !sessions.is_empty())
+        {
+            std::thread::sleep(SHUTDOWN_POLL_INTERVAL);
+        }
+    }
+
+    /// Detach a closed window from every session; the sessions live on for
+    /// another window to attach to.
+    pub(crate) fn detach_window(&self, label: &str) {
+        if let Ok(sessions) = self.sessions.lock() {
+            for session in sessions.values() {
+                session.detach(label);
+            }
+        }
+    }
+}
+
 impl Default for PtyState {
     fn default() -> Self {
         Self {
@@ -137,10 +356,169 @@ impl Default for PtyState {
 }
 
 struct Session {
-    master: Mutex<Box<dyn portable_pty::MasterPty + Send>>,
+    encoding: &'static encoding_rs::Encoding,
+    // None for serial sessions, which have no window size.
+    master: Option<Mutex<Box<dyn portable_pty::MasterPty + Send>>>,
     writer: Mutex<Box<dyn Write + Send>>,
+    // Input the session types on its own, see `queue_input`.
+    auto_input: Mutex<Option<InputQueue>>,
     killer: Mutex<Box<dyn portable_pty::ChildKiller + Send + Sync>>,
+    // Closed by `pty_pause` to stop reading output until `pty_resume`.
+    read_gate: ReadGate,
+    // Output of a session using the polling transport, waiting for
+    // `pty_read_chunk`; None when output is emitted as `pty:data`.
+    output_ring: Option<PtyRing>,
+    // Set when a full ring paused reading; the next read that empties the
+    // overflow resumes it.
+    ring_stalled: AtomicBool,
+    // Set once the session ended; a polled session is dropped when its ring
+    // has been read empty or it is killed.
+    exited: AtomicBool,
     auto_password: Mutex<Option<AutoPasswordState>>,
+    prompt_rules: Mutex<Option<PromptRulesState>>,
+    startup_commands: Mutex<Option<StartupCommandsState>>,
+    terminal_modes: Mutex<TerminalModeTracker>,
+    scrollback: Mutex<Scrollback>,
+    // Set when the host's session time limit closed the session.
+    limit_enforced: AtomicBool,
+    // Raw bytes from and to the PTY or serial device, before decoding.
+    bytes_read: AtomicU64,
+    bytes_written: AtomicU64,
+    last_read: Mutex<Instant>,
+    last_write: Mutex<Instant>,
+    bell_action: BellAction,
+    // The host of an SSH session.
+    host_id: Option<String>,
+    // The spawned child; None for serial sessions.
+    pid: Option<u32>,
+    // Labels of the windows showing the session. Its events go to these only,
+    // so PTY traffic is not serialized for every open window (e.g. the
+    // settings window).
+    windows: Mutex<Vec<String>>,
+}
+
+impl Session {
+    fn note_written(&self, len: usize) {
+        self.bytes_written.fetch_add(len as u64, Ordering::Relaxed);
+        if let Ok(mut last_write) = self.last_write.lock() {
+            *last_write = Instant::now();
+        }
+    }
+
+    fn write_input(&self, bytes: &[u8]) -> Result<(), String> {
+        let mut w = self.writer.lock().map_err(|_| "writer poisoned")?;
+        w.write_all(bytes).map_err(|e| e.to_string())?;
+        w.flush().map_err(|e| e.to_string())?;
+        self.note_written(bytes.len());
+        Ok(())
+    }
+
+    /// Type `text` on the session's behalf (the saved password, a prompt
+    /// rule's answer, startup commands). The shared reader decides what to
+    /// type but must not wait for a PTY that is not reading its input, so a
+    /// thread of the session's own writes it, in order.
+    fn queue_input(self: &Arc<Self>, text: &str, what: &'static str) {
+        let bytes = encode_pty_input(self.encoding, text).into_owned();
+        let Ok(mut queue) = self.auto_input.lock() else {
+            eprintln!("[pty] input queue poisoned while sending {what}");
+            return;
+        };
+        let queue = queue.get_or_insert_with(|| {
+            let (tx, rx) = mpsc::channel::<(Vec<u8>, &'static str)>();
+            let session = Arc::downgrade(self);
+            std::thread::spawn(move || {
+                for (bytes, what) in rx {
+                    let Some(session) = session.upgrade() else {
+                        return;
+                    };
+                    if let Err(error) = session.write_input(&bytes) {
+                        eprintln!("[pty] failed to write {what}: {error}");
+                    }
+                }
+            });
+            tx
+        });
+        let _ = queue.send((bytes, what));
+    }
+
+    /// Kill the child (SIGHUP) or close the serial device. A paused session
+    /// is resumed first, or its end would never be read.
+    fn hang_up(&self) -> Result<(), String> {
+        self.read_gate.resume();
+        let mut killer = self.killer.lock().map_err(|_| "killer poisoned")?;
+        killer.kill().map_err(|e| e.to_string())
+    }
+
+    /// Time since anything was read from or written to the session.
+    fn idle_for(&self) -> Duration {
+        let since = |at: &Mutex<Instant>| at.lock().map(|at| at.elapsed()).unwrap_or_default();
+        since(&self.last_read).min(since(&self.last_write))
+    }
+
+    fn window_labels(&self) -> Vec<String> {
+        self.windows
+            .lock()
+            .map(|windows| windows.clone())
+            .unwrap_or_default()
+    }
+
+    fn attach(&self, label: &str) {
+        if let Ok(mut windows) = self.windows.lock() {
+            if !windows.iter().any(|window| window == label) {
+                windows.push(label.to_string());
+            }
+        }
+    }
+
+    fn detach(&self, label: &str) {
+        if let Ok(mut windows) = self.windows.lock() {
+            windows.retain(|window| window != label);
+        }
+    }
+
+    /// Emit a session event to every window showing the session.
+    fn emit<R: Runtime, S: Serialize + Clone>(&self, app: &AppHandle<R>, event: &str, payload: S) {
+        for label in self.window_labels() {
+            let _ = app.emit_to(label.as_str(), event, payload.clone());
+        }
+    }
+
+    /// Leader of the terminal's foreground process group: the child itself
+    /// while it waits for input, or e.g. a local command it runs.
+    fn foreground_pid(&self) -> Option<u32> {
+        #[cfg(unix)]
+        {
+            let master = self.master.as_ref()?.lock().ok()?;
+            master
+                .process_group_leader()
+                .and_then(|pid| u32::try_from(pid).ok())
+        }
+        #[cfg(not(unix))]
+        {
+            self.pid
+        }
+    }
+
+    fn stats(&self, id: SessionId) -> PtySessionStats {
+        PtySessionStats {
+            session_id: id.to_string(),
+            bytes_read: self.bytes_read.load(Ordering::Relaxed),
+            bytes_written: self.bytes_written.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// How a session's output reaches the UI.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PtyTransport {
+    /// `pty:data` events, batched by the emitter thread.
+    #[default]
+    Events,
+    /// Buffered in the session until the UI calls `pty_read_chunk`; cheaper
+    /// for very chatty sessions, whose output would otherwise be serialized
+    /// into an event per batch.
+    Poll,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -155,6 +533,56 @@ pub struct PtyExitPayload {
     pub code: u32,
 }
 
+#[derive(Debug, Serialize, Clone)]
+pub struct PtyExpiringPayload {
+    pub session_id: String,
+    pub remaining_secs: u64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PtySessionInfo {
+    pub session_id: String,
+    pub encoding: String,
+    pub alternate_screen: bool,
+    pub mouse_tracking: MouseTrackingMode,
+    pub mouse_sgr: bool,
+    pub pid: Option<u32>,
+    /// What runs in the foreground of the terminal, and in which directory.
+    /// These are local processes: for an SSH session that is `ssh` itself,
+    /// whatever runs on the server.
+    pub foreground_pid: Option<u32>,
+    pub foreground_process: Option<String>,
+    pub cwd: Option<String>,
+}
+
+/// Cumulative traffic of a session since it started.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct PtySessionStats {
+    pub session_id: String,
+    pub bytes_read: u64,
+    pub bytes_written: u64,
+}
+
+/// A `pty:throughput` entry: the totals plus the rate since the last event.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct PtyThroughputPayload {
+    pub session_id: String,
+    pub bytes_read: u64,
+    pub bytes_written: u64,
+    pub read_bytes_per_sec: u64,
+    pub write_bytes_per_sec: u64,
+}
+
+/// A live session, as `pty_list` reports it.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct PtySessionSummary {
+    pub session_id: String,
+    /// None for serial sessions.
+    pub host_id: Option<String>,
+    /// Labels of the windows it is attached to; empty once they all closed.
+    pub windows: Vec<String>,
+}
+
 fn trim_auto_password_tail(tail: &mut String) {
     let len = tail.chars().count();
     if len > AUTO_PASSWORD_TAIL_CHARS {
@@ -284,11 +712,11 @@ impl AutoPasswordState {
     }
 }
 
-fn maybe_send_auto_password(session: &Arc<Session>, data: &str) {
+fn maybe_send_auto_password(session: &Arc<Session>, data: &str) -> bool {
     let password = {
         let Ok(mut state) = session.auto_password.lock() else {
             eprintln!("[pty] auto password state poisoned");
-            return;
+            return false;
         };
         let password = state
             .as_mut()
@@ -299,19 +727,407 @@ fn maybe_send_auto_password(session: &Arc<Session>, data: &str) {
         password
     };
 
-    if let Some(password) = password {
-        match session.writer.lock() {
-            Ok(mut writer) => {
-                if let Err(error) = writer.write_all(format!("{password}\n").as_bytes()) {
-                    eprintln!("[pty] failed to write saved SSH password: {error}");
+    let Some(password) = password else {
+        return false;
+    };
+    session.queue_input(&format!("{password}\n"), "saved SSH password");
+    true
+}
+
+/// Looks for OTP-style challenges while an SSH session logs in, so the UI can
+/// ask for the code (`ssh:prompt`).
+#[derive(Debug, Default)]
+struct ChallengeWatch {
+    armed_until: Option<Instant>,
+    tail: String,
+}
+
+impl ChallengeWatch {
+    fn new(armed_until: Option<Instant>) -> Self {
+        Self {
+            armed_until,
+            tail: String::new(),
+        }
+    }
+
+    fn feed(&mut self, data: &str, now: Instant) -> Option<String> {
+        let armed_until = self.armed_until?;
+        if now > armed_until {
+            self.armed_until = None;
+            self.tail.clear();
+            return None;
+        }
+        self.tail.push_str(&strip_escape_sequences(data));
+        trim_auto_password_tail(&mut self.tail);
+        let prompt = challenge_prompt(&self.tail)?;
+        self.tail.clear();
+        Some(prompt)
+    }
+
+    /// The output so far ended on a prompt something else answered.
+    fn skip(&mut self) {
+        self.tail.clear();
+    }
+}
+
+/// Checked when a host is saved, so a bad rule is reported there rather than
+/// silently never firing.
+pub(crate) fn validate_prompt_rules(rules: &[PromptRule]) -> Result<(), String> {
+    for rule in rules {
+        let regex = Regex::new(&rule.pattern)
+            .map_err(|e| format!("Invalid prompt rule pattern `{}`: {e}", rule.pattern))?;
+        // It would answer every blank line until it ran out of replays.
+        if regex.is_match("") {
+            return Err(format!(
+                "Prompt rule pattern `{}` matches empty output",
+                rule.pattern
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// A host's enabled prompt rules, each with the answers it has left in this
+/// session.
+#[derive(Debug)]
+struct PromptRulesState {
+    rules: Vec<(Regex, String, u32)>,
+    tail: String,
+}
+
+impl PromptRulesState {
+    /// None when no rule can fire. Rules that use the password are left out
+    /// when none is saved.
+    fn new(rules: &[PromptRule], password: Option<&str>) -> Option<Self> {
+        let rules: Vec<(Regex, String, u32)> = rules
+            .iter()
+            .filter(|rule| rule.enabled)
+            .filter_map(|rule| {
+                let response = if rule.response.contains(PROMPT_RULE_PASSWORD) {
+                    rule.response.replace(PROMPT_RULE_PASSWORD, password?)
+                } else {
+                    rule.response.clone()
+                };
+                let replays = rule
+                    .max_replays
+                    .unwrap_or(PROMPT_RULE_DEFAULT_REPLAYS)
+                    .min(PROMPT_RULE_MAX_REPLAYS);
+                Some((Regex::new(&rule.pattern).ok()?, response, replays))
+            })
+            .filter(|(_, _, replays)| *replays > 0)
+            .collect();
+        (!rules.is_empty()).then(|| Self {
+            rules,
+            tail: String::new(),
+        })
+    }
+
+    /// The response to type when the line the output ends on matches a
+    /// rule with answers left.
+    fn feed(&mut self, data: &str) -> Option<String> {
+        self.tail.push_str(&strip_escape_sequences(data));
+        trim_auto_password_tail(&mut self.tail);
+        let normalized = self.tail.replace('\r', "\n");
+        let line = normalized.rsplit('\n').next().unwrap_or("");
+        let (_, response, replays) = self
+            .rules
+            .iter_mut()
+            .find(|(regex, _, replays)| *replays > 0 && regex.is_match(line))?;
+        *replays -= 1;
+        self.tail.clear();
+        Some(response.clone())
+    }
+
+    /// The output so far ended on a prompt the saved password answered.
+    fn skip(&mut self) {
+        self.tail.clear();
+    }
+}
+
+// With `answered`, the prompt in `data` already got the saved password, so
+// the rules only drop it.
+fn maybe_answer_prompt_rules(session: &Arc<Session>, data: &str, answered: bool) -> bool {
+    let response = {
+        let Ok(mut state) = session.prompt_rules.lock() else {
+            eprintln!("[pty] prompt rules state poisoned");
+            return false;
+        };
+        match state.as_mut() {
+            Some(state) if answered => {
+                state.skip();
+                None
+            }
+            Some(state) => state.feed(data),
+            None => None,
+        }
+    };
+    let Some(response) = response else {
+        return false;
+    };
+    session.queue_input(&line_input(&response), "prompt rule answer");
+    true
+}
+
+// Drops CSI/OSC escape sequences so colored prompts compare as plain text.
+fn strip_escape_sequences(data: &str) -> String {
+    let mut out = String::with_capacity(data.len());
+    let mut chars = data.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch != '\x1b' {
+            out.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('\x40'..='\x7e').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+fn looks_like_shell_prompt(tail: &str) -> bool {
+    let plain = strip_escape_sequences(tail).replace('\r', "\n");
+    let line = plain.rsplit('\n').next().unwrap_or("").trim_end();
+    line.ends_with(['$', '#', '%', '>'])
+}
+
+#[derive(Debug)]
+struct StartupCommandsState {
+    payload: Option<String>,
+    // None waits for a shell prompt; Some sends after a fixed delay instead.
+    delay: Option<Duration>,
+    wait_until: Instant,
+    prompt_tail: String,
+}
+
+impl StartupCommandsState {
+    fn new(commands: &[String], delay: Option<Duration>, now: Instant) -> Option<Self> {
+        let payload: String = commands
+            .iter()
+            .map(|command| command.trim())
+            .filter(|command| !command.is_empty())
+            .map(|command| format!("{command}\n"))
+            .collect();
+        if payload.is_empty() {
+            return None;
+        }
+        Some(Self {
+            payload: Some(payload),
+            delay,
+            wait_until: now + Duration::from_secs(STARTUP_COMMANDS_PROMPT_WAIT_SECONDS),
+            prompt_tail: String::new(),
+        })
+    }
+
+    fn disarm(&mut self) {
+        self.payload = None;
+        self.prompt_tail.clear();
+    }
+
+    fn take_payload_for_output(&mut self, data: &str, now: Instant) -> Option<String> {
+        if self.delay.is_some() {
+            return None;
+        }
+        self.payload.as_ref()?;
+        if now > self.wait_until {
+            self.disarm();
+            return None;
+        }
+
+        self.prompt_tail.push_str(data);
+        let len = self.prompt_tail.chars().count();
+        if len > STARTUP_COMMANDS_TAIL_CHARS {
+            self.prompt_tail = self
+                .prompt_tail
+                .chars()
+                .skip(len - STARTUP_COMMANDS_TAIL_CHARS)
+                .collect();
+        }
+        if !looks_like_shell_prompt(&self.prompt_tail) {
+            return None;
+        }
+        self.prompt_tail.clear();
+        self.payload.take()
+    }
+}
+
+fn maybe_send_startup_commands(session: &Arc<Session>, data: &str) {
+    let payload = {
+        let Ok(mut state) = session.startup_commands.lock() else {
+            return;
+        };
+        let payload = state
+            .as_mut()
+            .and_then(|state| state.take_payload_for_output(data, Instant::now()));
+        if state.as_ref().is_some_and(|state| state.payload.is_none()) {
+            *state = None;
+        }
+        payload
+    };
+    if let Some(payload) = payload {
+        session.queue_input(&payload, "startup commands");
+    }
+}
+
+/// Output side of a session: decodes what the shared reader delivers, feeds
+/// the prompt matchers and mode tracker, and on EOF reaps the child (if any)
+/// and reports the exit after the last output has been emitted.
+struct SessionOutput<R: Runtime> {
+    id: SessionId,
+    session: Arc<Session>,
+    decoder: PtyOutputDecoder,
+    pending: Vec<u8>,
+    emit: EmitFn,
+    child: Option<Box<dyn portable_pty::Child + Send + Sync>>,
+    host_id: Option<String>,
+    spawned_at: Instant,
+    bells: BellDetector,
+    last_bell: Option<Instant>,
+    challenges: ChallengeWatch,
+    app: AppHandle<R>,
+    sessions: Arc<Mutex<HashMap<SessionId, Arc<Session>>>>,
+}
+
+impl<R: Runtime> SessionOutput<R> {
+    fn ring_bells(&mut self, data: &str) {
+        if self.bells.feed(data) == 0 || self.session.bell_action == BellAction::Ignore {
+            return;
+        }
+        let now = Instant::now();
+        if self
+            .last_bell
+            .is_some_and(|last| now.duration_since(last) < BELL_MIN_INTERVAL)
+        {
+            return;
+        }
+        self.last_bell = Some(now);
+        self.session.emit(
+            &self.app,
+            "bell:rang",
+            BellPayload {
+                session_id: self.id.to_string(),
+                host_id: self.host_id.clone(),
+                action: self.session.bell_action,
+            },
+        );
+    }
+}
+
+impl<R: Runtime> PtyOutputSink for SessionOutput<R> {
+    fn on_data(&mut self, bytes: &[u8]) {
+        self.session
+            .bytes_read
+            .fetch_add(bytes.len() as u64, Ordering::Relaxed);
+        if let Ok(mut last_read) = self.session.last_read.lock() {
+            *last_read = Instant::now();
+        }
+        self.pending.extend_from_slice(bytes);
+        for data in extract_ready_output_chunks(&mut self.decoder, &mut self.pending) {
+            // One answer per prompt: the saved password goes before the
+            // prompt rules, and an answered prompt is no challenge for the UI.
+            let answered = maybe_send_auto_password(&self.session, &data);
+            let answered = maybe_answer_prompt_rules(&self.session, &data, answered) || answered;
+            if answered {
+                self.challenges.skip();
+            } else if let Some(prompt) = self.challenges.feed(&data, Instant::now()) {
+                self.session.emit(
+                    &self.app,
+                    "ssh:prompt",
+                    SshPromptPayload {
+                        session_id: self.id.to_string(),
+                        host_id: self.host_id.clone(),
+                        prompt,
+                    },
+                );
+            }
+            maybe_send_startup_commands(&self.session, &data);
+            if let Ok(mut modes) = self.session.terminal_modes.lock() {
+                modes.feed(&data);
+            }
+            self.ring_bells(&data);
+            push_output(&self.session, self.id, data, &self.emit);
+        }
+    }
+
+    fn on_eof(self: Box<Self>) {
+        let mut output = *self;
+        if let Some(data) = drain_output_tail(&mut output.decoder, &mut output.pending) {
+            push_output(&output.session, output.id, data, &output.emit);
+        }
+        tauri::async_runtime::spawn_blocking(move || {
+            // A serial session ends when it is closed or the device goes away.
+            let code = match output.child.as_mut() {
+                Some(child) => child.wait().ok().map(|s| s.exit_code()).unwrap_or(1),
+                None => 0,
+            };
+            if let Some(host_id) = output.host_id.as_deref() {
+                crate::connection_history::record_connection(
+                    host_id,
+                    output.spawned_at.elapsed(),
+                    code,
+                    output.session.limit_enforced.load(Ordering::Relaxed),
+                );
+            }
+            let SessionOutput {
+                id,
+                session,
+                app,
+                sessions,
+                ..
+            } = output;
+            pty_io::after_output(move || {
+                session.emit(
+                    &app,
+                    "pty:exit",
+                    PtyExitPayload {
+                        session_id: id.to_string(),
+                        code,
+                    },
+                );
+                session.exited.store(true, Ordering::Release);
+                let unread = session.output_ring.as_ref().is_some_and(|r| !r.is_empty());
+                if unread {
                     return;
                 }
-                if let Err(error) = writer.flush() {
-                    eprintln!("[pty] failed to flush saved SSH password: {error}");
+                if let Ok(mut sessions) = sessions.lock() {
+                    sessions.remove(&id);
                 }
+            });
+        });
+    }
+}
+
+// Queued while holding the scrollback lock, so a window attaching meanwhile
+// gets each chunk exactly once: in the scrollback `pty_attach` returns or as
+// a `pty:data` event.
+fn push_output(session: &Session, id: SessionId, data: String, emit: &EmitFn) {
+    let mut scrollback = session.scrollback.lock();
+    if let Ok(scrollback) = scrollback.as_mut() {
+        scrollback.push(&data);
+    }
+    match session.output_ring.as_ref() {
+        Some(ring) => {
+            // Paused before the flag is set, so a read that sees the flag
+            // always finds reading paused and can resume it.
+            if !ring.push(data.as_bytes()) {
+                session.read_gate.pause();
+                session.ring_stalled.store(true, Ordering::Release);
             }
-            Err(_) => eprintln!("[pty] writer poisoned while sending saved SSH password"),
         }
+        None => pty_io::emit_output(id, data, emit),
     }
 }
 
@@ -324,6 +1140,12 @@ async fn spawn_pty_command<R: Runtime>(
     env: BTreeMap<String, String>,
     encoding: Option<String>,
     auto_password: Option<AutoPasswordState>,
+    prompt_rules: Option<PromptRulesState>,
+    startup_commands: Option<StartupCommandsState>,
+    host_id: Option<String>,
+    bell_action: BellAction,
+    transport: PtyTransport,
+    window: tauri::Window<R>,
     app: AppHandle<R>,
     state: tauri::State<'_, PtyState>,
 ) -> Result<String, String> {
@@ -340,7 +1162,7 @@ async fn spawn_pty_command<R: Runtime>(
     // Take writer + clone reader now; keep master for resize.
     let master = pair.master;
     let writer = master.take_writer().map_err(|e| e.to_string())?;
-    let mut reader = master.try_clone_reader().map_err(|e| e.to_string())?;
+    let reader = master.try_clone_reader().map_err(|e| e.to_string())?;
 
     let mut cmd = CommandBuilder::new(file);
     cmd.args(args);
@@ -355,101 +1177,138 @@ async fn spawn_pty_command<R: Runtime>(
         cmd.env(OsString::from(k), OsString::from(v));
     }
 
-    let mut child = match pair.slave.spawn_command(cmd) {
+    let child = match pair.slave.spawn_command(cmd) {
         Ok(child) => child,
         Err(e) => return Err(e.to_string()),
     };
     let killer = child.clone_killer();
+    let pid = child.process_id();
 
-    let id = state.next_id.fetch_add(1, Ordering::Relaxed);
-    let id_s = id.to_string();
-
-    let session = Arc::new(Session {
-        master: Mutex::new(master),
+    let session = Session {
+        encoding: resolve_pty_encoding(encoding.as_deref()),
+        master: Some(Mutex::new(master)),
         writer: Mutex::new(writer),
+        auto_input: Mutex::new(None),
         killer: Mutex::new(killer),
+        read_gate: ReadGate::default(),
+        output_ring: (transport == PtyTransport::Poll).then(|| PtyRing::new(PTY_RING_BYTES)),
+        ring_stalled: AtomicBool::new(false),
+        exited: AtomicBool::new(false),
         auto_password: Mutex::new(auto_password),
-    });
+        prompt_rules: Mutex::new(prompt_rules),
+        startup_commands: Mutex::new(startup_commands),
+        terminal_modes: Mutex::new(TerminalModeTracker::default()),
+        scrollback: Mutex::new(Scrollback::new(crate::scrollback::memory_cap())),
+        limit_enforced: AtomicBool::new(false),
+        bytes_read: AtomicU64::new(0),
+        bytes_written: AtomicU64::new(0),
+        last_read: Mutex::new(Instant::now()),
+        last_write: Mutex::new(Instant::now()),
+        bell_action,
+        host_id: host_id.clone(),
+        pid,
+        windows: Mutex::new(vec![window.label().to_string()]),
+    };
+    start_session(
+        session,
+        reader,
+        encoding.as_deref(),
+        Some(child),
+        host_id,
+        app,
+        &state,
+    )
+}
+
+/// Register a session and start delivering its output as `pty:data` events
+/// to the windows it is attached to. Returns the session id.
+fn start_session<R: Runtime>(
+    session: Session,
+    reader: Box<dyn Read + Send>,
+    encoding: Option<&str>,
+    child: Option<Box<dyn portable_pty::Child + Send + Sync>>,
+    host_id: Option<String>,
+    app: AppHandle<R>,
+    state: &PtyState,
+) -> Result<String, String> {
+    let spawned_at = Instant::now();
+    let id = state.next_id.fetch_add(1, Ordering::Relaxed);
+    let id_s = id.to_string();
+
+    let startup_delay = session
+        .startup_commands
+        .lock()
+        .ok()
+        .and_then(|state| state.as_ref().and_then(|state| state.delay));
+    let session = Arc::new(session);
 
     {
         let mut sessions = state.sessions.lock().map_err(|_| "PtyState poisoned")?;
         sessions.insert(id, session.clone());
     }
+    start_throughput_events(app.clone(), state.sessions.clone());
 
-    // Reader thread: blocks on PTY read, decodes, and hands chunks to the
-    // emitter thread. Kept separate so slow event emission never stalls reads.
-    let id_data = id_s.clone();
-    let session_for_reader = session.clone();
-    let mut output_decoder = PtyOutputDecoder::new(encoding.as_deref());
-    let (chunk_tx, chunk_rx) = mpsc::channel::<String>();
-    let reader_handle = thread::spawn(move || {
-        let mut buf = [0u8; PTY_READ_BUFFER_BYTES];
-        let mut pending = Vec::new();
-        loop {
-            match reader.read(&mut buf) {
-                Ok(0) => break,
-                Ok(n) => {
-                    pending.extend_from_slice(&buf[..n]);
-                    for data in extract_ready_output_chunks(&mut output_decoder, &mut pending) {
-                        maybe_send_auto_password(&session_for_reader, &data);
-                        if chunk_tx.send(data).is_err() {
-                            return;
-                        }
-                    }
-                }
-                Err(_) => break,
+    if let Some(delay) = startup_delay {
+        let session_for_startup = session.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(delay).await;
+            let payload = session_for_startup
+                .startup_commands
+                .lock()
+                .ok()
+                .and_then(|mut state| state.take())
+                .and_then(|state| state.payload);
+            if let Some(payload) = payload {
+                session_for_startup.queue_input(&payload, "startup commands");
             }
-        }
-
-        if let Some(data) = drain_output_tail(&mut output_decoder, &mut pending) {
-            let _ = chunk_tx.send(data);
-        }
-    });
+        });
+    }
 
-    // Emitter thread: coalesces whatever backlog accumulated while the
-    // previous emit was in flight into a single event. Adds no latency for
-    // interactive output; batches aggressively under heavy throughput.
+    // Output goes through the shared reader and emitter threads; the child
+    // is reaped on the blocking pool once its output ends.
     let app_data = app.clone();
-    let emitter_handle = thread::spawn(move || {
-        while let Ok(first) = chunk_rx.recv() {
-            let mut batch = first;
-            while batch.len() < PTY_EMIT_MAX_BATCH_CHARS {
-                match chunk_rx.try_recv() {
-                    Ok(chunk) => batch.push_str(&chunk),
-                    Err(_) => break,
-                }
-            }
-            let _ = app_data.emit_to(
-                MAIN_WINDOW_LABEL,
-                "pty:data",
-                PtyDataPayload {
-                    session_id: id_data.clone(),
-                    data: batch,
-                },
-            );
-        }
-    });
-
-    // Wait thread: emits exit event and removes session from state.
-    let app_exit = app.clone();
-    let id_exit = id_s.clone();
-    let sessions_for_exit = state.sessions.clone();
-    thread::spawn(move || {
-        let code = child.wait().ok().map(|s| s.exit_code()).unwrap_or(1);
-        let _ = reader_handle.join();
-        let _ = emitter_handle.join();
-        let _ = app_exit.emit_to(
-            MAIN_WINDOW_LABEL,
-            "pty:exit",
-            PtyExitPayload {
-                session_id: id_exit.clone(),
-                code,
+    let session_data = session.clone();
+    let id_data = id_s.clone();
+    let emit: EmitFn = Arc::new(move |data| {
+        session_data.emit(
+            &app_data,
+            "pty:data",
+            PtyDataPayload {
+                session_id: id_data.clone(),
+                data,
             },
         );
-        if let Ok(mut sessions) = sessions_for_exit.lock() {
-            sessions.remove(&id);
-        }
     });
+    // Only SSH sessions belong to a host.
+    let challenges = ChallengeWatch::new(
+        host_id
+            .is_some()
+            .then(|| spawned_at + Duration::from_secs(CHALLENGE_PROMPT_ARM_SECONDS)),
+    );
+    let sink = Box::new(SessionOutput {
+        id,
+        session: session.clone(),
+        decoder: PtyOutputDecoder::new(encoding),
+        pending: Vec::new(),
+        emit,
+        child,
+        host_id,
+        spawned_at,
+        bells: BellDetector::default(),
+        last_bell: None,
+        challenges,
+        app,
+        sessions: state.sessions.clone(),
+    });
+    let reader = match session.master.as_ref() {
+        Some(master) => {
+            let master = master.lock().map_err(|_| "PTY master poisoned")?;
+            PtyReader::new(reader, master.as_ref(), sink)
+        }
+        None => PtyReader::without_fd(reader, sink),
+    }
+    .with_gate(session.read_gate.clone());
+    pty_io::watch(reader);
 
     Ok(id_s)
 }
@@ -459,9 +1318,13 @@ pub async fn pty_spawn_ssh<R: Runtime>(
     host_id: String,
     cols: u16,
     rows: u16,
-    app: AppHandle<R>,
+    encoding: Option<String>,
+    transport: Option<PtyTransport>,
+    window: tauri::Window<R>,
     state: tauri::State<'_, PtyState>,
 ) -> Result<String, String> {
+    crate::app_lock::ensure_unlocked()?;
+    let app = window.app_handle().clone();
     let hosts = crate::host_store::hosts_load()?;
     let host = hosts
         .iter()
@@ -484,15 +1347,36 @@ pub async fn pty_spawn_ssh<R: Runtime>(
         "-F".to_string(),
         ssh_config_path.to_string_lossy().to_string(),
         "-o".to_string(),
-        "ConnectTimeout=10".to_string(),
+        // Options given with -o win over the config file, so the host's own
+        // timeout has to be applied here.
+        format!("ConnectTimeout={}", host.connect_timeout.unwrap_or(10)),
         "-o".to_string(),
         "ConnectionAttempts=1".to_string(),
     ];
+    let control_path = crate::ssh_config::get_ssh_control_path();
+    args.extend(crate::ssh_config::ssh_multiplex_args(
+        control_path.to_string_lossy().as_ref(),
+        crate::ssh_config::control_persist_secs(),
+    ));
     let env = parse_env_vars(host.env_vars.as_deref())?;
     for (key, value) in env.iter() {
         args.extend(["-o".to_string(), format!("SetEnv={key}={value}")]);
     }
-    let auto_password_state = crate::credential_store::keychain_get_password(&host.id)?
+    let saved_password = match crate::credential_store::keychain_get_password(&host.id, "terminal")
+    {
+        Ok(password) => password,
+        // ssh asks for the password itself while the keychain is locked.
+        Err(_) if crate::credential_store::keychain_locked() => None,
+        Err(e) => return Err(e),
+    };
+    let prompt_rules = PromptRulesState::new(
+        &host.prompt_rules,
+        // Typed as saved: spaces at either end can be part of it.
+        saved_password
+            .as_deref()
+            .filter(|password| !password.is_empty()),
+    );
+    let auto_password_state = saved_password
         .map(|password| password.trim().to_string())
         .filter(|password| !password.is_empty())
         .map(|password| {
@@ -505,24 +1389,309 @@ pub async fn pty_spawn_ssh<R: Runtime>(
     if auto_password_state.is_some() {
         args.extend(["-o".to_string(), "BatchMode=no".to_string()]);
     }
+    let startup_commands = StartupCommandsState::new(
+        &host.startup_commands,
+        host.startup_commands_delay_ms.map(Duration::from_millis),
+        Instant::now(),
+    );
 
     args.push(target_alias);
 
-    spawn_pty_command(
+    let sessions = state.sessions.clone();
+    let app_for_limit = app.clone();
+    let session_id = spawn_pty_command(
         "/usr/bin/ssh".to_string(),
         args,
         cols,
         rows,
         None,
         env,
-        host.encoding.clone(),
+        // A per-session choice wins over the host default, e.g. to reopen a
+        // legacy server in GBK without editing the host.
+        encoding
+            .filter(|label| !label.trim().is_empty())
+            .or_else(|| host.encoding.clone()),
         auto_password_state,
+        prompt_rules,
+        startup_commands,
+        Some(host.id.clone()),
+        host.bell_action.unwrap_or_default(),
+        transport.unwrap_or_default(),
+        window,
+        app,
+        state,
+    )
+    .await?;
+    if let Some(minutes) = host.keepalive_idle_minutes.filter(|minutes| *minutes > 0) {
+        let keepalive = host
+            .keepalive_string
+            .clone()
+            .filter(|keepalive| !keepalive.is_empty())
+            .unwrap_or_else(|| KEEPALIVE_DEFAULT_STRING.to_string());
+        start_idle_keepalive(&session_id, minutes, keepalive, sessions.clone())?;
+    }
+    if let Some(minutes) = host.max_session_minutes.filter(|minutes| *minutes > 0) {
+        enforce_session_limit(&session_id, &host.id, minutes, sessions, app_for_limit)?;
+    }
+    Ok(session_id)
+}
+
+/// Start a local shell from a profile (see `shell_profiles`). Returns the
+/// session id; output, input and exit work as for SSH sessions.
+#[tauri::command]
+pub async fn pty_spawn_local<R: Runtime>(
+    profile_id: String,
+    cols: u16,
+    rows: u16,
+    window: tauri::Window<R>,
+    state: tauri::State<'_, PtyState>,
+) -> Result<String, String> {
+    crate::app_lock::ensure_unlocked()?;
+    let profile = crate::shell_profiles::shell_profile_get(&profile_id)?;
+    let env = parse_env_vars(profile.env_vars.as_deref())?;
+    let cwd = profile.cwd.map(|cwd| match cwd.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => dirs::home_dir()
+            .map(|home| format!("{}{rest}", home.to_string_lossy()))
+            .unwrap_or(cwd.clone()),
+        _ => cwd,
+    });
+    let startup_commands: Vec<String> = profile
+        .startup_command
+        .as_deref()
+        .unwrap_or_default()
+        .lines()
+        .map(str::to_string)
+        .collect();
+    let app = window.app_handle().clone();
+    spawn_pty_command(
+        profile.shell,
+        profile.args,
+        cols,
+        rows,
+        cwd,
+        env,
+        None,
+        None,
+        None,
+        StartupCommandsState::new(&startup_commands, None, Instant::now()),
+        None,
+        BellAction::default(),
+        PtyTransport::default(),
+        window,
         app,
         state,
     )
     .await
 }
 
+fn find_session(
+    sessions: &Mutex<HashMap<SessionId, Arc<Session>>>,
+    id: SessionId,
+) -> Option<Arc<Session>> {
+    sessions.lock().ok()?.get(&id).cloned()
+}
+
+/// Work out `pty:throughput` entries from the current totals: sessions whose
+/// counters moved since the previous round, with their rates. `previous` is
+/// updated and forgets sessions that have ended.
+fn throughput_samples(
+    previous: &mut HashMap<SessionId, (u64, u64)>,
+    current: Vec<(SessionId, u64, u64)>,
+    elapsed: Duration,
+) -> Vec<PtyThroughputPayload> {
+    let secs = elapsed.as_secs_f64().max(0.001);
+    let rate = |delta: u64| (delta as f64 / secs).round() as u64;
+    let mut samples = Vec::new();
+    let mut seen = HashMap::with_capacity(current.len());
+    for (id, read, written) in current {
+        let (last_read, last_written) = previous.get(&id).copied().unwrap_or_default();
+        if (read, written) != (last_read, last_written) {
+            samples.push(PtyThroughputPayload {
+                session_id: id.to_string(),
+                bytes_read: read,
+                bytes_written: written,
+                read_bytes_per_sec: rate(read.saturating_sub(last_read)),
+                write_bytes_per_sec: rate(written.saturating_sub(last_written)),
+            });
+        }
+        seen.insert(id, (read, written));
+    }
+    *previous = seen;
+    samples
+}
+
+/// Emit `pty:throughput` every couple of seconds for sessions that moved
+/// data. One loop serves all sessions; it starts with the first session.
+fn start_throughput_events<R: Runtime>(
+    app: AppHandle<R>,
+    sessions: Arc<Mutex<HashMap<SessionId, Arc<Session>>>>,
+) {
+    static STARTED: AtomicBool = AtomicBool::new(false);
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    tauri::async_runtime::spawn(async move {
+        let mut previous = HashMap::new();
+        let mut last_round = Instant::now();
+        loop {
+            tokio::time::sleep(THROUGHPUT_INTERVAL).await;
+            let mut windows = HashMap::new();
+            let current: Vec<(SessionId, u64, u64)> = match sessions.lock() {
+                Ok(sessions) => sessions
+                    .iter()
+                    .map(|(id, session)| {
+                        windows.insert(id.to_string(), session.window_labels());
+                        (
+                            *id,
+                            session.bytes_read.load(Ordering::Relaxed),
+                            session.bytes_written.load(Ordering::Relaxed),
+                        )
+                    })
+                    .collect(),
+                Err(_) => continue,
+            };
+            let samples = throughput_samples(&mut previous, current, last_round.elapsed());
+            last_round = Instant::now();
+            // Each window hears about the sessions it shows.
+            let mut by_window: HashMap<&str, Vec<PtyThroughputPayload>> = HashMap::new();
+            for sample in samples {
+                for label in windows.get(&sample.session_id).into_iter().flatten() {
+                    by_window.entry(label).or_default().push(sample.clone());
+                }
+            }
+            for (label, samples) in by_window {
+                let _ = app.emit_to(label, "pty:throughput", samples);
+            }
+        }
+    });
+}
+
+/// Type `keepalive` into the session whenever nothing was read or written for
+/// `minutes`, until it closes.
+fn start_idle_keepalive(
+    session_id: &str,
+    minutes: u32,
+    keepalive: String,
+    sessions: Arc<Mutex<HashMap<SessionId, Arc<Session>>>>,
+) -> Result<(), String> {
+    let id: SessionId = session_id.parse().map_err(|_| "invalid session_id")?;
+    let idle_limit = Duration::from_secs(u64::from(minutes) * 60);
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let wait = {
+                let Some(session) = find_session(&sessions, id) else {
+                    return;
+                };
+                let idle_for = session.idle_for();
+                if idle_for >= idle_limit {
+                    let bytes = encode_pty_input(session.encoding, &keepalive);
+                    let written = match session.writer.lock() {
+                        Ok(mut writer) => writer.write_all(&bytes).and_then(|()| writer.flush()),
+                        Err(_) => return,
+                    };
+                    if written.is_err() {
+                        return;
+                    }
+                    session.note_written(bytes.len());
+                    idle_limit
+                } else {
+                    idle_limit - idle_for
+                }
+            };
+            tokio::time::sleep(wait).await;
+        }
+    });
+    Ok(())
+}
+
+/// Disconnect the session once the host's time limit runs out, emitting
+/// `pty:session-expiring` shortly before. The connection history records the
+/// session as ended by the limit.
+fn enforce_session_limit<R: Runtime>(
+    session_id: &str,
+    host_id: &str,
+    minutes: u32,
+    sessions: Arc<Mutex<HashMap<SessionId, Arc<Session>>>>,
+    app: AppHandle<R>,
+) -> Result<(), String> {
+    let id: SessionId = session_id.parse().map_err(|_| "invalid session_id")?;
+    let host_id = host_id.to_string();
+    let limit = Duration::from_secs(u64::from(minutes) * 60);
+    let warning = SESSION_EXPIRY_WARNING.min(limit / 2);
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(limit - warning).await;
+        let Some(session) = find_session(&sessions, id) else {
+            return;
+        };
+        session.emit(
+            &app,
+            "pty:session-expiring",
+            PtyExpiringPayload {
+                session_id: id.to_string(),
+                remaining_secs: warning.as_secs(),
+            },
+        );
+        tokio::time::sleep(warning).await;
+        let Some(session) = find_session(&sessions, id) else {
+            return;
+        };
+        session.limit_enforced.store(true, Ordering::Relaxed);
+        let _ = session.hang_up();
+        eprintln!(
+            "[pty] session {id} to host {host_id} reached its {minutes} minute limit; disconnected"
+        );
+    });
+    Ok(())
+}
+
+/// Open a serial console (e.g. `/dev/tty.usbserial-1410` or `COM3`) as a
+/// session. Output, input and exit use the same events and commands as PTY
+/// sessions; `pty_kill` closes the device.
+#[tauri::command]
+pub async fn serial_spawn<R: Runtime>(
+    device: String,
+    baud: u32,
+    data_bits: Option<u8>,
+    parity: Option<String>,
+    stop_bits: Option<u8>,
+    window: tauri::Window<R>,
+    app: AppHandle<R>,
+    state: tauri::State<'_, PtyState>,
+) -> Result<String, String> {
+    crate::app_lock::ensure_unlocked()?;
+    let settings = SerialSettings::parse(baud, data_bits, parity.as_deref(), stop_bits)?;
+    let connection =
+        tauri::async_runtime::spawn_blocking(move || crate::serial::open(&device, &settings))
+            .await
+            .map_err(|e| e.to_string())??;
+    let session = Session {
+        encoding: resolve_pty_encoding(None),
+        master: None,
+        writer: Mutex::new(connection.writer),
+        auto_input: Mutex::new(None),
+        killer: Mutex::new(connection.killer),
+        read_gate: ReadGate::default(),
+        output_ring: None,
+        ring_stalled: AtomicBool::new(false),
+        exited: AtomicBool::new(false),
+        auto_password: Mutex::new(None),
+        prompt_rules: Mutex::new(None),
+        startup_commands: Mutex::new(None),
+        terminal_modes: Mutex::new(TerminalModeTracker::default()),
+        scrollback: Mutex::new(Scrollback::new(crate::scrollback::memory_cap())),
+        limit_enforced: AtomicBool::new(false),
+        bytes_read: AtomicU64::new(0),
+        bytes_written: AtomicU64::new(0),
+        last_read: Mutex::new(Instant::now()),
+        last_write: Mutex::new(Instant::now()),
+        bell_action: BellAction::default(),
+        host_id: None,
+        pid: None,
+        windows: Mutex::new(vec![window.label().to_string()]),
+    };
+    start_session(session, connection.reader, None, None, None, app, &state)
+}
+
 #[tauri::command]
 pub async fn pty_write(
     session_id: String,
@@ -540,11 +1709,68 @@ pub async fn pty_write(
         }
         *auto_password = None;
     }
+    // Typing before the prompt shows up means the user took over the session.
+    if let Ok(mut startup_commands) = session.startup_commands.lock() {
+        *startup_commands = None;
+    }
+    crate::app_lock::note_activity();
+    let bytes = encode_pty_input(session.encoding, &data);
     let mut w = session.writer.lock().map_err(|_| "writer poisoned")?;
-    w.write_all(data.as_bytes()).map_err(|e| e.to_string())?;
+    w.write_all(&bytes).map_err(|e| e.to_string())?;
+    session.note_written(bytes.len());
     Ok(())
 }
 
+/// Type the answer to an `ssh:prompt` challenge into the session.
+pub(crate) fn write_prompt_answer(
+    state: &PtyState,
+    session_id: &str,
+    answer: &str,
+) -> Result<(), String> {
+    let id: u32 = session_id.parse().map_err(|_| "No prompt is waiting")?;
+    let session = {
+        let sessions = state.sessions.lock().map_err(|_| "PtyState poisoned")?;
+        sessions.get(&id).cloned().ok_or("Unavailable session")?
+    };
+    write_line(&session, answer)
+}
+
+// `text` and Enter.
+fn line_input(text: &str) -> String {
+    format!("{}\n", text.trim_end_matches(['\r', '\n']))
+}
+
+/// Write `text` and Enter to the session.
+fn write_line(session: &Session, text: &str) -> Result<(), String> {
+    session.write_input(&encode_pty_input(session.encoding, &line_input(text)))
+}
+
+/// Type `host_id`'s saved password and Enter into the session, for `sudo`
+/// and `su` prompts. The password goes from the keychain straight to the
+/// PTY; it never reaches the UI or the clipboard.
+#[tauri::command]
+pub async fn pty_type_secret(
+    session_id: String,
+    host_id: String,
+    state: tauri::State<'_, PtyState>,
+) -> Result<(), String> {
+    let id: u32 = session_id.parse().map_err(|_| "invalid session_id")?;
+    let session = {
+        let sessions = state.sessions.lock().map_err(|_| "PtyState poisoned")?;
+        sessions.get(&id).cloned().ok_or("Unavailable session")?
+    };
+    // The keychain read may wait for the OS to confirm the device owner.
+    let password = tauri::async_runtime::spawn_blocking(move || {
+        crate::credential_store::keychain_reveal_password(&host_id, "autotype")
+    })
+    .await
+    .map_err(|e| e.to_string())??
+    .filter(|password| !password.is_empty())
+    .ok_or("No password saved for this host")?;
+    crate::app_lock::note_activity();
+    write_line(&session, &password)
+}
+
 #[tauri::command]
 pub async fn pty_resize(
     session_id: String,
@@ -557,7 +1783,10 @@ pub async fn pty_resize(
         let sessions = state.sessions.lock().map_err(|_| "PtyState poisoned")?;
         sessions.get(&id).cloned().ok_or("Unavailable session")?
     };
-    let master = session.master.lock().map_err(|_| "master poisoned")?;
+    let Some(master) = session.master.as_ref() else {
+        return Ok(());
+    };
+    let master = master.lock().map_err(|_| "master poisoned")?;
     master
         .resize(PtySize {
             rows,
@@ -570,23 +1799,234 @@ pub async fn pty_resize(
 }
 
 #[tauri::command]
-pub async fn pty_kill(session_id: String, state: tauri::State<'_, PtyState>) -> Result<(), String> {
+pub async fn pty_session_info(
+    session_id: String,
+    state: tauri::State<'_, PtyState>,
+) -> Result<PtySessionInfo, String> {
     let id: u32 = session_id.parse().map_err(|_| "invalid session_id")?;
     let session = {
         let sessions = state.sessions.lock().map_err(|_| "PtyState poisoned")?;
         sessions.get(&id).cloned().ok_or("Unavailable session")?
     };
-    let mut k = session.killer.lock().map_err(|_| "killer poisoned")?;
-    k.kill().map_err(|e| e.to_string())?;
+    let modes = session
+        .terminal_modes
+        .lock()
+        .map_err(|_| "terminal modes poisoned")?
+        .modes;
+    let foreground_pid = session.foreground_pid();
+    let (foreground_process, cwd) = tauri::async_runtime::spawn_blocking(move || {
+        foreground_pid.map_or((None, None), |pid| {
+            (
+                crate::process_info::process_name(pid),
+                crate::process_info::process_cwd(pid),
+            )
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(PtySessionInfo {
+        session_id,
+        encoding: session.encoding.name().to_string(),
+        alternate_screen: modes.alternate_screen,
+        mouse_tracking: modes.mouse_tracking,
+        mouse_sgr: modes.mouse_sgr,
+        pid: session.pid,
+        foreground_pid,
+        foreground_process,
+        cwd,
+    })
+}
+
+/// Bytes read and written so far by one session, or by every open session
+/// when `session_id` is not given.
+#[tauri::command]
+pub async fn pty_session_stats(
+    session_id: Option<String>,
+    state: tauri::State<'_, PtyState>,
+) -> Result<Vec<PtySessionStats>, String> {
+    let sessions = state.sessions.lock().map_err(|_| "PtyState poisoned")?;
+    let Some(session_id) = session_id else {
+        let mut stats: Vec<PtySessionStats> = sessions
+            .iter()
+            .map(|(id, session)| session.stats(*id))
+            .collect();
+        stats.sort_by_key(|stats| stats.session_id.parse::<SessionId>().unwrap_or_default());
+        return Ok(stats);
+    };
+    let id: u32 = session_id.parse().map_err(|_| "invalid session_id")?;
+    let session = sessions.get(&id).ok_or("Unavailable session")?;
+    Ok(vec![session.stats(id)])
+}
+
+/// The newest `max_bytes` of a session's output (memory and spilled part),
+/// e.g. to repopulate a terminal view.
+#[tauri::command]
+pub async fn pty_scrollback(
+    session_id: String,
+    max_bytes: Option<usize>,
+    state: tauri::State<'_, PtyState>,
+) -> Result<String, String> {
+    let id: u32 = session_id.parse().map_err(|_| "invalid session_id")?;
+    let session = {
+        let sessions = state.sessions.lock().map_err(|_| "PtyState poisoned")?;
+        sessions.get(&id).cloned().ok_or("Unavailable session")?
+    };
+    let max_bytes = max_bytes.unwrap_or(PTY_SCROLLBACK_DEFAULT_BYTES);
+    tauri::async_runtime::spawn_blocking(move || {
+        session
+            .scrollback
+            .lock()
+            .map_err(|_| "scrollback poisoned".to_string())?
+            .tail(max_bytes)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Show a live session in the calling window too, e.g. a second window or
+/// one reopened after closing. Returns the newest `max_bytes` of its output;
+/// every later chunk arrives as a `pty:data` event, so listen before calling
+/// and apply buffered events after writing the returned output.
+#[tauri::command]
+pub async fn pty_attach<R: Runtime>(
+    session_id: String,
+    max_bytes: Option<usize>,
+    window: tauri::Window<R>,
+    state: tauri::State<'_, PtyState>,
+) -> Result<String, String> {
+    let id: u32 = session_id.parse().map_err(|_| "invalid session_id")?;
+    let session = {
+        let sessions = state.sessions.lock().map_err(|_| "PtyState poisoned")?;
+        sessions.get(&id).cloned().ok_or("Unavailable session")?
+    };
+    let label = window.label().to_string();
+    let max_bytes = max_bytes.unwrap_or(PTY_SCROLLBACK_DEFAULT_BYTES);
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut scrollback = session
+            .scrollback
+            .lock()
+            .map_err(|_| "scrollback poisoned".to_string())?;
+        let output = scrollback.tail(max_bytes)?;
+        // Output already queued was part of the tail; the window starts
+        // receiving events right after it has been emitted.
+        let attached = session.clone();
+        pty_io::after_output(move || attached.attach(&label));
+        Ok(output)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Stop sending a session's events to the calling window. The session keeps
+/// running and can be attached to again.
+#[tauri::command]
+pub async fn pty_detach<R: Runtime>(
+    session_id: String,
+    window: tauri::Window<R>,
+    state: tauri::State<'_, PtyState>,
+) -> Result<(), String> {
+    let id: u32 = session_id.parse().map_err(|_| "invalid session_id")?;
+    let session = {
+        let sessions = state.sessions.lock().map_err(|_| "PtyState poisoned")?;
+        sessions.get(&id).cloned().ok_or("Unavailable session")?
+    };
+    session.detach(window.label());
+    Ok(())
+}
+
+/// Every live session, oldest first.
+#[tauri::command]
+pub async fn pty_list(state: tauri::State<'_, PtyState>) -> Result<Vec<PtySessionSummary>, String> {
+    state.list()
+}
+
+/// Stop reading a session's output, e.g. while the terminal view works
+/// through a backlog. Whatever writes to the session blocks once the PTY
+/// buffer is full, until `pty_resume`.
+#[tauri::command]
+pub async fn pty_pause(
+    session_id: String,
+    state: tauri::State<'_, PtyState>,
+) -> Result<(), String> {
+    let id: u32 = session_id.parse().map_err(|_| "invalid session_id")?;
+    let sessions = state.sessions.lock().map_err(|_| "PtyState poisoned")?;
+    sessions
+        .get(&id)
+        .ok_or("Unavailable session")?
+        .read_gate
+        .pause();
     Ok(())
 }
 
+#[tauri::command]
+pub async fn pty_resume(
+    session_id: String,
+    state: tauri::State<'_, PtyState>,
+) -> Result<(), String> {
+    let id: u32 = session_id.parse().map_err(|_| "invalid session_id")?;
+    let sessions = state.sessions.lock().map_err(|_| "PtyState poisoned")?;
+    let session = sessions.get(&id).ok_or("Unavailable session")?;
+    // A polled session whose ring is full stays paused until it is read.
+    if !session.ring_stalled.load(Ordering::Acquire) {
+        session.read_gate.resume();
+    }
+    Ok(())
+}
+
+/// Take up to `max_bytes` of a polled session's output (see
+/// `PtyTransport::Poll`), in whole characters; empty when none is waiting.
+/// After `pty:exit`, keep reading until the result is empty.
+#[tauri::command]
+pub async fn pty_read_chunk(
+    session_id: String,
+    max_bytes: Option<usize>,
+    state: tauri::State<'_, PtyState>,
+) -> Result<String, String> {
+    let id: u32 = session_id.parse().map_err(|_| "invalid session_id")?;
+    let session = find_session(&state.sessions, id).ok_or("Unavailable session")?;
+    let ring = session
+        .output_ring
+        .as_ref()
+        .ok_or("Session output is delivered as events")?;
+    let data = ring.read(max_bytes.unwrap_or(PTY_READ_CHUNK_DEFAULT_BYTES));
+    if !ring.overflowed() && session.ring_stalled.swap(false, Ordering::AcqRel) {
+        session.read_gate.resume();
+    }
+    // A `max_bytes` smaller than the next character reads nothing, so only
+    // an empty ring means the output was read to the end.
+    if ring.is_empty() && session.exited.load(Ordering::Acquire) {
+        if let Ok(mut sessions) = state.sessions.lock() {
+            sessions.remove(&id);
+        }
+    }
+    Ok(data)
+}
+
+#[tauri::command]
+pub async fn pty_kill(session_id: String, state: tauri::State<'_, PtyState>) -> Result<(), String> {
+    let id: u32 = session_id.parse().map_err(|_| "invalid session_id")?;
+    // Closed for good: output the UI has not read yet (a polled session's
+    // ring) goes with it, and an ended session is not kept around for it.
+    let session = {
+        let mut sessions = state.sessions.lock().map_err(|_| "PtyState poisoned")?;
+        sessions.remove(&id).ok_or("Unavailable session")?
+    };
+    if session.exited.load(Ordering::Acquire) {
+        return Ok(());
+    }
+    session.hang_up()
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
-        drain_output_tail, extract_ready_output_chunks, parse_env_vars, AutoPasswordPromptMatcher,
-        AutoPasswordState, PtyOutputDecoder,
+        drain_output_tail, encode_pty_input, extract_ready_output_chunks, parse_env_vars,
+        resolve_pty_encoding, throughput_samples, validate_prompt_rules, AutoPasswordPromptMatcher,
+        AutoPasswordState, ChallengeWatch, MouseTrackingMode, PromptRulesState, PtyOutputDecoder,
+        StartupCommandsState, TerminalModeTracker,
     };
+    use crate::models::PromptRule;
+    use std::collections::HashMap;
     use std::time::{Duration, Instant};
 
     #[test]
@@ -643,6 +2083,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn encodes_input_in_session_encoding() {
+        let gbk = resolve_pty_encoding(Some(" GBK "));
+        assert_eq!(
+            encode_pty_input(gbk, "ls 中文\n").as_ref(),
+            b"ls \xD6\xD0\xCE\xC4\n"
+        );
+        assert_eq!(encode_pty_input(gbk, "\u{1F600}").as_ref(), b"?");
+
+        let latin1 = resolve_pty_encoding(Some("latin1"));
+        assert_eq!(encode_pty_input(latin1, "café").as_ref(), b"caf\xE9");
+        assert_eq!(resolve_pty_encoding(Some("shift_jis")).name(), "Shift_JIS");
+        assert_eq!(resolve_pty_encoding(Some("big5")).name(), "Big5");
+        assert_eq!(
+            resolve_pty_encoding(Some("no-such-encoding")).name(),
+            "UTF-8"
+        );
+    }
+
     #[test]
     fn parses_host_env_vars() {
         let env =
@@ -663,6 +2122,58 @@ mod tests {
         assert!(parse_env_vars(Some("VAR1=value1, VAR2=value2")).is_err());
     }
 
+    #[test]
+    fn prompt_rules_answer_matching_prompts_up_to_their_cap() {
+        let rule = |pattern: &str, response: &str, max_replays: Option<u32>| PromptRule {
+            pattern: pattern.to_string(),
+            response: response.to_string(),
+            enabled: true,
+            max_replays,
+        };
+        let rules = vec![
+            rule(r"^\[sudo\] password for \w+:", "{password}", Some(2)),
+            rule(r"Continue\? \[y/N\]", "y", None),
+            PromptRule {
+                enabled: false,
+                ..rule("login:", "admin", None)
+            },
+        ];
+        assert!(validate_prompt_rules(&rules).is_ok());
+        assert!(validate_prompt_rules(&[rule("(", "x", None)]).is_err());
+        assert!(validate_prompt_rules(&[rule(".*", "x", None)]).is_err());
+
+        let mut state = PromptRulesState::new(&rules, Some("secret")).unwrap();
+        assert_eq!(state.feed("$ sudo apt upgrade\r\n[sudo] pass"), None);
+        assert_eq!(state.feed("word for deploy: ").as_deref(), Some("secret"));
+        assert_eq!(
+            state
+                .feed("\r\nSorry, try again.\r\n\x1b[1m[sudo] password for deploy: ")
+                .as_deref(),
+            Some("secret")
+        );
+        // Out of replays: a rejected password is not sent a third time.
+        assert_eq!(
+            state.feed("\r\nSorry, try again.\r\n[sudo] password for deploy: "),
+            None
+        );
+        assert_eq!(state.feed("\r\nlogin: "), None);
+        assert_eq!(state.feed("\r\nContinue? [y/N] ").as_deref(), Some("y"));
+
+        // Once the saved password answered a prompt, a rule does not see it.
+        let mut state = PromptRulesState::new(&rules, Some(" secret ")).unwrap();
+        assert_eq!(state.feed("[sudo] password for "), None);
+        state.skip();
+        assert_eq!(state.feed("deploy: "), None);
+        assert_eq!(
+            state.feed("\r\n[sudo] password for deploy: ").as_deref(),
+            Some(" secret ")
+        );
+
+        // Without a saved password only the rules that do not need it remain.
+        let state = PromptRulesState::new(&rules[..1], None);
+        assert!(state.is_none());
+    }
+
     #[test]
     fn auto_password_matches_only_configured_ssh_target_prompt() {
         let matcher = AutoPasswordPromptMatcher::new([
@@ -707,4 +2218,117 @@ mod tests {
             None
         );
     }
+
+    #[test]
+    fn challenge_watch_reports_otp_prompts_during_login() {
+        let now = Instant::now();
+        let mut watch = ChallengeWatch::new(Some(now + Duration::from_secs(60)));
+        assert_eq!(watch.feed("user@example.com's password: ", now), None);
+        assert_eq!(watch.feed("\r\n\x1b[1mVerification", now), None);
+        assert_eq!(
+            watch.feed(" code:\x1b[0m ", now).as_deref(),
+            Some("Verification code:")
+        );
+        assert_eq!(
+            watch.feed("\r\nVerification code: ", now + Duration::from_secs(61)),
+            None
+        );
+        assert_eq!(
+            ChallengeWatch::new(None).feed("Verification code: ", now),
+            None
+        );
+    }
+
+    #[test]
+    fn tracks_alternate_screen_and_mouse_modes_across_reads() {
+        let mut tracker = TerminalModeTracker::default();
+        tracker.feed("vim starting\x1b[?10");
+        assert!(!tracker.modes.alternate_screen);
+        tracker.feed("49h\x1b[?1002;1006h");
+        assert!(tracker.modes.alternate_screen);
+        assert_eq!(tracker.modes.mouse_tracking, MouseTrackingMode::ButtonEvent);
+        assert!(tracker.modes.mouse_sgr);
+
+        tracker.feed("\x1b[?1000l");
+        assert_eq!(tracker.modes.mouse_tracking, MouseTrackingMode::ButtonEvent);
+        tracker.feed("\x1b[?1002l\x1b[?1006l\x1b");
+        tracker.feed("[?1049l");
+        assert_eq!(tracker.modes.mouse_tracking, MouseTrackingMode::Off);
+        assert!(!tracker.modes.mouse_sgr);
+        assert!(!tracker.modes.alternate_screen);
+        assert!(tracker.carry.is_empty());
+    }
+
+    #[test]
+    fn full_reset_clears_terminal_modes() {
+        let mut tracker = TerminalModeTracker::default();
+        tracker.feed("\x1b[?1049h\x1b[?1003h\x1b[1;1H");
+        assert!(tracker.modes.alternate_screen);
+        assert_eq!(tracker.modes.mouse_tracking, MouseTrackingMode::AnyEvent);
+        tracker.feed("\x1bc");
+        assert!(!tracker.modes.alternate_screen);
+        assert_eq!(tracker.modes.mouse_tracking, MouseTrackingMode::Off);
+    }
+
+    #[test]
+    fn startup_commands_wait_for_shell_prompt() {
+        let now = Instant::now();
+        let commands = vec![
+            "cd /var/www".to_string(),
+            "  ".to_string(),
+            "sudo -i".to_string(),
+        ];
+        let mut state = StartupCommandsState::new(&commands, None, now).unwrap();
+        assert_eq!(
+            state.take_payload_for_output("Welcome to Ubuntu\r\n", now),
+            None
+        );
+        assert_eq!(
+            state.take_payload_for_output("user@example.com's password: ", now),
+            None
+        );
+        assert_eq!(
+            state.take_payload_for_output(
+                "\r\n\x1b]0;deploy@web\x07\x1b[01;32mdeploy@web\x1b[00m:~",
+                now
+            ),
+            None
+        );
+        assert_eq!(
+            state.take_payload_for_output("$ ", now),
+            Some("cd /var/www\nsudo -i\n".to_string())
+        );
+        assert_eq!(state.take_payload_for_output("\r\n$ ", now), None);
+
+        let mut late = StartupCommandsState::new(&commands, None, now).unwrap();
+        assert_eq!(
+            late.take_payload_for_output("$ ", now + Duration::from_secs(31)),
+            None
+        );
+        assert!(StartupCommandsState::new(&[" ".to_string()], None, now).is_none());
+    }
+
+    #[test]
+    fn reports_throughput_only_for_busy_sessions() {
+        let mut previous = HashMap::new();
+        let first = throughput_samples(&mut previous, vec![(1, 4000, 10)], Duration::from_secs(2));
+        assert_eq!(first.len(), 1);
+        assert_eq!(
+            (first[0].read_bytes_per_sec, first[0].write_bytes_per_sec),
+            (2000, 5)
+        );
+
+        let samples = throughput_samples(
+            &mut previous,
+            vec![(1, 4000, 10), (2, 100, 0)],
+            Duration::from_secs(2),
+        );
+        assert_eq!(samples.len(), 1);
+        assert_eq!(samples[0].session_id, "2");
+
+        let samples = throughput_samples(&mut previous, vec![(2, 1100, 0)], Duration::from_secs(1));
+        assert_eq!(samples[0].read_bytes_per_sec, 1000);
+        assert_eq!(samples[0].bytes_read, 1100);
+        assert!(!previous.contains_key(&1));
+    }
 }
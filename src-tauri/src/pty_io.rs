@@ -0,0 +1,487 @@
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[cfg(unix)]
+use std::io::Write;
+#[cfg(unix)]
+use std::os::unix::io::{AsRawFd, RawFd};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+
+pub(crate) const PTY_READ_BUFFER_BYTES: usize = 64 * 1024;
+// Cap for how much decoded output a single pty:data event may carry when the
+// emitter coalesces backlogged chunks.
+const PTY_EMIT_MAX_BATCH_CHARS: usize = 1024 * 1024;
+// Under sustained output events go out at most this often, so a flood (`cat`
+// of a huge file) cannot keep the webview busy with events.
+const PTY_EMIT_MIN_INTERVAL: Duration = Duration::from_millis(16);
+// How often a paused dedicated reader looks whether it was resumed.
+const PAUSED_READER_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Delivers one coalesced batch of a session's output to the frontend.
+pub(crate) type EmitFn = Arc<dyn Fn(String) + Send + Sync>;
+
+/// Receives a session's raw output from the shared reader. Both methods run
+/// on the reader thread, so they must not block.
+pub(crate) trait PtyOutputSink: Send {
+    fn on_data(&mut self, bytes: &[u8]);
+    fn on_eof(self: Box<Self>);
+}
+
+/// Pauses and resumes reading a session's output. While paused nothing is
+/// read, so the PTY buffer fills up and whatever writes to it (e.g. a remote
+/// `cat`) blocks until the UI has caught up.
+#[derive(Clone, Default)]
+pub(crate) struct ReadGate(Arc<AtomicBool>);
+
+impl ReadGate {
+    pub(crate) fn pause(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub(crate) fn resume(&self) {
+        if self.0.swap(false, Ordering::SeqCst) {
+            // The shared reader waits on the fds it is reading; have it add
+            // this one back.
+            #[cfg(unix)]
+            if let Some(watcher) = watcher() {
+                let _ = (&watcher.wake).write(&[1]);
+            }
+        }
+    }
+
+    fn is_paused(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// A session's PTY output stream, registered with [`watch`].
+pub(crate) struct PtyReader {
+    reader: Box<dyn Read + Send>,
+    // The master fd to wait on; `reader` is a dup of it, so readiness is
+    // shared.
+    #[cfg(unix)]
+    fd: Option<RawFd>,
+    sink: Box<dyn PtyOutputSink>,
+    gate: ReadGate,
+}
+
+impl PtyReader {
+    pub(crate) fn new(
+        reader: Box<dyn Read + Send>,
+        master: &dyn portable_pty::MasterPty,
+        sink: Box<dyn PtyOutputSink>,
+    ) -> Self {
+        #[cfg(not(unix))]
+        let _ = master;
+        Self {
+            reader,
+            #[cfg(unix)]
+            fd: master.as_raw_fd(),
+            sink,
+            gate: ReadGate::default(),
+        }
+    }
+
+    /// A stream with no fd to wait on (e.g. a serial device); it always
+    /// gets a dedicated reader thread.
+    pub(crate) fn without_fd(reader: Box<dyn Read + Send>, sink: Box<dyn PtyOutputSink>) -> Self {
+        Self {
+            reader,
+            #[cfg(unix)]
+            fd: None,
+            sink,
+            gate: ReadGate::default(),
+        }
+    }
+
+    /// Read only while `gate` is open.
+    pub(crate) fn with_gate(mut self, gate: ReadGate) -> Self {
+        self.gate = gate;
+        self
+    }
+
+    /// Read once; false once the stream has ended.
+    fn pump(&mut self, buf: &mut [u8]) -> bool {
+        match self.reader.read(buf) {
+            Ok(0) => false,
+            Ok(n) => {
+                self.sink.on_data(&buf[..n]);
+                true
+            }
+            Err(e) => e.kind() == io::ErrorKind::Interrupted,
+        }
+    }
+
+    fn finish(self) {
+        self.sink.on_eof();
+    }
+}
+
+enum EmitMsg {
+    Data {
+        session_id: u32,
+        data: String,
+        emit: EmitFn,
+    },
+    Done(Box<dyn FnOnce() + Send>),
+}
+
+fn emitter() -> &'static mpsc::Sender<EmitMsg> {
+    static EMITTER: OnceLock<mpsc::Sender<EmitMsg>> = OnceLock::new();
+    EMITTER.get_or_init(|| {
+        let (tx, rx) = mpsc::channel();
+        thread::Builder::new()
+            .name("pty-emitter".to_string())
+            .spawn(move || run_emitter(rx))
+            .expect("failed to start PTY emitter thread");
+        tx
+    })
+}
+
+/// Queue decoded output of a session for emission.
+pub(crate) fn emit_output(session_id: u32, data: String, emit: &EmitFn) {
+    let _ = emitter().send(EmitMsg::Data {
+        session_id,
+        data,
+        emit: emit.clone(),
+    });
+}
+
+/// Run `f` on the emitter thread once everything queued before it has been
+/// emitted, e.g. to send a session's exit event after its last output.
+pub(crate) fn after_output(f: impl FnOnce() + Send + 'static) {
+    let _ = emitter().send(EmitMsg::Done(Box::new(f)));
+}
+
+fn flush_batches(batches: &mut Vec<(u32, String, EmitFn)>) {
+    for (_, batch, emit) in batches.drain(..) {
+        emit(batch);
+    }
+}
+
+// Coalesces whatever backlog accumulated while the previous emits were in
+// flight into one event per session, and under sustained output holds each
+// batch until PTY_EMIT_MIN_INTERVAL has passed since the last one. Output
+// after a quiet spell (interactive echo) goes out at once.
+fn run_emitter(rx: mpsc::Receiver<EmitMsg>) {
+    let mut last_flush: Option<Instant> = None;
+    while let Ok(first) = rx.recv() {
+        let hold_until = last_flush
+            .map(|at| at + PTY_EMIT_MIN_INTERVAL)
+            .filter(|until| *until > Instant::now());
+        let mut batches: Vec<(u32, String, EmitFn)> = Vec::new();
+        let mut queued = 0;
+        let mut next = Some(first);
+        while let Some(msg) = next.take() {
+            match msg {
+                EmitMsg::Data {
+                    session_id,
+                    data,
+                    emit,
+                } => {
+                    queued += data.len();
+                    match batches.iter_mut().find(|(id, batch, _)| {
+                        *id == session_id && batch.len() < PTY_EMIT_MAX_BATCH_CHARS
+                    }) {
+                        Some((_, batch, _)) => batch.push_str(&data),
+                        None => batches.push((session_id, data, emit)),
+                    }
+                }
+                EmitMsg::Done(f) => {
+                    flush_batches(&mut batches);
+                    f();
+                }
+            }
+            if queued < PTY_EMIT_MAX_BATCH_CHARS {
+                next = match hold_until {
+                    Some(until) => rx
+                        .recv_timeout(until.saturating_duration_since(Instant::now()))
+                        .ok(),
+                    None => rx.try_recv().ok(),
+                };
+            }
+        }
+        flush_batches(&mut batches);
+        last_flush = Some(Instant::now());
+    }
+}
+
+/// Start delivering a session's output. On unix every session shares one
+/// reader thread; elsewhere (or for fds select(2) cannot hold) the session
+/// gets its own.
+pub(crate) fn watch(reader: PtyReader) {
+    #[cfg(unix)]
+    let reader = match reader.fd {
+        Some(fd) if fd >= 0 && (fd as usize) < libc::FD_SETSIZE => match watcher() {
+            Some(watcher) => match watcher.tx.send(reader) {
+                Ok(()) => {
+                    let _ = (&watcher.wake).write(&[1]);
+                    return;
+                }
+                Err(mpsc::SendError(reader)) => reader,
+            },
+            None => reader,
+        },
+        _ => reader,
+    };
+    spawn_dedicated_reader(reader);
+}
+
+fn spawn_dedicated_reader(mut reader: PtyReader) {
+    thread::spawn(move || {
+        let mut buf = vec![0u8; PTY_READ_BUFFER_BYTES];
+        loop {
+            while reader.gate.is_paused() {
+                thread::sleep(PAUSED_READER_POLL_INTERVAL);
+            }
+            if !reader.pump(&mut buf) {
+                break;
+            }
+        }
+        reader.finish();
+    });
+}
+
+#[cfg(unix)]
+struct Watcher {
+    tx: mpsc::Sender<PtyReader>,
+    wake: UnixStream,
+}
+
+#[cfg(unix)]
+fn watcher() -> Option<&'static Watcher> {
+    static WATCHER: OnceLock<Option<Watcher>> = OnceLock::new();
+    WATCHER
+        .get_or_init(|| {
+            let (wake_rx, wake) = UnixStream::pair().ok()?;
+            wake_rx.set_nonblocking(true).ok()?;
+            wake.set_nonblocking(true).ok()?;
+            let (tx, rx) = mpsc::channel();
+            thread::Builder::new()
+                .name("pty-reader".to_string())
+                .spawn(move || run_watcher(rx, wake_rx))
+                .ok()?;
+            Some(Watcher { tx, wake })
+        })
+        .as_ref()
+}
+
+// select(2) rather than poll(2): macOS poll does not support tty devices.
+#[cfg(unix)]
+fn run_watcher(rx: mpsc::Receiver<PtyReader>, wake: UnixStream) {
+    let wake_fd = wake.as_raw_fd();
+    let mut readers: Vec<(RawFd, PtyReader)> = Vec::new();
+    let mut buf = vec![0u8; PTY_READ_BUFFER_BYTES];
+    loop {
+        // SAFETY: every fd is below FD_SETSIZE (checked in `watch`) and stays
+        // open while registered: each sink keeps its session's master alive.
+        let mut set: libc::fd_set = unsafe { std::mem::zeroed() };
+        let mut max_fd = wake_fd;
+        unsafe {
+            libc::FD_ZERO(&mut set);
+            libc::FD_SET(wake_fd, &mut set);
+            for (fd, reader) in &readers {
+                if reader.gate.is_paused() {
+                    continue;
+                }
+                libc::FD_SET(*fd, &mut set);
+                max_fd = max_fd.max(*fd);
+            }
+        }
+        let ready = unsafe {
+            libc::select(
+                max_fd + 1,
+                &mut set,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            )
+        };
+        if ready < 0 {
+            if io::Error::last_os_error().kind() != io::ErrorKind::Interrupted {
+                // Only a closed fd fails select here; treat its session as ended.
+                let mut i = 0;
+                while i < readers.len() {
+                    if unsafe { libc::fcntl(readers[i].0, libc::F_GETFD) } == -1 {
+                        readers.swap_remove(i).1.finish();
+                    } else {
+                        i += 1;
+                    }
+                }
+            }
+            continue;
+        }
+
+        if unsafe { libc::FD_ISSET(wake_fd, &set) } {
+            let mut scratch = [0u8; 64];
+            while matches!((&wake).read(&mut scratch), Ok(n) if n > 0) {}
+            while let Ok(reader) = rx.try_recv() {
+                if let Some(fd) = reader.fd {
+                    readers.push((fd, reader));
+                }
+            }
+        }
+
+        // One read per ready session per round keeps a flooding session from
+        // starving the others.
+        let mut i = 0;
+        while i < readers.len() {
+            let (fd, reader) = &mut readers[i];
+            if !unsafe { libc::FD_ISSET(*fd, &set) } || reader.pump(&mut buf) {
+                i += 1;
+            } else {
+                readers.swap_remove(i).1.finish();
+            }
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::{watch, PtyOutputSink, PtyReader, ReadGate};
+    use portable_pty::{native_pty_system, CommandBuilder, PtySize};
+    #[cfg(target_os = "linux")]
+    use std::io::Write;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    struct CollectSink {
+        output: Vec<u8>,
+        done: mpsc::Sender<Vec<u8>>,
+    }
+
+    impl PtyOutputSink for CollectSink {
+        fn on_data(&mut self, bytes: &[u8]) {
+            self.output.extend_from_slice(bytes);
+        }
+
+        fn on_eof(self: Box<Self>) {
+            let _ = self.done.send(self.output);
+        }
+    }
+
+    fn open_session(
+        program: &str,
+        args: &[&str],
+        done: mpsc::Sender<Vec<u8>>,
+    ) -> (PtyReader, Box<dyn portable_pty::MasterPty + Send>) {
+        let pair = native_pty_system()
+            .openpty(PtySize {
+                rows: 24,
+                cols: 80,
+                pixel_width: 0,
+                pixel_height: 0,
+            })
+            .unwrap();
+        let mut cmd = CommandBuilder::new(program);
+        cmd.args(args);
+        let _child = pair.slave.spawn_command(cmd).unwrap();
+        drop(pair.slave);
+        let reader = PtyReader::new(
+            pair.master.try_clone_reader().unwrap(),
+            pair.master.as_ref(),
+            Box::new(CollectSink {
+                output: Vec::new(),
+                done,
+            }),
+        );
+        (reader, pair.master)
+    }
+
+    #[test]
+    fn shared_reader_delivers_output_of_every_session() {
+        let (done_tx, done_rx) = mpsc::channel();
+        let mut masters = Vec::new();
+        for i in 0..4 {
+            let (reader, master) =
+                open_session("sh", &["-c", &format!("echo session-{i}")], done_tx.clone());
+            watch(reader);
+            masters.push(master);
+        }
+        let mut outputs: Vec<String> = (0..4)
+            .map(|_| {
+                let output = done_rx.recv_timeout(Duration::from_secs(10)).unwrap();
+                String::from_utf8_lossy(&output).trim().to_string()
+            })
+            .collect();
+        outputs.sort();
+        assert_eq!(
+            outputs,
+            vec!["session-0", "session-1", "session-2", "session-3"]
+        );
+    }
+
+    #[test]
+    fn paused_session_is_not_read_until_resumed() {
+        let (done_tx, done_rx) = mpsc::channel();
+        let gate = ReadGate::default();
+        gate.pause();
+        let (reader, _master) = open_session("sh", &["-c", "echo paused"], done_tx);
+        watch(reader.with_gate(gate.clone()));
+        assert!(done_rx.recv_timeout(Duration::from_millis(300)).is_err());
+
+        gate.resume();
+        let output = done_rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert_eq!(String::from_utf8_lossy(&output).trim(), "paused");
+    }
+
+    #[cfg(target_os = "linux")]
+    fn process_status(field: &str) -> u64 {
+        let status = std::fs::read_to_string("/proc/self/status").unwrap();
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix(field))
+            .and_then(|rest| rest.split_whitespace().next())
+            .and_then(|value| value.parse().ok())
+            .unwrap()
+    }
+
+    /// Benchmark: `cargo test pty_io -- --ignored --nocapture`. Opens 50
+    /// idle sessions with the shared reader, then with a reader and an
+    /// emitter thread per session as before, and prints thread and memory
+    /// growth of each.
+    #[cfg(target_os = "linux")]
+    #[test]
+    #[ignore]
+    fn bench_threads_and_memory_at_50_sessions() {
+        const SESSIONS: usize = 50;
+        let measure = |label: &str, per_session_threads: bool| {
+            let (done_tx, done_rx) = mpsc::channel();
+            let threads_before = process_status("Threads:");
+            let rss_before = process_status("VmRSS:");
+            let mut open = Vec::new();
+            for _ in 0..SESSIONS {
+                let (reader, master) = open_session("cat", &[], done_tx.clone());
+                let writer = master.take_writer().unwrap();
+                if per_session_threads {
+                    let (emit_tx, emit_rx) = mpsc::channel::<String>();
+                    std::thread::spawn(move || while emit_rx.recv().is_ok() {});
+                    super::spawn_dedicated_reader(reader);
+                    open.push((master, writer, Some(emit_tx)));
+                } else {
+                    watch(reader);
+                    open.push((master, writer, None));
+                }
+            }
+            std::thread::sleep(Duration::from_millis(300));
+            println!(
+                "{label}: +{} threads, +{} kB RSS for {SESSIONS} sessions",
+                process_status("Threads:") - threads_before,
+                process_status("VmRSS:").saturating_sub(rss_before)
+            );
+            // ^D ends each `cat`; masters stay open until their reader is done.
+            for (_, writer, _) in open.iter_mut() {
+                writer.write_all(&[4]).unwrap();
+            }
+            for _ in 0..SESSIONS {
+                done_rx.recv_timeout(Duration::from_secs(10)).unwrap();
+            }
+        };
+        measure("shared reader", false);
+        measure("per-session threads", true);
+    }
+}
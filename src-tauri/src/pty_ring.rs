@@ -0,0 +1,162 @@
+// Output buffer of sessions using the polling transport (`pty_read_chunk`):
+// the reader thread appends decoded output without taking a lock, and
+// readers take it in whole characters. What does not fit waits in an
+// overflow queue while the session's reading is paused, so nothing is lost
+// and a flooding session is slowed down instead.
+
+use std::cell::UnsafeCell;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+pub(crate) struct PtyRing {
+    buf: Box<[UnsafeCell<u8>]>,
+    // Bytes ever written and read; slots are these modulo the capacity.
+    head: AtomicUsize,
+    tail: AtomicUsize,
+    overflowed: AtomicBool,
+    overflow: Mutex<VecDeque<u8>>,
+    // Readers (several windows may poll) take turns; the writer never waits
+    // for them.
+    reader: Mutex<()>,
+}
+
+// SAFETY: the single writer only touches slots in [head, tail + capacity),
+// which no reader looks at, and publishes them with a Release store of
+// `head`; readers, one at a time under `reader`, only read slots in
+// [tail, head) and hand them back with a Release store of `tail`.
+unsafe impl Sync for PtyRing {}
+
+fn is_continuation(byte: u8) -> bool {
+    byte & 0xc0 == 0x80
+}
+
+impl PtyRing {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            buf: (0..capacity.max(1)).map(|_| UnsafeCell::new(0)).collect(),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            overflowed: AtomicBool::new(false),
+            overflow: Mutex::new(VecDeque::new()),
+            reader: Mutex::new(()),
+        }
+    }
+
+    fn slot(&self, pos: usize) -> *mut u8 {
+        self.buf[pos % self.buf.len()].get()
+    }
+
+    /// Append output. Only the session's reader thread may call this. False
+    /// when the ring was full and output went to the overflow: reading
+    /// should pause until `read` has caught up.
+    pub(crate) fn push(&self, data: &[u8]) -> bool {
+        let mut data = data;
+        if !self.overflowed.load(Ordering::Acquire) {
+            let head = self.head.load(Ordering::Relaxed);
+            let tail = self.tail.load(Ordering::Acquire);
+            let n = (self.buf.len() - (head - tail)).min(data.len());
+            for (i, byte) in data[..n].iter().enumerate() {
+                // SAFETY: see `impl Sync`; slot head + i is free.
+                unsafe { *self.slot(head + i) = *byte };
+            }
+            self.head.store(head + n, Ordering::Release);
+            if n == data.len() {
+                return true;
+            }
+            data = &data[n..];
+        }
+        let mut overflow = self.overflow.lock().unwrap_or_else(|e| e.into_inner());
+        overflow.extend(data);
+        self.overflowed.store(true, Ordering::Release);
+        false
+    }
+
+    /// Take up to `max_bytes` of the oldest output, never splitting a UTF-8
+    /// sequence; empty when there is none.
+    pub(crate) fn read(&self, max_bytes: usize) -> String {
+        let _reader = self.reader.lock().unwrap_or_else(|e| e.into_inner());
+        let tail = self.tail.load(Ordering::Relaxed);
+        let available = self.head.load(Ordering::Acquire) - tail;
+        let mut n = available.min(max_bytes);
+        // SAFETY (reads below): see `impl Sync`; slots before head are filled.
+        while n > 0 && n < available && is_continuation(unsafe { *self.slot(tail + n) }) {
+            n -= 1;
+        }
+        let mut out: Vec<u8> = (0..n).map(|i| unsafe { *self.slot(tail + i) }).collect();
+        self.tail.store(tail + n, Ordering::Release);
+
+        if out.len() < max_bytes && self.overflowed.load(Ordering::Acquire) {
+            let mut overflow = self.overflow.lock().unwrap_or_else(|e| e.into_inner());
+            // The overflow is newer than anything in the ring, so it is only
+            // read once the ring is empty.
+            if self.head.load(Ordering::Acquire) == tail + n {
+                let mut m = overflow.len().min(max_bytes - out.len());
+                while m > 0 && m < overflow.len() && is_continuation(overflow[m]) {
+                    m -= 1;
+                }
+                out.extend(overflow.drain(..m));
+                if overflow.is_empty() {
+                    self.overflowed.store(false, Ordering::Release);
+                }
+            }
+        }
+        String::from_utf8(out)
+            .unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned())
+    }
+
+    /// Whether output is waiting in the overflow, i.e. reading should stay
+    /// paused.
+    pub(crate) fn overflowed(&self) -> bool {
+        self.overflowed.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.head.load(Ordering::Acquire) == self.tail.load(Ordering::Acquire) && !self.overflowed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PtyRing;
+    use std::sync::Arc;
+
+    #[test]
+    fn overflows_in_order_and_keeps_characters_whole() {
+        let ring = PtyRing::new(8);
+        assert!(ring.push("héllo".as_bytes()));
+        assert!(!ring.push(" wörld".as_bytes()));
+        assert!(ring.overflowed());
+
+        // "é" is two bytes; a cut inside it is moved before it.
+        assert_eq!(ring.read(2), "h");
+        assert_eq!(ring.read(7), "éllo w");
+        assert_eq!(ring.read(2), "ö");
+        // Still behind the overflow, so it queues up there.
+        assert!(!ring.push(b"!"));
+        assert_eq!(ring.read(100), "rld!");
+        assert!(ring.is_empty());
+        assert_eq!(ring.read(100), "");
+    }
+
+    #[test]
+    fn concurrent_writer_and_reader_see_every_byte() {
+        let ring = Arc::new(PtyRing::new(64));
+        let expected: String = (0..5000).map(|i| format!("{i},")).collect();
+        let writer = {
+            let ring = ring.clone();
+            let expected = expected.clone();
+            std::thread::spawn(move || {
+                for chunk in expected.as_bytes().chunks(37) {
+                    ring.push(chunk);
+                }
+            })
+        };
+        let mut received = String::new();
+        while received.len() < expected.len() {
+            received.push_str(&ring.read(50));
+        }
+        writer.join().unwrap();
+        assert_eq!(received, expected);
+    }
+}
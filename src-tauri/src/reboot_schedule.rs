@@ -0,0 +1,311 @@
+use crate::host_probe::run_probe;
+use crate::host_store::{atomic_write, ensure_config_dir, get_config_dir, settings_load};
+use crate::models::Host;
+use crate::operations;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+const REBOOT_TIMEOUT: Duration = Duration::from_secs(60);
+/// Checked before a reboot unless settings or the call name other processes.
+const DEFAULT_CRITICAL_PROCESSES: &[&str] = &[
+    "apt",
+    "apt-get",
+    "dpkg",
+    "unattended-upgrades",
+    "yum",
+    "dnf",
+    "rpm",
+    "pacman",
+    "pg_dump",
+    "pg_basebackup",
+    "mysqldump",
+    "rsync",
+    "borg",
+    "restic",
+];
+// Linux cuts process names (`comm`) to this many bytes.
+const COMM_MAX_LEN: usize = 15;
+const REBOOT_MESSAGE: &str = "Reboot scheduled from xTermius";
+// Root runs shutdown directly; anyone else needs passwordless sudo, as a
+// password prompt would hang the probe.
+const AS_ROOT: &str = "if [ \"$(id -u)\" -eq 0 ]; then SUDO=; else SUDO='sudo -n'; fi";
+const PREFLIGHT_SCRIPT: &str = r#"who 2>/dev/null | awk '{print "user=" $1 " " $2}'
+ps -eo comm= 2>/dev/null | sort -u | awk '{print "proc=" $0}'"#;
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RebootPreflight {
+    /// Refuse while anyone is logged in (per `who`).
+    #[serde(default = "default_true")]
+    pub check_users: bool,
+    /// Refuse while one of these is running; the settings' list (or a
+    /// built-in one of package managers and backup tools) when unset.
+    #[serde(default)]
+    pub critical_processes: Option<Vec<String>>,
+}
+
+impl Default for RebootPreflight {
+    fn default() -> Self {
+        Self {
+            check_users: true,
+            critical_processes: None,
+        }
+    }
+}
+
+/// A reboot this app scheduled and has not seen cancelled.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingReboot {
+    pub host_id: String,
+    /// When the host goes down, to the minute `shutdown` works in.
+    pub at: String,
+    pub scheduled_at: String,
+}
+
+fn get_pending_reboots_path() -> PathBuf {
+    get_config_dir().join("pending_reboots.json")
+}
+
+fn pending_reboots_lock() -> &'static Mutex<()> {
+    static LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+    LOCK.get_or_init(|| Mutex::new(()))
+}
+
+/// Reboots still ahead; ones whose time has passed are dropped.
+fn load_pending(now: DateTime<Utc>) -> Result<Vec<PendingReboot>, String> {
+    let path = get_pending_reboots_path();
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    let pending: Vec<PendingReboot> = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+    Ok(pending
+        .into_iter()
+        .filter(|reboot| {
+            DateTime::parse_from_rfc3339(&reboot.at).is_ok_and(|at| at.with_timezone(&Utc) > now)
+        })
+        .collect())
+}
+
+fn save_pending(pending: &[PendingReboot]) -> Result<(), String> {
+    ensure_config_dir()?;
+    let content = serde_json::to_string_pretty(pending).map_err(|e| e.to_string())?;
+    atomic_write(&get_pending_reboots_path(), content.as_bytes())
+}
+
+/// Whole minutes from `now` until `at`, rounded up, as `shutdown -r +N`
+/// takes them.
+fn minutes_until(at: &str, now: DateTime<Utc>) -> Result<i64, String> {
+    let at = DateTime::parse_from_rfc3339(at.trim())
+        .map_err(|e| format!("Invalid reboot time: {e}"))?
+        .with_timezone(&Utc);
+    let seconds = (at - now).num_seconds();
+    if seconds <= 0 {
+        return Err("Reboot time must be in the future".to_string());
+    }
+    Ok((seconds + 59) / 60)
+}
+
+fn critical_process_running(running: &str, critical: &str) -> bool {
+    running == critical || (running.len() == COMM_MAX_LEN && critical.starts_with(running))
+}
+
+/// What stands in the way of a reboot: logged-in users and running critical
+/// processes, as readable reasons.
+fn preflight_blockers(stdout: &str, check_users: bool, critical: &[String]) -> Vec<String> {
+    let mut users = Vec::new();
+    let mut processes = Vec::new();
+    for line in stdout.lines() {
+        if let Some(user) = line.strip_prefix("user=") {
+            if check_users && !user.trim().is_empty() {
+                users.push(user.trim().to_string());
+            }
+        } else if let Some(running) = line.strip_prefix("proc=") {
+            let running = running.trim();
+            if let Some(name) = critical
+                .iter()
+                .find(|name| critical_process_running(running, name.trim()))
+            {
+                processes.push(name.trim().to_string());
+            }
+        }
+    }
+    let mut blockers = Vec::new();
+    if !users.is_empty() {
+        blockers.push(format!("logged in: {}", users.join(", ")));
+    }
+    if !processes.is_empty() {
+        blockers.push(format!("running: {}", processes.join(", ")));
+    }
+    blockers
+}
+
+fn critical_processes(preflight: &RebootPreflight) -> Result<Vec<String>, String> {
+    if let Some(processes) = &preflight.critical_processes {
+        return Ok(processes.clone());
+    }
+    Ok(settings_load()?
+        .reboot_critical_processes
+        .unwrap_or_else(|| {
+            DEFAULT_CRITICAL_PROCESSES
+                .iter()
+                .map(|name| name.to_string())
+                .collect()
+        }))
+}
+
+fn schedule_reboot(
+    host: &Host,
+    at: &str,
+    preflight: RebootPreflight,
+) -> Result<PendingReboot, String> {
+    let now = Utc::now();
+    let minutes = minutes_until(at, now)?;
+    let critical = critical_processes(&preflight)?;
+    let blockers = preflight_blockers(
+        &run_probe(host, PREFLIGHT_SCRIPT)?,
+        preflight.check_users,
+        &critical,
+    );
+    if !blockers.is_empty() {
+        return Err(format!("Reboot not scheduled; {}", blockers.join("; ")));
+    }
+    operations::check_current()?;
+    run_probe(
+        host,
+        &format!("{AS_ROOT}\n$SUDO shutdown -r +{minutes} '{REBOOT_MESSAGE}'"),
+    )?;
+
+    let reboot = PendingReboot {
+        host_id: host.id.clone(),
+        at: (now + chrono::Duration::minutes(minutes))
+            .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        scheduled_at: now.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+    };
+    let _guard = pending_reboots_lock()
+        .lock()
+        .map_err(|_| "pending reboots lock poisoned".to_string())?;
+    let mut pending = load_pending(now)?;
+    pending.retain(|existing| existing.host_id != host.id);
+    pending.push(reboot.clone());
+    save_pending(&pending)?;
+    Ok(reboot)
+}
+
+fn cancel_reboot(host: &Host) -> Result<(), String> {
+    run_probe(host, &format!("{AS_ROOT}\n$SUDO shutdown -c"))?;
+    let _guard = pending_reboots_lock()
+        .lock()
+        .map_err(|_| "pending reboots lock poisoned".to_string())?;
+    let mut pending = load_pending(Utc::now())?;
+    pending.retain(|reboot| reboot.host_id != host.id);
+    save_pending(&pending)
+}
+
+/// Schedule a reboot of the host at `at` (RFC 3339) with `shutdown -r`, after
+/// checking that nobody is logged in and no critical process is running.
+/// Fails, naming what is in the way, when a check does not pass.
+#[tauri::command]
+pub async fn host_schedule_reboot(
+    host: Host,
+    at: String,
+    preflight: Option<RebootPreflight>,
+    op_id: String,
+) -> Result<PendingReboot, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        operations::run(op_id, "host_schedule_reboot", REBOOT_TIMEOUT, || {
+            schedule_reboot(&host, &at, preflight.unwrap_or_default())
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Call off a scheduled reboot (`shutdown -c`) and forget it.
+#[tauri::command]
+pub async fn host_cancel_reboot(host: Host, op_id: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        operations::run(op_id, "host_cancel_reboot", REBOOT_TIMEOUT, || {
+            cancel_reboot(&host)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Reboots scheduled from this device that are still ahead.
+#[tauri::command]
+pub fn host_pending_reboots() -> Result<Vec<PendingReboot>, String> {
+    load_pending(Utc::now())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        cancel_reboot, load_pending, minutes_until, preflight_blockers, schedule_reboot,
+        RebootPreflight,
+    };
+    use crate::mock_backends::{mock_ssh, test_host, TestEnv};
+    use crate::models::Host;
+    use chrono::{DateTime, Utc};
+
+    #[test]
+    fn preflight_reports_users_and_critical_processes() {
+        let stdout = "user=alice pts/0\n\
+                      proc=bash\n\
+                      proc=unattended-upgr\n\
+                      proc=rsyncd\n";
+        let critical = vec!["unattended-upgrades".to_string(), "rsync".to_string()];
+        assert_eq!(
+            preflight_blockers(stdout, true, &critical),
+            vec!["logged in: alice pts/0", "running: unattended-upgrades"]
+        );
+        assert!(preflight_blockers(stdout, false, &[]).is_empty());
+
+        let now = DateTime::parse_from_rfc3339("2026-05-01T10:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(minutes_until("2026-05-01T10:00:30Z", now), Ok(1));
+        assert_eq!(minutes_until("2026-05-01T12:00:00+01:00", now), Ok(60));
+        assert!(minutes_until("2026-05-01T09:59:00Z", now).is_err());
+    }
+
+    #[test]
+    fn schedules_and_cancels_reboot() {
+        let _env = TestEnv::new();
+        let host = Host {
+            hostname: "db.example.com".to_string(),
+            ..test_host(&uuid::Uuid::new_v4().to_string())
+        };
+        mock_ssh(&host.id, |script| {
+            if script.contains("who") {
+                Ok("proc=sshd\nproc=postgres\n".to_string())
+            } else {
+                Ok(String::new())
+            }
+        });
+        let at = (Utc::now() + chrono::Duration::minutes(30)).to_rfc3339();
+
+        let blocked = RebootPreflight {
+            check_users: true,
+            critical_processes: Some(vec!["postgres".to_string()]),
+        };
+        let err = schedule_reboot(&host, &at, blocked).unwrap_err();
+        assert!(err.contains("running: postgres"), "{err}");
+        assert!(load_pending(Utc::now()).unwrap().is_empty());
+
+        let reboot = schedule_reboot(&host, &at, RebootPreflight::default()).unwrap();
+        assert_eq!(load_pending(Utc::now()).unwrap(), vec![reboot]);
+        cancel_reboot(&host).unwrap();
+        assert!(load_pending(Utc::now()).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,326 @@
+// One-off remote commands over an SSH exec channel (no PTY), with stdout,
+// stderr and the exit code kept apart, for snippets, health checks and
+// scripting. They share the host's ControlMaster connection, so a command
+// costs a channel rather than a login.
+
+use crate::host_probe::{probe_failure_message, ssh_exec_capped};
+use crate::models::Host;
+use crate::operations::{self, OutputTruncation};
+use serde::Serialize;
+use std::process::Output;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Runtime};
+
+const MAIN_WINDOW_LABEL: &str = "main";
+const DEFAULT_EXEC_TIMEOUT_SECS: u64 = 60;
+// For a whole batch, however many hosts it has.
+const DEFAULT_BATCH_TIMEOUT_SECS: u64 = 10 * 60;
+const MAX_EXEC_TIMEOUT_SECS: u64 = 60 * 60;
+const DEFAULT_BATCH_CONCURRENCY: usize = 8;
+const MAX_BATCH_CONCURRENCY: usize = 32;
+// ssh's own failures (unreachable, auth, dropped link).
+const SSH_FAILURE_EXIT_CODE: i32 = 255;
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SshExecResult {
+    pub host_id: String,
+    pub stdout: String,
+    pub stderr: String,
+    /// None when the command was killed by a signal.
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+    /// Set when stdout or stderr was cut at the output cap.
+    pub truncated: Option<OutputTruncation>,
+}
+
+/// One host's row of `ssh_exec_batch`: its result, or why the command could
+/// not run there.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SshExecBatchEntry {
+    pub host_id: String,
+    pub result: Option<SshExecResult>,
+    pub error: Option<String>,
+}
+
+/// An `ssh-exec:host-done` event, sent as each host of a batch finishes.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SshExecHostDonePayload {
+    pub op_id: String,
+    pub entry: SshExecBatchEntry,
+    pub done: u64,
+    pub total: u64,
+}
+
+fn exec_result(
+    host_id: &str,
+    output: Output,
+    truncated: Option<OutputTruncation>,
+    elapsed: Duration,
+) -> Result<SshExecResult, String> {
+    if output.status.code() == Some(SSH_FAILURE_EXIT_CODE) {
+        return Err(probe_failure_message(&output));
+    }
+    Ok(SshExecResult {
+        host_id: host_id.to_string(),
+        stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        exit_code: output.status.code(),
+        duration_ms: elapsed.as_millis().min(u64::MAX as u128) as u64,
+        truncated,
+    })
+}
+
+fn exec_on_host(host: &Host, command: &str) -> Result<SshExecResult, String> {
+    let started = Instant::now();
+    let (output, truncated) = ssh_exec_capped(host, command)?;
+    exec_result(&host.id, output, truncated, started.elapsed())
+}
+
+fn find_host<'a>(hosts: &'a [Host], host_id: &str) -> Result<&'a Host, String> {
+    hosts
+        .iter()
+        .find(|host| host.id == host_id.trim() && !host.deleted)
+        .ok_or_else(|| "Host not found".to_string())
+}
+
+fn exec_timeout(timeout_secs: Option<u64>, default_secs: u64) -> Duration {
+    Duration::from_secs(
+        timeout_secs
+            .unwrap_or(default_secs)
+            .clamp(1, MAX_EXEC_TIMEOUT_SECS),
+    )
+}
+
+fn batch_entry(host_id: &str, outcome: Result<SshExecResult, String>) -> SshExecBatchEntry {
+    let (result, error) = match outcome {
+        Ok(result) => (Some(result), None),
+        Err(error) => (None, Some(error)),
+    };
+    SshExecBatchEntry {
+        host_id: host_id.to_string(),
+        result,
+        error,
+    }
+}
+
+/// Run `exec` for every host with at most `concurrency` at a time, calling
+/// `on_done` with each result and the number finished so far. Results keep
+/// the order of `host_ids`; hosts not started before the current operation
+/// was cancelled are left out.
+pub(crate) fn run_batch<T: Send>(
+    host_ids: &[String],
+    concurrency: usize,
+    exec: impl Fn(&str) -> T + Sync,
+    on_done: impl Fn(&T, u64) + Sync,
+) -> Vec<T> {
+    // Workers share the command's operation, so one cancel stops every host.
+    let op = operations::current();
+    let next = AtomicUsize::new(0);
+    let done = AtomicUsize::new(0);
+    let entries: Mutex<Vec<(usize, T)>> = Mutex::new(Vec::new());
+    thread::scope(|scope| {
+        for _ in 0..concurrency.clamp(1, host_ids.len().max(1)) {
+            scope.spawn(|| {
+                operations::enter(op.as_ref(), || loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(host_id) = host_ids.get(index) else {
+                        break;
+                    };
+                    if operations::check_current().is_err() {
+                        break;
+                    }
+                    let entry = exec(host_id);
+                    on_done(&entry, done.fetch_add(1, Ordering::Relaxed) as u64 + 1);
+                    if let Ok(mut entries) = entries.lock() {
+                        entries.push((index, entry));
+                    }
+                })
+            });
+        }
+    });
+    let mut entries = entries.into_inner().unwrap_or_else(|e| e.into_inner());
+    entries.sort_by_key(|(index, _)| *index);
+    entries.into_iter().map(|(_, entry)| entry).collect()
+}
+
+/// Run `command` on a host without a terminal; the remote login shell
+/// interprets it. A non-zero exit is a result, not an error; failing to
+/// connect is. Killed after `timeout_secs` (60 by default) or when `op_id`
+/// is cancelled; each stream is capped at `max_output_kb` (1 MiB by
+/// default).
+#[tauri::command]
+pub async fn ssh_exec(
+    host_id: String,
+    command: String,
+    timeout_secs: Option<u64>,
+    op_id: String,
+    max_output_kb: Option<u64>,
+) -> Result<SshExecResult, String> {
+    if command.trim().is_empty() {
+        return Err("command is required".to_string());
+    }
+    let timeout = exec_timeout(timeout_secs, DEFAULT_EXEC_TIMEOUT_SECS);
+    tauri::async_runtime::spawn_blocking(move || {
+        operations::run(op_id, "ssh_exec", timeout, || {
+            if let Some(kb) = max_output_kb {
+                operations::set_output_cap(kb.saturating_mul(1024) as usize);
+            }
+            let hosts = crate::host_store::hosts_load()?;
+            exec_on_host(find_host(&hosts, &host_id)?, &command)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// `ssh_exec` on many hosts, `concurrency` (8 by default) at a time. Each
+/// finished host is sent as `ssh-exec:host-done` and counted in the job's
+/// progress; the result has a row per host, in the order given.
+/// `timeout_secs` bounds the whole batch (10 minutes by default).
+#[tauri::command]
+pub async fn ssh_exec_batch<R: Runtime>(
+    host_ids: Vec<String>,
+    command: String,
+    concurrency: Option<usize>,
+    timeout_secs: Option<u64>,
+    op_id: String,
+    app: AppHandle<R>,
+) -> Result<Vec<SshExecBatchEntry>, String> {
+    if command.trim().is_empty() {
+        return Err("command is required".to_string());
+    }
+    let timeout = exec_timeout(timeout_secs, DEFAULT_BATCH_TIMEOUT_SECS);
+    let concurrency = concurrency
+        .unwrap_or(DEFAULT_BATCH_CONCURRENCY)
+        .clamp(1, MAX_BATCH_CONCURRENCY);
+    tauri::async_runtime::spawn_blocking(move || {
+        operations::run(op_id, "ssh_exec_batch", timeout, || {
+            let op_id = operations::current()
+                .map(|op| op.id().to_string())
+                .unwrap_or_default();
+            let hosts = crate::host_store::hosts_load()?;
+            let total = host_ids.len() as u64;
+            let entries = run_batch(
+                &host_ids,
+                concurrency,
+                |host_id| {
+                    let outcome =
+                        find_host(&hosts, host_id).and_then(|host| exec_on_host(host, &command));
+                    batch_entry(host_id, outcome)
+                },
+                |entry, done| {
+                    operations::report_progress(done, Some(total), Some(&entry.host_id));
+                    let _ = app.emit_to(
+                        MAIN_WINDOW_LABEL,
+                        "ssh-exec:host-done",
+                        SshExecHostDonePayload {
+                            op_id: op_id.clone(),
+                            entry: entry.clone(),
+                            done,
+                            total,
+                        },
+                    );
+                },
+            );
+            // A cancelled batch would otherwise list the hosts it never
+            // reached as missing rather than report the cancel.
+            operations::check_current()?;
+            Ok(entries)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::{batch_entry, exec_result, run_batch};
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::{ExitStatus, Output};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    fn output(raw_status: i32, stdout: &str, stderr: &str) -> Output {
+        Output {
+            status: ExitStatus::from_raw(raw_status),
+            stdout: stdout.as_bytes().to_vec(),
+            stderr: stderr.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn keeps_streams_apart_and_fails_on_ssh_errors() {
+        let result = exec_result(
+            "web",
+            output(2 << 8, "partial\n", "grep: x: No such file\n"),
+            None,
+            Duration::from_millis(1500),
+        )
+        .unwrap();
+        assert_eq!(result.exit_code, Some(2));
+        assert_eq!(result.stdout, "partial\n");
+        assert_eq!(result.stderr, "grep: x: No such file\n");
+        assert_eq!(result.duration_ms, 1500);
+
+        // Killed by SIGKILL.
+        let killed = exec_result("web", output(9, "", ""), None, Duration::ZERO).unwrap();
+        assert_eq!(killed.exit_code, None);
+
+        let error = exec_result(
+            "web",
+            output(
+                255 << 8,
+                "",
+                "ssh: connect to host web port 22: Connection refused\n",
+            ),
+            None,
+            Duration::ZERO,
+        )
+        .unwrap_err();
+        assert!(error.contains("Connection refused"));
+    }
+
+    #[test]
+    fn batch_keeps_host_order_and_bounds_concurrency() {
+        let host_ids: Vec<String> = (0..10).map(|i| format!("h{i}")).collect();
+        let active = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        let reported = Mutex::new(Vec::new());
+        let entries = run_batch(
+            &host_ids,
+            3,
+            |host_id| {
+                let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                // Later hosts finish first.
+                let index: u64 = host_id[1..].parse().unwrap();
+                std::thread::sleep(Duration::from_millis(30 - 2 * index));
+                active.fetch_sub(1, Ordering::SeqCst);
+                let outcome = if host_id == "h4" {
+                    Err("Host not found".to_string())
+                } else {
+                    exec_result(host_id, output(0, host_id, ""), None, Duration::ZERO)
+                };
+                batch_entry(host_id, outcome)
+            },
+            |_, done| reported.lock().unwrap().push(done),
+        );
+
+        let ids: Vec<&str> = entries.iter().map(|e| e.host_id.as_str()).collect();
+        assert_eq!(ids, host_ids.iter().map(String::as_str).collect::<Vec<_>>());
+        assert_eq!(peak.load(Ordering::SeqCst), 3);
+        assert_eq!(entries[4].error.as_deref(), Some("Host not found"));
+        let h7 = entries[7].result.as_ref().unwrap();
+        assert_eq!(h7.stdout, "h7");
+        let mut reported = reported.into_inner().unwrap();
+        reported.sort();
+        assert_eq!(reported, (1..=10).collect::<Vec<u64>>());
+    }
+}
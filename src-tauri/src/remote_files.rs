@@ -0,0 +1,481 @@
+use crate::host_probe::{run_probe, shell_quote};
+use crate::models::Host;
+use crate::operations;
+use serde::Serialize;
+use std::time::Duration;
+
+const REMOTE_TRASH_DIR: &str = ".xterm-trash";
+const DEFAULT_TRASH_TTL_DAYS: u32 = 30;
+// Archiving or deleting a large tree can legitimately take a while.
+const REMOTE_FILE_OP_TIMEOUT: Duration = Duration::from_secs(3600);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArchiveFormat {
+    TarGz,
+    Tar,
+    TarBz2,
+    TarXz,
+    Zip,
+}
+
+impl ArchiveFormat {
+    fn parse(format: &str) -> Result<Self, String> {
+        match format.trim().to_ascii_lowercase().as_str() {
+            "tar.gz" | "tgz" | "" => Ok(Self::TarGz),
+            "tar" => Ok(Self::Tar),
+            "tar.bz2" | "tbz2" => Ok(Self::TarBz2),
+            "tar.xz" | "txz" => Ok(Self::TarXz),
+            "zip" => Ok(Self::Zip),
+            other => Err(format!("Unsupported archive format: {other}")),
+        }
+    }
+
+    fn for_path(path: &str) -> Result<Self, String> {
+        let lower = path.trim().to_ascii_lowercase();
+        [
+            (".tar.gz", Self::TarGz),
+            (".tgz", Self::TarGz),
+            (".tar.bz2", Self::TarBz2),
+            (".tbz2", Self::TarBz2),
+            (".tar.xz", Self::TarXz),
+            (".txz", Self::TarXz),
+            (".tar", Self::Tar),
+            (".zip", Self::Zip),
+        ]
+        .into_iter()
+        .find(|(suffix, _)| lower.ends_with(suffix))
+        .map(|(_, format)| format)
+        .ok_or_else(|| format!("Cannot tell the archive format of {path}"))
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::TarGz => "tar.gz",
+            Self::Tar => "tar",
+            Self::TarBz2 => "tar.bz2",
+            Self::TarXz => "tar.xz",
+            Self::Zip => "zip",
+        }
+    }
+
+    fn required_tool(self) -> &'static str {
+        match self {
+            Self::Zip => "zip",
+            _ => "tar",
+        }
+    }
+
+    fn tar_flag(self) -> &'static str {
+        match self {
+            Self::TarGz => "z",
+            Self::TarBz2 => "j",
+            Self::TarXz => "J",
+            Self::Tar | Self::Zip => "",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteArchiveResult {
+    pub archive_path: String,
+    pub size_bytes: Option<u64>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteTrashEntry {
+    pub id: String,
+    pub original_path: String,
+    pub trashed_at: Option<String>,
+}
+
+/// Quote a remote path for `sh`, keeping a leading `~/` expandable.
+pub(crate) fn remote_path_arg(path: &str) -> Result<String, String> {
+    let path = path.trim();
+    if path.is_empty() {
+        return Err("Remote path is required".to_string());
+    }
+    if path.chars().any(char::is_control) {
+        return Err(format!(
+            "Remote path contains unsupported control characters: {path:?}"
+        ));
+    }
+    if path == "~" {
+        return Ok("\"$HOME\"".to_string());
+    }
+    if let Some(rest) = path.strip_prefix("~/") {
+        return Ok(format!("\"$HOME\"/{}", shell_quote(rest)));
+    }
+    Ok(shell_quote(path))
+}
+
+fn require_tool_snippet(tool: &str) -> String {
+    format!(
+        "command -v {tool} >/dev/null 2>&1 || {{ echo '{tool} is not installed on the remote host' >&2; exit 127; }}\n"
+    )
+}
+
+fn archive_script(paths: &[String], format: ArchiveFormat) -> Result<String, String> {
+    if paths.is_empty() {
+        return Err("At least one remote path is required".to_string());
+    }
+    let args = paths
+        .iter()
+        .map(|path| remote_path_arg(path))
+        .collect::<Result<Vec<_>, _>>()?
+        .join(" ");
+    let mut script = String::from("set -eu\n");
+    script.push_str(&require_tool_snippet(format.required_tool()));
+    script.push_str("DIR=\"$(mktemp -d \"${TMPDIR:-/tmp}/xtermius-archive.XXXXXX\")\"\n");
+    script.push_str(&format!(
+        "ARCHIVE=\"$DIR/archive.{}\"\n",
+        format.extension()
+    ));
+    if format == ArchiveFormat::Zip {
+        script.push_str(&format!("zip -qr \"$ARCHIVE\" {args}\n"));
+    } else {
+        script.push_str(&format!(
+            "tar -c{}f \"$ARCHIVE\" -- {args}\n",
+            format.tar_flag()
+        ));
+    }
+    script.push_str("printf 'archive=%s\\n' \"$ARCHIVE\"\n");
+    script.push_str("printf 'size=%s\\n' \"$(wc -c < \"$ARCHIVE\" | tr -d ' ')\"\n");
+    Ok(script)
+}
+
+fn extract_script(archive: &str, dest: &str) -> Result<String, String> {
+    let format = ArchiveFormat::for_path(archive)?;
+    let archive = remote_path_arg(archive)?;
+    let dest = remote_path_arg(dest)?;
+    let tool = match format {
+        ArchiveFormat::Zip => "unzip",
+        _ => "tar",
+    };
+    let mut script = String::from("set -eu\n");
+    script.push_str(&require_tool_snippet(tool));
+    script.push_str(&format!("mkdir -p -- {dest}\n"));
+    if format == ArchiveFormat::Zip {
+        script.push_str(&format!("unzip -oq {archive} -d {dest}\n"));
+    } else {
+        script.push_str(&format!(
+            "tar -x{}f {archive} -C {dest}\n",
+            format.tar_flag()
+        ));
+    }
+    Ok(script)
+}
+
+fn remote_archive_impl(
+    host: Host,
+    paths: Vec<String>,
+    format: String,
+) -> Result<RemoteArchiveResult, String> {
+    let script = archive_script(&paths, ArchiveFormat::parse(&format)?)?;
+    let stdout = run_probe(&host, &script)?;
+    let mut archive_path = None;
+    let mut size_bytes = None;
+    for line in stdout.lines() {
+        if let Some(path) = line.strip_prefix("archive=") {
+            archive_path = Some(path.trim().to_string());
+        } else if let Some(size) = line.strip_prefix("size=") {
+            size_bytes = size.trim().parse::<u64>().ok();
+        }
+    }
+    Ok(RemoteArchiveResult {
+        archive_path: archive_path.ok_or("Remote archive path was not reported")?,
+        size_bytes,
+    })
+}
+
+/// Pack remote paths into a single archive in a fresh remote temp directory,
+/// so a directory download is one transfer instead of one per file.
+#[tauri::command]
+pub async fn remote_archive(
+    host: Host,
+    paths: Vec<String>,
+    format: String,
+    op_id: String,
+) -> Result<RemoteArchiveResult, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        operations::run(op_id, "remote_archive", REMOTE_FILE_OP_TIMEOUT, || {
+            remote_archive_impl(host, paths, format)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn remote_extract(
+    host: Host,
+    archive: String,
+    dest: String,
+    op_id: String,
+) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let script = extract_script(&archive, &dest)?;
+        operations::run(op_id, "remote_extract", REMOTE_FILE_OP_TIMEOUT, || {
+            run_probe(&host, &script).map(|_| ())
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+fn reject_dangerous_delete_target(path: &str) -> Result<(), String> {
+    let normalized = path.trim().trim_end_matches('/');
+    if matches!(normalized, "" | "~" | "." | ".." | "$HOME") {
+        return Err(format!("Refusing to delete {path:?}"));
+    }
+    Ok(())
+}
+
+fn valid_trash_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '-')
+}
+
+fn trash_prelude() -> String {
+    let mut script = String::from("set -eu\n");
+    script.push_str(&format!("TRASH=\"$HOME/{REMOTE_TRASH_DIR}\"\n"));
+    script.push_str("mkdir -p \"$TRASH\"\nchmod 700 \"$TRASH\"\n");
+    script
+}
+
+// Each entry keeps the TTL it was trashed with in `.ttl`; entries from before
+// that was recorded get the default.
+fn purge_expired_trash_script() -> String {
+    format!(
+        r#"for DIR in "$TRASH"/*/; do
+  [ -d "$DIR" ] || continue
+  KEEP="$(head -n 1 "$DIR.ttl" 2>/dev/null || true)"
+  case "$KEEP" in ''|*[!0-9]*) KEEP={DEFAULT_TRASH_TTL_DAYS} ;; esac
+  if [ -n "$(find "$DIR" -maxdepth 0 -mtime +"$KEEP" 2>/dev/null)" ]; then
+    rm -rf -- "$DIR"
+  fi
+done
+"#
+    )
+}
+
+fn delete_script(paths: &[String], use_trash: bool, ttl_days: u32) -> Result<String, String> {
+    if paths.is_empty() {
+        return Err("At least one remote path is required".to_string());
+    }
+    let mut args = Vec::with_capacity(paths.len());
+    for path in paths {
+        reject_dangerous_delete_target(path)?;
+        args.push(remote_path_arg(path)?);
+    }
+    if !use_trash {
+        return Ok(format!("set -eu\nrm -rf -- {}\n", args.join(" ")));
+    }
+    let mut script = trash_prelude();
+    // Expire old entries opportunistically whenever something is trashed.
+    script.push_str(&purge_expired_trash_script());
+    script.push_str(&format!("TTL_DAYS={ttl_days}\n"));
+    script.push_str(
+        r#"STAMP="$(date -u +%Y%m%d%H%M%S)"
+N=0
+trash_one() {
+  SRC="$1"
+  if [ ! -e "$SRC" ] && [ ! -L "$SRC" ]; then
+    echo "No such file or directory: $SRC" >&2
+    exit 1
+  fi
+  case "$SRC" in
+    /*) ORIGIN="$SRC" ;;
+    *) ORIGIN="$(pwd)/$SRC" ;;
+  esac
+  N=$((N + 1))
+  ID="$STAMP-$$-$N"
+  mkdir "$TRASH/$ID"
+  mv -- "$SRC" "$TRASH/$ID/item"
+  printf '%s\n' "$ORIGIN" > "$TRASH/$ID/.origin"
+  printf '%s\n' "$TTL_DAYS" > "$TRASH/$ID/.ttl"
+  printf 'entry=%s|%s\n' "$ID" "$ORIGIN"
+}
+"#,
+    );
+    for arg in args {
+        script.push_str(&format!("trash_one {arg}\n"));
+    }
+    Ok(script)
+}
+
+fn trash_list_script() -> String {
+    // Listing never purges: expiry follows each entry's own TTL on delete.
+    let mut script = trash_prelude();
+    script.push_str(
+        r#"for DIR in "$TRASH"/*/; do
+  [ -f "$DIR.origin" ] || continue
+  printf 'entry=%s|%s\n' "$(basename "$DIR")" "$(head -n 1 "$DIR.origin")"
+done
+"#,
+    );
+    script
+}
+
+fn restore_script(id: &str) -> Result<String, String> {
+    if !valid_trash_id(id) {
+        return Err(format!("Invalid trash entry id: {id}"));
+    }
+    let mut script = String::from("set -eu\n");
+    script.push_str(&format!("ENTRY=\"$HOME/{REMOTE_TRASH_DIR}/{id}\"\n"));
+    script.push_str(
+        r#"[ -f "$ENTRY/.origin" ] || { echo "Trash entry not found" >&2; exit 1; }
+ORIGIN="$(head -n 1 "$ENTRY/.origin")"
+if [ -e "$ORIGIN" ] || [ -L "$ORIGIN" ]; then
+  echo "Cannot restore: $ORIGIN already exists" >&2
+  exit 1
+fi
+mkdir -p -- "$(dirname -- "$ORIGIN")"
+mv -- "$ENTRY/item" "$ORIGIN"
+rm -rf -- "$ENTRY"
+printf 'restored=%s\n' "$ORIGIN"
+"#,
+    );
+    Ok(script)
+}
+
+fn parse_trash_entries(stdout: &str) -> Vec<RemoteTrashEntry> {
+    stdout
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("entry="))
+        .filter_map(|rest| rest.split_once('|'))
+        .map(|(id, original_path)| RemoteTrashEntry {
+            id: id.to_string(),
+            original_path: original_path.to_string(),
+            // Ids start with the UTC time the entry was trashed.
+            trashed_at: id
+                .get(..14)
+                .and_then(|stamp| chrono::NaiveDateTime::parse_from_str(stamp, "%Y%m%d%H%M%S").ok())
+                .map(|at| at.and_utc().to_rfc3339()),
+        })
+        .collect()
+}
+
+/// Delete remote paths. With `use_trash` they are moved into `~/.xterm-trash`
+/// and can be restored; each is kept for `trash_ttl_days` and purged by a
+/// later delete once that has passed.
+#[tauri::command]
+pub async fn remote_delete(
+    host: Host,
+    paths: Vec<String>,
+    use_trash: bool,
+    trash_ttl_days: Option<u32>,
+    op_id: String,
+) -> Result<Vec<RemoteTrashEntry>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let ttl_days = trash_ttl_days.unwrap_or(DEFAULT_TRASH_TTL_DAYS);
+        let script = delete_script(&paths, use_trash, ttl_days)?;
+        operations::run(op_id, "remote_delete", REMOTE_FILE_OP_TIMEOUT, || {
+            run_probe(&host, &script).map(|stdout| parse_trash_entries(&stdout))
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn remote_trash_list(host: Host) -> Result<Vec<RemoteTrashEntry>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        run_probe(&host, &trash_list_script()).map(|stdout| parse_trash_entries(&stdout))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn remote_trash_restore(host: Host, id: String) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let stdout = run_probe(&host, &restore_script(id.trim())?)?;
+        stdout
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("restored="))
+            .map(str::to_string)
+            .ok_or_else(|| "Remote restore did not report a path".to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        archive_script, delete_script, extract_script, parse_trash_entries, remote_path_arg,
+        restore_script, trash_list_script, ArchiveFormat, RemoteTrashEntry,
+    };
+
+    #[test]
+    fn remote_paths_are_quoted_but_keep_home_expansion() {
+        assert_eq!(remote_path_arg("/var/www").unwrap(), "'/var/www'");
+        assert_eq!(
+            remote_path_arg("~/it's here").unwrap(),
+            "\"$HOME\"/'it'\\''s here'"
+        );
+        assert!(remote_path_arg("/tmp/x\n rm -rf /").is_err());
+        assert!(remote_path_arg("  ").is_err());
+    }
+
+    #[test]
+    fn archive_format_detection() {
+        assert_eq!(
+            ArchiveFormat::for_path("/tmp/a.TGZ").unwrap(),
+            ArchiveFormat::TarGz
+        );
+        assert_eq!(
+            ArchiveFormat::for_path("site.tar.xz").unwrap(),
+            ArchiveFormat::TarXz
+        );
+        assert_eq!(
+            ArchiveFormat::for_path("x.zip").unwrap(),
+            ArchiveFormat::Zip
+        );
+        assert!(ArchiveFormat::for_path("x.rar").is_err());
+        assert!(ArchiveFormat::parse("7z").is_err());
+    }
+
+    #[test]
+    fn builds_archive_and_extract_scripts() {
+        let script = archive_script(&["/var/www".to_string()], ArchiveFormat::TarGz).unwrap();
+        assert!(script.contains("tar -czf \"$ARCHIVE\" -- '/var/www'"));
+        assert!(archive_script(&[], ArchiveFormat::Zip).is_err());
+
+        let script = extract_script("/tmp/site.zip", "~/site").unwrap();
+        assert!(script.contains("unzip -oq '/tmp/site.zip' -d \"$HOME\"/'site'"));
+    }
+
+    #[test]
+    fn delete_moves_to_trash_and_refuses_home() {
+        let script = delete_script(&["/var/www/old".to_string()], true, 7).unwrap();
+        assert!(script.contains("TTL_DAYS=7"));
+        assert!(script.contains("-mtime +\"$KEEP\""));
+        assert!(script.contains("trash_one '/var/www/old'"));
+        assert!(!script.contains("rm -rf -- '/var/www/old'"));
+
+        let script = delete_script(&["/var/www/old".to_string()], false, 7).unwrap();
+        assert!(script.contains("rm -rf -- '/var/www/old'"));
+
+        assert!(delete_script(&["~/".to_string()], false, 7).is_err());
+        assert!(delete_script(&["/".to_string()], true, 7).is_err());
+        assert!(restore_script("../../etc").is_err());
+
+        // Opening the trash must not expire entries kept for longer.
+        assert!(!trash_list_script().contains("rm -rf"));
+    }
+
+    #[test]
+    fn parses_trash_entries() {
+        let entries = parse_trash_entries("entry=20260102030405-42-1|/srv/app/config.yml\nnoise\n");
+        assert_eq!(
+            entries,
+            vec![RemoteTrashEntry {
+                id: "20260102030405-42-1".to_string(),
+                original_path: "/srv/app/config.yml".to_string(),
+                trashed_at: Some("2026-01-02T03:04:05+00:00".to_string()),
+            }]
+        );
+    }
+}
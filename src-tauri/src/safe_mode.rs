@@ -0,0 +1,135 @@
+use crate::host_store::{atomic_write, get_config_dir, get_hosts_db_path, hosts_db_snapshot_bytes};
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+const SAFE_MODE_ENV: &str = "XTERMIUS_SAFE_MODE";
+const SAFE_MODE_FLAG: &str = "--safe-mode";
+// What safe mode turns off, for the diagnostics screen.
+const SKIPPED_SERVICES: &[&str] = &[
+    "legacy password and hosts.json migrations",
+    "WebDAV sync",
+    "host monitoring probes",
+];
+
+/// Whether this launch runs in safe mode, and what enabled it.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SafeModeStatus {
+    pub enabled: bool,
+    pub source: Option<&'static str>,
+    pub skipped: Vec<&'static str>,
+    pub config_dir: String,
+}
+
+fn safe_mode_source() -> &'static OnceLock<Option<&'static str>> {
+    static SOURCE: OnceLock<Option<&'static str>> = OnceLock::new();
+    &SOURCE
+}
+
+fn env_enabled(value: Option<&str>) -> bool {
+    matches!(
+        value.map(|v| v.trim().to_ascii_lowercase()).as_deref(),
+        Some("1" | "true" | "yes" | "on")
+    )
+}
+
+fn detect(args: &[String], env: Option<&str>) -> Option<&'static str> {
+    if args.iter().skip(1).any(|arg| arg == SAFE_MODE_FLAG) {
+        Some("flag")
+    } else if env_enabled(env) {
+        Some("env")
+    } else {
+        None
+    }
+}
+
+/// Decide once, at launch, whether to run in safe mode (`--safe-mode` or
+/// `XTERMIUS_SAFE_MODE=1`).
+pub(crate) fn init() {
+    let args: Vec<String> = std::env::args().collect();
+    let env = std::env::var(SAFE_MODE_ENV).ok();
+    let source = *safe_mode_source().get_or_init(|| detect(&args, env.as_deref()));
+    if let Some(source) = source {
+        eprintln!(
+            "[safe-mode] enabled by {source}; skipping legacy migrations and background services"
+        );
+    }
+}
+
+pub(crate) fn is_enabled() -> bool {
+    safe_mode_source().get().copied().flatten().is_some()
+}
+
+/// `Err` for work safe mode turns off; the caller names the feature.
+pub(crate) fn ensure_not_safe_mode(feature: &str) -> Result<(), String> {
+    if is_enabled() {
+        return Err(format!("{feature} is disabled in safe mode"));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn safe_mode_status() -> SafeModeStatus {
+    let source = safe_mode_source().get().copied().flatten();
+    SafeModeStatus {
+        enabled: source.is_some(),
+        source,
+        skipped: if source.is_some() {
+            SKIPPED_SERVICES.to_vec()
+        } else {
+            Vec::new()
+        },
+        config_dir: get_config_dir().to_string_lossy().to_string(),
+    }
+}
+
+/// Copy the hosts DB (as a consistent snapshot) and the JSON config files
+/// into a new folder under `dest_dir`, so data can be rescued before a
+/// repair. Returns the folder. Works in and outside of safe mode.
+#[tauri::command]
+pub async fn safe_mode_export(dest_dir: String) -> Result<String, String> {
+    let dest_dir = PathBuf::from(dest_dir.trim());
+    if !dest_dir.is_dir() {
+        return Err(format!("{} is not a folder", dest_dir.display()));
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        let timestamp = chrono::Utc::now().format("%Y%m%d-%H%M%S");
+        let target = dest_dir.join(format!("xtermius-export-{timestamp}"));
+        fs::create_dir(&target).map_err(|e| format!("{}: {e}", target.display()))?;
+        if get_hosts_db_path().exists() {
+            atomic_write(&target.join("hosts.db"), &hosts_db_snapshot_bytes(&[])?)?;
+        }
+        for entry in fs::read_dir(get_config_dir()).map_err(|e| e.to_string())? {
+            let path = entry.map_err(|e| e.to_string())?.path();
+            if path.is_file() && path.extension().and_then(|ext| ext.to_str()) == Some("json") {
+                if let Some(name) = path.file_name() {
+                    let content = fs::read(&path).map_err(|e| e.to_string())?;
+                    atomic_write(&target.join(name), &content)?;
+                }
+            }
+        }
+        Ok(target.to_string_lossy().to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::detect;
+
+    #[test]
+    fn detects_flag_and_env() {
+        let args = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            detect(&args(&["xtermius", "--safe-mode"]), None),
+            Some("flag")
+        );
+        assert_eq!(detect(&args(&["xtermius"]), Some(" TRUE ")), Some("env"));
+        assert_eq!(detect(&args(&["xtermius"]), Some("0")), None);
+        // The binary path itself never counts as the flag.
+        assert_eq!(detect(&args(&["--safe-mode"]), None), None);
+    }
+}
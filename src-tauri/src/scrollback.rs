@@ -0,0 +1,423 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex, Once, OnceLock, Weak};
+use std::thread;
+
+const DEFAULT_MEMORY_CAP_KB: u64 = 1024;
+const MIN_MEMORY_CAP_KB: u64 = 64;
+const MAX_MEMORY_CAP_KB: u64 = 256 * 1024;
+// Per session; once full the oldest spilled output is overwritten.
+const DEFAULT_SPILL_MB: u64 = 64;
+const MAX_SPILL_MB: u64 = 4096;
+const SPILL_DIR_NAME: &str = "scrollback";
+const SPILL_FILE_EXTENSION: &str = "ring";
+
+// 0 until the settings were read once.
+static MEMORY_CAP_BYTES: AtomicUsize = AtomicUsize::new(0);
+// u64::MAX until the settings were read once; 0 turns spilling off.
+static SPILL_CAP_BYTES: AtomicU64 = AtomicU64::new(u64::MAX);
+
+fn cap_from_kb(kb: Option<u64>) -> usize {
+    let kb = kb
+        .unwrap_or(DEFAULT_MEMORY_CAP_KB)
+        .clamp(MIN_MEMORY_CAP_KB, MAX_MEMORY_CAP_KB);
+    (kb * 1024) as usize
+}
+
+/// Memory cap for new sessions, from `scrollback_memory_kb` in settings.
+pub(crate) fn memory_cap() -> usize {
+    let cached = MEMORY_CAP_BYTES.load(Ordering::Relaxed);
+    if cached != 0 {
+        return cached;
+    }
+    let kb = crate::host_store::settings_load()
+        .ok()
+        .and_then(|settings| settings.scrollback_memory_kb);
+    set_memory_cap_kb(kb);
+    cap_from_kb(kb)
+}
+
+/// Applies to scrollback buffers created from now on; running sessions keep
+/// the cap they started with.
+pub(crate) fn set_memory_cap_kb(kb: Option<u64>) {
+    MEMORY_CAP_BYTES.store(cap_from_kb(kb), Ordering::Relaxed);
+}
+
+fn spill_cap_from_mb(mb: Option<u64>) -> u64 {
+    mb.unwrap_or(DEFAULT_SPILL_MB).min(MAX_SPILL_MB) * 1024 * 1024
+}
+
+/// Disk cap for new sessions, from `scrollback_spill_mb` in settings.
+pub(crate) fn spill_cap() -> u64 {
+    let cached = SPILL_CAP_BYTES.load(Ordering::Relaxed);
+    if cached != u64::MAX {
+        return cached;
+    }
+    let mb = crate::host_store::settings_load()
+        .ok()
+        .and_then(|settings| settings.scrollback_spill_mb);
+    set_spill_cap_mb(mb);
+    spill_cap_from_mb(mb)
+}
+
+/// Takes effect for sessions opened afterwards.
+pub(crate) fn set_spill_cap_mb(mb: Option<u64>) {
+    SPILL_CAP_BYTES.store(spill_cap_from_mb(mb), Ordering::Relaxed);
+}
+
+// Scrollback is terminal output in plain text, so it goes in the profile's
+// private config dir rather than the shared temp dir.
+fn spill_dir() -> PathBuf {
+    crate::host_store::get_config_dir().join(SPILL_DIR_NAME)
+}
+
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // Signal 0 only checks that the process exists; EPERM still means it does.
+    let alive = unsafe { libc::kill(pid, 0) } == 0;
+    alive || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    true
+}
+
+/// Ring files are named `<pid>-<uuid>.ring`; remove the ones left behind by
+/// an instance that crashed or was killed before its sessions closed.
+fn remove_stale_spill_files(dir: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let own_pid = std::process::id();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some(SPILL_FILE_EXTENSION) {
+            continue;
+        }
+        let pid = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.split('-').next())
+            .and_then(|pid| pid.parse::<u32>().ok());
+        match pid {
+            Some(pid) if pid == own_pid || process_alive(pid) => {}
+            _ => {
+                let _ = fs::remove_file(&path);
+            }
+        }
+    }
+}
+
+fn create_spill_file(dir: &Path) -> io::Result<(File, PathBuf)> {
+    static CLEANUP: Once = Once::new();
+    CLEANUP.call_once(|| remove_stale_spill_files(dir));
+
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(dir)?;
+
+    let path = dir.join(format!(
+        "{}-{}.{SPILL_FILE_EXTENSION}",
+        std::process::id(),
+        uuid::Uuid::new_v4()
+    ));
+    let mut options = OpenOptions::new();
+    options.read(true).write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    Ok((options.open(&path)?, path))
+}
+
+/// Fixed-size ring file holding the oldest part of a session's output.
+/// Removed when dropped.
+struct SpillRing {
+    file: File,
+    path: PathBuf,
+    capacity: u64,
+    len: u64,
+    head: u64,
+}
+
+impl SpillRing {
+    fn create(dir: &Path, capacity: u64) -> io::Result<Self> {
+        let (file, path) = create_spill_file(dir)?;
+        Ok(Self {
+            file,
+            path,
+            capacity,
+            len: 0,
+            head: 0,
+        })
+    }
+
+    fn write(&mut self, mut data: &[u8]) -> io::Result<()> {
+        if data.len() as u64 > self.capacity {
+            data = &data[data.len() - self.capacity as usize..];
+        }
+        while !data.is_empty() {
+            let n = ((self.capacity - self.head) as usize).min(data.len());
+            self.file.seek(SeekFrom::Start(self.head))?;
+            self.file.write_all(&data[..n])?;
+            self.head = (self.head + n as u64) % self.capacity;
+            self.len = (self.len + n as u64).min(self.capacity);
+            data = &data[n..];
+        }
+        Ok(())
+    }
+
+    /// The newest `max` bytes, oldest first.
+    fn read_tail(&mut self, max: u64) -> io::Result<Vec<u8>> {
+        let total = max.min(self.len);
+        let mut out = vec![0; total as usize];
+        let mut pos = (self.head + self.capacity - total) % self.capacity;
+        let mut filled = 0;
+        while filled < out.len() {
+            let n = ((self.capacity - pos) as usize).min(out.len() - filled);
+            self.file.seek(SeekFrom::Start(pos))?;
+            self.file.read_exact(&mut out[filled..filled + n])?;
+            pos = (pos + n as u64) % self.capacity;
+            filled += n;
+        }
+        Ok(out)
+    }
+}
+
+impl Drop for SpillRing {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+// Output on its way to a session's ring file. The PTY reader only appends to
+// `pending`; the spill writer thread moves it to disk.
+struct Spill {
+    dir: PathBuf,
+    capacity: u64,
+    // Locked before `pending` by whoever takes both.
+    ring: Mutex<Option<SpillRing>>,
+    pending: Mutex<Vec<u8>>,
+    // Set after the spill file failed once; older output is then dropped.
+    disabled: AtomicBool,
+}
+
+impl Spill {
+    /// Append to the ring file what is pending. The ring stays locked until
+    /// it is written, so a reader never misses output that is in flight.
+    fn flush(&self) {
+        let Ok(mut ring) = self.ring.lock() else {
+            return;
+        };
+        let data = match self.pending.lock() {
+            Ok(mut pending) => std::mem::take(&mut *pending),
+            Err(_) => return,
+        };
+        if data.is_empty() || self.disabled.load(Ordering::Relaxed) {
+            return;
+        }
+        let written = match ring.as_mut() {
+            Some(ring) => ring.write(&data),
+            None => SpillRing::create(&self.dir, self.capacity).and_then(|mut created| {
+                created.write(&data)?;
+                *ring = Some(created);
+                Ok(())
+            }),
+        };
+        if let Err(error) = written {
+            eprintln!("[scrollback] spill to disk failed, dropping older output: {error}");
+            *ring = None;
+            self.disabled.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+fn spill_writer() -> &'static Mutex<mpsc::Sender<Weak<Spill>>> {
+    static WRITER: OnceLock<Mutex<mpsc::Sender<Weak<Spill>>>> = OnceLock::new();
+    WRITER.get_or_init(|| {
+        let (tx, rx) = mpsc::channel::<Weak<Spill>>();
+        thread::Builder::new()
+            .name("scrollback-spill".to_string())
+            .spawn(move || {
+                for spill in rx {
+                    if let Some(spill) = spill.upgrade() {
+                        spill.flush();
+                    }
+                }
+            })
+            .expect("failed to start scrollback spill thread");
+        Mutex::new(tx)
+    })
+}
+
+/// A session's output history: the newest part in memory up to a cap, older
+/// output spilled to a ring file so huge sessions keep a bounded RSS. The
+/// file is written on a thread of its own, since output arrives on the
+/// shared PTY reader.
+pub(crate) struct Scrollback {
+    memory: Vec<u8>,
+    memory_cap: usize,
+    // None when spilling is turned off.
+    spill: Option<Arc<Spill>>,
+}
+
+impl Scrollback {
+    pub(crate) fn new(memory_cap: usize) -> Self {
+        Self::with_spill(memory_cap, spill_dir(), spill_cap())
+    }
+
+    fn with_spill(memory_cap: usize, dir: PathBuf, spill_capacity: u64) -> Self {
+        Self {
+            memory: Vec::new(),
+            memory_cap: memory_cap.max(1),
+            spill: (spill_capacity > 0).then(|| {
+                Arc::new(Spill {
+                    dir,
+                    capacity: spill_capacity,
+                    ring: Mutex::new(None),
+                    pending: Mutex::new(Vec::new()),
+                    disabled: AtomicBool::new(false),
+                })
+            }),
+        }
+    }
+
+    pub(crate) fn push(&mut self, data: &str) {
+        self.memory.extend_from_slice(data.as_bytes());
+        if self.memory.len() <= self.memory_cap {
+            return;
+        }
+        // Spill down to half the cap so a busy session spills in large,
+        // infrequent writes.
+        let spill_len = self.memory.len() - self.memory_cap / 2;
+        if let Some(spill) = self
+            .spill
+            .as_ref()
+            .filter(|spill| !spill.disabled.load(Ordering::Relaxed))
+        {
+            let queued = match spill.pending.lock() {
+                Ok(mut pending) => {
+                    let was_empty = pending.is_empty();
+                    pending.extend_from_slice(&self.memory[..spill_len]);
+                    // A writer that cannot keep up loses the oldest output,
+                    // as the ring itself would.
+                    let excess = pending.len().saturating_sub(spill.capacity as usize);
+                    pending.drain(..excess);
+                    was_empty
+                }
+                Err(_) => false,
+            };
+            if queued {
+                if let Ok(writer) = spill_writer().lock() {
+                    let _ = writer.send(Arc::downgrade(spill));
+                }
+            }
+        }
+        self.memory.drain(..spill_len);
+    }
+
+    /// The newest `max_bytes` of output, starting at a character boundary.
+    pub(crate) fn tail(&mut self, max_bytes: usize) -> Result<String, String> {
+        let mut bytes = Vec::new();
+        if max_bytes > self.memory.len() {
+            if let Some(spill) = self.spill.as_ref() {
+                let want = (max_bytes - self.memory.len()).min(spill.capacity as usize);
+                let mut ring = spill.ring.lock().map_err(|_| "scrollback poisoned")?;
+                let pending = spill.pending.lock().map_err(|_| "scrollback poisoned")?;
+                if want > pending.len() {
+                    if let Some(ring) = ring.as_mut() {
+                        bytes = ring
+                            .read_tail((want - pending.len()) as u64)
+                            .map_err(|e| e.to_string())?;
+                    }
+                }
+                bytes.extend_from_slice(&pending[pending.len().saturating_sub(want)..]);
+            }
+        }
+        let start = self.memory.len().saturating_sub(max_bytes);
+        bytes.extend_from_slice(&self.memory[start..]);
+        let skip = bytes
+            .iter()
+            .take_while(|byte| (**byte & 0xC0) == 0x80)
+            .count();
+        Ok(String::from_utf8_lossy(&bytes[skip..]).into_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{spill_dir, Scrollback};
+    use crate::mock_backends::TestEnv;
+    use std::time::Duration;
+
+    fn flush(scrollback: &Scrollback) {
+        scrollback.spill.as_ref().unwrap().flush();
+    }
+
+    #[test]
+    fn spills_beyond_memory_cap_and_removes_file_on_drop() {
+        let _env = TestEnv::new();
+        let mut scrollback = Scrollback::with_spill(64, spill_dir(), 1024);
+        let lines: Vec<String> = (0..40).map(|i| format!("line {i:02} ✓\n")).collect();
+        for line in &lines {
+            scrollback.push(line);
+            assert!(scrollback.memory.len() <= 64);
+        }
+        // Output still queued for the spill thread is part of the tail.
+        assert_eq!(scrollback.tail(usize::MAX).unwrap(), lines.concat());
+        flush(&scrollback);
+        assert_eq!(scrollback.tail(usize::MAX).unwrap(), lines.concat());
+
+        let spill = scrollback.spill.as_ref().unwrap();
+        let path = spill.ring.lock().unwrap().as_ref().unwrap().path.clone();
+        assert!(path.starts_with(spill_dir()));
+        drop(scrollback);
+        // The spill thread may still hold the ring for a moment.
+        let removed = (0..100).any(|_| {
+            std::thread::sleep(Duration::from_millis(10));
+            !path.exists()
+        });
+        assert!(removed);
+    }
+
+    #[test]
+    fn ring_keeps_newest_output_and_trims_partial_characters() {
+        let _env = TestEnv::new();
+        let text: String = (0..30).map(|i| format!("é{i:02}")).collect();
+        let mut scrollback = Scrollback::with_spill(16, spill_dir(), 40);
+        for i in 0..30 {
+            scrollback.push(&format!("é{i:02}"));
+        }
+        flush(&scrollback);
+        let tail = scrollback.tail(usize::MAX).unwrap();
+        assert!(tail.len() <= 16 + 40);
+        assert!(text.ends_with(&tail));
+        assert!(tail.ends_with("é29"));
+        assert_eq!(scrollback.tail(5).unwrap(), "8é29");
+        assert_eq!(scrollback.tail(3).unwrap(), "29");
+    }
+
+    #[test]
+    fn zero_spill_cap_keeps_only_memory() {
+        let _env = TestEnv::new();
+        let mut scrollback = Scrollback::with_spill(16, spill_dir(), 0);
+        for i in 0..10 {
+            scrollback.push(&format!("line {i}\n"));
+        }
+        assert!(scrollback.spill.is_none());
+        assert!(scrollback.tail(usize::MAX).unwrap().ends_with("line 9\n"));
+        assert!(!spill_dir().exists());
+    }
+}
@@ -0,0 +1,212 @@
+use serde::Serialize;
+use serialport::{DataBits, Parity, SerialPort, SerialPortType, StopBits};
+use std::io::{self, Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+// Reads wake up this often to notice a closed session; serial consoles are
+// interactive, so this is also the worst-case latency of a kill.
+const SERIAL_READ_TIMEOUT: Duration = Duration::from_millis(200);
+const SERIAL_MAX_BAUD: u32 = 4_000_000;
+
+/// A serial device offered for a console session.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerialPortEntry {
+    pub device: String,
+    /// `usb`, `pci`, `bluetooth` or `unknown`.
+    pub kind: &'static str,
+    /// Manufacturer and product of USB adapters, when the OS reports them.
+    pub description: Option<String>,
+}
+
+/// Line settings of a serial session, e.g. the common 115200 8N1.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SerialSettings {
+    pub baud: u32,
+    pub data_bits: DataBits,
+    pub parity: Parity,
+    pub stop_bits: StopBits,
+}
+
+impl SerialSettings {
+    pub(crate) fn parse(
+        baud: u32,
+        data_bits: Option<u8>,
+        parity: Option<&str>,
+        stop_bits: Option<u8>,
+    ) -> Result<Self, String> {
+        if baud == 0 || baud > SERIAL_MAX_BAUD {
+            return Err(format!("Unsupported baud rate: {baud}"));
+        }
+        let data_bits = match data_bits.unwrap_or(8) {
+            5 => DataBits::Five,
+            6 => DataBits::Six,
+            7 => DataBits::Seven,
+            8 => DataBits::Eight,
+            other => return Err(format!("Unsupported data bits: {other}")),
+        };
+        let parity = match parity
+            .map(|p| p.trim().to_ascii_lowercase())
+            .as_deref()
+            .unwrap_or("none")
+        {
+            "none" | "n" | "" => Parity::None,
+            "odd" | "o" => Parity::Odd,
+            "even" | "e" => Parity::Even,
+            other => return Err(format!("Unsupported parity: {other}")),
+        };
+        let stop_bits = match stop_bits.unwrap_or(1) {
+            1 => StopBits::One,
+            2 => StopBits::Two,
+            other => return Err(format!("Unsupported stop bits: {other}")),
+        };
+        Ok(Self {
+            baud,
+            data_bits,
+            parity,
+            stop_bits,
+        })
+    }
+}
+
+/// An open serial device, split like a PTY into output, input and a killer.
+pub(crate) struct SerialConnection {
+    pub reader: Box<dyn Read + Send>,
+    pub writer: Box<dyn Write + Send>,
+    pub killer: Box<dyn portable_pty::ChildKiller + Send + Sync>,
+}
+
+// Serial reads time out instead of blocking forever, so the reader can notice
+// the session was closed; a timeout is not the end of the stream.
+struct SerialReader {
+    port: Box<dyn SerialPort>,
+    closed: Arc<AtomicBool>,
+}
+
+impl Read for SerialReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if self.closed.load(Ordering::SeqCst) {
+                return Ok(0);
+            }
+            match self.port.read(buf) {
+                Err(e) if e.kind() == io::ErrorKind::TimedOut => continue,
+                result => return result,
+            }
+        }
+    }
+}
+
+/// "Kills" a serial session by ending its output stream; the device is
+/// released once the session drops its handles.
+#[derive(Debug, Clone)]
+struct SerialKiller {
+    closed: Arc<AtomicBool>,
+}
+
+impl portable_pty::ChildKiller for SerialKiller {
+    fn kill(&mut self) -> io::Result<()> {
+        self.closed.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn clone_killer(&self) -> Box<dyn portable_pty::ChildKiller + Send + Sync> {
+        Box::new(self.clone())
+    }
+}
+
+pub(crate) fn open(device: &str, settings: &SerialSettings) -> Result<SerialConnection, String> {
+    let device = device.trim();
+    if device.is_empty() {
+        return Err("Serial device is required".to_string());
+    }
+    let port = serialport::new(device, settings.baud)
+        .data_bits(settings.data_bits)
+        .parity(settings.parity)
+        .stop_bits(settings.stop_bits)
+        .flow_control(serialport::FlowControl::None)
+        .timeout(SERIAL_READ_TIMEOUT)
+        .open()
+        .map_err(|e| format!("{device}: {e}"))?;
+    let writer = port.try_clone().map_err(|e| format!("{device}: {e}"))?;
+    let closed = Arc::new(AtomicBool::new(false));
+    Ok(SerialConnection {
+        reader: Box::new(SerialReader {
+            port,
+            closed: closed.clone(),
+        }),
+        writer: Box::new(writer),
+        killer: Box::new(SerialKiller { closed }),
+    })
+}
+
+fn port_entry(info: serialport::SerialPortInfo) -> SerialPortEntry {
+    let (kind, description) = match info.port_type {
+        SerialPortType::UsbPort(usb) => {
+            let description = [usb.manufacturer, usb.product]
+                .into_iter()
+                .flatten()
+                .map(|part| part.trim().to_string())
+                .filter(|part| !part.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ");
+            ("usb", Some(description).filter(|d| !d.is_empty()))
+        }
+        SerialPortType::PciPort => ("pci", None),
+        SerialPortType::BluetoothPort => ("bluetooth", None),
+        SerialPortType::Unknown => ("unknown", None),
+    };
+    SerialPortEntry {
+        device: info.port_name,
+        kind,
+        description,
+    }
+}
+
+/// Serial devices the OS currently reports (USB console cables show up here
+/// once plugged in).
+#[tauri::command]
+pub async fn serial_ports() -> Result<Vec<SerialPortEntry>, String> {
+    tauri::async_runtime::spawn_blocking(|| {
+        let mut ports: Vec<SerialPortEntry> = serialport::available_ports()
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(port_entry)
+            .collect();
+        ports.sort_by(|a, b| a.device.cmp(&b.device));
+        Ok(ports)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SerialSettings;
+    use serialport::{DataBits, Parity, StopBits};
+
+    #[test]
+    fn parses_line_settings() {
+        let settings = SerialSettings::parse(9600, Some(7), Some(" Even "), Some(2)).unwrap();
+        assert_eq!(
+            settings,
+            SerialSettings {
+                baud: 9600,
+                data_bits: DataBits::Seven,
+                parity: Parity::Even,
+                stop_bits: StopBits::Two,
+            }
+        );
+        let defaults = SerialSettings::parse(115_200, None, None, None).unwrap();
+        assert_eq!(
+            (defaults.data_bits, defaults.parity, defaults.stop_bits),
+            (DataBits::Eight, Parity::None, StopBits::One)
+        );
+        assert!(SerialSettings::parse(0, None, None, None).is_err());
+        assert!(SerialSettings::parse(9600, Some(9), None, None).is_err());
+        assert!(SerialSettings::parse(9600, None, Some("mark"), None).is_err());
+        assert!(SerialSettings::parse(9600, None, None, Some(3)).is_err());
+    }
+}
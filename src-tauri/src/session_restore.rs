@@ -0,0 +1,86 @@
+// Hosts whose terminal sessions were still open when the app quit, so the
+// next launch can offer to reopen them.
+
+use crate::host_store::{atomic_write, ensure_config_dir, get_config_dir, hosts_load};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionsAtExit {
+    pub exited_at: String,
+    /// One per open session, oldest first; a host with two sessions is
+    /// listed twice.
+    pub host_ids: Vec<String>,
+}
+
+fn get_sessions_at_exit_path() -> PathBuf {
+    get_config_dir().join("sessions_at_exit.json")
+}
+
+/// Replace the record with the hosts of the sessions open now. Quitting with
+/// none open drops an earlier record nobody acted on.
+pub(crate) fn save_sessions_at_exit(host_ids: Vec<String>) -> Result<(), String> {
+    if host_ids.is_empty() {
+        return sessions_at_exit_clear();
+    }
+    ensure_config_dir()?;
+    let record = SessionsAtExit {
+        exited_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        host_ids,
+    };
+    let content = serde_json::to_string_pretty(&record).map_err(|e| e.to_string())?;
+    atomic_write(&get_sessions_at_exit_path(), content.as_bytes())
+}
+
+/// The sessions open when the app last quit, leaving out hosts deleted
+/// since. None when there is nothing to reopen.
+#[tauri::command]
+pub fn sessions_at_exit() -> Result<Option<SessionsAtExit>, String> {
+    let path = get_sessions_at_exit_path();
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    let mut record: SessionsAtExit = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+    let hosts = hosts_load()?;
+    record
+        .host_ids
+        .retain(|id| hosts.iter().any(|host| host.id == *id && !host.deleted));
+    Ok((!record.host_ids.is_empty()).then_some(record))
+}
+
+/// Forget the record, once the user reopened the hosts or declined.
+#[tauri::command]
+pub fn sessions_at_exit_clear() -> Result<(), String> {
+    match fs::remove_file(get_sessions_at_exit_path()) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{save_sessions_at_exit, sessions_at_exit, sessions_at_exit_clear};
+    use crate::host_store::hosts_save;
+    use crate::mock_backends::{test_host as host, TestEnv};
+
+    #[test]
+    fn records_sessions_of_hosts_that_still_exist() {
+        let _env = TestEnv::new();
+        hosts_save(vec![host("web"), host("db")]).unwrap();
+        assert_eq!(sessions_at_exit().unwrap(), None);
+
+        save_sessions_at_exit(vec!["web".into(), "gone".into(), "web".into()]).unwrap();
+        let record = sessions_at_exit().unwrap().unwrap();
+        assert_eq!(record.host_ids, vec!["web", "web"]);
+
+        save_sessions_at_exit(Vec::new()).unwrap();
+        assert_eq!(sessions_at_exit().unwrap(), None);
+        save_sessions_at_exit(vec!["db".into()]).unwrap();
+        sessions_at_exit_clear().unwrap();
+        assert_eq!(sessions_at_exit().unwrap(), None);
+    }
+}
@@ -0,0 +1,280 @@
+use crate::host_store::{atomic_write, ensure_config_dir, get_config_dir, parse_updated_at};
+use crate::webdav_sync::WebdavTarget;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+const REMOTE_SETTINGS_FILE: &str = "settings.json";
+const SETTINGS_SYNC_VERSION: u32 = 1;
+// Matched against every key, nested ones included, so a preference blob can
+// never carry a secret to the server.
+const CREDENTIAL_KEY_MARKERS: &[&str] = &[
+    "password",
+    "passphrase",
+    "secret",
+    "token",
+    "credential",
+    "privatekey",
+    "webdav",
+];
+
+/// One synced preference. A `null` value is a tombstone, so removing a key
+/// propagates instead of being resurrected by the next pull.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PreferenceEntry {
+    value: Value,
+    updated_at: String,
+}
+
+/// The sanitized preferences document (theme, fonts, snippets, groups,
+/// shortcuts...), stored locally and as `settings.json` next to hosts.db.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PreferencesDoc {
+    #[serde(default)]
+    version: u32,
+    #[serde(default)]
+    entries: BTreeMap<String, PreferenceEntry>,
+}
+
+fn get_preferences_sync_path() -> PathBuf {
+    get_config_dir().join("preferences.sync.json")
+}
+
+fn is_credential_key(key: &str) -> bool {
+    let key: String = key
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    CREDENTIAL_KEY_MARKERS
+        .iter()
+        .any(|marker| key.contains(marker))
+}
+
+fn sanitize_value(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .filter(|(key, _)| !is_credential_key(key))
+                .map(|(key, value)| (key, sanitize_value(value)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(sanitize_value).collect()),
+        other => other,
+    }
+}
+
+impl PreferencesDoc {
+    fn load_local() -> Result<Self, String> {
+        let path = get_preferences_sync_path();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+        serde_json::from_str(&content).map_err(|e| e.to_string())
+    }
+
+    fn save_local(&self) -> Result<(), String> {
+        ensure_config_dir()?;
+        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        atomic_write(&get_preferences_sync_path(), content.as_bytes())
+    }
+
+    /// Stamp every key whose value differs from the last synced one;
+    /// keys missing from `current` become tombstones.
+    fn record_local(&mut self, current: Map<String, Value>, now: &str) {
+        let current: Map<String, Value> = current
+            .into_iter()
+            .filter(|(key, _)| !is_credential_key(key))
+            .map(|(key, value)| (key, sanitize_value(value)))
+            .collect();
+        for (key, entry) in self.entries.iter_mut() {
+            if !entry.value.is_null() && !current.contains_key(key) {
+                entry.value = Value::Null;
+                entry.updated_at = now.to_string();
+            }
+        }
+        for (key, value) in current {
+            if self.entries.get(&key).map(|entry| &entry.value) != Some(&value) {
+                self.entries.insert(
+                    key,
+                    PreferenceEntry {
+                        value,
+                        updated_at: now.to_string(),
+                    },
+                );
+            }
+        }
+    }
+
+    /// Per-key merge: whichever side changed a key last wins; ties keep the
+    /// local value.
+    fn merge(&mut self, remote: PreferencesDoc) {
+        for (key, remote_entry) in remote.entries {
+            if is_credential_key(&key) {
+                continue;
+            }
+            let remote_newer = match self.entries.get(&key) {
+                None => true,
+                Some(local) => match (
+                    parse_updated_at(&remote_entry.updated_at),
+                    parse_updated_at(&local.updated_at),
+                ) {
+                    (Some(a), Some(b)) => a > b,
+                    _ => remote_entry.updated_at > local.updated_at,
+                },
+            };
+            if remote_newer {
+                self.entries.insert(
+                    key,
+                    PreferenceEntry {
+                        value: sanitize_value(remote_entry.value),
+                        updated_at: remote_entry.updated_at,
+                    },
+                );
+            }
+        }
+        self.version = SETTINGS_SYNC_VERSION;
+    }
+
+    fn values(&self) -> Map<String, Value> {
+        self.entries
+            .iter()
+            .filter(|(_, entry)| !entry.value.is_null())
+            .map(|(key, entry)| (key.clone(), entry.value.clone()))
+            .collect()
+    }
+}
+
+fn now_rfc3339() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+async fn fetch_remote_preferences(target: &WebdavTarget) -> Result<PreferencesDoc, String> {
+    match target.get_optional(REMOTE_SETTINGS_FILE).await? {
+        Some(bytes) => serde_json::from_slice(&bytes)
+            .map_err(|e| format!("Remote {REMOTE_SETTINGS_FILE} is invalid: {e}")),
+        None => Ok(PreferencesDoc::default()),
+    }
+}
+
+async fn merge_with_remote(
+    target: &WebdavTarget,
+    preferences: Map<String, Value>,
+) -> Result<PreferencesDoc, String> {
+    let mut doc = PreferencesDoc::load_local()?;
+    doc.record_local(preferences, &now_rfc3339());
+    doc.merge(fetch_remote_preferences(target).await?);
+    Ok(doc)
+}
+
+async fn settings_push_impl(
+    target: &WebdavTarget,
+    preferences: Map<String, Value>,
+) -> Result<Map<String, Value>, String> {
+    let doc = merge_with_remote(target, preferences).await?;
+    let body = serde_json::to_vec_pretty(&doc).map_err(|e| e.to_string())?;
+    target.put(REMOTE_SETTINGS_FILE, body).await?;
+    doc.save_local()?;
+    Ok(doc.values())
+}
+
+/// Merge the frontend's current preferences with the remote `settings.json`
+/// key by key and upload the result. Returns the merged preferences for the
+/// UI to apply. Credentials are never part of the document.
+#[tauri::command]
+pub async fn settings_sync_push(
+    preferences: Map<String, Value>,
+) -> Result<Map<String, Value>, String> {
+    let target = WebdavTarget::from_settings()?;
+    target.ensure_remote_folder().await?;
+    let lock = target.acquire_sync_lock().await?;
+    let result = settings_push_impl(&target, preferences).await;
+    target.release_sync_lock(lock).await;
+    crate::audit_log::record("settings_sync_push", None, "ui", &result);
+    result
+}
+
+/// Merge the remote `settings.json` into the local preferences without
+/// uploading. Local edits since the last sync are recorded first so a
+/// newer local value is not replaced by an older remote one.
+#[tauri::command]
+pub async fn settings_sync_pull(
+    preferences: Map<String, Value>,
+) -> Result<Map<String, Value>, String> {
+    let target = WebdavTarget::from_settings()?;
+    let result = async {
+        let doc = merge_with_remote(&target, preferences).await?;
+        doc.save_local()?;
+        Ok(doc.values())
+    }
+    .await;
+    crate::audit_log::record("settings_sync_pull", None, "ui", &result);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PreferenceEntry, PreferencesDoc};
+    use serde_json::{json, Map, Value};
+
+    fn prefs(value: Value) -> Map<String, Value> {
+        value.as_object().cloned().unwrap()
+    }
+
+    #[test]
+    fn strips_credentials_at_any_depth() {
+        let mut doc = PreferencesDoc::default();
+        doc.record_local(
+            prefs(json!({
+                "theme": "dark",
+                "webdavPassword": "hunter2",
+                "snippets": [{ "name": "deploy", "apiToken": "abc", "body": "make" }],
+            })),
+            "2026-01-01T00:00:00.000Z",
+        );
+        assert_eq!(
+            Value::Object(doc.values()),
+            json!({
+                "theme": "dark",
+                "snippets": [{ "name": "deploy", "body": "make" }],
+            })
+        );
+    }
+
+    #[test]
+    fn merges_per_key_by_timestamp_and_propagates_removals() {
+        let mut local = PreferencesDoc::default();
+        local.record_local(
+            prefs(json!({ "theme": "dark", "fontSize": 13, "shortcuts": { "newTab": "Cmd+T" } })),
+            "2026-01-01T00:00:00.000Z",
+        );
+        local.record_local(
+            prefs(json!({ "theme": "light", "fontSize": 13 })),
+            "2026-01-03T00:00:00.000Z",
+        );
+
+        let mut remote = PreferencesDoc::default();
+        remote.record_local(
+            prefs(json!({ "theme": "solarized", "fontSize": 15, "shortcuts": { "newTab": "Ctrl+T" } })),
+            "2026-01-02T00:00:00.000Z",
+        );
+        remote.entries.insert(
+            "fontFamily".to_string(),
+            PreferenceEntry {
+                value: json!("JetBrains Mono"),
+                updated_at: "2026-01-02T00:00:00.000Z".to_string(),
+            },
+        );
+
+        local.merge(remote);
+        assert_eq!(
+            Value::Object(local.values()),
+            json!({ "theme": "light", "fontSize": 15, "fontFamily": "JetBrains Mono" })
+        );
+    }
+}
@@ -0,0 +1,212 @@
+// Local terminal profiles (a shell, its arguments, directory and
+// environment), listed next to the hosts and spawned through the same PTY
+// machinery by `pty_spawn_local`.
+
+use crate::host_store::open_hosts_db;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShellProfile {
+    /// Empty for a new profile; `shell_profile_save` assigns one.
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    /// Path of the program to run, e.g. `/bin/zsh`.
+    pub shell: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Starting directory; `~` is the home directory. The home directory
+    /// when not set.
+    #[serde(default)]
+    pub cwd: Option<String>,
+    /// `KEY=VALUE` per line, as for hosts.
+    #[serde(default)]
+    pub env_vars: Option<String>,
+    /// Typed once the shell shows its prompt; one command per line.
+    #[serde(default)]
+    pub startup_command: Option<String>,
+    #[serde(default)]
+    pub sort_order: Option<i64>,
+}
+
+fn ensure_shell_profiles_schema(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS shell_profiles (
+          id              TEXT PRIMARY KEY,
+          name            TEXT NOT NULL,
+          shell           TEXT NOT NULL,
+          args_json       TEXT NOT NULL,
+          cwd             TEXT,
+          env_vars        TEXT,
+          startup_command TEXT,
+          sort_order      INTEGER
+        );
+        "#,
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn query_profiles(conn: &Connection) -> Result<Vec<ShellProfile>, String> {
+    ensure_shell_profiles_schema(conn)?;
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT id, name, shell, args_json, cwd, env_vars, startup_command, sort_order
+            FROM shell_profiles
+            ORDER BY sort_order IS NULL, sort_order, name, id
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            let args_json: String = row.get(3)?;
+            Ok(ShellProfile {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                shell: row.get(2)?,
+                args: serde_json::from_str(&args_json).unwrap_or_default(),
+                cwd: row.get(4)?,
+                env_vars: row.get(5)?,
+                startup_command: row.get(6)?,
+                sort_order: row.get(7)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.map(|r| r.map_err(|e| e.to_string())).collect()
+}
+
+fn upsert_profile(conn: &Connection, profile: &ShellProfile) -> Result<(), String> {
+    ensure_shell_profiles_schema(conn)?;
+    let args_json = serde_json::to_string(&profile.args).map_err(|e| e.to_string())?;
+    conn.execute(
+        r#"
+        INSERT INTO shell_profiles (
+          id, name, shell, args_json, cwd, env_vars, startup_command, sort_order
+        )
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+        ON CONFLICT(id) DO UPDATE SET
+          name = excluded.name,
+          shell = excluded.shell,
+          args_json = excluded.args_json,
+          cwd = excluded.cwd,
+          env_vars = excluded.env_vars,
+          startup_command = excluded.startup_command,
+          sort_order = excluded.sort_order
+        "#,
+        params![
+            profile.id,
+            profile.name,
+            profile.shell,
+            args_json,
+            profile.cwd,
+            profile.env_vars,
+            profile.startup_command,
+            profile.sort_order
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn normalize(mut profile: ShellProfile) -> Result<ShellProfile, String> {
+    let non_empty = |value: Option<String>| {
+        value
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    profile.id = profile.id.trim().to_string();
+    if profile.id.is_empty() {
+        profile.id = uuid::Uuid::new_v4().to_string();
+    }
+    profile.shell = profile.shell.trim().to_string();
+    if profile.shell.is_empty() {
+        return Err("Shell path is required".to_string());
+    }
+    profile.name = profile.name.trim().to_string();
+    if profile.name.is_empty() {
+        profile.name = profile.shell.clone();
+    }
+    profile.cwd = non_empty(profile.cwd);
+    profile.env_vars = non_empty(profile.env_vars);
+    profile.startup_command = non_empty(profile.startup_command);
+    // Rejected here rather than when spawning, where the user is not looking
+    // at the profile any more.
+    crate::pty::parse_env_vars(profile.env_vars.as_deref())?;
+    Ok(profile)
+}
+
+pub(crate) fn shell_profile_get(id: &str) -> Result<ShellProfile, String> {
+    let conn = open_hosts_db()?;
+    query_profiles(&conn)?
+        .into_iter()
+        .find(|profile| profile.id == id.trim())
+        .ok_or_else(|| "Shell profile not found".to_string())
+}
+
+#[tauri::command]
+pub fn shell_profiles_list() -> Result<Vec<ShellProfile>, String> {
+    let conn = open_hosts_db()?;
+    query_profiles(&conn)
+}
+
+/// Create or update a profile; returns it as saved, with its id.
+#[tauri::command]
+pub fn shell_profile_save(profile: ShellProfile) -> Result<ShellProfile, String> {
+    let profile = normalize(profile)?;
+    let conn = open_hosts_db()?;
+    upsert_profile(&conn, &profile)?;
+    Ok(profile)
+}
+
+#[tauri::command]
+pub fn shell_profile_delete(id: String) -> Result<(), String> {
+    let conn = open_hosts_db()?;
+    ensure_shell_profiles_schema(&conn)?;
+    conn.execute(
+        "DELETE FROM shell_profiles WHERE id = ?1",
+        params![id.trim()],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize, query_profiles, upsert_profile, ShellProfile};
+    use rusqlite::Connection;
+
+    fn profile(name: &str, shell: &str) -> ShellProfile {
+        serde_json::from_value(serde_json::json!({ "name": name, "shell": shell })).unwrap()
+    }
+
+    #[test]
+    fn saves_and_orders_profiles() {
+        let conn = Connection::open_in_memory().unwrap();
+        let mut zsh = normalize(profile(" zsh ", "/bin/zsh")).unwrap();
+        zsh.args = vec!["-l".to_string()];
+        zsh.cwd = Some("~/src".to_string());
+        upsert_profile(&conn, &zsh).unwrap();
+        let mut bash = normalize(profile("", " /bin/bash ")).unwrap();
+        bash.sort_order = Some(0);
+        upsert_profile(&conn, &bash).unwrap();
+
+        let profiles = query_profiles(&conn).unwrap();
+        assert_eq!(profiles, vec![bash.clone(), zsh.clone()]);
+        assert_eq!(profiles[0].name, "/bin/bash");
+        assert_eq!(profiles[1].name, "zsh");
+        assert!(!zsh.id.is_empty());
+
+        zsh.args.clear();
+        upsert_profile(&conn, &zsh).unwrap();
+        assert_eq!(query_profiles(&conn).unwrap()[1].args, Vec::<String>::new());
+
+        assert!(normalize(profile("empty", " ")).is_err());
+        let mut bad_env = profile("env", "/bin/sh");
+        bad_env.env_vars = Some("NOT A PAIR".to_string());
+        assert!(normalize(bad_env).is_err());
+    }
+}
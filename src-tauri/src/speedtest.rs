@@ -0,0 +1,183 @@
+use crate::host_probe::{probe_failure_message, probe_ssh_command, ssh_exec};
+use crate::models::Host;
+use crate::operations;
+use serde::Serialize;
+use std::io::{Read, Write};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+const DEFAULT_SPEEDTEST_SECONDS: u64 = 5;
+const MAX_SPEEDTEST_SECONDS: u64 = 30;
+const LATENCY_SAMPLES: usize = 5;
+const SPEEDTEST_CHUNK_BYTES: usize = 256 * 1024;
+// Zeros compress to almost nothing; with compression on the test would
+// measure the CPU, not the link.
+const SPEEDTEST_SSH_OPTIONS: &[&str] = &["Compression=no"];
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostSpeedtestResult {
+    pub latency_min_ms: f64,
+    pub latency_avg_ms: f64,
+    pub download_bytes: u64,
+    pub download_bytes_per_sec: f64,
+    pub upload_bytes: u64,
+    pub upload_bytes_per_sec: f64,
+}
+
+fn bytes_per_sec(bytes: u64, elapsed: Duration) -> f64 {
+    let secs = elapsed.as_secs_f64();
+    if secs <= 0.0 {
+        0.0
+    } else {
+        bytes as f64 / secs
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+// Each sample opens a new channel on the shared ControlMaster connection, so
+// this is the round trip of an SSH request, not of a fresh handshake.
+fn measure_latency(host: &Host) -> Result<(f64, f64), String> {
+    let warmup = ssh_exec(host, "true")?;
+    if !warmup.status.success() {
+        return Err(probe_failure_message(&warmup));
+    }
+    let mut samples = Vec::with_capacity(LATENCY_SAMPLES);
+    for _ in 0..LATENCY_SAMPLES {
+        let started = Instant::now();
+        let output = ssh_exec(host, "true")?;
+        if !output.status.success() {
+            return Err(probe_failure_message(&output));
+        }
+        samples.push(millis(started.elapsed()));
+    }
+    let min = samples.iter().cloned().fold(f64::INFINITY, f64::min);
+    let avg = samples.iter().sum::<f64>() / samples.len() as f64;
+    Ok((min, avg))
+}
+
+fn measure_download(host: &Host, duration: Duration) -> Result<(u64, Duration), String> {
+    let mut prepared = probe_ssh_command(host, SPEEDTEST_SSH_OPTIONS, "cat /dev/zero")?;
+    let mut child = prepared
+        .command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    let mut stdout = child.stdout.take().ok_or("ssh stdout unavailable")?;
+    let mut buf = vec![0u8; SPEEDTEST_CHUNK_BYTES];
+    let mut total = 0u64;
+    let started = Instant::now();
+    let result = loop {
+        match stdout.read(&mut buf) {
+            Ok(0) => break Err("Connection closed during download test".to_string()),
+            Ok(n) => total += n as u64,
+            Err(e) => break Err(e.to_string()),
+        }
+        if let Err(error) = operations::check_current() {
+            break Err(error);
+        }
+        if started.elapsed() >= duration {
+            break Ok(());
+        }
+    };
+    let elapsed = started.elapsed();
+    // Closing the client closes the channel; the remote cat exits on EPIPE.
+    let _ = child.kill();
+    let _ = child.wait();
+    result.map(|()| (total, elapsed))
+}
+
+// The remote side counts what actually arrived, so bytes still buffered
+// locally or in flight when the clock stops are not credited.
+fn measure_upload(host: &Host, duration: Duration) -> Result<(u64, Duration), String> {
+    let mut prepared = probe_ssh_command(host, SPEEDTEST_SSH_OPTIONS, "wc -c")?;
+    let mut child = prepared
+        .command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    let mut stdin = child.stdin.take().ok_or("ssh stdin unavailable")?;
+    let buf = vec![0u8; SPEEDTEST_CHUNK_BYTES];
+    let started = Instant::now();
+    while started.elapsed() < duration {
+        if let Err(error) = operations::check_current() {
+            drop(stdin);
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(error);
+        }
+        if stdin.write_all(&buf).is_err() {
+            break;
+        }
+    }
+    drop(stdin);
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    let elapsed = started.elapsed();
+    if !output.status.success() {
+        return Err(probe_failure_message(&output));
+    }
+    let received = parse_byte_count(&String::from_utf8_lossy(&output.stdout))
+        .ok_or("Remote byte count missing from upload test")?;
+    Ok((received, elapsed))
+}
+
+fn parse_byte_count(stdout: &str) -> Option<u64> {
+    stdout.split_whitespace().next()?.parse().ok()
+}
+
+fn host_speedtest_impl(host: Host, seconds: u64) -> Result<HostSpeedtestResult, String> {
+    let duration = Duration::from_secs(seconds.clamp(1, MAX_SPEEDTEST_SECONDS));
+    let (latency_min_ms, latency_avg_ms) = measure_latency(&host)?;
+    let (download_bytes, download_elapsed) = measure_download(&host, duration)?;
+    let (upload_bytes, upload_elapsed) = measure_upload(&host, duration)?;
+    Ok(HostSpeedtestResult {
+        latency_min_ms,
+        latency_avg_ms,
+        download_bytes,
+        download_bytes_per_sec: bytes_per_sec(download_bytes, download_elapsed),
+        upload_bytes,
+        upload_bytes_per_sec: bytes_per_sec(upload_bytes, upload_elapsed),
+    })
+}
+
+/// Measure SSH round-trip latency and raw throughput in both directions by
+/// streaming zeros (no disk involved), to tell a slow link from a slow disk.
+#[tauri::command]
+pub async fn host_speedtest(
+    host: Host,
+    seconds: Option<u64>,
+    op_id: String,
+) -> Result<HostSpeedtestResult, String> {
+    let seconds = seconds.unwrap_or(DEFAULT_SPEEDTEST_SECONDS);
+    // Two timed phases plus latency samples and connection setup.
+    let timeout = Duration::from_secs(2 * seconds.clamp(1, MAX_SPEEDTEST_SECONDS) + 60);
+    tauri::async_runtime::spawn_blocking(move || {
+        operations::run(op_id, "host_speedtest", timeout, || {
+            host_speedtest_impl(host, seconds)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bytes_per_sec, parse_byte_count};
+    use std::time::Duration;
+
+    #[test]
+    fn parses_remote_byte_count_and_rates() {
+        assert_eq!(parse_byte_count("   1048576\n"), Some(1_048_576));
+        assert_eq!(parse_byte_count("1048576 -\n"), Some(1_048_576));
+        assert_eq!(parse_byte_count(""), None);
+        assert_eq!(bytes_per_sec(10_000, Duration::from_millis(500)), 20_000.0);
+        assert_eq!(bytes_per_sec(10_000, Duration::ZERO), 0.0);
+    }
+}
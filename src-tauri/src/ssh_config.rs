@@ -1,7 +1,10 @@
-use crate::host_store::{atomic_write, ensure_config_dir};
+use crate::host_store::{atomic_write, ensure_config_dir, get_config_dir};
 use crate::models::Host;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::PathBuf;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 
 // Set once this process has written a config that reflects the current host
 // list. hosts_save regenerates on every change, so callers that merely need a
@@ -16,11 +19,136 @@ pub(crate) fn ensure_ssh_config() -> Result<(), String> {
     generate_ssh_config(hosts)
 }
 
+/// In the profile's config dir, like everything else it writes: the host
+/// aliases differ between profiles, and so must the mux sockets beside it.
 pub(crate) fn get_ssh_config_path() -> PathBuf {
-    dirs::config_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("xtermius")
-        .join("ssh_config")
+    get_config_dir().join("ssh_config")
+}
+
+const DEFAULT_CONTROL_PERSIST_SECS: u64 = 60;
+// `u64::MAX` until settings have been read; 0 is a valid setting.
+static CONTROL_PERSIST_SECS: AtomicU64 = AtomicU64::new(u64::MAX);
+
+/// ControlMaster socket shared by every connection to a host: terminal tabs,
+/// probes, remote commands and file operations. The first one authenticates;
+/// later ones ride the same connection, so the server sees a single login.
+pub(crate) fn get_ssh_control_path() -> PathBuf {
+    get_ssh_config_path().with_file_name("mux_%C")
+}
+
+/// How long a shared connection outlives its last user, from
+/// `ssh_control_persist_secs` in settings.
+pub(crate) fn control_persist_secs() -> u64 {
+    let cached = CONTROL_PERSIST_SECS.load(Ordering::Relaxed);
+    if cached != u64::MAX {
+        return cached;
+    }
+    let secs = crate::host_store::settings_load()
+        .ok()
+        .and_then(|settings| settings.ssh_control_persist_secs);
+    set_control_persist_secs(secs);
+    CONTROL_PERSIST_SECS.load(Ordering::Relaxed)
+}
+
+/// Unset falls back to the default; the clamp keeps `u64::MAX` free as the
+/// "not loaded yet" marker.
+pub(crate) fn set_control_persist_secs(secs: Option<u64>) {
+    CONTROL_PERSIST_SECS.store(
+        secs.unwrap_or(DEFAULT_CONTROL_PERSIST_SECS)
+            .min(u64::MAX - 1),
+        Ordering::Relaxed,
+    );
+}
+
+pub(crate) fn ssh_multiplex_args(control_path: &str, persist_secs: u64) -> Vec<String> {
+    vec![
+        "-o".to_string(),
+        "ControlMaster=auto".to_string(),
+        "-o".to_string(),
+        format!("ControlPath={control_path}"),
+        // Keep the master after the last client exits so reopening a tab or
+        // the next probe does not trigger a fresh login. ssh reads 0 as
+        // "forever", so no persistence is spelled `no`.
+        "-o".to_string(),
+        if persist_secs == 0 {
+            "ControlPersist=no".to_string()
+        } else {
+            format!("ControlPersist={persist_secs}s")
+        },
+    ]
+}
+
+/// Close a host's shared connection, e.g. after changing its credentials;
+/// the next session logs in afresh. Open terminals on it are disconnected.
+/// Nothing to do when there is none.
+#[tauri::command]
+pub fn ssh_connection_close(host_id: String) -> Result<(), String> {
+    let hosts = crate::host_store::hosts_load()?;
+    let host = hosts
+        .iter()
+        .find(|host| host.id == host_id.trim() && !host.deleted)
+        .ok_or_else(|| "Host not found".to_string())?;
+    let target = if host.alias.trim().is_empty() {
+        host.hostname.trim()
+    } else {
+        host.alias.trim()
+    };
+    ensure_ssh_config()?;
+    // `-O exit` only talks to the local socket; it fails when there is no
+    // master, which is the state we want anyway.
+    Command::new("/usr/bin/ssh")
+        .arg("-F")
+        .arg(get_ssh_config_path())
+        .arg("-o")
+        .arg(format!(
+            "ControlPath={}",
+            get_ssh_control_path().to_string_lossy()
+        ))
+        .args(["-O", "exit", target])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Hosts sync between devices, so `extra_config` from a synced host must not
+// be able to run commands or load code here, or restructure the generated
+// file. Known options other than these are allowed.
+const BLOCKED_EXTRA_DIRECTIVES: &[&str] = &[
+    "Host",
+    "Match",
+    "Include",
+    "ProxyCommand",
+    "LocalCommand",
+    "PermitLocalCommand",
+    "KnownHostsCommand",
+    // Load a shared library into ssh.
+    "PKCS11Provider",
+    "SecurityKeyProvider",
+    // Names the xauth program ssh runs for X11 forwarding.
+    "XAuthLocation",
+];
+
+/// Whether `keyword` may appear in a host's `extra_config`: an option of
+/// `ssh_config(5)` that is not blocked above. Unknown keywords are refused,
+/// so options added to ssh later stay out until they are reviewed.
+pub(crate) fn is_allowed_extra_directive(keyword: &str) -> bool {
+    crate::ssh_options::is_known_option(keyword)
+        && !BLOCKED_EXTRA_DIRECTIVES
+            .iter()
+            .any(|blocked| blocked.eq_ignore_ascii_case(keyword))
+}
+
+/// The non-empty, non-comment lines of a host's `extra_config`, trimmed.
+fn extra_config_lines(host: &Host) -> impl Iterator<Item = &str> {
+    host.extra_config
+        .as_deref()
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
 }
 
 fn reject_control_chars(field: &str, value: &str) -> Result<(), String> {
@@ -50,7 +178,7 @@ fn quote_ssh_config_value(value: &str) -> String {
     }
 }
 
-fn validate_host_for_ssh_config(host: &Host) -> Result<(), String> {
+pub(crate) fn validate_host_for_ssh_config(host: &Host) -> Result<(), String> {
     let alias = if host.alias.trim().is_empty() {
         host.hostname.trim()
     } else {
@@ -92,52 +220,244 @@ fn validate_host_for_ssh_config(host: &Host) -> Result<(), String> {
         reject_control_chars("proxy_jump", proxy_jump)?;
         reject_whitespace("proxy_jump", proxy_jump)?;
     }
+    if let Some(proxy_command) = non_empty(&host.proxy_command) {
+        reject_control_chars("proxy_command", proxy_command)?;
+        // ssh uses whichever of the two comes first, so one would be ignored.
+        if non_empty(&host.proxy_jump).is_some() || !host.jump_host_ids.is_empty() {
+            return Err(format!(
+                "SSH config ProxyCommand cannot be combined with jump hosts for host {alias}"
+            ));
+        }
+    }
+    if host.server_alive_count_max == Some(0) {
+        return Err(format!(
+            "SSH config ServerAliveCountMax must be positive for host {alias}"
+        ));
+    }
+    if host.connect_timeout == Some(0) {
+        return Err(format!(
+            "SSH config ConnectTimeout must be positive for host {alias}"
+        ));
+    }
+    for (field, forwards) in [
+        ("LocalForward", &host.local_forwards),
+        ("RemoteForward", &host.remote_forwards),
+    ] {
+        for forward in forwards {
+            reject_control_chars(field, forward)?;
+            if forward.split_whitespace().count() != 2 {
+                return Err(format!(
+                    "SSH config {field} must be `[bind:]port host:hostport`, got {:?}",
+                    forward.trim()
+                ));
+            }
+        }
+    }
+    if let Some(request_tty) = non_empty(&host.request_tty) {
+        if !matches!(request_tty, "yes" | "no" | "force" | "auto") {
+            return Err(format!(
+                "SSH config RequestTTY must be yes, no, force or auto, got {request_tty:?}"
+            ));
+        }
+    }
+    if let Some(algorithms) = non_empty(&host.host_key_algorithms) {
+        reject_control_chars("HostKeyAlgorithms", algorithms)?;
+        reject_whitespace("HostKeyAlgorithms", algorithms)?;
+    }
+    for line in extra_config_lines(host) {
+        reject_control_chars("extra config", line)?;
+        let keyword = line
+            .split(|ch: char| ch.is_whitespace() || ch == '=')
+            .next()
+            .unwrap_or_default();
+        if keyword.is_empty() || !keyword.chars().all(|ch| ch.is_ascii_alphanumeric()) {
+            return Err(format!(
+                "SSH config extra line must start with an option name, got {line:?}"
+            ));
+        }
+        if !is_allowed_extra_directive(keyword) {
+            return Err(format!(
+                "SSH config {keyword} is not allowed in extra config for host {alias}"
+            ));
+        }
+    }
     Ok(())
 }
 
-#[tauri::command]
-pub fn generate_ssh_config(hosts: Vec<Host>) -> Result<(), String> {
-    ensure_config_dir()?;
-    let mut config = String::new();
-    for host in hosts {
-        if host.deleted {
-            continue;
+fn config_alias(host: &Host) -> &str {
+    if host.alias.trim().is_empty() {
+        host.hostname.trim()
+    } else {
+        host.alias.trim()
+    }
+}
+
+// Depth-first walk along jump hosts; `path` holds the hosts being visited, so
+// meeting one of them again closes a loop.
+fn find_jump_loop<'a>(
+    host: &'a Host,
+    live: &HashMap<&str, &'a Host>,
+    path: &mut Vec<&'a Host>,
+    done: &mut HashSet<&'a str>,
+) -> Option<Vec<&'a Host>> {
+    if done.contains(host.id.as_str()) {
+        return None;
+    }
+    if let Some(start) = path.iter().position(|visited| visited.id == host.id) {
+        let mut cycle = path[start..].to_vec();
+        cycle.push(host);
+        return Some(cycle);
+    }
+    path.push(host);
+    for id in &host.jump_host_ids {
+        if let Some(hop) = live.get(id.trim()) {
+            if let Some(cycle) = find_jump_loop(hop, live, path, done) {
+                return Some(cycle);
+            }
         }
-        validate_host_for_ssh_config(&host)?;
-        let alias = if host.alias.trim().is_empty() {
-            host.hostname.trim().to_string()
-        } else {
-            host.alias.trim().to_string()
-        };
-        config.push_str(&format!("Host {}\n", alias));
-        config.push_str(&format!("  HostName {}\n", host.hostname.trim()));
-        if !host.user.trim().is_empty() {
-            config.push_str(&format!("  User {}\n", host.user.trim()));
+    }
+    path.pop();
+    done.insert(&host.id);
+    None
+}
+
+/// The `ProxyJump` value of every live host with jump hosts: the hops'
+/// aliases, comma-separated. Fails when a hop is not a live host or when
+/// following the hops' own jump hosts leads back to a host on the way.
+pub(crate) fn resolve_jump_chains(hosts: &[Host]) -> Result<HashMap<String, String>, String> {
+    let live: HashMap<&str, &Host> = hosts
+        .iter()
+        .filter(|host| !host.deleted)
+        .map(|host| (host.id.as_str(), host))
+        .collect();
+    let mut chains = HashMap::new();
+    for host in hosts.iter().filter(|host| !host.deleted) {
+        if host.jump_host_ids.is_empty() {
+            continue;
         }
-        if host.port != 22 {
-            config.push_str(&format!("  Port {}\n", host.port));
+        let mut hops = Vec::with_capacity(host.jump_host_ids.len());
+        for id in &host.jump_host_ids {
+            let hop = live.get(id.trim()).ok_or_else(|| {
+                format!(
+                    "Jump host {} of host {} does not exist",
+                    id.trim(),
+                    config_alias(host)
+                )
+            })?;
+            hops.push(config_alias(hop));
         }
-        if let Some(identity_file) = host
-            .identity_file
-            .as_ref()
-            .map(|v| v.trim())
-            .filter(|v| !v.is_empty())
-        {
-            config.push_str(&format!(
-                "  IdentityFile {}\n",
-                quote_ssh_config_value(identity_file)
-            ));
-            config.push_str("  IdentitiesOnly yes\n");
+        chains.insert(host.id.clone(), hops.join(","));
+    }
+    let mut done = HashSet::new();
+    for host in hosts.iter().filter(|host| !host.deleted) {
+        if let Some(cycle) = find_jump_loop(host, &live, &mut Vec::new(), &mut done) {
+            let names: Vec<&str> = cycle.into_iter().map(config_alias).collect();
+            return Err(format!("Jump hosts form a loop: {}", names.join(" -> ")));
         }
-        if let Some(proxy_jump) = host
-            .proxy_jump
+    }
+    Ok(chains)
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
+/// `proxy_jump` is the resolved jump chain, which wins over the host's
+/// free-form `proxy_jump`.
+pub(crate) fn host_config_block(host: &Host, proxy_jump: Option<&str>) -> String {
+    let alias = if host.alias.trim().is_empty() {
+        host.hostname.trim().to_string()
+    } else {
+        host.alias.trim().to_string()
+    };
+    let mut config = String::new();
+    config.push_str(&format!("Host {}\n", alias));
+    config.push_str(&format!("  HostName {}\n", host.hostname.trim()));
+    if !host.user.trim().is_empty() {
+        config.push_str(&format!("  User {}\n", host.user.trim()));
+    }
+    if host.port != 22 {
+        config.push_str(&format!("  Port {}\n", host.port));
+    }
+    if let Some(identity_file) = host
+        .identity_file
+        .as_ref()
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+    {
+        config.push_str(&format!(
+            "  IdentityFile {}\n",
+            quote_ssh_config_value(identity_file)
+        ));
+        config.push_str("  IdentitiesOnly yes\n");
+    }
+    if let Some(proxy_jump) = proxy_jump.or_else(|| {
+        host.proxy_jump
             .as_ref()
             .map(|v| v.trim())
             .filter(|v| !v.is_empty())
-        {
-            config.push_str(&format!("  ProxyJump {}\n", proxy_jump));
+    }) {
+        config.push_str(&format!("  ProxyJump {}\n", proxy_jump));
+    }
+    // The rest of the line is the command, `%h`/`%p` tokens included.
+    if let Some(proxy_command) = non_empty(&host.proxy_command) {
+        config.push_str(&format!("  ProxyCommand {proxy_command}\n"));
+    }
+    if let Some(forward_agent) = host.forward_agent {
+        config.push_str(&format!("  ForwardAgent {}\n", yes_no(forward_agent)));
+    }
+    if let Some(compression) = host.compression {
+        config.push_str(&format!("  Compression {}\n", yes_no(compression)));
+    }
+    if let Some(timeout) = host.connect_timeout {
+        config.push_str(&format!("  ConnectTimeout {timeout}\n"));
+    }
+    if let Some(request_tty) = non_empty(&host.request_tty) {
+        config.push_str(&format!("  RequestTTY {request_tty}\n"));
+    }
+    if let Some(algorithms) = non_empty(&host.host_key_algorithms) {
+        config.push_str(&format!("  HostKeyAlgorithms {algorithms}\n"));
+    }
+    for forward in &host.local_forwards {
+        let forward = forward.split_whitespace().collect::<Vec<_>>().join(" ");
+        config.push_str(&format!("  LocalForward {forward}\n"));
+    }
+    for forward in &host.remote_forwards {
+        let forward = forward.split_whitespace().collect::<Vec<_>>().join(" ");
+        config.push_str(&format!("  RemoteForward {forward}\n"));
+    }
+    config.push_str("  ServerAliveInterval 30\n");
+    if let Some(count) = host.server_alive_count_max {
+        config.push_str(&format!("  ServerAliveCountMax {count}\n"));
+    }
+    for line in extra_config_lines(host) {
+        config.push_str(&format!("  {line}\n"));
+    }
+    config
+}
+
+#[tauri::command]
+pub fn generate_ssh_config(hosts: Vec<Host>) -> Result<(), String> {
+    ensure_config_dir()?;
+    let chains = resolve_jump_chains(&hosts)?;
+    let mut config = String::new();
+    for host in &hosts {
+        if host.deleted {
+            continue;
         }
-        config.push_str("  ServerAliveInterval 30\n");
+        validate_host_for_ssh_config(host)?;
+        config.push_str(&host_config_block(
+            host,
+            chains.get(&host.id).map(String::as_str),
+        ));
         config.push('\n');
     }
     let path = get_ssh_config_path();
@@ -146,9 +466,127 @@ pub fn generate_ssh_config(hosts: Vec<Host>) -> Result<(), String> {
     Ok(())
 }
 
+/// What to leave out of an exported config, for sharing it with others.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SshConfigExportOptions {
+    /// Hosts to export; every host when empty.
+    pub host_ids: Vec<String>,
+    pub omit_users: bool,
+    pub omit_identity_files: bool,
+    /// Port forwards and extra config lines.
+    pub omit_forwards_and_extra: bool,
+    /// Write each host's notes as comments above its block.
+    pub include_notes: bool,
+}
+
+const UNGROUPED_HEADING: &str = "Other hosts";
+
+fn comment_lines(text: &str) -> String {
+    text.lines()
+        .map(|line| {
+            let line: String = line.chars().filter(|ch| !ch.is_control()).collect();
+            format!("# {}\n", line.trim_end())
+        })
+        .collect()
+}
+
+/// A standalone config for `hosts`: grouped under their first tag (groups
+/// and hosts in alphabetical order, untagged hosts last), each block
+/// commented with the host's name.
+fn export_ssh_config(
+    hosts: &[Host],
+    options: &SshConfigExportOptions,
+    exported_at: &str,
+) -> Result<(String, usize), String> {
+    let chains = resolve_jump_chains(hosts)?;
+    let mut groups: BTreeMap<(bool, String), Vec<Host>> = BTreeMap::new();
+    for host in hosts.iter().filter(|host| {
+        !host.deleted && (options.host_ids.is_empty() || options.host_ids.contains(&host.id))
+    }) {
+        validate_host_for_ssh_config(host)?;
+        let mut host = host.clone();
+        if options.omit_users {
+            host.user.clear();
+        }
+        if options.omit_identity_files {
+            host.identity_file = None;
+        }
+        if options.omit_forwards_and_extra {
+            host.local_forwards.clear();
+            host.remote_forwards.clear();
+            host.extra_config = None;
+        }
+        let group = match host
+            .tags
+            .iter()
+            .map(|tag| tag.trim())
+            .find(|tag| !tag.is_empty())
+        {
+            Some(tag) => (false, tag.to_lowercase()),
+            None => (true, String::new()),
+        };
+        groups.entry(group).or_default().push(host);
+    }
+
+    let mut config = format!("# Exported from xTermius on {exported_at}\n");
+    let mut count = 0;
+    for ((ungrouped, _), mut hosts) in groups {
+        hosts.sort_by_key(|host| host.alias.trim().to_lowercase());
+        let heading = if ungrouped {
+            UNGROUPED_HEADING.to_string()
+        } else {
+            hosts[0]
+                .tags
+                .iter()
+                .map(|tag| tag.trim())
+                .find(|tag| !tag.is_empty())
+                .unwrap_or_default()
+                .to_string()
+        };
+        config.push_str(&format!("\n{}\n", comment_lines(&heading).trim_end()));
+        for host in hosts {
+            config.push('\n');
+            if !host.name.trim().is_empty() && host.name.trim() != host.alias.trim() {
+                config.push_str(&comment_lines(host.name.trim()));
+            }
+            if options.include_notes && !host.notes.trim().is_empty() {
+                config.push_str(&comment_lines(host.notes.trim()));
+            }
+            config.push_str(&host_config_block(
+                &host,
+                chains.get(&host.id).map(String::as_str),
+            ));
+            count += 1;
+        }
+    }
+    Ok((config, count))
+}
+
+/// Write the hosts as an OpenSSH config file to `path`, e.g. to hand to
+/// teammates or keep in a dotfiles repo. Returns the number of hosts written.
+#[tauri::command]
+pub fn hosts_export_ssh_config(
+    path: String,
+    options: Option<SshConfigExportOptions>,
+) -> Result<usize, String> {
+    let path = PathBuf::from(path.trim());
+    if path.as_os_str().is_empty() {
+        return Err("Export path is required".to_string());
+    }
+    let hosts = crate::host_store::hosts_load()?;
+    let exported_at = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    let (config, count) = export_ssh_config(&hosts, &options.unwrap_or_default(), &exported_at)?;
+    atomic_write(&path, config.as_bytes())?;
+    Ok(count)
+}
+
 #[cfg(test)]
 mod tests {
-    use super::generate_ssh_config;
+    use super::{
+        export_ssh_config, generate_ssh_config, host_config_block, resolve_jump_chains,
+        ssh_multiplex_args, validate_host_for_ssh_config, SshConfigExportOptions,
+    };
     use crate::models::Host;
 
     fn host_with_alias(alias: &str) -> Host {
@@ -162,12 +600,37 @@ mod tests {
             port: 22,
             password: None,
             has_password: false,
+            has_totp: false,
             host_insights_enabled: true,
             host_live_metrics_enabled: true,
             identity_file: None,
             proxy_jump: None,
+            jump_host_ids: Vec::new(),
+            proxy_command: None,
             env_vars: None,
             encoding: Some("utf-8".to_string()),
+            startup_commands: Vec::new(),
+            startup_commands_delay_ms: None,
+            probe_shell: None,
+            sync_excluded: false,
+            import_source: None,
+            mac_address: None,
+            max_session_minutes: None,
+            live_probe_lite: false,
+            extended_probe: false,
+            prompt_rules: Vec::new(),
+            forward_agent: None,
+            compression: None,
+            server_alive_count_max: None,
+            connect_timeout: None,
+            local_forwards: Vec::new(),
+            remote_forwards: Vec::new(),
+            request_tty: None,
+            host_key_algorithms: None,
+            extra_config: None,
+            bell_action: None,
+            keepalive_idle_minutes: None,
+            keepalive_string: None,
             tags: vec![],
             notes: "".to_string(),
             updated_at: "2026-05-03T00:00:00Z".to_string(),
@@ -181,4 +644,169 @@ mod tests {
             .unwrap_err();
         assert!(err.contains("alias"));
     }
+
+    #[test]
+    fn writes_per_host_options() {
+        let mut host = host_with_alias("prod");
+        host.forward_agent = Some(true);
+        host.compression = Some(false);
+        host.server_alive_count_max = Some(3);
+        host.connect_timeout = Some(5);
+        host.local_forwards = vec!["8080  localhost:80".to_string()];
+        host.remote_forwards = vec!["9000 127.0.0.1:9000".to_string()];
+        host.request_tty = Some("force".to_string());
+        host.host_key_algorithms = Some("+ssh-rsa".to_string());
+        assert!(validate_host_for_ssh_config(&host).is_ok());
+        let block = host_config_block(&host, None);
+        for line in [
+            "  ForwardAgent yes\n",
+            "  Compression no\n",
+            "  ConnectTimeout 5\n",
+            "  RequestTTY force\n",
+            "  HostKeyAlgorithms +ssh-rsa\n",
+            "  LocalForward 8080 localhost:80\n",
+            "  RemoteForward 9000 127.0.0.1:9000\n",
+            "  ServerAliveCountMax 3\n",
+        ] {
+            assert!(block.contains(line), "missing {line:?} in {block}");
+        }
+
+        host.local_forwards = vec!["8080".to_string()];
+        assert!(validate_host_for_ssh_config(&host).is_err());
+        host.local_forwards.clear();
+        host.request_tty = Some("always".to_string());
+        assert!(validate_host_for_ssh_config(&host).is_err());
+    }
+
+    #[test]
+    fn resolves_jump_chains_and_rejects_loops() {
+        let mut bastion = host_with_alias("bastion");
+        bastion.id = "b".to_string();
+        let mut inner = host_with_alias("inner-jump");
+        inner.id = "i".to_string();
+        let mut db = host_with_alias("db");
+        db.id = "d".to_string();
+        db.proxy_jump = Some("ignored".to_string());
+        db.jump_host_ids = vec!["b".to_string(), "i".to_string()];
+        let mut hosts = vec![bastion, inner, db];
+
+        let chains = resolve_jump_chains(&hosts).unwrap();
+        assert_eq!(chains.len(), 1);
+        assert_eq!(chains["d"], "bastion,inner-jump");
+        let block = host_config_block(&hosts[2], Some(&chains["d"]));
+        assert!(block.contains("  ProxyJump bastion,inner-jump\n"));
+        assert!(!block.contains("ignored"));
+
+        hosts[0].jump_host_ids = vec!["d".to_string()];
+        let err = resolve_jump_chains(&hosts).unwrap_err();
+        assert!(err.contains("loop"), "{err}");
+        assert!(err.contains("bastion -> db -> bastion"), "{err}");
+
+        hosts[0].jump_host_ids.clear();
+        hosts[1].deleted = true;
+        let err = resolve_jump_chains(&hosts).unwrap_err();
+        assert!(err.contains("does not exist"), "{err}");
+    }
+
+    #[test]
+    fn writes_proxy_command_instead_of_jump_hosts() {
+        let mut host = host_with_alias("tunnel");
+        host.proxy_command = Some("cloudflared access ssh --hostname %h".to_string());
+        assert!(validate_host_for_ssh_config(&host).is_ok());
+        let block = host_config_block(&host, None);
+        assert!(block.contains("  ProxyCommand cloudflared access ssh --hostname %h\n"));
+        assert!(!block.contains("ProxyJump"));
+
+        host.proxy_jump = Some("bastion".to_string());
+        assert!(validate_host_for_ssh_config(&host).is_err());
+        host.proxy_jump = None;
+        host.proxy_command = Some("nc %h %p\n  LocalCommand id".to_string());
+        assert!(validate_host_for_ssh_config(&host).is_err());
+    }
+
+    #[test]
+    fn writes_extra_config_but_blocks_local_commands() {
+        let mut host = host_with_alias("prod");
+        host.extra_config =
+            Some("# from ~/.ssh/config\nControlMaster auto\n\n  SendEnv LANG\n".to_string());
+        assert!(validate_host_for_ssh_config(&host).is_ok());
+        assert!(host_config_block(&host, None).ends_with("  ControlMaster auto\n  SendEnv LANG\n"));
+
+        for extra in [
+            "ProxyCommand nc attacker 22",
+            "proxycommand=nc attacker 22",
+            "Match exec true",
+            "PKCS11Provider /tmp/evil.so",
+            "SecurityKeyProvider=/tmp/evil.so",
+            "XAuthLocation /tmp/evil",
+            "SmartcardDevice /tmp/evil.so",
+            "\u{1b}[2J",
+        ] {
+            host.extra_config = Some(extra.to_string());
+            assert!(validate_host_for_ssh_config(&host).is_err(), "{extra:?}");
+        }
+    }
+
+    #[test]
+    fn exports_grouped_sanitized_config() {
+        let mut web = host_with_alias("web");
+        web.id = "web".to_string();
+        web.name = "Web frontend".to_string();
+        web.tags = vec!["Prod".to_string()];
+        web.identity_file = Some("~/.ssh/id_work".to_string());
+        web.notes = "Primary\nfrontend".to_string();
+        let mut api = host_with_alias("api");
+        api.id = "api".to_string();
+        api.name = "api".to_string();
+        api.tags = vec!["prod".to_string()];
+        let mut lab = host_with_alias("lab");
+        lab.id = "lab".to_string();
+        lab.name = "Lab box".to_string();
+        let mut db = host_with_alias("db");
+        db.id = "db".to_string();
+        db.tags = vec!["Data".to_string()];
+        db.deleted = true;
+
+        let options = SshConfigExportOptions {
+            omit_users: true,
+            omit_identity_files: true,
+            include_notes: true,
+            ..Default::default()
+        };
+        let (config, count) =
+            export_ssh_config(&[web, lab, api, db], &options, "2026-01-01T00:00:00Z").unwrap();
+        assert_eq!(count, 3);
+        let hosts: Vec<&str> = config
+            .lines()
+            .filter(|line| line.starts_with("Host ") || line.starts_with("# "))
+            .collect();
+        assert_eq!(
+            hosts,
+            vec![
+                "# Exported from xTermius on 2026-01-01T00:00:00Z",
+                "# prod",
+                "Host api",
+                "# Web frontend",
+                "# Primary",
+                "# frontend",
+                "Host web",
+                "# Other hosts",
+                "# Lab box",
+                "Host lab",
+            ]
+        );
+        assert!(!config.contains("User ") && !config.contains("IdentityFile"));
+    }
+
+    #[test]
+    fn multiplex_args_share_control_socket() {
+        let args = ssh_multiplex_args("/tmp/xtermius/mux_%C", 600);
+        assert!(args
+            .windows(2)
+            .any(|pair| pair == ["-o", "ControlPath=/tmp/xtermius/mux_%C"]));
+        assert!(args.iter().any(|arg| arg == "ControlMaster=auto"));
+        assert!(args.iter().any(|arg| arg == "ControlPersist=600s"));
+        let args = ssh_multiplex_args("/tmp/xtermius/mux_%C", 0);
+        assert!(args.iter().any(|arg| arg == "ControlPersist=no"));
+    }
 }
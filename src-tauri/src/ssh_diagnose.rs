@@ -0,0 +1,464 @@
+// "Why is SSH slow?": one verbose login to a host, timed line by line and cut
+// into the phases of an SSH connection, so the answer names the slow step
+// instead of pasting the `ssh -vvv` output.
+
+use crate::models::Host;
+use crate::operations;
+use crate::ssh_config::{ensure_ssh_config, get_ssh_config_path};
+use serde::Serialize;
+use std::io::{BufRead, BufReader};
+use std::net::ToSocketAddrs;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const DIAGNOSE_TIMEOUT: Duration = Duration::from_secs(90);
+const DIAGNOSE_CONNECT_TIMEOUT_SECS: u64 = 30;
+// A phase slower than this is called out.
+const SLOW_PHASE_MS: f64 = 1000.0;
+// Offering more keys than this before one works is worth a mention.
+const MANY_KEYS_OFFERED: usize = 3;
+const MAX_LOG_LINES: usize = 2000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SshPhase {
+    Dns,
+    Connect,
+    Banner,
+    KeyExchange,
+    /// Part of `Auth`, listed on its own because it is the usual culprit.
+    Gssapi,
+    Auth,
+    /// From login to the remote command exiting.
+    Session,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SshPhaseTiming {
+    pub phase: SshPhase,
+    pub duration_ms: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SshDiagnosis {
+    pub host_id: String,
+    /// Whether the login got through and the test command ran.
+    pub connected: bool,
+    pub total_ms: f64,
+    /// The hostname looked up with the system resolver, apart from ssh.
+    pub dns_ms: Option<f64>,
+    /// Phases seen in the log, in connection order. A phase is missing when
+    /// the attempt failed before it or ssh did not log it (e.g. the connect
+    /// step through a ProxyCommand).
+    pub phases: Vec<SshPhaseTiming>,
+    pub slowest: Option<SshPhase>,
+    /// Plain-language explanations, slowest cause first.
+    pub findings: Vec<String>,
+    pub error: Option<String>,
+    /// ssh's verbose output, each line prefixed with the milliseconds since
+    /// the start.
+    pub log: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct LogLine {
+    at_ms: f64,
+    text: String,
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+fn secs(ms: f64) -> String {
+    format!("{:.1}s", ms / 1000.0)
+}
+
+// ssh logs a line for every step of the login; these mark where each phase
+// ends. The last match wins: with a jump host the jump's own login comes
+// first in the log.
+fn marker(lines: &[LogLine], needles: &[&str]) -> Option<f64> {
+    lines
+        .iter()
+        .rev()
+        .find(|line| needles.iter().any(|needle| line.text.contains(needle)))
+        .map(|line| line.at_ms)
+}
+
+// Time from each `gssapi-*` attempt to the next method, or to the end of
+// authentication when it was the last one tried.
+fn gssapi_ms(lines: &[LogLine], auth_end: Option<f64>) -> Option<f64> {
+    let mut total = None;
+    let mut started: Option<f64> = None;
+    for line in lines {
+        let Some(method) = line.text.split("Next authentication method: ").nth(1) else {
+            continue;
+        };
+        if let Some(start) = started.take() {
+            *total.get_or_insert(0.0) += line.at_ms - start;
+        }
+        if method.trim().starts_with("gssapi") {
+            started = Some(line.at_ms);
+        }
+    }
+    if let (Some(start), Some(end)) = (started, auth_end) {
+        *total.get_or_insert(0.0) += end - start;
+    }
+    total
+}
+
+fn phase_timings(lines: &[LogLine], total_ms: f64, connected: bool) -> Vec<SshPhaseTiming> {
+    let resolving = marker(lines, &["resolving \""]);
+    let connecting = marker(lines, &["Connecting to "]);
+    let established = marker(lines, &["Connection established"]);
+    let banner = marker(lines, &["Remote protocol version"]);
+    let kex_done = marker(lines, &["SSH2_MSG_NEWKEYS received"]);
+    let authenticated = marker(lines, &["Authenticated to ", "Authentication succeeded"]);
+    let session_end = connected.then_some(total_ms);
+    // A failed attempt may have died in GSSAPI; it ran until ssh gave up.
+    let auth_end = authenticated.or((!connected).then_some(total_ms));
+
+    let between =
+        |start: Option<f64>, end: Option<f64>| Some(end? - start?).filter(|ms| *ms >= 0.0);
+    [
+        (SshPhase::Dns, between(resolving, connecting)),
+        (SshPhase::Connect, between(connecting, established)),
+        (SshPhase::Banner, between(established, banner)),
+        (SshPhase::KeyExchange, between(banner, kex_done)),
+        (SshPhase::Gssapi, gssapi_ms(lines, auth_end)),
+        (SshPhase::Auth, between(kex_done, authenticated)),
+        (SshPhase::Session, between(authenticated, session_end)),
+    ]
+    .into_iter()
+    .filter_map(|(phase, ms)| {
+        Some(SshPhaseTiming {
+            phase,
+            duration_ms: ms?,
+        })
+    })
+    .collect()
+}
+
+fn phase_ms(phases: &[SshPhaseTiming], phase: SshPhase) -> Option<f64> {
+    phases
+        .iter()
+        .find(|timing| timing.phase == phase)
+        .map(|timing| timing.duration_ms)
+}
+
+fn finding(phase: SshPhase, ms: f64, hostname: &str, keys_offered: usize) -> String {
+    let took = secs(ms);
+    match phase {
+        SshPhase::Dns => format!(
+            "Resolving {hostname} took {took}. The DNS server is slow or a search domain is \
+             tried first; an entry in /etc/hosts or the IP address avoids the lookup."
+        ),
+        SshPhase::Connect => format!(
+            "Opening the TCP connection took {took}: a slow or lossy network path, or a \
+             firewall dropping the first packets."
+        ),
+        SshPhase::Banner => format!(
+            "The server took {took} to send its SSH banner. sshd is usually looking up the \
+             client's name (set `UseDNS no` in its sshd_config) or is overloaded."
+        ),
+        SshPhase::KeyExchange => {
+            format!("Key exchange took {took}, which points at a busy or underpowered server.")
+        }
+        SshPhase::Gssapi => format!(
+            "GSSAPI (Kerberos) authentication was tried and cost {took}. Unless you use \
+             Kerberos, add `GSSAPIAuthentication no` to the host's extra config."
+        ),
+        SshPhase::Auth if keys_offered > MANY_KEYS_OFFERED => format!(
+            "Authentication took {took}; {keys_offered} keys were offered before one was \
+             accepted. Set the host's identity file so only that key is tried."
+        ),
+        SshPhase::Auth => format!(
+            "Authentication took {took}: the server is slow to check the account, typically \
+             PAM or a directory lookup (LDAP, SSSD)."
+        ),
+        SshPhase::Session => format!(
+            "The remote command finished {took} after login: PAM session modules (motd, \
+             lastlog, mail check) or the login shell's startup files."
+        ),
+    }
+}
+
+// The reason ssh gave up, which it prints without a `debug` prefix.
+fn failure_reason(lines: &[LogLine]) -> Option<String> {
+    lines
+        .iter()
+        .rev()
+        .map(|line| line.text.trim())
+        .find(|text| !text.is_empty() && !text.starts_with("debug") && !text.starts_with("OpenSSH"))
+        .map(str::to_string)
+}
+
+fn diagnose(
+    host_id: &str,
+    hostname: &str,
+    dns_ms: Option<f64>,
+    lines: &[LogLine],
+    total_ms: f64,
+    connected: bool,
+) -> SshDiagnosis {
+    let mut phases = phase_timings(lines, total_ms, connected);
+    // Through a ProxyCommand ssh never resolves the name itself.
+    if phase_ms(&phases, SshPhase::Dns).is_none() {
+        if let Some(ms) = dns_ms {
+            phases.insert(
+                0,
+                SshPhaseTiming {
+                    phase: SshPhase::Dns,
+                    duration_ms: ms,
+                },
+            );
+        }
+    }
+    let gssapi = phase_ms(&phases, SshPhase::Gssapi).unwrap_or(0.0);
+    let keys_offered = lines
+        .iter()
+        .filter(|line| line.text.contains("Offering public key"))
+        .count();
+
+    // GSSAPI is part of authentication; the rest of it is judged alone.
+    let mut slow: Vec<(SshPhase, f64)> = phases
+        .iter()
+        .map(|timing| match timing.phase {
+            SshPhase::Auth => (timing.phase, timing.duration_ms - gssapi),
+            _ => (timing.phase, timing.duration_ms),
+        })
+        .filter(|(_, ms)| *ms >= SLOW_PHASE_MS)
+        .collect();
+    slow.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let mut findings: Vec<String> = slow
+        .iter()
+        .map(|(phase, ms)| finding(*phase, *ms, hostname, keys_offered))
+        .collect();
+    if findings.is_empty() && connected {
+        findings.push(format!(
+            "No step took over {}; the whole connection took {}.",
+            secs(SLOW_PHASE_MS),
+            secs(total_ms)
+        ));
+    }
+
+    SshDiagnosis {
+        host_id: host_id.to_string(),
+        connected,
+        total_ms,
+        dns_ms,
+        slowest: slow.first().map(|(phase, _)| *phase),
+        phases,
+        findings,
+        error: (!connected).then(|| {
+            failure_reason(lines).unwrap_or_else(|| "ssh exited without connecting".to_string())
+        }),
+        log: lines
+            .iter()
+            .map(|line| format!("{:>8.1} {}", line.at_ms, line.text))
+            .collect(),
+    }
+}
+
+fn resolve_ms(hostname: &str, port: u16) -> Option<f64> {
+    let started = Instant::now();
+    (hostname, port).to_socket_addrs().ok()?;
+    Some(millis(started.elapsed()))
+}
+
+fn run_diagnosis(host: &Host) -> Result<SshDiagnosis, String> {
+    let hostname = host.hostname.trim();
+    let target = if host.alias.trim().is_empty() {
+        hostname
+    } else {
+        host.alias.trim()
+    };
+    if target.is_empty() {
+        return Err("hostname is required".to_string());
+    }
+    ensure_ssh_config()?;
+    let dns_ms = resolve_ms(hostname, host.port);
+
+    // A fresh, non-interactive login: a shared connection would skip the very
+    // steps being timed, and a prompt would time the user.
+    let started = Instant::now();
+    let mut child = Command::new("/usr/bin/ssh")
+        .arg("-vvv")
+        .arg("-F")
+        .arg(get_ssh_config_path())
+        .args(["-o", "ControlMaster=no", "-o", "ControlPath=none"])
+        .args(["-o", "BatchMode=yes", "-o", "StrictHostKeyChecking=yes"])
+        .arg("-o")
+        .arg(format!("ConnectTimeout={DIAGNOSE_CONNECT_TIMEOUT_SECS}"))
+        .args(["-o", "ConnectionAttempts=1", target, "exit"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    let stderr = child.stderr.take();
+    let reader = thread::spawn(move || {
+        let mut lines = Vec::new();
+        let Some(stderr) = stderr else {
+            return lines;
+        };
+        let mut stderr = BufReader::new(stderr);
+        let mut buf = Vec::new();
+        while let Ok(n) = stderr.read_until(b'\n', &mut buf) {
+            if n == 0 {
+                break;
+            }
+            if lines.len() < MAX_LOG_LINES {
+                lines.push(LogLine {
+                    at_ms: millis(started.elapsed()),
+                    text: String::from_utf8_lossy(&buf).trim_end().to_string(),
+                });
+            }
+            buf.clear();
+        }
+        lines
+    });
+    let status = operations::wait_child(&mut child)?;
+    let total_ms = millis(started.elapsed());
+    let lines = reader.join().unwrap_or_default();
+    Ok(diagnose(
+        &host.id,
+        hostname,
+        dns_ms,
+        &lines,
+        total_ms,
+        status.success(),
+    ))
+}
+
+/// Log in to the host once with `ssh -vvv` (no shared connection, no
+/// prompts) and explain where the time went: DNS, TCP connect, the wait for
+/// the server's banner, key exchange, GSSAPI, authentication and the remote
+/// session. Hosts that only accept a password are timed up to the point
+/// where ssh would ask for it. A failed login is a result with `error` set.
+#[tauri::command]
+pub async fn host_ssh_diagnose(host: Host, op_id: String) -> Result<SshDiagnosis, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        operations::run(op_id, "host_ssh_diagnose", DIAGNOSE_TIMEOUT, || {
+            run_diagnosis(&host)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diagnose, LogLine, SshPhase, SshPhaseTiming};
+
+    fn log(lines: &[(f64, &str)]) -> Vec<LogLine> {
+        lines
+            .iter()
+            .map(|(at_ms, text)| LogLine {
+                at_ms: *at_ms,
+                text: text.to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn explains_where_a_slow_login_spent_its_time() {
+        let lines = log(&[
+            (5.0, "OpenSSH_9.6p1, LibreSSL 3.3.6"),
+            (6.0, "debug2: resolving \"db.example.com\" port 22"),
+            (
+                40.0,
+                "debug1: Connecting to db.example.com [10.0.0.5] port 22.",
+            ),
+            (52.0, "debug1: Connection established."),
+            (
+                5052.0,
+                "debug1: Remote protocol version 2.0, remote software version OpenSSH_8.9",
+            ),
+            (5150.0, "debug1: SSH2_MSG_NEWKEYS received"),
+            (
+                5160.0,
+                "debug1: Next authentication method: gssapi-with-mic",
+            ),
+            (7160.0, "debug1: Next authentication method: publickey"),
+            (
+                7170.0,
+                "debug1: Offering public key: /home/me/.ssh/id_ed25519 ED25519",
+            ),
+            (
+                7300.0,
+                "debug1: Authenticated to db.example.com ([10.0.0.5]:22) using \"publickey\".",
+            ),
+        ]);
+        let result = diagnose("db", "db.example.com", Some(30.0), &lines, 7400.0, true);
+
+        assert!(result.connected);
+        assert_eq!(result.error, None);
+        assert_eq!(result.slowest, Some(SshPhase::Banner));
+        assert_eq!(
+            result.phases[..3],
+            [
+                SshPhaseTiming {
+                    phase: SshPhase::Dns,
+                    duration_ms: 34.0
+                },
+                SshPhaseTiming {
+                    phase: SshPhase::Connect,
+                    duration_ms: 12.0
+                },
+                SshPhaseTiming {
+                    phase: SshPhase::Banner,
+                    duration_ms: 5000.0
+                },
+            ]
+        );
+        assert!(result.phases.contains(&SshPhaseTiming {
+            phase: SshPhase::Gssapi,
+            duration_ms: 2000.0
+        }));
+        // Authentication less GSSAPI was quick, so it is not called out.
+        assert_eq!(result.findings.len(), 2);
+        assert!(result.findings[0].contains("UseDNS no"));
+        assert!(result.findings[1].contains("GSSAPIAuthentication no"));
+    }
+
+    #[test]
+    fn reports_why_a_login_failed() {
+        let lines = log(&[
+            (
+                1.0,
+                "debug1: Executing proxy command: exec ssh -W db:22 bastion",
+            ),
+            (
+                900.0,
+                "debug1: Remote protocol version 2.0, remote software version OpenSSH_8.9",
+            ),
+            (1000.0, "debug1: SSH2_MSG_NEWKEYS received"),
+            (1010.0, "debug1: Next authentication method: publickey"),
+            (1100.0, "debug1: No more authentication methods to try."),
+            (1101.0, "me@db: Permission denied (publickey,password)."),
+        ]);
+        let result = diagnose("db", "db", Some(1500.0), &lines, 1110.0, false);
+
+        assert!(!result.connected);
+        assert_eq!(
+            result.error.as_deref(),
+            Some("me@db: Permission denied (publickey,password).")
+        );
+        // ssh never resolved the name itself, so the resolver's time is used.
+        assert_eq!(result.phases[0].phase, SshPhase::Dns);
+        assert_eq!(result.slowest, Some(SshPhase::Dns));
+        assert!(result
+            .phases
+            .iter()
+            .all(|timing| timing.phase != SshPhase::Session));
+        assert_eq!(
+            result.log[5],
+            "  1101.0 me@db: Permission denied (publickey,password)."
+        );
+    }
+}
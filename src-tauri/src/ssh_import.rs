@@ -1,7 +1,12 @@
+use crate::ssh_config::is_allowed_extra_directive;
+use base64::Engine;
+use hmac::{Hmac, Mac};
 use serde::Serialize;
+use sha1::Sha1;
 use std::collections::HashSet;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::net::IpAddr;
+use std::path::{Component, Path, PathBuf};
 
 #[derive(Debug, Clone, Default)]
 struct HostOptions {
@@ -10,6 +15,10 @@ struct HostOptions {
     port: Option<u16>,
     identity_file: Option<String>,
     proxy_jump: Option<String>,
+    forward_agent: Option<bool>,
+    local_forwards: Vec<String>,
+    control_master: Option<String>,
+    extra_lines: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -21,26 +30,28 @@ pub struct SshImportCandidate {
     pub port: u16,
     pub identity_file: Option<String>,
     pub proxy_jump: Option<String>,
+    pub forward_agent: Option<bool>,
+    pub local_forwards: Vec<String>,
+    pub control_master: Option<String>,
+    /// Lines of the block not covered by the fields above, verbatim, so the
+    /// generated config keeps them. Directives that run local commands or
+    /// load code are left out (see `ssh_config::is_allowed_extra_directive`).
+    pub extra_config: Option<String>,
     pub source_path: String,
 }
 
+// OpenSSH's own limit on nested `Include`s.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
 #[tauri::command]
 pub fn ssh_config_scan_importable_hosts() -> Result<Vec<SshImportCandidate>, String> {
-    let files = discover_ssh_config_files();
-    if files.is_empty() {
-        return Ok(Vec::new());
-    }
-
     let mut candidates = Vec::new();
     let mut seen_aliases = HashSet::new();
 
-    for path in files {
-        let parsed = parse_config_file(&path)?;
-        for item in parsed {
-            let key = item.alias.to_lowercase();
-            if seen_aliases.insert(key) {
-                candidates.push(item);
-            }
+    for item in parse_ssh_config_files()? {
+        let key = item.alias.to_lowercase();
+        if seen_aliases.insert(key) {
+            candidates.push(item);
         }
     }
 
@@ -48,6 +59,150 @@ pub fn ssh_config_scan_importable_hosts() -> Result<Vec<SshImportCandidate>, Str
     Ok(candidates)
 }
 
+/// Hosts the user has connected to, from `~/.ssh/known_hosts`. Hashed entries
+/// (`HashKnownHosts yes`) cannot be read back; they are only listed when they
+/// match a host from the ssh config or the host list.
+#[tauri::command]
+pub fn ssh_known_hosts_scan_importable_hosts() -> Result<Vec<SshImportCandidate>, String> {
+    let Some(path) = dirs::home_dir().map(|home| home.join(".ssh").join("known_hosts")) else {
+        return Ok(Vec::new());
+    };
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+
+    let mut known_names: Vec<(String, u16)> = Vec::new();
+    for item in parse_ssh_config_files().unwrap_or_default() {
+        known_names.push((item.hostname, item.port));
+        known_names.push((item.alias, item.port));
+    }
+    for host in crate::host_store::hosts_load().unwrap_or_default() {
+        known_names.push((host.hostname.trim().to_string(), host.port));
+        known_names.push((host.alias.trim().to_string(), host.port));
+    }
+    known_names.retain(|(name, _)| !name.is_empty());
+
+    let mut candidates = parse_known_hosts(&content, &path.display().to_string(), &known_names);
+    candidates.sort_by_key(|candidate| candidate.alias.to_lowercase());
+    Ok(candidates)
+}
+
+// known_hosts writes `host` for port 22 and `[host]:port` otherwise.
+fn known_hosts_name(host: &str, port: u16) -> String {
+    if port == 22 {
+        host.to_string()
+    } else {
+        format!("[{host}]:{port}")
+    }
+}
+
+fn parse_known_hosts_name(name: &str) -> Option<(String, u16)> {
+    if let Some(rest) = name.strip_prefix('[') {
+        let (host, port) = rest.split_once("]:")?;
+        return Some((host.to_string(), port.parse().ok()?));
+    }
+    Some((name.to_string(), 22))
+}
+
+// `|1|base64(salt)|base64(hmac_sha1(salt, name))`
+fn hashed_name_matches(entry: &str, name: &str) -> bool {
+    let engine = base64::engine::general_purpose::STANDARD;
+    let Some((salt, hash)) = entry
+        .strip_prefix("|1|")
+        .and_then(|rest| rest.split_once('|'))
+    else {
+        return false;
+    };
+    let (Ok(salt), Ok(hash)) = (engine.decode(salt), engine.decode(hash)) else {
+        return false;
+    };
+    let Ok(mut mac) = Hmac::<Sha1>::new_from_slice(&salt) else {
+        return false;
+    };
+    mac.update(name.as_bytes());
+    mac.verify_slice(&hash).is_ok()
+}
+
+fn parse_known_hosts(
+    content: &str,
+    source_path: &str,
+    known_names: &[(String, u16)],
+) -> Vec<SshImportCandidate> {
+    let mut out = Vec::new();
+    let mut seen = HashSet::new();
+    for line in content.lines() {
+        let line = line.trim();
+        // `@cert-authority` and `@revoked` lines name patterns, not hosts.
+        if line.is_empty() || line.starts_with('#') || line.starts_with('@') {
+            continue;
+        }
+        let Some(names) = line.split_whitespace().next() else {
+            continue;
+        };
+        let mut hosts: Vec<(String, u16)> = Vec::new();
+        for name in names.split(',') {
+            if name.starts_with("|1|") {
+                hosts.extend(
+                    known_names
+                        .iter()
+                        .filter(|(host, port)| {
+                            hashed_name_matches(name, &known_hosts_name(host, *port))
+                        })
+                        .cloned(),
+                );
+            } else if is_importable_alias(name) {
+                hosts.extend(parse_known_hosts_name(name));
+            }
+        }
+        // With CheckHostIP a line lists the name and its address; the name
+        // is the one worth importing.
+        let Some((host, port)) = hosts
+            .iter()
+            .find(|(host, _)| host.parse::<IpAddr>().is_err())
+            .or_else(|| hosts.first())
+            .cloned()
+        else {
+            continue;
+        };
+        if !seen.insert((host.to_lowercase(), port)) {
+            continue;
+        }
+        out.push(SshImportCandidate {
+            alias: if port == 22 {
+                host.clone()
+            } else {
+                format!("{host}-{port}")
+            },
+            hostname: host,
+            user: String::new(),
+            port,
+            identity_file: None,
+            proxy_jump: None,
+            forward_agent: None,
+            local_forwards: Vec::new(),
+            control_master: None,
+            extra_config: None,
+            source_path: source_path.to_string(),
+        });
+    }
+    out
+}
+
+/// Candidates from every discovered config file, with `Include`s resolved.
+/// A file reached more than once (included twice, or also discovered on its
+/// own) is only read the first time.
+fn parse_ssh_config_files() -> Result<Vec<SshImportCandidate>, String> {
+    let Some(ssh_dir) = dirs::home_dir().map(|home| home.join(".ssh")) else {
+        return Ok(Vec::new());
+    };
+    let mut parser = ConfigParser::new(ssh_dir);
+    for path in discover_ssh_config_files() {
+        parser.parse_file(&path, 0)?;
+    }
+    Ok(parser.finish())
+}
+
 fn discover_ssh_config_files() -> Vec<PathBuf> {
     let mut paths = Vec::new();
     let home = match dirs::home_dir() {
@@ -111,97 +266,210 @@ fn collect_conf_files(dir: &Path) -> Vec<PathBuf> {
     files
 }
 
-fn parse_config_file(path: &Path) -> Result<Vec<SshImportCandidate>, String> {
-    let content = fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
-    let source_path = path.display().to_string();
-
-    let mut out = Vec::new();
-    let mut current_aliases: Vec<String> = Vec::new();
-    let mut current_options = HostOptions::default();
+/// Reads config files the way `ssh` does: an `Include`d file is read in
+/// place, so its lines continue the including file's current `Host` block
+/// and its own `Host` blocks come before the rest of the including file.
+struct ConfigParser {
+    ssh_dir: PathBuf,
+    seen_files: HashSet<PathBuf>,
+    out: Vec<SshImportCandidate>,
+    aliases: Vec<String>,
+    options: HostOptions,
+    source_path: String,
+}
 
-    let mut flush_current = |aliases: &mut Vec<String>, opts: &mut HostOptions| {
-        if aliases.is_empty() {
-            return;
+impl ConfigParser {
+    fn new(ssh_dir: PathBuf) -> Self {
+        Self {
+            ssh_dir,
+            seen_files: HashSet::new(),
+            out: Vec::new(),
+            aliases: Vec::new(),
+            options: HostOptions::default(),
+            source_path: String::new(),
         }
-        for alias in aliases.iter() {
+    }
+
+    fn flush(&mut self) {
+        for alias in self.aliases.drain(..) {
             if alias.trim().is_empty() {
                 continue;
             }
-            let hostname = opts
+            let hostname = self
+                .options
                 .hostname
                 .clone()
                 .filter(|v| !v.trim().is_empty())
                 .unwrap_or_else(|| alias.clone());
-            out.push(SshImportCandidate {
-                alias: alias.clone(),
+            self.out.push(SshImportCandidate {
+                alias,
                 hostname,
-                user: opts.user.clone().unwrap_or_default(),
-                port: opts.port.unwrap_or(22),
-                identity_file: opts.identity_file.clone(),
-                proxy_jump: opts.proxy_jump.clone(),
-                source_path: source_path.clone(),
+                user: self.options.user.clone().unwrap_or_default(),
+                port: self.options.port.unwrap_or(22),
+                identity_file: self.options.identity_file.clone(),
+                proxy_jump: self.options.proxy_jump.clone(),
+                forward_agent: self.options.forward_agent,
+                local_forwards: self.options.local_forwards.clone(),
+                control_master: self.options.control_master.clone(),
+                extra_config: (!self.options.extra_lines.is_empty())
+                    .then(|| self.options.extra_lines.join("\n")),
+                source_path: self.source_path.clone(),
             });
         }
-        aliases.clear();
-        *opts = HostOptions::default();
-    };
+        self.options = HostOptions::default();
+    }
 
-    for raw_line in content.lines() {
-        let line = strip_comments(raw_line);
-        let line = line.trim();
-        if line.is_empty() {
-            continue;
+    fn finish(mut self) -> Vec<SshImportCandidate> {
+        self.flush();
+        self.out
+    }
+
+    fn parse_file(&mut self, path: &Path, depth: usize) -> Result<(), String> {
+        if depth > MAX_INCLUDE_DEPTH {
+            return Err(format!("{}: too many nested Includes", path.display()));
+        }
+        // Also stops Include cycles.
+        let key = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        if !self.seen_files.insert(key) {
+            return Ok(());
         }
+        let content = fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        for raw_line in content.lines() {
+            self.parse_line(raw_line, path, depth)?;
+        }
+        Ok(())
+    }
 
+    fn parse_line(&mut self, raw_line: &str, path: &Path, depth: usize) -> Result<(), String> {
+        let line = strip_comments(raw_line);
         let mut parts = line.split_whitespace();
-        let key = match parts.next() {
-            Some(v) => v,
-            None => continue,
+        let Some(key) = parts.next() else {
+            return Ok(());
         };
         let rest = parts.collect::<Vec<_>>().join(" ");
 
+        if key.eq_ignore_ascii_case("Include") {
+            for pattern in rest.split_whitespace() {
+                for included in resolve_include(pattern, &self.ssh_dir) {
+                    self.parse_file(&included, depth + 1)?;
+                }
+            }
+            return Ok(());
+        }
+
         if key.eq_ignore_ascii_case("Host") {
-            flush_current(&mut current_aliases, &mut current_options);
-            let aliases: Vec<String> = rest
+            self.flush();
+            self.aliases = rest
                 .split_whitespace()
                 .filter(|token| is_importable_alias(token))
                 .map(|token| token.to_string())
                 .collect();
-            current_aliases = aliases;
-            continue;
+            self.source_path = path.display().to_string();
+            return Ok(());
         }
 
         if key.eq_ignore_ascii_case("Match") {
-            flush_current(&mut current_aliases, &mut current_options);
-            continue;
-        }
-
-        if current_aliases.is_empty() {
-            continue;
+            self.flush();
+            return Ok(());
         }
 
         let value = rest.trim();
-        if value.is_empty() {
-            continue;
+        if self.aliases.is_empty() || value.is_empty() {
+            return Ok(());
         }
 
+        let options = &mut self.options;
         if key.eq_ignore_ascii_case("HostName") {
-            current_options.hostname = Some(value.to_string());
+            options.hostname = Some(value.to_string());
         } else if key.eq_ignore_ascii_case("User") {
-            current_options.user = Some(value.to_string());
+            options.user = Some(value.to_string());
         } else if key.eq_ignore_ascii_case("Port") {
             if let Ok(port) = value.parse::<u16>() {
-                current_options.port = Some(port);
+                options.port = Some(port);
             }
         } else if key.eq_ignore_ascii_case("IdentityFile") {
-            current_options.identity_file = Some(value.to_string());
+            options.identity_file = Some(value.to_string());
         } else if key.eq_ignore_ascii_case("ProxyJump") {
-            current_options.proxy_jump = Some(value.to_string());
+            options.proxy_jump = Some(value.to_string());
+        } else if key.eq_ignore_ascii_case("ForwardAgent")
+            && matches!(value.to_ascii_lowercase().as_str(), "yes" | "no")
+        {
+            options.forward_agent = Some(value.eq_ignore_ascii_case("yes"));
+        } else if key.eq_ignore_ascii_case("LocalForward") {
+            options.local_forwards.push(value.to_string());
+        } else if key.eq_ignore_ascii_case("ControlMaster") {
+            options.control_master = Some(value.to_string());
+        } else if is_allowed_extra_directive(key.split('=').next().unwrap_or(key)) {
+            options.extra_lines.push(line.trim().to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Files matched by one `Include` argument, in glob(3) order. Relative paths
+/// are relative to `~/.ssh`, as `ssh` treats them in user configs; patterns
+/// that match nothing are ignored, as they are by `ssh`.
+fn resolve_include(pattern: &str, ssh_dir: &Path) -> Vec<PathBuf> {
+    let pattern = pattern.trim_matches('"');
+    let path = match pattern.strip_prefix("~/") {
+        Some(rest) => match dirs::home_dir() {
+            Some(home) => home.join(rest),
+            None => return Vec::new(),
+        },
+        None => ssh_dir.join(pattern),
+    };
+
+    let mut matches = vec![PathBuf::new()];
+    for component in path.components() {
+        let name = component.as_os_str().to_string_lossy();
+        if !matches!(component, Component::Normal(_)) || !name.contains(['*', '?']) {
+            for path in &mut matches {
+                path.push(component);
+            }
+            continue;
         }
+        let mut next = Vec::new();
+        for dir in &matches {
+            let Ok(entries) = fs::read_dir(dir) else {
+                continue;
+            };
+            let mut names: Vec<String> = entries
+                .flatten()
+                .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+                .filter(|entry| !entry.starts_with('.') || name.starts_with('.'))
+                .filter(|entry| wildcard_match(&name, entry))
+                .collect();
+            names.sort();
+            next.extend(names.into_iter().map(|entry| dir.join(entry)));
+        }
+        matches = next;
     }
+    matches.retain(|path| path.is_file());
+    matches
+}
 
-    flush_current(&mut current_aliases, &mut current_options);
-    Ok(out)
+/// `*` and `?` matching of one path component.
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
 }
 
 fn strip_comments(input: &str) -> String {
@@ -235,3 +503,129 @@ fn is_importable_alias(alias: &str) -> bool {
     }
     !alias.contains('*') && !alias.contains('?') && !alias.contains('!')
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_known_hosts, wildcard_match, ConfigParser, Hmac, Mac, Sha1};
+    use base64::Engine;
+    use std::fs;
+
+    fn hashed(name: &str) -> String {
+        let engine = base64::engine::general_purpose::STANDARD;
+        let salt = b"0123456789abcdefghij";
+        let mut mac = Hmac::<Sha1>::new_from_slice(salt).unwrap();
+        mac.update(name.as_bytes());
+        format!(
+            "|1|{}|{}",
+            engine.encode(salt),
+            engine.encode(mac.finalize().into_bytes())
+        )
+    }
+
+    #[test]
+    fn imports_plain_and_resolvable_hashed_entries() {
+        let content = format!(
+            "web.example.com,203.0.113.7 ssh-ed25519 AAAA\n\
+             [git.example.com]:2222 ssh-ed25519 AAAA\n\
+             198.51.100.4 ecdsa-sha2-nistp256 AAAA\n\
+             WEB.example.com ssh-rsa AAAA\n\
+             @cert-authority *.example.com ssh-ed25519 AAAA\n\
+             {} ssh-ed25519 AAAA\n\
+             {} ssh-ed25519 AAAA\n",
+            hashed("[db.internal]:2200"),
+            hashed("unknown.internal"),
+        );
+        let known = vec![("db.internal".to_string(), 2200)];
+        let candidates = parse_known_hosts(&content, "known_hosts", &known);
+        let hosts: Vec<(&str, &str, u16)> = candidates
+            .iter()
+            .map(|c| (c.alias.as_str(), c.hostname.as_str(), c.port))
+            .collect();
+        assert_eq!(
+            hosts,
+            vec![
+                ("web.example.com", "web.example.com", 22),
+                ("git.example.com-2222", "git.example.com", 2222),
+                ("198.51.100.4", "198.51.100.4", 22),
+                ("db.internal-2200", "db.internal", 2200),
+            ]
+        );
+    }
+
+    #[test]
+    fn resolves_includes_recursively() {
+        let dir = std::env::temp_dir().join(format!("xtermius-include-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join("corp/teams")).unwrap();
+        fs::write(
+            dir.join("config"),
+            "Include corp/*.conf\nHost personal\n  HostName home.example.com\n",
+        )
+        .unwrap();
+        // Relative to ~/.ssh, not to the including file.
+        fs::write(
+            dir.join("corp/base.conf"),
+            "Host bastion\n  HostName bastion.corp.example.com\n  Include corp/teams/*\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("corp/teams/db"),
+            "  User dba\nHost db-1\n  HostName 10.0.0.5\n  Include config\n",
+        )
+        .unwrap();
+        fs::write(dir.join("corp/teams/.hidden"), "Host hidden\n").unwrap();
+
+        let mut parser = ConfigParser::new(dir.clone());
+        parser.parse_file(&dir.join("config"), 0).unwrap();
+        let hosts: Vec<(String, String, String)> = parser
+            .finish()
+            .into_iter()
+            .map(|c| (c.alias, c.hostname, c.user))
+            .collect();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            hosts,
+            vec![
+                (
+                    "bastion".to_string(),
+                    "bastion.corp.example.com".to_string(),
+                    "dba".to_string()
+                ),
+                ("db-1".to_string(), "10.0.0.5".to_string(), String::new()),
+                (
+                    "personal".to_string(),
+                    "home.example.com".to_string(),
+                    String::new()
+                ),
+            ]
+        );
+
+        assert!(wildcard_match("*.conf", "work.conf"));
+        assert!(wildcard_match("team-?", "team-a"));
+        assert!(!wildcard_match("*.conf", "work.conf.bak"));
+    }
+
+    #[test]
+    fn keeps_other_directives_as_extra_config() {
+        let dir = std::env::temp_dir().join(format!("xtermius-extra-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("config"),
+            "Host app\n  HostName app.internal\n  ForwardAgent yes\n  LocalForward 5432 db:5432\n  \
+             ControlMaster auto\n  ControlPersist 10m\n  SendEnv LANG LC_*\n  \
+             ProxyCommand nc %h %p\n  ForwardAgent $SSH_AUTH_SOCK\n",
+        )
+        .unwrap();
+
+        let mut parser = ConfigParser::new(dir.clone());
+        parser.parse_file(&dir.join("config"), 0).unwrap();
+        let candidate = parser.finish().pop().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(candidate.forward_agent, Some(true));
+        assert_eq!(candidate.local_forwards, vec!["5432 db:5432"]);
+        assert_eq!(candidate.control_master.as_deref(), Some("auto"));
+        assert_eq!(
+            candidate.extra_config.as_deref(),
+            Some("ControlPersist 10m\nSendEnv LANG LC_*\nForwardAgent $SSH_AUTH_SOCK")
+        );
+    }
+}
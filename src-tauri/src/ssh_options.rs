@@ -0,0 +1,437 @@
+// Checks a host's extra config lines against the options OpenSSH knows, so a
+// typo is caught when the host is saved instead of when ssh refuses the
+// generated config (an unknown option there breaks every host, not just one).
+
+use crate::models::Host;
+use serde::Serialize;
+use OptionValue::{Any, Number, OneOf, YesNo};
+
+// Older than anything tracked below; ssh releases before it are not told apart.
+const BASELINE: (u32, u32) = (0, 0);
+// How far apart a typo and an option name may be to suggest the option.
+const MAX_SUGGESTION_DISTANCE: usize = 3;
+
+#[derive(Debug, Clone, Copy)]
+enum OptionValue {
+    Any,
+    YesNo,
+    Number,
+    OneOf(&'static [&'static str]),
+}
+
+struct SshOption {
+    name: &'static str,
+    /// The OpenSSH release that added the option.
+    since: (u32, u32),
+    value: OptionValue,
+}
+
+const fn option(name: &'static str, since: (u32, u32), value: OptionValue) -> SshOption {
+    SshOption { name, since, value }
+}
+
+/// Client options of `ssh_config(5)`, with the release that added each.
+const OPENSSH_OPTIONS: &[SshOption] = &[
+    option("AddKeysToAgent", (7, 2), Any),
+    option("AddressFamily", BASELINE, OneOf(&["any", "inet", "inet6"])),
+    option("BatchMode", BASELINE, YesNo),
+    option("BindAddress", BASELINE, Any),
+    option("BindInterface", (7, 7), Any),
+    option("CanonicalDomains", (6, 5), Any),
+    option("CanonicalizeFallbackLocal", (6, 5), YesNo),
+    option(
+        "CanonicalizeHostname",
+        (6, 5),
+        OneOf(&["yes", "no", "always", "none"]),
+    ),
+    option("CanonicalizeMaxDots", (6, 5), Number),
+    option("CanonicalizePermittedCNAMEs", (6, 5), Any),
+    option("CASignatureAlgorithms", (8, 2), Any),
+    option("CertificateFile", (7, 2), Any),
+    option("ChallengeResponseAuthentication", BASELINE, YesNo),
+    option("ChannelTimeout", (9, 6), Any),
+    option("CheckHostIP", BASELINE, YesNo),
+    option("Ciphers", BASELINE, Any),
+    option("ClearAllForwardings", BASELINE, YesNo),
+    option("Compression", BASELINE, YesNo),
+    option("ConnectionAttempts", BASELINE, Number),
+    option("ConnectTimeout", BASELINE, Any),
+    option(
+        "ControlMaster",
+        BASELINE,
+        OneOf(&["yes", "no", "ask", "auto", "autoask"]),
+    ),
+    option("ControlPath", BASELINE, Any),
+    option("ControlPersist", BASELINE, Any),
+    option("DynamicForward", BASELINE, Any),
+    option("EnableEscapeCommandline", (9, 2), YesNo),
+    option("EnableSSHKeysign", BASELINE, YesNo),
+    option("EscapeChar", BASELINE, Any),
+    option("ExitOnForwardFailure", BASELINE, YesNo),
+    option("FingerprintHash", (6, 8), OneOf(&["md5", "sha256"])),
+    option("ForkAfterAuthentication", (8, 7), YesNo),
+    option("ForwardAgent", BASELINE, Any),
+    option("ForwardX11", BASELINE, YesNo),
+    option("ForwardX11Timeout", BASELINE, Any),
+    option("ForwardX11Trusted", BASELINE, YesNo),
+    option("GatewayPorts", BASELINE, YesNo),
+    option("GlobalKnownHostsFile", BASELINE, Any),
+    option("GSSAPIAuthentication", BASELINE, YesNo),
+    option("GSSAPIDelegateCredentials", BASELINE, YesNo),
+    // The GSSAPI key exchange options come from a patch most Linux
+    // distributions carry.
+    option("GSSAPIClientIdentity", BASELINE, Any),
+    option("GSSAPIKeyExchange", BASELINE, YesNo),
+    option("GSSAPIKexAlgorithms", BASELINE, Any),
+    option("GSSAPIRenewalForcesRekey", BASELINE, YesNo),
+    option("GSSAPIServerIdentity", BASELINE, Any),
+    option("GSSAPITrustDns", BASELINE, YesNo),
+    option("HashKnownHosts", BASELINE, YesNo),
+    option("Host", BASELINE, Any),
+    option("HostbasedAcceptedAlgorithms", (8, 5), Any),
+    option("HostbasedAuthentication", BASELINE, YesNo),
+    option("HostbasedKeyTypes", (7, 0), Any),
+    option("HostKeyAlgorithms", BASELINE, Any),
+    option("HostKeyAlias", BASELINE, Any),
+    option("Hostname", BASELINE, Any),
+    option("IdentitiesOnly", BASELINE, YesNo),
+    option("IdentityAgent", (7, 3), Any),
+    option("IdentityFile", BASELINE, Any),
+    option("IgnoreUnknown", (6, 3), Any),
+    option("Include", (7, 3), Any),
+    option("IPQoS", BASELINE, Any),
+    option("KbdInteractiveAuthentication", BASELINE, YesNo),
+    option("KbdInteractiveDevices", BASELINE, Any),
+    option("KexAlgorithms", BASELINE, Any),
+    option("KnownHostsCommand", (8, 5), Any),
+    option("LocalCommand", BASELINE, Any),
+    option("LocalForward", BASELINE, Any),
+    option(
+        "LogLevel",
+        BASELINE,
+        OneOf(&[
+            "quiet", "fatal", "error", "info", "verbose", "debug", "debug1", "debug2", "debug3",
+        ]),
+    ),
+    option("LogVerbose", (8, 5), Any),
+    option("MACs", BASELINE, Any),
+    option("Match", BASELINE, Any),
+    option("NoHostAuthenticationForLocalhost", BASELINE, YesNo),
+    option("NumberOfPasswordPrompts", BASELINE, Number),
+    option("ObscureKeystrokeTiming", (9, 5), Any),
+    option("PasswordAuthentication", BASELINE, YesNo),
+    option("PermitLocalCommand", BASELINE, YesNo),
+    option("PermitRemoteOpen", (8, 5), Any),
+    option("PKCS11Provider", BASELINE, Any),
+    option("Port", BASELINE, Number),
+    option("PreferredAuthentications", BASELINE, Any),
+    option("ProxyCommand", BASELINE, Any),
+    option("ProxyJump", (7, 3), Any),
+    option("ProxyUseFdpass", (6, 5), YesNo),
+    option("PubkeyAcceptedAlgorithms", (8, 5), Any),
+    option("PubkeyAcceptedKeyTypes", (7, 0), Any),
+    option(
+        "PubkeyAuthentication",
+        BASELINE,
+        OneOf(&["yes", "no", "unbound", "host-bound"]),
+    ),
+    option("RefuseConnection", (10, 0), YesNo),
+    option("RekeyLimit", BASELINE, Any),
+    option("RemoteCommand", (7, 6), Any),
+    option("RemoteForward", BASELINE, Any),
+    option(
+        "RequestTTY",
+        BASELINE,
+        OneOf(&["yes", "no", "force", "auto"]),
+    ),
+    option("RequiredRSASize", (9, 1), Number),
+    option("RevokedHostKeys", (6, 8), Any),
+    option("SecurityKeyProvider", (8, 2), Any),
+    option("SendEnv", BASELINE, Any),
+    option("ServerAliveCountMax", BASELINE, Number),
+    option("ServerAliveInterval", BASELINE, Any),
+    option(
+        "SessionType",
+        (8, 7),
+        OneOf(&["none", "subsystem", "default"]),
+    ),
+    option("SetEnv", (7, 8), Any),
+    option("StdinNull", (8, 7), YesNo),
+    option("StreamLocalBindMask", BASELINE, Any),
+    option("StreamLocalBindUnlink", BASELINE, YesNo),
+    option(
+        "StrictHostKeyChecking",
+        BASELINE,
+        OneOf(&["yes", "no", "ask", "accept-new", "off"]),
+    ),
+    option("SyslogFacility", BASELINE, Any),
+    option("Tag", (9, 4), Any),
+    option("TCPKeepAlive", BASELINE, YesNo),
+    option(
+        "Tunnel",
+        BASELINE,
+        OneOf(&["yes", "no", "point-to-point", "ethernet"]),
+    ),
+    option("TunnelDevice", BASELINE, Any),
+    option("UpdateHostKeys", (6, 8), OneOf(&["yes", "no", "ask"])),
+    option("User", BASELINE, Any),
+    option("UserKnownHostsFile", BASELINE, Any),
+    option("VerifyHostKeyDNS", BASELINE, OneOf(&["yes", "no", "ask"])),
+    option("VisualHostKey", BASELINE, YesNo),
+    option("XAuthLocation", BASELINE, Any),
+];
+
+/// Options ssh still accepts but ignores, with a warning.
+const DEPRECATED_OPTIONS: &[&str] = &[
+    "Cipher",
+    "CompressionLevel",
+    "DSAAuthentication",
+    "FallBackToRsh",
+    "Protocol",
+    "RhostsRSAAuthentication",
+    "RSAAuthentication",
+    "UsePrivilegedPort",
+    "UseRoaming",
+    "UseRsh",
+];
+
+/// Only Apple's build of ssh knows it; others refuse the config.
+const APPLE_ONLY_OPTION: &str = "UseKeychain";
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SshOptionIssue {
+    pub line: String,
+    pub message: String,
+    /// ssh would refuse the config over it, so saving is refused too.
+    pub fatal: bool,
+}
+
+/// `9.6`, `9.6p1` or `OpenSSH_9.6p1, OpenSSL ...` (as `ssh -V` prints it).
+pub(crate) fn parse_openssh_version(input: &str) -> Result<(u32, u32), String> {
+    let trimmed = input.trim();
+    let version = trimmed
+        .strip_prefix("OpenSSH_")
+        .unwrap_or(trimmed)
+        .split(|ch: char| !(ch.is_ascii_digit() || ch == '.'))
+        .next()
+        .unwrap_or_default();
+    let mut parts = version.split('.');
+    let major = parts.next().and_then(|part| part.parse().ok());
+    let minor = parts.next().and_then(|part| part.parse().ok());
+    match (major, minor, parts.next()) {
+        (Some(major), Some(minor), None) => Ok((major, minor)),
+        _ => Err(format!("Invalid OpenSSH version: {trimmed:?}")),
+    }
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = previous[j] + usize::from(ca != *cb);
+            current.push(substitute.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+pub(crate) fn is_known_option(keyword: &str) -> bool {
+    OPENSSH_OPTIONS
+        .iter()
+        .any(|option| option.name.eq_ignore_ascii_case(keyword))
+}
+
+fn suggestion(keyword: &str) -> Option<&'static str> {
+    let keyword = keyword.to_ascii_lowercase();
+    OPENSSH_OPTIONS
+        .iter()
+        .map(|option| {
+            (
+                edit_distance(&keyword, &option.name.to_ascii_lowercase()),
+                option.name,
+            )
+        })
+        .filter(|(distance, _)| *distance <= MAX_SUGGESTION_DISTANCE)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, name)| name)
+}
+
+fn check_value(option: &SshOption, value: &str) -> Option<String> {
+    if value.is_empty() {
+        return Some(format!("{} needs a value", option.name));
+    }
+    let first = value.split_whitespace().next().unwrap_or_default();
+    match option.value {
+        Any => None,
+        YesNo if first.eq_ignore_ascii_case("yes") || first.eq_ignore_ascii_case("no") => None,
+        YesNo => Some(format!("{} must be yes or no, got {first:?}", option.name)),
+        Number if first.parse::<u32>().is_ok() => None,
+        Number => Some(format!("{} must be a number, got {first:?}", option.name)),
+        OneOf(allowed) if allowed.iter().any(|name| name.eq_ignore_ascii_case(first)) => None,
+        OneOf(allowed) => Some(format!(
+            "{} must be one of {}, got {first:?}",
+            option.name,
+            allowed.join(", ")
+        )),
+    }
+}
+
+fn check_line(line: &str, version: Option<(u32, u32)>) -> Option<SshOptionIssue> {
+    let split = line
+        .find(|ch: char| ch.is_whitespace() || ch == '=')
+        .unwrap_or(line.len());
+    let keyword = &line[..split];
+    let value = line[split..]
+        .trim_start()
+        .strip_prefix('=')
+        .unwrap_or(line[split..].trim_start())
+        .trim();
+    let issue = |message: String, fatal: bool| {
+        Some(SshOptionIssue {
+            line: line.to_string(),
+            message,
+            fatal,
+        })
+    };
+
+    if let Some(option) = OPENSSH_OPTIONS
+        .iter()
+        .find(|option| option.name.eq_ignore_ascii_case(keyword))
+    {
+        if let Some(version) = version.filter(|version| *version < option.since) {
+            return issue(
+                format!(
+                    "{} needs OpenSSH {}.{} or later (configured: {}.{})",
+                    option.name, option.since.0, option.since.1, version.0, version.1
+                ),
+                true,
+            );
+        }
+        return check_value(option, value).and_then(|message| issue(message, true));
+    }
+    if DEPRECATED_OPTIONS
+        .iter()
+        .any(|name| name.eq_ignore_ascii_case(keyword))
+    {
+        return issue(format!("{keyword} is deprecated and ignored by ssh"), false);
+    }
+    if keyword.eq_ignore_ascii_case(APPLE_ONLY_OPTION) {
+        return issue(
+            format!("{APPLE_ONLY_OPTION} is only understood by the ssh that ships with macOS"),
+            !cfg!(target_os = "macos"),
+        );
+    }
+    let message = match suggestion(keyword) {
+        Some(name) => format!("Unknown SSH option {keyword:?}; did you mean {name}?"),
+        None => format!("Unknown SSH option {keyword:?}"),
+    };
+    issue(message, true)
+}
+
+/// Problems with the lines of an extra config, checked against `version`
+/// (every known option when `None`).
+fn check_extra_config(extra_config: &str, version: Option<(u32, u32)>) -> Vec<SshOptionIssue> {
+    extra_config
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| check_line(line, version))
+        .collect()
+}
+
+fn configured_version() -> Result<Option<(u32, u32)>, String> {
+    crate::host_store::settings_load()?
+        .openssh_version
+        .as_deref()
+        .map(str::trim)
+        .filter(|version| !version.is_empty())
+        .map(parse_openssh_version)
+        .transpose()
+}
+
+/// Refuse hosts whose extra config ssh would reject, naming the host and the
+/// first offending line.
+pub(crate) fn validate_hosts_extra_config(hosts: &[Host]) -> Result<(), String> {
+    let hosts_with_extra: Vec<&Host> = hosts
+        .iter()
+        .filter(|host| !host.deleted && host.extra_config.is_some())
+        .collect();
+    if hosts_with_extra.is_empty() {
+        return Ok(());
+    }
+    let version = configured_version()?;
+    for host in hosts_with_extra {
+        let extra_config = host.extra_config.as_deref().unwrap_or_default();
+        if let Some(issue) = check_extra_config(extra_config, version)
+            .into_iter()
+            .find(|issue| issue.fatal)
+        {
+            let name = if host.name.trim().is_empty() {
+                host.hostname.trim()
+            } else {
+                host.name.trim()
+            };
+            return Err(format!("{name}: {}", issue.message));
+        }
+    }
+    Ok(())
+}
+
+/// Check extra config lines as the host editor types them.
+#[tauri::command]
+pub fn ssh_options_check(extra_config: String) -> Result<Vec<SshOptionIssue>, String> {
+    Ok(check_extra_config(&extra_config, configured_version()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_extra_config, parse_openssh_version};
+
+    #[test]
+    fn flags_typos_values_and_options_too_new() {
+        let extra = "# keep alive\n\
+                     ServerAliveInterval 15\n\
+                     Compresion yes\n\
+                     StrictHostKeyChecking=maybe\n\
+                     Tag prod\n\
+                     UseRoaming no\n\
+                     LogLevel VERBOSE\n";
+        let issues = check_extra_config(extra, Some((9, 2)));
+        let messages: Vec<(&str, bool)> = issues
+            .iter()
+            .map(|issue| (issue.message.as_str(), issue.fatal))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                (
+                    "Unknown SSH option \"Compresion\"; did you mean Compression?",
+                    true
+                ),
+                (
+                    "StrictHostKeyChecking must be one of yes, no, ask, accept-new, off, got \"maybe\"",
+                    true
+                ),
+                ("Tag needs OpenSSH 9.4 or later (configured: 9.2)", true),
+                ("UseRoaming is deprecated and ignored by ssh", false),
+            ]
+        );
+        assert_eq!(issues[1].line, "StrictHostKeyChecking=maybe");
+        assert!(check_extra_config("Tag prod\nPort 2222", None).is_empty());
+        assert_eq!(
+            check_extra_config("Port twenty-two", None)[0].message,
+            "Port must be a number, got \"twenty-two\""
+        );
+
+        assert_eq!(parse_openssh_version("9.6"), Ok((9, 6)));
+        assert_eq!(
+            parse_openssh_version("OpenSSH_9.2p1 Debian-2+deb12u6, OpenSSL 3.0.19"),
+            Ok((9, 2))
+        );
+        assert!(parse_openssh_version("nine").is_err());
+    }
+}
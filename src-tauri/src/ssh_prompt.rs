@@ -0,0 +1,242 @@
+// Keyboard-interactive challenges (OTP and other 2FA codes) that a saved
+// password cannot answer. They are sent to the UI as `ssh:prompt` and answered
+// with `ssh_prompt_answer`, for terminal sessions and for probes alike.
+
+use crate::pty::PtyState;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::{self, DirBuilder, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Runtime};
+
+#[cfg(unix)]
+use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+
+const MAIN_WINDOW_LABEL: &str = "main";
+/// Lower-case words that mark a prompt as a one-time challenge rather than
+/// the account password.
+pub(crate) const CHALLENGE_NEEDLES: &[&str] = &[
+    "verification",
+    "one-time",
+    "otp",
+    "2fa",
+    "two-factor",
+    "two-step",
+    "passcode",
+    "token",
+    "code:",
+];
+const RELAY_POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// How long the askpass script waits for an answer before failing the login.
+const RELAY_ANSWER_TIMEOUT_SECONDS: u32 = 120;
+const RELAY_PROMPT_FILE: &str = "prompt";
+const RELAY_ANSWER_FILE: &str = "answer";
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct SshPromptPayload {
+    /// A PTY session id, or the id of a probe's relay.
+    pub session_id: String,
+    pub host_id: Option<String>,
+    pub prompt: String,
+}
+
+type PromptEmitFn = Box<dyn Fn(&SshPromptPayload) + Send + Sync>;
+
+fn prompt_emitter() -> &'static OnceLock<PromptEmitFn> {
+    static EMITTER: OnceLock<PromptEmitFn> = OnceLock::new();
+    &EMITTER
+}
+
+// Relay id -> directory of the askpass script waiting for the answer.
+fn waiting_relays() -> &'static Mutex<HashMap<String, PathBuf>> {
+    static RELAYS: OnceLock<Mutex<HashMap<String, PathBuf>>> = OnceLock::new();
+    RELAYS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Send prompts to the main window. Until then probes cannot ask, and their
+/// challenges time out.
+pub(crate) fn init<R: Runtime>(app: AppHandle<R>) {
+    let _ = prompt_emitter().set(Box::new(move |payload| {
+        let _ = app.emit_to(MAIN_WINDOW_LABEL, "ssh:prompt", payload);
+    }));
+}
+
+pub(crate) fn emit_prompt(payload: SshPromptPayload) {
+    if let Some(emit) = prompt_emitter().get() {
+        emit(&payload);
+    }
+}
+
+/// The challenge on the last line of `text`, e.g. `Verification code:`.
+pub(crate) fn challenge_prompt(text: &str) -> Option<String> {
+    let normalized = text.replace('\r', "\n");
+    let line = normalized.rsplit('\n').next().unwrap_or("").trim();
+    let lower = line.to_ascii_lowercase();
+    if !lower.ends_with(':')
+        || !CHALLENGE_NEEDLES
+            .iter()
+            .any(|needle| lower.contains(needle))
+    {
+        return None;
+    }
+    Some(line.to_string())
+}
+
+/// A private directory through which one probe's askpass script hands its
+/// prompt over and waits for the answer. Removed, with anything waiting in
+/// it, when dropped.
+pub(crate) struct PromptRelay {
+    id: String,
+    dir: PathBuf,
+    stop: Arc<AtomicBool>,
+}
+
+impl PromptRelay {
+    pub(crate) fn start(host_id: &str) -> Result<Self, String> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let dir = std::env::temp_dir().join(format!("xtermius-prompt-{id}"));
+        let mut builder = DirBuilder::new();
+        #[cfg(unix)]
+        {
+            builder.mode(0o700);
+        }
+        builder.create(&dir).map_err(|e| e.to_string())?;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let watch = (id.clone(), dir.clone(), stop.clone(), host_id.to_string());
+        thread::Builder::new()
+            .name("ssh-prompt-relay".to_string())
+            .spawn(move || {
+                let (id, dir, stop, host_id) = watch;
+                while !stop.load(Ordering::Relaxed) {
+                    thread::sleep(RELAY_POLL_INTERVAL);
+                    let path = dir.join(RELAY_PROMPT_FILE);
+                    let Ok(prompt) = fs::read_to_string(&path) else {
+                        continue;
+                    };
+                    let _ = fs::remove_file(&path);
+                    if let Ok(mut relays) = waiting_relays().lock() {
+                        relays.insert(id.clone(), dir.clone());
+                    }
+                    emit_prompt(SshPromptPayload {
+                        session_id: id.clone(),
+                        host_id: Some(host_id.clone()),
+                        prompt: prompt.trim().to_string(),
+                    });
+                }
+            })
+            .map_err(|e| e.to_string())?;
+        Ok(Self { id, dir, stop })
+    }
+
+    pub(crate) fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Shell lines that hand `$1` to the app and print its answer.
+    pub(crate) fn script_lines(&self) -> String {
+        let dir = crate::host_probe::shell_quote(&self.dir.to_string_lossy());
+        format!(
+            "printf '%s' \"$1\" > {dir}/{RELAY_PROMPT_FILE}.tmp && \
+             mv {dir}/{RELAY_PROMPT_FILE}.tmp {dir}/{RELAY_PROMPT_FILE} || exit 1\n\
+             i=0\n\
+             while [ ! -f {dir}/{RELAY_ANSWER_FILE} ]; do\n\
+             \x20 i=$((i + 1))\n\
+             \x20 [ \"$i\" -gt {RELAY_ANSWER_TIMEOUT_SECONDS} ] && exit 1\n\
+             \x20 sleep 1\n\
+             done\n\
+             cat {dir}/{RELAY_ANSWER_FILE}\n\
+             rm -f {dir}/{RELAY_ANSWER_FILE}\n"
+        )
+    }
+}
+
+impl Drop for PromptRelay {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Ok(mut relays) = waiting_relays().lock() {
+            relays.remove(&self.id);
+        }
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// `Ok(false)` when no probe is waiting under `session_id`.
+fn answer_relay(session_id: &str, answer: &str) -> Result<bool, String> {
+    let dir = waiting_relays()
+        .lock()
+        .map_err(|_| "prompt relays poisoned".to_string())?
+        .remove(session_id);
+    let Some(dir) = dir else {
+        return Ok(false);
+    };
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(dir.join(RELAY_ANSWER_FILE))
+        .map_err(|e| e.to_string())?;
+    file.write_all(format!("{}\n", answer.trim_end_matches(['\r', '\n'])).as_bytes())
+        .map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// Answer an `ssh:prompt`, for a terminal session or a probe.
+#[tauri::command]
+pub fn ssh_prompt_answer(
+    session_id: String,
+    answer: String,
+    state: tauri::State<'_, PtyState>,
+) -> Result<(), String> {
+    if answer_relay(&session_id, &answer)? {
+        return Ok(());
+    }
+    crate::pty::write_prompt_answer(&state, &session_id, &answer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{answer_relay, challenge_prompt, waiting_relays, PromptRelay};
+    use std::fs;
+
+    #[test]
+    fn recognizes_challenges_but_not_passwords() {
+        assert_eq!(
+            challenge_prompt("Last login: never\r\nVerification code: ").as_deref(),
+            Some("Verification code:")
+        );
+        assert!(challenge_prompt("(deploy@web1) One-time password (OATH) for `deploy':").is_some());
+        assert!(challenge_prompt("deploy@web1's password:").is_none());
+        assert!(challenge_prompt("Enter the token you received").is_none());
+    }
+
+    #[test]
+    fn relays_answer_to_waiting_script() {
+        let relay = PromptRelay::start("h1").unwrap();
+        assert!(relay.dir.is_dir());
+        assert!(!answer_relay(&relay.id, "123456").unwrap());
+
+        waiting_relays()
+            .lock()
+            .unwrap()
+            .insert(relay.id.clone(), relay.dir.clone());
+        assert!(answer_relay(&relay.id, "123456\n").unwrap());
+        assert_eq!(
+            fs::read_to_string(relay.dir.join("answer")).unwrap(),
+            "123456\n"
+        );
+        assert!(relay.script_lines().contains("-gt 120 ] && exit 1"));
+
+        let dir = relay.dir.clone();
+        drop(relay);
+        assert!(!dir.exists());
+    }
+}
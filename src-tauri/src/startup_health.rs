@@ -0,0 +1,161 @@
+use crate::host_store::{ensure_config_dir, ensure_hosts_schema, get_config_dir, open_hosts_db};
+use serde::Serialize;
+use std::fs::{self, OpenOptions};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use tauri::{AppHandle, Emitter, Runtime};
+
+const SSH_BINARY: &str = "/usr/bin/ssh";
+const MAIN_WINDOW_LABEL: &str = "main";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupCheck {
+    pub name: &'static str,
+    pub ok: bool,
+    pub detail: Option<String>,
+    /// What stops working while this check fails, for the degraded-mode banner.
+    pub impact: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupStatus {
+    pub degraded: bool,
+    pub safe_mode: bool,
+    pub checks: Vec<StartupCheck>,
+}
+
+fn run_check(
+    name: &'static str,
+    impact: &'static str,
+    check: impl FnOnce() -> Result<(), String>,
+) -> StartupCheck {
+    let result = check();
+    StartupCheck {
+        name,
+        ok: result.is_ok(),
+        detail: result.err(),
+        impact,
+    }
+}
+
+fn summarize(checks: Vec<StartupCheck>) -> StartupStatus {
+    StartupStatus {
+        degraded: checks.iter().any(|check| !check.ok),
+        safe_mode: crate::safe_mode::is_enabled(),
+        checks,
+    }
+}
+
+fn check_config_dir_writable() -> Result<(), String> {
+    ensure_config_dir()?;
+    let probe = get_config_dir().join(format!(".write-check-{}", uuid::Uuid::new_v4()));
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe)
+        .map_err(|e| format!("{}: {e}", get_config_dir().display()))?;
+    let _ = fs::remove_file(&probe);
+    Ok(())
+}
+
+fn check_hosts_db() -> Result<(), String> {
+    let conn = open_hosts_db()?;
+    ensure_hosts_schema(&conn)?;
+    conn.query_row("SELECT COUNT(*) FROM hosts", [], |row| row.get::<_, i64>(0))
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+fn check_ssh_binary() -> Result<(), String> {
+    if !Path::new(SSH_BINARY).exists() {
+        return Err(format!("{SSH_BINARY} not found"));
+    }
+    let status = Command::new(SSH_BINARY)
+        .arg("-V")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| format!("{SSH_BINARY} could not be run: {e}"))?;
+    if !status.success() {
+        return Err(format!("{SSH_BINARY} -V exited with {status}"));
+    }
+    Ok(())
+}
+
+fn run_startup_checks() -> StartupStatus {
+    summarize(vec![
+        run_check(
+            "config_dir",
+            "Hosts and settings cannot be saved.",
+            check_config_dir_writable,
+        ),
+        run_check(
+            "hosts_db",
+            "The host list cannot be loaded or saved.",
+            check_hosts_db,
+        ),
+        run_check(
+            "keychain",
+            "Saved passwords are unavailable; SSH will prompt for them instead.",
+            crate::credential_store::keychain_health_check,
+        ),
+        run_check(
+            "ssh_binary",
+            "Terminal sessions, probes and file operations cannot connect.",
+            check_ssh_binary,
+        ),
+    ])
+}
+
+fn startup_status_cached() -> StartupStatus {
+    static STATUS: OnceLock<StartupStatus> = OnceLock::new();
+    STATUS.get_or_init(run_startup_checks).clone()
+}
+
+/// Run the startup checks off the main thread and announce the result with a
+/// `startup:status` event. The UI may subscribe after the event fired, so it
+/// should also call `startup_status` once on load.
+pub(crate) fn spawn_startup_checks<R: Runtime>(app: AppHandle<R>) {
+    std::thread::spawn(move || {
+        let status = startup_status_cached();
+        if status.degraded {
+            for check in status.checks.iter().filter(|check| !check.ok) {
+                eprintln!(
+                    "[startup] {} check failed: {}",
+                    check.name,
+                    check.detail.as_deref().unwrap_or("unknown error")
+                );
+            }
+        }
+        let _ = app.emit_to(MAIN_WINDOW_LABEL, "startup:status", status);
+    });
+}
+
+#[tauri::command]
+pub async fn startup_status() -> Result<StartupStatus, String> {
+    tauri::async_runtime::spawn_blocking(startup_status_cached)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{run_check, summarize};
+
+    #[test]
+    fn failed_check_marks_status_degraded() {
+        let ok = run_check("a", "nothing", || Ok(()));
+        assert!(ok.ok && ok.detail.is_none());
+        assert!(!summarize(vec![ok.clone()]).degraded);
+
+        let failed = run_check("b", "everything", || Err("boom".to_string()));
+        assert_eq!(failed.detail.as_deref(), Some("boom"));
+        let status = summarize(vec![ok, failed]);
+        assert!(status.degraded);
+        assert_eq!(status.checks.len(), 2);
+    }
+}
@@ -0,0 +1,190 @@
+use crate::host_import::{
+    alias_from_name, import_candidates, linked_host_id, run_inventory_cli, HostImportReport,
+    ImportCandidate,
+};
+use crate::host_store::hosts_load;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const TAILSCALE_BINARIES: &[&str] = &[
+    "/Applications/Tailscale.app/Contents/MacOS/Tailscale",
+    "/opt/homebrew/bin/tailscale",
+    "/usr/local/bin/tailscale",
+    "/usr/bin/tailscale",
+    "tailscale",
+];
+const SOURCE_PREFIX: &str = "tailscale:";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct StatusJson {
+    #[serde(default)]
+    peer: HashMap<String, PeerJson>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PeerJson {
+    #[serde(rename = "ID", default)]
+    id: String,
+    #[serde(default)]
+    host_name: String,
+    #[serde(rename = "DNSName", default)]
+    dns_name: String,
+    #[serde(rename = "TailscaleIPs", default)]
+    tailscale_ips: Vec<String>,
+    #[serde(rename = "OS", default)]
+    os: String,
+    #[serde(default)]
+    online: bool,
+    #[serde(default)]
+    tags: Option<Vec<String>>,
+}
+
+/// A tailnet peer offered for import.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TailscalePeer {
+    pub id: String,
+    pub name: String,
+    pub dns_name: Option<String>,
+    pub ip: Option<String>,
+    pub os: String,
+    pub online: bool,
+    pub tags: Vec<String>,
+    /// The host already imported from this peer, if any.
+    pub linked_host_id: Option<String>,
+}
+
+fn run_status_json() -> Result<Vec<u8>, String> {
+    run_inventory_cli(TAILSCALE_BINARIES, &["status", "--json"])
+}
+
+fn parse_peers(json: &[u8]) -> Result<Vec<TailscalePeer>, String> {
+    let status: StatusJson =
+        serde_json::from_slice(json).map_err(|e| format!("Unexpected tailscale status: {e}"))?;
+    let mut peers: Vec<TailscalePeer> = status
+        .peer
+        .into_values()
+        .filter(|peer| !peer.id.is_empty())
+        .map(|peer| {
+            let dns_name = Some(peer.dns_name.trim_end_matches('.').to_string())
+                .filter(|name| !name.is_empty());
+            let name = dns_name
+                .as_deref()
+                .and_then(|name| name.split('.').next())
+                .filter(|name| !name.is_empty())
+                .unwrap_or(&peer.host_name)
+                .to_string();
+            let ip = peer
+                .tailscale_ips
+                .iter()
+                .find(|ip| ip.contains('.'))
+                .or_else(|| peer.tailscale_ips.first())
+                .cloned();
+            TailscalePeer {
+                id: peer.id,
+                name,
+                dns_name,
+                ip,
+                os: peer.os,
+                online: peer.online,
+                tags: peer
+                    .tags
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|tag| tag.trim_start_matches("tag:").to_string())
+                    .collect(),
+                linked_host_id: None,
+            }
+        })
+        .collect();
+    peers.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(peers)
+}
+
+fn source_of(peer_id: &str) -> String {
+    format!("{SOURCE_PREFIX}{peer_id}")
+}
+
+fn list_peers() -> Result<Vec<TailscalePeer>, String> {
+    let mut peers = parse_peers(&run_status_json()?)?;
+    let hosts = hosts_load()?;
+    for peer in peers.iter_mut() {
+        peer.linked_host_id = linked_host_id(&hosts, &source_of(&peer.id));
+    }
+    Ok(peers)
+}
+
+/// Peers from `tailscale status --json`, with the host each one is already
+/// imported as.
+#[tauri::command]
+pub async fn tailscale_peers() -> Result<Vec<TailscalePeer>, String> {
+    tauri::async_runtime::spawn_blocking(list_peers)
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Import the selected peers (by node id). Hosts imported before are linked
+/// by node id, so a re-import only refreshes their address and tags.
+#[tauri::command]
+pub async fn tailscale_import(
+    peer_ids: Vec<String>,
+    user: Option<String>,
+) -> Result<HostImportReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let peers = parse_peers(&run_status_json()?)?;
+        let candidates = peers
+            .into_iter()
+            .filter(|peer| peer_ids.contains(&peer.id))
+            .filter_map(|peer| {
+                // The tailnet IP works even where MagicDNS is off.
+                let hostname = peer.ip.clone().or_else(|| peer.dns_name.clone())?;
+                Some(ImportCandidate {
+                    source: source_of(&peer.id),
+                    alias: alias_from_name(&peer.name, "tailscale-host"),
+                    name: peer.dns_name.clone().unwrap_or_else(|| peer.name.clone()),
+                    hostname,
+                    port: 22,
+                    identity_file: None,
+                    tags: peer.tags,
+                })
+            })
+            .collect();
+        import_candidates(candidates, user)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_peers;
+
+    #[test]
+    fn parses_peers_from_status_json() {
+        let json = br#"{
+            "Self": { "ID": "n0", "HostName": "laptop" },
+            "Peer": {
+                "nodekey:b": {
+                    "ID": "n2", "HostName": "db", "DNSName": "db-1.tail1234.ts.net.",
+                    "TailscaleIPs": ["fd7a:115c:a1e0::2", "100.64.0.2"],
+                    "OS": "linux", "Online": false, "Tags": ["tag:prod", "tag:db"]
+                },
+                "nodekey:a": {
+                    "ID": "n1", "HostName": "Build Box", "DNSName": "",
+                    "TailscaleIPs": ["100.64.0.1"], "OS": "macOS", "Online": true
+                }
+            }
+        }"#;
+        let peers = parse_peers(json).unwrap();
+        assert_eq!(peers.len(), 2);
+        assert_eq!(peers[0].name, "Build Box");
+        assert_eq!(peers[0].dns_name, None);
+        assert!(peers[0].tags.is_empty());
+        assert_eq!(peers[1].name, "db-1");
+        assert_eq!(peers[1].dns_name.as_deref(), Some("db-1.tail1234.ts.net"));
+        assert_eq!(peers[1].ip.as_deref(), Some("100.64.0.2"));
+        assert_eq!(peers[1].tags, vec!["prod", "db"]);
+    }
+}
@@ -0,0 +1,271 @@
+// Optional OTLP/HTTP (JSON) export of a span per backend job: syncs, probes,
+// transfers and bulk commands. Off unless `otlp_endpoint` is set in settings.
+
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::sync::{Mutex, Once, OnceLock};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use url::Url;
+
+const SERVICE_NAME: &str = "xtermius";
+const OTLP_TRACES_PATH: &str = "/v1/traces";
+const EXPORT_INTERVAL: Duration = Duration::from_secs(5);
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+// Spans beyond this wait for an unreachable collector are dropped, oldest
+// first, so a dead endpoint cannot grow memory without bound.
+const MAX_QUEUED_SPANS: usize = 2048;
+const MAX_SPANS_PER_EXPORT: usize = 512;
+
+/// Where spans go, once the settings were read: `None` inside means off.
+fn endpoint_state() -> &'static Mutex<Option<Option<String>>> {
+    static ENDPOINT: OnceLock<Mutex<Option<Option<String>>>> = OnceLock::new();
+    ENDPOINT.get_or_init(|| Mutex::new(None))
+}
+
+fn queue() -> &'static Mutex<VecDeque<FinishedSpan>> {
+    static QUEUE: OnceLock<Mutex<VecDeque<FinishedSpan>>> = OnceLock::new();
+    QUEUE.get_or_init(|| Mutex::new(VecDeque::new()))
+}
+
+/// The traces URL for a collector base URL (`http://localhost:4318`) or a
+/// full `/v1/traces` URL; `None` for an empty value, which turns export off.
+pub(crate) fn parse_endpoint(input: Option<&str>) -> Result<Option<String>, String> {
+    let Some(raw) = input.map(str::trim).filter(|raw| !raw.is_empty()) else {
+        return Ok(None);
+    };
+    let mut url = Url::parse(raw).map_err(|e| format!("Invalid OTLP endpoint: {e}"))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!(
+            "OTLP endpoint must use http or https, got {}",
+            url.scheme()
+        ));
+    }
+    if !url.path().ends_with(OTLP_TRACES_PATH) {
+        let path = format!("{}{OTLP_TRACES_PATH}", url.path().trim_end_matches('/'));
+        url.set_path(&path);
+    }
+    Ok(Some(url.to_string()))
+}
+
+fn endpoint() -> Option<String> {
+    let mut state = endpoint_state().lock().ok()?;
+    if state.is_none() {
+        let configured = crate::host_store::settings_load()
+            .ok()
+            .and_then(|settings| parse_endpoint(settings.otlp_endpoint.as_deref()).ok())
+            .flatten();
+        *state = Some(configured);
+    }
+    state.clone().flatten()
+}
+
+/// Takes an already validated endpoint; `None` turns exporting off.
+pub(crate) fn set_endpoint(endpoint: Option<String>) {
+    if let Ok(mut state) = endpoint_state().lock() {
+        *state = Some(endpoint);
+    }
+}
+
+fn unix_nanos(time: SystemTime) -> String {
+    time.duration_since(UNIX_EPOCH)
+        .map(|since| since.as_nanos())
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// A running span; record it with [`Span::end`].
+pub(crate) struct Span {
+    name: String,
+    trace_id: [u8; 16],
+    span_id: [u8; 8],
+    start: SystemTime,
+    attributes: Vec<(String, String)>,
+}
+
+struct FinishedSpan {
+    span: Span,
+    end: SystemTime,
+    error: Option<String>,
+}
+
+/// Start a span, or `None` while export is off so callers pay nothing.
+pub(crate) fn start_span(name: &str) -> Option<Span> {
+    endpoint()?;
+    let mut span_id = [0u8; 8];
+    span_id.copy_from_slice(&uuid::Uuid::new_v4().as_bytes()[..8]);
+    Some(Span {
+        name: name.to_string(),
+        trace_id: *uuid::Uuid::new_v4().as_bytes(),
+        span_id,
+        start: SystemTime::now(),
+        attributes: Vec::new(),
+    })
+}
+
+impl Span {
+    pub(crate) fn attribute(&mut self, key: &str, value: impl Into<String>) {
+        self.attributes.push((key.to_string(), value.into()));
+    }
+
+    /// Queue the span for export; `error` marks it failed.
+    pub(crate) fn end(self, error: Option<&str>) {
+        start_exporter();
+        if let Ok(mut queue) = queue().lock() {
+            if queue.len() >= MAX_QUEUED_SPANS {
+                queue.pop_front();
+            }
+            queue.push_back(FinishedSpan {
+                span: self,
+                end: SystemTime::now(),
+                error: error.map(str::to_string),
+            });
+        }
+    }
+}
+
+fn span_json(finished: &FinishedSpan) -> Value {
+    let span = &finished.span;
+    let attributes: Vec<Value> = span
+        .attributes
+        .iter()
+        .map(|(key, value)| json!({ "key": key, "value": { "stringValue": value } }))
+        .collect();
+    let status = match &finished.error {
+        // STATUS_CODE_ERROR
+        Some(message) => json!({ "code": 2, "message": message }),
+        // STATUS_CODE_OK
+        None => json!({ "code": 1 }),
+    };
+    json!({
+        "traceId": hex(&span.trace_id),
+        "spanId": hex(&span.span_id),
+        "name": span.name,
+        // SPAN_KIND_INTERNAL
+        "kind": 1,
+        "startTimeUnixNano": unix_nanos(span.start),
+        "endTimeUnixNano": unix_nanos(finished.end),
+        "attributes": attributes,
+        "status": status,
+    })
+}
+
+fn export_request(spans: &[FinishedSpan]) -> Value {
+    json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [
+                    { "key": "service.name", "value": { "stringValue": SERVICE_NAME } },
+                    {
+                        "key": "service.version",
+                        "value": { "stringValue": env!("CARGO_PKG_VERSION") }
+                    },
+                ],
+            },
+            "scopeSpans": [{
+                "scope": { "name": SERVICE_NAME },
+                "spans": spans.iter().map(span_json).collect::<Vec<_>>(),
+            }],
+        }],
+    })
+}
+
+fn export(endpoint: &str, spans: &[FinishedSpan]) -> Result<(), String> {
+    let response = reqwest::blocking::Client::builder()
+        .timeout(EXPORT_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?
+        .post(endpoint)
+        .json(&export_request(spans))
+        .send()
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("{endpoint} answered {}", response.status()));
+    }
+    Ok(())
+}
+
+// One thread batches queued spans; a failed export drops its batch rather
+// than retrying, as tracing must never hold up the app.
+fn start_exporter() {
+    static START: Once = Once::new();
+    START.call_once(|| {
+        let spawned = thread::Builder::new()
+            .name("otlp-export".to_string())
+            .spawn(|| loop {
+                thread::sleep(EXPORT_INTERVAL);
+                let batch: Vec<FinishedSpan> = match queue().lock() {
+                    Ok(mut queue) => {
+                        let count = queue.len().min(MAX_SPANS_PER_EXPORT);
+                        queue.drain(..count).collect()
+                    }
+                    Err(_) => continue,
+                };
+                if batch.is_empty() {
+                    continue;
+                }
+                let Some(endpoint) = endpoint() else {
+                    continue;
+                };
+                if let Err(error) = export(&endpoint, &batch) {
+                    eprintln!(
+                        "[telemetry] failed to export {} spans: {error}",
+                        batch.len()
+                    );
+                }
+            });
+        if let Err(error) = spawned {
+            eprintln!("[telemetry] failed to start exporter: {error}");
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{export_request, parse_endpoint, FinishedSpan, Span};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn builds_otlp_json_for_configured_endpoint() {
+        assert_eq!(parse_endpoint(None).unwrap(), None);
+        assert_eq!(parse_endpoint(Some("  ")).unwrap(), None);
+        assert_eq!(
+            parse_endpoint(Some("http://localhost:4318"))
+                .unwrap()
+                .as_deref(),
+            Some("http://localhost:4318/v1/traces")
+        );
+        assert_eq!(
+            parse_endpoint(Some("https://otel.example.com/v1/traces"))
+                .unwrap()
+                .as_deref(),
+            Some("https://otel.example.com/v1/traces")
+        );
+        assert!(parse_endpoint(Some("grpc://localhost:4317")).is_err());
+
+        let start = UNIX_EPOCH + Duration::from_secs(1);
+        let span = FinishedSpan {
+            span: Span {
+                name: "webdav_push".to_string(),
+                trace_id: [0xab; 16],
+                span_id: [0x01; 8],
+                start,
+                attributes: vec![("job.id".to_string(), "j1".to_string())],
+            },
+            end: start + Duration::from_millis(250),
+            error: Some("timed out".to_string()),
+        };
+        let request = export_request(&[span]);
+        let json = &request["resourceSpans"][0]["scopeSpans"][0]["spans"][0];
+        assert_eq!(json["traceId"], "ab".repeat(16));
+        assert_eq!(json["spanId"], "0101010101010101");
+        assert_eq!(json["startTimeUnixNano"], "1000000000");
+        assert_eq!(json["endTimeUnixNano"], "1250000000");
+        assert_eq!(json["status"]["code"], 2);
+        assert_eq!(json["attributes"][0]["value"]["stringValue"], "j1");
+    }
+}
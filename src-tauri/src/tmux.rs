@@ -0,0 +1,626 @@
+// Remote tmux sessions driven in control mode (`tmux -C`) over an SSH exec
+// channel. Windows and panes are listed as structured objects and pane
+// output arrives as events; the tmux session lives on the server, so it
+// survives a dropped connection and is picked up again by the next attach.
+
+use crate::host_probe::{probe_ssh_command, shell_quote, ProbeSshCommand};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Child, ChildStderr, ChildStdin, ChildStdout, Stdio};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{mpsc, Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Runtime};
+
+const MAIN_WINDOW_LABEL: &str = "main";
+const DEFAULT_SESSION_NAME: &str = "xtermius";
+// Covers connecting and authenticating for the first command.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+// Keys are sent as hex bytes, this many per `send-keys`.
+const SEND_KEYS_CHUNK_BYTES: usize = 256;
+const LIST_PANES_FORMAT: &str = "#{window_id}\t#{window_index}\t#{window_name}\t#{window_active}\t#{pane_id}\t#{pane_width}\t#{pane_height}\t#{pane_active}";
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TmuxPane {
+    /// `%N`
+    pub id: String,
+    pub width: u16,
+    pub height: u16,
+    pub active: bool,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TmuxWindow {
+    /// `@N`
+    pub id: String,
+    pub index: u32,
+    pub name: String,
+    pub active: bool,
+    pub panes: Vec<TmuxPane>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TmuxClientSummary {
+    pub client_id: String,
+    pub host_id: String,
+    pub session_name: String,
+}
+
+/// A `tmux:output` event: output of one pane.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TmuxOutputPayload {
+    pub client_id: String,
+    pub pane_id: String,
+    pub data: String,
+}
+
+/// A `tmux:changed` event: windows or panes were added, closed, renamed or
+/// resized; `tmux_windows` has the new layout.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TmuxChangedPayload {
+    pub client_id: String,
+}
+
+/// A `tmux:exit` event: the client detached or its connection ended.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TmuxExitPayload {
+    pub client_id: String,
+    pub reason: Option<String>,
+}
+
+enum TmuxEvent {
+    Output(TmuxOutputPayload),
+    Changed(TmuxChangedPayload),
+    Exit(TmuxExitPayload),
+}
+
+type TmuxEmitFn = Box<dyn Fn(TmuxEvent) + Send + Sync>;
+
+fn emitter() -> &'static OnceLock<TmuxEmitFn> {
+    static EMITTER: OnceLock<TmuxEmitFn> = OnceLock::new();
+    &EMITTER
+}
+
+/// Send tmux client events to the main window.
+pub(crate) fn init<R: Runtime>(app: AppHandle<R>) {
+    let _ = emitter().set(Box::new(move |event| {
+        let _ = match event {
+            TmuxEvent::Output(payload) => app.emit_to(MAIN_WINDOW_LABEL, "tmux:output", payload),
+            TmuxEvent::Changed(payload) => app.emit_to(MAIN_WINDOW_LABEL, "tmux:changed", payload),
+            TmuxEvent::Exit(payload) => app.emit_to(MAIN_WINDOW_LABEL, "tmux:exit", payload),
+        };
+    }));
+}
+
+fn emit(event: TmuxEvent) {
+    if let Some(emit) = emitter().get() {
+        emit(event);
+    }
+}
+
+/// One line of control mode output, outside a command's reply.
+#[derive(Debug, PartialEq)]
+enum ControlLine {
+    /// Start of a command's reply; flag 1 marks commands this client sent,
+    /// as opposed to the one on the `tmux -C` command line.
+    Begin {
+        flags: u32,
+    },
+    End {
+        error: bool,
+    },
+    Output {
+        pane: String,
+        data: Vec<u8>,
+    },
+    LayoutChanged,
+    Exit {
+        reason: Option<String>,
+    },
+    Other,
+}
+
+fn parse_control_line(line: &[u8]) -> ControlLine {
+    let (name, rest) = match line.iter().position(|byte| *byte == b' ') {
+        Some(space) => (&line[..space], &line[space + 1..]),
+        None => (line, &line[line.len()..]),
+    };
+    let text = || String::from_utf8_lossy(rest).into_owned();
+    match name {
+        b"%begin" => ControlLine::Begin {
+            flags: text()
+                .split_whitespace()
+                .nth(2)
+                .and_then(|flags| flags.parse().ok())
+                .unwrap_or(0),
+        },
+        b"%end" => ControlLine::End { error: false },
+        b"%error" => ControlLine::End { error: true },
+        b"%output" => {
+            let Some(space) = rest.iter().position(|byte| *byte == b' ') else {
+                return ControlLine::Other;
+            };
+            ControlLine::Output {
+                pane: String::from_utf8_lossy(&rest[..space]).into_owned(),
+                data: unescape_output(&rest[space + 1..]),
+            }
+        }
+        b"%window-add"
+        | b"%window-close"
+        | b"%unlinked-window-close"
+        | b"%window-renamed"
+        | b"%layout-change"
+        | b"%window-pane-changed"
+        | b"%session-changed"
+        | b"%session-window-changed" => ControlLine::LayoutChanged,
+        b"%exit" => ControlLine::Exit {
+            reason: Some(text().trim().to_string()).filter(|reason| !reason.is_empty()),
+        },
+        _ => ControlLine::Other,
+    }
+}
+
+// tmux writes bytes below 32 and `\` of pane output as `\ooo`.
+fn unescape_output(escaped: &[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(escaped.len());
+    let mut i = 0;
+    while i < escaped.len() {
+        let octal = escaped.get(i + 1..i + 4).filter(|digits| {
+            escaped[i] == b'\\' && digits.iter().all(|digit| (b'0'..=b'7').contains(digit))
+        });
+        match octal {
+            Some(digits) => {
+                let value = digits
+                    .iter()
+                    .fold(0u32, |value, digit| value * 8 + u32::from(digit - b'0'));
+                data.push(value as u8);
+                i += 4;
+            }
+            None => {
+                data.push(escaped[i]);
+                i += 1;
+            }
+        }
+    }
+    data
+}
+
+// Output is split into lines regardless of characters; an incomplete UTF-8
+// sequence at the end waits for the pane's next output.
+fn take_utf8(carry: &mut Vec<u8>) -> String {
+    let complete = match std::str::from_utf8(carry) {
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        _ => carry.len(),
+    };
+    let rest = carry.split_off(complete);
+    let text = String::from_utf8_lossy(carry).into_owned();
+    *carry = rest;
+    text
+}
+
+fn parse_windows(lines: &[String]) -> Vec<TmuxWindow> {
+    let mut windows: Vec<TmuxWindow> = Vec::new();
+    for line in lines {
+        let fields: Vec<&str> = line.split('\t').collect();
+        let [window_id, index, name, window_active, pane_id, width, height, pane_active] =
+            fields[..]
+        else {
+            continue;
+        };
+        let pane = TmuxPane {
+            id: pane_id.to_string(),
+            width: width.parse().unwrap_or(0),
+            height: height.parse().unwrap_or(0),
+            active: pane_active == "1",
+        };
+        match windows.iter_mut().find(|window| window.id == window_id) {
+            Some(window) => window.panes.push(pane),
+            None => windows.push(TmuxWindow {
+                id: window_id.to_string(),
+                index: index.parse().unwrap_or(0),
+                name: name.to_string(),
+                active: window_active == "1",
+                panes: vec![pane],
+            }),
+        }
+    }
+    windows
+}
+
+type Reply = Result<Vec<String>, String>;
+
+struct TmuxClient {
+    host_id: String,
+    session_name: String,
+    stdin: Mutex<ChildStdin>,
+    // Commands sent and not answered yet, oldest first.
+    replies: Mutex<VecDeque<mpsc::Sender<Reply>>>,
+    child: Mutex<Child>,
+}
+
+impl TmuxClient {
+    /// Run a tmux command and wait for its output lines.
+    fn command(&self, command: &str) -> Reply {
+        if command.contains(['\n', '\r']) {
+            return Err("tmux commands must be a single line".to_string());
+        }
+        let (sender, receiver) = mpsc::channel();
+        {
+            // Queued in the order written, under one lock, so replies are
+            // matched to the right command.
+            let mut replies = self.replies.lock().map_err(|_| "tmux client poisoned")?;
+            let mut stdin = self.stdin.lock().map_err(|_| "tmux client poisoned")?;
+            stdin
+                .write_all(format!("{command}\n").as_bytes())
+                .and_then(|()| stdin.flush())
+                .map_err(|e| format!("tmux client is gone: {e}"))?;
+            replies.push_back(sender);
+        }
+        receiver
+            .recv_timeout(COMMAND_TIMEOUT)
+            .map_err(|_| "tmux did not answer".to_string())?
+    }
+}
+
+fn clients() -> &'static Mutex<HashMap<String, Arc<TmuxClient>>> {
+    static CLIENTS: OnceLock<Mutex<HashMap<String, Arc<TmuxClient>>>> = OnceLock::new();
+    CLIENTS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn find_client(client_id: &str) -> Result<Arc<TmuxClient>, String> {
+    clients()
+        .lock()
+        .map_err(|_| "tmux clients poisoned")?
+        .get(client_id.trim())
+        .cloned()
+        .ok_or_else(|| "Unavailable tmux client".to_string())
+}
+
+fn valid_id(id: &str, sigil: char) -> bool {
+    id.strip_prefix(sigil)
+        .is_some_and(|number| !number.is_empty() && number.chars().all(|ch| ch.is_ascii_digit()))
+}
+
+fn require_pane_id(pane_id: &str) -> Result<&str, String> {
+    let pane_id = pane_id.trim();
+    if !valid_id(pane_id, '%') {
+        return Err(format!("Invalid tmux pane id: {pane_id}"));
+    }
+    Ok(pane_id)
+}
+
+/// Read a client's control mode output until it exits, then forget it.
+/// `ssh` keeps the askpass script alive while the connection is set up.
+fn read_control_output(
+    client_id: String,
+    client: Arc<TmuxClient>,
+    stdout: ChildStdout,
+    stderr: Option<ChildStderr>,
+    ssh: ProbeSshCommand,
+) {
+    let _ssh = ssh;
+    let mut reader = BufReader::new(stdout);
+    let mut line = Vec::new();
+    let mut block: Option<(u32, Vec<String>)> = None;
+    let mut carries: HashMap<String, Vec<u8>> = HashMap::new();
+    let mut reason = None;
+    loop {
+        line.clear();
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        while matches!(line.last(), Some(b'\n' | b'\r')) {
+            line.pop();
+        }
+        let parsed = parse_control_line(&line);
+        if let Some((flags, lines)) = block.as_mut() {
+            let ControlLine::End { error } = parsed else {
+                lines.push(String::from_utf8_lossy(&line).into_owned());
+                continue;
+            };
+            let (flags, lines) = (*flags, std::mem::take(lines));
+            block = None;
+            if flags & 1 == 0 {
+                continue;
+            }
+            let reply = client.replies.lock().ok().and_then(|mut r| r.pop_front());
+            if let Some(reply) = reply {
+                let _ = reply.send(if error {
+                    Err(lines.join("\n"))
+                } else {
+                    Ok(lines)
+                });
+            }
+            continue;
+        }
+        match parsed {
+            ControlLine::Begin { flags } => block = Some((flags, Vec::new())),
+            ControlLine::Output { pane, data } => {
+                let carry = carries.entry(pane.clone()).or_default();
+                carry.extend(data);
+                let data = take_utf8(carry);
+                if !data.is_empty() {
+                    emit(TmuxEvent::Output(TmuxOutputPayload {
+                        client_id: client_id.clone(),
+                        pane_id: pane,
+                        data,
+                    }));
+                }
+            }
+            ControlLine::LayoutChanged => emit(TmuxEvent::Changed(TmuxChangedPayload {
+                client_id: client_id.clone(),
+            })),
+            ControlLine::Exit { reason: exited } => {
+                reason = exited;
+                break;
+            }
+            ControlLine::End { .. } | ControlLine::Other => {}
+        }
+    }
+
+    if let Ok(mut clients) = clients().lock() {
+        clients.remove(&client_id);
+    }
+    if let Ok(mut child) = client.child.lock() {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+    // Without an `%exit`, ssh or the remote shell said what went wrong.
+    let reason = reason.or_else(|| {
+        let mut message = String::new();
+        stderr?.read_to_string(&mut message).ok()?;
+        Some(message.trim().to_string()).filter(|message| !message.is_empty())
+    });
+    if let Ok(mut replies) = client.replies.lock() {
+        for reply in replies.drain(..) {
+            let _ = reply.send(Err(reason
+                .clone()
+                .unwrap_or_else(|| "tmux client exited".to_string())));
+        }
+    }
+    emit(TmuxEvent::Exit(TmuxExitPayload { client_id, reason }));
+}
+
+fn tmux_attach_impl(host_id: &str, session_name: Option<String>) -> Result<String, String> {
+    let host = crate::host_store::hosts_load()?
+        .into_iter()
+        .find(|host| host.id == host_id && !host.deleted)
+        .ok_or_else(|| "Host not found".to_string())?;
+    let session_name = session_name
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| DEFAULT_SESSION_NAME.to_string());
+    // tmux uses `:` and `.` in target names.
+    if session_name
+        .chars()
+        .any(|ch| ch.is_control() || ch == ':' || ch == '.')
+    {
+        return Err("tmux session names must not contain ':', '.' or control characters".into());
+    }
+
+    let remote_command = format!("tmux -C new-session -A -s {}", shell_quote(&session_name));
+    let mut ssh = probe_ssh_command(&host, &[], &remote_command)?;
+    let mut child = ssh
+        .command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    let stdin = child.stdin.take().ok_or("ssh stdin unavailable")?;
+    let stdout = child.stdout.take().ok_or("ssh stdout unavailable")?;
+    let stderr = child.stderr.take();
+
+    static NEXT_CLIENT_ID: AtomicU32 = AtomicU32::new(1);
+    let client_id = NEXT_CLIENT_ID.fetch_add(1, Ordering::Relaxed).to_string();
+    let client = Arc::new(TmuxClient {
+        host_id: host.id.clone(),
+        session_name,
+        stdin: Mutex::new(stdin),
+        replies: Mutex::new(VecDeque::new()),
+        child: Mutex::new(child),
+    });
+    clients()
+        .lock()
+        .map_err(|_| "tmux clients poisoned")?
+        .insert(client_id.clone(), client.clone());
+    let reader_id = client_id.clone();
+    let reader_client = client.clone();
+    std::thread::spawn(move || read_control_output(reader_id, reader_client, stdout, stderr, ssh));
+
+    // The id is handed out once tmux answers, so a failed connection or a
+    // missing tmux is reported here rather than as a `tmux:exit`.
+    if let Err(e) = client.command("refresh-client") {
+        if let Ok(mut child) = client.child.lock() {
+            let _ = child.kill();
+        }
+        return Err(e);
+    }
+    Ok(client_id)
+}
+
+/// Attach to (or create) tmux session `session_name` on a host in control
+/// mode. Returns the client id used by the other `tmux_*` commands; pane
+/// output arrives as `tmux:output`, layout changes as `tmux:changed`.
+#[tauri::command]
+pub async fn tmux_attach(host_id: String, session_name: Option<String>) -> Result<String, String> {
+    crate::app_lock::ensure_unlocked()?;
+    tauri::async_runtime::spawn_blocking(move || tmux_attach_impl(&host_id, session_name))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub fn tmux_clients() -> Result<Vec<TmuxClientSummary>, String> {
+    let clients = clients().lock().map_err(|_| "tmux clients poisoned")?;
+    let mut summaries: Vec<TmuxClientSummary> = clients
+        .iter()
+        .map(|(id, client)| TmuxClientSummary {
+            client_id: id.clone(),
+            host_id: client.host_id.clone(),
+            session_name: client.session_name.clone(),
+        })
+        .collect();
+    summaries.sort_by_key(|summary| summary.client_id.parse::<u32>().unwrap_or_default());
+    Ok(summaries)
+}
+
+/// Windows of the attached session with their panes, in index order.
+#[tauri::command]
+pub async fn tmux_windows(client_id: String) -> Result<Vec<TmuxWindow>, String> {
+    let client = find_client(&client_id)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let lines = client.command(&format!(
+            "list-panes -s -F {}",
+            shell_quote(LIST_PANES_FORMAT)
+        ))?;
+        Ok(parse_windows(&lines))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Type `data` into a pane.
+#[tauri::command]
+pub async fn tmux_send_keys(
+    client_id: String,
+    pane_id: String,
+    data: String,
+) -> Result<(), String> {
+    let client = find_client(&client_id)?;
+    let pane_id = require_pane_id(&pane_id)?.to_string();
+    tauri::async_runtime::spawn_blocking(move || {
+        // Hex keeps control characters and quotes out of the command line.
+        for chunk in data.as_bytes().chunks(SEND_KEYS_CHUNK_BYTES) {
+            let hex: Vec<String> = chunk.iter().map(|byte| format!("{byte:02x}")).collect();
+            client.command(&format!("send-keys -t {pane_id} -H {}", hex.join(" ")))?;
+        }
+        Ok(())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Set the size of the client, which tmux fits the windows to.
+#[tauri::command]
+pub async fn tmux_resize(client_id: String, cols: u16, rows: u16) -> Result<(), String> {
+    let client = find_client(&client_id)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        client
+            .command(&format!(
+                "refresh-client -C {},{}",
+                cols.max(1),
+                rows.max(1)
+            ))
+            .map(|_| ())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn tmux_new_window(client_id: String) -> Result<(), String> {
+    let client = find_client(&client_id)?;
+    tauri::async_runtime::spawn_blocking(move || client.command("new-window").map(|_| ()))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// What a pane shows now, with colors, to fill a view opened after its
+/// output started.
+#[tauri::command]
+pub async fn tmux_capture_pane(client_id: String, pane_id: String) -> Result<String, String> {
+    let client = find_client(&client_id)?;
+    let pane_id = require_pane_id(&pane_id)?.to_string();
+    tauri::async_runtime::spawn_blocking(move || {
+        let lines = client.command(&format!("capture-pane -p -e -t {pane_id}"))?;
+        Ok(lines.join("\r\n"))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Detach the client; the tmux session keeps running on the server.
+#[tauri::command]
+pub fn tmux_detach(client_id: String) -> Result<(), String> {
+    let client = find_client(&client_id)?;
+    let mut stdin = client.stdin.lock().map_err(|_| "tmux client poisoned")?;
+    // Answered with `%exit`, which ends the client.
+    stdin
+        .write_all(b"detach-client\n")
+        .and_then(|()| stdin.flush())
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_control_line, parse_windows, take_utf8, ControlLine};
+
+    #[test]
+    fn parses_control_mode_lines() {
+        assert_eq!(
+            parse_control_line(b"%begin 1363006971 2 1"),
+            ControlLine::Begin { flags: 1 }
+        );
+        assert_eq!(
+            parse_control_line(b"%error 1363006971 2 1"),
+            ControlLine::End { error: true }
+        );
+        assert_eq!(
+            parse_control_line(b"%output %3 ls\\015\\012a\\134b \\033[0m"),
+            ControlLine::Output {
+                pane: "%3".to_string(),
+                data: b"ls\r\na\\b \x1b[0m".to_vec(),
+            }
+        );
+        assert_eq!(
+            parse_control_line(b"%layout-change @1 b25d,80x24,0,0,0"),
+            ControlLine::LayoutChanged
+        );
+        assert_eq!(
+            parse_control_line(b"%exit server exited"),
+            ControlLine::Exit {
+                reason: Some("server exited".to_string())
+            }
+        );
+        assert_eq!(
+            parse_control_line(b"%exit"),
+            ControlLine::Exit { reason: None }
+        );
+        assert_eq!(
+            parse_control_line(b"%pane-mode-changed %1"),
+            ControlLine::Other
+        );
+    }
+
+    #[test]
+    fn groups_panes_and_keeps_characters_whole() {
+        let lines = [
+            "@1\t0\tzsh\t1\t%1\t80\t24\t0",
+            "@2\t1\tvim\t0\t%2\t40\t24\t1",
+            "@1\t0\tzsh\t1\t%3\t39\t24\t1",
+            "garbage",
+        ]
+        .map(str::to_string);
+        let windows = parse_windows(&lines);
+        assert_eq!(windows.len(), 2);
+        assert_eq!(windows[0].panes.len(), 2);
+        assert_eq!(windows[0].panes[1].id, "%3");
+        assert!(windows[0].active && windows[0].panes[1].active);
+        assert_eq!(windows[1].name, "vim");
+
+        let mut carry = "né".as_bytes()[..2].to_vec();
+        assert_eq!(take_utf8(&mut carry), "n");
+        carry.extend_from_slice(&"é".as_bytes()[1..]);
+        assert_eq!(take_utf8(&mut carry), "é");
+        assert!(carry.is_empty());
+    }
+}
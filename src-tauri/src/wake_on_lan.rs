@@ -0,0 +1,136 @@
+use crate::host_status::tcp_connect_rtt;
+use crate::host_store::hosts_load;
+use crate::operations;
+use serde::Serialize;
+use std::net::UdpSocket;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const WOL_PORT: u16 = 9;
+// UDP is fire-and-forget; a few copies make a lost packet unlikely.
+const WOL_PACKET_COPIES: usize = 3;
+const WAKE_POLL_INTERVAL: Duration = Duration::from_secs(2);
+const WAKE_POLL_CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+const MAX_WAKE_WAIT_SECS: u64 = 600;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostWakeResult {
+    /// Whether the SSH port answered; `None` when no wait was requested.
+    pub reachable: Option<bool>,
+    pub waited_ms: u64,
+}
+
+/// `aa:bb:cc:dd:ee:ff`, `aa-bb-cc-dd-ee-ff` or `aabbccddeeff`.
+fn parse_mac(input: &str) -> Result<[u8; 6], String> {
+    let hex: String = input
+        .trim()
+        .chars()
+        .filter(|c| !matches!(c, ':' | '-' | '.'))
+        .collect();
+    if hex.len() != 12 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("Invalid MAC address: {}", input.trim()));
+    }
+    let mut mac = [0u8; 6];
+    for (i, byte) in mac.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|e| e.to_string())?;
+    }
+    Ok(mac)
+}
+
+/// Six 0xff bytes followed by the MAC sixteen times.
+fn magic_packet(mac: &[u8; 6]) -> Vec<u8> {
+    let mut packet = vec![0xff; 6];
+    for _ in 0..16 {
+        packet.extend_from_slice(mac);
+    }
+    packet
+}
+
+fn send_magic_packet(mac: &[u8; 6]) -> Result<(), String> {
+    let socket = UdpSocket::bind("0.0.0.0:0").map_err(|e| e.to_string())?;
+    socket.set_broadcast(true).map_err(|e| e.to_string())?;
+    let packet = magic_packet(mac);
+    for _ in 0..WOL_PACKET_COPIES {
+        socket
+            .send_to(&packet, ("255.255.255.255", WOL_PORT))
+            .map_err(|e| format!("Failed to send Wake-on-LAN packet: {e}"))?;
+    }
+    Ok(())
+}
+
+fn wait_for_ssh(hostname: &str, port: u16, wait: Duration) -> Result<bool, String> {
+    let deadline = Instant::now() + wait;
+    loop {
+        operations::check_current()?;
+        if tcp_connect_rtt(hostname, port, WAKE_POLL_CONNECT_TIMEOUT).is_ok() {
+            return Ok(true);
+        }
+        if Instant::now() >= deadline {
+            return Ok(false);
+        }
+        thread::sleep(WAKE_POLL_INTERVAL);
+    }
+}
+
+/// Send a Wake-on-LAN packet to the host's MAC address. With `wait_secs`,
+/// then poll its SSH port until it answers or the wait runs out, so the UI
+/// can connect as soon as the machine is up.
+#[tauri::command]
+pub async fn host_wake(
+    host_id: String,
+    wait_secs: Option<u64>,
+    op_id: String,
+) -> Result<HostWakeResult, String> {
+    let host = hosts_load()?
+        .into_iter()
+        .find(|host| host.id == host_id.trim() && !host.deleted)
+        .ok_or_else(|| "Host not found".to_string())?;
+    let mac = parse_mac(
+        host.mac_address
+            .as_deref()
+            .ok_or_else(|| "Host has no MAC address".to_string())?,
+    )?;
+    let wait = wait_secs.map(|secs| Duration::from_secs(secs.min(MAX_WAKE_WAIT_SECS)));
+    tauri::async_runtime::spawn_blocking(move || {
+        send_magic_packet(&mac)?;
+        let Some(wait) = wait else {
+            return Ok(HostWakeResult {
+                reachable: None,
+                waited_ms: 0,
+            });
+        };
+        let started = Instant::now();
+        // Leave room for the last poll's connect attempt.
+        let timeout = wait + WAKE_POLL_INTERVAL + WAKE_POLL_CONNECT_TIMEOUT;
+        operations::run(op_id, "host_wake", timeout, || {
+            let reachable = wait_for_ssh(host.hostname.trim(), host.port, wait)?;
+            Ok(HostWakeResult {
+                reachable: Some(reachable),
+                waited_ms: started.elapsed().as_millis() as u64,
+            })
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{magic_packet, parse_mac};
+
+    #[test]
+    fn parses_mac_and_builds_magic_packet() {
+        let mac = [0x00, 0x1a, 0x2b, 0x3c, 0x4d, 0x5e];
+        assert_eq!(parse_mac("00:1A:2B:3C:4D:5E").unwrap(), mac);
+        assert_eq!(parse_mac(" 00-1a-2b-3c-4d-5e ").unwrap(), mac);
+        assert_eq!(parse_mac("001a2b3c4d5e").unwrap(), mac);
+        assert!(parse_mac("00:1a:2b:3c:4d").is_err());
+        assert!(parse_mac("zz:1a:2b:3c:4d:5e").is_err());
+
+        let packet = magic_packet(&mac);
+        assert_eq!(packet.len(), 102);
+        assert_eq!(&packet[..6], &[0xff; 6]);
+        assert_eq!(&packet[96..], &mac);
+    }
+}
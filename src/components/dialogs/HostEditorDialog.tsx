@@ -174,6 +174,14 @@ export function HostEditorDialog(props: {
                         placeholder="jump-host or user@jump:port"
                       />
                     </div>
+                    <div className="grid gap-2">
+                      <label className="text-sm font-medium">Probe Shell</label>
+                      <Input
+                        value={formData.probeShell || ""}
+                        onChange={(e) => setFormData({ ...formData, probeShell: e.target.value })}
+                        placeholder="Auto-detect (sh, busybox sh, ash, bash)"
+                      />
+                    </div>
                   </div>
                 </div>
               </div>
@@ -289,6 +297,31 @@ export function HostEditorDialog(props: {
                       One KEY=VALUE per line. Values must not contain spaces or quotes.
                     </div>
                   </div>
+                  <div className="grid gap-2">
+                    <label className="text-sm font-medium">Startup Commands</label>
+                    <textarea
+                      className="min-h-[72px] w-full rounded-md border border-input bg-transparent px-3 py-2 text-sm font-mono shadow-sm focus-visible:outline-none focus-visible:ring-1 focus-visible:ring-ring"
+                      value={(formData.startupCommands || []).join("\n")}
+                      onChange={(e) => setFormData({ ...formData, startupCommands: e.target.value.split("\n") })}
+                      placeholder={"cd /var/www\nsudo -i"}
+                      spellCheck={false}
+                    />
+                    <Input
+                      type="number"
+                      min={0}
+                      value={formData.startupCommandsDelayMs ?? ""}
+                      onChange={(e) =>
+                        setFormData({
+                          ...formData,
+                          startupCommandsDelayMs: e.target.value === "" ? undefined : Math.max(0, Number(e.target.value)),
+                        })
+                      }
+                      placeholder="Delay in ms (empty = wait for shell prompt)"
+                    />
+                    <div className="text-[11px] text-muted-foreground">
+                      One command per line, sent after the shell prompt appears or after the delay.
+                    </div>
+                  </div>
                   <div className="grid gap-2">
                     <label className="text-sm font-medium">Encoding</label>
                     <div className="relative">
@@ -153,6 +153,12 @@ export function SettingsWindowApp() {
           hostLiveMetricsEnabled: true,
           identityFile: item.identityFile,
           proxyJump: item.proxyJump,
+          forwardAgent: item.forwardAgent ?? null,
+          localForwards: item.localForwards ?? [],
+          extraConfig:
+            [item.controlMaster ? `ControlMaster ${item.controlMaster}` : "", item.extraConfig ?? ""]
+              .filter(Boolean)
+              .join("\n") || null,
           envVars: "",
           encoding: "utf-8",
           sortOrder: maxSortOrder + imported + 1,
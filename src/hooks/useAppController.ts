@@ -109,6 +109,7 @@ export function useAppController() {
     let unlistenTerminalOptions: (() => void) | null = null;
     let unlistenMetricsDock: (() => void) | null = null;
     let unlistenHostsReload: (() => void) | null = null;
+    let unlistenHostsChanged: (() => void) | null = null;
 
     (async () => {
       try {
@@ -141,6 +142,11 @@ export function useAppController() {
           void hostsMgr.loadHosts();
         });
 
+        // Tag rules retagged hosts after a probe.
+        unlistenHostsChanged = await listen("hosts:changed", () => {
+          void hostsMgr.loadHosts();
+        });
+
       } catch (error) {
         console.debug("[settings-window] event listeners unavailable", error);
       }
@@ -172,6 +178,11 @@ export function useAppController() {
       } catch {
         // ignore
       }
+      try {
+        unlistenHostsChanged?.();
+      } catch {
+        // ignore
+      }
     };
   }, [isInTauri]);
 
@@ -68,7 +68,11 @@ export function useHostInsights(params: {
       const source = opts?.source ?? "manual";
       setRefreshingHostIds((prev) => ({ ...prev, [host.id]: true }));
       try {
-        const info = await invoke<HostStaticInfo>("host_probe_static", { host });
+        const info = await invoke<HostStaticInfo>("host_probe_static", {
+          host,
+          opId: crypto.randomUUID(),
+          forceRefresh: source === "manual",
+        });
         setHostStaticById((prev) => ({
           ...prev,
           [host.id]: {
@@ -218,7 +222,10 @@ export function useHostInsights(params: {
       livePollInFlight.current = pollKey;
       if (first) setLiveLoading(true);
       try {
-        const info = await invoke<HostLiveInfo>("host_probe_live", { host });
+        const info = await invoke<HostLiveInfo>("host_probe_live", {
+          host,
+          opId: crypto.randomUUID(),
+        });
         if (cancelled) return;
         setLiveInfo(info);
         setLiveError(null);
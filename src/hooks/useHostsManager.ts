@@ -195,6 +195,12 @@ export function useHostsManager(params: { isInTauri: boolean; sidebarOpen: boole
         hostLiveMetricsEnabled: true,
         identityFile: item.identityFile,
         proxyJump: item.proxyJump,
+        forwardAgent: item.forwardAgent ?? null,
+        localForwards: item.localForwards ?? [],
+        extraConfig:
+          [item.controlMaster ? `ControlMaster ${item.controlMaster}` : "", item.extraConfig ?? ""]
+            .filter(Boolean)
+            .join("\n") || null,
         envVars: "",
         encoding: "utf-8",
         sortOrder: maxSortOrder + imported + 1,
@@ -278,6 +284,9 @@ export function useHostsManager(params: { isInTauri: boolean; sidebarOpen: boole
         proxyJump: formData.proxyJump,
         envVars: formData.envVars,
         encoding: formData.encoding || "utf-8",
+        startupCommands: formData.startupCommands || [],
+        startupCommandsDelayMs: formData.startupCommandsDelayMs,
+        probeShell: formData.probeShell,
         tags: formData.tags || [],
         notes: formData.notes || "",
         updatedAt: now,
@@ -7,12 +7,42 @@ export interface Host {
   port: number;
   password?: string;
   hasPassword?: boolean;
+  hasTotp?: boolean;
   hostInsightsEnabled?: boolean;
   hostLiveMetricsEnabled?: boolean;
   identityFile?: string;
   proxyJump?: string;
+  /** Jump hosts by id, first hop first; overrides `proxyJump`. */
+  jumpHostIds?: string[];
+  /** `ProxyCommand` (cloudflared, corkscrew, SSM...); excludes jump hosts. */
+  proxyCommand?: string | null;
   envVars?: string;
   encoding?: string;
+  startupCommands?: string[];
+  startupCommandsDelayMs?: number;
+  probeShell?: string;
+  syncExcluded?: boolean;
+  importSource?: string | null;
+  macAddress?: string | null;
+  maxSessionMinutes?: number | null;
+  liveProbeLite?: boolean;
+  /** Add temperatures and GPU metrics to the live probe. */
+  extendedProbe?: boolean;
+  /** Answered automatically in the host's terminal sessions. */
+  promptRules?: PromptRule[];
+  forwardAgent?: boolean | null;
+  compression?: boolean | null;
+  serverAliveCountMax?: number | null;
+  connectTimeout?: number | null;
+  localForwards?: string[];
+  remoteForwards?: string[];
+  requestTty?: string | null;
+  hostKeyAlgorithms?: string | null;
+  extraConfig?: string | null;
+  bellAction?: BellAction | null;
+  /** Type `keepaliveString` (a NUL byte by default) after this long idle. */
+  keepaliveIdleMinutes?: number | null;
+  keepaliveString?: string | null;
   sortOrder?: number;
   tags: string[];
   notes: string;
@@ -27,6 +57,23 @@ export interface HostStaticInfo {
   cpuModel?: string;
   cpuCores?: number;
   memTotalKb?: number;
+  osId?: string;
+  osVersion?: string;
+  hostname?: string;
+  factTags?: string[];
+}
+
+/** Payload of the `host:rename-suggested` event. */
+export interface HostRenameSuggestion {
+  hostId: string;
+  currentName: string;
+  remoteName: string;
+}
+
+export interface TagRule {
+  fact: string;
+  equals?: string | null;
+  tag: string;
 }
 
 export interface HostLiveProcess {
@@ -48,12 +95,103 @@ export interface HostLiveInfo {
   memUsedKb?: number;
   memFreeKb?: number;
   memPageCacheKb?: number;
+  swapTotalKb?: number;
+  swapUsedKb?: number;
   load1?: number;
   load5?: number;
   load15?: number;
   diskRootTotalKb?: number;
   diskRootUsedKb?: number;
+  filesystems: HostFilesystem[];
+  network: HostNetInterface[];
+  temperatures: HostThermalZone[];
+  gpus: HostGpu[];
   processes: HostLiveProcess[];
+  sbc?: HostSbcInfo | null;
+}
+
+export interface HostFilesystem {
+  mountPoint: string;
+  device: string;
+  fsType?: string | null;
+  totalKb: number;
+  usedKb: number;
+  availableKb: number;
+  inodesTotal?: number | null;
+  inodesUsed?: number | null;
+}
+
+export interface HostNetInterface {
+  name: string;
+  rxBytes: number;
+  txBytes: number;
+  rxBytesPerSec?: number | null;
+  txBytesPerSec?: number | null;
+}
+
+export interface HostThermalZone {
+  name: string;
+  tempC: number;
+}
+
+export interface HostGpu {
+  vendor: "nvidia" | "amd";
+  index: number;
+  name?: string | null;
+  utilizationPercent?: number | null;
+  memUsedKb?: number | null;
+  memTotalKb?: number | null;
+  tempC?: number | null;
+}
+
+export interface HostSbcInfo {
+  model: string;
+  socTempC?: number | null;
+  throttledRaw?: string | null;
+  underVoltageNow: boolean;
+  freqCappedNow: boolean;
+  throttledNow: boolean;
+  softTempLimitNow: boolean;
+  underVoltageOccurred: boolean;
+  freqCappedOccurred: boolean;
+  throttledOccurred: boolean;
+  softTempLimitOccurred: boolean;
+}
+
+export interface HostOomEvent {
+  at?: string | null;
+  pid?: number | null;
+  process: string;
+  anonRssKb?: number | null;
+  cgroup: boolean;
+}
+
+export type ProbeAccess = "missing" | "direct" | "sudo" | "denied";
+
+export interface HostDockerContainer {
+  id: string;
+  name: string;
+  image: string;
+  state: string;
+  status: string;
+  cpuPercent?: number | null;
+  memUsedBytes?: number | null;
+  memLimitBytes?: number | null;
+  memPercent?: number | null;
+}
+
+export interface HostDocker {
+  access: ProbeAccess;
+  containers: HostDockerContainer[];
+}
+
+export interface HostService {
+  unit: string;
+  description?: string | null;
+  loadState: string;
+  activeState: string;
+  subState: string;
+  enabled?: string | null;
 }
 
 export interface Session {
@@ -73,6 +211,54 @@ export interface Settings {
   has_webdav_password?: boolean;
   webdav_password?: string | null;
   webdav_password_clear?: boolean;
+  password_presence?: "off" | "per_call" | "per_session";
+  sync_excluded_tags?: string[];
+  scrollback_memory_kb?: number | null;
+  scrollback_spill_mb?: number | null;
+  alert_channels?: AlertChannel[];
+  alert_rules?: AlertRule[];
+  live_probe_lite?: boolean;
+  /** OTLP/HTTP collector for job traces, e.g. `http://localhost:4318`. */
+  otlp_endpoint?: string | null;
+  /** Processes that hold off a scheduled reboot; built-in list when unset. */
+  reboot_critical_processes?: string[] | null;
+  /** OpenSSH release (e.g. "9.6") extra config is checked against. */
+  openssh_version?: string | null;
+  /** Seconds a shared SSH connection outlives its last user; 60 when unset. */
+  ssh_control_persist_secs?: number | null;
+}
+
+export interface AlertChannel {
+  id: string;
+  name: string;
+  kind: "webhook" | "slack" | "smtp";
+  url?: string | null;
+  smtp_host?: string | null;
+  smtp_port?: number | null;
+  smtp_username?: string | null;
+  smtp_from?: string | null;
+  smtp_to?: string[];
+  has_smtp_password?: boolean;
+  smtp_password?: string;
+  smtp_password_clear?: boolean;
+}
+
+export interface AlertRule {
+  id: string;
+  name: string;
+  enabled?: boolean;
+  condition: "host_down" | "probe_failed" | "disk_usage";
+  /** Percent full for `disk_usage`; 90 when unset. */
+  threshold?: number | null;
+  /** Every host when empty. */
+  host_ids?: string[];
+  channel_ids?: string[];
+}
+
+export interface AlertDelivery {
+  channelId: string;
+  ok: boolean;
+  error?: string | null;
 }
 
 export interface SshConfigImportCandidate {
@@ -82,5 +268,478 @@ export interface SshConfigImportCandidate {
   port: number;
   identityFile?: string;
   proxyJump?: string;
+  forwardAgent?: boolean | null;
+  localForwards?: string[];
+  controlMaster?: string | null;
+  extraConfig?: string | null;
   sourcePath: string;
 }
+
+export interface SshConfigExportOptions {
+  hostIds?: string[];
+  omitUsers?: boolean;
+  omitIdentityFiles?: boolean;
+  omitForwardsAndExtra?: boolean;
+  includeNotes?: boolean;
+}
+
+export interface TailscalePeer {
+  id: string;
+  name: string;
+  dnsName?: string | null;
+  ip?: string | null;
+  os: string;
+  online: boolean;
+  tags: string[];
+  linkedHostId?: string | null;
+}
+
+export interface HostImportReport {
+  added: number;
+  updated: number;
+  unchanged: number;
+}
+
+export interface CloudInstance {
+  provider: string;
+  region: string;
+  instanceId: string;
+  name?: string | null;
+  publicIp?: string | null;
+  privateIp?: string | null;
+  keyName?: string | null;
+  linkedHostId?: string | null;
+}
+
+export interface SerialPortEntry {
+  device: string;
+  kind: "usb" | "pci" | "bluetooth" | "unknown";
+  description?: string | null;
+}
+
+export type JobStatus = "running" | "succeeded" | "failed" | "cancelled";
+
+export interface JobInfo {
+  jobId: string;
+  kind: string;
+  status: JobStatus;
+  cancellable: boolean;
+  progress?: { done: number; total?: number | null } | null;
+  message?: string | null;
+  startedAt: string;
+  finishedAt?: string | null;
+  result?: unknown;
+  error?: string | null;
+}
+
+export interface BannerStatus {
+  bannerHash: string;
+  acknowledged: boolean;
+  changed: boolean;
+  acknowledgedAt?: string | null;
+}
+
+export interface BannerAck {
+  hostId: string;
+  bannerHash: string;
+  banner: string;
+  acknowledgedAt: string;
+}
+
+export interface HostRuntimes {
+  python3?: string | null;
+  node?: string | null;
+  git?: string | null;
+  rsync?: string | null;
+  tmux?: string | null;
+  missing: string[];
+}
+
+export interface HostWakeResult {
+  reachable?: boolean | null;
+  waitedMs: number;
+}
+
+export interface LogCollectSpec {
+  at: string;
+  windowMinutes?: number | null;
+  files?: string[];
+  journal?: boolean;
+  journalUnits?: string[];
+  maxFileKb?: number | null;
+  maxBundleMb?: number | null;
+}
+
+export interface LogBundleResult {
+  path: string;
+  sizeBytes: number;
+  clockOffsetSecs: number;
+  since: string;
+  until: string;
+  included: string[];
+  skipped: string[];
+}
+
+export interface HostPingResult {
+  hostId: string;
+  /** Unknown for hosts behind a jump host or proxy command, which are not pinged. */
+  reachable: boolean | null;
+  rttMs?: number | null;
+  error?: string | null;
+  checkedAt: string;
+}
+
+export type SshPhase =
+  | "dns"
+  | "connect"
+  | "banner"
+  | "keyExchange"
+  | "gssapi"
+  | "auth"
+  | "session";
+
+export interface SshPhaseTiming {
+  phase: SshPhase;
+  durationMs: number;
+}
+
+export interface SshDiagnosis {
+  hostId: string;
+  connected: boolean;
+  totalMs: number;
+  dnsMs?: number | null;
+  phases: SshPhaseTiming[];
+  slowest?: SshPhase | null;
+  findings: string[];
+  error?: string | null;
+  log: string[];
+}
+
+export interface Alert {
+  title: string;
+  message: string;
+  severity: "info" | "warning" | "critical";
+  hostId?: string | null;
+  firedAt: string;
+}
+
+/** Cached health for the sidebar; gathered without contacting the host. */
+export interface HostHealth {
+  hostId: string;
+  ping?: HostPingResult | null;
+  lastConnectedAt?: string | null;
+  lastAlert?: Alert | null;
+}
+
+export interface DiskUsageNode {
+  path: string;
+  name: string;
+  sizeKb: number;
+  children: DiskUsageNode[];
+}
+
+export interface RemoteDiskUsage {
+  root: DiskUsageNode;
+  depth: number;
+  scannedAt: string;
+  cached: boolean;
+}
+
+/** Cumulative traffic of a PTY or serial session (`pty_session_stats`). */
+export interface PtySessionStats {
+  session_id: string;
+  bytes_read: number;
+  bytes_written: number;
+}
+
+/** One entry of the periodic `pty:throughput` event. */
+export interface PtyThroughput extends PtySessionStats {
+  read_bytes_per_sec: number;
+  write_bytes_per_sec: number;
+}
+
+export interface BackupExportResult {
+  hosts: number;
+  secrets: number;
+}
+
+export interface BackupImportResult {
+  createdAt: string;
+  hosts: number;
+  secrets: number;
+  preferences: Record<string, unknown>;
+}
+
+/** What a terminal bell does: a sound, a notification, a tab badge or nothing. */
+export type BellAction = "sound" | "notification" | "badge" | "ignore";
+
+/** Payload of the `bell:rang` event. */
+export interface BellPayload {
+  session_id: string;
+  host_id: string | null;
+  action: BellAction;
+}
+
+/**
+ * Payload of the `ssh:prompt` event: an OTP or other challenge to answer with
+ * `ssh_prompt_answer`. `session_id` is a PTY session or a probe waiting to log in.
+ */
+export interface SshPromptPayload {
+  session_id: string;
+  host_id: string | null;
+  prompt: string;
+}
+
+export type CompareAspect = "packages" | "services" | "sysctl" | "ports";
+
+export interface AspectDiff {
+  aspect: CompareAspect;
+  onlyA: Record<string, string>;
+  onlyB: Record<string, string>;
+  changed: { key: string; a: string; b: string }[];
+  same: number;
+  /** Hosts without a tool for this aspect; nothing was compared. */
+  unavailableOn: string[];
+}
+
+export interface HostCompareReport {
+  hostAId: string;
+  hostBId: string;
+  aspects: AspectDiff[];
+  comparedAt: string;
+}
+
+export interface RebootPreflight {
+  checkUsers?: boolean;
+  criticalProcesses?: string[] | null;
+}
+
+export interface PendingReboot {
+  hostId: string;
+  at: string;
+  scheduledAt: string;
+}
+
+export interface TotpCode {
+  code: string;
+  validForSecs: number;
+}
+
+export interface SshOptionIssue {
+  line: string;
+  message: string;
+  /** ssh would refuse the config; saving the host fails. */
+  fatal: boolean;
+}
+
+export interface AuditEntry {
+  id: number;
+  at: string;
+  action: string;
+  hostId?: string | null;
+  /** What asked: "ui", "terminal", "probe", "backup", "merge"... */
+  caller: string;
+  ok: boolean;
+  error?: string | null;
+}
+
+export interface AuditFilter {
+  hostId?: string;
+  action?: string;
+  caller?: string;
+  /** RFC 3339; entries at or after it. */
+  since?: string;
+  limit?: number;
+}
+
+export interface IdentityCandidate {
+  path: string;
+  /** From the matching .pub file, e.g. "ssh-ed25519". */
+  keyType?: string | null;
+  /** Passphrase-protected; only usable through the agent. */
+  encrypted: boolean;
+}
+
+export type IdentityTestOutcome = "accepted" | "rejected" | "locked";
+
+export interface IdentityTestResult {
+  path: string;
+  outcome: IdentityTestOutcome;
+}
+
+export interface IdentitySuggestion {
+  identityFile?: string | null;
+  results: IdentityTestResult[];
+}
+
+/** A live PTY or serial session, as `pty_list` reports it. */
+export interface PtySessionSummary {
+  session_id: string;
+  host_id: string | null;
+  /** Labels of the windows the session is attached to. */
+  windows: string[];
+}
+
+export type CredentialBackendState = "available" | "locked" | "unavailable";
+
+/** Result of `credential_backend_status`, also sent as `credential:backend-status`. */
+export interface CredentialBackendStatus {
+  state: CredentialBackendState;
+  detail?: string | null;
+  /** Saves and deletes made while locked, applied once it unlocks. */
+  pendingWrites: number;
+}
+
+/** Returned by `sessions_at_exit`: what to offer reopening on launch. */
+export interface SessionsAtExit {
+  exitedAt: string;
+  /** One per session open at exit, oldest first. */
+  hostIds: string[];
+}
+
+/**
+ * `transport` of `pty_spawn_ssh`: `"poll"` keeps output in the session until
+ * `pty_read_chunk` takes it, instead of sending `pty:data` events.
+ */
+export type PtyTransport = "events" | "poll";
+
+export type MouseTrackingMode = "off" | "x10" | "normal" | "button_event" | "any_event";
+
+/** Result of `pty_session_info`. */
+export interface PtySessionInfo {
+  session_id: string;
+  encoding: string;
+  alternate_screen: boolean;
+  mouse_tracking: MouseTrackingMode;
+  mouse_sgr: boolean;
+  pid?: number | null;
+  /**
+   * The local foreground process and its directory; for an SSH session that
+   * is `ssh` unless a local command runs in front of it.
+   */
+  foreground_pid?: number | null;
+  foreground_process?: string | null;
+  cwd?: string | null;
+}
+
+/** A local terminal profile, from `shell_profiles_list`. */
+export interface ShellProfile {
+  /** Empty for a new profile; `shell_profile_save` assigns one. */
+  id: string;
+  name: string;
+  /** Path of the program to run, e.g. `/bin/zsh`. */
+  shell: string;
+  args: string[];
+  /** Starting directory; `~` is the home directory. */
+  cwd?: string | null;
+  /** `KEY=VALUE` per line, as for hosts. */
+  envVars?: string | null;
+  /** Typed once the shell shows its prompt; one command per line. */
+  startupCommand?: string | null;
+  sortOrder?: number | null;
+}
+
+export interface TmuxPane {
+  /** `%N` */
+  id: string;
+  width: number;
+  height: number;
+  active: boolean;
+}
+
+/** A window of an attached tmux session, from `tmux_windows`. */
+export interface TmuxWindow {
+  /** `@N` */
+  id: string;
+  index: number;
+  name: string;
+  active: boolean;
+  panes: TmuxPane[];
+}
+
+export interface TmuxClientSummary {
+  clientId: string;
+  hostId: string;
+  sessionName: string;
+}
+
+/** Payload of `tmux:output`. */
+export interface TmuxOutputPayload {
+  clientId: string;
+  paneId: string;
+  data: string;
+}
+
+/** Payload of `tmux:exit`; `tmux:changed` carries only `clientId`. */
+export interface TmuxExitPayload {
+  clientId: string;
+  reason?: string | null;
+}
+
+/** Set when a command printed more than the output cap; sizes are what it wrote. */
+export interface OutputTruncation {
+  stdoutBytes: number;
+  stderrBytes: number;
+  capBytes: number;
+}
+
+/** Result of `ssh_exec`: a remote command run without a terminal. */
+export interface SshExecResult {
+  hostId: string;
+  stdout: string;
+  stderr: string;
+  /** Null when the command was killed by a signal. */
+  exitCode?: number | null;
+  durationMs: number;
+  /** Set when stdout or stderr was cut at the output cap. */
+  truncated?: OutputTruncation | null;
+}
+
+/** A host's row of `ssh_exec_batch`: its result, or why it could not run. */
+export interface SshExecBatchEntry {
+  hostId: string;
+  result?: SshExecResult | null;
+  error?: string | null;
+}
+
+/** Payload of `ssh-exec:host-done`, sent as each host of a batch finishes. */
+export interface SshExecHostDonePayload {
+  opId: string;
+  entry: SshExecBatchEntry;
+  done: number;
+  total: number;
+}
+
+export type MetricsRange = "1h" | "24h" | "7d" | "30d";
+
+export interface MetricsSample {
+  /** Unix seconds. */
+  ts: number;
+  cpuPercent?: number | null;
+  memPercent?: number | null;
+  load1?: number | null;
+  diskPercent?: number | null;
+}
+
+export interface HostLiveEntry {
+  hostId: string;
+  live?: HostLiveInfo | null;
+  error?: string | null;
+  durationMs: number;
+}
+
+export interface HostsLiveSummary {
+  hosts: HostLiveEntry[];
+  reachable: number;
+  failed: number;
+  skipped: string[];
+}
+
+/** `response` may use `{password}` for the host's saved password. */
+export interface PromptRule {
+  pattern: string;
+  response: string;
+  enabled?: boolean;
+  /** Answers per session; 3 when unset, at most 20. */
+  maxReplays?: number | null;
+}